@@ -4,9 +4,11 @@ pub use notitia_macros::*;
 pub mod prelude {
     pub use std::collections::BTreeMap;
 
+    pub use ordered_map::{ArcOrderedMap, OrderedMap};
+
     pub use crate::{
-        BuiltRecord, Collection, Database, KeyedRow, OnStartup, OrderDirection, OrderKey,
-        OrderedCollection, SelectStmtBuildable, SelectStmtFilterable, SelectStmtJoinable,
+        BuiltRecord, Collection, Database, Fixtures, KeyedRow, OnStartup, OrderDirection, OrderKey,
+        OrderedCollection, PrimaryKeyedRow, SelectStmtBuildable, SelectStmtFilterable, SelectStmtJoinable,
         SelectStmtOrderable, SelectStmtSelectable, Table, database, record,
     };
 
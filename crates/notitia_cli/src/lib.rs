@@ -0,0 +1,11 @@
+//! Build an admin binary for an app's databases: print their schema,
+//! migrate, export and import rows, vacuum, and reindex embeddings, without
+//! writing a one-off program for each.
+
+mod registry;
+pub use registry::*;
+
+mod scrub;
+pub use scrub::*;
+
+mod rows;
@@ -0,0 +1,306 @@
+use std::{ffi::OsString, marker::PhantomData, path::PathBuf, pin::Pin};
+
+use anyhow::{Context, anyhow, bail};
+use clap::{Parser, Subcommand};
+use notitia_core::{Adapter, ConnectionOptions, Database, Notitia};
+use serde_json::{Map, Value};
+
+use crate::{
+    ScrubPolicy,
+    rows::{export_table, import_order, import_table},
+};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// The databases an app's admin binary can operate on.
+///
+/// Register every database under a name and hand over to [`run`](Self::run)
+/// from the binary's `main`:
+///
+/// ```ignore
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     notitia_cli::Registry::new()
+///         .register::<ChatDb, SqliteAdapter>("chat")
+///         .run()
+///         .await
+/// }
+/// ```
+///
+/// ```sh
+/// my-app-admin export chat sqlite://chat.db -o backup.json
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    databases: Vec<(&'static str, Box<dyn RegisteredDatabase>)>,
+    scrub_policies: Vec<(&'static str, ScrubPolicy)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `Db`, connecting with default [`ConnectionOptions`].
+    pub fn register<Db, Adptr>(self, name: &'static str) -> Self
+    where
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        Adptr::QueryBuilder: sea_query::SchemaBuilder + Default,
+    {
+        self.register_with::<Db, Adptr>(name, ConnectionOptions::new)
+    }
+
+    /// Register `Db`, building its [`ConnectionOptions`] from the url given on
+    /// the command line. Databases with embedded fields need this to attach
+    /// their embedder.
+    pub fn register_with<Db, Adptr>(
+        mut self,
+        name: &'static str,
+        options: impl Fn(&str) -> ConnectionOptions + 'static,
+    ) -> Self
+    where
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        Adptr::QueryBuilder: sea_query::SchemaBuilder + Default,
+    {
+        self.databases.push((
+            name,
+            Box::new(Registered::<Db, Adptr> {
+                options: Box::new(options),
+                _marker: PhantomData,
+            }),
+        ));
+        self
+    }
+
+    /// The columns `export --scrub` hashes or drops for the database
+    /// registered as `name`.
+    pub fn scrub(mut self, name: &'static str, policy: ScrubPolicy) -> Self {
+        self.scrub_policies
+            .retain(|(registered, _)| *registered != name);
+        self.scrub_policies.push((name, policy));
+        self
+    }
+
+    /// Parse the process arguments and run the command they name.
+    pub async fn run(self) -> anyhow::Result<()> {
+        self.run_from(std::env::args_os()).await
+    }
+
+    /// Like [`run`](Self::run), with the arguments given explicitly. The
+    /// first one is the binary name.
+    pub async fn run_from<I, T>(self, args: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let cli = Cli::parse_from(args);
+
+        let Some(name) = cli.command.database() else {
+            for (name, _) in &self.databases {
+                println!("{name}");
+            }
+            return Ok(());
+        };
+
+        let (_, database) = self
+            .databases
+            .iter()
+            .find(|(registered, _)| *registered == name)
+            .ok_or_else(|| anyhow!("no database named `{name}` is registered"))?;
+
+        let scrub = self
+            .scrub_policies
+            .iter()
+            .find(|(registered, _)| *registered == name)
+            .map(|(_, policy)| policy);
+
+        database.run(&cli.command, scrub).await
+    }
+}
+
+#[derive(Parser)]
+#[command(about = "Operate on the app's database files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the registered databases
+    List,
+    /// Print the SQL that creates a database's schema
+    Schema { database: String },
+    /// Create a database file, or bring its schema up to date
+    Migrate { database: String, url: String },
+    /// Write the rows of every table as JSON
+    Export {
+        database: String,
+        url: String,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Hash or drop the columns named by the database's scrub policy,
+        /// e.g. to share the export for debugging
+        #[arg(long)]
+        scrub: bool,
+    },
+    /// Insert the rows of an export, usually into a fresh database
+    Import {
+        database: String,
+        url: String,
+        input: PathBuf,
+    },
+    /// Reclaim the space left behind by deleted rows
+    Vacuum { database: String, url: String },
+    /// Recompute the embeddings of every row, e.g. after an import or after
+    /// switching embedders
+    #[cfg(feature = "embeddings")]
+    Reindex { database: String, url: String },
+}
+
+impl Command {
+    fn database(&self) -> Option<&str> {
+        match self {
+            Self::List => None,
+            Self::Schema { database }
+            | Self::Migrate { database, .. }
+            | Self::Export { database, .. }
+            | Self::Import { database, .. }
+            | Self::Vacuum { database, .. } => Some(database),
+            #[cfg(feature = "embeddings")]
+            Self::Reindex { database, .. } => Some(database),
+        }
+    }
+}
+
+/// A registered database with its types erased.
+trait RegisteredDatabase {
+    fn run<'a>(
+        &'a self,
+        command: &'a Command,
+        scrub: Option<&'a ScrubPolicy>,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+struct Registered<Db, Adptr> {
+    options: Box<dyn Fn(&str) -> ConnectionOptions>,
+    _marker: PhantomData<fn() -> (Db, Adptr)>,
+}
+
+impl<Db, Adptr> Registered<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    /// Connecting creates the file if needed and migrates its schema.
+    async fn connect(&self, url: &str) -> anyhow::Result<Notitia<Db, Adptr>> {
+        Db::connect::<Adptr>((self.options)(url))
+            .await
+            .map_err(|err| anyhow!("failed to open {url}: {err}"))
+    }
+}
+
+impl<Db, Adptr> RegisteredDatabase for Registered<Db, Adptr>
+where
+    Db: Database + 'static,
+    Adptr: Adapter + 'static,
+    Adptr::QueryBuilder: sea_query::SchemaBuilder + Default,
+{
+    fn run<'a>(
+        &'a self,
+        command: &'a Command,
+        scrub: Option<&'a ScrubPolicy>,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            match command {
+                Command::List => unreachable!("listing needs no database"),
+                Command::Schema { .. } => {
+                    let db = Db::new();
+                    println!("{}", db.schema_sql(Adptr::QueryBuilder::default()));
+                    let index_sql = db.index_sql(Adptr::QueryBuilder::default());
+                    if !index_sql.is_empty() {
+                        println!("\n{index_sql}");
+                    }
+                }
+                Command::Migrate { url, .. } => {
+                    self.connect(url).await?;
+                    eprintln!("{url} is up to date");
+                }
+                Command::Export {
+                    url,
+                    output,
+                    scrub: scrubbed,
+                    ..
+                } => {
+                    let schema = Db::schema();
+                    let scrub = match (scrubbed, scrub) {
+                        (false, _) => None,
+                        (true, Some(policy)) => {
+                            policy.check(&schema)?;
+                            Some(policy)
+                        }
+                        (true, None) => bail!("no scrub policy is registered for this database"),
+                    };
+
+                    let db = self.connect(url).await?;
+
+                    let mut tables = Map::new();
+                    for table in &schema.tables {
+                        let rows = export_table(db.adapter(), table, scrub).await?;
+                        tables.insert(table.name.to_string(), Value::Array(rows));
+                    }
+
+                    let json = serde_json::to_string_pretty(&Value::Object(tables))?;
+                    match output {
+                        Some(path) => std::fs::write(path, json)
+                            .with_context(|| format!("failed to write {}", path.display()))?,
+                        None => println!("{json}"),
+                    }
+                }
+                Command::Import { url, input, .. } => {
+                    let json = std::fs::read_to_string(input)
+                        .with_context(|| format!("failed to read {}", input.display()))?;
+                    let Value::Object(tables) = serde_json::from_str(&json)? else {
+                        bail!("{} is not an export", input.display());
+                    };
+
+                    let schema = Db::schema();
+                    if let Some(unknown) = tables.keys().find(|name| schema.table(name).is_none()) {
+                        bail!("the export has a table `{unknown}` the schema doesn't");
+                    }
+
+                    let db = self.connect(url).await?;
+                    for table in import_order(&schema) {
+                        let rows = match tables.get(table.name) {
+                            Some(Value::Array(rows)) => rows,
+                            Some(_) => bail!("`{}` in the export is not a list", table.name),
+                            None => continue,
+                        };
+                        let count = import_table(db.adapter(), table, rows).await?;
+                        eprintln!("{}: imported {count} rows", table.name);
+                    }
+                }
+                Command::Vacuum { url, .. } => {
+                    let db = self.connect(url).await?;
+                    db.adapter()
+                        .vacuum()
+                        .await
+                        .map_err(|err| anyhow!("failed to vacuum {url}: {err}"))?;
+                }
+                #[cfg(feature = "embeddings")]
+                Command::Reindex { url, .. } => {
+                    let db = self.connect(url).await?;
+                    let count = db
+                        .reindex_embeddings()
+                        .await
+                        .map_err(|err| anyhow!("failed to reindex {url}: {err}"))?;
+                    eprintln!("reindexed {count} rows");
+                }
+            }
+            Ok(())
+        })
+    }
+}
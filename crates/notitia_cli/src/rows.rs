@@ -0,0 +1,170 @@
+use anyhow::{Context, bail};
+use notitia_core::{Adapter, Datatype, DatatypeKind, SchemaDef, TableDef};
+use serde_json::{Map, Value};
+
+use crate::ScrubPolicy;
+
+/// Read every row of `table` as JSON objects keyed by column name, with the
+/// columns `scrub` names hashed or dropped.
+pub(crate) async fn export_table<Adptr: Adapter>(
+    adapter: &Adptr,
+    table: &TableDef,
+    scrub: Option<&ScrubPolicy>,
+) -> anyhow::Result<Vec<Value>> {
+    let columns: Vec<&'static str> = table.columns.iter().map(|column| column.name).collect();
+    let rows = adapter
+        .fetch_rows(table.name, &columns)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to read {}: {err}", table.name))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let object: Map<String, Value> = table
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(column, (name, value))| {
+                    let value = match scrub {
+                        Some(scrub) => scrub.apply(table.name, column, value),
+                        None => value,
+                    };
+                    (name.to_string(), datatype_to_json(value))
+                })
+                .collect();
+            Value::Object(object)
+        })
+        .collect())
+}
+
+/// Insert rows written by [`export_table`] into `table`, returning how many
+/// there were. Columns missing from a row are inserted as null.
+pub(crate) async fn import_table<Adptr: Adapter>(
+    adapter: &Adptr,
+    table: &TableDef,
+    rows: &[Value],
+) -> anyhow::Result<usize> {
+    let mut decoded = Vec::with_capacity(rows.len());
+    for (idx, row) in rows.iter().enumerate() {
+        let object = row
+            .as_object()
+            .with_context(|| format!("{} row {idx} is not an object", table.name))?;
+
+        let values = table
+            .columns
+            .iter()
+            .map(|column| {
+                let value = object.get(column.name).unwrap_or(&Value::Null);
+                let datatype = json_to_datatype(&column.kind, value)
+                    .with_context(|| format!("{}.{} in row {idx}", table.name, column.name))?;
+                Ok((column.name, datatype))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        decoded.push(values);
+    }
+
+    let count = decoded.len();
+    if count > 0 {
+        adapter
+            .insert_rows(table.name, decoded)
+            .await
+            .map_err(|err| anyhow::anyhow!("failed to insert into {}: {err}", table.name))?;
+    }
+    Ok(count)
+}
+
+/// The tables of `schema` ordered so that every table comes after the
+/// tables its foreign keys point to. Cycles keep their declaration order.
+pub(crate) fn import_order(schema: &SchemaDef) -> Vec<&TableDef> {
+    let mut remaining: Vec<&TableDef> = schema.tables.iter().collect();
+    let mut ordered: Vec<&TableDef> = Vec::with_capacity(remaining.len());
+
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|table| {
+            table.foreign_keys.iter().all(|fk| {
+                fk.foreign_table == table.name
+                    || ordered.iter().any(|done| done.name == fk.foreign_table)
+                    || !remaining.iter().any(|other| other.name == fk.foreign_table)
+            })
+        });
+        ordered.push(remaining.remove(ready.unwrap_or(0)));
+    }
+
+    ordered
+}
+
+/// Blobs become arrays of bytes; JSON has no binary type.
+fn datatype_to_json(datatype: Datatype) -> Value {
+    match datatype {
+        Datatype::Int(v) => Value::from(v),
+        Datatype::BigInt(v) => Value::from(v),
+        Datatype::Float(v) => Value::from(v),
+        Datatype::Double(v) => Value::from(v),
+        Datatype::Text(v) => Value::String(v),
+        Datatype::Blob(v) => Value::from(v),
+        Datatype::Bool(v) => Value::Bool(v),
+        Datatype::Array(v) => Value::Array(v.into_iter().map(datatype_to_json).collect()),
+        Datatype::Null => Value::Null,
+    }
+}
+
+fn json_to_datatype(kind: &DatatypeKind, value: &Value) -> anyhow::Result<Datatype> {
+    if value.is_null() {
+        return Ok(Datatype::Null);
+    }
+
+    let datatype = match kind {
+        DatatypeKind::Int(_) => value
+            .as_i64()
+            .and_then(|v| i32::try_from(v).ok())
+            .map(Datatype::Int),
+        DatatypeKind::BigInt(_) => value.as_i64().map(Datatype::BigInt),
+        DatatypeKind::Float(_) => value
+            .as_f64()
+            .and_then(|v| f32::try_from(Datatype::Double(v)).ok())
+            .map(Datatype::Float),
+        DatatypeKind::Double(_) => value.as_f64().map(Datatype::Double),
+        DatatypeKind::Text(_) => value.as_str().map(|v| Datatype::Text(v.to_string())),
+        DatatypeKind::Blob(_) => value.as_array().and_then(|bytes| {
+            bytes
+                .iter()
+                .map(|byte| byte.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .map(Datatype::Blob)
+        }),
+        // SQLite stores booleans as integers, so exports contain 0 and 1.
+        DatatypeKind::Bool(_) => value
+            .as_bool()
+            .or_else(|| value.as_i64().map(|v| v != 0))
+            .map(Datatype::Bool),
+        DatatypeKind::Array(_, element) => match value.as_array() {
+            Some(items) => Some(Datatype::Array(
+                items
+                    .iter()
+                    .map(|item| json_to_datatype(element, item))
+                    .collect::<anyhow::Result<_>>()?,
+            )),
+            // SQLite reads arrays back as their JSON text.
+            None => value
+                .as_str()
+                .and_then(Datatype::array_from_json)
+                .map(Datatype::Array),
+        },
+    };
+
+    match datatype {
+        Some(datatype) => Ok(datatype),
+        None => bail!("expected {}, got {value}", kind_name(kind)),
+    }
+}
+
+fn kind_name(kind: &DatatypeKind) -> &'static str {
+    match kind {
+        DatatypeKind::Int(_) | DatatypeKind::BigInt(_) => "an integer",
+        DatatypeKind::Float(_) | DatatypeKind::Double(_) => "a number",
+        DatatypeKind::Text(_) => "a string",
+        DatatypeKind::Blob(_) => "an array of bytes",
+        DatatypeKind::Bool(_) => "a boolean",
+        DatatypeKind::Array(..) => "an array",
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::bail;
+use notitia_core::{ColumnDef, Datatype, DatatypeKind, SchemaDef};
+use sha2::{Digest, Sha256};
+
+/// What an export writes instead of a scrubbed column's values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrubAction {
+    /// A salted SHA-256 of the value: hex for text, the digest for blobs and
+    /// its leading bytes for integers. Equal values hash alike, so hashed
+    /// keys still join. Other types are dropped.
+    Hash,
+    /// Null, or the type's empty value if the column isn't nullable so that
+    /// the export still imports. Hash unique columns instead.
+    Drop,
+}
+
+/// Columns to hash or drop when exporting with `--scrub`, so a database can
+/// be shared for debugging without its private content:
+///
+/// ```ignore
+/// notitia_cli::Registry::new()
+///     .register::<ChatDb, SqliteAdapter>("chat")
+///     .scrub(
+///         "chat",
+///         ScrubPolicy::new()
+///             .salt(std::env::var("SCRUB_SALT")?)
+///             .hash_column("users", "email")
+///             .drop_column("messages", "body"),
+///     )
+///     .run()
+///     .await
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScrubPolicy {
+    salt: Vec<u8>,
+    columns: Vec<(&'static str, &'static str, ScrubAction)>,
+}
+
+impl ScrubPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mixed into every hash. Without one, hashes of guessable values like
+    /// emails can be reversed by hashing candidates.
+    pub fn salt(mut self, salt: impl Into<Vec<u8>>) -> Self {
+        self.salt = salt.into();
+        self
+    }
+
+    pub fn hash_column(self, table: &'static str, column: &'static str) -> Self {
+        self.column(table, column, ScrubAction::Hash)
+    }
+
+    pub fn drop_column(self, table: &'static str, column: &'static str) -> Self {
+        self.column(table, column, ScrubAction::Drop)
+    }
+
+    /// Configuring a column again replaces its action.
+    pub fn column(
+        mut self,
+        table: &'static str,
+        column: &'static str,
+        action: ScrubAction,
+    ) -> Self {
+        self.columns.retain(|(t, c, _)| (*t, *c) != (table, column));
+        self.columns.push((table, column, action));
+        self
+    }
+
+    pub fn action(&self, table: &str, column: &str) -> Option<ScrubAction> {
+        self.columns
+            .iter()
+            .find(|(t, c, _)| *t == table && *c == column)
+            .map(|(_, _, action)| *action)
+    }
+
+    /// Fail on columns the schema doesn't have, so a renamed column isn't
+    /// silently exported in the clear.
+    pub(crate) fn check(&self, schema: &SchemaDef) -> anyhow::Result<()> {
+        for (table, column, _) in &self.columns {
+            match schema.table(table) {
+                None => bail!("the scrub policy names a table `{table}` the schema doesn't have"),
+                Some(def) if def.column(column).is_none() => {
+                    bail!(
+                        "the scrub policy names a column `{table}.{column}` the schema doesn't have"
+                    )
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply(&self, table: &str, column: &ColumnDef, value: Datatype) -> Datatype {
+        match self.action(table, column.name) {
+            None => value,
+            Some(_) if matches!(value, Datatype::Null) => value,
+            Some(ScrubAction::Hash) => self.hash(column, value),
+            Some(ScrubAction::Drop) => dropped(&column.kind),
+        }
+    }
+
+    fn hash(&self, column: &ColumnDef, value: Datatype) -> Datatype {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.salt);
+        match &value {
+            Datatype::Int(v) => hasher.update(v.to_le_bytes()),
+            Datatype::BigInt(v) => hasher.update(v.to_le_bytes()),
+            Datatype::Text(v) => hasher.update(v.as_bytes()),
+            Datatype::Blob(v) => hasher.update(v),
+            _ => return dropped(&column.kind),
+        }
+        let digest = hasher.finalize();
+
+        match value {
+            Datatype::Int(_) => Datatype::Int(i32::from_le_bytes(digest[..4].try_into().unwrap())),
+            Datatype::BigInt(_) => {
+                Datatype::BigInt(i64::from_le_bytes(digest[..8].try_into().unwrap()))
+            }
+            Datatype::Text(_) => {
+                Datatype::Text(digest.iter().map(|b| format!("{b:02x}")).collect())
+            }
+            _ => Datatype::Blob(digest.to_vec()),
+        }
+    }
+}
+
+fn dropped(kind: &DatatypeKind) -> Datatype {
+    if kind.metadata().optional {
+        return Datatype::Null;
+    }
+    match kind {
+        DatatypeKind::Int(_) => Datatype::Int(0),
+        DatatypeKind::BigInt(_) => Datatype::BigInt(0),
+        DatatypeKind::Float(_) => Datatype::Float(0.0),
+        DatatypeKind::Double(_) => Datatype::Double(0.0),
+        DatatypeKind::Text(_) => Datatype::Text(String::new()),
+        DatatypeKind::Blob(_) => Datatype::Blob(Vec::new()),
+        DatatypeKind::Bool(_) => Datatype::Bool(false),
+        DatatypeKind::Array(..) => Datatype::Array(Vec::new()),
+    }
+}
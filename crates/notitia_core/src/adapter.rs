@@ -1,19 +1,37 @@
 use std::error::Error;
 
+use futures_util::io::{AsyncRead, AsyncWrite};
 use unions::IsUnion;
 
 use crate::{
-    Database, DeleteStmtBuilt, FieldKindGroup, InsertStmtBuilt, Notitia, PartialRecord, Record,
-    SelectStmtBuilt, SelectStmtFetchMode, UpdateStmtBuilt,
+    AdapterCapabilities, ArchiveStmt, BlobLocation, BulkInsertStmtBuilt, Database, Datatype,
+    DeleteByKeysStmtBuilt, DeleteStmtBuilt, FieldKindGroup, InsertFromSelectStmtBuilt,
+    InsertStmtBuilt, MutationEvent, MutationRejected, NonFiniteFloat, Notitia, OutboxEvent,
+    PartialRecord, Record, SelectStmtBuilt, SelectStmtDef, SelectStmtFetchMode, SqlFunction,
+    Unsupported, UpdateStmtBuilt,
 };
 
 pub trait Adapter: Sized + Send + Sync {
     type QueryBuilder: sea_query::QueryBuilder;
     type Connection: Send + Sync;
-    type Error: Error;
+    /// Also reports the errors notitia catches before a statement reaches
+    /// the database.
+    type Error: Error + From<NonFiniteFloat> + From<Unsupported> + From<MutationRejected>;
+    /// Streams a blob out of the database, see [`Notitia::blob_reader`].
+    type BlobReader: AsyncRead + Send + Unpin;
+    /// Streams a blob into the database, see [`Notitia::blob_writer`]. The
+    /// blob is complete once the writer is closed.
+    type BlobWriter: AsyncWrite + Send + Unpin;
+    /// A read transaction pinned to one point in time, see
+    /// [`Notitia::snapshot`].
+    type Snapshot: Send + Sync;
 
     fn new(connection: Self::Connection) -> Self;
 
+    /// What the database supports, checked before running statements that
+    /// need more than plain SQL.
+    fn capabilities(&self) -> AdapterCapabilities;
+
     fn initialize<Db: Database>(&self, database: &Db) -> impl Future<Output = ()> + Send;
 
     fn migrate<Db: Database>(&self, database: &Db) -> impl Future<Output = ()> + Send;
@@ -22,6 +40,22 @@ pub trait Adapter: Sized + Send + Sync {
         url: &str,
     ) -> impl Future<Output = Result<Notitia<Db, Self>, Self::Error>> + Send;
 
+    /// Fail unless the database can be reached.
+    fn ping(&self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Replace the connection after [`ping`](Self::ping) failed. The default
+    /// does nothing, for adapters whose connections recover on their own.
+    fn reconnect(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Make `function` callable by name in SQL on every connection,
+    /// including ones opened later.
+    fn register_function(
+        &self,
+        function: SqlFunction,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
     fn execute_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
         &self,
         stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
@@ -33,18 +67,186 @@ pub trait Adapter: Sized + Send + Sync {
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
         Mode: SelectStmtFetchMode<Fields::Type> + Sync;
 
+    /// Like [`execute_select_stmt`](Self::execute_select_stmt), but stops
+    /// reading rows once `deadline` resolves, returning those decoded so
+    /// far and whether any were left unread. Adapters that can't stop early
+    /// read every row.
+    fn execute_select_stmt_until<Db, FieldUnion, FieldPath, Fields, Mode, Deadline>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+        _deadline: Deadline,
+    ) -> impl Future<Output = Result<(Mode::Output, bool), Self::Error>> + Send
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+        Deadline: Future<Output = ()> + Send,
+    {
+        let execute = self.execute_select_stmt(stmt);
+        async move { Ok((execute.await?, false)) }
+    }
+
+    fn begin_snapshot(&self) -> impl Future<Output = Result<Self::Snapshot, Self::Error>> + Send;
+
+    /// Like [`execute_select_stmt`](Self::execute_select_stmt), but reading
+    /// from `snapshot` instead of the latest state.
+    fn execute_snapshot_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        snapshot: &Self::Snapshot,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> impl Future<Output = Result<Mode::Output, Self::Error>> + Send
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync;
+
+    /// Run a select sent as data. Each row holds the values of
+    /// `def.fields` followed by the value of every `def.order_by` expression.
+    fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> impl Future<Output = Result<Vec<Vec<Datatype>>, Self::Error>> + Send;
+
+    /// Returns whether the row was inserted, which is only false when `stmt`
+    /// [ignores conflicts](InsertStmtBuilt::ignore_conflicts) and hit one.
     fn execute_insert_stmt<Db: Database, R: Record + Send>(
         &self,
         stmt: InsertStmtBuilt<Db, R>,
-    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Insert every record of `stmt` atomically, returning the rows that
+    /// were inserted: all of them, unless `stmt` ignores conflicts.
+    fn execute_bulk_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> impl Future<Output = Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error>> + Send;
+
+    /// Run an `INSERT ... SELECT`, returning every row it inserted.
+    fn execute_insert_from_select_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> impl Future<Output = Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error>> + Send;
 
+    /// Returns the `returning` columns of the rows the update changed, as it
+    /// left them, or no rows if there are none or the adapter can't read
+    /// them back.
     fn execute_update_stmt<Db: Database, Rec: Record + Send, P: PartialRecord + Send>(
         &self,
         stmt: UpdateStmtBuilt<Db, Rec, P>,
-    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    ) -> impl Future<Output = Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error>> + Send;
 
+    /// Returns the delete events of the rows `ON DELETE CASCADE` foreign
+    /// keys removed along with them, from the lookups of
+    /// [`stmt.cascade()`](DeleteStmtBuilt::cascade) read in the delete's
+    /// transaction.
     fn execute_delete_stmt<Db: Database, Rec: Record + Send>(
         &self,
         stmt: DeleteStmtBuilt<Db, Rec>,
+    ) -> impl Future<Output = Result<Vec<MutationEvent>, Self::Error>> + Send;
+
+    /// Delete the rows of every key in `stmt` atomically. Returns the
+    /// cascaded deletes like [`execute_delete_stmt`](Self::execute_delete_stmt).
+    fn execute_delete_by_keys_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> impl Future<Output = Result<Vec<MutationEvent>, Self::Error>> + Send;
+
+    /// Copy the rows `stmt` matches into its archive table and delete them
+    /// from the hot table in one transaction, returning the archived rows
+    /// and the deletes of [`stmt.cascade`](ArchiveStmt::cascade).
+    fn execute_archive_stmt(
+        &self,
+        stmt: &ArchiveStmt,
+    ) -> impl Future<
+        Output = Result<(Vec<Vec<(&'static str, Datatype)>>, Vec<MutationEvent>), Self::Error>,
+    > + Send;
+
+    fn is_table_empty(
+        &self,
+        table_name: &'static str,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Every row of `table_name`, with `columns` in the given order. For
+    /// tools that don't know the table's record type, like exports.
+    fn fetch_rows(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> impl Future<Output = Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error>> + Send;
+
+    /// Insert untyped rows atomically, the counterpart of
+    /// [`fetch_rows`](Self::fetch_rows).
+    fn insert_rows(
+        &self,
+        table_name: &'static str,
+        rows: Vec<Vec<(&'static str, Datatype)>>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Set `column` of the rows whose `key_column` is the first of each
+    /// pair to the second, atomically.
+    fn update_rows(
+        &self,
+        table_name: &'static str,
+        key_column: &'static str,
+        column: &'static str,
+        rows: Vec<(Datatype, Datatype)>,
     ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    fn open_blob_reader(
+        &self,
+        location: &BlobLocation,
+    ) -> impl Future<Output = Result<Self::BlobReader, Self::Error>> + Send;
+
+    /// Set the blob to `len` zero bytes and open it for writing.
+    fn open_blob_writer(
+        &self,
+        location: &BlobLocation,
+        len: u64,
+    ) -> impl Future<Output = Result<Self::BlobWriter, Self::Error>> + Send;
+
+    /// Reclaim the space left behind by deleted rows. The default does
+    /// nothing, for adapters that manage their storage themselves.
+    fn vacuum(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    /// The version fixture `name` was last applied at, if ever.
+    fn fixture_version(
+        &self,
+        name: &'static str,
+    ) -> impl Future<Output = Result<Option<u32>, Self::Error>> + Send;
+
+    fn set_fixture_version(
+        &self,
+        name: &'static str,
+        version: u32,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Up to `limit` outbox messages that weren't marked dispatched, by id.
+    /// Mutations commit theirs, the `outbox` of their statement, in the
+    /// same transaction as the statement.
+    fn fetch_outbox(
+        &self,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<OutboxEvent>, Self::Error>> + Send;
+
+    fn mark_outbox_dispatched(
+        &self,
+        ids: Vec<i64>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// A write clashed with an existing row on its primary key or unique
+/// columns, for telling the user e.g. that an email is already in use.
+/// Adapters report it as one of their errors.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{table}` already has a row with the same {}", .columns.join(", "))]
+pub struct UniqueViolation {
+    pub table: String,
+    /// The columns of the violated constraint, in its order.
+    pub columns: Vec<String>,
 }
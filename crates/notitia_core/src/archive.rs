@@ -0,0 +1,240 @@
+use std::{marker::PhantomData, time::Duration};
+
+use smallvec::SmallVec;
+use tracing::warn;
+
+use crate::{
+    Adapter, CascadeWalk, Database, FieldFilter, IntoFieldFilter, IsTable, MutationEvent,
+    MutationEventKind, Notitia, Record, StrongTableKind, TableKind,
+};
+
+/// Moves the rows of a table that match its conditions into an archive
+/// table, keeping the hot table small:
+///
+/// ```ignore
+/// let policy = MyDb::MESSAGES
+///     .archive_into(MyDb::MESSAGES_ARCHIVE)
+///     .when(|| Message::SENT_AT.lt(unix_millis() - NINETY_DAYS_MS));
+/// ```
+///
+/// Conditions are closures, evaluated again every time the policy runs, so
+/// cutoffs can be relative to the current time. Run it once with
+/// [`Notitia::archive`], or periodically with an [`Archiver`].
+pub struct ArchivePolicy<Db: Database, Rec: Record> {
+    pub hot_table: &'static str,
+    pub archive_table: &'static str,
+    conditions: Vec<Box<dyn Fn() -> FieldFilter + Send + Sync>>,
+    columns: SmallVec<[&'static str; 4]>,
+    _marker: PhantomData<fn() -> (Db, Rec)>,
+}
+
+impl<Db: Database, Rec: Record> ArchivePolicy<Db, Rec> {
+    pub(crate) fn new<Archived: Record>(
+        hot_table: &'static str,
+        archive_table: &'static str,
+    ) -> Self {
+        let columns: SmallVec<[&'static str; 4]> =
+            Archived::_FIELDS.iter().map(|(name, _)| *name).collect();
+        if let Some(missing) = columns
+            .iter()
+            .find(|column| !Rec::_FIELDS.iter().any(|(name, _)| name == *column))
+        {
+            panic!("archive_into: `{hot_table}` has no column `{missing}` to archive");
+        }
+
+        Self {
+            hot_table,
+            archive_table,
+            conditions: Vec::new(),
+            columns,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Only archive rows matching `condition`, on top of earlier ones.
+    pub fn when<FieldPath, Filter>(
+        mut self,
+        condition: impl Fn() -> Filter + Send + Sync + 'static,
+    ) -> Self
+    where
+        Db: 'static,
+        FieldPath: 'static,
+        Filter: IntoFieldFilter<Db, Rec::FieldKind, FieldPath> + 'static,
+    {
+        self.conditions
+            .push(Box::new(move || condition().into_field_filter()));
+        self
+    }
+
+    /// The statement for one run, with the conditions evaluated now.
+    pub fn to_stmt(&self) -> ArchiveStmt {
+        let filters: SmallVec<[FieldFilter; 1]> = self
+            .conditions
+            .iter()
+            .map(|condition| condition())
+            .collect();
+        ArchiveStmt {
+            hot_table: self.hot_table,
+            archive_table: self.archive_table,
+            columns: self.columns.clone(),
+            cascade: CascadeWalk::new::<Db>(self.hot_table, &filters),
+            filters,
+        }
+    }
+}
+
+/// One run of an [`ArchivePolicy`]. Adapters copy the matching rows of
+/// `hot_table` into `archive_table` and delete them in one transaction.
+#[derive(Clone, Debug)]
+pub struct ArchiveStmt {
+    pub hot_table: &'static str,
+    pub archive_table: &'static str,
+    /// The archive table's columns, each copied from the hot table's column
+    /// of the same name.
+    pub columns: SmallVec<[&'static str; 4]>,
+    pub filters: SmallVec<[FieldFilter; 1]>,
+    /// The rows `ON DELETE CASCADE` foreign keys delete along with the
+    /// archived ones, to look up in the same transaction.
+    pub cascade: CascadeWalk,
+}
+
+impl<Db, Tbl> StrongTableKind<Db, Tbl>
+where
+    Db: Database,
+    Tbl: IsTable<Database = Db>,
+{
+    /// A policy moving rows of this table into `archive`, which must have a
+    /// subset of its columns; panics otherwise.
+    pub fn archive_into<Archive>(
+        &self,
+        archive: StrongTableKind<Db, Archive>,
+    ) -> ArchivePolicy<Db, Tbl::Record>
+    where
+        Archive: IsTable<Database = Db>,
+    {
+        ArchivePolicy::new::<Archive::Record>(self.kind.name(), archive.kind.name())
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Run `policy` once, returning how many rows it moved.
+    ///
+    /// Subscribers of the hot table receive a delete for the moved rows, so
+    /// live views shrink, and those of the archive table a bulk insert. No
+    /// events are sent when nothing was moved.
+    pub async fn archive<Rec: Record>(
+        &self,
+        policy: &ArchivePolicy<Db, Rec>,
+    ) -> Result<usize, Adptr::Error> {
+        self.execute_archive_stmt(policy.to_stmt()).await
+    }
+
+    /// An [`Archiver`] running archive policies on this database.
+    pub fn archiver(&self) -> Archiver<Db, Adptr> {
+        Archiver {
+            db: self.clone(),
+            policies: Vec::new(),
+        }
+    }
+
+    async fn execute_archive_stmt(&self, stmt: ArchiveStmt) -> Result<usize, Adptr::Error> {
+        let capabilities = self.capabilities();
+        capabilities.require_returning("archive policies")?;
+        capabilities.require_filters(&stmt.filters)?;
+        let (rows, cascaded) = self.inner.adapter.execute_archive_stmt(&stmt).await?;
+        let count = rows.len();
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let events: Vec<MutationEvent> = [
+            MutationEvent {
+                table_name: stmt.hot_table,
+                sequence: 0,
+                kind: MutationEventKind::Delete {
+                    filters: stmt.filters,
+                },
+            },
+            MutationEvent {
+                table_name: stmt.archive_table,
+                sequence: 0,
+                kind: MutationEventKind::BulkInsert { rows },
+            },
+        ]
+        .into_iter()
+        .chain(cascaded)
+        .collect();
+        self.notify_subscribers_batch(&events);
+        Ok(count)
+    }
+}
+
+/// Runs archive policies on a schedule.
+///
+/// Like the [`Supervisor`](crate::Supervisor), it is driven by the
+/// application: call [`check`](Self::check) periodically, or hand
+/// [`run`](Self::run) a sleep function and spawn it.
+///
+/// ```ignore
+/// let archiver = db.archiver().policy(messages_policy).policy(events_policy);
+/// tokio::spawn(archiver.run(Duration::from_secs(3600), tokio::time::sleep));
+/// ```
+pub struct Archiver<Db: Database, Adptr: Adapter> {
+    db: Notitia<Db, Adptr>,
+    policies: Vec<Box<dyn ErasedPolicy>>,
+}
+
+/// An [`ArchivePolicy`] without its record type, so that policies for
+/// different tables fit in one list.
+trait ErasedPolicy: Send + Sync {
+    fn to_stmt(&self) -> ArchiveStmt;
+}
+
+impl<Db: Database, Rec: Record> ErasedPolicy for ArchivePolicy<Db, Rec> {
+    fn to_stmt(&self) -> ArchiveStmt {
+        ArchivePolicy::to_stmt(self)
+    }
+}
+
+impl<Db, Adptr> Archiver<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    pub fn policy<Rec>(mut self, policy: ArchivePolicy<Db, Rec>) -> Self
+    where
+        Rec: Record + 'static,
+        Db: 'static,
+    {
+        self.policies.push(Box::new(policy));
+        self
+    }
+
+    /// Run every policy once, returning how many rows they moved. A policy
+    /// that fails is logged and retried on the next check.
+    pub async fn check(&self) -> usize {
+        let mut moved = 0;
+        for policy in &self.policies {
+            let stmt = policy.to_stmt();
+            let hot_table = stmt.hot_table;
+            match self.db.execute_archive_stmt(stmt).await {
+                Ok(count) => moved += count,
+                Err(err) => warn!("failed to archive rows of {hot_table}: {err}"),
+            }
+        }
+        moved
+    }
+
+    /// Run the policies every `interval`, forever. `sleep` is the runtime's
+    /// timer, e.g. `tokio::time::sleep`.
+    pub async fn run<Sleep, SleepFut>(self, interval: Duration, mut sleep: Sleep)
+    where
+        Sleep: FnMut(Duration) -> SleepFut,
+        SleepFut: Future<Output = ()>,
+    {
+        loop {
+            self.check().await;
+            sleep(interval).await;
+        }
+    }
+}
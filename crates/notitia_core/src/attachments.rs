@@ -0,0 +1,327 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use unions::{IntoUnion, UnionPath};
+
+use crate::{
+    Adapter, Database, Datatype, FieldKindOfDatabase, InnerFieldType, IsTable, MutationEvent,
+    MutationEventKind, MutationHook, Notitia, Record, StrongFieldKind, StrongTableKind, TableKind,
+};
+
+/// Stores attachments as files named by the SHA-256 of their contents, so
+/// records only keep the hash (and whatever metadata they like) instead of
+/// the bytes:
+///
+/// ```ignore
+/// let store = Arc::new(
+///     AttachmentStore::new(data_dir.join("attachments"))
+///         .referenced_by(MyDb::ATTACHMENTS, Attachment::HASH),
+/// );
+/// db.set_mutation_hook(store.clone());
+///
+/// let stored = store.put(File::open(path)?)?;
+/// let record = Attachment::build().hash(stored.hash).size(stored.size as i64);
+/// db.mutate(MyDb::ATTACHMENTS.insert(record)).execute().await?;
+/// ```
+///
+/// Identical contents are stored once. Files no longer referenced by any
+/// [`referenced_by`](Self::referenced_by) column are removed by
+/// [`collect_garbage`](Self::collect_garbage); installed as the mutation
+/// hook, the store notices deletes and [`run`](Self::run) only collects
+/// after them. A database has one mutation hook, so this doesn't combine
+/// with an `EmbeddingManager` yet.
+pub struct AttachmentStore {
+    dir: PathBuf,
+    references: Vec<(&'static str, &'static str)>,
+    grace_period: Duration,
+    /// Whether rows may have stopped referencing files since the last
+    /// collection. Starts set, to catch files left behind before a restart.
+    pending: AtomicBool,
+    next_temp: AtomicU64,
+}
+
+/// An attachment written to an [`AttachmentStore`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredAttachment {
+    /// The lowercase hex SHA-256 of the contents, to keep in the record.
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AttachmentError<E: std::error::Error> {
+    #[error("failed to read referenced attachments: {0}")]
+    Adapter(E),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl AttachmentStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            references: Vec::new(),
+            grace_period: Duration::from_secs(600),
+            pending: AtomicBool::new(true),
+            next_temp: AtomicU64::new(0),
+        }
+    }
+
+    /// Keep the files whose hashes are in `field` of `table`. Declare every
+    /// column holding hashes, or collection removes the files it refers to.
+    pub fn referenced_by<Db, Tbl, FieldPath, Field, T>(
+        mut self,
+        table: StrongTableKind<Db, Tbl>,
+        field: StrongFieldKind<Field, T>,
+    ) -> Self
+    where
+        Db: Database,
+        Tbl: IsTable<Database = Db>,
+        FieldPath: UnionPath,
+        Field: FieldKindOfDatabase<Db> + IntoUnion<<Tbl::Record as Record>::FieldKind, FieldPath>,
+        T: InnerFieldType<Inner = String>,
+    {
+        self.references.push((table.kind.name(), field.kind.name()));
+        self
+    }
+
+    /// How old an unreferenced file must be to be collected, 10 minutes by
+    /// default. It covers the time between [`put`](Self::put) and inserting
+    /// the record referencing the file.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Write `contents` to the store, or refresh the file if the store has
+    /// them already.
+    pub fn put(&self, mut contents: impl Read) -> io::Result<StoredAttachment> {
+        fs::create_dir_all(&self.dir)?;
+        let temp = self.dir.join(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            self.next_temp.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let written = (|| {
+            let mut file = File::create(&temp)?;
+            let mut hasher = Sha256::new();
+            let mut size = 0;
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                let n = contents.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+                file.write_all(&buf[..n])?;
+                size += n as u64;
+            }
+            file.sync_all()?;
+
+            let hash: String = hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            Ok::<_, io::Error>(StoredAttachment { hash, size })
+        })();
+        let stored = match written {
+            Ok(stored) => stored,
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                return Err(err);
+            }
+        };
+
+        let path = self.file_path(&stored.hash);
+        if path.exists() {
+            fs::remove_file(&temp)?;
+            // A collection running before the record is inserted must not
+            // take the file for an old unreferenced one.
+            File::options()
+                .append(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())?;
+        } else {
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::rename(&temp, &path)?;
+        }
+        Ok(stored)
+    }
+
+    /// Open the attachment with `hash` for reading.
+    pub fn open(&self, hash: &str) -> io::Result<File> {
+        File::open(self.path(hash)?)
+    }
+
+    pub fn read(&self, hash: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path(hash)?)
+    }
+
+    /// The file holding the attachment with `hash`. Fails on anything that
+    /// isn't a hash as [`put`](Self::put) returns them, so that values read
+    /// from the database can't point outside the store.
+    pub fn path(&self, hash: &str) -> io::Result<PathBuf> {
+        if !is_hash(hash) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{hash}` is not an attachment hash"),
+            ));
+        }
+        Ok(self.file_path(hash))
+    }
+
+    /// Remove the files no referencing column mentions, and leftovers of
+    /// failed writes, that are older than the grace period. Returns how many
+    /// were removed.
+    pub async fn collect_garbage<Db, Adptr>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<usize, AttachmentError<Adptr::Error>>
+    where
+        Db: Database,
+        Adptr: Adapter,
+    {
+        self.pending.store(false, Ordering::Relaxed);
+        let result = self.sweep(db).await;
+        if result.is_err() {
+            self.pending.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Collect garbage every `interval` when rows were deleted since the last
+    /// collection, forever. `sleep` is the runtime's timer, e.g.
+    /// `tokio::time::sleep`. Without the store installed as the mutation
+    /// hook, this collects only once.
+    pub async fn run<Db, Adptr, Sleep, SleepFut>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        interval: Duration,
+        mut sleep: Sleep,
+    ) where
+        Db: Database,
+        Adptr: Adapter,
+        Sleep: FnMut(Duration) -> SleepFut,
+        SleepFut: Future<Output = ()>,
+    {
+        loop {
+            if self.pending.load(Ordering::Relaxed) {
+                let collected = self.collect_garbage(db).await;
+                if let Err(err) = collected {
+                    warn!("failed to collect attachments: {err}");
+                }
+            }
+            sleep(interval).await;
+        }
+    }
+
+    async fn sweep<Db, Adptr>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<usize, AttachmentError<Adptr::Error>>
+    where
+        Db: Database,
+        Adptr: Adapter,
+    {
+        // Anything older than this when the references are read can't be
+        // referenced by a record inserted afterwards.
+        let cutoff = SystemTime::now() - self.grace_period;
+
+        let mut referenced = HashSet::new();
+        for &(table, column) in &self.references {
+            let rows = db
+                .inner
+                .adapter
+                .fetch_rows(table, &[column])
+                .await
+                .map_err(AttachmentError::Adapter)?;
+            referenced.extend(
+                rows.into_iter()
+                    .filter_map(|row| match row.into_iter().next() {
+                        Some((_, Datatype::Text(hash))) => Some(hash),
+                        _ => None,
+                    }),
+            );
+        }
+
+        let mut removed = 0;
+        for file in files(&self.dir)? {
+            let name = file.file_name().and_then(|name| name.to_str());
+            let stale = match name {
+                Some(name) if is_hash(name) => !referenced.contains(name),
+                Some(name) => name.starts_with('.') && name.ends_with(".tmp"),
+                None => false,
+            };
+            if stale && fs::metadata(&file)?.modified()? < cutoff {
+                fs::remove_file(&file)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn file_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(hash)
+    }
+
+    fn is_reference(&self, table_name: &str, column: &str) -> bool {
+        self.references.contains(&(table_name, column))
+    }
+}
+
+impl MutationHook for AttachmentStore {
+    fn on_event(&self, event: &MutationEvent) {
+        let unreferences = match &event.kind {
+            MutationEventKind::Delete { .. } => self
+                .references
+                .iter()
+                .any(|(table, _)| *table == event.table_name),
+            MutationEventKind::Update { changed, .. } => changed
+                .iter()
+                .any(|(column, _)| self.is_reference(event.table_name, column)),
+            MutationEventKind::Insert { .. } | MutationEventKind::BulkInsert { .. } => false,
+        };
+        if unreferences {
+            self.pending.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn is_hash(name: &str) -> bool {
+    name.len() == 64
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// The files in the store's directory and its shard directories.
+fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            for entry in fs::read_dir(entry.path())? {
+                files.push(entry?.path());
+            }
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use crate::{Adapter, Database, IsTable, Mutation, MutationEvent, Notitia};
+
+/// Runs before the mutations of a table reach the database, and can change
+/// what they write or reject them, e.g. to normalize phone numbers or to
+/// block writes to archived conversations.
+///
+/// The hook gets the event the mutation is about to broadcast. Changes to
+/// the values of inserts and to the expressions of the columns updates set
+/// are written back into the mutation; everything else, like the filters,
+/// only informs the hook. Columns can't be added or removed.
+pub trait BeforeMutationHook: Send + Sync {
+    fn before_mutation(&self, event: &mut MutationEvent) -> Result<(), MutationRejected>;
+}
+
+/// A [`BeforeMutationHook`] refused a mutation, or changed it into one that
+/// can't be written. Adapters report it as one of their errors.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("mutation of `{table}` rejected: {reason}")]
+pub struct MutationRejected {
+    pub table: String,
+    pub reason: String,
+}
+
+impl MutationRejected {
+    pub fn new(table: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Run `hook` before every mutation of `table`, after the hooks added
+    /// before it. Rows deleted by `ON DELETE CASCADE` skip the hooks of
+    /// their table.
+    pub fn add_before_mutation_hook<Tbl: IsTable<Database = Db>>(
+        &self,
+        table: Tbl,
+        hook: Arc<dyn BeforeMutationHook>,
+    ) {
+        self.inner
+            .before_mutation_hooks
+            .lock()
+            .unwrap()
+            .entry(table.name())
+            .or_default()
+            .push(hook);
+    }
+
+    /// Run the hooks of the table `stmt` mutates on it.
+    pub(crate) fn run_before_mutation_hooks<M: Mutation<Db>>(
+        &self,
+        stmt: &mut M,
+    ) -> Result<(), MutationRejected> {
+        let all_hooks = self.inner.before_mutation_hooks.lock().unwrap();
+        // Skips building the event of mutations no hook looks at.
+        if all_hooks.is_empty() {
+            return Ok(());
+        }
+        let mut event = stmt.to_mutation_event();
+        // Cloned out, so that hooks can add hooks.
+        let hooks = match all_hooks.get(event.table_name) {
+            Some(hooks) => hooks.clone(),
+            None => return Ok(()),
+        };
+        drop(all_hooks);
+
+        run_hooks(&hooks, &mut event)?;
+        let table = event.table_name;
+        stmt.apply_mutation_event(event)
+            .map_err(|err| MutationRejected::new(table, err.to_string()))
+    }
+}
+
+fn run_hooks(
+    hooks: &[Arc<dyn BeforeMutationHook>],
+    event: &mut MutationEvent,
+) -> Result<(), MutationRejected> {
+    hooks
+        .iter()
+        .try_for_each(|hook| hook.before_mutation(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, MutationEventKind};
+
+    use super::*;
+
+    struct Digits;
+
+    impl BeforeMutationHook for Digits {
+        fn before_mutation(&self, event: &mut MutationEvent) -> Result<(), MutationRejected> {
+            if let MutationEventKind::Insert { values } = &mut event.kind {
+                for (_, value) in values {
+                    if let Datatype::Text(text) = value {
+                        text.retain(|c| c.is_ascii_digit());
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    struct Archived;
+
+    impl BeforeMutationHook for Archived {
+        fn before_mutation(&self, event: &mut MutationEvent) -> Result<(), MutationRejected> {
+            Err(MutationRejected::new(event.table_name, "archived"))
+        }
+    }
+
+    #[test]
+    fn runs_hooks_in_order_until_one_rejects() {
+        let mut event = MutationEvent {
+            table_name: "contacts",
+            sequence: 0,
+            kind: MutationEventKind::Insert {
+                values: vec![("phone", Datatype::Text("+1 (555) 010-99".into()))],
+            },
+        };
+
+        run_hooks(&[Arc::new(Digits)], &mut event).unwrap();
+        let MutationEventKind::Insert { values } = &event.kind else {
+            unreachable!();
+        };
+        assert_eq!(values[0], ("phone", Datatype::Text("155501099".into())));
+
+        assert_eq!(
+            run_hooks(&[Arc::new(Digits), Arc::new(Archived)], &mut event),
+            Err(MutationRejected::new("contacts", "archived"))
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use unions::{IntoUnion, UnionPath};
+
+use crate::{
+    Adapter, Database, Datatype, FieldFilter, FieldFilterMetadata, FieldKindOfDatabase,
+    InnerFieldType, IsTable, Notitia, Record, StrongFieldKind, StrongTableKind, TableFieldPair,
+    TableKind,
+};
+
+/// The blob column of one row, found by primary key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlobLocation {
+    pub table_name: &'static str,
+    pub column: &'static str,
+    /// The table's primary key column.
+    pub key_column: &'static str,
+    pub key: Datatype,
+}
+
+impl BlobLocation {
+    fn new<Db, Tbl, Field, T>(
+        table: &StrongTableKind<Db, Tbl>,
+        field: &StrongFieldKind<Field, T>,
+        key: Datatype,
+    ) -> Self
+    where
+        Db: Database,
+        Tbl: IsTable<Database = Db>,
+        Field: FieldKindOfDatabase<Db>,
+        T: InnerFieldType,
+    {
+        let table_name = table.kind.name();
+        let mut primary_key = <Tbl::Record as Record>::_FIELDS
+            .iter()
+            .filter(|(_, kind)| kind.metadata().primary_key);
+
+        let key_column = match (primary_key.next(), primary_key.next()) {
+            (Some((name, _)), None) => *name,
+            _ => panic!("blobs: `{table_name}` must have a single primary key column"),
+        };
+
+        Self {
+            table_name,
+            column: field.kind.name(),
+            key_column,
+            key,
+        }
+    }
+
+    /// The filter selecting the row.
+    pub fn key_filter(&self) -> FieldFilter {
+        FieldFilter::Eq(FieldFilterMetadata {
+            left: TableFieldPair::new(self.table_name, self.key_column),
+            right: self.key.clone(),
+        })
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Read a blob column incrementally, e.g. to stream a large attachment
+    /// to a file without holding all of it in memory:
+    ///
+    /// ```ignore
+    /// let mut reader = db.blob_reader(MyDb::ATTACHMENTS, Attachment::DATA, id).await?;
+    /// futures::io::copy(&mut reader, &mut file).await?;
+    /// ```
+    ///
+    /// Panics if the table's primary key isn't a single column.
+    pub async fn blob_reader<Tbl, FieldPath, Field, T>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+        field: StrongFieldKind<Field, T>,
+        key: impl Into<Datatype>,
+    ) -> Result<Adptr::BlobReader, Adptr::Error>
+    where
+        Tbl: IsTable<Database = Db>,
+        FieldPath: UnionPath,
+        Field: FieldKindOfDatabase<Db> + IntoUnion<<Tbl::Record as Record>::FieldKind, FieldPath>,
+        T: InnerFieldType<Inner = Vec<u8>>,
+    {
+        let location = BlobLocation::new(&table, &field, key.into());
+        self.inner.adapter.open_blob_reader(&location).await
+    }
+
+    /// Replace a blob column with `len` bytes written incrementally. Writing
+    /// more than `len` bytes fails, and bytes not written stay zero.
+    ///
+    /// ```ignore
+    /// let mut writer = db.blob_writer(MyDb::ATTACHMENTS, Attachment::DATA, id, size).await?;
+    /// futures::io::copy(&mut file, &mut writer).await?;
+    /// writer.close().await?;
+    /// ```
+    ///
+    /// The row must exist already. Subscribers are not notified of the new
+    /// contents, so select the column on demand instead of subscribing to
+    /// it. Panics if the table's primary key isn't a single column.
+    pub async fn blob_writer<Tbl, FieldPath, Field, T>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+        field: StrongFieldKind<Field, T>,
+        key: impl Into<Datatype>,
+        len: u64,
+    ) -> Result<Adptr::BlobWriter, Adptr::Error>
+    where
+        Tbl: IsTable<Database = Db>,
+        FieldPath: UnionPath,
+        Field: FieldKindOfDatabase<Db> + IntoUnion<<Tbl::Record as Record>::FieldKind, FieldPath>,
+        T: InnerFieldType<Inner = Vec<u8>>,
+    {
+        let location = BlobLocation::new(&table, &field, key.into());
+        self.inner.adapter.open_blob_writer(&location, len).await
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Adapter, Database, FieldFilter, Notitia};
+
+/// What the database behind an adapter supports beyond the SQL every
+/// adapter runs. Statements that need more fail with [`Unsupported`]
+/// before they reach the database, rather than with whatever error it
+/// reports for SQL it doesn't understand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdapterCapabilities {
+    /// `RETURNING` clauses, which tell inserts ignoring conflicts,
+    /// `INSERT ... SELECT`s and archiving which rows they wrote.
+    pub returning: bool,
+    /// Notifications of writes made over other connections, like Postgres'
+    /// `LISTEN`/`NOTIFY`. Without them, subscriptions only see the writes
+    /// made through their own [`Notitia`].
+    pub listen_notify: bool,
+    /// Array columns, native or stored as JSON, and the
+    /// `contains_element` filters on them.
+    pub arrays: bool,
+    /// Full-text indexes and queries, like SQLite's FTS5.
+    pub full_text_search: bool,
+    /// Savepoints, letting one statement of a transaction fail without
+    /// rolling back the others.
+    pub savepoints: bool,
+}
+
+impl AdapterCapabilities {
+    pub const ALL: Self = Self {
+        returning: true,
+        listen_notify: true,
+        arrays: true,
+        full_text_search: true,
+        savepoints: true,
+    };
+
+    /// What both `self` and `other` support, for adapters writing to two
+    /// databases.
+    pub const fn intersection(self, other: Self) -> Self {
+        Self {
+            returning: self.returning && other.returning,
+            listen_notify: self.listen_notify && other.listen_notify,
+            arrays: self.arrays && other.arrays,
+            full_text_search: self.full_text_search && other.full_text_search,
+            savepoints: self.savepoints && other.savepoints,
+        }
+    }
+
+    pub(crate) fn require_returning(self, needed_by: &'static str) -> Result<(), Unsupported> {
+        require(self.returning, "RETURNING", needed_by)
+    }
+
+    pub(crate) fn require_filters(self, filters: &[FieldFilter]) -> Result<(), Unsupported> {
+        let has_arrays = filters
+            .iter()
+            .flat_map(FieldFilter::leaves)
+            .any(|filter| matches!(filter, FieldFilter::ContainsElement(_)));
+        require(
+            self.arrays || !has_arrays,
+            "arrays",
+            "`contains_element` filters",
+        )
+    }
+}
+
+fn require(
+    supported: bool,
+    feature: &'static str,
+    needed_by: &'static str,
+) -> Result<(), Unsupported> {
+    if supported {
+        Ok(())
+    } else {
+        Err(Unsupported { feature, needed_by })
+    }
+}
+
+/// A statement needs something the adapter's [`AdapterCapabilities`]
+/// lack. Adapters report it as one of their errors.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{needed_by} need {feature}, which the database doesn't support")]
+pub struct Unsupported {
+    /// e.g. `RETURNING`.
+    pub feature: &'static str,
+    /// e.g. `inserts ignoring conflicts`.
+    pub needed_by: &'static str,
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    pub fn capabilities(&self) -> AdapterCapabilities {
+        self.inner.adapter.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Datatype, FieldFilterMetadata, TableFieldPair};
+
+    #[test]
+    fn rejects_what_the_database_lacks() {
+        let filters = [FieldFilter::ContainsElement(FieldFilterMetadata {
+            left: TableFieldPair::new("posts", "tags"),
+            right: Datatype::Text("rust".into()),
+        })];
+        assert_eq!(AdapterCapabilities::ALL.require_filters(&filters), Ok(()));
+
+        let without_arrays = AdapterCapabilities::ALL.intersection(AdapterCapabilities {
+            arrays: false,
+            ..AdapterCapabilities::ALL
+        });
+        let err = without_arrays.require_filters(&filters).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`contains_element` filters need arrays, which the database doesn't support"
+        );
+        assert_eq!(without_arrays.require_filters(&[]), Ok(()));
+        assert_eq!(without_arrays.require_returning("archiving"), Ok(()));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Where a database gets the time from, e.g. for `#[db(created_at)]` fields.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_millis(&self) -> i64;
+}
+
+/// Where a database gets new ids from, e.g. for `#[db(auto_id)]` fields.
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+/// The system's wall clock, the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as i64)
+    }
+}
+
+/// Random version 4 UUIDs in their hyphenated form, the default.
+#[derive(Debug, Default)]
+pub struct RandomIds {
+    counter: AtomicU64,
+}
+
+impl RandomIds {
+    pub const fn new() -> Self {
+        Self {
+            counter: AtomicU64::new(0),
+        }
+    }
+}
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> String {
+        // A fresh `RandomState` is keyed from the OS's randomness, and the
+        // counter keeps ids apart even if two states were keyed alike.
+        let count = self.counter.fetch_add(1, Ordering::Relaxed);
+        let half = |salt: u64| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(count);
+            hasher.write_u64(salt);
+            hasher.finish()
+        };
+        let mut bits = (u128::from(half(0)) << 64) | u128::from(half(1));
+        // Version 4, variant 1.
+        bits = (bits & !(0xf << 76)) | (0x4 << 76);
+        bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+
+        let hex = format!("{bits:032x}");
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+/// A clock that only moves when told to, so tests get the same timestamps
+/// on every run:
+///
+/// ```ignore
+/// let clock = Arc::new(FixedClock::new(1_700_000_000_000));
+/// let db = MyDb::connect::<SqliteAdapter>(
+///     ConnectionOptions::new("sqlite::memory:").clock(clock.clone()),
+/// )
+/// .await?;
+/// clock.advance(Duration::from_secs(60));
+/// ```
+#[derive(Debug, Default)]
+pub struct FixedClock {
+    millis: AtomicI64,
+}
+
+impl FixedClock {
+    pub fn new(millis: i64) -> Self {
+        Self {
+            millis: AtomicI64::new(millis),
+        }
+    }
+
+    pub fn set(&self, millis: i64) {
+        self.millis.store(millis, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.millis
+            .fetch_add(by.as_millis() as i64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> i64 {
+        self.millis.load(Ordering::Relaxed)
+    }
+}
+
+/// Ids counting up from 1 after a prefix, for tests that assert on ids:
+/// `SequentialIds::new("user-")` gives `user-1`, `user-2` and so on.
+#[derive(Debug)]
+pub struct SequentialIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl SequentialIds {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            next: AtomicU64::new(1),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> String {
+        format!(
+            "{}{}",
+            self.prefix,
+            self.next.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+}
+
+impl<T: Clock + ?Sized> Clock for std::sync::Arc<T> {
+    fn now_millis(&self) -> i64 {
+        (**self).now_millis()
+    }
+}
+
+impl<T: IdGenerator + ?Sized> IdGenerator for std::sync::Arc<T> {
+    fn next_id(&self) -> String {
+        (**self).next_id()
+    }
+}
@@ -1,6 +1,11 @@
-use std::{collections::BTreeMap, hash::Hash};
+use std::{collections::BTreeMap, hash::Hash, sync::Arc};
 
-use crate::{Datatype, DatatypeConversionError, OrderKey, subscription::merge::SubscribableRow};
+use ordered_map::{ArcOrderedMap, OrderedMap};
+
+use crate::{
+    Datatype, DatatypeConversionError, OrderKey, PrimaryKey, index_between,
+    subscription::merge::SubscribableRow,
+};
 
 /// Base collection trait for query results.
 pub trait Collection: Clone + PartialEq + Send + 'static {
@@ -15,21 +20,132 @@ pub trait Collection: Clone + PartialEq + Send + 'static {
     /// For ordered collections, this inserts in sorted position.
     fn push(&mut self, item: Self::Item, order_key: OrderKey);
 
+    /// Iterate over all items, in order.
+    fn iter(&self) -> impl Iterator<Item = &Self::Item>;
+
     /// Iterate mutably over all items.
     fn iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Item>;
 
     /// Retain only items for which the predicate returns `true`.
     fn retain(&mut self, f: impl FnMut(&Self::Item) -> bool);
 
+    /// Remove the items at `positions`, ascending indexes into the order
+    /// `iter` visits them in.
+    fn remove_positions(&mut self, positions: &[usize]);
+
+    /// Replace every item for which `f` returns `Some`, visiting them in the
+    /// order `iter` does.
+    ///
+    /// The default goes through `iter_mut`; copy-on-write collections
+    /// override it so items that stay the same are never touched.
+    fn update_each(&mut self, mut f: impl FnMut(&Self::Item) -> Option<Self::Item>) {
+        for item in self.iter_mut() {
+            if let Some(updated) = f(item) {
+                *item = updated;
+            }
+        }
+    }
+
     /// Update the order key for a given item. No-op for unordered collections.
     fn update_order(&mut self, _item: &Self::Item, _order_key: OrderKey) {}
+
+    /// Replace the item `same_key` picks out with `item` and move it to
+    /// `order_key`, or push `item` if there is none, e.g. for an insert of a
+    /// row the collection already has by primary key.
+    ///
+    /// The default scans for the item; collections keyed by primary key
+    /// look up the key of `item` instead and never call `same_key`.
+    fn upsert(
+        &mut self,
+        item: Self::Item,
+        order_key: OrderKey,
+        mut same_key: impl FnMut(&Self::Item) -> bool,
+    ) -> Upsert {
+        let replaced = match self.iter_mut().find(|existing| same_key(existing)) {
+            None => None,
+            Some(existing) if *existing == item => Some(Upsert::Unchanged),
+            Some(existing) => {
+                *existing = item.clone();
+                Some(Upsert::Replaced)
+            }
+        };
+        match replaced {
+            Some(upsert) => {
+                self.update_order(&item, order_key);
+                upsert
+            }
+            None => {
+                self.push(item, order_key);
+                Upsert::Inserted
+            }
+        }
+    }
+}
+
+/// What [`Collection::upsert`] did with the item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upsert {
+    Inserted,
+    Replaced,
+    /// An equal item was there already.
+    Unchanged,
 }
 
 /// Marker trait for ordered collections.
 ///
 /// `push` must insert in sorted position (not append).
 /// Required by queries that have ORDER BY clauses.
-pub trait OrderedCollection: Collection {}
+pub trait OrderedCollection: Collection {
+    /// Move `item` between `before` and `after`, either `None` for that
+    /// end of the list, e.g. when it's dragged and dropped there.
+    ///
+    /// The query has to be ordered by a text column of
+    /// [fractional indexes](index_between) first. The new index of `item`
+    /// is returned, to store in that column with an update, whose event
+    /// then finds the row already in place. `None` if one of the rows isn't
+    /// in the collection or has no index.
+    fn update_order_between(
+        &mut self,
+        item: &Self::Item,
+        before: Option<&Self::Item>,
+        after: Option<&Self::Item>,
+    ) -> Option<String>;
+}
+
+/// `order_key` with its leading fractional index moved between those of
+/// `before` and `after`, along with the new index.
+fn order_key_between(
+    order_key: &OrderKey,
+    before: Option<&OrderKey>,
+    after: Option<&OrderKey>,
+) -> Option<(OrderKey, String)> {
+    fn leading_index(order_key: &OrderKey) -> Option<&str> {
+        match order_key.values.first().map(|value| &**value) {
+            Some(Datatype::Text(index)) => Some(index),
+            _ => None,
+        }
+    }
+
+    leading_index(order_key)?;
+    let before = match before {
+        Some(before) => Some(leading_index(before)?),
+        None => None,
+    };
+    let after = match after {
+        Some(after) => Some(leading_index(after)?),
+        None => None,
+    };
+    // Descending lists show the larger index first.
+    let index = if order_key.reversed.first() == Some(&true) {
+        index_between(after, before)?
+    } else {
+        index_between(before, after)?
+    };
+
+    let mut moved = order_key.clone();
+    moved.values[0] = Arc::new(Datatype::Text(index.clone()));
+    Some((moved, index))
+}
 
 /// Trait for row types that have a unique key for deduplication.
 pub trait KeyedRow {
@@ -150,6 +266,114 @@ impl_keyed_row_tuple!(
     60: T60, 61: T61, 62: T62, 63: T63,
 );
 
+/// Row types whose record primary key can be read off the row itself.
+///
+/// Implemented for a bare `PrimaryKey<T>` and for tuples whose first element
+/// is a `PrimaryKey<T>`, so any selection that lists the primary key field
+/// first qualifies.
+pub trait PrimaryKeyedRow: SubscribableRow {
+    type PrimaryKey: Eq + Hash + Clone + Into<Datatype> + Send + Sync + 'static;
+    fn primary_key(&self) -> &Self::PrimaryKey;
+}
+
+impl<K> PrimaryKeyedRow for PrimaryKey<K>
+where
+    K: Clone
+        + Eq
+        + Hash
+        + Into<Datatype>
+        + TryFrom<Datatype, Error = DatatypeConversionError>
+        + Send
+        + Sync
+        + 'static,
+{
+    type PrimaryKey = K;
+    fn primary_key(&self) -> &K {
+        &self.inner
+    }
+}
+
+macro_rules! impl_primary_keyed_row_tuple {
+    (@impl $($T:ident),+) => {
+        impl<K, $($T),+> PrimaryKeyedRow for (PrimaryKey<K>, $($T,)+)
+        where
+            K: Clone + Eq + Hash + Into<Datatype> + TryFrom<Datatype, Error = DatatypeConversionError> + Send + Sync + 'static,
+            $($T: Clone + PartialEq + Into<Datatype> + TryFrom<Datatype, Error = DatatypeConversionError> + Send + 'static,)+
+        {
+            type PrimaryKey = K;
+            fn primary_key(&self) -> &K {
+                &self.0.inner
+            }
+        }
+    };
+
+    (@build [$($acc:ident)*] $T:ident) => {
+        impl_primary_keyed_row_tuple!(@impl $($acc,)* $T);
+    };
+
+    (@build [$($acc:ident)*] $T:ident, $($rest:ident),+) => {
+        impl_primary_keyed_row_tuple!(@impl $($acc,)* $T);
+        impl_primary_keyed_row_tuple!(@build [$($acc)* $T] $($rest),+);
+    };
+
+    ($($T:ident),+ $(,)?) => {
+        impl_primary_keyed_row_tuple!(@build [] $($T),+);
+    };
+}
+
+// The primary key takes the first slot, so each tier covers one fewer
+// trailing field than its tuple size.
+
+// Tier 1: 4 fields (extra_small_fields)
+#[cfg(feature = "extra_small_fields")]
+impl_primary_keyed_row_tuple!(T1, T2, T3);
+
+// Tier 2: 12 fields (small_fields)
+#[cfg(feature = "small_fields")]
+impl_primary_keyed_row_tuple!(
+    T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11,
+);
+
+// Tier 3: 22 fields (medium_fields)
+#[cfg(feature = "medium_fields")]
+impl_primary_keyed_row_tuple!(
+    T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11,
+    T12, T13, T14, T15, T16, T17, T18, T19, T20, T21,
+);
+
+// Tier 4: 42 fields (large_fields)
+#[cfg(feature = "large_fields")]
+impl_primary_keyed_row_tuple!(
+    T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11,
+    T12, T13, T14, T15, T16, T17, T18, T19, T20, T21,
+    T22, T23, T24, T25, T26, T27, T28, T29, T30, T31,
+    T32, T33, T34, T35, T36, T37, T38, T39, T40, T41,
+);
+
+// Tier 5: 64 fields (extra_large_fields)
+#[cfg(feature = "extra_large_fields")]
+impl_primary_keyed_row_tuple!(
+    T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11,
+    T12, T13, T14, T15, T16, T17, T18, T19, T20, T21,
+    T22, T23, T24, T25, T26, T27, T28, T29, T30, T31,
+    T32, T33, T34, T35, T36, T37, T38, T39, T40, T41,
+    T42, T43, T44, T45, T46, T47, T48, T49, T50, T51,
+    T52, T53, T54, T55, T56, T57, T58, T59, T60, T61,
+    T62, T63,
+);
+
+/// Called once for every item in order, whether to keep it rather than
+/// remove one at `positions`.
+fn not_at(positions: &[usize]) -> impl FnMut() -> bool + '_ {
+    let mut position = 0;
+    let mut positions = positions.iter().peekable();
+    move || {
+        let removed = positions.next_if_eq(&&position).is_some();
+        position += 1;
+        !removed
+    }
+}
+
 // --- Vec implementation ---
 
 impl<T: SubscribableRow> Collection for Vec<T> {
@@ -163,6 +387,10 @@ impl<T: SubscribableRow> Collection for Vec<T> {
         Vec::push(self, item);
     }
 
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.as_slice().iter()
+    }
+
     fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.as_mut_slice().iter_mut()
     }
@@ -170,6 +398,11 @@ impl<T: SubscribableRow> Collection for Vec<T> {
     fn retain(&mut self, f: impl FnMut(&T) -> bool) {
         Vec::retain(self, f);
     }
+
+    fn remove_positions(&mut self, positions: &[usize]) {
+        let mut keep = not_at(positions);
+        Vec::retain(self, |_| keep());
+    }
 }
 
 // --- BTreeMap implementation ---
@@ -192,6 +425,10 @@ where
         self.insert(order_key, item);
     }
 
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
+    }
+
     fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.values_mut()
     }
@@ -200,6 +437,11 @@ where
         self.retain(|_, v| f(v));
     }
 
+    fn remove_positions(&mut self, positions: &[usize]) {
+        let mut keep = not_at(positions);
+        self.retain(|_, _| keep());
+    }
+
     fn update_order(&mut self, item: &T, order_key: OrderKey) {
         // Find and remove the old entry, re-insert with new key.
         let old_key = self.iter().find_map(|(k, v)| {
@@ -217,4 +459,270 @@ where
     }
 }
 
-impl<T> OrderedCollection for BTreeMap<OrderKey, T> where T: SubscribableRow {}
+impl<T> OrderedCollection for BTreeMap<OrderKey, T>
+where
+    T: SubscribableRow,
+{
+    fn update_order_between(
+        &mut self,
+        item: &T,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Option<String> {
+        let order_key_of = |item: &T| self.iter().find(|(_, v)| *v == item).map(|(k, _)| k);
+        let order_key = order_key_of(item)?;
+        let before = match before {
+            Some(before) => Some(order_key_of(before)?),
+            None => None,
+        };
+        let after = match after {
+            Some(after) => Some(order_key_of(after)?),
+            None => None,
+        };
+
+        let (moved, index) = order_key_between(order_key, before, after)?;
+        let old_key = order_key.clone();
+        let value = self.remove(&old_key)?;
+        self.insert(moved, value);
+        Some(index)
+    }
+}
+
+// --- OrderedMap implementation ---
+
+/// Append the primary key as a final ascending component, so rows that tie on
+/// every ORDER BY column still get distinct order keys instead of replacing
+/// each other.
+fn order_key_with_primary_key<K: Clone + Into<Datatype>>(
+    mut order_key: OrderKey,
+    primary_key: &K,
+) -> OrderKey {
+    order_key.push(primary_key.clone().into(), false);
+    order_key
+}
+
+/// `Collection::upsert` for the maps keyed by primary key, which share no
+/// trait to look the key up through.
+macro_rules! upsert_by_primary_key {
+    ($map:expr, $item:expr, $order_key:expr) => {{
+        let key = $item.primary_key().clone();
+        let upsert = match $map.get(&key) {
+            None => Upsert::Inserted,
+            Some(existing) if *existing == $item => Upsert::Unchanged,
+            Some(_) => Upsert::Replaced,
+        };
+        let order_key = order_key_with_primary_key($order_key, &key);
+        if upsert == Upsert::Unchanged {
+            $map.update_order_for_key(&key, order_key);
+        } else {
+            $map.insert(key, $item, order_key);
+        }
+        upsert
+    }};
+}
+
+/// Rows keyed by primary key and iterated in ORDER BY order.
+///
+/// Unlike `BTreeMap<OrderKey, T>`, updates locate their row by key rather
+/// than by scanning, so this is the preferred type for long-lived lists.
+impl<K, T> Collection for OrderedMap<K, T, OrderKey>
+where
+    K: Eq + Hash + Clone + Into<Datatype> + Send + Sync + 'static,
+    T: PrimaryKeyedRow<PrimaryKey = K>,
+{
+    type Item = T;
+
+    fn from_vec(items: Vec<T>, order_keys: Vec<OrderKey>) -> Self {
+        items
+            .into_iter()
+            .zip(order_keys)
+            .map(|(item, order_key)| {
+                let key = item.primary_key().clone();
+                let order_key = order_key_with_primary_key(order_key, &key);
+                (key, item, order_key)
+            })
+            .collect()
+    }
+
+    fn push(&mut self, item: T, order_key: OrderKey) {
+        let key = item.primary_key().clone();
+        let order_key = order_key_with_primary_key(order_key, &key);
+        self.insert(key, item, order_key);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.values_mut()
+    }
+
+    fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        OrderedMap::retain(self, f);
+    }
+
+    fn remove_positions(&mut self, positions: &[usize]) {
+        let mut keep = not_at(positions);
+        let keys: Vec<K> = self.keys().filter(|_| !keep()).cloned().collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    fn update_each(&mut self, mut f: impl FnMut(&T) -> Option<T>) {
+        // `iter_mut` visits the rows in hash order.
+        for item in self.values_mut_ordered() {
+            if let Some(updated) = f(item) {
+                *item = updated;
+            }
+        }
+    }
+
+    fn update_order(&mut self, item: &T, order_key: OrderKey) {
+        let key = item.primary_key();
+        self.update_order_for_key(key, order_key_with_primary_key(order_key, key));
+    }
+
+    fn upsert(
+        &mut self,
+        item: T,
+        order_key: OrderKey,
+        _same_key: impl FnMut(&T) -> bool,
+    ) -> Upsert {
+        upsert_by_primary_key!(self, item, order_key)
+    }
+}
+
+impl<K, T> OrderedCollection for OrderedMap<K, T, OrderKey>
+where
+    K: Eq + Hash + Clone + Into<Datatype> + Send + Sync + 'static,
+    T: PrimaryKeyedRow<PrimaryKey = K>,
+{
+    fn update_order_between(
+        &mut self,
+        item: &T,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Option<String> {
+        let order_key_of = |item: &T| self.order_key_of(item.primary_key());
+        let order_key = order_key_of(item)?;
+        let before = match before {
+            Some(before) => Some(order_key_of(before)?),
+            None => None,
+        };
+        let after = match after {
+            Some(after) => Some(order_key_of(after)?),
+            None => None,
+        };
+
+        // The primary key tie-breaker carries over from the old order key.
+        let (moved, index) = order_key_between(order_key, before, after)?;
+        self.update_order_for_key(item.primary_key(), moved)?;
+        Some(index)
+    }
+}
+
+// --- ArcOrderedMap implementation ---
+
+/// Like the `OrderedMap` collection, but clones share storage, so handing a
+/// snapshot of the subscription's data to a reader after every merge doesn't
+/// copy the rows.
+impl<K, T> Collection for ArcOrderedMap<K, T, OrderKey>
+where
+    K: Eq + Hash + Clone + Into<Datatype> + Send + Sync + 'static,
+    T: PrimaryKeyedRow<PrimaryKey = K> + Sync,
+{
+    type Item = T;
+
+    fn from_vec(items: Vec<T>, order_keys: Vec<OrderKey>) -> Self {
+        items
+            .into_iter()
+            .zip(order_keys)
+            .map(|(item, order_key)| {
+                let key = item.primary_key().clone();
+                let order_key = order_key_with_primary_key(order_key, &key);
+                (key, item, order_key)
+            })
+            .collect()
+    }
+
+    fn push(&mut self, item: T, order_key: OrderKey) {
+        let key = item.primary_key().clone();
+        let order_key = order_key_with_primary_key(order_key, &key);
+        self.insert(key, item, order_key);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        // Un-shares every row; merges go through `update_each` instead.
+        self.values_mut()
+    }
+
+    fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        ArcOrderedMap::retain(self, f);
+    }
+
+    fn remove_positions(&mut self, positions: &[usize]) {
+        let mut keep = not_at(positions);
+        let keys: Vec<K> = self.keys().filter(|_| !keep()).cloned().collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    fn update_each(&mut self, mut f: impl FnMut(&T) -> Option<T>) {
+        let updates: Vec<(K, T)> = self
+            .iter()
+            .filter_map(|(key, _, item)| f(item).map(|updated| (key.clone(), updated)))
+            .collect();
+        for (key, updated) in updates {
+            self.replace(&key, updated);
+        }
+    }
+
+    fn update_order(&mut self, item: &T, order_key: OrderKey) {
+        let key = item.primary_key();
+        self.update_order_for_key(key, order_key_with_primary_key(order_key, key));
+    }
+
+    fn upsert(
+        &mut self,
+        item: T,
+        order_key: OrderKey,
+        _same_key: impl FnMut(&T) -> bool,
+    ) -> Upsert {
+        upsert_by_primary_key!(self, item, order_key)
+    }
+}
+
+impl<K, T> OrderedCollection for ArcOrderedMap<K, T, OrderKey>
+where
+    K: Eq + Hash + Clone + Into<Datatype> + Send + Sync + 'static,
+    T: PrimaryKeyedRow<PrimaryKey = K> + Sync,
+{
+    fn update_order_between(
+        &mut self,
+        item: &T,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Option<String> {
+        let order_key_of = |item: &T| self.order_key_of(item.primary_key());
+        let order_key = order_key_of(item)?;
+        let before = match before {
+            Some(before) => Some(order_key_of(before)?),
+            None => None,
+        };
+        let after = match after {
+            Some(after) => Some(order_key_of(after)?),
+            None => None,
+        };
+
+        let (moved, index) = order_key_between(order_key, before, after)?;
+        self.update_order_for_key(item.primary_key(), moved)?;
+        Some(index)
+    }
+}
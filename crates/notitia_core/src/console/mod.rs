@@ -0,0 +1,228 @@
+//! A query console for poking at a live database, e.g. from an in-app
+//! developer menu. It understands a small subset of SQL, checked against
+//! [`Database::schema`]:
+//!
+//! ```text
+//! tables
+//! describe messages
+//! select id, body from messages where conversation_id = 'abc' and edited_at is not null
+//!     order by created_at desc limit 20
+//! ```
+//!
+//! Statements go through the adapter directly, so only reads are supported.
+
+mod parse;
+mod render;
+
+use crate::{
+    Adapter, Database, Datatype, DatatypeKind, FieldExpr, FieldFilter, FieldFilterMetadata,
+    Notitia, OrderBy, SchemaDef, SelectStmtDef, TableDef, TableFieldPair,
+};
+
+use parse::{Command, Condition, Literal, Op, SelectCommand};
+use render::{render_cell, render_table};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConsoleError<E: std::error::Error> {
+    #[error("{0}")]
+    Parse(String),
+    #[error("no table named `{0}`")]
+    UnknownTable(String),
+    #[error("`{table}` has no column named `{column}`")]
+    UnknownColumn { table: &'static str, column: String },
+    #[error("`{table}.{column}`: {message}")]
+    BadValue {
+        table: &'static str,
+        column: &'static str,
+        message: String,
+    },
+    #[error("query failed: {0}")]
+    Query(E),
+}
+
+pub struct Console<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    db: Notitia<Db, Adptr>,
+    schema: SchemaDef,
+}
+
+impl<Db, Adptr> Console<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    pub fn new(db: Notitia<Db, Adptr>) -> Self {
+        Self {
+            db,
+            schema: Db::schema(),
+        }
+    }
+
+    /// Run one command and return its output, ready to print.
+    pub async fn run(&self, input: &str) -> Result<String, ConsoleError<Adptr::Error>> {
+        match parse::parse(input).map_err(ConsoleError::Parse)? {
+            Command::Tables => Ok(self.tables()),
+            Command::Describe(table) => Ok(describe(self.table(&table)?)),
+            Command::Select(select) => self.select(select).await,
+        }
+    }
+
+    fn tables(&self) -> String {
+        let names: Vec<&str> = self.schema.tables.iter().map(|table| table.name).collect();
+        names.join("\n")
+    }
+
+    fn table(&self, name: &str) -> Result<&TableDef, ConsoleError<Adptr::Error>> {
+        self.schema
+            .table(name)
+            .ok_or_else(|| ConsoleError::UnknownTable(name.to_string()))
+    }
+
+    async fn select(&self, select: SelectCommand) -> Result<String, ConsoleError<Adptr::Error>> {
+        let table = self.table(&select.table)?;
+        let column = |name: &str| {
+            table.column(name).map(|column| column.name).ok_or_else(|| {
+                ConsoleError::UnknownColumn {
+                    table: table.name,
+                    column: name.to_string(),
+                }
+            })
+        };
+
+        let fields = match select.fields {
+            Some(fields) => fields
+                .iter()
+                .map(|name| column(name))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => table.columns.iter().map(|column| column.name).collect(),
+        };
+
+        let filters = select
+            .filters
+            .into_iter()
+            .map(|condition| filter(table, condition))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let order_by = select
+            .order_by
+            .iter()
+            .map(|(name, direction)| {
+                Ok(OrderBy {
+                    expr: FieldExpr::Field(column(name)?),
+                    table: table.name,
+                    direction: *direction,
+                })
+            })
+            .collect::<Result<Vec<_>, ConsoleError<Adptr::Error>>>()?;
+
+        let def = SelectStmtDef {
+            tables: vec![table.name],
+            fields,
+            filters,
+            order_by,
+            limit: select.limit,
+            per_group: None,
+        };
+        let rows = self
+            .db
+            .execute_select_def(&def)
+            .await
+            .map_err(ConsoleError::Query)?;
+
+        // Rows end with the order values, which aren't shown.
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| row.iter().take(def.fields.len()).map(render_cell).collect())
+            .collect();
+        Ok(render_table(&def.fields, &rows))
+    }
+}
+
+fn describe(table: &TableDef) -> String {
+    let rows: Vec<Vec<String>> = table
+        .columns
+        .iter()
+        .map(|column| {
+            let metadata = column.kind.metadata();
+            let mut flags = Vec::new();
+            if metadata.primary_key {
+                flags.push("primary key");
+            }
+            if metadata.unique {
+                flags.push("unique");
+            }
+            if metadata.optional {
+                flags.push("nullable");
+            }
+            vec![
+                column.name.to_string(),
+                column.kind.name().to_string(),
+                flags.join(", "),
+            ]
+        })
+        .collect();
+    render_table(&["column", "type", ""], &rows)
+}
+
+fn filter<E: std::error::Error>(
+    table: &TableDef,
+    condition: Condition,
+) -> Result<FieldFilter, ConsoleError<E>> {
+    let column = table
+        .column(&condition.column)
+        .ok_or_else(|| ConsoleError::UnknownColumn {
+            table: table.name,
+            column: condition.column.clone(),
+        })?;
+    let bad_value = |message: String| ConsoleError::BadValue {
+        table: table.name,
+        column: column.name,
+        message,
+    };
+
+    let value = literal_to_datatype(&column.kind, condition.value).map_err(bad_value)?;
+    if matches!(value, Datatype::Null) && !matches!(condition.op, Op::Is | Op::IsNot) {
+        return Err(bad_value("compare with NULL using `is` or `is not`".into()));
+    }
+
+    let metadata = FieldFilterMetadata {
+        left: TableFieldPair::new(table.name, column.name),
+        right: value,
+    };
+    Ok(match condition.op {
+        Op::Eq => FieldFilter::Eq(metadata),
+        Op::Ne => FieldFilter::Ne(metadata),
+        Op::Lt => FieldFilter::Lt(metadata),
+        Op::Lte => FieldFilter::Lte(metadata),
+        Op::Gt => FieldFilter::Gt(metadata),
+        Op::Gte => FieldFilter::Gte(metadata),
+        Op::Is => FieldFilter::EqNullable(metadata),
+        Op::IsNot => FieldFilter::DistinctFrom(metadata),
+    })
+}
+
+/// Convert a literal to the column's type, so that `id = 1` compares with an
+/// `Int` column's values and not with a `BigInt`.
+fn literal_to_datatype(kind: &DatatypeKind, literal: Literal) -> Result<Datatype, String> {
+    fn number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+        text.parse()
+            .map_err(|_| format!("`{text}` isn't a valid value for this column"))
+    }
+
+    Ok(match (kind, literal) {
+        (_, Literal::Null) => Datatype::Null,
+        (DatatypeKind::Int(_), Literal::Number(n)) => Datatype::Int(number(&n)?),
+        (DatatypeKind::BigInt(_), Literal::Number(n)) => Datatype::BigInt(number(&n)?),
+        (DatatypeKind::Float(_), Literal::Number(n)) => Datatype::Float(number(&n)?),
+        (DatatypeKind::Double(_), Literal::Number(n)) => Datatype::Double(number(&n)?),
+        (DatatypeKind::Text(_), Literal::Text(text)) => Datatype::Text(text),
+        (DatatypeKind::Bool(_), Literal::Bool(b)) => Datatype::Bool(b),
+        (DatatypeKind::Blob(_), _) => return Err("blobs can't be compared here".into()),
+        (kind, literal) => {
+            return Err(format!("expected a {} value, got {literal:?}", kind.name()));
+        }
+    })
+}
@@ -0,0 +1,363 @@
+use crate::OrderDirection;
+
+/// A console command, with names not yet checked against the schema.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Tables,
+    Describe(String),
+    Select(SelectCommand),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct SelectCommand {
+    pub table: String,
+    /// `None` for `*`.
+    pub fields: Option<Vec<String>>,
+    pub filters: Vec<Condition>,
+    pub order_by: Vec<(String, OrderDirection)>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Condition {
+    pub column: String,
+    pub op: Op,
+    pub value: Literal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Is,
+    IsNot,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Literal {
+    Text(String),
+    /// Kept as written until the column's type is known.
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(String),
+    Op(&'static str),
+    Comma,
+    Star,
+}
+
+pub(crate) fn parse(input: &str) -> Result<Command, String> {
+    let mut tokens = Tokens {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+
+    let command = match tokens.next_word()?.as_str() {
+        "tables" => Command::Tables,
+        "describe" => Command::Describe(tokens.next_word()?),
+        "select" => Command::Select(parse_select(&mut tokens)?),
+        other => return Err(format!("unknown command `{other}`, try `select`")),
+    };
+
+    match tokens.peek() {
+        None => Ok(command),
+        Some(token) => Err(format!("unexpected {token:?} after the command")),
+    }
+}
+
+fn parse_select(tokens: &mut Tokens) -> Result<SelectCommand, String> {
+    let fields = if tokens.eat(&Token::Star) {
+        None
+    } else {
+        Some(tokens.comma_separated(|tokens| tokens.next_word())?)
+    };
+
+    tokens.expect_keyword("from")?;
+    let table = tokens.next_word()?;
+
+    let mut filters = Vec::new();
+    if tokens.eat_keyword("where") {
+        loop {
+            filters.push(parse_condition(tokens)?);
+            if !tokens.eat_keyword("and") {
+                break;
+            }
+        }
+    }
+
+    let mut order_by = Vec::new();
+    if tokens.eat_keyword("order") {
+        tokens.expect_keyword("by")?;
+        order_by = tokens.comma_separated(|tokens| {
+            let column = tokens.next_word()?;
+            let direction = if tokens.eat_keyword("desc") {
+                OrderDirection::Desc
+            } else {
+                tokens.eat_keyword("asc");
+                OrderDirection::Asc
+            };
+            Ok((column, direction))
+        })?;
+    }
+
+    let limit = if tokens.eat_keyword("limit") {
+        match tokens.next() {
+            Some(Token::Number(n)) => Some(n.parse().map_err(|_| format!("bad limit `{n}`"))?),
+            other => return Err(format!("expected a number after `limit`, got {other:?}")),
+        }
+    } else {
+        None
+    };
+
+    Ok(SelectCommand {
+        table,
+        fields,
+        filters,
+        order_by,
+        limit,
+    })
+}
+
+fn parse_condition(tokens: &mut Tokens) -> Result<Condition, String> {
+    let column = tokens.next_word()?;
+
+    let op = if tokens.eat_keyword("is") {
+        if tokens.eat_keyword("not") {
+            Op::IsNot
+        } else {
+            Op::Is
+        }
+    } else {
+        match tokens.next() {
+            Some(Token::Op(op)) => match op {
+                "=" => Op::Eq,
+                "!=" => Op::Ne,
+                "<" => Op::Lt,
+                "<=" => Op::Lte,
+                ">" => Op::Gt,
+                ">=" => Op::Gte,
+                _ => unreachable!("tokenize only produces these"),
+            },
+            other => {
+                return Err(format!(
+                    "expected a comparison after `{column}`, got {other:?}"
+                ));
+            }
+        }
+    };
+
+    let value = match tokens.next() {
+        Some(Token::Text(text)) => Literal::Text(text),
+        Some(Token::Number(number)) => Literal::Number(number),
+        Some(Token::Word(word)) => match word.as_str() {
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            "null" => Literal::Null,
+            _ => return Err(format!("expected a value, got `{word}`; quote text with '")),
+        },
+        other => return Err(format!("expected a value, got {other:?}")),
+    };
+
+    Ok(Condition { column, op, value })
+}
+
+struct Tokens {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Tokens {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get_mut(self.pos)?;
+        self.pos += 1;
+        // Tokens are read once, so they can be taken out.
+        Some(std::mem::replace(token, Token::Comma))
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Keywords are matched case-insensitively; they are lowercased while
+    /// tokenizing.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word == keyword => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("expected `{keyword}`, got {:?}", self.peek()))
+        }
+    }
+
+    fn next_word(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(format!("expected a name, got {other:?}")),
+        }
+    }
+
+    fn comma_separated<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut items = vec![item(self)?];
+        while self.eat(&Token::Comma) {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '*' => {
+                chars.next();
+                tokens.push(Token::Star);
+            }
+            '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // '' is an escaped quote, like in SQL.
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            text.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let op = match (c, chars.peek()) {
+                    ('!', Some('=')) => "!=",
+                    ('<', Some('=')) => "<=",
+                    ('>', Some('=')) => ">=",
+                    ('<', Some('>')) => "!=",
+                    ('!', _) => return Err("expected `!=`".to_string()),
+                    ('=', _) => "=",
+                    ('<', _) => "<",
+                    _ => ">",
+                };
+                if op.len() == 2 {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '-' || c == '.' || c == 'e' || c == 'E') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                // Table and column names are snake_case.
+                tokens.push(Token::Word(word.to_lowercase()));
+            }
+            c => return Err(format!("unexpected `{c}`")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_select() {
+        let command = parse(
+            "SELECT id, body FROM messages WHERE conversation_id = 'it''s' AND edited_at IS NOT null \
+             ORDER BY created_at DESC, id LIMIT 20",
+        )
+        .unwrap();
+
+        assert_eq!(
+            command,
+            Command::Select(SelectCommand {
+                table: "messages".into(),
+                fields: Some(vec!["id".into(), "body".into()]),
+                filters: vec![
+                    Condition {
+                        column: "conversation_id".into(),
+                        op: Op::Eq,
+                        value: Literal::Text("it's".into()),
+                    },
+                    Condition {
+                        column: "edited_at".into(),
+                        op: Op::IsNot,
+                        value: Literal::Null,
+                    },
+                ],
+                order_by: vec![
+                    ("created_at".into(), OrderDirection::Desc),
+                    ("id".into(), OrderDirection::Asc),
+                ],
+                limit: Some(20),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_trailing_tokens() {
+        assert!(parse("select * from users 5").is_err());
+        assert!(parse("describe").is_err());
+    }
+}
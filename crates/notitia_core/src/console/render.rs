@@ -0,0 +1,44 @@
+use crate::Datatype;
+
+pub(crate) fn render_cell(value: &Datatype) -> String {
+    match value {
+        Datatype::Null => "NULL".to_string(),
+        Datatype::Blob(bytes) => format!("<{} bytes>", bytes.len()),
+        other => other.to_string(),
+    }
+}
+
+/// Lay `rows` out under `headers` as a plain-text table, followed by the
+/// row count.
+pub(crate) fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    push_line(&mut out, headers.iter().copied(), &widths);
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    push_line(&mut out, separator.iter().map(String::as_str), &widths);
+    for row in rows {
+        push_line(&mut out, row.iter().map(String::as_str), &widths);
+    }
+
+    let noun = if rows.len() == 1 { "row" } else { "rows" };
+    out.push_str(&format!("({} {noun})", rows.len()));
+    out
+}
+
+fn push_line<'a>(out: &mut String, cells: impl Iterator<Item = &'a str>, widths: &[usize]) {
+    let line: Vec<String> = cells
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect();
+    out.push_str(line.join(" | ").trim_end());
+    out.push('\n');
+}
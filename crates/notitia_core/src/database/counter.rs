@@ -0,0 +1,124 @@
+use crate::{
+    Adapter, Database, Datatype, FieldFilter, FieldFilterMetadata, FieldKind, FieldKindOfDatabase,
+    InnerFieldType, Notitia, SelectStmtDef, StrongFieldKind, TableFieldPair,
+};
+
+/// The number of rows of a table by the value of one of its columns, kept
+/// in a side table so that reading it doesn't scan, declared with
+/// `#[db(counted_by(...))]` on the table's field:
+///
+/// ```ignore
+/// #[database]
+/// struct ChatDatabase {
+///     #[db(counted_by(conversation_id))]
+///     messages: Table<Message>,
+/// }
+///
+/// let unread = db.count_by(Message::CONVERSATION_ID, conversation_id).await?;
+/// ```
+///
+/// The side table is maintained by triggers, so its counts change in the
+/// same transaction as the rows they count, whichever statement or process
+/// wrote them. Rows whose column is NULL aren't counted.
+#[derive(Clone, Debug)]
+pub struct CounterDef {
+    pub table_name: &'static str,
+    pub column: &'static str,
+    /// `{table}_counts_by_{column}`, with a `value` and a `count` column.
+    pub counts_table: &'static str,
+}
+
+impl CounterDef {
+    /// Statements that create the side table, fill it from the table's rows
+    /// if it's empty, and replace its triggers.
+    pub fn sql(&self) -> String {
+        let Self {
+            table_name,
+            column,
+            counts_table,
+        } = self;
+        let increment = |value: &str| {
+            format!(
+                "INSERT INTO \"{counts_table}\" (\"value\", \"count\") SELECT {value}, 1 WHERE {value} IS NOT NULL ON CONFLICT (\"value\") DO UPDATE SET \"count\" = \"count\" + 1;"
+            )
+        };
+        let decrement = |value: &str| {
+            format!(
+                "UPDATE \"{counts_table}\" SET \"count\" = \"count\" - 1 WHERE \"value\" = {value}; DELETE FROM \"{counts_table}\" WHERE \"value\" = {value} AND \"count\" <= 0;"
+            )
+        };
+        let trigger = |suffix: &str, event: &str, when: &str, body: String| {
+            let name = format!("{counts_table}_{suffix}");
+            format!(
+                "DROP TRIGGER IF EXISTS \"{name}\";\n\nCREATE TRIGGER \"{name}\" {event} ON \"{table_name}\" FOR EACH ROW {when}BEGIN {body} END;"
+            )
+        };
+        let old = format!("OLD.\"{column}\"");
+        let new = format!("NEW.\"{column}\"");
+
+        [
+            format!(
+                "CREATE TABLE IF NOT EXISTS \"{counts_table}\" (\"value\" PRIMARY KEY, \"count\" integer NOT NULL);"
+            ),
+            // Once, when the counter is added to a table that has rows.
+            format!(
+                "INSERT INTO \"{counts_table}\" (\"value\", \"count\") SELECT \"{column}\", COUNT(*) FROM \"{table_name}\" WHERE \"{column}\" IS NOT NULL AND NOT EXISTS (SELECT 1 FROM \"{counts_table}\") GROUP BY \"{column}\";"
+            ),
+            trigger("after_insert", "AFTER INSERT", "", increment(&new)),
+            trigger("after_delete", "AFTER DELETE", "", decrement(&old)),
+            trigger(
+                "after_update",
+                &format!("AFTER UPDATE OF \"{column}\""),
+                &format!("WHEN {old} IS NOT {new} "),
+                format!("{} {}", decrement(&old), increment(&new)),
+            ),
+        ]
+        .join("\n\n")
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// The number of rows whose `field` is `value`, read from the counter
+    /// declared with `#[db(counted_by(...))]`, see [`CounterDef`]. Panics if
+    /// `field` has no counter.
+    pub async fn count_by<Field, T>(
+        &self,
+        field: StrongFieldKind<Field, T>,
+        value: impl Into<T::Inner>,
+    ) -> Result<u64, Adptr::Error>
+    where
+        Field: FieldKindOfDatabase<Db>,
+        T: InnerFieldType,
+    {
+        let table_name = Field::table_name();
+        let column = field.kind.name();
+        let Some(counter) = self
+            .database()
+            .counters()
+            .find(|counter| counter.table_name == table_name && counter.column == column)
+        else {
+            panic!("count_by: `{table_name}.{column}` isn't `#[db(counted_by(...))]`");
+        };
+
+        let rows = self
+            .adapter()
+            .execute_select_def(&SelectStmtDef {
+                tables: vec![counter.counts_table],
+                fields: vec!["count"],
+                filters: vec![FieldFilter::Eq(FieldFilterMetadata {
+                    left: TableFieldPair::new(counter.counts_table, "value"),
+                    right: value.into().into(),
+                })],
+                order_by: Vec::new(),
+                limit: Some(1),
+                per_group: None,
+            })
+            .await?;
+
+        Ok(match rows.first().and_then(|row| row.first()) {
+            Some(Datatype::BigInt(count)) => *count as u64,
+            Some(Datatype::Int(count)) => *count as u64,
+            _ => 0,
+        })
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use futures_util::future::BoxFuture;
+
+use crate::{
+    Adapter, BuiltRecord, BulkInsertStmtBuilt, Database, IsTable, Notitia, Record, StrongTableKind,
+};
+
+/// Seed data applied when a database is opened.
+///
+/// Each entry targets one table and is guarded either by the table being
+/// empty ([`table`](Self::table)) or by a named version
+/// ([`versioned`](Self::versioned)), so applying the same fixtures on every
+/// start is safe.
+///
+/// ```ignore
+/// let fixtures = Fixtures::new()
+///     .table(MyDb::SETTINGS, [Setting::build().key("theme").value("dark")])
+///     .versioned("demo_users", 2, MyDb::USERS, demo_users());
+///
+/// let db = MyDb::connect::<SqliteAdapter>("sqlite::memory:")
+///     .await?
+///     .with_fixtures(fixtures)
+///     .await?;
+/// ```
+pub struct Fixtures<Db: Database, Adptr: Adapter> {
+    fixtures: Vec<Fixture<Db, Adptr>>,
+}
+
+struct Fixture<Db: Database, Adptr: Adapter> {
+    table_name: &'static str,
+    condition: FixtureCondition,
+    seed: Box<dyn FixtureSeed<Db, Adptr>>,
+}
+
+enum FixtureCondition {
+    IfEmpty,
+    Version { name: &'static str, version: u32 },
+}
+
+trait FixtureSeed<Db: Database, Adptr: Adapter>: Send {
+    fn apply<'a>(
+        self: Box<Self>,
+        db: &'a Notitia<Db, Adptr>,
+    ) -> BoxFuture<'a, Result<(), Adptr::Error>>;
+}
+
+impl<Db, Adptr, R> FixtureSeed<Db, Adptr> for BulkInsertStmtBuilt<Db, R>
+where
+    Db: Database,
+    Adptr: Adapter,
+    R: Record + Send,
+{
+    fn apply<'a>(
+        self: Box<Self>,
+        db: &'a Notitia<Db, Adptr>,
+    ) -> BoxFuture<'a, Result<(), Adptr::Error>> {
+        // Going through `mutate` keeps hooks (e.g. embeddings) in the loop.
+        Box::pin(db.mutate(*self).execute())
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Default for Fixtures<Db, Adptr> {
+    fn default() -> Self {
+        Self {
+            fixtures: Vec::new(),
+        }
+    }
+}
+
+impl<Db, Adptr> Fixtures<Db, Adptr>
+where
+    Db: Database + 'static,
+    Adptr: Adapter,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `records` into `table` if it has no rows yet.
+    pub fn table<Tbl, B>(
+        self,
+        table: StrongTableKind<Db, Tbl>,
+        records: impl IntoIterator<Item = B>,
+    ) -> Self
+    where
+        Tbl: IsTable<Database = Db>,
+        Tbl::Record: Send + 'static,
+        B: BuiltRecord<Record = Tbl::Record>,
+    {
+        self.push(table, FixtureCondition::IfEmpty, records)
+    }
+
+    /// Insert `records` into `table` unless fixture `name` has already been
+    /// applied at `version` or later.
+    ///
+    /// Several entries can share a name. Only those newer than the recorded
+    /// version are inserted, so seed rows can be added later under a higher
+    /// version without inserting the earlier ones again.
+    pub fn versioned<Tbl, B>(
+        self,
+        name: &'static str,
+        version: u32,
+        table: StrongTableKind<Db, Tbl>,
+        records: impl IntoIterator<Item = B>,
+    ) -> Self
+    where
+        Tbl: IsTable<Database = Db>,
+        Tbl::Record: Send + 'static,
+        B: BuiltRecord<Record = Tbl::Record>,
+    {
+        self.push(table, FixtureCondition::Version { name, version }, records)
+    }
+
+    fn push<Tbl, B>(
+        mut self,
+        table: StrongTableKind<Db, Tbl>,
+        condition: FixtureCondition,
+        records: impl IntoIterator<Item = B>,
+    ) -> Self
+    where
+        Tbl: IsTable<Database = Db>,
+        Tbl::Record: Send + 'static,
+        B: BuiltRecord<Record = Tbl::Record>,
+    {
+        let stmt = table.insert_many(records);
+        self.fixtures.push(Fixture {
+            table_name: stmt.table_name,
+            condition,
+            seed: Box::new(stmt),
+        });
+        self
+    }
+
+    pub(crate) async fn apply(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
+        let adapter = &db.inner.adapter;
+
+        // A name's version is looked up once and only recorded after every
+        // entry has run, so later entries under the same name still apply.
+        let mut applied_versions: HashMap<&'static str, Option<u32>> = HashMap::new();
+        let mut bumped: Vec<(&'static str, u32)> = Vec::new();
+
+        for fixture in self.fixtures {
+            match fixture.condition {
+                FixtureCondition::IfEmpty => {
+                    if !adapter.is_table_empty(fixture.table_name).await? {
+                        continue;
+                    }
+                }
+                FixtureCondition::Version { name, version } => {
+                    let applied = match applied_versions.get(name) {
+                        Some(applied) => *applied,
+                        None => {
+                            let applied = adapter.fixture_version(name).await?;
+                            applied_versions.insert(name, applied);
+                            applied
+                        }
+                    };
+                    if applied.is_some_and(|applied| applied >= version) {
+                        continue;
+                    }
+                    match bumped.iter_mut().find(|(bumped, _)| *bumped == name) {
+                        Some((_, bumped_version)) => {
+                            *bumped_version = (*bumped_version).max(version)
+                        }
+                        None => bumped.push((name, version)),
+                    }
+                }
+            }
+
+            fixture.seed.apply(db).await?;
+        }
+
+        for (name, version) in bumped {
+            adapter.set_fixture_version(name, version).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -22,7 +22,21 @@ impl ForeignRelationship {
     }
 }
 
-#[derive(Default, Debug)]
+/// A foreign key of a database, from `table.field` to
+/// `foreign_table.foreign_field`, see [`Database::relationships`].
+///
+/// [`Database::relationships`]: crate::Database::relationships
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Relationship {
+    pub table: &'static str,
+    pub field: &'static str,
+    pub foreign_table: &'static str,
+    pub foreign_field: &'static str,
+    pub on_delete: OnAction,
+    pub on_update: OnAction,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OnAction {
     #[default]
     NoAction,
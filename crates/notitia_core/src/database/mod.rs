@@ -1,10 +1,20 @@
+mod counter;
+mod fixtures;
 mod foreign_relationship;
+mod schema_def;
+mod schema_doc;
+mod schema_typescript;
+mod trigger;
 
-pub use foreign_relationship::{ForeignRelationship, OnAction};
+pub use counter::*;
+pub use fixtures::Fixtures;
+pub use foreign_relationship::{ForeignRelationship, OnAction, Relationship};
+pub use schema_def::*;
+pub use trigger::*;
 
 use crate::{
-    Adapter, DatatypeKind, DatatypeKindMetadata, FieldsDef, Notitia, TableKind,
-    utils::iter_join::Join,
+    Adapter, ComposedTableKind, DatatypeKind, DatatypeKindMetadata, FieldsDef, Notitia,
+    OrderDirection, TableKind, utils::iter_join::Join,
 };
 
 pub struct EmbeddedTableDef {
@@ -50,6 +60,8 @@ pub(crate) fn set_column_type<'a>(
         DatatypeKind::Text(metadata) => set_column_metadata(column.text(), metadata),
         DatatypeKind::Blob(metadata) => set_column_metadata(column.blob(), metadata),
         DatatypeKind::Bool(metadata) => set_column_metadata(column.boolean(), metadata),
+        // Stored as JSON, which every backend has a column type for.
+        DatatypeKind::Array(metadata, _) => set_column_metadata(column.json(), metadata),
     }
 }
 
@@ -70,6 +82,162 @@ pub trait Database: Send + Sync + Sized {
         std::iter::empty()
     }
 
+    /// The foreign keys declared on `table_name`, including those of tables
+    /// that come from an included database.
+    fn foreign_relationships(
+        &self,
+        table_name: &str,
+    ) -> Option<&'static phf::Map<&'static str, ForeignRelationship>> {
+        Self::_FOREIGN_RELATIONSHIPS.get(table_name)
+    }
+
+    /// The name of the `#[record]` struct of each table, by table name,
+    /// including those of included databases.
+    fn record_names(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        std::iter::empty()
+    }
+
+    /// The triggers declared with `#[db(trigger(...))]`, including those of
+    /// included databases.
+    fn triggers(&self) -> impl Iterator<Item = TriggerDef> {
+        std::iter::empty()
+    }
+
+    /// The counters declared with `#[db(counted_by(...))]`, including those
+    /// of included databases.
+    fn counters(&self) -> impl Iterator<Item = CounterDef> {
+        std::iter::empty()
+    }
+
+    /// Every foreign key, including those of included databases, e.g. for
+    /// walking from a row to the rows that refer to it.
+    fn relationships() -> Vec<Relationship> {
+        let db = Self::new();
+        db.tables()
+            .flat_map(|(table, _)| {
+                db.foreign_relationships(table)
+                    .into_iter()
+                    .flat_map(|relationships| relationships.entries())
+                    .map(move |(field, relationship)| Relationship {
+                        table,
+                        field: *field,
+                        foreign_table: relationship.foreign_table,
+                        foreign_field: relationship.foreign_field,
+                        on_delete: relationship.on_delete,
+                        on_update: relationship.on_update,
+                    })
+            })
+            .collect()
+    }
+
+    /// The indexes declared with `#[db(index(...))]` and the constraints
+    /// declared with `#[db(unique(...))]`, by the name of their table,
+    /// including those of included databases.
+    fn indexes(&self) -> impl Iterator<Item = (&'static str, IndexDef)> {
+        std::iter::empty()
+    }
+
+    /// The tables, columns, indexes and foreign keys of this database.
+    fn schema() -> SchemaDef {
+        let db = Self::new();
+        let triggers: Vec<TriggerDef> = db.triggers().collect();
+        let declared_indexes: Vec<(&'static str, IndexDef)> = db.indexes().collect();
+        let relationships = Self::relationships();
+        let record_names: Vec<(&'static str, &'static str)> = db.record_names().collect();
+
+        let tables = db
+            .tables()
+            .map(|(name, fields)| {
+                let columns: Vec<ColumnDef> = fields
+                    .iter()
+                    .map(|(column, kind)| ColumnDef {
+                        name: *column,
+                        kind: kind.clone(),
+                    })
+                    .collect();
+
+                let foreign_keys = relationships
+                    .iter()
+                    .filter(|relationship| relationship.table == name)
+                    .map(|relationship| ForeignKeyDef {
+                        column: relationship.field,
+                        foreign_table: relationship.foreign_table,
+                        foreign_column: relationship.foreign_field,
+                        on_delete: relationship.on_delete,
+                        on_update: relationship.on_update,
+                    })
+                    .collect();
+
+                let indexes = columns
+                    .iter()
+                    .filter(|column| column.kind.metadata().unique)
+                    .map(|column| IndexDef {
+                        name: None,
+                        columns: vec![IndexColumnDef {
+                            name: column.name,
+                            direction: OrderDirection::Asc,
+                        }],
+                        include: Vec::new(),
+                        unique: true,
+                    })
+                    .chain(
+                        declared_indexes
+                            .iter()
+                            .filter(|(table_name, _)| *table_name == name)
+                            .map(|(_, index)| index.clone()),
+                    )
+                    .collect();
+
+                TableDef {
+                    name,
+                    record_name: record_names
+                        .iter()
+                        .find(|(table_name, _)| *table_name == name)
+                        .map(|(_, record_name)| *record_name),
+                    columns,
+                    foreign_keys,
+                    indexes,
+                    triggers: triggers
+                        .iter()
+                        .filter(|trigger| trigger.table_name == name)
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect();
+
+        SchemaDef { tables }
+    }
+
+    /// A Markdown reference of the schema, see [`SchemaDef::to_markdown`].
+    fn schema_markdown() -> String {
+        Self::schema().to_markdown()
+    }
+
+    /// A Graphviz diagram of the tables and their foreign keys, see
+    /// [`SchemaDef::to_dot`].
+    fn schema_dot() -> String {
+        Self::schema().to_dot()
+    }
+
+    /// TypeScript interfaces of the records and constants of the table and
+    /// column names, see [`SchemaDef::to_typescript`].
+    fn schema_typescript() -> String {
+        Self::schema().to_typescript()
+    }
+
+    /// Write [`schema_typescript`](Self::schema_typescript) to `path`, e.g.
+    /// from the build script of a frontend. An up to date file is left
+    /// untouched, so that watchers don't rebuild.
+    fn write_schema_typescript(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let typescript = Self::schema_typescript();
+        if std::fs::read_to_string(path).is_ok_and(|current| current == typescript) {
+            return Ok(());
+        }
+        std::fs::write(path, typescript)
+    }
+
     fn schema_sql(&self, schema_builder: impl sea_query::SchemaBuilder) -> String {
         fn set_relationship_on_delete<'a>(
             relationship: &'a mut sea_query::ForeignKeyCreateStatement,
@@ -115,7 +283,7 @@ pub trait Database: Send + Sync + Sized {
                     ));
                 }
 
-                if let Some(relationships) = Self::_FOREIGN_RELATIONSHIPS.get(table_name) {
+                if let Some(relationships) = self.foreign_relationships(table_name) {
                     for (local_field_name, foreign_table) in relationships {
                         table.foreign_key(set_relationship_on_update(
                             set_relationship_on_delete(
@@ -129,8 +297,24 @@ pub trait Database: Send + Sync + Sized {
                     }
                 }
 
+                // Only tables created from here on get these, existing ones
+                // need a unique index instead.
+                let unique_constraints = self
+                    .indexes()
+                    .filter(|(name, index)| *name == table_name && index.name.is_none());
+                for (_, index) in unique_constraints {
+                    let mut constraint = sea_query::Index::create().unique().to_owned();
+                    for column in &index.columns {
+                        constraint.col(column.name);
+                    }
+                    table.index(&mut constraint);
+                }
+
                 format!("{};", table.build_any(&schema_builder))
             })
+            // Triggers come last, as their bodies may refer to any table.
+            .chain(self.triggers().map(|trigger| trigger.sql()))
+            .chain(self.counters().map(|counter| counter.sql()))
             .join("\n\n")
     }
 
@@ -165,9 +349,49 @@ pub trait Database: Send + Sync + Sized {
             }
         }
 
+        // After the columns, which the indexes may be on.
+        let index_sql = self.index_sql(schema_builder);
+        if !index_sql.is_empty() {
+            stmts.push(index_sql);
+        }
+
         stmts.join("\n")
     }
 
+    /// Statements that create the declared indexes which don't exist yet.
+    /// An index that exists is left as it is, so changing its columns needs
+    /// a new name.
+    fn index_sql(&self, schema_builder: impl sea_query::SchemaBuilder) -> String {
+        self.indexes()
+            .filter_map(|(table_name, index)| {
+                let name = index.name?;
+                let mut stmt = sea_query::Index::create()
+                    .if_not_exists()
+                    .name(name)
+                    .table(table_name)
+                    .to_owned();
+
+                for column in &index.columns {
+                    let order = match column.direction {
+                        OrderDirection::Asc => sea_query::IndexOrder::Asc,
+                        OrderDirection::Desc => sea_query::IndexOrder::Desc,
+                    };
+                    stmt.col((column.name, order));
+                }
+                // SQLite has no `INCLUDE`, the columns go at the end of the
+                // key instead.
+                for column in &index.include {
+                    stmt.col(*column);
+                }
+                if index.unique {
+                    stmt.unique();
+                }
+
+                Some(format!("{};", stmt.build_any(&schema_builder)))
+            })
+            .join("\n")
+    }
+
     fn embedded_tables(&self) -> Vec<EmbeddedTableDef> {
         Vec::new()
     }
@@ -179,12 +403,22 @@ pub trait Database: Send + Sync + Sized {
     ) -> impl Future<Output = Result<Notitia<Self, Adptr>, ConnectionError<Adptr::Error>>> + Send
     {
         async move {
-            let options = options.into();
+            let mut options = options.into();
 
             let db = Adptr::open::<Self>(&options.uri)
                 .await
                 .map_err(ConnectionError::Adapter)?;
 
+            if let Some(clock) = options.clock.take() {
+                db.set_clock(clock);
+            }
+            if let Some(ids) = options.id_generator.take() {
+                db.set_id_generator(ids);
+            }
+            if let Some(policy) = options.float_policy {
+                db.set_float_policy(policy);
+            }
+
             #[cfg(feature = "embeddings")]
             {
                 let embedded = db.database().embedded_tables();
@@ -210,6 +444,9 @@ pub trait Database: Send + Sync + Sized {
 pub struct ConnectionOptions {
     pub uri: String,
     pub embeddings_uri: Option<String>,
+    pub(crate) clock: Option<std::sync::Arc<dyn crate::Clock>>,
+    pub(crate) id_generator: Option<std::sync::Arc<dyn crate::IdGenerator>>,
+    pub(crate) float_policy: Option<crate::FloatPolicy>,
     #[cfg(feature = "embeddings")]
     pub(crate) embedder: Option<Box<dyn crate::embeddings::DatabaseEmbedder>>,
 }
@@ -219,11 +456,32 @@ impl ConnectionOptions {
         Self {
             uri: uri.into(),
             embeddings_uri: None,
+            clock: None,
+            id_generator: None,
+            float_policy: None,
             #[cfg(feature = "embeddings")]
             embedder: None,
         }
     }
 
+    /// See [`Notitia::set_clock`].
+    pub fn clock(mut self, clock: impl crate::Clock + 'static) -> Self {
+        self.clock = Some(std::sync::Arc::new(clock));
+        self
+    }
+
+    /// See [`Notitia::set_id_generator`].
+    pub fn id_generator(mut self, ids: impl crate::IdGenerator + 'static) -> Self {
+        self.id_generator = Some(std::sync::Arc::new(ids));
+        self
+    }
+
+    /// See [`Notitia::set_float_policy`].
+    pub fn float_policy(mut self, policy: crate::FloatPolicy) -> Self {
+        self.float_policy = Some(policy);
+        self
+    }
+
     pub fn embeddings_uri(mut self, uri: impl Into<String>) -> Self {
         self.embeddings_uri = Some(uri.into());
         self
@@ -281,6 +539,28 @@ pub enum ConnectionError<E: std::error::Error> {
     Embeddings(crate::embeddings::EmbeddingError),
 }
 
+/// Implemented by a database for every database it includes through a
+/// `#[db(include)]` field. The included tables become part of its schema and
+/// are reachable through the `{Database}Tables` type of the included database:
+///
+/// ```ignore
+/// #[database(includable)]
+/// pub struct AuthDatabase {
+///     users: Table<User>,
+/// }
+///
+/// #[database]
+/// struct AppDatabase {
+///     #[db(include)]
+///     auth: AuthDatabase,
+///     messages: Table<Message>,
+/// }
+///
+/// // `AppDatabaseAuth` is generated for the `auth` field.
+/// AppDatabaseAuth::USERS.select(User::NAME).fetch_vec();
+/// ```
+pub trait Includes<Inner: Database>: Database<TableKind = ComposedTableKind> {}
+
 impl Database for () {
     type TableKind = ();
 
@@ -305,5 +585,13 @@ impl TableKind for () {
 }
 
 pub trait OnStartup: Database {
+    /// Seed data applied by [`Notitia::seed`].
+    fn fixtures<Adptr: Adapter>(&self) -> Fixtures<Self, Adptr>
+    where
+        Self: 'static,
+    {
+        Fixtures::new()
+    }
+
     fn on_startup(&self) -> impl Future<Output = Result<(), ()>> + Send;
 }
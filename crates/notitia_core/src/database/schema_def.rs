@@ -0,0 +1,79 @@
+use crate::{DatatypeKind, OnAction, OrderDirection, TriggerDef};
+
+/// The schema of a database as data, for tools that need to inspect it
+/// (migrations, admin UIs, sync) without parsing [`schema_sql`].
+///
+/// [`schema_sql`]: crate::Database::schema_sql
+#[derive(Clone, Debug)]
+pub struct SchemaDef {
+    pub tables: Vec<TableDef>,
+}
+
+impl SchemaDef {
+    pub fn table(&self, name: &str) -> Option<&TableDef> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TableDef {
+    pub name: &'static str,
+    /// The name of the `#[record]` struct of its rows, if the database knows
+    /// it.
+    pub record_name: Option<&'static str>,
+    /// In declaration order.
+    pub columns: Vec<ColumnDef>,
+    pub foreign_keys: Vec<ForeignKeyDef>,
+    pub indexes: Vec<IndexDef>,
+    pub triggers: Vec<TriggerDef>,
+}
+
+impl TableDef {
+    pub fn column(&self, name: &str) -> Option<&ColumnDef> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    pub fn primary_key(&self) -> impl Iterator<Item = &ColumnDef> {
+        self.columns
+            .iter()
+            .filter(|column| column.kind.metadata().primary_key)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ColumnDef {
+    pub name: &'static str,
+    /// The column's type, and whether it is a primary key, unique or
+    /// nullable.
+    pub kind: DatatypeKind,
+}
+
+#[derive(Clone, Debug)]
+pub struct ForeignKeyDef {
+    pub column: &'static str,
+    pub foreign_table: &'static str,
+    pub foreign_column: &'static str,
+    pub on_delete: OnAction,
+    pub on_update: OnAction,
+}
+
+/// An index the database maintains on a table. Unique columns and
+/// `#[db(unique(...))]` constraints are listed here as unique indexes, next
+/// to the indexes declared with `#[db(index(...))]`.
+#[derive(Clone, Debug)]
+pub struct IndexDef {
+    /// `None` for unique constraints, which are part of the table and named
+    /// by SQLite.
+    pub name: Option<&'static str>,
+    pub columns: Vec<IndexColumnDef>,
+    /// Columns stored in the index after the key, so that queries reading
+    /// only indexed columns never look up the row.
+    pub include: Vec<&'static str>,
+    pub unique: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexColumnDef {
+    pub name: &'static str,
+    pub direction: OrderDirection,
+}
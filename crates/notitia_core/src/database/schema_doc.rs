@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use crate::{ColumnDef, OnAction, OrderDirection, SchemaDef, TableDef};
+
+impl SchemaDef {
+    /// A reference of every table: its columns with their types and
+    /// constraints, the columns they reference, indexes and triggers.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Schema\n");
+
+        for table in &self.tables {
+            let _ = write!(out, "\n## `{}`\n\n", table.name);
+            out.push_str("| Column | Type | Constraints | References |\n");
+            out.push_str("| --- | --- | --- | --- |\n");
+            for column in &table.columns {
+                let _ = writeln!(
+                    out,
+                    "| `{}` | {} | {} | {} |",
+                    column.name,
+                    column.kind.name(),
+                    constraints(column).join(", "),
+                    reference(table, column).unwrap_or_default(),
+                );
+            }
+
+            // Unique columns are listed as a constraint already.
+            let indexes: Vec<String> = table
+                .indexes
+                .iter()
+                .filter(|index| !(index.unique && index.columns.len() == 1))
+                .map(|index| {
+                    let columns: Vec<String> = index
+                        .columns
+                        .iter()
+                        .map(|column| match column.direction {
+                            OrderDirection::Asc => format!("`{}`", column.name),
+                            OrderDirection::Desc => format!("`{}` desc", column.name),
+                        })
+                        .collect();
+                    let key = if index.unique {
+                        format!("unique ({})", columns.join(", "))
+                    } else {
+                        format!("({})", columns.join(", "))
+                    };
+                    let mut line = match index.name {
+                        Some(name) => format!("- `{name}`: {key}"),
+                        None => format!("- {key}"),
+                    };
+                    if !index.include.is_empty() {
+                        let _ = write!(line, ", including `{}`", index.include.join("`, `"));
+                    }
+                    line
+                })
+                .collect();
+            if !indexes.is_empty() {
+                let _ = write!(out, "\nIndexes:\n\n{}\n", indexes.join("\n"));
+            }
+
+            if !table.triggers.is_empty() {
+                out.push_str("\nTriggers:\n\n");
+                for trigger in &table.triggers {
+                    let _ = writeln!(out, "- `{}`: {}", trigger.name(), trigger.event.sql());
+                }
+            }
+        }
+
+        out
+    }
+
+    /// A Graphviz diagram with a node per table listing its columns, and an
+    /// edge per foreign key, e.g. for `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph schema {\n");
+        out.push_str("    rankdir=LR;\n");
+        out.push_str("    node [shape=plaintext];\n");
+
+        for table in &self.tables {
+            let _ = writeln!(
+                out,
+                "\n    \"{}\" [label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\">",
+                table.name
+            );
+            let _ = writeln!(
+                out,
+                "        <tr><td bgcolor=\"lightgrey\"><b>{}</b></td></tr>",
+                table.name
+            );
+            for column in &table.columns {
+                let constraints = constraints(column);
+                let constraints = if constraints.is_empty() {
+                    String::new()
+                } else {
+                    format!(" <i>({})</i>", constraints.join(", "))
+                };
+                let _ = writeln!(
+                    out,
+                    "        <tr><td port=\"{0}\" align=\"left\">{0}: {1}{constraints}</td></tr>",
+                    column.name,
+                    column.kind.name(),
+                );
+            }
+            out.push_str("    </table>>];\n");
+        }
+
+        let foreign_keys = self
+            .tables
+            .iter()
+            .flat_map(|table| table.foreign_keys.iter().map(move |key| (table, key)));
+        for (table, key) in foreign_keys {
+            let _ = write!(
+                out,
+                "\n    \"{}\":\"{}\" -> \"{}\":\"{}\"",
+                table.name, key.column, key.foreign_table, key.foreign_column
+            );
+            if let Some(action) = action_name(&key.on_delete) {
+                let _ = write!(out, " [label=\"on delete {action}\"]");
+            }
+            out.push(';');
+        }
+
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+fn constraints(column: &ColumnDef) -> Vec<&'static str> {
+    let metadata = column.kind.metadata();
+    let mut constraints = Vec::new();
+    if metadata.primary_key {
+        constraints.push("primary key");
+    }
+    if metadata.unique {
+        constraints.push("unique");
+    }
+    if metadata.optional {
+        constraints.push("nullable");
+    }
+    constraints
+}
+
+fn reference(table: &TableDef, column: &ColumnDef) -> Option<String> {
+    let key = table
+        .foreign_keys
+        .iter()
+        .find(|key| key.column == column.name)?;
+
+    let mut reference = format!("`{}.{}`", key.foreign_table, key.foreign_column);
+    if let Some(action) = action_name(&key.on_delete) {
+        let _ = write!(reference, ", on delete {action}");
+    }
+    if let Some(action) = action_name(&key.on_update) {
+        let _ = write!(reference, ", on update {action}");
+    }
+    Some(reference)
+}
+
+/// `None` for the default, which isn't worth mentioning.
+fn action_name(action: &OnAction) -> Option<&'static str> {
+    match action {
+        OnAction::NoAction => None,
+        OnAction::Restrict => Some("restrict"),
+        OnAction::SetNull => Some("set null"),
+        OnAction::SetDefault => Some("set default"),
+        OnAction::Cascade => Some("cascade"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DatatypeKind, DatatypeKindMetadata, ForeignKeyDef};
+
+    use super::*;
+
+    fn schema() -> SchemaDef {
+        let column = |name, primary_key, optional| ColumnDef {
+            name,
+            kind: DatatypeKind::Text(DatatypeKindMetadata {
+                primary_key,
+                unique: false,
+                optional,
+            }),
+        };
+        let table = |name, columns, foreign_keys| TableDef {
+            name,
+            record_name: None,
+            columns,
+            foreign_keys,
+            indexes: Vec::new(),
+            triggers: Vec::new(),
+        };
+
+        SchemaDef {
+            tables: vec![
+                table("chats", vec![column("id", true, false)], Vec::new()),
+                table(
+                    "messages",
+                    vec![column("id", true, false), column("chat_id", false, true)],
+                    vec![ForeignKeyDef {
+                        column: "chat_id",
+                        foreign_table: "chats",
+                        foreign_column: "id",
+                        on_delete: OnAction::Cascade,
+                        on_update: OnAction::NoAction,
+                    }],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let markdown = schema().to_markdown();
+
+        assert!(markdown.contains("## `messages`"));
+        assert!(markdown.contains("| `id` | Text | primary key |  |"));
+        assert!(
+            markdown.contains("| `chat_id` | Text | nullable | `chats.id`, on delete cascade |")
+        );
+    }
+
+    #[test]
+    fn renders_foreign_keys_as_edges() {
+        let dot = schema().to_dot();
+
+        assert!(dot.starts_with("digraph schema {"));
+        assert!(
+            dot.contains(
+                "<td port=\"chat_id\" align=\"left\">chat_id: Text <i>(nullable)</i></td>"
+            )
+        );
+        assert!(dot.contains(
+            "\"messages\":\"chat_id\" -> \"chats\":\"id\" [label=\"on delete cascade\"];"
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::Write;
+
+use crate::{DatatypeKind, SchemaDef, TableDef};
+
+impl SchemaDef {
+    /// A TypeScript module with an interface per table, named after its
+    /// record, and a constant of its table and column names, for frontends
+    /// reading serialized rows, e.g. through `notitia_tauri`.
+    ///
+    /// Fields are typed as the values they're stored as, which is what they
+    /// serialize to unless their type says otherwise: integers and floats as
+    /// `number`, blobs as arrays of bytes and nullable columns as `| null`.
+    pub fn to_typescript(&self) -> String {
+        let mut out = String::from("// Generated by notitia from the schema. Do not edit.\n");
+
+        for table in &self.tables {
+            let _ = write!(out, "\nexport interface {} {{\n", interface_name(table));
+            for column in &table.columns {
+                let mut ty = typescript_type(&column.kind);
+                if column.kind.metadata().optional {
+                    ty.push_str(" | null");
+                }
+                let _ = writeln!(out, "  {}: {ty};", column.name);
+            }
+            out.push_str("}\n");
+
+            let _ = write!(
+                out,
+                "\nexport const {} = {{\n  table: \"{}\",\n  columns: {{\n",
+                table.name.to_ascii_uppercase(),
+                table.name
+            );
+            for column in &table.columns {
+                let _ = writeln!(out, "    {0}: \"{0}\",", column.name);
+            }
+            out.push_str("  },\n} as const;\n");
+        }
+
+        out
+    }
+}
+
+/// The record's name, or the table's in PascalCase if it's unknown.
+fn interface_name(table: &TableDef) -> String {
+    if let Some(record_name) = table.record_name {
+        return record_name.to_string();
+    }
+
+    table
+        .name
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn typescript_type(kind: &DatatypeKind) -> String {
+    match kind {
+        DatatypeKind::Int(_)
+        | DatatypeKind::BigInt(_)
+        | DatatypeKind::Float(_)
+        | DatatypeKind::Double(_) => "number".to_string(),
+        DatatypeKind::Text(_) => "string".to_string(),
+        DatatypeKind::Blob(_) => "number[]".to_string(),
+        DatatypeKind::Bool(_) => "boolean".to_string(),
+        DatatypeKind::Array(_, items) => format!("{}[]", typescript_type(items)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ColumnDef, DatatypeKindMetadata};
+
+    use super::*;
+
+    #[test]
+    fn renders_interfaces_and_column_constants() {
+        let metadata = |optional| DatatypeKindMetadata {
+            primary_key: false,
+            unique: false,
+            optional,
+        };
+        let table = |name, record_name| TableDef {
+            name,
+            record_name,
+            columns: vec![
+                ColumnDef {
+                    name: "id",
+                    kind: DatatypeKind::Text(metadata(false)),
+                },
+                ColumnDef {
+                    name: "tags",
+                    kind: DatatypeKind::Array(
+                        metadata(true),
+                        Box::new(DatatypeKind::Text(metadata(false))),
+                    ),
+                },
+            ],
+            foreign_keys: Vec::new(),
+            indexes: Vec::new(),
+            triggers: Vec::new(),
+        };
+        let schema = SchemaDef {
+            tables: vec![
+                table("chat_messages", Some("Message")),
+                table("read_receipts", None),
+            ],
+        };
+
+        let typescript = schema.to_typescript();
+
+        assert!(
+            typescript.contains(
+                "export interface Message {\n  id: string;\n  tags: string[] | null;\n}\n"
+            )
+        );
+        assert!(typescript.contains("export interface ReadReceipts {"));
+        assert!(typescript.contains(
+            "export const CHAT_MESSAGES = {\n  table: \"chat_messages\",\n  columns: {\n    id: \"id\",\n    tags: \"tags\",\n  },\n} as const;\n"
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+/// When a [`TriggerDef`] runs, relative to the statement that changes a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    BeforeInsert,
+    AfterInsert,
+    BeforeUpdate,
+    AfterUpdate,
+    BeforeDelete,
+    AfterDelete,
+}
+
+impl TriggerEvent {
+    pub fn sql(&self) -> &'static str {
+        match self {
+            Self::BeforeInsert => "BEFORE INSERT",
+            Self::AfterInsert => "AFTER INSERT",
+            Self::BeforeUpdate => "BEFORE UPDATE",
+            Self::AfterUpdate => "AFTER UPDATE",
+            Self::BeforeDelete => "BEFORE DELETE",
+            Self::AfterDelete => "AFTER DELETE",
+        }
+    }
+
+    /// The name of the event in `#[db(trigger(...))]`.
+    pub fn attr_name(&self) -> &'static str {
+        match self {
+            Self::BeforeInsert => "before_insert",
+            Self::AfterInsert => "after_insert",
+            Self::BeforeUpdate => "before_update",
+            Self::AfterUpdate => "after_update",
+            Self::BeforeDelete => "before_delete",
+            Self::AfterDelete => "after_delete",
+        }
+    }
+}
+
+/// A trigger the database runs for every row of a table, declared with
+/// `#[db(trigger(...))]` on the table's field:
+///
+/// ```ignore
+/// #[database]
+/// struct ChatDatabase {
+///     #[db(trigger(
+///         after_insert = "UPDATE chats SET message_count = message_count + 1 WHERE id = NEW.chat_id",
+///         after_delete = "UPDATE chats SET message_count = message_count - 1 WHERE id = OLD.chat_id",
+///     ))]
+///     messages: Table<Message>,
+///     chats: Table<Chat>,
+/// }
+/// ```
+///
+/// Keeping a counter up to date this way stays correct when several
+/// processes write to the same database.
+#[derive(Clone, Debug)]
+pub struct TriggerDef {
+    pub table_name: &'static str,
+    pub event: TriggerEvent,
+    /// The statements between `BEGIN` and `END`. They refer to the changed
+    /// row through `NEW` and `OLD`, and name tables as they are in the
+    /// database, prefix included.
+    pub body: &'static str,
+}
+
+impl TriggerDef {
+    /// `{table}_{event}`, e.g. `messages_after_insert`.
+    pub fn name(&self) -> String {
+        format!("{}_{}", self.table_name, self.event.attr_name())
+    }
+
+    /// Statements that replace the trigger, so that changing its body takes
+    /// effect the next time the schema is created.
+    pub fn sql(&self) -> String {
+        let name = self.name();
+        let body = self.body.trim();
+        let terminator = if body.ends_with(';') { "" } else { ";" };
+
+        format!(
+            "DROP TRIGGER IF EXISTS \"{name}\";\n\nCREATE TRIGGER \"{name}\" {} ON \"{}\" FOR EACH ROW BEGIN {body}{terminator} END;",
+            self.event.sql(),
+            self.table_name,
+        )
+    }
+}
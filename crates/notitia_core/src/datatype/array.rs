@@ -0,0 +1,128 @@
+use serde_json::{Number, Value};
+
+use crate::{
+    AsDatatypeKind, Datatype, DatatypeConversionError, DatatypeKind, DatatypeKindMetadata,
+    FromDatatypeRef,
+};
+
+impl Datatype {
+    /// The JSON text an array is stored as by databases without array
+    /// columns. Blobs in it become arrays of bytes, and floats that aren't
+    /// finite become `null`.
+    pub fn array_to_json(items: &[Datatype]) -> String {
+        Value::Array(items.iter().map(to_json).collect()).to_string()
+    }
+
+    /// The array of [`array_to_json`](Self::array_to_json) text, with
+    /// integers as `BigInt` and other numbers as `Double`.
+    pub fn array_from_json(json: &str) -> Option<Vec<Datatype>> {
+        match serde_json::from_str(json).ok()? {
+            Value::Array(items) => items.into_iter().map(from_json).collect(),
+            _ => None,
+        }
+    }
+
+    /// The elements of an array, or of one read back as JSON text.
+    fn into_array(self) -> Result<Vec<Datatype>, DatatypeConversionError> {
+        match self {
+            Datatype::Array(items) => Ok(items),
+            Datatype::Text(json) => {
+                Self::array_from_json(&json).ok_or(DatatypeConversionError::TypeMismatch {
+                    expected: "Array",
+                    got: "Text",
+                })
+            }
+            other => Err(other.mismatch("Array")),
+        }
+    }
+}
+
+fn to_json(value: &Datatype) -> Value {
+    let float = |v: f64| Number::from_f64(v).map_or(Value::Null, Value::Number);
+    match value {
+        Datatype::Int(v) => Value::from(*v),
+        Datatype::BigInt(v) => Value::from(*v),
+        Datatype::Float(v) => float(*v as f64),
+        Datatype::Double(v) => float(*v),
+        Datatype::Text(v) => Value::String(v.clone()),
+        Datatype::Blob(v) => Value::Array(v.iter().map(|byte| Value::from(*byte)).collect()),
+        Datatype::Bool(v) => Value::Bool(*v),
+        Datatype::Array(v) => Value::Array(v.iter().map(to_json).collect()),
+        Datatype::Null => Value::Null,
+    }
+}
+
+fn from_json(value: Value) -> Option<Datatype> {
+    Some(match value {
+        Value::Null => Datatype::Null,
+        Value::Bool(v) => Datatype::Bool(v),
+        Value::Number(v) => match v.as_i64() {
+            Some(v) => Datatype::BigInt(v),
+            None => Datatype::Double(v.as_f64()?),
+        },
+        Value::String(v) => Datatype::Text(v),
+        Value::Array(items) => {
+            Datatype::Array(items.into_iter().map(from_json).collect::<Option<_>>()?)
+        }
+        Value::Object(_) => return None,
+    })
+}
+
+// `Vec<u8>` is a blob, so arrays are implemented per element type.
+macro_rules! impl_array {
+    ($($ty:ty),*) => {
+        $(
+            impl Into<Datatype> for Vec<$ty> {
+                fn into(self) -> Datatype {
+                    Datatype::Array(self.into_iter().map(Into::into).collect())
+                }
+            }
+
+            impl TryFrom<Datatype> for Vec<$ty> {
+                type Error = DatatypeConversionError;
+
+                fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
+                    datatype.into_array()?.into_iter().map(<$ty>::try_from).collect()
+                }
+            }
+
+            impl FromDatatypeRef for Vec<$ty> {}
+
+            impl AsDatatypeKind for Vec<$ty> {
+                fn as_datatype_kind() -> DatatypeKind {
+                    DatatypeKind::Array(
+                        DatatypeKindMetadata::default(),
+                        Box::new(<$ty>::as_datatype_kind()),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+impl_array!(i32, i64, f32, f64, bool, String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {
+        let tags: Datatype = vec!["rust".to_string(), "db".to_string()].into();
+        let Datatype::Array(items) = &tags else {
+            panic!("not an array: {tags:?}");
+        };
+
+        let json = Datatype::array_to_json(items);
+        assert_eq!(json, r#"["rust","db"]"#);
+        assert_eq!(
+            Vec::<String>::try_from(Datatype::Text(json)).unwrap(),
+            ["rust", "db"]
+        );
+        assert_eq!(
+            Vec::<i32>::try_from(Datatype::Text("[1,2]".into())).unwrap(),
+            [1, 2]
+        );
+        assert!(Vec::<i32>::try_from(Datatype::Text("not json".into())).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{Datatype, DatatypeConversionError, PrimaryKey, Unique};
+
+use super::{narrow_float, narrow_int};
+
+/// A [`Datatype`] borrowing its text and blob contents, typically straight
+/// from an adapter's row buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DatatypeRef<'a> {
+    Int(i32),
+    BigInt(i64),
+
+    Float(f32),
+    Double(f64),
+
+    Text(&'a str),
+
+    Blob(&'a [u8]),
+
+    Bool(bool),
+
+    Null,
+}
+
+impl DatatypeRef<'_> {
+    pub fn into_owned(self) -> Datatype {
+        match self {
+            DatatypeRef::Int(v) => Datatype::Int(v),
+            DatatypeRef::BigInt(v) => Datatype::BigInt(v),
+            DatatypeRef::Float(v) => Datatype::Float(v),
+            DatatypeRef::Double(v) => Datatype::Double(v),
+            DatatypeRef::Text(v) => Datatype::Text(v.to_owned()),
+            DatatypeRef::Blob(v) => Datatype::Blob(v.to_owned()),
+            DatatypeRef::Bool(v) => Datatype::Bool(v),
+            DatatypeRef::Null => Datatype::Null,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            DatatypeRef::Int(_) => "Int",
+            DatatypeRef::BigInt(_) => "BigInt",
+            DatatypeRef::Float(_) => "Float",
+            DatatypeRef::Double(_) => "Double",
+            DatatypeRef::Text(_) => "Text",
+            DatatypeRef::Blob(_) => "Blob",
+            DatatypeRef::Bool(_) => "Bool",
+            DatatypeRef::Null => "Null",
+        }
+    }
+
+    fn mismatch(&self, expected: &'static str) -> DatatypeConversionError {
+        match self {
+            DatatypeRef::Null => DatatypeConversionError::UnexpectedNull,
+            other => DatatypeConversionError::TypeMismatch {
+                expected,
+                got: other.type_name(),
+            },
+        }
+    }
+}
+
+/// Decoding from a borrowed value, used when reading query results.
+///
+/// The default goes through an owned [`Datatype`], so custom field types
+/// only need an empty `impl FromDatatypeRef for MyType {}`. Types that parse
+/// text or bytes (ids, timestamps, ...) can override it to skip the
+/// intermediate `String`/`Vec<u8>`.
+pub trait FromDatatypeRef: TryFrom<Datatype, Error = DatatypeConversionError> {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        Self::try_from(value.into_owned())
+    }
+}
+
+impl FromDatatypeRef for i32 {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Int(v) => Ok(v),
+            DatatypeRef::BigInt(v) => narrow_int(v),
+            other => Err(other.mismatch("Int")),
+        }
+    }
+}
+
+impl FromDatatypeRef for i64 {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::BigInt(v) => Ok(v),
+            DatatypeRef::Int(v) => Ok(v as i64),
+            other => Err(other.mismatch("BigInt")),
+        }
+    }
+}
+
+impl FromDatatypeRef for f32 {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Float(v) => Ok(v),
+            DatatypeRef::Double(v) => narrow_float(v),
+            other => Err(other.mismatch("Float")),
+        }
+    }
+}
+
+impl FromDatatypeRef for f64 {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Double(v) => Ok(v),
+            DatatypeRef::Float(v) => Ok(v as f64),
+            other => Err(other.mismatch("Double")),
+        }
+    }
+}
+
+impl FromDatatypeRef for bool {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Bool(v) => Ok(v),
+            DatatypeRef::Int(v) => Ok(v != 0),
+            DatatypeRef::BigInt(v) => Ok(v != 0),
+            other => Err(other.mismatch("Bool")),
+        }
+    }
+}
+
+impl FromDatatypeRef for String {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Text(v) => Ok(v.to_owned()),
+            other => Err(other.mismatch("Text")),
+        }
+    }
+}
+
+impl FromDatatypeRef for Vec<u8> {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Blob(v) => Ok(v.to_owned()),
+            other => Err(other.mismatch("Blob")),
+        }
+    }
+}
+
+impl<T: FromDatatypeRef> FromDatatypeRef for Option<T> {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Null => Ok(None),
+            other => Ok(Some(T::from_datatype_ref(other)?)),
+        }
+    }
+}
+
+impl<T: FromDatatypeRef> FromDatatypeRef for PrimaryKey<T> {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        Ok(PrimaryKey::new(T::from_datatype_ref(value)?))
+    }
+}
+
+impl<T: FromDatatypeRef> FromDatatypeRef for Unique<T> {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        Ok(Unique::new(T::from_datatype_ref(value)?))
+    }
+}
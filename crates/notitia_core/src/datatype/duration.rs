@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use crate::{
+    AsDatatypeKind, Datatype, DatatypeConversionError, DatatypeKind, DatatypeKindMetadata,
+    DatatypeRef, FromDatatypeRef,
+};
+
+// Durations are stored as whole microseconds in a `BigInt` column, so they
+// compare and add up in SQL like any other integer.
+
+impl Into<Datatype> for Duration {
+    /// Saturates at `i64::MAX` microseconds, about 292,000 years.
+    fn into(self) -> Datatype {
+        Datatype::BigInt(i64::try_from(self.as_micros()).unwrap_or(i64::MAX))
+    }
+}
+
+impl TryFrom<Datatype> for Duration {
+    type Error = DatatypeConversionError;
+
+    fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
+        from_micros(i64::try_from(datatype)?)
+    }
+}
+
+impl FromDatatypeRef for Duration {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        from_micros(i64::from_datatype_ref(value)?)
+    }
+}
+
+fn from_micros(micros: i64) -> Result<Duration, DatatypeConversionError> {
+    match u64::try_from(micros) {
+        Ok(micros) => Ok(Duration::from_micros(micros)),
+        Err(_) => Err(DatatypeConversionError::TypeMismatch {
+            expected: "Duration",
+            got: "negative BigInt",
+        }),
+    }
+}
+
+impl AsDatatypeKind for Duration {
+    fn as_datatype_kind() -> DatatypeKind {
+        DatatypeKind::BigInt(DatatypeKindMetadata::default())
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    impl Into<Datatype> for TimeDelta {
+        /// Saturates at `i64::MIN` and `i64::MAX` microseconds.
+        fn into(self) -> Datatype {
+            let saturated = if self < TimeDelta::zero() {
+                i64::MIN
+            } else {
+                i64::MAX
+            };
+            Datatype::BigInt(self.num_microseconds().unwrap_or(saturated))
+        }
+    }
+
+    impl TryFrom<Datatype> for TimeDelta {
+        type Error = DatatypeConversionError;
+
+        fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
+            Ok(TimeDelta::microseconds(i64::try_from(datatype)?))
+        }
+    }
+
+    impl FromDatatypeRef for TimeDelta {
+        fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+            Ok(TimeDelta::microseconds(i64::from_datatype_ref(value)?))
+        }
+    }
+
+    impl AsDatatypeKind for TimeDelta {
+        fn as_datatype_kind() -> DatatypeKind {
+            DatatypeKind::BigInt(DatatypeKindMetadata::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_microseconds() {
+        let call: Datatype = Duration::from_millis(1500).into();
+        assert_eq!(call, Datatype::BigInt(1_500_000));
+        assert_eq!(
+            Duration::try_from(call).unwrap(),
+            Duration::from_millis(1500)
+        );
+
+        let forever: Datatype = Duration::MAX.into();
+        assert_eq!(forever, Datatype::BigInt(i64::MAX));
+        assert!(Duration::try_from(Datatype::BigInt(-1)).is_err());
+    }
+}
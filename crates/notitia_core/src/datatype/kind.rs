@@ -17,6 +17,25 @@ pub enum DatatypeKind {
     Blob(DatatypeKindMetadata),
 
     Bool(DatatypeKindMetadata),
+
+    /// A list of values of the element kind, whose own metadata is unused.
+    Array(DatatypeKindMetadata, Box<DatatypeKind>),
+}
+
+impl DatatypeKind {
+    /// The variant's name, e.g. `BigInt`.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "Int",
+            Self::BigInt(_) => "BigInt",
+            Self::Float(_) => "Float",
+            Self::Double(_) => "Double",
+            Self::Text(_) => "Text",
+            Self::Blob(_) => "Blob",
+            Self::Bool(_) => "Bool",
+            Self::Array(..) => "Array",
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -89,3 +108,9 @@ impl AsDatatypeKind for String {
         DatatypeKind::Text(DatatypeKindMetadata::default())
     }
 }
+
+impl AsDatatypeKind for Vec<u8> {
+    fn as_datatype_kind() -> DatatypeKind {
+        DatatypeKind::Blob(DatatypeKindMetadata::default())
+    }
+}
@@ -2,14 +2,24 @@ mod kind;
 
 pub use kind::*;
 
+mod datatype_ref;
+pub use datatype_ref::*;
+
+mod array;
+
+mod duration;
+
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 use smallvec::SmallVec;
 
 use crate::{PrimaryKey, Unique};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Datatype {
     Int(i32),
     BigInt(i64),
@@ -23,6 +33,10 @@ pub enum Datatype {
 
     Bool(bool),
 
+    /// A list column, e.g. of tags: an array on Postgres, JSON text on
+    /// SQLite (see [`Datatype::array_to_json`]).
+    Array(Vec<Datatype>),
+
     Null,
 }
 
@@ -89,17 +103,59 @@ impl Into<Datatype> for &str {
     }
 }
 
+impl Into<Datatype> for Vec<u8> {
+    fn into(self) -> Datatype {
+        Datatype::Blob(self)
+    }
+}
+
 #[derive(Debug)]
 pub enum DatatypeConversionError {
     TypeMismatch {
         expected: &'static str,
         got: &'static str,
     },
+    /// A number that doesn't fit the type it's read into, e.g. a `BigInt`
+    /// past `i32::MAX` for an `i32` field.
+    OutOfRange {
+        expected: &'static str,
+        value: String,
+    },
+    /// A NULL read into a type that isn't an `Option`.
     UnexpectedNull,
     WrongNumberOfValues {
         expected: usize,
         got: usize,
     },
+    /// Another conversion error, annotated by the adapter with the column
+    /// and row of the result set it happened in.
+    InColumn {
+        table: String,
+        column: &'static str,
+        row: usize,
+        source: Box<DatatypeConversionError>,
+    },
+}
+
+impl DatatypeConversionError {
+    /// Attach the location of the failing value. `row` is the index of the
+    /// row within the result set.
+    pub fn in_column(self, table: impl Into<String>, column: &'static str, row: usize) -> Self {
+        Self::InColumn {
+            table: table.into(),
+            column,
+            row,
+            source: Box::new(self),
+        }
+    }
+
+    /// The error without any location context.
+    pub fn root_cause(&self) -> &DatatypeConversionError {
+        match self {
+            Self::InColumn { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
 }
 
 impl std::fmt::Display for DatatypeConversionError {
@@ -108,15 +164,31 @@ impl std::fmt::Display for DatatypeConversionError {
             Self::TypeMismatch { expected, got } => {
                 write!(f, "type mismatch: expected {expected}, got {got}")
             }
+            Self::OutOfRange { expected, value } => {
+                write!(f, "out of range: {value} doesn't fit in {expected}")
+            }
             Self::UnexpectedNull => write!(f, "unexpected null value"),
             Self::WrongNumberOfValues { expected, got } => {
                 write!(f, "wrong number of values: expected {expected}, got {got}")
             }
+            Self::InColumn {
+                table,
+                column,
+                row,
+                source,
+            } => write!(f, "{table}.{column} (row {row}): {source}"),
         }
     }
 }
 
-impl std::error::Error for DatatypeConversionError {}
+impl std::error::Error for DatatypeConversionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InColumn { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl Datatype {
     fn discriminant(&self) -> u8 {
@@ -129,6 +201,7 @@ impl Datatype {
             Datatype::Double(_) => 5,
             Datatype::Text(_) => 6,
             Datatype::Blob(_) => 7,
+            Datatype::Array(_) => 8,
         }
     }
 }
@@ -146,6 +219,7 @@ impl Hash for Datatype {
             Datatype::Text(v) => v.hash(state),
             Datatype::Blob(v) => v.hash(state),
             Datatype::Bool(v) => v.hash(state),
+            Datatype::Array(v) => v.hash(state),
             Datatype::Null => {}
         }
     }
@@ -171,6 +245,7 @@ impl Ord for Datatype {
             (Datatype::Text(a), Datatype::Text(b)) => a.cmp(b),
             (Datatype::Blob(a), Datatype::Blob(b)) => a.cmp(b),
             (Datatype::Bool(a), Datatype::Bool(b)) => a.cmp(b),
+            (Datatype::Array(a), Datatype::Array(b)) => a.cmp(b),
             (Datatype::Null, Datatype::Null) => Ordering::Equal,
             _ => self.discriminant().cmp(&other.discriminant()),
         }
@@ -182,9 +257,12 @@ impl Ord for Datatype {
 ///
 /// Each component has an associated direction flag. When `reversed[i]` is true,
 /// the comparison for that component is reversed (for ORDER BY ... DESC).
+///
+/// Components are reference counted, so cloning a key never copies its text,
+/// and keys built through an [`OrderKeyInterner`] share equal values.
 #[derive(Clone, Debug)]
 pub struct OrderKey {
-    pub values: SmallVec<[Datatype; 1]>,
+    pub values: SmallVec<[Arc<Datatype>; 1]>,
     pub reversed: SmallVec<[bool; 1]>,
 }
 
@@ -199,16 +277,50 @@ impl Default for OrderKey {
 
 impl OrderKey {
     pub fn new(values: SmallVec<[Datatype; 1]>, reversed: SmallVec<[bool; 1]>) -> Self {
+        Self::from_shared(values.into_iter().map(Arc::new).collect(), reversed)
+    }
+
+    pub fn from_shared(
+        values: SmallVec<[Arc<Datatype>; 1]>,
+        reversed: SmallVec<[bool; 1]>,
+    ) -> Self {
         Self { values, reversed }
     }
 
     /// Construct an all-ascending OrderKey (backwards compatible).
     pub fn asc(values: SmallVec<[Datatype; 1]>) -> Self {
         let len = values.len();
-        Self {
-            values,
-            reversed: smallvec::smallvec![false; len],
+        Self::new(values, smallvec::smallvec![false; len])
+    }
+
+    /// Append a component, e.g. a tie-breaker.
+    pub fn push(&mut self, value: Datatype, reversed: bool) {
+        self.values.push(Arc::new(value));
+        self.reversed.push(reversed);
+    }
+}
+
+/// Deduplicates order key components while decoding a result set.
+///
+/// Sorted columns such as timestamps or group ids often repeat across many
+/// rows; interning them keeps a single allocation per distinct value.
+#[derive(Default)]
+pub struct OrderKeyInterner {
+    values: HashSet<Arc<Datatype>>,
+}
+
+impl OrderKeyInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, value: Datatype) -> Arc<Datatype> {
+        if let Some(existing) = self.values.get(&value) {
+            return existing.clone();
         }
+        let value = Arc::new(value);
+        self.values.insert(value.clone());
+        value
     }
 }
 
@@ -255,6 +367,16 @@ impl std::fmt::Display for Datatype {
             Datatype::Text(v) => write!(f, "{v}"),
             Datatype::Blob(v) => write!(f, "{v:?}"),
             Datatype::Bool(v) => write!(f, "{v}"),
+            Datatype::Array(v) => {
+                write!(f, "[")?;
+                for (i, item) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
             Datatype::Null => write!(f, "null"),
         }
     }
@@ -270,9 +392,42 @@ impl Datatype {
             Datatype::Text(_) => "Text",
             Datatype::Blob(_) => "Blob",
             Datatype::Bool(_) => "Bool",
+            Datatype::Array(_) => "Array",
             Datatype::Null => "Null",
         }
     }
+
+    fn mismatch(&self, expected: &'static str) -> DatatypeConversionError {
+        match self {
+            Datatype::Null => DatatypeConversionError::UnexpectedNull,
+            other => DatatypeConversionError::TypeMismatch {
+                expected,
+                got: other.type_name(),
+            },
+        }
+    }
+}
+
+/// `value` as an `i32`, if it is in range.
+pub(crate) fn narrow_int(value: i64) -> Result<i32, DatatypeConversionError> {
+    i32::try_from(value).map_err(|_| DatatypeConversionError::OutOfRange {
+        expected: "Int",
+        value: value.to_string(),
+    })
+}
+
+/// `value` as an `f32`, if it is in range. Precision is lost, but not
+/// magnitude: NaN and the infinities carry over, finite values past
+/// `f32::MAX` are rejected.
+pub(crate) fn narrow_float(value: f64) -> Result<f32, DatatypeConversionError> {
+    let narrowed = value as f32;
+    if narrowed.is_infinite() && value.is_finite() {
+        return Err(DatatypeConversionError::OutOfRange {
+            expected: "Float",
+            value: value.to_string(),
+        });
+    }
+    Ok(narrowed)
 }
 
 impl TryFrom<Datatype> for i32 {
@@ -281,11 +436,8 @@ impl TryFrom<Datatype> for i32 {
     fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
         match datatype {
             Datatype::Int(v) => Ok(v),
-            Datatype::BigInt(v) => Ok(v as i32),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Int",
-                got: other.type_name(),
-            }),
+            Datatype::BigInt(v) => narrow_int(v),
+            other => Err(other.mismatch("Int")),
         }
     }
 }
@@ -297,10 +449,7 @@ impl TryFrom<Datatype> for i64 {
         match datatype {
             Datatype::BigInt(v) => Ok(v),
             Datatype::Int(v) => Ok(v as i64),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "BigInt",
-                got: other.type_name(),
-            }),
+            other => Err(other.mismatch("BigInt")),
         }
     }
 }
@@ -311,11 +460,8 @@ impl TryFrom<Datatype> for f32 {
     fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
         match datatype {
             Datatype::Float(v) => Ok(v),
-            Datatype::Double(v) => Ok(v as f32),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Float",
-                got: other.type_name(),
-            }),
+            Datatype::Double(v) => narrow_float(v),
+            other => Err(other.mismatch("Float")),
         }
     }
 }
@@ -327,10 +473,7 @@ impl TryFrom<Datatype> for f64 {
         match datatype {
             Datatype::Double(v) => Ok(v),
             Datatype::Float(v) => Ok(v as f64),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Double",
-                got: other.type_name(),
-            }),
+            other => Err(other.mismatch("Double")),
         }
     }
 }
@@ -343,10 +486,7 @@ impl TryFrom<Datatype> for bool {
             Datatype::Bool(v) => Ok(v),
             Datatype::Int(v) => Ok(v != 0),
             Datatype::BigInt(v) => Ok(v != 0),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Bool",
-                got: other.type_name(),
-            }),
+            other => Err(other.mismatch("Bool")),
         }
     }
 }
@@ -357,10 +497,7 @@ impl TryFrom<Datatype> for String {
     fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
         match datatype {
             Datatype::Text(v) => Ok(v),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Text",
-                got: other.type_name(),
-            }),
+            other => Err(other.mismatch("Text")),
         }
     }
 }
@@ -371,10 +508,7 @@ impl TryFrom<Datatype> for Vec<u8> {
     fn try_from(datatype: Datatype) -> Result<Self, Self::Error> {
         match datatype {
             Datatype::Blob(v) => Ok(v),
-            other => Err(DatatypeConversionError::TypeMismatch {
-                expected: "Blob",
-                got: other.type_name(),
-            }),
+            other => Err(other.mismatch("Blob")),
         }
     }
 }
@@ -405,3 +539,30 @@ impl<T: TryFrom<Datatype, Error = DatatypeConversionError>> TryFrom<Datatype> fo
         Ok(Unique::new(T::try_from(datatype)?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_narrowing_out_of_range() {
+        assert_eq!(i32::try_from(Datatype::BigInt(-5)).unwrap(), -5);
+        assert!(matches!(
+            i32::try_from(Datatype::BigInt(i64::from(i32::MAX) + 1)),
+            Err(DatatypeConversionError::OutOfRange {
+                expected: "Int",
+                ..
+            })
+        ));
+
+        assert_eq!(f32::try_from(Datatype::Double(0.5)).unwrap(), 0.5);
+        assert!(f32::try_from(Datatype::Double(f64::INFINITY)).is_ok());
+        assert!(matches!(
+            f32::try_from(Datatype::Double(1e300)),
+            Err(DatatypeConversionError::OutOfRange {
+                expected: "Float",
+                ..
+            })
+        ));
+    }
+}
@@ -54,6 +54,12 @@ impl<T: TryFrom<Datatype, Error = DatatypeConversionError>> TryFrom<Datatype> fo
     }
 }
 
+impl<T: crate::FromDatatypeRef> crate::FromDatatypeRef for Embedded<T> {
+    fn from_datatype_ref(value: crate::DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        Ok(Embedded(T::from_datatype_ref(value)?))
+    }
+}
+
 impl<T: crate::AsDatatypeKind> crate::AsDatatypeKind for Embedded<T> {
     fn as_datatype_kind() -> crate::DatatypeKind {
         T::as_datatype_kind()
@@ -179,6 +185,16 @@ impl From<zvec_bindings::Error> for EmbeddingError {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ReindexError<E: std::error::Error> {
+    #[error("no embedding manager is set")]
+    NoEmbeddingManager,
+    #[error("failed to read rows: {0}")]
+    Adapter(E),
+    #[error(transparent)]
+    Embedding(#[from] EmbeddingError),
+}
+
 // ---------------------------------------------------------------------------
 // SimilarityResult
 // ---------------------------------------------------------------------------
@@ -290,6 +306,60 @@ impl<E: DatabaseEmbedder> EmbeddingSidecar<E> {
             .get(table_name)
             .ok_or_else(|| EmbeddingError::UnknownTable(table_name.to_string()))?;
 
+        let doc = self.insert_doc(state, values)?;
+        state.collection.insert(&[doc])?;
+        Ok(())
+    }
+
+    /// Like `on_insert` for many rows, writing all vectors to the collection
+    /// in one call.
+    pub fn on_insert_many(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError> {
+        let state = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| EmbeddingError::UnknownTable(table_name.to_string()))?;
+
+        let docs = rows
+            .iter()
+            .map(|values| self.insert_doc(state, values))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !docs.is_empty() {
+            state.collection.insert(&docs)?;
+        }
+        Ok(())
+    }
+
+    /// Recompute the vectors of `rows`, replacing any stored for the same
+    /// primary keys, e.g. after switching to a different embedder.
+    pub fn reindex(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError> {
+        let state = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| EmbeddingError::UnknownTable(table_name.to_string()))?;
+
+        let docs = rows
+            .iter()
+            .map(|values| self.insert_doc(state, values))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !docs.is_empty() {
+            state.collection.upsert(&docs)?;
+        }
+        Ok(())
+    }
+
+    fn insert_doc(
+        &self,
+        state: &TableEmbeddingState,
+        values: &[(&str, Datatype)],
+    ) -> Result<Doc, EmbeddingError> {
         let pk = values
             .iter()
             .find(|(name, _)| *name == state.pk_field)
@@ -315,8 +385,7 @@ impl<E: DatabaseEmbedder> EmbeddingSidecar<E> {
             doc.set_vector(&vname, &vector)?;
         }
 
-        state.collection.insert(&[doc])?;
-        Ok(())
+        Ok(doc)
     }
 
     pub fn on_update(
@@ -415,12 +484,22 @@ trait DynEmbeddingSidecar: Send + Sync {
         table_name: &'static str,
         values: &[(&str, Datatype)],
     ) -> Result<(), EmbeddingError>;
+    fn on_insert_many(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError>;
     fn on_update(
         &self,
         table_name: &'static str,
         pk: &str,
         changed: &[(&str, &str)],
     ) -> Result<(), EmbeddingError>;
+    fn reindex(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError>;
     fn on_delete(&self, table_name: &'static str, pk: &str) -> Result<(), EmbeddingError>;
     fn has_table(&self, table_name: &str) -> bool;
     fn table_pk_field(&self, table_name: &str) -> Option<&'static str>;
@@ -451,6 +530,14 @@ impl<E: DatabaseEmbedder + Send + Sync> DynEmbeddingSidecar for EmbeddingSidecar
         self.on_insert(table_name, values)
     }
 
+    fn on_insert_many(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError> {
+        self.on_insert_many(table_name, rows)
+    }
+
     fn on_update(
         &self,
         table_name: &'static str,
@@ -460,6 +547,14 @@ impl<E: DatabaseEmbedder + Send + Sync> DynEmbeddingSidecar for EmbeddingSidecar
         self.on_update(table_name, pk, changed)
     }
 
+    fn reindex(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError> {
+        self.reindex(table_name, rows)
+    }
+
     fn on_delete(&self, table_name: &'static str, pk: &str) -> Result<(), EmbeddingError> {
         self.on_delete(table_name, pk)
     }
@@ -553,6 +648,15 @@ impl EmbeddingManager {
             .similarity_search_vec(table_name, field, query_vec, topk)
     }
 
+    /// See [`EmbeddingSidecar::reindex`].
+    pub fn reindex(
+        &self,
+        table_name: &'static str,
+        rows: &[Vec<(&'static str, Datatype)>],
+    ) -> Result<(), EmbeddingError> {
+        self.inner.lock().unwrap().reindex(table_name, rows)
+    }
+
     pub fn pk_field_for_table(&self, table_name: &str) -> Option<&'static str> {
         self.inner.lock().unwrap().table_pk_field(table_name)
     }
@@ -589,7 +693,12 @@ impl MutationHook for EmbeddingManager {
             MutationEventKind::Insert { values } => {
                 let _ = inner.on_insert(event.table_name, values);
             }
-            MutationEventKind::Update { changed, filters } => {
+            MutationEventKind::BulkInsert { rows } => {
+                let _ = inner.on_insert_many(event.table_name, rows);
+            }
+            MutationEventKind::Update {
+                changed, filters, ..
+            } => {
                 let Some(pk) = Self::extract_pk(&**inner, event.table_name, filters) else {
                     return;
                 };
@@ -1,22 +1,34 @@
 use smallvec::SmallVec;
 use unions::{IntoUnion, IsUnion, UnionPath};
 
-use crate::{Datatype, DatatypeConversionError, IsStrongFieldKind, SubscribableRow};
+use crate::{
+    Datatype, DatatypeConversionError, DatatypeRef, FromDatatypeRef, IsStrongFieldKind,
+    SubscribableRow,
+};
 
 pub trait FieldKindGroup<F, D> {
     type Type: Send;
 
     fn field_names(&self) -> SmallVec<[&'static str; 4]>;
+    /// The table each field was qualified with, in the order of
+    /// [`field_names`](Self::field_names).
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]>;
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError>;
+
+    /// Like [`from_datatypes`](Self::from_datatypes), but decodes borrowed
+    /// values, letting adapters read straight from their row buffers.
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError>;
 }
 
 // Single item.
 impl<U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>>
     FieldKindGroup<U, P0> for F0
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = F0::Type;
 
@@ -24,6 +36,10 @@ where
         smallvec::smallvec![self.name()]
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        smallvec::smallvec![self.table_name()]
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -35,13 +51,25 @@ where
             })?;
         F0::Type::try_from(val)
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        let val = values
+            .next()
+            .ok_or(DatatypeConversionError::WrongNumberOfValues {
+                expected: 1,
+                got: 0,
+            })?;
+        F0::Type::from_datatype_ref(val)
+    }
 }
 
 // Array.
 impl<U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>, const N: usize>
     FieldKindGroup<U, P0> for [F0; N]
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = [F0::Type; N];
 
@@ -49,6 +77,10 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -63,6 +95,21 @@ where
             }
         })
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        let converted: Vec<F0::Type> = values
+            .take(N)
+            .map(|v| F0::Type::from_datatype_ref(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        converted.try_into().map_err(|v: Vec<F0::Type>| {
+            DatatypeConversionError::WrongNumberOfValues {
+                expected: N,
+                got: v.len(),
+            }
+        })
+    }
 }
 
 // Array reference.
@@ -74,7 +121,7 @@ impl<
     const N: usize,
 > FieldKindGroup<U, P0> for &'a [F0; N]
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = [F0::Type; N];
 
@@ -82,6 +129,10 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -96,6 +147,21 @@ where
             }
         })
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        let converted: Vec<F0::Type> = values
+            .take(N)
+            .map(|v| F0::Type::from_datatype_ref(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        converted.try_into().map_err(|v: Vec<F0::Type>| {
+            DatatypeConversionError::WrongNumberOfValues {
+                expected: N,
+                got: v.len(),
+            }
+        })
+    }
 }
 
 // Slice.
@@ -103,7 +169,7 @@ where
 impl<'a, U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>>
     FieldKindGroup<U, P0> for &'a [F0]
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = Box<[F0::Type]>;
 
@@ -111,6 +177,10 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -119,13 +189,22 @@ where
             .collect::<Result<Vec<_>, _>>()
             .map(|v| v.into_boxed_slice())
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        values
+            .map(|v| F0::Type::from_datatype_ref(v))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|v| v.into_boxed_slice())
+    }
 }
 
 // Vec.
 impl<U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>>
     FieldKindGroup<U, P0> for Vec<F0>
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = Vec<F0::Type>;
 
@@ -133,18 +212,28 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
         values.map(|v| F0::Type::try_from(v)).collect()
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        values.map(|v| F0::Type::from_datatype_ref(v)).collect()
+    }
 }
 
 // Boxed array.
 impl<U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>, const N: usize>
     FieldKindGroup<U, P0> for Box<[F0; N]>
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = [F0::Type; N];
 
@@ -152,6 +241,10 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -166,13 +259,28 @@ where
             }
         })
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        let converted: Vec<F0::Type> = values
+            .take(N)
+            .map(|v| F0::Type::from_datatype_ref(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        converted.try_into().map_err(|v: Vec<F0::Type>| {
+            DatatypeConversionError::WrongNumberOfValues {
+                expected: N,
+                got: v.len(),
+            }
+        })
+    }
 }
 
 // Boxed slice.
 impl<U: IsUnion, P0: UnionPath, F0: IsStrongFieldKind<Kind = impl IntoUnion<U, P0>>>
     FieldKindGroup<U, P0> for Box<[F0]>
 where
-    F0::Type: TryFrom<Datatype, Error = DatatypeConversionError>,
+    F0::Type: FromDatatypeRef,
 {
     type Type = Box<[F0::Type]>;
 
@@ -180,6 +288,10 @@ where
         self.iter().map(|f| f.name()).collect()
     }
 
+    fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+        self.iter().map(|f| f.table_name()).collect()
+    }
+
     fn from_datatypes(
         values: &mut impl Iterator<Item = Datatype>,
     ) -> Result<Self::Type, DatatypeConversionError> {
@@ -188,6 +300,15 @@ where
             .collect::<Result<Vec<_>, _>>()
             .map(|v| v.into_boxed_slice())
     }
+
+    fn from_datatype_refs<'r>(
+        values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+    ) -> Result<Self::Type, DatatypeConversionError> {
+        values
+            .map(|v| F0::Type::from_datatype_ref(v))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|v| v.into_boxed_slice())
+    }
 }
 
 macro_rules! impl_field_group {
@@ -198,7 +319,7 @@ macro_rules! impl_field_group {
             $($F: IsStrongFieldKind<Kind = impl IntoUnion<U, $P>>,)+
         > FieldKindGroup<U, ($($P,)+)> for ($($F,)+)
         where
-            $($F::Type: TryFrom<Datatype, Error = DatatypeConversionError>,)+
+            $($F::Type: FromDatatypeRef,)+
         {
             #[allow(unused)]
             type Type = ($($F::Type),+);
@@ -209,6 +330,12 @@ macro_rules! impl_field_group {
                 smallvec::smallvec![$($F.name()),+]
             }
 
+            #[allow(non_snake_case)]
+            fn field_tables(&self) -> SmallVec<[Option<&'static str>; 4]> {
+                let ($($F,)+) = self;
+                smallvec::smallvec![$($F.table_name()),+]
+            }
+
             fn from_datatypes(
                 values: &mut impl Iterator<Item = Datatype>,
             ) -> Result<Self::Type, DatatypeConversionError> {
@@ -219,6 +346,17 @@ macro_rules! impl_field_group {
                     <$F>::Type::try_from(val)?
                 }),+))
             }
+
+            fn from_datatype_refs<'r>(
+                values: &mut impl Iterator<Item = DatatypeRef<'r>>,
+            ) -> Result<Self::Type, DatatypeConversionError> {
+                Ok(($({
+                    let val = values.next().ok_or(
+                        DatatypeConversionError::WrongNumberOfValues { expected: 0, got: 0 },
+                    )?;
+                    <$F>::Type::from_datatype_ref(val)?
+                }),+))
+            }
         }
     };
 
@@ -4,7 +4,7 @@ pub use field_group::FieldKindGroup;
 
 use std::marker::PhantomData;
 
-use crate::{Database, Datatype, FieldExpr, PrimaryKey, StrongFieldFilter, Unique};
+use crate::{Database, Datatype, FieldExpr, PrimaryKey, SqlFunction, StrongFieldFilter, Unique};
 
 #[cfg(feature = "embeddings")]
 use crate::Embedded;
@@ -38,7 +38,19 @@ macro_rules! impl_field_wrapper_identity {
     };
 }
 
-impl_field_wrapper_identity!(i32, i64, f32, f64, bool, String);
+impl_field_wrapper_identity!(i32, i64, f32, f64, bool, String, Vec<u8>);
+impl_field_wrapper_identity!(
+    Vec<i32>,
+    Vec<i64>,
+    Vec<f32>,
+    Vec<f64>,
+    Vec<bool>,
+    Vec<String>
+);
+impl_field_wrapper_identity!(std::time::Duration);
+
+#[cfg(feature = "chrono")]
+impl_field_wrapper_identity!(chrono::TimeDelta);
 
 impl<T: InnerFieldType> InnerFieldType for Option<T> {
     type Inner = T::Inner;
@@ -46,6 +58,11 @@ impl<T: InnerFieldType> InnerFieldType for Option<T> {
 
 pub trait FieldKind: Clone {
     fn name(&self) -> &'static str;
+
+    /// The alias of the table the field is taken from, see [`TableAlias`](crate::TableAlias).
+    fn table_alias(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 pub trait FieldKindOfDatabase<D: Database>: FieldKind {
@@ -93,6 +110,24 @@ impl<K: FieldKind, T: InnerFieldType> StrongFieldKind<K, T> {
         StrongFieldFilter::Ne(self.clone(), datatype.into().into())
     }
 
+    /// Like [`eq`](Self::eq), except that `None` matches NULL columns.
+    ///
+    /// ```ignore
+    /// MyDb::USERS.select(User::NAME).filter(User::NICKNAME.eq_nullable(None::<String>))
+    /// ```
+    pub fn eq_nullable(&self, datatype: impl Into<Option<T::Inner>>) -> StrongFieldFilter<K, T> {
+        StrongFieldFilter::EqNullable(self.clone(), datatype.into().into())
+    }
+
+    /// The negation of [`eq_nullable`](Self::eq_nullable): unlike
+    /// [`ne`](Self::ne), it also matches NULL columns when given a value.
+    pub fn is_distinct_from(
+        &self,
+        datatype: impl Into<Option<T::Inner>>,
+    ) -> StrongFieldFilter<K, T> {
+        StrongFieldFilter::DistinctFrom(self.clone(), datatype.into().into())
+    }
+
     pub fn is_in(
         &self,
         values: impl IntoIterator<Item = impl Into<T::Inner>>,
@@ -101,6 +136,19 @@ impl<K: FieldKind, T: InnerFieldType> StrongFieldKind<K, T> {
         StrongFieldFilter::In(self.clone(), datatypes)
     }
 
+    /// Keep rows for which `function(field, args...)` is true.
+    ///
+    /// ```ignore
+    /// MyDb::USERS.select(User::NAME).filter(User::NAME.satisfies(&FUZZY_MATCH, ["bob".into()]))
+    /// ```
+    pub fn satisfies(
+        &self,
+        function: &SqlFunction,
+        args: impl IntoIterator<Item = Datatype>,
+    ) -> StrongFieldFilter<K, T> {
+        StrongFieldFilter::Call(self.clone(), function.clone(), args.into_iter().collect())
+    }
+
     /// Create a concat expression: `Concat(Field(self.name), value)`.
     ///
     /// Used in update builders:
@@ -113,6 +161,64 @@ impl<K: FieldKind, T: InnerFieldType> StrongFieldKind<K, T> {
             Box::new(value.into()),
         )
     }
+
+    /// Create an addition expression: `Add(Field(self.name), value)`, e.g.
+    /// to extend a duration:
+    /// ```ignore
+    /// Call::build().length(Call::LENGTH.plus(Duration::from_secs(30)))
+    /// ```
+    pub fn plus(&self, value: impl Into<FieldExpr>) -> FieldExpr {
+        FieldExpr::Add(
+            Box::new(FieldExpr::Field(self.kind.name())),
+            Box::new(value.into()),
+        )
+    }
+
+    /// Create a subtraction expression: `Sub(Field(self.name), value)`.
+    pub fn minus(&self, value: impl Into<FieldExpr>) -> FieldExpr {
+        FieldExpr::Sub(
+            Box::new(FieldExpr::Field(self.kind.name())),
+            Box::new(value.into()),
+        )
+    }
+
+    /// Create a call expression passing the field's current value to a
+    /// one-argument function: `Call(function, [Field(self.name)])`.
+    pub fn apply(&self, function: &SqlFunction) -> FieldExpr {
+        FieldExpr::Call(function.clone(), vec![FieldExpr::Field(self.kind.name())])
+    }
+}
+
+impl<K: FieldKind, T: InnerFieldType> StrongFieldKind<K, Option<T>> {
+    /// Keep rows where the optional field is NULL.
+    ///
+    /// ```ignore
+    /// MyDb::USERS.select(User::NAME).filter(User::NICKNAME.is_null())
+    /// ```
+    pub fn is_null(&self) -> StrongFieldFilter<K, Option<T>> {
+        StrongFieldFilter::IsNull(self.clone())
+    }
+
+    /// Keep rows where the optional field has a value.
+    pub fn is_not_null(&self) -> StrongFieldFilter<K, Option<T>> {
+        StrongFieldFilter::IsNotNull(self.clone())
+    }
+}
+
+impl<K, T, E> StrongFieldKind<K, T>
+where
+    K: FieldKind,
+    T: InnerFieldType<Inner = Vec<E>>,
+    E: Into<Datatype>,
+{
+    /// Keep rows whose array field has `element` among its elements.
+    ///
+    /// ```ignore
+    /// MyDb::POSTS.select(Post::TITLE).filter(Post::TAGS.contains_element("rust"))
+    /// ```
+    pub fn contains_element(&self, element: impl Into<E>) -> StrongFieldFilter<K, T> {
+        StrongFieldFilter::ContainsElement(self.clone(), element.into().into())
+    }
 }
 
 /// Allow passing a `StrongFieldKind` directly as a `FieldExpr` (becomes `Field` reference).
@@ -132,6 +238,11 @@ pub trait IsStrongFieldKind {
     type Type: Into<Datatype> + Clone + Send;
 
     fn name(&self) -> &'static str;
+
+    /// The table the field was qualified with, see [`QualifiedField`].
+    fn table_name(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 impl<K: FieldKind + Clone, T: InnerFieldType + Send> IsStrongFieldKind for StrongFieldKind<K, T> {
@@ -141,4 +252,40 @@ impl<K: FieldKind + Clone, T: InnerFieldType + Send> IsStrongFieldKind for Stron
     fn name(&self) -> &'static str {
         self.kind.name()
     }
+
+    fn table_name(&self) -> Option<&'static str> {
+        self.kind.table_alias()
+    }
+}
+
+/// A field together with the table it's selected from, for joins of tables
+/// that have columns of the same name:
+///
+/// ```ignore
+/// MyDb::USERS
+///     .join(MyDb::POSTS)
+///     .select((MyDb::POSTS.field(Post::ID), MyDb::USERS.field(User::ID), Post::TITLE))
+/// ```
+///
+/// Unqualified fields of a join are qualified with the one joined table
+/// that has a column of their name, and left for the database to resolve
+/// when several do.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct QualifiedField<K: FieldKind + Clone, T: InnerFieldType> {
+    pub table_name: &'static str,
+    pub field: StrongFieldKind<K, T>,
+}
+
+impl<K: FieldKind + Clone, T: InnerFieldType + Send> IsStrongFieldKind for QualifiedField<K, T> {
+    type Kind = K;
+    type Type = T;
+
+    fn name(&self) -> &'static str {
+        self.field.kind.name()
+    }
+
+    fn table_name(&self) -> Option<&'static str> {
+        Some(self.table_name)
+    }
 }
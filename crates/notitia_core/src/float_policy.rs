@@ -0,0 +1,204 @@
+use crate::{Adapter, Database, Datatype, FieldExpr, MutationEvent, MutationEventKind, Notitia};
+
+/// What mutations do with floats SQL can't order like Rust does.
+///
+/// Subscriptions sort and filter rows with [`f64::total_cmp`], which puts
+/// NaN after every number, while SQLite stores NaN as NULL, which sorts
+/// first and compares with nothing. Either policy keeps the two in line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Write NaN as NULL, into the database and subscriptions alike. The
+    /// infinities are written as they are; they sort the same everywhere.
+    #[default]
+    NanAsNull,
+    /// Fail mutations that write NaN or an infinity, with
+    /// [`NonFiniteFloat`], before they reach the database.
+    RejectNonFinite,
+}
+
+/// A mutation wrote NaN or an infinity under
+/// [`FloatPolicy::RejectNonFinite`]. Adapters report it as one of their
+/// errors.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{table}.{column}` can't be set to {value}")]
+pub struct NonFiniteFloat {
+    pub table: String,
+    pub column: String,
+    /// `NaN`, `inf` or `-inf`.
+    pub value: String,
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Handle NaN and the infinities with `policy` instead of
+    /// [`FloatPolicy::NanAsNull`]. It can only be set once.
+    pub fn set_float_policy(&self, policy: FloatPolicy) {
+        let _ = self.inner.float_policy.set(policy);
+    }
+
+    pub fn float_policy(&self) -> FloatPolicy {
+        self.inner.float_policy.get().copied().unwrap_or_default()
+    }
+}
+
+impl MutationEvent {
+    /// The first value the mutation writes that isn't a finite number.
+    pub(crate) fn non_finite_float(&self) -> Option<NonFiniteFloat> {
+        let reject = |column: &str, value: &Datatype| {
+            let value = match value {
+                Datatype::Float(v) if !v.is_finite() => v.to_string(),
+                Datatype::Double(v) if !v.is_finite() => v.to_string(),
+                _ => return None,
+            };
+            Some(NonFiniteFloat {
+                table: self.table_name.to_string(),
+                column: column.to_string(),
+                value,
+            })
+        };
+
+        match &self.kind {
+            MutationEventKind::Insert { values } => values
+                .iter()
+                .find_map(|(column, value)| reject(column, value)),
+            MutationEventKind::BulkInsert { rows } => rows
+                .iter()
+                .flatten()
+                .find_map(|(column, value)| reject(column, value)),
+            MutationEventKind::Update { changed, .. } => {
+                changed.iter().find_map(|(column, expr)| {
+                    let mut found = None;
+                    for_each_literal(expr, &mut |value| {
+                        if found.is_none() {
+                            found = reject(column, value);
+                        }
+                    });
+                    found
+                })
+            }
+            MutationEventKind::Delete { .. } => None,
+        }
+    }
+
+    /// Replace the NaNs the mutation writes with NULL, like the database
+    /// stored them.
+    pub(crate) fn nan_to_null(&mut self) {
+        let normalize = |value: &mut Datatype| {
+            if value.is_nan() {
+                *value = Datatype::Null;
+            }
+        };
+
+        match &mut self.kind {
+            MutationEventKind::Insert { values } => {
+                values.iter_mut().for_each(|(_, value)| normalize(value));
+            }
+            MutationEventKind::BulkInsert { rows } => {
+                rows.iter_mut()
+                    .flatten()
+                    .for_each(|(_, value)| normalize(value));
+            }
+            MutationEventKind::Update { changed, .. } => {
+                for (_, expr) in changed {
+                    for_each_literal_mut(expr, &mut |value| normalize(value));
+                }
+            }
+            MutationEventKind::Delete { .. } => {}
+        }
+    }
+}
+
+impl Datatype {
+    pub(crate) fn is_nan(&self) -> bool {
+        match self {
+            Datatype::Float(v) => v.is_nan(),
+            Datatype::Double(v) => v.is_nan(),
+            _ => false,
+        }
+    }
+}
+
+fn for_each_literal(expr: &FieldExpr, f: &mut impl FnMut(&Datatype)) {
+    match expr {
+        FieldExpr::Literal(value) => f(value),
+        FieldExpr::Field(_) => {}
+        FieldExpr::Concat(left, right)
+        | FieldExpr::Add(left, right)
+        | FieldExpr::Sub(left, right) => {
+            for_each_literal(left, f);
+            for_each_literal(right, f);
+        }
+        FieldExpr::Call(_, args) => args.iter().for_each(|arg| for_each_literal(arg, f)),
+    }
+}
+
+fn for_each_literal_mut(expr: &mut FieldExpr, f: &mut impl FnMut(&mut Datatype)) {
+    match expr {
+        FieldExpr::Literal(value) => f(value),
+        FieldExpr::Field(_) => {}
+        FieldExpr::Concat(left, right)
+        | FieldExpr::Add(left, right)
+        | FieldExpr::Sub(left, right) => {
+            for_each_literal_mut(left, f);
+            for_each_literal_mut(right, f);
+        }
+        FieldExpr::Call(_, args) => args.iter_mut().for_each(|arg| for_each_literal_mut(arg, f)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(score: f64) -> MutationEvent {
+        MutationEvent {
+            table_name: "players",
+            sequence: 0,
+            kind: MutationEventKind::Insert {
+                values: vec![
+                    ("id", Datatype::Text("abc".into())),
+                    ("score", Datatype::Double(score)),
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn finds_non_finite_floats() {
+        assert_eq!(insert(1.5).non_finite_float(), None);
+        assert_eq!(
+            insert(f64::NEG_INFINITY).non_finite_float(),
+            Some(NonFiniteFloat {
+                table: "players".into(),
+                column: "score".into(),
+                value: "-inf".into(),
+            })
+        );
+
+        let update = MutationEvent {
+            table_name: "players",
+            sequence: 0,
+            kind: MutationEventKind::Update {
+                changed: vec![(
+                    "score",
+                    FieldExpr::Add(
+                        Box::new(FieldExpr::Field("score")),
+                        Box::new(FieldExpr::Literal(Datatype::Double(f64::NAN))),
+                    ),
+                )],
+                filters: Default::default(),
+                rows: Vec::new(),
+            },
+        };
+        assert_eq!(update.non_finite_float().unwrap().value, "NaN");
+    }
+
+    #[test]
+    fn writes_nan_as_null() {
+        let mut event = insert(f64::NAN);
+        event.nan_to_null();
+        let MutationEventKind::Insert { values } = &event.kind else {
+            unreachable!();
+        };
+        assert_eq!(values[1], ("score", Datatype::Null));
+    }
+}
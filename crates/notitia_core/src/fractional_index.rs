@@ -0,0 +1,117 @@
+/// The digits of a fractional index, in ascending byte order, so that
+/// indexes sort the same as text in Rust and in SQL.
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// A fractional index that sorts between `before` and `after`, either
+/// `None` for that end of the list, for storing the manual order of rows in
+/// a text column:
+///
+/// ```ignore
+/// let first = index_between(None, None).unwrap();
+/// let second = index_between(Some(&first), None).unwrap();
+/// // Dropped between the two, without touching either.
+/// let between = index_between(Some(&first), Some(&second)).unwrap();
+/// ```
+///
+/// There is always room for another index, the new one just grows by a
+/// digit now and then. `None` if `before` doesn't sort before `after`, or
+/// either isn't an index this returned.
+pub fn index_between(before: Option<&str>, after: Option<&str>) -> Option<String> {
+    let before = before.map(str::as_bytes);
+    let after = after.map(str::as_bytes);
+    if !before.is_none_or(is_index) || !after.is_none_or(is_index) {
+        return None;
+    }
+    if let (Some(before), Some(after)) = (before, after) {
+        if before >= after {
+            return None;
+        }
+    }
+
+    let index = midpoint(before.unwrap_or_default(), after);
+    Some(String::from_utf8(index).expect("digits are ASCII"))
+}
+
+/// Indexes are made of digits and never end in the smallest one, which
+/// leaves room before every index.
+fn is_index(index: &[u8]) -> bool {
+    index.iter().all(|c| DIGITS.contains(c)) && index.last().is_some_and(|&c| c != DIGITS[0])
+}
+
+fn digit(c: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|&d| d == c)
+        .expect("validated index")
+}
+
+/// The shortest digits between `low` and `high`, where a missing digit of
+/// `low` counts as zero and a missing `high` is past the end.
+fn midpoint(low: &[u8], high: Option<&[u8]>) -> Vec<u8> {
+    if let Some(high) = high {
+        let common = high
+            .iter()
+            .enumerate()
+            .take_while(|&(i, &c)| low.get(i).copied().unwrap_or(DIGITS[0]) == c)
+            .count();
+        if common > 0 {
+            let mut index = high[..common].to_vec();
+            index.extend(midpoint(
+                low.get(common..).unwrap_or_default(),
+                Some(&high[common..]),
+            ));
+            return index;
+        }
+    }
+
+    let low_digit = low.first().map_or(0, |&c| digit(c));
+    let high_digit = high.map_or(DIGITS.len(), |high| digit(high[0]));
+    if high_digit - low_digit > 1 {
+        return vec![DIGITS[(low_digit + high_digit) / 2]];
+    }
+    // The first digits are adjacent: a longer `high` can be cut short,
+    // otherwise keep the first digit of `low` and look past it.
+    match high {
+        Some(high) if high.len() > 1 => vec![high[0]],
+        _ => {
+            let mut index = vec![DIGITS[low_digit]];
+            index.extend(midpoint(low.get(1..).unwrap_or_default(), None));
+            index
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_between_its_neighbours() {
+        let first = index_between(None, None).unwrap();
+        let last = index_between(Some(&first), None).unwrap();
+        assert!(first < last);
+
+        // Keep dropping rows right after the first one.
+        let mut after = last;
+        for _ in 0..200 {
+            let index = index_between(Some(&first), Some(&after)).unwrap();
+            assert!(
+                first < index && index < after,
+                "{first} < {index} < {after}"
+            );
+            after = index;
+        }
+
+        let before = index_between(None, Some(&first)).unwrap();
+        assert!(before < first);
+    }
+
+    #[test]
+    fn rejects_misordered_and_foreign_indexes() {
+        assert_eq!(index_between(Some("b"), Some("a")), None);
+        assert_eq!(index_between(Some("a"), Some("a")), None);
+        assert_eq!(index_between(Some("a0"), None), None);
+        assert_eq!(index_between(Some("a-b"), None), None);
+        assert_eq!(index_between(Some(""), None), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::Datatype;
+
+/// A scalar function registered with the database, so that filters and
+/// update expressions can call it in SQL instead of loading every row into
+/// Rust.
+///
+/// The function must be deterministic: subscriptions call the same closure
+/// in Rust to decide whether a changed row matches.
+///
+/// ```ignore
+/// static NORMALIZE: LazyLock<SqlFunction> = LazyLock::new(|| {
+///     SqlFunction::new("normalize", 1, |args| match &args[0] {
+///         Datatype::Text(text) => Datatype::Text(text.trim().to_lowercase()),
+///         other => other.clone(),
+///     })
+/// });
+///
+/// db.register_function(NORMALIZE.clone()).await?;
+/// ```
+#[derive(Clone)]
+pub struct SqlFunction {
+    name: &'static str,
+    arity: usize,
+    func: Arc<dyn Fn(&[Datatype]) -> Datatype + Send + Sync>,
+}
+
+impl SqlFunction {
+    pub fn new(
+        name: &'static str,
+        arity: usize,
+        func: impl Fn(&[Datatype]) -> Datatype + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name,
+            arity,
+            func: Arc::new(func),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The number of arguments it takes.
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, args: &[Datatype]) -> Datatype {
+        (self.func)(args)
+    }
+}
+
+impl fmt::Debug for SqlFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqlFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions are identified by their name and arity, like in SQL.
+impl PartialEq for SqlFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.arity == other.arity
+    }
+}
+
+impl Hash for SqlFunction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.arity.hash(state);
+    }
+}
+
+/// Whether SQL treats `value` as true in a `WHERE` clause.
+pub(crate) fn is_truthy(value: &Datatype) -> bool {
+    match value {
+        Datatype::Bool(v) => *v,
+        Datatype::Int(v) => *v != 0,
+        Datatype::BigInt(v) => *v != 0,
+        Datatype::Float(v) => *v != 0.0,
+        Datatype::Double(v) => *v != 0.0,
+        // SQLite reads text as a number here; anything that isn't one is 0.
+        Datatype::Text(v) => v.trim().parse::<f64>().is_ok_and(|v| v != 0.0),
+        Datatype::Blob(_) | Datatype::Array(_) | Datatype::Null => false,
+    }
+}
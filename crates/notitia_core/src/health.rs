@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::{Adapter, Database, Notitia};
+
+/// A change in the health of the connection, as seen by a [`Supervisor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// A ping failed, e.g. because the database file was deleted or the
+    /// server restarted. The supervisor is about to reconnect.
+    Lost { error: String },
+    /// Reconnecting failed. It is tried again on the next check.
+    ReconnectFailed { error: String },
+    /// A new connection is up and the schema was initialized and migrated
+    /// again.
+    Reconnected,
+}
+
+/// Watches a connection and reconnects when it dies.
+///
+/// Notitia doesn't depend on an async runtime, so the supervisor is driven
+/// by the application: call [`check`](Self::check) periodically, or hand
+/// [`run`](Self::run) a sleep function and spawn it.
+///
+/// ```ignore
+/// let (supervisor, status) = db.supervisor();
+/// tokio::spawn(supervisor.run(Duration::from_secs(5), tokio::time::sleep));
+///
+/// for status in status.iter() {
+///     show_connection_banner(status);
+/// }
+/// ```
+pub struct Supervisor<Db: Database, Adptr: Adapter> {
+    db: Notitia<Db, Adptr>,
+    sender: crossbeam_channel::Sender<ConnectionStatus>,
+    lost: bool,
+}
+
+impl<Db: Database, Adptr: Adapter> Supervisor<Db, Adptr> {
+    pub(crate) fn new(
+        db: Notitia<Db, Adptr>,
+        sender: crossbeam_channel::Sender<ConnectionStatus>,
+    ) -> Self {
+        Self {
+            db,
+            sender,
+            lost: false,
+        }
+    }
+
+    /// Ping the database, and reconnect if that fails or an earlier attempt
+    /// to reconnect did. Returns whether the connection is up afterwards.
+    pub async fn check(&mut self) -> bool {
+        if !self.lost {
+            match self.db.ping().await {
+                Ok(()) => return true,
+                Err(err) => {
+                    self.lost = true;
+                    self.emit(ConnectionStatus::Lost {
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+
+        let adapter = &self.db.inner.adapter;
+        let reconnected = match adapter.reconnect().await {
+            Ok(()) => adapter.ping().await,
+            Err(err) => Err(err),
+        };
+
+        match reconnected {
+            Ok(()) => {
+                let database = self.db.database();
+                adapter.initialize(database).await;
+                adapter.migrate(database).await;
+
+                self.lost = false;
+                self.emit(ConnectionStatus::Reconnected);
+                true
+            }
+            Err(err) => {
+                self.emit(ConnectionStatus::ReconnectFailed {
+                    error: err.to_string(),
+                });
+                false
+            }
+        }
+    }
+
+    /// Check the connection every `interval`, forever. `sleep` is the
+    /// runtime's timer, e.g. `tokio::time::sleep`.
+    pub async fn run<Sleep, SleepFut>(mut self, interval: Duration, mut sleep: Sleep)
+    where
+        Sleep: FnMut(Duration) -> SleepFut,
+        SleepFut: Future<Output = ()>,
+    {
+        loop {
+            self.check().await;
+            sleep(interval).await;
+        }
+    }
+
+    fn emit(&self, status: ConnectionStatus) {
+        match &status {
+            ConnectionStatus::Reconnected => info!("reconnected to the database"),
+            ConnectionStatus::Lost { error } => warn!("lost the database connection: {error}"),
+            ConnectionStatus::ReconnectFailed { error } => {
+                warn!("failed to reconnect to the database: {error}")
+            }
+        }
+        // Nobody listening is fine; the supervisor keeps reconnecting.
+        let _ = self.sender.send(status);
+    }
+}
@@ -1,4 +1,6 @@
 pub use phf;
+#[cfg(feature = "serde")]
+pub use serde;
 
 mod database;
 pub use database::*;
@@ -21,6 +23,9 @@ pub use datatype::*;
 mod adapter;
 pub use adapter::*;
 
+mod capabilities;
+pub use capabilities::*;
+
 mod utils;
 pub use utils::*;
 
@@ -30,14 +35,81 @@ pub use subscription::*;
 mod collection;
 pub use collection::*;
 
+mod health;
+pub use health::*;
+
+mod archive;
+pub use archive::*;
+
+mod blob;
+pub use blob::*;
+
+mod rewrite;
+pub use rewrite::*;
+
+mod snapshot;
+pub use snapshot::*;
+
+mod read_only;
+pub use read_only::*;
+
+mod clock;
+pub use clock::*;
+
+mod function;
+pub use function::*;
+
+mod suspend;
+pub use suspend::*;
+
+mod tee;
+pub use tee::*;
+
+mod fractional_index;
+pub use fractional_index::*;
+
+mod row_lock;
+
+mod float_policy;
+pub use float_policy::*;
+
+mod outbox;
+pub use outbox::*;
+
+mod before_mutation;
+pub use before_mutation::*;
+
+mod unexpected_null;
+pub use unexpected_null::*;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+#[cfg(feature = "attachments")]
+mod attachments;
+#[cfg(feature = "attachments")]
+pub use attachments::*;
+
+#[cfg(feature = "console")]
+mod console;
+#[cfg(feature = "console")]
+pub use console::*;
+
 #[cfg(feature = "embeddings")]
 pub mod embeddings;
 #[cfg(feature = "embeddings")]
 pub use embeddings::*;
 
-use std::sync::{Arc, OnceLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
 
 /// General-purpose hook for receiving mutation events.
+///
+/// It's called with every event in [sequence](MutationEvent::sequence)
+/// order while the broadcast holds the database's notification lock, so it
+/// must not broadcast events itself, e.g. by running a mutation to the end.
 pub trait MutationHook: Send + Sync {
     fn on_event(&self, event: &MutationEvent);
 }
@@ -51,6 +123,13 @@ where
     pub(crate) adapter: Adptr,
     pub(crate) subscriptions: SubscriptionRegistry,
     pub(crate) mutation_hook: OnceLock<Arc<dyn MutationHook>>,
+    pub(crate) before_mutation_hooks:
+        Mutex<HashMap<&'static str, Vec<Arc<dyn BeforeMutationHook>>>>,
+    pub(crate) suspension: Mutex<Suspension>,
+    pub(crate) row_locks: OnceLock<row_lock::RowLocks>,
+    pub(crate) float_policy: OnceLock<FloatPolicy>,
+    clock: OnceLock<Arc<dyn Clock>>,
+    id_generator: OnceLock<Arc<dyn IdGenerator>>,
     #[cfg(feature = "embeddings")]
     pub(crate) embedding_manager: OnceLock<Arc<EmbeddingManager>>,
 }
@@ -90,6 +169,12 @@ where
                 adapter,
                 subscriptions: SubscriptionRegistry::new(),
                 mutation_hook: OnceLock::new(),
+                before_mutation_hooks: Mutex::default(),
+                suspension: Mutex::new(Suspension::default()),
+                row_locks: OnceLock::new(),
+                float_policy: OnceLock::new(),
+                clock: OnceLock::new(),
+                id_generator: OnceLock::new(),
                 #[cfg(feature = "embeddings")]
                 embedding_manager: OnceLock::new(),
             }),
@@ -100,26 +185,147 @@ where
         &self.inner.database
     }
 
+    /// The adapter, for tools that need to go around the typed statements.
+    /// Nothing run on it directly notifies subscribers or hooks.
+    pub fn adapter(&self) -> &Adptr {
+        &self.inner.adapter
+    }
+
+    /// Take the adapter back, unless another handle to this database is
+    /// still around.
+    pub(crate) fn into_adapter(self) -> Option<Adptr> {
+        Arc::try_unwrap(self.inner).ok().map(|inner| inner.adapter)
+    }
+
+    /// Check that the database can still be reached.
+    pub async fn ping(&self) -> Result<(), Adptr::Error> {
+        self.inner.adapter.ping().await
+    }
+
+    /// Make `function` callable from filters and update expressions.
+    pub async fn register_function(&self, function: SqlFunction) -> Result<(), Adptr::Error> {
+        #[cfg(feature = "serde")]
+        serde_impls::remember_function(function.clone());
+        self.inner.adapter.register_function(function).await
+    }
+
+    /// A [`Supervisor`] that reconnects this database when it becomes
+    /// unreachable, and the channel it reports [`ConnectionStatus`] changes on.
+    pub fn supervisor(
+        &self,
+    ) -> (
+        Supervisor<Db, Adptr>,
+        crossbeam_channel::Receiver<ConnectionStatus>,
+    ) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        (Supervisor::new(self.clone(), sender), receiver)
+    }
+
     pub fn set_mutation_hook(&self, hook: Arc<dyn MutationHook>) {
         let _ = self.inner.mutation_hook.set(hook);
     }
 
+    /// Use `clock` for generated timestamps instead of the system clock.
+    /// Like the mutation hook, it can only be set once.
+    pub fn set_clock(&self, clock: Arc<dyn Clock>) {
+        let _ = self.inner.clock.set(clock);
+    }
+
+    /// Use `ids` for generated ids instead of random UUIDs. It can only be
+    /// set once.
+    pub fn set_id_generator(&self, ids: Arc<dyn IdGenerator>) {
+        let _ = self.inner.id_generator.set(ids);
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        match self.inner.clock.get() {
+            Some(clock) => &**clock,
+            None => &SystemClock,
+        }
+    }
+
+    pub fn id_generator(&self) -> &dyn IdGenerator {
+        static RANDOM_IDS: RandomIds = RandomIds::new();
+        match self.inner.id_generator.get() {
+            Some(ids) => &**ids,
+            None => &RANDOM_IDS,
+        }
+    }
+
+    pub(crate) fn fill_generated<R: Record>(&self, record: &mut R) {
+        record.fill_generated(self.id_generator(), self.clock());
+    }
+
     #[cfg(feature = "embeddings")]
     pub fn set_embedding_manager(&self, mgr: Arc<EmbeddingManager>) {
         let _ = self.inner.mutation_hook.set(mgr.clone());
         let _ = self.inner.embedding_manager.set(mgr);
     }
 
+    /// Recompute the embeddings of every row of every embedded table, e.g.
+    /// after switching embedders. Returns the number of rows reindexed.
+    #[cfg(feature = "embeddings")]
+    pub async fn reindex_embeddings(&self) -> Result<usize, ReindexError<Adptr::Error>> {
+        let manager = self
+            .embedding_manager()
+            .ok_or(ReindexError::NoEmbeddingManager)?;
+
+        let mut reindexed = 0;
+        for table in self.database().embedded_tables() {
+            let mut columns = vec![table.pk_field];
+            columns.extend(table.embedded_fields.iter().map(|(name, _)| *name));
+
+            let rows = self
+                .inner
+                .adapter
+                .fetch_rows(table.table_name, &columns)
+                .await
+                .map_err(ReindexError::Adapter)?;
+            manager.reindex(table.table_name, &rows)?;
+            reindexed += rows.len();
+        }
+        Ok(reindexed)
+    }
+
     #[cfg(feature = "embeddings")]
     pub(crate) fn embedding_manager(&self) -> Option<&Arc<EmbeddingManager>> {
         self.inner.embedding_manager.get()
     }
 
+    /// Broadcast `event` under the next sequence number.
     pub fn notify_subscribers(&self, event: &MutationEvent) {
-        self.inner.subscriptions.broadcast(event);
+        self.notify_subscribers_with(std::slice::from_ref(event), &Delivery::none());
+    }
+
+    /// Broadcast `events` under consecutive sequence numbers, with each
+    /// subscription notified at most once for them all, see
+    /// [`SubscriptionRegistry::broadcast_batch`].
+    pub fn notify_subscribers_batch(&self, events: &[MutationEvent]) {
+        self.notify_subscribers_with(events, &Delivery::none());
+    }
+
+    pub(crate) fn notify_subscribers_with(&self, events: &[MutationEvent], delivery: &Delivery) {
+        let mut suspension = self.inner.suspension.lock().unwrap();
+        if suspension.depth > 0 {
+            suspension.events.extend_from_slice(events);
+            return;
+        }
+        let events = self.inner.subscriptions.broadcast_batch(events, delivery);
+
+        // Still under the lock, so that the hook sees the events of
+        // concurrent broadcasts in order.
         if let Some(hook) = self.inner.mutation_hook.get() {
-            hook.on_event(event);
+            for event in &events {
+                hook.on_event(event);
+            }
         }
+        drop(suspension);
+    }
+
+    /// The sequence number of the last event broadcast, see
+    /// [`MutationEvent::sequence`].
+    pub fn sequence(&self) -> u64 {
+        self.inner.subscriptions.sequence()
     }
 
     pub fn query<FieldUnion, FieldPath, Fields, Mode>(
@@ -134,13 +340,111 @@ where
         QueryExecutor {
             db: self.clone(),
             stmt,
+            merge_pool: None,
         }
     }
 
+    /// Run a tuple of queries concurrently, returning a tuple of their
+    /// outputs in the same order.
+    ///
+    /// ```ignore
+    /// let (user, chats, unread) = db
+    ///     .query_all((db.query(user_q), db.query(chats_q), db.query(unread_q)))
+    ///     .await?;
+    /// ```
+    pub async fn query_all<Q: QueryAll<Adptr>>(
+        &self,
+        queries: Q,
+    ) -> Result<Q::Output, Adptr::Error> {
+        queries.execute().await
+    }
+
+    /// Subscribe to a value computed from the outputs of up to four
+    /// queries, like a view the builder can't express:
+    ///
+    /// ```ignore
+    /// let unread = db
+    ///     .derive((db.query(messages_q), db.query(reads_q)), |messages, reads| {
+    ///         messages.iter().filter(|(id, _)| !reads.contains(id)).count()
+    ///     })
+    ///     .await?;
+    /// ```
+    ///
+    /// `derive` reruns whenever an input's output changes, and the
+    /// subscription is notified only when the value it returns differs from
+    /// the last one.
+    pub async fn derive<Q: DeriveQueries<Adptr, F>, F>(
+        &self,
+        queries: Q,
+        derive: F,
+    ) -> Result<Subscription<Q::Output>, Adptr::Error> {
+        queries.subscribe(derive).await
+    }
+
+    /// Every event of `table` from now on, in sequence order, for services
+    /// that need the change feed rather than the output of a query, like
+    /// indexers. The watch ends when the receiver is dropped.
+    ///
+    /// Unlike subscriptions, it gets updates and deletes whatever columns
+    /// and rows they touch, and mutations run with
+    /// [`read_your_writes`](MutateExecutor::read_your_writes) don't wait for
+    /// it to be received from.
+    pub fn watch_table<Tbl: IsTable<Database = Db>>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+    ) -> crossbeam_channel::Receiver<MutationEvent> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.inner.subscriptions.watch(
+            table.kind.name(),
+            Box::new(move |event: &MutationEvent, _: &Delivery| sender.send(event.clone()).is_ok()),
+        );
+        receiver
+    }
+
+    /// Insert many records in a single transaction, e.g. when importing
+    /// history. Subscribers get one aggregated event for the whole batch.
+    pub fn bulk_load<Tbl, B>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+        records: impl IntoIterator<Item = B>,
+    ) -> MutateExecutor<Db, Adptr, BulkInsertStmtBuilt<Db, Tbl::Record>>
+    where
+        Tbl: IsTable<Database = Db>,
+        Tbl::Record: Send,
+        B: BuiltRecord<Record = Tbl::Record>,
+    {
+        self.mutate(table.insert_many(records))
+    }
+
+    /// Apply seed data, e.g. right after connecting in a test:
+    ///
+    /// ```ignore
+    /// let db = MyDb::connect::<SqliteAdapter>("sqlite::memory:")
+    ///     .await?
+    ///     .with_fixtures(Fixtures::new().table(MyDb::USERS, [alice, bob]))
+    ///     .await?;
+    /// ```
+    pub async fn with_fixtures(self, fixtures: Fixtures<Db, Adptr>) -> Result<Self, Adptr::Error>
+    where
+        Db: 'static,
+    {
+        fixtures.apply(&self).await?;
+        Ok(self)
+    }
+
+    /// Apply the fixtures declared by [`OnStartup::fixtures`].
+    pub async fn seed(&self) -> Result<(), Adptr::Error>
+    where
+        Db: OnStartup + 'static,
+    {
+        self.database().fixtures::<Adptr>().apply(self).await
+    }
+
     pub fn mutate<M: Mutation<Db>>(&self, stmt: M) -> MutateExecutor<Db, Adptr, M> {
         MutateExecutor {
             db: self.clone(),
             stmt,
+            read_your_writes: false,
         }
     }
 
@@ -154,29 +458,95 @@ where
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
         Mode: SelectStmtFetchMode<Fields::Type> + Sync,
     {
+        self.capabilities().require_filters(&stmt.filters)?;
         self.inner.adapter.execute_select_stmt(stmt).await
     }
 
+    pub(crate) async fn execute_select_stmt_until<FieldUnion, FieldPath, Fields, Mode, Deadline>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+        deadline: Deadline,
+    ) -> Result<(Mode::Output, bool), Adptr::Error>
+    where
+        FieldUnion: unions::IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+        Deadline: Future<Output = ()> + Send,
+    {
+        self.capabilities().require_filters(&stmt.filters)?;
+        self.inner
+            .adapter
+            .execute_select_stmt_until(stmt, deadline)
+            .await
+    }
+
+    /// Run a select a client sent as a [`SelectStmtDef`], returning rows it
+    /// can decode with [`SelectStmtBuilt::decode_def_rows`].
+    pub async fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> Result<Vec<Vec<Datatype>>, Adptr::Error> {
+        self.capabilities().require_filters(&def.filters)?;
+        self.inner.adapter.execute_select_def(def).await
+    }
+
     pub(crate) async fn execute_insert_stmt<R: Record + Send>(
         &self,
         stmt: InsertStmtBuilt<Db, R>,
-    ) -> Result<(), Adptr::Error> {
+    ) -> Result<bool, Adptr::Error> {
+        if stmt.ignore_conflicts {
+            self.capabilities()
+                .require_returning("inserts ignoring conflicts")?;
+        }
         self.inner.adapter.execute_insert_stmt(stmt).await
     }
 
+    pub(crate) async fn execute_bulk_insert_stmt<R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Adptr::Error> {
+        if stmt.ignore_conflicts {
+            self.capabilities()
+                .require_returning("bulk inserts ignoring conflicts")?;
+        }
+        self.inner.adapter.execute_bulk_insert_stmt(stmt).await
+    }
+
+    pub(crate) async fn execute_insert_from_select_stmt<Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Adptr::Error> {
+        self.capabilities()
+            .require_returning("`INSERT ... SELECT` statements")?;
+        self.inner
+            .adapter
+            .execute_insert_from_select_stmt(stmt)
+            .await
+    }
+
     pub(crate) async fn execute_update_stmt<Rec: Record + Send, P: PartialRecord + Send>(
         &self,
         stmt: UpdateStmtBuilt<Db, Rec, P>,
-    ) -> Result<(), Adptr::Error> {
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Adptr::Error> {
+        self.capabilities().require_filters(&stmt.filters)?;
         self.inner.adapter.execute_update_stmt(stmt).await
     }
 
     pub(crate) async fn execute_delete_stmt<Rec: Record + Send>(
         &self,
         stmt: DeleteStmtBuilt<Db, Rec>,
-    ) -> Result<(), Adptr::Error> {
+    ) -> Result<Vec<MutationEvent>, Adptr::Error> {
+        self.capabilities().require_filters(&stmt.filters)?;
         self.inner.adapter.execute_delete_stmt(stmt).await
     }
+
+    pub(crate) async fn execute_delete_by_keys_stmt<Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Adptr::Error> {
+        self.inner.adapter.execute_delete_by_keys_stmt(stmt).await
+    }
 }
 
 pub trait Connection {}
@@ -0,0 +1,63 @@
+use crate::{Adapter, Database, Notitia};
+
+/// An integration event for an external system, committed to the outbox
+/// in the same transaction as the mutation that enqueues it, see
+/// [`MutateExecutor::enqueue`](crate::MutateExecutor::enqueue). The event
+/// is then sent if and only if the mutation happened, however the process
+/// fails in between.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxMessage {
+    /// What happened, e.g. `user.signed_up`.
+    pub topic: String,
+    /// Its details, e.g. as JSON.
+    pub payload: String,
+}
+
+impl OutboxMessage {
+    pub fn new(topic: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+        }
+    }
+}
+
+/// A message in the outbox that wasn't dispatched yet, see
+/// [`Notitia::pending_outbox`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxEvent {
+    /// Increases in the order the messages were committed.
+    pub id: i64,
+    pub topic: String,
+    pub payload: String,
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Up to `limit` messages that weren't marked dispatched yet, oldest
+    /// first. Send them on, then mark them with
+    /// [`mark_dispatched`](Self::mark_dispatched):
+    ///
+    /// ```ignore
+    /// for event in db.pending_outbox(100).await? {
+    ///     broker.publish(&event.topic, &event.payload).await?;
+    ///     db.mark_dispatched(&[event.id]).await?;
+    /// }
+    /// ```
+    ///
+    /// A consumer that fails after sending sends the message again, so
+    /// receivers should tell repeated ids apart.
+    pub async fn pending_outbox(&self, limit: usize) -> Result<Vec<OutboxEvent>, Adptr::Error> {
+        self.inner.adapter.fetch_outbox(limit).await
+    }
+
+    /// Take the messages of `ids` off [`pending_outbox`](Self::pending_outbox).
+    pub async fn mark_dispatched(&self, ids: &[i64]) -> Result<(), Adptr::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.inner
+            .adapter
+            .mark_outbox_dispatched(ids.to_vec())
+            .await
+    }
+}
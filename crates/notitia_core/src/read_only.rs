@@ -0,0 +1,109 @@
+use crate::{
+    Adapter, AdapterCapabilities, Database, DeriveQueries, FieldKindGroup, FieldKindOfDatabase,
+    InnerFieldType, IsTable, MutationEvent, Notitia, QueryAll, QueryExecutor, SelectStmtBuilt,
+    SelectStmtFetchMode, Snapshot, StrongFieldKind, StrongTableKind, Subscription,
+};
+
+/// A handle to the database that can query and subscribe but not mutate,
+/// for modules that must not write, like renderers and exporters. See
+/// [`Notitia::read_only_handle`].
+pub struct ReadOnlyNotitia<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    db: Notitia<Db, Adptr>,
+}
+
+impl<Db, Adptr> Clone for ReadOnlyNotitia<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+        }
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// A handle sharing this database that only exposes reads. It has no
+    /// `mutate`, nor the adapter, so code given one can't write by
+    /// construction.
+    pub fn read_only_handle(&self) -> ReadOnlyNotitia<Db, Adptr> {
+        ReadOnlyNotitia { db: self.clone() }
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> ReadOnlyNotitia<Db, Adptr> {
+    pub fn database(&self) -> &Db {
+        self.db.database()
+    }
+
+    pub fn capabilities(&self) -> AdapterCapabilities {
+        self.db.capabilities()
+    }
+
+    /// See [`Notitia::sequence`].
+    pub fn sequence(&self) -> u64 {
+        self.db.sequence()
+    }
+
+    /// See [`Notitia::query`]. The executor runs the query or subscribes to
+    /// it.
+    pub fn query<FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        stmt: SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+    where
+        FieldUnion: unions::IsUnion,
+        Fields: FieldKindGroup<FieldUnion, FieldPath>,
+        Mode: SelectStmtFetchMode<Fields::Type>,
+    {
+        self.db.query(stmt)
+    }
+
+    /// See [`Notitia::query_all`].
+    pub async fn query_all<Q: QueryAll<Adptr>>(
+        &self,
+        queries: Q,
+    ) -> Result<Q::Output, Adptr::Error> {
+        self.db.query_all(queries).await
+    }
+
+    /// See [`Notitia::derive`].
+    pub async fn derive<Q: DeriveQueries<Adptr, F>, F>(
+        &self,
+        queries: Q,
+        derive: F,
+    ) -> Result<Subscription<Q::Output>, Adptr::Error> {
+        self.db.derive(queries, derive).await
+    }
+
+    /// See [`Notitia::count_by`].
+    pub async fn count_by<Field, T>(
+        &self,
+        field: StrongFieldKind<Field, T>,
+        value: impl Into<T::Inner>,
+    ) -> Result<u64, Adptr::Error>
+    where
+        Field: FieldKindOfDatabase<Db>,
+        T: InnerFieldType,
+    {
+        self.db.count_by(field, value).await
+    }
+
+    /// See [`Notitia::watch_table`].
+    pub fn watch_table<Tbl: IsTable<Database = Db>>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+    ) -> crossbeam_channel::Receiver<MutationEvent> {
+        self.db.watch_table(table)
+    }
+
+    /// See [`Notitia::snapshot`].
+    pub async fn snapshot(&self) -> Result<Snapshot<Db, Adptr>, Adptr::Error> {
+        self.db.snapshot().await
+    }
+}
@@ -6,7 +6,9 @@ pub use primary_key::PrimaryKey;
 mod unique;
 pub use unique::Unique;
 
-use crate::{Datatype, DatatypeKind, FieldExpr, FieldKind};
+use crate::{
+    Clock, Datatype, DatatypeConversionError, DatatypeKind, FieldExpr, FieldKind, IdGenerator,
+};
 
 pub type FieldsDef = LazyLock<Box<[(&'static str, DatatypeKind)]>>;
 pub type FieldsDefArray = Box<[(&'static str, DatatypeKind)]>;
@@ -20,6 +22,19 @@ pub trait Record: Clone {
     const _FIELD_MIGRATIONS: &'static [(&'static str, &'static [&'static str])] = &[];
 
     fn into_datatypes(self) -> Vec<(&'static str, Datatype)>;
+
+    /// Set the field stored in `column` to `value`, e.g. as a
+    /// [`BeforeMutationHook`](crate::BeforeMutationHook) rewrote it.
+    /// Columns the record doesn't have are ignored.
+    fn set_datatype(
+        &mut self,
+        column: &str,
+        value: Datatype,
+    ) -> Result<(), DatatypeConversionError>;
+
+    /// Set the `#[db(auto_id)]` and `#[db(created_at)]` fields that were
+    /// left unset, right before the record is inserted.
+    fn fill_generated(&mut self, _ids: &dyn IdGenerator, _clock: &dyn Clock) {}
 }
 
 #[derive(Clone)]
@@ -33,24 +48,40 @@ pub trait BuiltRecord {
 pub trait PartialRecord: Clone {
     type FieldKind: FieldKind;
     fn into_set_fields(self) -> Vec<(&'static str, FieldExpr)>;
+
+    /// Replace the expression `column` is set to. Returns `false`, changing
+    /// nothing, if the column wasn't set.
+    fn replace_set_field(&mut self, column: &str, expr: FieldExpr) -> bool;
 }
 
 /// Trait for field storage in the builder type-state pattern.
 /// `UnsetField` returns `None`, `FieldExpr` returns `Some(expr)`.
 pub trait MaybeSetExpr: Clone {
     fn into_field_expr(self) -> Option<FieldExpr>;
+    /// Replace the expression if one is set, see
+    /// [`PartialRecord::replace_set_field`].
+    fn replace_field_expr(&mut self, expr: FieldExpr) -> bool;
 }
 
 impl MaybeSetExpr for UnsetField {
     fn into_field_expr(self) -> Option<FieldExpr> {
         None
     }
+
+    fn replace_field_expr(&mut self, _expr: FieldExpr) -> bool {
+        false
+    }
 }
 
 impl MaybeSetExpr for FieldExpr {
     fn into_field_expr(self) -> Option<FieldExpr> {
         Some(self)
     }
+
+    fn replace_field_expr(&mut self, expr: FieldExpr) -> bool {
+        *self = expr;
+        true
+    }
 }
 
 // Keep the old MaybeSet trait for BuiltRecord::finish (which still needs concrete types).
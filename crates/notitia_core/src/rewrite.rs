@@ -0,0 +1,222 @@
+use std::marker::PhantomData;
+
+use smallvec::smallvec;
+use unions::{IntoUnion, UnionPath};
+
+use crate::{
+    Adapter, Database, Datatype, DatatypeConversionError, FieldExpr, FieldFilter,
+    FieldFilterMetadata, FieldKindOfDatabase, InnerFieldType, IsTable, MutationEvent,
+    MutationEventKind, Notitia, OrderBy, OrderDirection, Record, SelectStmtDef, StrongFieldKind,
+    StrongTableKind, TableFieldPair, TableKind,
+};
+
+/// Rewrites every value of one column, see [`Notitia::rewrite_column`].
+pub struct ColumnRewrite<Db, Adptr, T, F>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    db: Notitia<Db, Adptr>,
+    table_name: &'static str,
+    column: &'static str,
+    key_column: &'static str,
+    rewrite: F,
+    batch_size: usize,
+    on_progress: Option<Box<dyn FnMut(RewriteProgress) + Send>>,
+    _value: PhantomData<fn(T) -> T>,
+}
+
+/// How far a [`ColumnRewrite`] got, reported after every batch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RewriteProgress {
+    /// Rows read so far.
+    pub scanned: usize,
+    /// Rows whose value changed and was written back.
+    pub rewritten: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RewriteError<E: std::error::Error> {
+    #[error(transparent)]
+    Adapter(E),
+    /// A stored value didn't decode into the column's type. Rows of earlier
+    /// batches were rewritten already.
+    #[error("failed to decode row: {0}")]
+    Decode(#[from] DatatypeConversionError),
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Replace every value of `field` with `rewrite(value)`, e.g. when an
+    /// enum stored as text changes its representation:
+    ///
+    /// ```ignore
+    /// db.rewrite_column(MyDb::TASKS, Task::STATUS, |old: String| match old.as_str() {
+    ///     "done" => "completed".to_string(),
+    ///     _ => old,
+    /// })
+    /// .on_progress(|progress| info!("{} rows scanned", progress.scanned))
+    /// .execute()
+    /// .await?;
+    /// ```
+    ///
+    /// Rows are read in batches ordered by primary key, and each batch's
+    /// changed values are written back in one transaction, so a failure
+    /// leaves earlier batches rewritten. Subscribers receive an update per
+    /// changed row. Panics if the table's primary key isn't a single column,
+    /// or is `field`.
+    pub fn rewrite_column<Tbl, FieldPath, Field, T, F>(
+        &self,
+        table: StrongTableKind<Db, Tbl>,
+        field: StrongFieldKind<Field, T>,
+        rewrite: F,
+    ) -> ColumnRewrite<Db, Adptr, T, F>
+    where
+        Tbl: IsTable<Database = Db>,
+        FieldPath: UnionPath,
+        Field: FieldKindOfDatabase<Db> + IntoUnion<<Tbl::Record as Record>::FieldKind, FieldPath>,
+        T: InnerFieldType + TryFrom<Datatype, Error = DatatypeConversionError>,
+        F: FnMut(T) -> T,
+    {
+        let table_name = table.kind.name();
+        let mut primary_key = <Tbl::Record as Record>::_FIELDS
+            .iter()
+            .filter(|(_, kind)| kind.metadata().primary_key);
+
+        let key_column = match (primary_key.next(), primary_key.next()) {
+            (Some((name, _)), None) => *name,
+            _ => panic!("rewrite_column: `{table_name}` must have a single primary key column"),
+        };
+        assert_ne!(
+            field.kind.name(),
+            key_column,
+            "rewrite_column: the primary key of `{table_name}` can't be rewritten"
+        );
+
+        ColumnRewrite {
+            db: self.clone(),
+            table_name,
+            column: field.kind.name(),
+            key_column,
+            rewrite,
+            batch_size: 500,
+            on_progress: None,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<Db, Adptr, T, F> ColumnRewrite<Db, Adptr, T, F>
+where
+    Db: Database,
+    Adptr: Adapter,
+    T: InnerFieldType + TryFrom<Datatype, Error = DatatypeConversionError>,
+    F: FnMut(T) -> T,
+{
+    /// Rows read and written per batch, 500 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Called after every batch.
+    pub fn on_progress(
+        mut self,
+        on_progress: impl FnMut(RewriteProgress) + Send + 'static,
+    ) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    pub async fn execute(mut self) -> Result<RewriteProgress, RewriteError<Adptr::Error>> {
+        let mut progress = RewriteProgress::default();
+        let mut last_key = None;
+
+        loop {
+            let rows = self
+                .db
+                .execute_select_def(&self.batch_def(last_key.take()))
+                .await
+                .map_err(RewriteError::Adapter)?;
+            let done = rows.len() < self.batch_size;
+            progress.scanned += rows.len();
+
+            let mut changed = Vec::new();
+            for row in rows {
+                let mut row = row.into_iter();
+                let (Some(key), Some(old)) = (row.next(), row.next()) else {
+                    continue;
+                };
+                let new: Datatype = (self.rewrite)(T::try_from(old.clone())?).into();
+                if new != old {
+                    changed.push((key.clone(), new));
+                }
+                last_key = Some(key);
+            }
+
+            if !changed.is_empty() {
+                progress.rewritten += changed.len();
+                self.db
+                    .inner
+                    .adapter
+                    .update_rows(
+                        self.table_name,
+                        self.key_column,
+                        self.column,
+                        changed.clone(),
+                    )
+                    .await
+                    .map_err(RewriteError::Adapter)?;
+                let events: Vec<MutationEvent> = changed
+                    .into_iter()
+                    .map(|(key, value)| MutationEvent {
+                        table_name: self.table_name,
+                        sequence: 0,
+                        kind: MutationEventKind::Update {
+                            changed: vec![(self.column, FieldExpr::Literal(value))],
+                            filters: smallvec![self.key_filter(FieldFilter::Eq, key)],
+                            rows: Vec::new(),
+                        },
+                    })
+                    .collect();
+                self.db.notify_subscribers_batch(&events);
+            }
+
+            if let Some(on_progress) = &mut self.on_progress {
+                on_progress(progress);
+            }
+            if done || last_key.is_none() {
+                return Ok(progress);
+            }
+        }
+    }
+
+    /// The next batch: keys and values of the rows after `after`.
+    fn batch_def(&self, after: Option<Datatype>) -> SelectStmtDef {
+        SelectStmtDef {
+            tables: vec![self.table_name],
+            fields: vec![self.key_column, self.column],
+            filters: after
+                .map(|key| self.key_filter(FieldFilter::Gt, key))
+                .into_iter()
+                .collect(),
+            order_by: vec![OrderBy {
+                expr: FieldExpr::Field(self.key_column),
+                table: self.table_name,
+                direction: OrderDirection::Asc,
+            }],
+            limit: Some(self.batch_size),
+            per_group: None,
+        }
+    }
+
+    fn key_filter(
+        &self,
+        filter: fn(FieldFilterMetadata) -> FieldFilter,
+        key: Datatype,
+    ) -> FieldFilter {
+        filter(FieldFilterMetadata {
+            left: TableFieldPair::new(self.table_name, self.key_column),
+            right: key,
+        })
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    Adapter, Database, Datatype, FieldFilter, MutationEvent, MutationEventKind, Notitia, SchemaDef,
+};
+
+/// A table and the primary key of one of its rows.
+type RowKey = (&'static str, Datatype);
+
+/// The mutations queued up for every row, see
+/// [`Notitia::serialize_row_mutations`].
+pub(crate) struct RowLocks {
+    /// The primary key column of every table that has a single one.
+    primary_keys: HashMap<&'static str, &'static str>,
+    /// The turn of the last mutation in line for every row.
+    tails: Mutex<HashMap<RowKey, Arc<Turn>>>,
+}
+
+type Turn = Mutex<TurnState>;
+
+#[derive(Default)]
+struct TurnState {
+    over: bool,
+    waker: Option<Waker>,
+    /// The turn before, until this one has come. A mutation that gives up
+    /// waiting ends its turn early, and the next one waits for this instead.
+    previous: Option<Arc<Turn>>,
+}
+
+impl RowLocks {
+    pub(crate) fn new(schema: &SchemaDef) -> Self {
+        let primary_keys = schema
+            .tables
+            .iter()
+            .filter_map(|table| {
+                let mut primary_key = table.primary_key();
+                match (primary_key.next(), primary_key.next()) {
+                    (Some(column), None) => Some((table.name, column.name)),
+                    _ => None,
+                }
+            })
+            .collect();
+        Self {
+            primary_keys,
+            tails: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until every mutation that got in line before for one of the
+    /// rows of `event` is over.
+    pub(crate) async fn acquire(&self, event: &MutationEvent) -> RowGuard<'_> {
+        let mut keys = self.row_keys(event);
+        keys.sort();
+        keys.dedup();
+
+        let mut guard = RowGuard {
+            locks: self,
+            turns: Vec::with_capacity(keys.len()),
+            acquired: false,
+        };
+        let mut waits = Vec::new();
+        {
+            // Queued up for all rows at once, so that mutations of
+            // overlapping rows line up in the same order on every row.
+            let mut tails = self.tails.lock().unwrap();
+            for key in keys {
+                let turn = Arc::new(Turn::default());
+                let previous = tails.insert(key.clone(), turn.clone());
+                turn.lock().unwrap().previous = previous.clone();
+                waits.push(Released(previous));
+                guard.turns.push((key, turn));
+            }
+        }
+
+        for wait in waits {
+            wait.await;
+        }
+        guard.acquired = true;
+        guard
+    }
+
+    /// The rows `event` writes to, as far as its values and filters show.
+    fn row_keys(&self, event: &MutationEvent) -> Vec<RowKey> {
+        let Some(&column) = self.primary_keys.get(event.table_name) else {
+            return Vec::new();
+        };
+        let is_key = |table_name: &str, field_name: &str| {
+            table_name == event.table_name && field_name == column
+        };
+
+        let values: Vec<Datatype> = match &event.kind {
+            MutationEventKind::Insert { values } => values
+                .iter()
+                .filter(|(name, _)| *name == column)
+                .map(|(_, value)| value.clone())
+                .collect(),
+            MutationEventKind::BulkInsert { rows } => rows
+                .iter()
+                .flatten()
+                .filter(|(name, _)| *name == column)
+                .map(|(_, value)| value.clone())
+                .collect(),
+            MutationEventKind::Update { filters, .. } | MutationEventKind::Delete { filters } => {
+                filters
+                    .iter()
+                    .flat_map(|filter| match filter {
+                        FieldFilter::Eq(metadata)
+                            if is_key(metadata.left.table_name, metadata.left.field_name) =>
+                        {
+                            vec![metadata.right.clone()]
+                        }
+                        FieldFilter::In(metadata)
+                            if is_key(metadata.left.table_name, metadata.left.field_name) =>
+                        {
+                            metadata.right.clone()
+                        }
+                        _ => Vec::new(),
+                    })
+                    .collect()
+            }
+        };
+        values
+            .into_iter()
+            .map(|value| (event.table_name, value))
+            .collect()
+    }
+}
+
+/// The turn of a mutation on its rows; the next mutation of each row goes
+/// once it's dropped.
+pub(crate) struct RowGuard<'a> {
+    locks: &'a RowLocks,
+    turns: Vec<(RowKey, Arc<Turn>)>,
+    acquired: bool,
+}
+
+impl Drop for RowGuard<'_> {
+    fn drop(&mut self) {
+        let mut tails = self.locks.tails.lock().unwrap();
+        for (key, turn) in self.turns.drain(..) {
+            let mut state = turn.lock().unwrap();
+            state.over = true;
+            let previous = if self.acquired {
+                state.previous = None;
+                None
+            } else {
+                state.previous.clone()
+            };
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            drop(state);
+
+            // Whoever gets in line next waits for the turn before instead.
+            if tails.get(&key).is_some_and(|tail| Arc::ptr_eq(tail, &turn)) {
+                match previous {
+                    Some(previous) => {
+                        tails.insert(key, previous);
+                    }
+                    None => {
+                        tails.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resolves once a turn, and every turn before it, is over.
+struct Released(Option<Arc<Turn>>);
+
+impl Future for Released {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        while let Some(turn) = self.0.clone() {
+            let mut state = turn.lock().unwrap();
+            if !state.over {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            let previous = state.previous.clone();
+            drop(state);
+            self.0 = previous;
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// From now on, run mutations of the same row one after another, in the
+    /// order they were started, and notify subscribers of each before the
+    /// next one runs. Without this, two concurrent updates of a row may
+    /// reach the database in one order and subscribers in the other.
+    ///
+    /// Rows are told apart by primary key: the one an insert sets, or the
+    /// ones an update or delete filters on with `eq` or `in`. Mutations
+    /// that filter on other columns, and tables with a composite primary
+    /// key, aren't serialized.
+    pub fn serialize_row_mutations(&self) {
+        let _ = self.inner.row_locks.set(RowLocks::new(&Db::schema()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::{FieldFilterMetadata, TableFieldPair};
+
+    fn locks() -> RowLocks {
+        RowLocks {
+            primary_keys: HashMap::from([("users", "id")]),
+            tails: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn delete(id: i64) -> MutationEvent {
+        MutationEvent {
+            table_name: "users",
+            sequence: 0,
+            kind: MutationEventKind::Delete {
+                filters: smallvec::smallvec![FieldFilter::Eq(FieldFilterMetadata {
+                    left: TableFieldPair::new("users", "id"),
+                    right: Datatype::BigInt(id),
+                })],
+            },
+        }
+    }
+
+    #[test]
+    fn queues_mutations_of_the_same_row() {
+        let locks = locks();
+        let (row, other_row) = (delete(1), delete(2));
+        let first = locks.acquire(&row).now_or_never().unwrap();
+
+        let mut second = pin!(locks.acquire(&row));
+        assert!(second.as_mut().now_or_never().is_none());
+        assert!(locks.acquire(&other_row).now_or_never().is_some());
+
+        drop(first);
+        assert!(second.now_or_never().is_some());
+    }
+
+    #[test]
+    fn passes_the_turn_on_when_a_waiter_gives_up() {
+        let locks = locks();
+        let row = delete(1);
+        let first = locks.acquire(&row).now_or_never().unwrap();
+
+        let mut second = Box::pin(locks.acquire(&row));
+        assert!(second.as_mut().now_or_never().is_none());
+        let mut third = pin!(locks.acquire(&row));
+        assert!(third.as_mut().now_or_never().is_none());
+
+        drop(second);
+        assert!(third.as_mut().now_or_never().is_none());
+        drop(first);
+        assert!(third.now_or_never().is_some());
+    }
+
+    #[test]
+    fn keeps_the_line_when_its_last_waiter_gives_up() {
+        let locks = locks();
+        let row = delete(1);
+        let first = locks.acquire(&row).now_or_never().unwrap();
+
+        let mut second = Box::pin(locks.acquire(&row));
+        assert!(second.as_mut().now_or_never().is_none());
+        drop(second);
+
+        let mut third = pin!(locks.acquire(&row));
+        assert!(third.as_mut().now_or_never().is_none());
+        drop(first);
+        assert!(third.now_or_never().is_some());
+    }
+}
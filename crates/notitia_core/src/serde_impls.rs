@@ -0,0 +1,94 @@
+//! Statements refer to tables, columns and functions by `&'static str` and
+//! by closure, neither of which can be deserialized as is. Names are interned
+//! instead, and functions are looked up among the ones registered with a
+//! database in this process.
+
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::SqlFunction;
+
+static NAMES: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+
+static FUNCTIONS: Mutex<Vec<SqlFunction>> = Mutex::new(Vec::new());
+
+/// Leak `name` the first time it is seen. Names come from a schema, so there
+/// are only so many of them.
+fn intern(name: String) -> &'static str {
+    let mut names = NAMES.lock().unwrap();
+    match names.get(name.as_str()) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+pub(crate) fn interned<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<&'static str, D::Error> {
+    String::deserialize(deserializer).map(intern)
+}
+
+pub(crate) fn interned_all<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    C: FromIterator<&'static str>,
+{
+    let names = Vec::<String>::deserialize(deserializer)?;
+    Ok(names.into_iter().map(intern).collect())
+}
+
+pub(crate) fn interned_opt<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<&'static str>, D::Error> {
+    Option::<String>::deserialize(deserializer).map(|name| name.map(intern))
+}
+
+pub(crate) fn remember_function(function: SqlFunction) {
+    let mut functions = FUNCTIONS.lock().unwrap();
+    functions.retain(|existing| existing != &function);
+    functions.push(function);
+}
+
+#[derive(Serialize, Deserialize)]
+struct FunctionRef {
+    name: String,
+    arity: usize,
+}
+
+/// Functions are written as their name and arity.
+impl Serialize for SqlFunction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FunctionRef {
+            name: self.name().to_string(),
+            arity: self.arity(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Fails unless a function of that name and arity was registered with
+/// [`Notitia::register_function`](crate::Notitia::register_function).
+impl<'de> Deserialize<'de> for SqlFunction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let FunctionRef { name, arity } = FunctionRef::deserialize(deserializer)?;
+        FUNCTIONS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|function| function.name() == name && function.arity() == arity)
+            .cloned()
+            .ok_or_else(|| {
+                D::Error::custom(format!(
+                    "no SQL function `{name}` taking {arity} arguments is registered"
+                ))
+            })
+    }
+}
@@ -0,0 +1,72 @@
+use tracing::error;
+use unions::IsUnion;
+
+use crate::{Adapter, Database, FieldKindGroup, Notitia, SelectStmtBuilt, SelectStmtFetchMode};
+
+/// Queries reading the database as it was when the snapshot was taken, see
+/// [`Notitia::snapshot`].
+pub struct Snapshot<Db, Adptr>
+where
+    Db: Database,
+    Adptr: Adapter,
+{
+    db: Notitia<Db, Adptr>,
+    snapshot: Adptr::Snapshot,
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Take a snapshot, so that a screen built from several queries shows a
+    /// consistent view even while other tasks write:
+    ///
+    /// ```ignore
+    /// let snapshot = db.snapshot().await?;
+    /// let chat = snapshot
+    ///     .query(MyDb::CHATS.select(Chat::TITLE).filter(Chat::ID.eq(id)).fetch_one())
+    ///     .await?;
+    /// let messages = snapshot
+    ///     .query(
+    ///         MyDb::MESSAGES
+    ///             .select(Message::BODY)
+    ///             .filter(Message::CHAT.eq(id))
+    ///             .fetch_vec(),
+    ///     )
+    ///     .await?;
+    /// ```
+    ///
+    /// Writes made after the snapshot was taken aren't visible to its
+    /// queries. It holds a connection and a read transaction until dropped,
+    /// so keep it for the span of one render rather than longer.
+    pub async fn snapshot(&self) -> Result<Snapshot<Db, Adptr>, Adptr::Error> {
+        let snapshot = self.inner.adapter.begin_snapshot().await?;
+        Ok(Snapshot {
+            db: self.clone(),
+            snapshot,
+        })
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Snapshot<Db, Adptr> {
+    pub async fn query<FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        stmt: SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Adptr::Error>
+    where
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        let stmt = self.db.query(stmt).into_stmt();
+        self.db.capabilities().require_filters(&stmt.filters)?;
+        let result = self
+            .db
+            .inner
+            .adapter
+            .execute_snapshot_select_stmt(&self.snapshot, &stmt)
+            .await;
+        if let Err(ref err) = result {
+            error!("notitia snapshot query failed: {}", err);
+        }
+        result
+    }
+}
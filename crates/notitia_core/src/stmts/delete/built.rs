@@ -1,16 +1,19 @@
 use std::marker::PhantomData;
 
+use derivative::Derivative;
 use smallvec::SmallVec;
-use unions::{IntoUnion, UnionPath};
 
 use crate::{
-    Adapter, Database, FieldFilter, FieldKindOfDatabase, InnerFieldType, Mutation, MutationEvent,
-    MutationEventKind, Notitia, Record, StrongFieldFilter,
+    Adapter, CascadeWalk, Database, FieldFilter, IntoFieldFilter, Mutation, MutationEvent,
+    MutationEventKind, Notitia, OutboxMessage, Record,
 };
 
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
 pub struct DeleteStmtBuilt<Db: Database, Rec: Record> {
     pub table_name: &'static str,
     pub filters: SmallVec<[FieldFilter; 1]>,
+    pub outbox: Vec<OutboxMessage>,
     _database: PhantomData<Db>,
     _record: PhantomData<Rec>,
 }
@@ -20,22 +23,25 @@ impl<Db: Database, Rec: Record> DeleteStmtBuilt<Db, Rec> {
         Self {
             table_name,
             filters,
+            outbox: Vec::new(),
             _database: PhantomData,
             _record: PhantomData,
         }
     }
 
-    pub fn filter<FieldPath: UnionPath, Field, T>(
-        mut self,
-        filter: StrongFieldFilter<Field, T>,
-    ) -> Self
+    pub fn filter<FieldPath, Filter>(mut self, filter: Filter) -> Self
     where
-        Field: FieldKindOfDatabase<Db> + IntoUnion<Rec::FieldKind, FieldPath>,
-        T: InnerFieldType,
+        Filter: IntoFieldFilter<Db, Rec::FieldKind, FieldPath>,
     {
-        self.filters.push(filter.to_weak());
+        self.filters.push(filter.into_field_filter());
         self
     }
+
+    /// The walk for the rows `ON DELETE CASCADE` foreign keys delete along
+    /// with these.
+    pub fn cascade(&self) -> CascadeWalk {
+        CascadeWalk::new::<Db>(self.table_name, &self.filters)
+    }
 }
 
 impl<Db, Rec> Mutation<Db> for DeleteStmtBuilt<Db, Rec>
@@ -48,13 +54,28 @@ where
     fn to_mutation_event(&self) -> MutationEvent {
         MutationEvent {
             table_name: self.table_name,
+            sequence: 0,
             kind: MutationEventKind::Delete {
                 filters: self.filters.clone(),
             },
         }
     }
 
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
     async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
-        db.execute_delete_stmt(self).await
+        db.execute_delete_stmt(self).await?;
+        Ok(())
+    }
+
+    async fn execute_with_cascades<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent, Vec<MutationEvent>), Adptr::Error> {
+        let event = self.to_mutation_event();
+        let cascaded = db.execute_delete_stmt(self).await?;
+        Ok(((), event, cascaded))
     }
 }
@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+
+use derivative::Derivative;
+use smallvec::smallvec;
+
+use crate::{
+    Adapter, CascadeWalk, Database, Datatype, FieldFilter, FieldFilterInMetadata, Mutation,
+    MutationEvent, MutationEventKind, Notitia, OutboxMessage, Record, TableFieldPair,
+};
+
+/// Deletes the rows whose primary key is one of `keys`.
+///
+/// Adapters split long key lists into several `IN (...)` statements with
+/// [`chunks`](Self::chunks) and run them in one transaction. Subscribers
+/// receive a single [`MutationEventKind::Delete`] for all the keys.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct DeleteByKeysStmtBuilt<Db: Database, Rec: Record> {
+    pub table_name: &'static str,
+    /// The table's primary key column.
+    pub key_field: &'static str,
+    pub keys: Vec<Datatype>,
+    pub outbox: Vec<OutboxMessage>,
+    _database: PhantomData<Db>,
+    _record: PhantomData<Rec>,
+}
+
+impl<Db: Database, Rec: Record> DeleteByKeysStmtBuilt<Db, Rec> {
+    pub(crate) fn new(table_name: &'static str, keys: Vec<Datatype>) -> Self {
+        let mut primary_key = Rec::_FIELDS
+            .iter()
+            .filter(|(_, kind)| kind.metadata().primary_key);
+
+        let key_field = match (primary_key.next(), primary_key.next()) {
+            (Some((name, _)), None) => *name,
+            _ => panic!("delete_by_keys: `{table_name}` must have a single primary key column"),
+        };
+
+        Self {
+            table_name,
+            key_field,
+            keys,
+            outbox: Vec::new(),
+            _database: PhantomData,
+            _record: PhantomData,
+        }
+    }
+
+    /// One `IN` filter per run of at most `size` keys.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = FieldFilter> + '_ {
+        self.keys.chunks(size.max(1)).map(|keys| self.filter(keys))
+    }
+
+    /// The walk for the rows `ON DELETE CASCADE` foreign keys delete along
+    /// with these.
+    pub fn cascade(&self) -> CascadeWalk {
+        CascadeWalk::new::<Db>(self.table_name, &[self.filter(&self.keys)])
+    }
+
+    fn filter(&self, keys: &[Datatype]) -> FieldFilter {
+        FieldFilter::In(FieldFilterInMetadata {
+            left: TableFieldPair::new(self.table_name, self.key_field),
+            right: keys.to_vec(),
+        })
+    }
+}
+
+impl<Db, Rec> Mutation<Db> for DeleteByKeysStmtBuilt<Db, Rec>
+where
+    Db: Database,
+    Rec: Record + Send,
+{
+    type Output = ();
+
+    fn to_mutation_event(&self) -> MutationEvent {
+        MutationEvent {
+            table_name: self.table_name,
+            sequence: 0,
+            kind: MutationEventKind::Delete {
+                filters: smallvec![self.filter(&self.keys)],
+            },
+        }
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
+    async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
+        self.execute_with_cascades(db).await?;
+        Ok(())
+    }
+
+    async fn execute_with_cascades<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent, Vec<MutationEvent>), Adptr::Error> {
+        let event = self.to_mutation_event();
+        if self.keys.is_empty() && self.outbox.is_empty() {
+            return Ok(((), event, Vec::new()));
+        }
+        let cascaded = db.execute_delete_by_keys_stmt(self).await?;
+        Ok(((), event, cascaded))
+    }
+}
@@ -0,0 +1,186 @@
+use smallvec::{SmallVec, smallvec};
+
+use crate::{
+    Adapter, Database, Datatype, FieldFilter, FieldFilterInMetadata, MutationEvent,
+    MutationEventKind, OnAction, Relationship, SelectStmtDef, TableFieldPair,
+};
+
+/// Finds the rows that `ON DELETE CASCADE` foreign keys remove along with a
+/// delete, so that subscriptions on the child tables don't keep them.
+///
+/// It asks for the keys the children refer to one
+/// [lookup](Self::next_lookup) at a time. Adapters read them in the
+/// delete's transaction, before deleting, while the keys can still be read
+/// and no other write can add children that the delete would take along.
+#[derive(Clone, Debug)]
+pub struct CascadeWalk {
+    relationships: Vec<Relationship>,
+    /// The foreign keys still to follow, the next one last, each with the
+    /// filters of the parent rows and the tables deleted from on the way
+    /// there, which a cycle of foreign keys would come back to.
+    pending: Vec<(Relationship, SmallVec<[FieldFilter; 1]>, Vec<&'static str>)>,
+    events: Vec<MutationEvent>,
+}
+
+impl CascadeWalk {
+    /// The walk for deleting the rows of `table_name` matching `filters`.
+    pub fn new<Db: Database>(table_name: &'static str, filters: &[FieldFilter]) -> Self {
+        Self::with_relationships(Db::relationships(), table_name, filters)
+    }
+
+    fn with_relationships(
+        relationships: Vec<Relationship>,
+        table_name: &'static str,
+        filters: &[FieldFilter],
+    ) -> Self {
+        let mut walk = Self {
+            relationships,
+            pending: Vec::new(),
+            events: Vec::new(),
+        };
+        walk.follow(table_name, filters.iter().cloned().collect(), Vec::new());
+        walk
+    }
+
+    /// Whether there is nothing left to look up, e.g. for a table no
+    /// foreign key cascades from.
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The select of the keys the children of the next foreign key refer
+    /// to, or `None` once the walk is done.
+    pub fn next_lookup(&self) -> Option<SelectStmtDef> {
+        self.pending
+            .last()
+            .map(|(relationship, filters, _)| SelectStmtDef {
+                tables: vec![relationship.foreign_table],
+                fields: vec![relationship.foreign_field],
+                filters: filters.to_vec(),
+                order_by: Vec::new(),
+                limit: None,
+                per_group: None,
+            })
+    }
+
+    /// Take the rows of the [next lookup](Self::next_lookup), whose children
+    /// are deleted too.
+    pub fn found(&mut self, rows: Vec<Vec<Datatype>>) {
+        let Some((relationship, _, path)) = self.pending.pop() else {
+            return;
+        };
+        let keys: Vec<Datatype> = rows
+            .into_iter()
+            .filter_map(|row| row.into_iter().next())
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        let filters: SmallVec<[FieldFilter; 1]> =
+            smallvec![FieldFilter::In(FieldFilterInMetadata {
+                left: TableFieldPair::new(relationship.table, relationship.field),
+                right: keys,
+            })];
+        self.events.push(MutationEvent {
+            table_name: relationship.table,
+            sequence: 0,
+            kind: MutationEventKind::Delete {
+                filters: filters.clone(),
+            },
+        });
+        self.follow(relationship.table, filters, path);
+    }
+
+    /// The delete events of the children found.
+    pub fn into_events(self) -> Vec<MutationEvent> {
+        self.events
+    }
+
+    /// Run the lookups with `adapter`'s reads, for adapters that can't read
+    /// in the transaction of a delete. Children written in between are
+    /// missed.
+    pub async fn read_with<Adptr: Adapter>(
+        mut self,
+        adapter: &Adptr,
+    ) -> Result<Vec<MutationEvent>, Adptr::Error> {
+        while let Some(lookup) = self.next_lookup() {
+            let rows = adapter.execute_select_def(&lookup).await?;
+            self.found(rows);
+        }
+        Ok(self.into_events())
+    }
+
+    /// Queue the foreign keys cascading from `parent`, whose rows matching
+    /// `filters` are deleted.
+    fn follow(
+        &mut self,
+        parent: &'static str,
+        filters: SmallVec<[FieldFilter; 1]>,
+        mut path: Vec<&'static str>,
+    ) {
+        path.push(parent);
+        let cascading: Vec<Relationship> = self
+            .relationships
+            .iter()
+            .filter(|relationship| {
+                relationship.foreign_table == parent
+                    && relationship.on_delete == OnAction::Cascade
+                    && !path.contains(&relationship.table)
+            })
+            .copied()
+            .collect();
+        // Reversed, as the next one is taken from the end.
+        for relationship in cascading.into_iter().rev() {
+            self.pending
+                .push((relationship, filters.clone(), path.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cascading(table: &'static str, foreign_table: &'static str) -> Relationship {
+        Relationship {
+            table,
+            field: "parent_id",
+            foreign_table,
+            foreign_field: "id",
+            on_delete: OnAction::Cascade,
+            on_update: OnAction::NoAction,
+        }
+    }
+
+    #[test]
+    fn follows_the_keys_each_lookup_found() {
+        let relationships = vec![
+            cascading("messages", "chats"),
+            cascading("reactions", "messages"),
+            cascading("chats", "messages"),
+        ];
+        let mut walk = CascadeWalk::with_relationships(relationships, "chats", &[]);
+
+        let lookup = walk.next_lookup().unwrap();
+        assert_eq!((lookup.tables, lookup.fields), (vec!["chats"], vec!["id"]));
+        walk.found(vec![vec![Datatype::BigInt(1)]]);
+
+        let lookup = walk.next_lookup().unwrap();
+        assert_eq!(lookup.tables, ["messages"]);
+        assert_eq!(
+            lookup.filters,
+            [FieldFilter::In(FieldFilterInMetadata {
+                left: TableFieldPair::new("messages", "parent_id"),
+                right: vec![Datatype::BigInt(1)],
+            })]
+        );
+        // No reactions, and no way back to the chats.
+        walk.found(Vec::new());
+        assert!(walk.is_done());
+
+        let events = walk.into_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].table_name, "messages");
+    }
+}
@@ -3,3 +3,9 @@ pub use built::*;
 
 mod unbuilt;
 pub use unbuilt::*;
+
+mod by_keys;
+pub use by_keys::*;
+
+mod cascade;
+pub use cascade::*;
@@ -1,15 +1,15 @@
 use std::marker::PhantomData;
 
 use smallvec::SmallVec;
-use unions::{IntoUnion, UnionPath};
 
 use crate::{
-    Adapter, Database, DeleteStmtBuilt, FieldKindOfDatabase, InnerFieldType, Mutation,
-    MutationEvent, MutationEventKind, Notitia, Record, StrongFieldFilter,
+    Adapter, Database, DeleteStmtBuilt, IntoFieldFilter, Mutation, MutationEvent,
+    MutationEventKind, Notitia, OutboxMessage, Record,
 };
 
 pub struct DeleteStmtUnbuilt<Db: Database, Rec: Record> {
     pub table_name: &'static str,
+    pub outbox: Vec<OutboxMessage>,
     _database: PhantomData<Db>,
     _record: PhantomData<Rec>,
 }
@@ -18,23 +18,29 @@ impl<Db: Database, Rec: Record> DeleteStmtUnbuilt<Db, Rec> {
     pub(crate) fn new(table_name: &'static str) -> Self {
         Self {
             table_name,
+            outbox: Vec::new(),
             _database: PhantomData,
             _record: PhantomData,
         }
     }
 
-    pub fn filter<FieldPath: UnionPath, Field, T>(
-        self,
-        filter: StrongFieldFilter<Field, T>,
-    ) -> DeleteStmtBuilt<Db, Rec>
+    pub fn filter<FieldPath, Filter>(self, filter: Filter) -> DeleteStmtBuilt<Db, Rec>
     where
-        Field: FieldKindOfDatabase<Db> + IntoUnion<Rec::FieldKind, FieldPath>,
-        T: InnerFieldType,
+        Filter: IntoFieldFilter<Db, Rec::FieldKind, FieldPath>,
     {
         let mut filters = SmallVec::new();
-        filters.push(filter.to_weak());
+        filters.push(filter.into_field_filter());
+
+        let mut built = DeleteStmtBuilt::new(self.table_name, filters);
+        built.outbox = self.outbox;
+        built
+    }
 
-        DeleteStmtBuilt::new(self.table_name, filters)
+    /// Delete every row.
+    fn into_built(self) -> DeleteStmtBuilt<Db, Rec> {
+        let mut built = DeleteStmtBuilt::new(self.table_name, SmallVec::new());
+        built.outbox = self.outbox;
+        built
     }
 }
 
@@ -48,15 +54,25 @@ where
     fn to_mutation_event(&self) -> MutationEvent {
         MutationEvent {
             table_name: self.table_name,
+            sequence: 0,
             kind: MutationEventKind::Delete {
                 filters: SmallVec::new(),
             },
         }
     }
 
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
     async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
-        let built: DeleteStmtBuilt<Db, Rec> =
-            DeleteStmtBuilt::new(self.table_name, SmallVec::new());
-        db.execute_delete_stmt(built).await
+        self.into_built().execute(db).await
+    }
+
+    async fn execute_with_cascades<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent, Vec<MutationEvent>), Adptr::Error> {
+        self.into_built().execute_with_cascades(db).await
     }
 }
@@ -1,10 +1,19 @@
 use std::marker::PhantomData;
 
-use crate::{Adapter, Database, Mutation, MutationEvent, MutationEventKind, Notitia, Record};
+use derivative::Derivative;
 
+use crate::{
+    Adapter, Database, DatatypeConversionError, Mutation, MutationEvent, MutationEventKind,
+    Notitia, OutboxMessage, Record,
+};
+
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
 pub struct InsertStmtBuilt<Db: Database, R: Record> {
     pub table_name: &'static str,
     pub record: R,
+    pub ignore_conflicts: bool,
+    pub outbox: Vec<OutboxMessage>,
     _database: PhantomData<Db>,
 }
 
@@ -13,15 +22,32 @@ impl<Db: Database, R: Record> InsertStmtBuilt<Db, R> {
         Self {
             table_name,
             record,
+            ignore_conflicts: false,
+            outbox: Vec::new(),
             _database: PhantomData,
         }
     }
 
-    pub async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error>
+    /// Skip the record if it conflicts with an existing row on its primary
+    /// key or a unique column, instead of failing (`ON CONFLICT DO NOTHING`).
+    /// Applying the same insert twice is then harmless, e.g. when replaying
+    /// an event log during sync. Subscribers only hear about the insert if
+    /// the row was written.
+    pub fn ignore_conflicts(mut self) -> Self {
+        self.ignore_conflicts = true;
+        self
+    }
+
+    pub async fn execute<Adptr: Adapter>(
+        mut self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<(), Adptr::Error>
     where
         R: Send,
     {
-        db.execute_insert_stmt(self).await
+        db.fill_generated(&mut self.record);
+        db.execute_insert_stmt(self).await?;
+        Ok(())
     }
 }
 
@@ -35,13 +61,58 @@ where
     fn to_mutation_event(&self) -> MutationEvent {
         MutationEvent {
             table_name: self.table_name,
+            sequence: 0,
             kind: MutationEventKind::Insert {
                 values: self.record.clone().into_datatypes(),
             },
         }
     }
 
-    async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
-        db.execute_insert_stmt(self).await
+    fn apply_mutation_event(
+        &mut self,
+        event: MutationEvent,
+    ) -> Result<(), DatatypeConversionError> {
+        if let MutationEventKind::Insert { values } = event.kind {
+            for (column, value) in values {
+                self.record.set_datatype(column, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
+    async fn execute<Adptr: Adapter>(
+        mut self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<(), Adptr::Error> {
+        db.fill_generated(&mut self.record);
+        db.execute_insert_stmt(self).await?;
+        Ok(())
+    }
+
+    async fn execute_with_event<Adptr: Adapter>(
+        mut self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent), Adptr::Error> {
+        db.fill_generated(&mut self.record);
+        let table_name = self.table_name;
+        let event = self.to_mutation_event();
+
+        if db.execute_insert_stmt(self).await? {
+            Ok(((), event))
+        } else {
+            // A skipped conflict changed nothing.
+            Ok((
+                (),
+                MutationEvent {
+                    table_name,
+                    sequence: 0,
+                    kind: MutationEventKind::BulkInsert { rows: Vec::new() },
+                },
+            ))
+        }
     }
 }
@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use derivative::Derivative;
+
+use crate::{
+    Adapter, Database, DatatypeConversionError, Mutation, MutationEvent, MutationEventKind,
+    Notitia, OutboxMessage, Record,
+};
+
+/// Inserts many records in one transaction, broadcasting a single
+/// [`MutationEventKind::BulkInsert`] instead of one event per row.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct BulkInsertStmtBuilt<Db: Database, R: Record> {
+    pub table_name: &'static str,
+    pub records: Vec<R>,
+    pub ignore_conflicts: bool,
+    pub outbox: Vec<OutboxMessage>,
+    _database: PhantomData<Db>,
+}
+
+impl<Db: Database, R: Record> BulkInsertStmtBuilt<Db, R> {
+    pub(crate) fn new(table_name: &'static str, records: Vec<R>) -> Self {
+        Self {
+            table_name,
+            records,
+            ignore_conflicts: false,
+            outbox: Vec::new(),
+            _database: PhantomData,
+        }
+    }
+
+    /// Skip the records that conflict with existing rows instead of failing,
+    /// like [`InsertStmtBuilt::ignore_conflicts`]. The broadcast event only
+    /// lists the rows that were written.
+    pub fn ignore_conflicts(mut self) -> Self {
+        self.ignore_conflicts = true;
+        self
+    }
+}
+
+impl<Db, R> Mutation<Db> for BulkInsertStmtBuilt<Db, R>
+where
+    Db: Database,
+    R: Record + Send,
+{
+    type Output = ();
+
+    fn to_mutation_event(&self) -> MutationEvent {
+        MutationEvent {
+            table_name: self.table_name,
+            sequence: 0,
+            kind: MutationEventKind::BulkInsert {
+                rows: self
+                    .records
+                    .iter()
+                    .map(|record| record.clone().into_datatypes())
+                    .collect(),
+            },
+        }
+    }
+
+    fn apply_mutation_event(
+        &mut self,
+        event: MutationEvent,
+    ) -> Result<(), DatatypeConversionError> {
+        if let MutationEventKind::BulkInsert { rows } = event.kind {
+            for (record, values) in self.records.iter_mut().zip(rows) {
+                for (column, value) in values {
+                    record.set_datatype(column, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
+    async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
+        self.execute_with_event(db).await?;
+        Ok(())
+    }
+
+    async fn execute_with_event<Adptr: Adapter>(
+        mut self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent), Adptr::Error> {
+        for record in &mut self.records {
+            db.fill_generated(record);
+        }
+        let table_name = self.table_name;
+        let rows = if self.records.is_empty() && self.outbox.is_empty() {
+            Vec::new()
+        } else {
+            db.execute_bulk_insert_stmt(self).await?
+        };
+
+        Ok((
+            (),
+            MutationEvent {
+                table_name,
+                sequence: 0,
+                kind: MutationEventKind::BulkInsert { rows },
+            },
+        ))
+    }
+}
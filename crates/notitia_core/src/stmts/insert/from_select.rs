@@ -0,0 +1,98 @@
+use std::marker::PhantomData;
+
+use derivative::Derivative;
+use smallvec::SmallVec;
+
+use crate::{
+    Adapter, Database, Datatype, FieldFilter, Mutation, MutationEvent, MutationEventKind, Notitia,
+    OutboxMessage, Record,
+};
+
+/// `INSERT INTO table (...) SELECT ...`: copies rows between tables inside
+/// the database, e.g. to move old messages into an archive table.
+///
+/// Each selected field is written to the destination column of the same
+/// name. Subscribers of the destination table receive the copied rows as one
+/// [`MutationEventKind::BulkInsert`], and executing it returns how many rows
+/// were copied.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct InsertFromSelectStmtBuilt<Db: Database, Rec: Record> {
+    pub table_name: &'static str,
+    pub columns: SmallVec<[&'static str; 4]>,
+    pub source_tables: SmallVec<[&'static str; 2]>,
+    pub filters: SmallVec<[FieldFilter; 1]>,
+    pub outbox: Vec<OutboxMessage>,
+    _database: PhantomData<Db>,
+    _record: PhantomData<Rec>,
+}
+
+impl<Db: Database, Rec: Record> InsertFromSelectStmtBuilt<Db, Rec> {
+    pub(crate) fn new(
+        table_name: &'static str,
+        columns: SmallVec<[&'static str; 4]>,
+        source_tables: SmallVec<[&'static str; 2]>,
+        filters: SmallVec<[FieldFilter; 1]>,
+    ) -> Self {
+        if let Some(missing) = columns
+            .iter()
+            .find(|column| !Rec::_FIELDS.iter().any(|(name, _)| name == *column))
+        {
+            panic!("insert_from: `{table_name}` has no column `{missing}` to copy into");
+        }
+
+        Self {
+            table_name,
+            columns,
+            source_tables,
+            filters,
+            outbox: Vec::new(),
+            _database: PhantomData,
+            _record: PhantomData,
+        }
+    }
+}
+
+impl<Db, Rec> Mutation<Db> for InsertFromSelectStmtBuilt<Db, Rec>
+where
+    Db: Database,
+    Rec: Record + Send,
+{
+    type Output = usize;
+
+    /// The rows aren't known before the statement runs, so this event is
+    /// empty; the one broadcast comes from [`execute_with_event`](Mutation::execute_with_event).
+    fn to_mutation_event(&self) -> MutationEvent {
+        MutationEvent {
+            table_name: self.table_name,
+            sequence: 0,
+            kind: MutationEventKind::BulkInsert { rows: Vec::new() },
+        }
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
+    async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<usize, Adptr::Error> {
+        let (count, _) = self.execute_with_event(db).await?;
+        Ok(count)
+    }
+
+    async fn execute_with_event<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<(usize, MutationEvent), Adptr::Error> {
+        let table_name = self.table_name;
+        let rows: Vec<Vec<(&'static str, Datatype)>> =
+            db.execute_insert_from_select_stmt(self).await?;
+        Ok((
+            rows.len(),
+            MutationEvent {
+                table_name,
+                sequence: 0,
+                kind: MutationEventKind::BulkInsert { rows },
+            },
+        ))
+    }
+}
@@ -1,5 +1,11 @@
 mod built;
 pub use built::*;
 
+mod bulk;
+pub use bulk::*;
+
+mod from_select;
+pub use from_select::*;
+
 mod mutate_executor;
 pub use mutate_executor::*;
@@ -1,4 +1,6 @@
-use crate::{Adapter, Database, Mutation, Notitia};
+use crate::{
+    Adapter, Database, Delivery, FloatPolicy, Mutation, MutationEvent, Notitia, OutboxMessage,
+};
 use tracing::error;
 
 pub struct MutateExecutor<Db, Adptr, M>
@@ -9,6 +11,7 @@ where
 {
     pub(crate) db: Notitia<Db, Adptr>,
     pub(crate) stmt: M,
+    pub(crate) read_your_writes: bool,
 }
 
 impl<Db, Adptr, M> MutateExecutor<Db, Adptr, M>
@@ -17,14 +20,78 @@ where
     Adptr: Adapter,
     M: Mutation<Db>,
 {
-    pub async fn execute(self) -> Result<M::Output, Adptr::Error> {
-        let event = self.stmt.to_mutation_event();
-        let result = self.stmt.execute(&self.db).await;
+    /// Resolve only once every subscription the mutation changed has
+    /// handed the change to its consumer, e.g. a UI entity, so that reading
+    /// right after sees it.
+    ///
+    /// A subscription whose consumer never receives keeps the mutation
+    /// waiting, so only use this where every subscriber is drained.
+    pub fn read_your_writes(mut self) -> Self {
+        self.read_your_writes = true;
+        self
+    }
+
+    /// Commit `message` to the outbox in the same transaction as the
+    /// mutation, for [`pending_outbox`](Notitia::pending_outbox) to hand to
+    /// an external system. It is committed whenever the mutation is, even
+    /// one that changed no rows.
+    ///
+    /// ```ignore
+    /// db.mutate(MyDb::USERS.insert(user))
+    ///     .enqueue(OutboxMessage::new("user.signed_up", payload))
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn enqueue(mut self, message: OutboxMessage) -> Self {
+        self.stmt.outbox().push(message);
+        self
+    }
+
+    pub async fn execute(mut self) -> Result<M::Output, Adptr::Error> {
+        if let Err(rejected) = self.db.run_before_mutation_hooks(&mut self.stmt) {
+            error!("notitia mutation rejected: {}", rejected);
+            return Err(rejected.into());
+        }
+
+        let float_policy = self.db.float_policy();
+        let rejected = match float_policy {
+            FloatPolicy::NanAsNull => None,
+            FloatPolicy::RejectNonFinite => self.stmt.to_mutation_event().non_finite_float(),
+        };
+        if let Some(rejected) = rejected {
+            error!("notitia mutation rejected: {}", rejected);
+            return Err(rejected.into());
+        }
+
+        // Held until subscribers were notified, so that they see the
+        // mutations of a row in the order they ran.
+        let row_guard = match self.db.inner.row_locks.get() {
+            Some(row_locks) => Some(row_locks.acquire(&self.stmt.to_mutation_event()).await),
+            None => None,
+        };
+
+        let result = self.stmt.execute_with_cascades(&self.db).await;
         if let Err(ref err) = result {
             error!("notitia mutation failed: {}", err);
         }
-        let output = result?;
-        self.db.notify_subscribers(&event);
+        let (output, mut event, cascaded) = result?;
+        if float_policy == FloatPolicy::NanAsNull {
+            event.nan_to_null();
+        }
+
+        let (delivery, settled) = if self.read_your_writes {
+            let (delivery, settled) = Delivery::tracked();
+            (delivery, Some(settled))
+        } else {
+            (Delivery::none(), None)
+        };
+        let events: Vec<MutationEvent> = std::iter::once(event).chain(cascaded).collect();
+        self.db.notify_subscribers_with(&events, &delivery);
+        drop(row_guard);
+        drop(delivery);
+        if let Some(settled) = settled {
+            settled.await;
+        }
         Ok(output)
     }
 }
@@ -10,15 +10,64 @@ pub use update::*;
 mod delete;
 pub use delete::*;
 
-use crate::{Adapter, Database, MutationEvent, Notitia};
+use crate::{Adapter, Database, DatatypeConversionError, MutationEvent, Notitia, OutboxMessage};
 
 pub trait Mutation<Db: Database> {
     type Output;
 
     fn to_mutation_event(&self) -> MutationEvent;
 
+    /// Write the values of `event`, an event of this mutation that
+    /// [`BeforeMutationHook`](crate::BeforeMutationHook)s changed, back into
+    /// the mutation. Only the values inserts write and the expressions
+    /// updates set are taken, so by default nothing is.
+    fn apply_mutation_event(
+        &mut self,
+        _event: MutationEvent,
+    ) -> Result<(), DatatypeConversionError> {
+        Ok(())
+    }
+
+    /// The messages committed to the outbox along with the mutation.
+    /// Adapters write them in the same transaction as the statement.
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage>;
+
     fn execute<Adptr: Adapter>(
         self,
         db: &Notitia<Db, Adptr>,
     ) -> impl Future<Output = Result<Self::Output, Adptr::Error>> + Send;
+
+    /// Run the mutation, returning its output along with the event to
+    /// broadcast. Mutations that only learn what they changed from the
+    /// database override this; by default it is `to_mutation_event`.
+    fn execute_with_event<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> impl Future<Output = Result<(Self::Output, MutationEvent), Adptr::Error>> + Send
+    where
+        Self: Sized,
+    {
+        let event = self.to_mutation_event();
+        let execute = self.execute(db);
+        async move { Ok((execute.await?, event)) }
+    }
+
+    /// Like [`execute_with_event`](Self::execute_with_event), along with the
+    /// delete events of the rows `ON DELETE CASCADE` foreign keys removed
+    /// with the ones this deletes. By default there are none.
+    fn execute_with_cascades<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> impl Future<
+        Output = Result<(Self::Output, MutationEvent, Vec<MutationEvent>), Adptr::Error>,
+    > + Send
+    where
+        Self: Sized,
+    {
+        let execute = self.execute_with_event(db);
+        async move {
+            let (output, event) = execute.await?;
+            Ok((output, event, Vec::new()))
+        }
+    }
 }
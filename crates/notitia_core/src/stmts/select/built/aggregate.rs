@@ -0,0 +1,674 @@
+use std::{cmp::Ordering, ops::Add};
+
+use unions::IsUnion;
+
+use crate::{
+    Adapter, Database, Datatype, DatatypeConversionError, DatatypeRef, FetchError, FieldKindGroup,
+    FromDatatypeRef, MergeCounts, MutationEvent, MutationEventKind, Notitia, OrderKey,
+    SelectStmtBuilt, SubscribableRow, SubscriptionDescriptor,
+    subscription::{merge::row_from_insert, overlap::insert_matches_filters},
+};
+
+use super::{SelectStmtFetchMode, SelectStmtFetchModeSealed};
+
+/// What an aggregating fetch mode selects instead of the rows, see
+/// [`AggregatingFetchMode::aggregate`]. All but `Count` are of the selected
+/// field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    /// The aggregate of the selected field of every matching row, as SQL
+    /// computes it, for adapters that can't in the query: NULLs only
+    /// count as rows, and all but `Count` are NULL without other values.
+    pub fn of(self, values: impl IntoIterator<Item = Datatype>) -> Datatype {
+        let values = values.into_iter();
+        if self == Self::Count {
+            return Datatype::BigInt(values.count() as i64);
+        }
+
+        let values = values.filter(|value| *value != Datatype::Null);
+        match self {
+            Self::Count => unreachable!(),
+            Self::Sum => sum(values).map_or(Datatype::Null, |(sum, _)| sum),
+            Self::Avg => match sum(values) {
+                Some((Datatype::BigInt(sum), count)) => Datatype::Double(sum as f64 / count as f64),
+                Some((Datatype::Double(sum), count)) => Datatype::Double(sum / count as f64),
+                _ => Datatype::Null,
+            },
+            Self::Min => values.min().unwrap_or(Datatype::Null),
+            Self::Max => values.max().unwrap_or(Datatype::Null),
+        }
+    }
+}
+
+/// A fetch mode that selects an aggregate instead of the rows, see
+/// [`SelectStmtFetchMode::aggregating`].
+#[allow(private_bounds)] // `SelectStmtFetchModeSealed` is an internal helper.
+pub trait AggregatingFetchMode<Output>: SelectStmtFetchModeSealed {
+    /// The aggregate adapters should select instead of the rows, as the
+    /// only column of one row, for
+    /// [`from_aggregate`](Self::from_aggregate). Adapters that can't hand
+    /// every matching row to
+    /// [`from_rows`](SelectStmtFetchMode::from_rows) instead.
+    fn aggregate(&self) -> Aggregate;
+
+    /// The output for the value of [`aggregate`](Self::aggregate).
+    fn from_aggregate(&self, value: DatatypeRef<'_>) -> Result<Output, DatatypeConversionError>;
+}
+
+/// The sum of the numbers among `values`, an integer unless one of them is
+/// a float, and how many values there were.
+fn sum(values: impl Iterator<Item = Datatype>) -> Option<(Datatype, usize)> {
+    let (mut integers, mut floats, mut fractional, mut count) = (0i64, 0f64, false, 0);
+    for value in values {
+        count += 1;
+        match value {
+            Datatype::Int(v) => integers += v as i64,
+            Datatype::BigInt(v) => integers += v,
+            Datatype::Bool(v) => integers += v as i64,
+            Datatype::Float(v) => {
+                floats += v as f64;
+                fractional = true;
+            }
+            Datatype::Double(v) => {
+                floats += v;
+                fractional = true;
+            }
+            // SQLite reads anything else as 0.
+            _ => {}
+        }
+    }
+    match (count, fractional) {
+        (0, _) => None,
+        (_, false) => Some((Datatype::BigInt(integers), count)),
+        (_, true) => Some((Datatype::Double(integers as f64 + floats), count)),
+    }
+}
+
+/// The rows `event` inserts that pass the filters of `descriptor`.
+fn inserted_rows<'e>(
+    descriptor: &'e SubscriptionDescriptor,
+    event: &'e MutationEvent,
+) -> impl Iterator<Item = &'e [(&'static str, Datatype)]> {
+    let rows: &[Vec<(&'static str, Datatype)>] = match &event.kind {
+        MutationEventKind::Insert { values } => std::slice::from_ref(values),
+        MutationEventKind::BulkInsert { rows } => rows,
+        MutationEventKind::Update { .. } | MutationEventKind::Delete { .. } => &[],
+    };
+    rows.iter()
+        .map(Vec::as_slice)
+        .filter(|values| insert_matches_filters(values, &descriptor.filters))
+}
+
+/// Whether an aggregate of `descriptor` can follow `event` by merging it,
+/// which only inserts into a single table allow: telling what an update or
+/// delete did to the aggregate takes the rows it aggregates.
+fn merges_insert(descriptor: &SubscriptionDescriptor, event: &MutationEvent) -> bool {
+    descriptor.tables.len() == 1
+        && matches!(
+            event.kind,
+            MutationEventKind::Insert { .. } | MutationEventKind::BulkInsert { .. }
+        )
+}
+
+/// The row whose value is the last by `keep`, of those that aren't NULL.
+fn extreme_row<T: Into<Datatype> + Clone>(rows: Vec<T>, keep: Ordering) -> Option<T> {
+    rows.into_iter()
+        .map(|row| {
+            let value: Datatype = row.clone().into();
+            (value, row)
+        })
+        .filter(|(value, _)| *value != Datatype::Null)
+        .reduce(|kept, next| {
+            if next.0.cmp(&kept.0) == keep {
+                next
+            } else {
+                kept
+            }
+        })
+        .map(|(_, row)| row)
+}
+
+/// The aggregate changed or not, as its one row being updated.
+fn updated_if(changed: bool) -> MergeCounts {
+    MergeCounts {
+        updated: changed as usize,
+        ..MergeCounts::default()
+    }
+}
+
+/// Replace `output` with the inserted rows that come after it by `keep`.
+fn merge_extreme<T: SubscribableRow + Into<Datatype>>(
+    output: &mut Option<T>,
+    descriptor: &SubscriptionDescriptor,
+    event: &MutationEvent,
+    keep: Ordering,
+) -> bool {
+    let mut changed = false;
+    for values in inserted_rows(descriptor, event) {
+        let Some(row) = row_from_insert::<T>(descriptor, values) else {
+            continue;
+        };
+        let value: Datatype = row.clone().into();
+        if value == Datatype::Null {
+            continue;
+        }
+        let replaces = match output {
+            Some(current) => value.cmp(&current.clone().into()) == keep,
+            None => true,
+        };
+        if replaces {
+            *output = Some(row);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// The number of matching rows, see
+/// [`fetch_count`](crate::SelectStmtBuildable::fetch_count).
+#[derive(Debug)]
+pub struct SelectStmtFetchCount {}
+
+impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchCount {
+    type Output = usize;
+
+    fn needs_order_keys(&self) -> bool {
+        false
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        Some(self)
+    }
+
+    fn from_rows(&self, rows: Vec<Ty>, _order_keys: Vec<OrderKey>) -> Result<usize, FetchError> {
+        Ok(rows.len())
+    }
+
+    fn merges_instead_of_requerying(
+        &self,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> bool {
+        merges_insert(descriptor, event)
+    }
+
+    fn merge_event(
+        &self,
+        output: &mut usize,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        Ty: SubscribableRow,
+    {
+        let inserted = inserted_rows(descriptor, event).count();
+        *output += inserted;
+        MergeCounts {
+            inserted,
+            ..MergeCounts::default()
+        }
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<usize, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = Ty> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl SelectStmtFetchModeSealed for SelectStmtFetchCount {}
+
+impl AggregatingFetchMode<usize> for SelectStmtFetchCount {
+    fn aggregate(&self) -> Aggregate {
+        Aggregate::Count
+    }
+
+    fn from_aggregate(&self, value: DatatypeRef<'_>) -> Result<usize, DatatypeConversionError> {
+        Ok(i64::from_datatype_ref(value)? as usize)
+    }
+}
+
+/// The sum of the selected field, see
+/// [`fetch_sum`](crate::SelectStmtBuildable::fetch_sum).
+#[derive(Debug)]
+pub struct SelectStmtFetchSum {}
+
+impl<T> SelectStmtFetchMode<T> for SelectStmtFetchSum
+where
+    T: FromDatatypeRef + Default + Add<Output = T> + Send,
+{
+    type Output = T;
+
+    fn needs_order_keys(&self) -> bool {
+        false
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        Some(self)
+    }
+
+    fn from_rows(&self, rows: Vec<T>, _order_keys: Vec<OrderKey>) -> Result<T, FetchError> {
+        Ok(rows.into_iter().fold(T::default(), Add::add))
+    }
+
+    fn merges_instead_of_requerying(
+        &self,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> bool {
+        merges_insert(descriptor, event)
+    }
+
+    fn merge_event(
+        &self,
+        output: &mut T,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        T: SubscribableRow,
+    {
+        let before = output.clone();
+        for values in inserted_rows(descriptor, event) {
+            if let Some(value) = row_from_insert::<T>(descriptor, values) {
+                *output = output.clone() + value;
+            }
+        }
+        updated_if(*output != before)
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<T, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = T> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl SelectStmtFetchModeSealed for SelectStmtFetchSum {}
+
+impl<T: FromDatatypeRef + Default> AggregatingFetchMode<T> for SelectStmtFetchSum {
+    fn aggregate(&self) -> Aggregate {
+        Aggregate::Sum
+    }
+
+    /// SQL sums no rows to NULL, which is zero here.
+    fn from_aggregate(&self, value: DatatypeRef<'_>) -> Result<T, DatatypeConversionError> {
+        match value {
+            DatatypeRef::Null => Ok(T::default()),
+            value => T::from_datatype_ref(value),
+        }
+    }
+}
+
+/// The average of the selected field, or `None` without rows, see
+/// [`fetch_avg`](crate::SelectStmtBuildable::fetch_avg).
+///
+/// Following an insert takes how many rows were averaged, so requerying
+/// subscriptions run the query again after every change.
+#[derive(Debug)]
+pub struct SelectStmtFetchAvg {}
+
+impl<T> SelectStmtFetchMode<T> for SelectStmtFetchAvg
+where
+    T: Into<Datatype> + Send,
+{
+    type Output = Option<f64>;
+
+    fn needs_order_keys(&self) -> bool {
+        false
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        Some(self)
+    }
+
+    fn from_rows(
+        &self,
+        rows: Vec<T>,
+        _order_keys: Vec<OrderKey>,
+    ) -> Result<Option<f64>, FetchError> {
+        Ok(match Aggregate::Avg.of(rows.into_iter().map(Into::into)) {
+            Datatype::Double(avg) => Some(avg),
+            _ => None,
+        })
+    }
+
+    fn merge_event(
+        &self,
+        _output: &mut Option<f64>,
+        _descriptor: &SubscriptionDescriptor,
+        _event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        T: SubscribableRow,
+    {
+        MergeCounts::default()
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<Option<f64>, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = T> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl SelectStmtFetchModeSealed for SelectStmtFetchAvg {}
+
+impl AggregatingFetchMode<Option<f64>> for SelectStmtFetchAvg {
+    fn aggregate(&self) -> Aggregate {
+        Aggregate::Avg
+    }
+
+    fn from_aggregate(
+        &self,
+        value: DatatypeRef<'_>,
+    ) -> Result<Option<f64>, DatatypeConversionError> {
+        Option::<f64>::from_datatype_ref(value)
+    }
+}
+
+/// The least value of the selected field, or `None` without rows, see
+/// [`fetch_min`](crate::SelectStmtBuildable::fetch_min).
+#[derive(Debug)]
+pub struct SelectStmtFetchMin {}
+
+impl<T> SelectStmtFetchMode<T> for SelectStmtFetchMin
+where
+    T: FromDatatypeRef + Into<Datatype> + Clone + Send,
+{
+    type Output = Option<T>;
+
+    fn needs_order_keys(&self) -> bool {
+        false
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        Some(self)
+    }
+
+    fn from_rows(&self, rows: Vec<T>, _order_keys: Vec<OrderKey>) -> Result<Option<T>, FetchError> {
+        Ok(extreme_row(rows, Ordering::Less))
+    }
+
+    fn merges_instead_of_requerying(
+        &self,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> bool {
+        merges_insert(descriptor, event)
+    }
+
+    fn merge_event(
+        &self,
+        output: &mut Option<T>,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        T: SubscribableRow,
+    {
+        updated_if(merge_extreme(output, descriptor, event, Ordering::Less))
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<Option<T>, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = T> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl SelectStmtFetchModeSealed for SelectStmtFetchMin {}
+
+impl<T: FromDatatypeRef> AggregatingFetchMode<Option<T>> for SelectStmtFetchMin {
+    fn aggregate(&self) -> Aggregate {
+        Aggregate::Min
+    }
+
+    fn from_aggregate(&self, value: DatatypeRef<'_>) -> Result<Option<T>, DatatypeConversionError> {
+        Option::<T>::from_datatype_ref(value)
+    }
+}
+
+/// The greatest value of the selected field, or `None` without rows, see
+/// [`fetch_max`](crate::SelectStmtBuildable::fetch_max).
+#[derive(Debug)]
+pub struct SelectStmtFetchMax {}
+
+impl<T> SelectStmtFetchMode<T> for SelectStmtFetchMax
+where
+    T: FromDatatypeRef + Into<Datatype> + Clone + Send,
+{
+    type Output = Option<T>;
+
+    fn needs_order_keys(&self) -> bool {
+        false
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        Some(self)
+    }
+
+    fn from_rows(&self, rows: Vec<T>, _order_keys: Vec<OrderKey>) -> Result<Option<T>, FetchError> {
+        Ok(extreme_row(rows, Ordering::Greater))
+    }
+
+    fn merges_instead_of_requerying(
+        &self,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> bool {
+        merges_insert(descriptor, event)
+    }
+
+    fn merge_event(
+        &self,
+        output: &mut Option<T>,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        T: SubscribableRow,
+    {
+        updated_if(merge_extreme(output, descriptor, event, Ordering::Greater))
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<Option<T>, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = T> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl SelectStmtFetchModeSealed for SelectStmtFetchMax {}
+
+impl<T: FromDatatypeRef> AggregatingFetchMode<Option<T>> for SelectStmtFetchMax {
+    fn aggregate(&self) -> Aggregate {
+        Aggregate::Max
+    }
+
+    fn from_aggregate(&self, value: DatatypeRef<'_>) -> Result<Option<T>, DatatypeConversionError> {
+        Option::<T>::from_datatype_ref(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use crate::{ChangeReason, FieldFilter, FieldFilterMetadata, TableFieldPair};
+
+    use super::*;
+
+    fn descriptor() -> SubscriptionDescriptor {
+        SubscriptionDescriptor {
+            tables: smallvec!["users"],
+            field_names: smallvec!["age"],
+            filters: smallvec![FieldFilter::Gt(FieldFilterMetadata {
+                left: TableFieldPair::new("users", "age"),
+                right: Datatype::BigInt(17),
+            })],
+            order_by: smallvec![],
+            primary_key: None,
+        }
+    }
+
+    fn bulk_insert(ages: &[i64]) -> MutationEvent {
+        MutationEvent {
+            table_name: "users",
+            sequence: 1,
+            kind: MutationEventKind::BulkInsert {
+                rows: ages
+                    .iter()
+                    .map(|age| vec![("age", Datatype::BigInt(*age))])
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn aggregates_skip_nulls_like_sql() {
+        let values = || {
+            [
+                Datatype::BigInt(3),
+                Datatype::Null,
+                Datatype::BigInt(1),
+                Datatype::BigInt(8),
+            ]
+        };
+        assert_eq!(Aggregate::Count.of(values()), Datatype::BigInt(4));
+        assert_eq!(Aggregate::Sum.of(values()), Datatype::BigInt(12));
+        assert_eq!(Aggregate::Avg.of(values()), Datatype::Double(4.0));
+        assert_eq!(Aggregate::Min.of(values()), Datatype::BigInt(1));
+        assert_eq!(Aggregate::Max.of(values()), Datatype::BigInt(8));
+
+        assert_eq!(Aggregate::Count.of([]), Datatype::BigInt(0));
+        assert_eq!(Aggregate::Sum.of([Datatype::Null]), Datatype::Null);
+        assert_eq!(
+            Aggregate::Sum.of([Datatype::BigInt(1), Datatype::Double(0.5)]),
+            Datatype::Double(1.5)
+        );
+    }
+
+    #[test]
+    fn inserts_add_the_rows_that_match_to_the_aggregate() {
+        let descriptor = descriptor();
+        let event = bulk_insert(&[30, 12, 20]);
+
+        let mut count = 2;
+        let mode = SelectStmtFetchCount {};
+        assert!(SelectStmtFetchMode::<i64>::merges_instead_of_requerying(
+            &mode,
+            &descriptor,
+            &event
+        ));
+        let counts =
+            SelectStmtFetchMode::<i64>::merge_event(&mode, &mut count, &descriptor, &event);
+        assert_eq!(counts.reason(), Some(ChangeReason::RowsInserted(2)));
+        assert_eq!(count, 4);
+
+        let mut sum = 40i64;
+        assert!(
+            !SelectStmtFetchMode::<i64>::merge_event(
+                &SelectStmtFetchSum {},
+                &mut sum,
+                &descriptor,
+                &event
+            )
+            .is_empty()
+        );
+        assert_eq!(sum, 90);
+
+        let mut min = Some(25i64);
+        assert!(
+            !SelectStmtFetchMode::<i64>::merge_event(
+                &SelectStmtFetchMin {},
+                &mut min,
+                &descriptor,
+                &event
+            )
+            .is_empty()
+        );
+        assert_eq!(min, Some(20));
+
+        let mut max = Some(45i64);
+        assert!(
+            SelectStmtFetchMode::<i64>::merge_event(
+                &SelectStmtFetchMax {},
+                &mut max,
+                &descriptor,
+                &event
+            )
+            .is_empty()
+        );
+        assert_eq!(max, Some(45));
+    }
+
+    #[test]
+    fn deletes_are_left_to_the_query() {
+        let event = MutationEvent {
+            table_name: "users",
+            sequence: 1,
+            kind: MutationEventKind::Delete {
+                filters: smallvec![],
+            },
+        };
+        assert!(!SelectStmtFetchMode::<i64>::merges_instead_of_requerying(
+            &SelectStmtFetchCount {},
+            &descriptor(),
+            &event
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use tracing::error;
+use unions::IsUnion;
+
+use crate::{Adapter, Database, FieldKindGroup};
+
+use super::{QueryExecutor, SelectStmtFetchMode};
+
+/// The output of a query given a time budget, see
+/// [`QueryExecutor::execute_within`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Partial<T> {
+    pub data: T,
+    /// Whether the budget ran out before every row was read, so `data`
+    /// holds only the first rows.
+    pub truncated: bool,
+}
+
+impl<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+    QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    Adptr: Adapter,
+    FieldUnion: IsUnion + Send + Sync,
+    FieldPath: Send + Sync,
+    Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+    Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+{
+    /// Run the query for at most `budget`, then settle for the rows read by
+    /// then, e.g. for search-as-you-type on a cold cache, where a partial
+    /// list now beats a full one later:
+    ///
+    /// ```ignore
+    /// let results = db
+    ///     .query(
+    ///         MyDb::MESSAGES
+    ///             .select(Message::BODY)
+    ///             .filter(Message::AUTHOR.eq(author))
+    ///             .fetch_vec(),
+    ///     )
+    ///     .execute_within(Duration::from_millis(50), tokio::time::sleep)
+    ///     .await?;
+    /// if results.truncated {
+    ///     // show "searching…"
+    /// }
+    /// ```
+    ///
+    /// The rows are those the database returned first, in the query's
+    /// order. A fetch mode that needs a row, like `fetch_one`, fails as it
+    /// would on an empty result if none was read in time.
+    pub async fn execute_within<Sleep, SleepFut>(
+        self,
+        budget: Duration,
+        sleep: Sleep,
+    ) -> Result<Partial<Mode::Output>, Adptr::Error>
+    where
+        Sleep: FnOnce(Duration) -> SleepFut,
+        SleepFut: Future<Output = ()> + Send,
+    {
+        let db = self.db.clone();
+        let stmt = self.into_stmt();
+        let result = db.execute_select_stmt_until(&stmt, sleep(budget)).await;
+        match result {
+            Ok((data, truncated)) => Ok(Partial { data, truncated }),
+            Err(err) => {
+                error!("notitia query failed: {}", err);
+                Err(err)
+            }
+        }
+    }
+}
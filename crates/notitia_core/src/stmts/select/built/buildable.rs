@@ -1,11 +1,12 @@
 use smallvec::SmallVec;
 use unions::IsUnion;
 
-use crate::{Collection, Database, FieldFilter, FieldKindGroup};
+use crate::{Collection, Database, FieldFilter, FieldKindGroup, SubscribableRow};
 
 use super::{
-    SelectStmtBuilt, SelectStmtFetchAll, SelectStmtFetchFirst,
-    SelectStmtFetchMany, SelectStmtFetchMode, SelectStmtFetchOne,
+    SelectStmtBuilt, SelectStmtFetchAll, SelectStmtFetchAvg, SelectStmtFetchCount,
+    SelectStmtFetchFirst, SelectStmtFetchMany, SelectStmtFetchMax, SelectStmtFetchMin,
+    SelectStmtFetchMode, SelectStmtFetchOne, SelectStmtFetchSum,
 };
 
 pub trait SelectStmtBuildable<Db, FieldUnion, FieldPath, Fields>: Sized
@@ -47,6 +48,18 @@ where
         SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchAll::new())
     }
 
+    /// Fetches all matching rows into a `Vec`, the same as
+    /// `fetch_all::<Vec<_>>()`.
+    fn fetch_vec(
+        self,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchAll<Vec<Fields::Type>>>
+    where
+        Fields::Type: SubscribableRow,
+        SelectStmtFetchAll<Vec<Fields::Type>>: SelectStmtFetchMode<Fields::Type>,
+    {
+        self.fetch_all()
+    }
+
     /// Fetches up to `max` matching rows into a collection.
     fn fetch_many<FetchAs: Collection>(
         self,
@@ -58,4 +71,60 @@ where
         let (tables, fields, filters) = self.tables_fields_and_filters();
         SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchMany::new(max))
     }
+
+    /// Counts the matching rows, without fetching them:
+    ///
+    /// ```ignore
+    /// let open = db
+    ///     .query(MyDb::TODOS.select(Todo::ID).filter(Todo::DONE.eq(false)).fetch_count())
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    ///
+    /// Like the other aggregates, it follows inserts by merging them but
+    /// has to count again after updates and deletes, so subscribe to it with
+    /// [`subscribe_requerying`](crate::QueryExecutor::subscribe_requerying).
+    fn fetch_count(
+        self,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchCount> {
+        let (tables, fields, filters) = self.tables_fields_and_filters();
+        SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchCount {})
+    }
+
+    /// Sums the selected field of the matching rows, zero if there are none.
+    fn fetch_sum(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchSum>
+    where
+        SelectStmtFetchSum: SelectStmtFetchMode<Fields::Type>,
+    {
+        let (tables, fields, filters) = self.tables_fields_and_filters();
+        SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchSum {})
+    }
+
+    /// Averages the selected field of the matching rows, `None` if there
+    /// are none.
+    fn fetch_avg(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchAvg>
+    where
+        SelectStmtFetchAvg: SelectStmtFetchMode<Fields::Type>,
+    {
+        let (tables, fields, filters) = self.tables_fields_and_filters();
+        SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchAvg {})
+    }
+
+    /// The least value of the selected field among the matching rows.
+    fn fetch_min(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchMin>
+    where
+        SelectStmtFetchMin: SelectStmtFetchMode<Fields::Type>,
+    {
+        let (tables, fields, filters) = self.tables_fields_and_filters();
+        SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchMin {})
+    }
+
+    /// The greatest value of the selected field among the matching rows.
+    fn fetch_max(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchMax>
+    where
+        SelectStmtFetchMax: SelectStmtFetchMode<Fields::Type>,
+    {
+        let (tables, fields, filters) = self.tables_fields_and_filters();
+        SelectStmtBuilt::new(tables, fields, filters, SelectStmtFetchMax {})
+    }
 }
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+use unions::IsUnion;
+
+use crate::{
+    Aggregate, Database, DatatypeConversionError, DatatypeKind, DatatypeRef, FetchError,
+    FieldKindGroup, FromDatatypeRef, OrderBy, OrderDirection, OrderKey, OrderKeyInterner,
+    UnexpectedNull, split_table_entry,
+};
+
+use super::{SelectStmtBuilt, SelectStmtFetchMode};
+
+/// The kinds the database's records declare for their columns, by table
+/// and column, for adapters whose values don't tell e.g. booleans from
+/// integers.
+#[derive(Debug, Default)]
+pub struct ColumnKinds(HashMap<(&'static str, &'static str), DatatypeKind>);
+
+impl ColumnKinds {
+    pub fn of<Db: Database>(database: &Db) -> Self {
+        let mut kinds = HashMap::new();
+        for (table, fields) in database.tables() {
+            for (column, kind) in fields.iter() {
+                kinds.insert((table, *column), kind.clone());
+            }
+        }
+        Self(kinds)
+    }
+
+    /// The kind of `column` in `table`, which may be an alias of one of
+    /// `tables`, or if it wasn't qualified, in the first of `tables` that
+    /// has it.
+    pub fn get(
+        &self,
+        table: Option<&'static str>,
+        tables: &[&'static str],
+        column: &'static str,
+    ) -> Option<&DatatypeKind> {
+        match table {
+            Some(table) => {
+                let table = tables
+                    .iter()
+                    .find_map(|entry| match split_table_entry(*entry) {
+                        (aliased, Some(alias)) if alias == table => Some(aliased),
+                        _ => None,
+                    })
+                    .unwrap_or(table);
+                self.0.get(&(table, column))
+            }
+            None => tables
+                .iter()
+                .find_map(|table| self.0.get(&(*table, column))),
+        }
+    }
+}
+
+/// A row an adapter read for a select, by column: the selected fields, then
+/// the [`unselected_order_by`](SelectStmtBuilt::unselected_order_by)
+/// values, then the total count if the fetch mode
+/// [needs one](SelectStmtFetchMode::needs_total_count). Fetch modes that
+/// [aggregate](SelectStmtFetchMode::aggregating) read one row with just the
+/// aggregate.
+pub trait RawRow {
+    fn column_count(&self) -> usize;
+
+    /// The value of column `index`, as a value of `kind` when the column's
+    /// declared kind is known.
+    fn value(&self, index: usize, kind: Option<&DatatypeKind>) -> DatatypeRef<'_>;
+}
+
+/// The rows an adapter read for a select didn't fit the statement.
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeRowsError {
+    #[error(transparent)]
+    Decode(#[from] DatatypeConversionError),
+    #[error(transparent)]
+    UnexpectedNull(#[from] UnexpectedNull),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+}
+
+impl<Db, FieldUnion, FieldPath, Fields, Mode>
+    SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    /// The orders whose values adapters select after the fields, for the
+    /// order keys: expressions, and fields that aren't selected. None if the
+    /// fetch mode doesn't need order keys.
+    pub fn unselected_order_by(&self) -> impl Iterator<Item = &OrderBy> {
+        let field_names = self.fields.field_names();
+        let needs_order_keys = self.mode.needs_order_keys();
+        self.order_by.iter().filter(move |order| {
+            needs_order_keys
+                && match order.field() {
+                    Some(field) => !field_names.contains(&field),
+                    None => true,
+                }
+        })
+    }
+}
+
+/// Decodes the rows of a select one at a time, extracting their order keys,
+/// so that adapters only convert column values. See
+/// [`decode_rows_with_order_keys`] for decoding them all at once.
+pub struct SelectRowDecoder<'s, Db, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    stmt: &'s SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    field_names: SmallVec<[&'static str; 4]>,
+    field_kinds: SmallVec<[Option<&'s DatatypeKind>; 4]>,
+    order_key_indices: SmallVec<[(usize, Option<&'s DatatypeKind>); 1]>,
+    reversed: SmallVec<[bool; 1]>,
+    // Sorted columns repeat across rows (timestamps, group ids), so equal
+    // values share one allocation for the lifetime of the result set.
+    interner: OrderKeyInterner,
+    typed_rows: Vec<Fields::Type>,
+    order_keys: Vec<OrderKey>,
+    total: Option<usize>,
+    aggregated: Option<Mode::Output>,
+}
+
+impl<'s, Db, FieldUnion, FieldPath, Fields, Mode>
+    SelectRowDecoder<'s, Db, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    /// A decoder for the rows of `stmt`, reading the declared kinds of its
+    /// columns from `column_kinds`.
+    pub fn new(
+        stmt: &'s SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+        column_kinds: Option<&'s ColumnKinds>,
+    ) -> Self {
+        let field_names = stmt.fields.field_names();
+        let kind_of = |table: Option<&'static str>, column: &'static str| {
+            column_kinds?.get(table, &stmt.tables, column)
+        };
+        let field_kinds: SmallVec<[Option<&DatatypeKind>; 4]> = field_names
+            .iter()
+            .zip(stmt.fields.field_tables())
+            .map(|(column, table)| kind_of(table, *column))
+            .collect();
+
+        // Build column index mapping for ORDER BY fields (only when needed).
+        let mut order_key_indices = SmallVec::new();
+        if stmt.mode.needs_order_keys() {
+            let mut extra_col_idx = field_names.len();
+            for order in &stmt.order_by {
+                // Expressions are always selected as extra columns.
+                let selected = order
+                    .field()
+                    .and_then(|field| field_names.iter().position(|n| *n == field));
+                if let Some(pos) = selected {
+                    order_key_indices.push((pos, field_kinds[pos]));
+                } else {
+                    let kind = order.field().and_then(|field| kind_of(None, field));
+                    order_key_indices.push((extra_col_idx, kind));
+                    extra_col_idx += 1;
+                }
+            }
+        }
+
+        Self {
+            stmt,
+            field_names,
+            field_kinds,
+            order_key_indices,
+            reversed: stmt
+                .order_by
+                .iter()
+                .map(|order| matches!(order.direction, OrderDirection::Desc))
+                .collect(),
+            interner: OrderKeyInterner::new(),
+            typed_rows: Vec::new(),
+            order_keys: Vec::new(),
+            total: None,
+            aggregated: None,
+        }
+    }
+
+    /// Make room for `additional` more rows.
+    pub fn reserve(&mut self, additional: usize) {
+        self.typed_rows.reserve(additional);
+        self.order_keys.reserve(additional);
+    }
+
+    pub fn push<R: RawRow>(&mut self, row: &R) -> Result<(), DecodeRowsError> {
+        let stmt = self.stmt;
+        let row_index = self.typed_rows.len();
+
+        if let Some(aggregating) = stmt.mode.aggregating() {
+            // A count or average isn't of the field's kind.
+            let kind = match aggregating.aggregate() {
+                Aggregate::Count | Aggregate::Avg => None,
+                Aggregate::Sum | Aggregate::Min | Aggregate::Max => {
+                    self.field_kinds.first().copied().flatten()
+                }
+            };
+            self.aggregated = Some(aggregating.from_aggregate(row.value(0, kind))?);
+            return Ok(());
+        }
+
+        // `COUNT(*) OVER ()` repeats the total on every row, as the last
+        // column.
+        if stmt.mode.needs_total_count() && self.total.is_none() {
+            let total = i64::from_datatype_ref(row.value(row.column_count() - 1, None))?;
+            self.total = Some(total as usize);
+        }
+
+        // Only order key columns are copied out of the row; selected fields
+        // are decoded from borrowed values below.
+        let order_key = if stmt.mode.needs_order_keys() {
+            OrderKey::from_shared(
+                self.order_key_indices
+                    .iter()
+                    .map(|&(idx, kind)| self.interner.intern(row.value(idx, kind).into_owned()))
+                    .collect(),
+                self.reversed.clone(),
+            )
+        } else {
+            OrderKey::default()
+        };
+
+        let available = self.field_names.len().min(row.column_count());
+        let mut values = (0..available).map(|i| row.value(i, self.field_kinds[i]));
+        let typed = Fields::from_datatype_refs(&mut values).map_err(|err| {
+            // The failing value is the last one the group pulled.
+            let consumed = available - values.len();
+            let column = consumed
+                .checked_sub(1)
+                .and_then(|idx| self.field_names.get(idx).copied())
+                .unwrap_or("?");
+            let err = err.in_column(stmt.tables.join(", "), column, row_index);
+            match UnexpectedNull::from_decode_error(&err, &stmt.filters) {
+                Some(null) => DecodeRowsError::from(null),
+                None => DecodeRowsError::from(err),
+            }
+        })?;
+
+        self.typed_rows.push(typed);
+        self.order_keys.push(order_key);
+        Ok(())
+    }
+
+    /// The output of the rows pushed so far.
+    pub fn finish(self) -> Result<Mode::Output, FetchError> {
+        if let Some(output) = self.aggregated {
+            Ok(output)
+        } else if self.stmt.mode.needs_total_count() {
+            let total = self.total.unwrap_or(0);
+            self.stmt
+                .mode
+                .from_counted_rows(self.typed_rows, self.order_keys, total)
+        } else {
+            self.stmt.mode.from_rows(self.typed_rows, self.order_keys)
+        }
+    }
+}
+
+/// Build the output of `stmt` from the rows an adapter read for it, laid
+/// out as [`RawRow`] describes, with the declared kinds of their columns
+/// from `column_kinds`.
+pub fn decode_rows_with_order_keys<Db, FieldUnion, FieldPath, Fields, Mode, R>(
+    stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    raw_rows: impl IntoIterator<Item = R>,
+    column_kinds: Option<&ColumnKinds>,
+) -> Result<Mode::Output, DecodeRowsError>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+    R: RawRow,
+{
+    let raw_rows = raw_rows.into_iter();
+    let mut decoder = SelectRowDecoder::new(stmt, column_kinds);
+    decoder.reserve(raw_rows.size_hint().0);
+    for row in raw_rows {
+        decoder.push(&row)?;
+    }
+    Ok(decoder.finish()?)
+}
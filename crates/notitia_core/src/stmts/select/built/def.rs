@@ -0,0 +1,99 @@
+use smallvec::SmallVec;
+use unions::IsUnion;
+
+use crate::{
+    Database, Datatype, DatatypeConversionError, FetchError, FieldFilter, FieldKindGroup, OrderBy,
+    OrderDirection, OrderKey,
+};
+
+#[cfg(feature = "serde")]
+use crate::serde_impls::interned_all;
+
+use super::{PerGroup, SelectStmtBuilt, SelectStmtFetchMode};
+
+/// A built select as plain data, without its row types, so that a client can
+/// send it to a service that runs it against its own adapter with
+/// [`Notitia::execute_select_def`](crate::Notitia::execute_select_def) and
+/// sends the rows back for [`SelectStmtBuilt::decode_def_rows`].
+///
+/// Similarity searches are not part of it; resolve them on the client.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectStmtDef {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned_all"))]
+    pub tables: Vec<&'static str>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned_all"))]
+    pub fields: Vec<&'static str>,
+    pub filters: Vec<FieldFilter>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub per_group: Option<PerGroup>,
+}
+
+/// Rows returned for a [`SelectStmtDef`] didn't fit the statement.
+#[derive(Debug, thiserror::Error)]
+pub enum DefRowsError {
+    #[error(transparent)]
+    Decode(#[from] DatatypeConversionError),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+}
+
+impl<Db, FieldUnion, FieldPath, Fields, Mode>
+    SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    pub fn to_def(&self) -> SelectStmtDef {
+        SelectStmtDef {
+            tables: self.tables.to_vec(),
+            fields: self.fields.field_names().to_vec(),
+            filters: self.filters.to_vec(),
+            order_by: self.order_by.to_vec(),
+            limit: self.mode.limit(),
+            per_group: self.per_group.clone(),
+        }
+    }
+
+    /// Build the output from the rows a service returned for
+    /// [`to_def`](Self::to_def). Each row holds the selected fields followed
+    /// by the value of every `order_by` expression.
+    pub fn decode_def_rows(&self, rows: Vec<Vec<Datatype>>) -> Result<Mode::Output, DefRowsError> {
+        let field_names = self.fields.field_names();
+        let reversed: SmallVec<[bool; 1]> = self
+            .order_by
+            .iter()
+            .map(|order| matches!(order.direction, OrderDirection::Desc))
+            .collect();
+
+        let mut typed_rows = Vec::with_capacity(rows.len());
+        let mut order_keys = Vec::with_capacity(rows.len());
+
+        for (row_index, row) in rows.into_iter().enumerate() {
+            let mut values = row.into_iter();
+            let mut selected = values.by_ref().take(field_names.len());
+            let typed = Fields::from_datatypes(&mut selected).map_err(|err| {
+                // The failing value is the last one the group pulled.
+                let consumed = field_names.len() - selected.len();
+                let field = consumed
+                    .checked_sub(1)
+                    .and_then(|idx| field_names.get(idx).copied())
+                    .unwrap_or("?");
+                err.in_column(self.tables.join(", "), field, row_index)
+            })?;
+            typed_rows.push(typed);
+
+            order_keys.push(if self.mode.needs_order_keys() {
+                OrderKey::new(values.collect(), reversed.clone())
+            } else {
+                OrderKey::default()
+            });
+        }
+
+        Ok(self.mode.from_rows(typed_rows, order_keys)?)
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use smallvec::SmallVec;
+use unions::IsUnion;
+
+use crate::{
+    Adapter, ChangeReason, Database, Delivery, FieldKindGroup, MutationEvent, QueryExecutor,
+    SubscribableRow, Subscription, SubscriptionMetadata, SubscriptionReceiver,
+};
+
+use super::{MergingFetchMode, SelectStmtFetchMode};
+
+type Recompute = Arc<OnceLock<Box<dyn Fn(&MutationEvent, &Delivery) -> bool + Send + Sync>>>;
+
+/// A tuple of queries whose outputs `Func` combines, see
+/// [`Notitia::derive`](crate::Notitia::derive).
+pub trait DeriveQueries<Adptr: Adapter, Func> {
+    type Output;
+
+    /// Subscribe to every query, and to the value `derive` computes from
+    /// their outputs.
+    fn subscribe(
+        self,
+        derive: Func,
+    ) -> impl Future<Output = Result<Subscription<Self::Output>, Adptr::Error>>;
+}
+
+/// Ignores changes until every input is subscribed; the initial value is
+/// computed after that.
+fn on_input_change(
+    recompute: &Recompute,
+) -> Box<dyn Fn(&MutationEvent, ChangeReason, &Delivery) -> bool + Send + Sync> {
+    let recompute = recompute.clone();
+    Box::new(move |event: &MutationEvent, _, delivery: &Delivery| {
+        recompute
+            .get()
+            .is_none_or(|recompute| recompute(event, delivery))
+    })
+}
+
+macro_rules! impl_derive_queries {
+    ($(($FU:ident, $FP:ident, $F:ident, $M:ident, $q:ident)),+ $(,)?) => {
+        impl<Db, Adptr, Func, T, $($FU, $FP, $F, $M),+> DeriveQueries<Adptr, Func>
+            for ($(QueryExecutor<Db, Adptr, $FU, $FP, $F, $M>,)+)
+        where
+            Db: Database,
+            Adptr: Adapter,
+            Func: Fn($(&<$M as SelectStmtFetchMode<$F::Type>>::Output),+) -> T
+                + Send
+                + Sync
+                + 'static,
+            T: PartialEq + Send + 'static,
+            $(
+                $FU: IsUnion + Send + Sync,
+                $FP: Send + Sync,
+                $F: FieldKindGroup<$FU, $FP> + Send + Sync,
+                $F::Type: SubscribableRow,
+                $M: MergingFetchMode<$F::Type> + Send + Sync + 'static,
+                $M::Output: Clone + PartialEq + Send + 'static,
+            )+
+        {
+            type Output = T;
+
+            async fn subscribe(self, derive: Func) -> Result<Subscription<T>, Adptr::Error> {
+                let recompute: Recompute = Arc::new(OnceLock::new());
+                let ($($q,)+) = self;
+                let inputs = futures_util::try_join!(
+                    $($q.subscribe_with(|_| on_input_change(&recompute), Arc::default())),+
+                )?;
+                let (sender, receiver) = crossbeam_channel::unbounded();
+                let _ = sender.send((SubscriptionMetadata::None, Delivery::none()));
+                let receiver = SubscriptionReceiver::Blocking(receiver);
+
+                // Holding every input until `recompute` is set keeps events
+                // from changing one after the initial value was computed.
+                let held = inputs.clone();
+                let ($($q,)+) = &held;
+                let ($($q,)+) = ($($q.lock().unwrap(),)+);
+                let output = Arc::new(Mutex::new(derive($(&*$q),+)));
+
+                let derived = output.clone();
+                let _ = recompute.set(Box::new(move |event: &MutationEvent, delivery: &Delivery| {
+                    let ($($q,)+) = &inputs;
+                    let value = derive($(&*$q.lock().unwrap()),+);
+                    let mut data = derived.lock().unwrap();
+                    // An input changing doesn't mean the derived value did.
+                    if *data == value {
+                        return true;
+                    }
+                    *data = value;
+                    drop(data);
+
+                    sender
+                        .send((
+                            SubscriptionMetadata::Changed(event.clone(), ChangeReason::Recomputed),
+                            delivery.clone(),
+                        ))
+                        .is_ok()
+                }));
+                drop(($($q,)+));
+
+                Ok(Subscription::new(output, receiver, SmallVec::new(), Arc::default()))
+            }
+        }
+    };
+}
+
+impl_derive_queries!((U0, P0, F0, M0, q0));
+impl_derive_queries!((U0, P0, F0, M0, q0), (U1, P1, F1, M1, q1));
+impl_derive_queries!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+);
+impl_derive_queries!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+    (U3, P3, F3, M3, q3),
+);
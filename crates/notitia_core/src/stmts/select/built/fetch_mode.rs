@@ -4,12 +4,25 @@ use derivative::Derivative;
 use unions::IsUnion;
 
 use crate::{
-    Adapter, Collection, Database, DatatypeConversionError, FieldKindGroup, MutationEvent,
-    MutationEventKind, Notitia, OrderKey, SelectStmtBuilt, SubscribableRow, SubscriptionDescriptor,
-    merge_event_into_data,
-    subscription::merge::{merge_update_single_row, row_from_insert},
+    Adapter, AggregatingFetchMode, Collection, Database, FieldKindGroup, MergeCounts,
+    MutationEvent, MutationEventKind, Notitia, OrderKey, SelectStmtBuilt, SubscribableRow,
+    SubscriptionDescriptor, merge_event_into_data,
+    subscription::merge::{
+        delete_removes_row, last_row_from_bulk_insert, merge_update_single_row, row_from_insert,
+    },
 };
 
+/// The rows returned for a query did not fit its fetch mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum FetchError {
+    /// `fetch_one` / `fetch_first` matched no rows.
+    #[error("no rows matched the query")]
+    NotFound,
+    /// `fetch_one` matched more than one row.
+    #[error("expected exactly one row, got {0}")]
+    MultipleRows(usize),
+}
+
 pub(crate) trait SelectStmtFetchModeSealed {}
 
 #[allow(private_bounds)] // `SelectStmtFetchModeSealed` is an internal helper.
@@ -23,19 +36,78 @@ pub trait SelectStmtFetchMode<Ty: Send>: SelectStmtFetchModeSealed + Sized {
         &self,
         rows: Vec<Ty>,
         order_keys: Vec<OrderKey>,
-    ) -> Result<Self::Output, DatatypeConversionError>;
+    ) -> Result<Self::Output, FetchError>;
+
+    /// The most rows the output can use, for adapters that apply it in the
+    /// query.
+    fn limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether the adapter should also select how many rows the query
+    /// matches, e.g. with `COUNT(*) OVER ()`, and hand the rows to
+    /// [`from_counted_rows`](Self::from_counted_rows).
+    fn needs_total_count(&self) -> bool {
+        false
+    }
+
+    /// Like [`from_rows`](Self::from_rows), with the number of rows the query
+    /// matched before any limit.
+    fn from_counted_rows(
+        &self,
+        rows: Vec<Ty>,
+        order_keys: Vec<OrderKey>,
+        _total: usize,
+    ) -> Result<Self::Output, FetchError> {
+        self.from_rows(rows, order_keys)
+    }
+
+    /// The fetch mode as an aggregating one, for those that select an
+    /// aggregate instead of the rows.
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        None
+    }
 
-    /// Apply a mutation event to the output data in place.
-    /// Returns `true` if the data was changed.
+    /// Whether a [requerying](crate::QueryExecutor::subscribe_requerying)
+    /// subscription can merge `event` rather than run its query again, for
+    /// outputs that only follow some changes, like an aggregate following
+    /// inserts.
+    fn merges_instead_of_requerying(
+        &self,
+        _descriptor: &SubscriptionDescriptor,
+        _event: &MutationEvent,
+    ) -> bool {
+        false
+    }
+
+    /// Apply a mutation event to the output data in place, returning what
+    /// it changed. The single row of `fetch_one` and `fetch_first`, like
+    /// most aggregates, can only be updated.
     fn merge_event(
         &self,
         output: &mut Self::Output,
         descriptor: &SubscriptionDescriptor,
         event: &MutationEvent,
-    ) -> bool
+    ) -> MergeCounts
     where
         Ty: SubscribableRow;
 
+    /// Whether `event` deletes what the output holds, for outputs that can't
+    /// be emptied in place, like the single row of `fetch_one`. The output is
+    /// left as it was and the subscription is marked
+    /// [removed](crate::Subscription::is_removed).
+    fn removes_output(
+        &self,
+        _output: &Self::Output,
+        _descriptor: &SubscriptionDescriptor,
+        _event: &MutationEvent,
+    ) -> bool
+    where
+        Ty: SubscribableRow,
+    {
+        false
+    }
+
     fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
         &self,
         db: &Notitia<Db, Adptr>,
@@ -49,6 +121,13 @@ pub trait SelectStmtFetchMode<Ty: Send>: SelectStmtFetchModeSealed + Sized {
         Fields: FieldKindGroup<FieldUnion, FieldPath, Type = Ty> + Send + Sync;
 }
 
+/// A fetch mode whose output follows every change by merging it, which
+/// [`subscribe`](crate::QueryExecutor::subscribe) takes. Aggregates and
+/// queries [limited per group](SelectStmtBuilt::limit_per_group) are run
+/// again instead, with
+/// [`subscribe_requerying`](crate::QueryExecutor::subscribe_requerying).
+pub trait MergingFetchMode<Ty: Send>: SelectStmtFetchMode<Ty> {}
+
 #[derive(Debug)]
 pub struct SelectStmtFetchOne {}
 
@@ -59,18 +138,21 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchOne {
         false
     }
 
+    /// A second row is enough to tell that there is more than one.
+    fn limit(&self) -> Option<usize> {
+        Some(2)
+    }
+
     fn from_rows(
         &self,
         rows: Vec<Ty>,
         _order_keys: Vec<OrderKey>,
-    ) -> Result<Self::Output, DatatypeConversionError> {
-        if rows.len() != 1 {
-            return Err(DatatypeConversionError::WrongNumberOfValues {
-                expected: 1,
-                got: rows.len(),
-            });
+    ) -> Result<Self::Output, FetchError> {
+        match rows.len() {
+            0 => Err(FetchError::NotFound),
+            1 => Ok(rows.into_iter().next().unwrap()),
+            n => Err(FetchError::MultipleRows(n)),
         }
-        Ok(rows.into_iter().next().unwrap())
     }
 
     fn merge_event(
@@ -78,29 +160,23 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchOne {
         output: &mut Ty,
         descriptor: &SubscriptionDescriptor,
         event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        Ty: SubscribableRow,
+    {
+        merge_single_row(output, descriptor, event)
+    }
+
+    fn removes_output(
+        &self,
+        output: &Ty,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
     ) -> bool
     where
         Ty: SubscribableRow,
     {
-        match &event.kind {
-            MutationEventKind::Insert { values } => {
-                if let Some(row) = row_from_insert::<Ty>(descriptor, values) {
-                    if *output != row {
-                        *output = row;
-                        return true;
-                    }
-                }
-                false
-            }
-            MutationEventKind::Update {
-                changed,
-                filters: mutation_filters,
-            } => merge_update_single_row(output, descriptor, changed, mutation_filters),
-            MutationEventKind::Delete { .. } => {
-                // Cannot remove a single-row output; no-op.
-                false
-            }
-        }
+        delete_removes_row(output, descriptor, event)
     }
 
     async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
@@ -121,6 +197,46 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchOne {
 
 impl SelectStmtFetchModeSealed for SelectStmtFetchOne {}
 
+/// Merge `event` into the single row of `fetch_one` or `fetch_first`.
+fn merge_single_row<Ty: SubscribableRow>(
+    output: &mut Ty,
+    descriptor: &SubscriptionDescriptor,
+    event: &MutationEvent,
+) -> MergeCounts {
+    let updated = match &event.kind {
+        MutationEventKind::Insert { values } => {
+            replace_row(output, row_from_insert::<Ty>(descriptor, values))
+        }
+        MutationEventKind::Update {
+            changed,
+            filters: mutation_filters,
+            ..
+        } => merge_update_single_row(output, descriptor, changed, mutation_filters),
+        // See `removes_output`.
+        MutationEventKind::Delete { .. } => false,
+        MutationEventKind::BulkInsert { rows } => {
+            replace_row(output, last_row_from_bulk_insert::<Ty>(descriptor, rows))
+        }
+    };
+    MergeCounts {
+        updated: updated as usize,
+        ..MergeCounts::default()
+    }
+}
+
+/// Replace `output` with a different `row`, returning whether it did.
+fn replace_row<Ty: SubscribableRow>(output: &mut Ty, row: Option<Ty>) -> bool {
+    match row {
+        Some(row) if *output != row => {
+            *output = row;
+            true
+        }
+        _ => false,
+    }
+}
+
+impl<Ty: Send> MergingFetchMode<Ty> for SelectStmtFetchOne {}
+
 #[derive(Debug)]
 pub struct SelectStmtFetchFirst {}
 
@@ -131,17 +247,16 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchFirst {
         false
     }
 
+    fn limit(&self) -> Option<usize> {
+        Some(1)
+    }
+
     fn from_rows(
         &self,
         rows: Vec<Ty>,
         _order_keys: Vec<OrderKey>,
-    ) -> Result<Self::Output, DatatypeConversionError> {
-        rows.into_iter()
-            .next()
-            .ok_or(DatatypeConversionError::WrongNumberOfValues {
-                expected: 1,
-                got: 0,
-            })
+    ) -> Result<Self::Output, FetchError> {
+        rows.into_iter().next().ok_or(FetchError::NotFound)
     }
 
     fn merge_event(
@@ -149,29 +264,23 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchFirst {
         output: &mut Ty,
         descriptor: &SubscriptionDescriptor,
         event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        Ty: SubscribableRow,
+    {
+        merge_single_row(output, descriptor, event)
+    }
+
+    fn removes_output(
+        &self,
+        output: &Ty,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
     ) -> bool
     where
         Ty: SubscribableRow,
     {
-        match &event.kind {
-            MutationEventKind::Insert { values } => {
-                if let Some(row) = row_from_insert::<Ty>(descriptor, values) {
-                    if *output != row {
-                        *output = row;
-                        return true;
-                    }
-                }
-                false
-            }
-            MutationEventKind::Update {
-                changed,
-                filters: mutation_filters,
-            } => merge_update_single_row(output, descriptor, changed, mutation_filters),
-            MutationEventKind::Delete { .. } => {
-                // Cannot remove a single-row output; no-op.
-                false
-            }
-        }
+        delete_removes_row(output, descriptor, event)
     }
 
     async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
@@ -192,6 +301,8 @@ impl<Ty: Send> SelectStmtFetchMode<Ty> for SelectStmtFetchFirst {
 
 impl SelectStmtFetchModeSealed for SelectStmtFetchFirst {}
 
+impl<Ty: Send> MergingFetchMode<Ty> for SelectStmtFetchFirst {}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct SelectStmtFetchAll<FetchAs: Collection> {
@@ -223,7 +334,7 @@ where
         &self,
         rows: Vec<T>,
         order_keys: Vec<OrderKey>,
-    ) -> Result<Self::Output, DatatypeConversionError> {
+    ) -> Result<Self::Output, FetchError> {
         Ok(FetchAs::from_vec(rows, order_keys))
     }
 
@@ -232,13 +343,11 @@ where
         output: &mut FetchAs,
         descriptor: &SubscriptionDescriptor,
         event: &MutationEvent,
-    ) -> bool
+    ) -> MergeCounts
     where
         T: SubscribableRow,
     {
-        let old = output.clone();
-        merge_event_into_data(output, descriptor, event);
-        *output != old
+        merge_event_into_data(output, descriptor, event)
     }
 
     async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
@@ -259,6 +368,13 @@ where
 
 impl<FetchAs: Collection> SelectStmtFetchModeSealed for SelectStmtFetchAll<FetchAs> {}
 
+impl<T, FetchAs> MergingFetchMode<T> for SelectStmtFetchAll<FetchAs>
+where
+    T: Send,
+    FetchAs: Collection<Item = T> + Send + Sync,
+{
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct SelectStmtFetchMany<FetchAs: Collection> {
@@ -288,28 +404,33 @@ where
         true
     }
 
+    fn limit(&self) -> Option<usize> {
+        Some(self.max)
+    }
+
     fn from_rows(
         &self,
         rows: Vec<T>,
         order_keys: Vec<OrderKey>,
-    ) -> Result<Self::Output, DatatypeConversionError> {
+    ) -> Result<Self::Output, FetchError> {
         let truncated_keys: Vec<_> = order_keys.into_iter().take(self.max).collect();
         let truncated: Vec<_> = rows.into_iter().take(self.max).collect();
         Ok(FetchAs::from_vec(truncated, truncated_keys))
     }
 
+    /// Rows merged past `max` are dropped.
     fn merge_event(
         &self,
         output: &mut FetchAs,
         descriptor: &SubscriptionDescriptor,
         event: &MutationEvent,
-    ) -> bool
+    ) -> MergeCounts
     where
         T: SubscribableRow,
     {
-        let old = output.clone();
-        merge_event_into_data(output, descriptor, event);
-        *output != old
+        let mut counts = merge_event_into_data(output, descriptor, event);
+        truncate(output, self.max, descriptor, event, &mut counts);
+        counts
     }
 
     async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
@@ -329,3 +450,237 @@ where
 }
 
 impl<FetchAs: Collection> SelectStmtFetchModeSealed for SelectStmtFetchMany<FetchAs> {}
+
+impl<T, FetchAs> MergingFetchMode<T> for SelectStmtFetchMany<FetchAs>
+where
+    T: Send,
+    FetchAs: Collection<Item = T> + Send + Sync,
+{
+}
+
+/// Drop the rows of `output` past the first `max` after merging `event`.
+/// Rows the event had just added don't count as added, and the others as
+/// removed.
+fn truncate<FetchAs: Collection>(
+    output: &mut FetchAs,
+    max: usize,
+    descriptor: &SubscriptionDescriptor,
+    event: &MutationEvent,
+    counts: &mut MergeCounts,
+) {
+    let len = output.iter().count();
+    if len <= max {
+        return;
+    }
+    let added: Vec<FetchAs::Item> = match &event.kind {
+        MutationEventKind::Insert { values } => std::slice::from_ref(values),
+        MutationEventKind::BulkInsert { rows } | MutationEventKind::Update { rows, .. } => {
+            rows.as_slice()
+        }
+        MutationEventKind::Delete { .. } => &[],
+    }
+    .iter()
+    .filter_map(|values| row_from_insert(descriptor, values))
+    .collect();
+    let dropped_added = output
+        .iter()
+        .skip(max)
+        .filter(|row| added.contains(row))
+        .count()
+        .min(counts.inserted);
+    counts.inserted -= dropped_added;
+    counts.removed += len - max - dropped_added;
+    output.remove_positions(&(max..len).collect::<Vec<_>>());
+}
+
+/// Rows fetched [`with_total_count`](SelectStmtBuilt::with_total_count).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counted<T> {
+    pub rows: T,
+    /// How many rows the query matches, including those past the
+    /// `fetch_many` limit.
+    pub total: usize,
+}
+
+#[derive(Debug)]
+pub struct SelectStmtFetchCounted<Mode> {
+    mode: Mode,
+}
+
+impl<Mode> SelectStmtFetchCounted<Mode> {
+    pub(crate) fn new(mode: Mode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<T, FetchAs, Mode> SelectStmtFetchMode<T> for SelectStmtFetchCounted<Mode>
+where
+    T: Send,
+    FetchAs: Collection<Item = T> + Send + Sync,
+    Mode: SelectStmtFetchMode<T, Output = FetchAs> + Sync,
+{
+    type Output = Counted<FetchAs>;
+
+    fn needs_order_keys(&self) -> bool {
+        self.mode.needs_order_keys()
+    }
+
+    fn limit(&self) -> Option<usize> {
+        self.mode.limit()
+    }
+
+    fn needs_total_count(&self) -> bool {
+        true
+    }
+
+    /// Adapters that can't count separately return every matching row.
+    fn from_rows(
+        &self,
+        rows: Vec<T>,
+        order_keys: Vec<OrderKey>,
+    ) -> Result<Self::Output, FetchError> {
+        let total = rows.len();
+        self.from_counted_rows(rows, order_keys, total)
+    }
+
+    fn from_counted_rows(
+        &self,
+        rows: Vec<T>,
+        order_keys: Vec<OrderKey>,
+        total: usize,
+    ) -> Result<Self::Output, FetchError> {
+        Ok(Counted {
+            rows: self.mode.from_rows(rows, order_keys)?,
+            total,
+        })
+    }
+
+    /// The total follows the rows the merge adds and removes, including those
+    /// it then drops past the `fetch_many` limit. Deleting rows past the
+    /// limit, which the output doesn't hold, leaves it unchanged.
+    fn merge_event(
+        &self,
+        output: &mut Counted<FetchAs>,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        T: SubscribableRow,
+    {
+        let mut counts = merge_event_into_data(&mut output.rows, descriptor, event);
+        output.total = (output.total + counts.inserted).saturating_sub(counts.removed);
+        if let Some(max) = self.mode.limit() {
+            truncate(&mut output.rows, max, descriptor, event, &mut counts);
+        }
+        counts
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<Counted<FetchAs>, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = T> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl<Mode> SelectStmtFetchModeSealed for SelectStmtFetchCounted<Mode> {}
+
+impl<T, FetchAs, Mode> MergingFetchMode<T> for SelectStmtFetchCounted<Mode>
+where
+    T: Send,
+    FetchAs: Collection<Item = T> + Send + Sync,
+    Mode: MergingFetchMode<T, Output = FetchAs> + Sync,
+{
+}
+
+/// The fetch mode of a statement
+/// [limited per group](SelectStmtBuilt::limit_per_group). Which rows are
+/// kept changes with rows outside of the output, so it can't merge changes.
+#[derive(Debug)]
+pub struct SelectStmtFetchPerGroup<Mode> {
+    mode: Mode,
+}
+
+impl<Mode> SelectStmtFetchPerGroup<Mode> {
+    pub(crate) fn new(mode: Mode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<Ty, Mode> SelectStmtFetchMode<Ty> for SelectStmtFetchPerGroup<Mode>
+where
+    Ty: Send,
+    Mode: SelectStmtFetchMode<Ty> + Sync,
+{
+    type Output = Mode::Output;
+
+    fn needs_order_keys(&self) -> bool {
+        self.mode.needs_order_keys()
+    }
+
+    fn from_rows(
+        &self,
+        rows: Vec<Ty>,
+        order_keys: Vec<OrderKey>,
+    ) -> Result<Self::Output, FetchError> {
+        self.mode.from_rows(rows, order_keys)
+    }
+
+    fn limit(&self) -> Option<usize> {
+        self.mode.limit()
+    }
+
+    fn needs_total_count(&self) -> bool {
+        self.mode.needs_total_count()
+    }
+
+    fn from_counted_rows(
+        &self,
+        rows: Vec<Ty>,
+        order_keys: Vec<OrderKey>,
+        total: usize,
+    ) -> Result<Self::Output, FetchError> {
+        self.mode.from_counted_rows(rows, order_keys, total)
+    }
+
+    fn aggregating(&self) -> Option<&dyn AggregatingFetchMode<Self::Output>> {
+        self.mode.aggregating()
+    }
+
+    fn merge_event(
+        &self,
+        output: &mut Self::Output,
+        descriptor: &SubscriptionDescriptor,
+        event: &MutationEvent,
+    ) -> MergeCounts
+    where
+        Ty: SubscribableRow,
+    {
+        self.mode.merge_event(output, descriptor, event)
+    }
+
+    async fn execute<Db, Adptr, FieldUnion, FieldPath, Fields>(
+        &self,
+        db: &Notitia<Db, Adptr>,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Self>,
+    ) -> Result<Self::Output, Adptr::Error>
+    where
+        Db: Database,
+        Adptr: Adapter,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath, Type = Ty> + Send + Sync,
+    {
+        db.execute_select_stmt(stmt).await
+    }
+}
+
+impl<Mode> SelectStmtFetchModeSealed for SelectStmtFetchPerGroup<Mode> {}
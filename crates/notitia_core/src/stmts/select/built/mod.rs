@@ -7,13 +7,37 @@ pub use buildable::*;
 mod query_executor;
 pub use query_executor::*;
 
+mod query_all;
+pub use query_all::*;
+
+mod derive;
+pub use derive::*;
+
+mod def;
+pub use def::*;
+
+mod decode;
+pub use decode::*;
+
+mod requery;
+pub use requery::*;
+
+mod budget;
+pub use budget::*;
+
+mod aggregate;
+pub use aggregate::*;
+
 use std::marker::PhantomData;
 
 use derivative::Derivative;
 use smallvec::SmallVec;
-use unions::IsUnion;
+use unions::{IntoUnion, IsUnion, UnionPath};
 
-use crate::{Adapter, Database, FieldFilter, FieldKindGroup, Notitia, OrderBy};
+use crate::{
+    Adapter, Database, FieldFilter, FieldKindGroup, FieldKindOfDatabase, InnerFieldType, Notitia,
+    OrderBy, SelectStmtFetchCounted, SelectStmtFetchPerGroup, StrongFieldKind, TableFieldPair,
+};
 
 #[cfg(feature = "embeddings")]
 use crate::SimilaritySearch;
@@ -32,6 +56,8 @@ where
     pub filters: SmallVec<[FieldFilter; 1]>,
     pub order_by: SmallVec<[OrderBy; 1]>,
     pub mode: Mode,
+    pub index_hint: Option<IndexHint>,
+    pub per_group: Option<PerGroup>,
     #[cfg(feature = "embeddings")]
     pub similarity_search: Option<SimilaritySearch>,
     #[cfg(feature = "embeddings")]
@@ -47,6 +73,25 @@ where
     _union: PhantomData<FieldUnion>,
 }
 
+/// Overrides the index the query planner picks for the first selected
+/// table. Adapters without hints ignore it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexHint {
+    /// Use the named index (SQLite `INDEXED BY`), failing if it can't.
+    IndexedBy(&'static str),
+    /// Scan the table without any index (SQLite `NOT INDEXED`).
+    NotIndexed,
+}
+
+/// Keeps the first `limit` rows of every value of `field`, in the order of
+/// the statement, see [`SelectStmtBuilt::limit_per_group`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerGroup {
+    pub field: TableFieldPair,
+    pub limit: usize,
+}
+
 impl<Db, FieldUnion, FieldPath, Fields, Mode>
     SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>
 where
@@ -67,6 +112,8 @@ where
             filters,
             order_by: SmallVec::new(),
             mode,
+            index_hint: None,
+            per_group: None,
             #[cfg(feature = "embeddings")]
             similarity_search: None,
             #[cfg(feature = "embeddings")]
@@ -90,6 +137,8 @@ where
             filters,
             order_by,
             mode,
+            index_hint: None,
+            per_group: None,
             #[cfg(feature = "embeddings")]
             similarity_search: None,
             #[cfg(feature = "embeddings")]
@@ -114,6 +163,8 @@ where
             filters,
             order_by: SmallVec::new(),
             mode,
+            index_hint: None,
+            per_group: None,
             similarity_search: Some(search),
             similarity_pk_order: None,
             _database: PhantomData,
@@ -122,6 +173,94 @@ where
         }
     }
 
+    /// Also fetch how many rows the query matches in total, in the same
+    /// round-trip, e.g. to show "page 2 of 7" next to a `fetch_many` page:
+    ///
+    /// ```ignore
+    /// let page = db
+    ///     .query(MyDb::USERS.select(User::NAME).fetch_many::<Vec<_>>(20).with_total_count())
+    ///     .execute()
+    ///     .await?;
+    /// println!("showing {} of {}", page.rows.len(), page.total);
+    /// ```
+    pub fn with_total_count(
+        self,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchCounted<Mode>>
+    where
+        SelectStmtFetchCounted<Mode>: SelectStmtFetchMode<Fields::Type>,
+    {
+        self.map_mode(SelectStmtFetchCounted::new)
+    }
+
+    /// The statement with the fetch mode `wrap` makes of its own.
+    fn map_mode<Wrapped: SelectStmtFetchMode<Fields::Type>>(
+        self,
+        wrap: impl FnOnce(Mode) -> Wrapped,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Wrapped> {
+        SelectStmtBuilt {
+            tables: self.tables,
+            fields: self.fields,
+            filters: self.filters,
+            order_by: self.order_by,
+            mode: wrap(self.mode),
+            index_hint: self.index_hint,
+            per_group: self.per_group,
+            #[cfg(feature = "embeddings")]
+            similarity_search: self.similarity_search,
+            #[cfg(feature = "embeddings")]
+            similarity_pk_order: self.similarity_pk_order,
+            _database: PhantomData,
+            _path: PhantomData,
+            _union: PhantomData,
+        }
+    }
+
+    /// Make the planner use `index`, for queries it otherwise runs with a
+    /// bad plan, e.g. on large message tables.
+    pub fn indexed_by(mut self, index: &'static str) -> Self {
+        self.index_hint = Some(IndexHint::IndexedBy(index));
+        self
+    }
+
+    /// Make the planner scan the table instead of using an index.
+    pub fn not_indexed(mut self) -> Self {
+        self.index_hint = Some(IndexHint::NotIndexed);
+        self
+    }
+
+    /// Keep the first `limit` rows of every value of `field`, by the order
+    /// of the statement, e.g. the latest message of every conversation:
+    ///
+    /// ```ignore
+    /// MyDb::MESSAGES
+    ///     .select((Message::CONVERSATION_ID, Message::BODY))
+    ///     .order_by(Message::SENT_AT, OrderDirection::Desc)
+    ///     .fetch_vec()
+    ///     .limit_per_group(Message::CONVERSATION_ID, 1)
+    /// ```
+    ///
+    /// Which rows are kept can change with rows outside of the output, so
+    /// the statement can only be subscribed to with
+    /// [`subscribe_requerying`](crate::QueryExecutor::subscribe_requerying).
+    pub fn limit_per_group<
+        InnerFieldPath: UnionPath,
+        InnerField: FieldKindOfDatabase<Db> + IntoUnion<FieldUnion, InnerFieldPath>,
+        T: InnerFieldType,
+    >(
+        mut self,
+        field: StrongFieldKind<InnerField, T>,
+        limit: usize,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchPerGroup<Mode>>
+    where
+        SelectStmtFetchPerGroup<Mode>: SelectStmtFetchMode<Fields::Type>,
+    {
+        self.per_group = Some(PerGroup {
+            field: field.table_field_pair::<Db>(),
+            limit,
+        });
+        self.map_mode(SelectStmtFetchPerGroup::new)
+    }
+
     pub fn sql(schema_builder: impl sea_query::SchemaBuilder) -> String {
         sea_query::Query::select().to_string(schema_builder)
     }
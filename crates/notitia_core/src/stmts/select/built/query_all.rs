@@ -0,0 +1,65 @@
+use unions::IsUnion;
+
+use crate::{Adapter, Database, FieldKindGroup, QueryExecutor, SelectStmtFetchMode};
+
+/// A tuple of independent queries that can be run together with
+/// [`Notitia::query_all`](crate::Notitia::query_all).
+pub trait QueryAll<Adptr: Adapter> {
+    type Output;
+
+    /// Run every query concurrently. Resolves to the first error if any
+    /// query fails.
+    fn execute(self) -> impl Future<Output = Result<Self::Output, Adptr::Error>>;
+}
+
+macro_rules! impl_query_all {
+    ($(($FU:ident, $FP:ident, $F:ident, $M:ident, $q:ident)),+ $(,)?) => {
+        impl<Db, Adptr, $($FU, $FP, $F, $M),+> QueryAll<Adptr>
+            for ($(QueryExecutor<Db, Adptr, $FU, $FP, $F, $M>,)+)
+        where
+            Db: Database,
+            Adptr: Adapter,
+            $(
+                $FU: IsUnion + Send + Sync,
+                $FP: Send + Sync,
+                $F: FieldKindGroup<$FU, $FP> + Send + Sync,
+                $M: SelectStmtFetchMode<$F::Type> + Sync,
+            )+
+        {
+            type Output = ($(<$M as SelectStmtFetchMode<$F::Type>>::Output,)+);
+
+            async fn execute(self) -> Result<Self::Output, Adptr::Error> {
+                let ($($q,)+) = self;
+                futures_util::try_join!($($q.execute()),+)
+            }
+        }
+    };
+}
+
+impl_query_all!((U0, P0, F0, M0, q0), (U1, P1, F1, M1, q1));
+impl_query_all!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+);
+impl_query_all!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+    (U3, P3, F3, M3, q3),
+);
+impl_query_all!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+    (U3, P3, F3, M3, q3),
+    (U4, P4, F4, M4, q4),
+);
+impl_query_all!(
+    (U0, P0, F0, M0, q0),
+    (U1, P1, F1, M1, q1),
+    (U2, P2, F2, M2, q2),
+    (U3, P3, F3, M3, q3),
+    (U4, P4, F4, M4, q4),
+    (U5, P5, F5, M5, q5),
+);
@@ -1,14 +1,18 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
 
 use tracing::error;
 use unions::IsUnion;
 
 use crate::{
-    Adapter, Database, FieldKindGroup, MutationEvent, Notitia, SubscribableRow, Subscription,
-    SubscriptionDescriptor, SubscriptionMetadata, subscription::overlap::event_matches_descriptor,
+    Adapter, ChangeReason, Database, Delivery, DeliveryStrategy, FieldKindGroup, MergeCounts,
+    MergePool, MutationEvent, MutationEventKind, Notitia, SubscribableRow, Subscription,
+    SubscriptionDescriptor, split_table_entry,
 };
 
-use super::{SelectStmtBuilt, SelectStmtFetchMode};
+use super::{MergingFetchMode, SelectStmtBuilt, SelectStmtFetchMode};
 
 pub struct QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
 where
@@ -20,6 +24,7 @@ where
 {
     pub(crate) db: Notitia<Db, Adptr>,
     pub(crate) stmt: SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    pub(crate) merge_pool: Option<MergePool>,
 }
 
 impl<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
@@ -45,6 +50,16 @@ where
         result
     }
 
+    /// The statement, with any similarity search resolved into filters.
+    pub(crate) fn into_stmt(
+        #[allow(unused_mut)] mut self,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode> {
+        #[cfg(feature = "embeddings")]
+        self.resolve_similarity_search();
+
+        self.stmt
+    }
+
     #[cfg(feature = "embeddings")]
     fn resolve_similarity_search(&mut self) {
         use crate::{Datatype, Embedding, FieldFilter, FieldFilterInMetadata, TableFieldPair};
@@ -107,6 +122,13 @@ where
         self.stmt.similarity_pk_order = Some(results.iter().map(|r| r.pk.clone()).collect());
     }
 
+    /// Merge the changes of a subscription to the query on `pool`, rather
+    /// than on the task of the mutation that made them.
+    pub fn merge_on(mut self, pool: &MergePool) -> Self {
+        self.merge_pool = Some(pool.clone());
+        self
+    }
+
     /// Extract the subscription descriptor for this query.
     /// Used by `notitia_gpui` to compare queries and detect changes.
     pub fn descriptor(&self) -> SubscriptionDescriptor {
@@ -114,17 +136,37 @@ where
             tables: self.stmt.tables.clone(),
             field_names: self.stmt.fields.field_names(),
             filters: self.stmt.filters.clone(),
-            order_by_field_names: self.stmt.order_by.iter().map(|o| o.field).collect(),
-            order_by_directions: self
-                .stmt
-                .order_by
-                .iter()
-                .map(|o| o.direction.clone())
-                .collect(),
+            order_by: self.stmt.order_by.clone(),
+            primary_key: selected_primary_key(
+                self.db.database(),
+                &self.stmt.tables,
+                &self.stmt.fields.field_names(),
+            ),
         }
     }
 }
 
+/// The primary key of the only table in `tables`, if it is one column and
+/// among `field_names`.
+fn selected_primary_key<Db: Database>(
+    database: &Db,
+    tables: &[&'static str],
+    field_names: &[&'static str],
+) -> Option<&'static str> {
+    let [entry] = tables else {
+        return None;
+    };
+    let table = split_table_entry(*entry).0;
+    let (_, fields) = database.tables().find(|(name, _)| *name == table)?;
+    let mut primary_key = fields
+        .iter()
+        .filter(|(_, kind)| kind.metadata().primary_key);
+    match (primary_key.next(), primary_key.next()) {
+        (Some((column, _)), None) if field_names.contains(column) => Some(*column),
+        _ => None,
+    }
+}
+
 impl<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
     QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
 where
@@ -134,66 +176,136 @@ where
     FieldPath: Send + Sync,
     Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
     Fields::Type: SubscribableRow,
-    Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
     Mode::Output: Clone + PartialEq + Send + 'static,
 {
+    /// Run the query and keep its output up to date by merging the changes
+    /// to it. Aggregates and queries
+    /// [limited per group](SelectStmtBuilt::limit_per_group) can't merge
+    /// every change, so they only have
+    /// [`subscribe_requerying`](Self::subscribe_requerying).
     pub async fn subscribe(self) -> Result<Subscription<Mode::Output>, Adptr::Error> {
+        self.subscribe_via(DeliveryStrategy::Blocking).await
+    }
+
+    /// Like [`subscribe`](Self::subscribe), handing changes to the consumer
+    /// as `strategy` says, e.g. through an async channel on an executor
+    /// rather than a thread blocked on [`Subscription::recv`]:
+    ///
+    /// ```ignore
+    /// let sub = query.subscribe_via(DeliveryStrategy::Async).await?;
+    /// while sub.recv_async().await.is_ok() {
+    ///     render(&sub.data());
+    /// }
+    /// ```
+    pub async fn subscribe_via(
+        self,
+        strategy: DeliveryStrategy<Mode::Output>,
+    ) -> Result<Subscription<Mode::Output>, Adptr::Error> {
+        let (sender, receiver) = strategy.channel();
+        let field_names = self.stmt.fields.field_names();
+        let removed = Arc::<AtomicBool>::default();
+
+        let output = self
+            .subscribe_with(|output| sender.on_change(output), removed.clone())
+            .await?;
+        Ok(Subscription::new(output, receiver, field_names, removed))
+    }
+
+    /// Run the query and keep its output up to date, calling the callback
+    /// `on_change` builds for the output after every batch of events that
+    /// changed it, with the last of them and how the output changed.
+    /// Like a registry callback, it returns `false` once nothing listens
+    /// anymore. `removed` is set while the output holds a row that was
+    /// deleted, see [`Subscription::is_removed`].
+    pub(crate) async fn subscribe_with(
+        self,
+        on_change: impl FnOnce(
+            &Arc<Mutex<Mode::Output>>,
+        ) -> Box<
+            dyn Fn(&MutationEvent, ChangeReason, &Delivery) -> bool + Send + Sync,
+        > + Send,
+        removed: Arc<AtomicBool>,
+    ) -> Result<Arc<Mutex<Mode::Output>>, Adptr::Error> {
         // 1. Execute the query using the mode's own execute method to get initial data.
         let initial_output = self.stmt.execute(&self.db).await?;
 
         // 2. Build subscription descriptor from the statement.
-        let descriptor = SubscriptionDescriptor {
-            tables: self.stmt.tables.clone(),
-            field_names: self.stmt.fields.field_names(),
-            filters: self.stmt.filters.clone(),
-            order_by_field_names: self.stmt.order_by.iter().map(|o| o.field).collect(),
-            order_by_directions: self
-                .stmt
-                .order_by
-                .iter()
-                .map(|o| o.direction.clone())
-                .collect(),
-        };
+        let descriptor = self.descriptor();
 
-        // 3. Create crossbeam channel.
-        let (sender, receiver) = crossbeam_channel::unbounded();
-
-        // 4. Store the mode's output in Arc<Mutex<_>> for the Subscription to read.
+        // 3. Store the mode's output in Arc<Mutex<_>> for the Subscription to read.
         let output = Arc::new(Mutex::new(initial_output));
+        let on_change = on_change(&output);
 
-        // 5. Send initial notification.
-        let _ = sender.send(SubscriptionMetadata::None);
-
-        // 6. Build the type-erased notify closure.
-        //    Uses mode.merge_event() to apply changes directly to the output.
-        let notify: Box<dyn Fn(&MutationEvent) -> bool + Send + Sync> = {
+        // 4. Build the type-erased notify closure.
+        //    Uses mode.merge_event() to apply changes directly to the output,
+        //    notifying once for a batch, with the last event that changed it.
+        let notify: Box<dyn Fn(&[&MutationEvent], &Delivery) -> bool + Send + Sync> = {
             let output = output.clone();
             let descriptor = descriptor.clone();
             let mode = self.stmt.mode;
-            Box::new(move |event: &MutationEvent| {
-                if !event_matches_descriptor(event, &descriptor) {
-                    return true; // still alive, just not relevant
-                }
-
+            Box::new(move |events: &[&MutationEvent], delivery: &Delivery| {
                 let mut data = output.lock().unwrap();
-                let changed = mode.merge_event(&mut *data, &descriptor, event);
-
-                if !changed {
-                    return true;
+                let was_removed = removed.load(Ordering::Acquire);
+                let mut counts = MergeCounts::default();
+                let mut last_change = None;
+                // The registry only passes the events that match the descriptor.
+                for event in events {
+                    let changed = if mode.removes_output(&data, &descriptor, event) {
+                        !removed.swap(true, Ordering::AcqRel)
+                    } else if removed.load(Ordering::Acquire) {
+                        // Only a new row can replace a deleted one.
+                        let inserted = matches!(
+                            event.kind,
+                            MutationEventKind::Insert { .. } | MutationEventKind::BulkInsert { .. }
+                        );
+                        let merged = if inserted {
+                            mode.merge_event(&mut *data, &descriptor, event)
+                        } else {
+                            MergeCounts::default()
+                        };
+                        counts += merged;
+                        let replaced = !merged.is_empty();
+                        if replaced {
+                            removed.store(false, Ordering::Release);
+                        }
+                        replaced
+                    } else {
+                        let merged = mode.merge_event(&mut *data, &descriptor, event);
+                        counts += merged;
+                        !merged.is_empty()
+                    };
+                    if changed {
+                        last_change = Some(*event);
+                    }
                 }
 
+                let reason = match (was_removed, removed.load(Ordering::Acquire)) {
+                    (false, true) => Some(ChangeReason::RowsRemoved(1)),
+                    (true, false) => Some(ChangeReason::RowsInserted(1)),
+                    _ => counts.reason(),
+                };
+                let (Some(event), Some(reason)) = (last_change, reason) else {
+                    return true; // still alive, just not changed
+                };
+
                 drop(data);
 
-                sender
-                    .send(SubscriptionMetadata::Changed(event.clone()))
-                    .is_ok()
+                on_change(event, reason, delivery)
             })
         };
 
-        // 7. Register on the Notitia instance.
-        self.db.inner.subscriptions.register(descriptor, notify);
+        // 5. Register on the Notitia instance, queueing the events for the
+        //    merge pool if there is one.
+        let notify = match &self.merge_pool {
+            Some(pool) => pool.offload(notify),
+            None => notify,
+        };
+        self.db
+            .inner
+            .subscriptions
+            .register_batched(descriptor, notify);
 
-        // 8. Return the subscription handle.
-        Ok(Subscription::new(output, receiver))
+        Ok(output)
     }
 }
@@ -0,0 +1,203 @@
+use std::{
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::future::BoxFuture;
+use tracing::warn;
+use unions::IsUnion;
+
+use crate::{
+    Adapter, ChangeReason, Database, Delivery, FieldKindGroup, MergeCounts, MutationEvent,
+    SubscribableRow, Subscription, SubscriptionMetadata, SubscriptionReceiver,
+    subscription::overlap::event_matches_descriptor,
+};
+
+use super::{QueryExecutor, SelectStmtFetchMode};
+
+impl<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+    QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database + 'static,
+    Adptr: Adapter + 'static,
+    FieldUnion: IsUnion + Send + Sync + 'static,
+    FieldPath: Send + Sync + 'static,
+    Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+    Fields::Type: SubscribableRow,
+    Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode::Output: Clone + PartialEq + Send + 'static,
+{
+    /// Like [`subscribe`](Self::subscribe), but runs the query again after
+    /// the changes that may affect it instead of merging them into its
+    /// output, for queries whose output can't be derived change by change,
+    /// like those [limited per group](super::SelectStmtBuilt::limit_per_group)
+    /// and aggregates. Aggregates still merge the inserts they can follow,
+    /// e.g. a count adds the inserted rows that match.
+    ///
+    /// The queries run in the returned future, which the application spawns.
+    /// Changes that arrive while one runs are taken together by the next.
+    /// It ends with the first change after the subscription was dropped.
+    ///
+    /// ```ignore
+    /// let (latest, requery) = db.query(stmt).subscribe_requerying().await?;
+    /// tokio::spawn(requery);
+    /// ```
+    pub async fn subscribe_requerying(
+        self,
+    ) -> Result<(Subscription<Mode::Output>, BoxFuture<'static, ()>), Adptr::Error> {
+        let db = self.db.clone();
+        let mut descriptor = self.descriptor();
+        let stmt = self.into_stmt();
+
+        // Which rows are kept changes with their group.
+        if let Some(per_group) = &stmt.per_group {
+            if !descriptor.field_names.contains(&per_group.field.field_name) {
+                descriptor.field_names.push(per_group.field.field_name);
+            }
+        }
+
+        let data = Arc::new(Mutex::new(stmt.execute(&db).await?));
+        let changes = Arc::new(Mutex::new(Changes::default()));
+
+        let notify: Box<dyn Fn(&MutationEvent, &Delivery) -> bool + Send + Sync> = {
+            let changes = changes.clone();
+            let data = Arc::downgrade(&data);
+            let descriptor = descriptor.clone();
+            Box::new(move |event: &MutationEvent, delivery: &Delivery| {
+                let mut changes = changes.lock().unwrap();
+                let alive = data.strong_count() > 0;
+                if !alive {
+                    changes.closed = true;
+                } else if event_matches_descriptor(event, &descriptor) {
+                    changes.pending.push((event.clone(), delivery.clone()));
+                    changes.overlapped |= changes.querying;
+                } else {
+                    return true;
+                }
+
+                if let Some(waker) = changes.waker.take() {
+                    waker.wake();
+                }
+                alive
+            })
+        };
+        db.inner.subscriptions.register(descriptor.clone(), notify);
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let _ = sender.send((SubscriptionMetadata::None, Delivery::none()));
+        let subscription = Subscription::new(
+            data.clone(),
+            SubscriptionReceiver::Blocking(receiver),
+            stmt.fields.field_names(),
+            Arc::default(),
+        );
+        let data = Arc::downgrade(&data);
+
+        let requery = async move {
+            while let Some((pending, overlapped)) = NextChanges(&changes).await {
+                // Changes that came in while a query ran may be in its output
+                // already.
+                let merges = !overlapped
+                    && stmt.per_group.is_none()
+                    && pending.iter().all(|(event, _)| {
+                        stmt.mode.merges_instead_of_requerying(&descriptor, event)
+                    });
+                if merges {
+                    let Some(shared) = data.upgrade() else {
+                        return;
+                    };
+                    let mut current = shared.lock().unwrap();
+                    let mut counts = MergeCounts::default();
+                    let merged: Vec<_> = pending
+                        .into_iter()
+                        .filter(|(event, _)| {
+                            let merged = stmt.mode.merge_event(&mut *current, &descriptor, event);
+                            counts += merged;
+                            !merged.is_empty()
+                        })
+                        .collect();
+                    drop(current);
+                    let Some(reason) = counts.reason() else {
+                        continue;
+                    };
+
+                    for (event, delivery) in merged {
+                        let metadata = SubscriptionMetadata::Changed(event, reason);
+                        if sender.send((metadata, delivery)).is_err() {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+
+                changes.lock().unwrap().querying = true;
+                let output = stmt.execute(&db).await;
+                changes.lock().unwrap().querying = false;
+                let output = match output {
+                    Ok(output) => output,
+                    Err(err) => {
+                        warn!("failed to run a subscribed query again: {err}");
+                        continue;
+                    }
+                };
+
+                let Some(shared) = data.upgrade() else {
+                    return;
+                };
+                let mut current = shared.lock().unwrap();
+                if *current == output {
+                    continue;
+                }
+                *current = output;
+                drop(current);
+
+                for (event, delivery) in pending {
+                    let metadata = SubscriptionMetadata::Changed(event, ChangeReason::Recomputed);
+                    if sender.send((metadata, delivery)).is_err() {
+                        return;
+                    }
+                }
+            }
+        };
+
+        Ok((subscription, Box::pin(requery)))
+    }
+}
+
+/// The changes a requerying subscription hasn't run its query for yet.
+#[derive(Default)]
+struct Changes {
+    pending: Vec<(MutationEvent, Delivery)>,
+    /// The query is running, so that changes coming in may or may not be in
+    /// its output.
+    querying: bool,
+    /// Some of `pending` came in while the query ran.
+    overlapped: bool,
+    /// The subscription was dropped.
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// Resolves with the pending changes, and whether any came in while the
+/// query ran, once there are any, or with `None` once the subscription was
+/// dropped.
+struct NextChanges<'a>(&'a Mutex<Changes>);
+
+impl Future for NextChanges<'_> {
+    type Output = Option<(Vec<(MutationEvent, Delivery)>, bool)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut changes = self.0.lock().unwrap();
+        if changes.closed {
+            Poll::Ready(None)
+        } else if !changes.pending.is_empty() {
+            let overlapped = mem::take(&mut changes.overlapped);
+            Poll::Ready(Some((mem::take(&mut changes.pending), overlapped)))
+        } else {
+            changes.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
@@ -6,9 +6,13 @@ use unions::{IntoUnion, IsUnion, UnionPath};
 
 use crate::{
     Database, Datatype, FieldKind, FieldKindGroup, FieldKindOfDatabase, InnerFieldType, OrderBy,
-    SelectStmtBuildable, SelectStmtOrderable, StrongFieldKind,
+    SelectStmtBuildable, SelectStmtOrderable, SqlFunction, StrongFieldKind,
+    subscription::overlap::{filter_outcomes, leaf_outcomes},
 };
 
+#[cfg(feature = "serde")]
+use crate::serde_impls::interned;
+
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct SelectStmtFilter<Db, FieldUnion, FieldPath, Fields>
@@ -142,16 +146,47 @@ where
         SmallVec<[FieldFilter; 1]>,
     );
 
-    fn filter<
-        InnerFieldPath: UnionPath,
-        InnerField: FieldKindOfDatabase<Db> + IntoUnion<FieldUnion, InnerFieldPath>,
-        T: InnerFieldType,
-    >(
+    /// Keep the rows that pass `filter`, on top of the filters so far.
+    /// Filters combine with [`or`](StrongFieldFilter::or) and `!`, e.g.
+    /// `User::AGE.lt(18i64).or(!User::NAME.eq("bob"))`.
+    fn filter<InnerFieldPath, Filter: IntoFieldFilter<Db, FieldUnion, InnerFieldPath>>(
         self,
-        filter: StrongFieldFilter<InnerField, T>,
+        filter: Filter,
     ) -> SelectStmtFilter<Db, FieldUnion, FieldPath, Fields> {
         let (tables, fields, mut filters) = self.tables_fields_and_filters();
-        filters.push(filter.to_weak());
+        filters.push(filter.into_field_filter());
+
+        SelectStmtFilter::new(tables, fields, filters)
+    }
+
+    /// Keep the rows where `left` equals `right`, to join tables on a key,
+    /// e.g. replies with the messages they reply to:
+    ///
+    /// ```ignore
+    /// let parents = MyDb::MESSAGES.alias("parents");
+    /// MyDb::MESSAGES
+    ///     .join(parents)
+    ///     .select((Message::BODY, parents.field(Message::BODY)))
+    ///     .join_on(Message::PARENT_ID, parents.field(Message::ID))
+    /// ```
+    fn join_on<LeftPath, RightPath, Left, Right, L, R>(
+        self,
+        left: StrongFieldKind<Left, L>,
+        right: StrongFieldKind<Right, R>,
+    ) -> SelectStmtFilter<Db, FieldUnion, FieldPath, Fields>
+    where
+        LeftPath: UnionPath,
+        RightPath: UnionPath,
+        Left: FieldKindOfDatabase<Db> + IntoUnion<FieldUnion, LeftPath>,
+        Right: FieldKindOfDatabase<Db> + IntoUnion<FieldUnion, RightPath>,
+        L: InnerFieldType,
+        R: InnerFieldType<Inner = L::Inner>,
+    {
+        let (tables, fields, mut filters) = self.tables_fields_and_filters();
+        filters.push(FieldFilter::EqColumn(FieldFilterColumnsMetadata {
+            left: left.table_field_pair::<Db>(),
+            right: right.table_field_pair::<Db>(),
+        }));
 
         SelectStmtFilter::new(tables, fields, filters)
     }
@@ -165,7 +200,26 @@ pub enum StrongFieldFilter<F: FieldKind, T: InnerFieldType> {
     Gte(StrongFieldKind<F, T>, Datatype),
     Lte(StrongFieldKind<F, T>, Datatype),
     Ne(StrongFieldKind<F, T>, Datatype),
+    EqNullable(StrongFieldKind<F, T>, Datatype),
+    DistinctFrom(StrongFieldKind<F, T>, Datatype),
     In(StrongFieldKind<F, T>, Vec<Datatype>),
+    Call(StrongFieldKind<F, T>, SqlFunction, Vec<Datatype>),
+    ContainsElement(StrongFieldKind<F, T>, Datatype),
+    IsNull(StrongFieldKind<F, T>),
+    IsNotNull(StrongFieldKind<F, T>),
+}
+
+impl<K: FieldKind, T: InnerFieldType> StrongFieldKind<K, T> {
+    /// The field's column, in the table alias it was taken from if any.
+    pub(crate) fn table_field_pair<D: Database>(&self) -> TableFieldPair
+    where
+        K: FieldKindOfDatabase<D>,
+    {
+        TableFieldPair::new(
+            self.kind.table_alias().unwrap_or(K::table_name()),
+            self.kind.name(),
+        )
+    }
 }
 
 impl<F: FieldKind, T: InnerFieldType> StrongFieldFilter<F, T> {
@@ -175,38 +229,159 @@ impl<F: FieldKind, T: InnerFieldType> StrongFieldFilter<F, T> {
     {
         match self {
             Self::Eq(strong_field, datatype) => FieldFilter::Eq(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
             Self::Gt(strong_field, datatype) => FieldFilter::Gt(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
             Self::Lt(strong_field, datatype) => FieldFilter::Lt(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
             Self::Gte(strong_field, datatype) => FieldFilter::Gte(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
             Self::Lte(strong_field, datatype) => FieldFilter::Lte(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
             Self::Ne(strong_field, datatype) => FieldFilter::Ne(FieldFilterMetadata::new(
-                TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                strong_field.table_field_pair::<D>(),
                 datatype,
             )),
+            Self::EqNullable(strong_field, datatype) => FieldFilter::EqNullable(
+                FieldFilterMetadata::new(strong_field.table_field_pair::<D>(), datatype),
+            ),
+            Self::DistinctFrom(strong_field, datatype) => FieldFilter::DistinctFrom(
+                FieldFilterMetadata::new(strong_field.table_field_pair::<D>(), datatype),
+            ),
             Self::In(strong_field, datatypes) => FieldFilter::In(FieldFilterInMetadata {
-                left: TableFieldPair::new(F::table_name(), strong_field.kind.name()),
+                left: strong_field.table_field_pair::<D>(),
                 right: datatypes,
             }),
+            Self::Call(strong_field, function, args) => {
+                FieldFilter::Call(FieldFilterCallMetadata {
+                    left: strong_field.table_field_pair::<D>(),
+                    function,
+                    args,
+                })
+            }
+            Self::ContainsElement(strong_field, datatype) => FieldFilter::ContainsElement(
+                FieldFilterMetadata::new(strong_field.table_field_pair::<D>(), datatype),
+            ),
+            Self::IsNull(strong_field) => FieldFilter::IsNull(strong_field.table_field_pair::<D>()),
+            Self::IsNotNull(strong_field) => {
+                FieldFilter::IsNotNull(strong_field.table_field_pair::<D>())
+            }
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl<F: FieldKind, T: InnerFieldType> StrongFieldFilter<F, T> {
+    /// Passes when this filter or `other` does.
+    pub fn or<Other>(self, other: Other) -> OrFilter<Self, Other> {
+        OrFilter(self, other)
+    }
+}
+
+impl<F: FieldKind, T: InnerFieldType> std::ops::Not for StrongFieldFilter<F, T> {
+    type Output = NotFilter<Self>;
+
+    fn not(self) -> Self::Output {
+        NotFilter(self)
+    }
+}
+
+/// Two filters combined with [`or`](StrongFieldFilter::or).
+#[derive(Clone, Debug)]
+pub struct OrFilter<A, B>(A, B);
+
+impl<A, B> OrFilter<A, B> {
+    pub fn or<Other>(self, other: Other) -> OrFilter<Self, Other> {
+        OrFilter(self, other)
+    }
+}
+
+impl<A, B> std::ops::Not for OrFilter<A, B> {
+    type Output = NotFilter<Self>;
+
+    fn not(self) -> Self::Output {
+        NotFilter(self)
+    }
+}
+
+/// A filter negated with `!`.
+#[derive(Clone, Debug)]
+pub struct NotFilter<A>(A);
+
+impl<A> NotFilter<A> {
+    pub fn or<Other>(self, other: Other) -> OrFilter<Self, Other> {
+        OrFilter(self, other)
+    }
+}
+
+impl<A> std::ops::Not for NotFilter<A> {
+    type Output = NotFilter<Self>;
+
+    fn not(self) -> Self::Output {
+        NotFilter(self)
+    }
+}
+
+/// A filter on the fields of `Db`'s tables in `FieldUnion`, which
+/// statements turn into a [`FieldFilter`].
+pub trait IntoFieldFilter<Db: Database, FieldUnion, FieldPath> {
+    fn into_field_filter(self) -> FieldFilter;
+}
+
+impl<Db, FieldUnion, FieldPath, F, T> IntoFieldFilter<Db, FieldUnion, FieldPath>
+    for StrongFieldFilter<F, T>
+where
+    Db: Database,
+    FieldPath: UnionPath,
+    F: FieldKindOfDatabase<Db> + IntoUnion<FieldUnion, FieldPath>,
+    T: InnerFieldType,
+{
+    fn into_field_filter(self) -> FieldFilter {
+        self.to_weak::<Db>()
+    }
+}
+
+impl<Db, FieldUnion, PathA, PathB, A, B> IntoFieldFilter<Db, FieldUnion, (PathA, PathB)>
+    for OrFilter<A, B>
+where
+    Db: Database,
+    A: IntoFieldFilter<Db, FieldUnion, PathA>,
+    B: IntoFieldFilter<Db, FieldUnion, PathB>,
+{
+    fn into_field_filter(self) -> FieldFilter {
+        // `a.or(b).or(c)` is one `Or` of the three.
+        let mut filters = Vec::new();
+        for filter in [self.0.into_field_filter(), self.1.into_field_filter()] {
+            match filter {
+                FieldFilter::Or(nested) => filters.extend(nested),
+                filter => filters.push(filter),
+            }
+        }
+        FieldFilter::Or(filters)
+    }
+}
+
+impl<Db, FieldUnion, FieldPath, A> IntoFieldFilter<Db, FieldUnion, FieldPath> for NotFilter<A>
+where
+    Db: Database,
+    A: IntoFieldFilter<Db, FieldUnion, FieldPath>,
+{
+    fn into_field_filter(self) -> FieldFilter {
+        FieldFilter::Not(Box::new(self.0.into_field_filter()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FieldFilter {
     Eq(FieldFilterMetadata),
     Gt(FieldFilterMetadata),
@@ -214,16 +389,56 @@ pub enum FieldFilter {
     Gte(FieldFilterMetadata),
     Lte(FieldFilterMetadata),
     Ne(FieldFilterMetadata),
+    /// Like `Eq`, except that NULL equals NULL (SQL `IS`). `Eq` never
+    /// matches NULL, just like `=` in SQL.
+    EqNullable(FieldFilterMetadata),
+    /// The negation of `EqNullable` (SQL `IS NOT`): unlike `Ne`, a NULL
+    /// column is distinct from every non-NULL value.
+    DistinctFrom(FieldFilterMetadata),
     In(FieldFilterInMetadata),
+    /// Passes when the function, called with the field and then `args`, is
+    /// true.
+    Call(FieldFilterCallMetadata),
+    /// Passes when the array field has `right` among its elements.
+    ContainsElement(FieldFilterMetadata),
+    /// Passes when the two columns are equal, which like `Eq` they never
+    /// are when either is NULL.
+    EqColumn(FieldFilterColumnsMetadata),
+    /// Passes when the column is NULL (SQL `IS NULL`).
+    IsNull(TableFieldPair),
+    /// Passes when the column isn't NULL (SQL `IS NOT NULL`).
+    IsNotNull(TableFieldPair),
+    /// Passes when any of the filters does, and fails when there are none.
+    Or(Vec<FieldFilter>),
+    /// Passes when the filter fails, like `NOT` in SQL: a filter that
+    /// compares with NULL fails negated too.
+    Not(Box<FieldFilter>),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FieldFilterInMetadata {
     pub left: TableFieldPair,
     pub right: Vec<Datatype>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldFilterColumnsMetadata {
+    pub left: TableFieldPair,
+    pub right: TableFieldPair,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldFilterCallMetadata {
+    pub left: TableFieldPair,
+    pub function: SqlFunction,
+    pub args: Vec<Datatype>,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FieldFilterMetadata {
     pub left: TableFieldPair,
     pub right: Datatype,
@@ -235,9 +450,12 @@ impl FieldFilterMetadata {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableFieldPair {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned"))]
     pub table_name: &'static str,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned"))]
     pub field_name: &'static str,
 }
 
@@ -253,22 +471,123 @@ impl TableFieldPair {
 impl FieldFilter {
     pub fn metadata(&self) -> &FieldFilterMetadata {
         match self {
-            Self::Eq(m) | Self::Gt(m) | Self::Lt(m) | Self::Gte(m) | Self::Lte(m) | Self::Ne(m) => {
-                m
-            }
-            Self::In(_) => panic!(
-                "FieldFilter::In does not have single-value metadata; use table_field_pair() instead"
+            Self::Eq(m)
+            | Self::Gt(m)
+            | Self::Lt(m)
+            | Self::Gte(m)
+            | Self::Lte(m)
+            | Self::Ne(m)
+            | Self::EqNullable(m)
+            | Self::DistinctFrom(m)
+            | Self::ContainsElement(m) => m,
+            Self::In(_)
+            | Self::Call(_)
+            | Self::EqColumn(_)
+            | Self::IsNull(_)
+            | Self::IsNotNull(_) => panic!(
+                "FieldFilter::In, FieldFilter::Call, FieldFilter::EqColumn, FieldFilter::IsNull and FieldFilter::IsNotNull do not have single-value metadata; use table_field_pair() instead"
+            ),
+            Self::Or(_) | Self::Not(_) => panic!(
+                "FieldFilter::Or and FieldFilter::Not do not have single-value metadata; use leaves() instead"
             ),
         }
     }
 
     pub fn table_field_pair(&self) -> &TableFieldPair {
         match self {
-            Self::Eq(m) | Self::Gt(m) | Self::Lt(m) | Self::Gte(m) | Self::Lte(m) | Self::Ne(m) => {
-                &m.left
-            }
+            Self::Eq(m)
+            | Self::Gt(m)
+            | Self::Lt(m)
+            | Self::Gte(m)
+            | Self::Lte(m)
+            | Self::Ne(m)
+            | Self::EqNullable(m)
+            | Self::DistinctFrom(m)
+            | Self::ContainsElement(m) => &m.left,
             Self::In(m) => &m.left,
+            Self::Call(m) => &m.left,
+            Self::EqColumn(m) => &m.left,
+            Self::IsNull(pair) | Self::IsNotNull(pair) => pair,
+            Self::Or(_) | Self::Not(_) => panic!(
+                "FieldFilter::Or and FieldFilter::Not compare several columns; use leaves() instead"
+            ),
+        }
+    }
+
+    /// Whether the filter is an `Or` or a `Not`, made of other filters.
+    pub fn is_compound(&self) -> bool {
+        matches!(self, Self::Or(_) | Self::Not(_))
+    }
+
+    /// The single-column filters the filter is made of: itself, unless it's
+    /// an `Or` or a `Not`.
+    pub fn leaves(&self) -> Vec<&FieldFilter> {
+        match self {
+            Self::Or(filters) => filters.iter().flat_map(Self::leaves).collect(),
+            Self::Not(filter) => filter.leaves(),
+            leaf => vec![leaf],
+        }
+    }
+
+    /// Whether a row passes the filter, reading the columns it compares
+    /// through `column`, for adapters that filter rows themselves. NULLs
+    /// compare as they do in SQL.
+    pub fn matches(&self, column: impl Fn(&TableFieldPair) -> Datatype) -> bool {
+        filter_outcomes(self, &|leaf| match leaf {
+            Self::EqColumn(m) => leaf_outcomes(
+                &Self::Eq(FieldFilterMetadata::new(m.left.clone(), column(&m.right))),
+                Some(&column(&m.left)),
+            ),
+            _ => leaf_outcomes(leaf, Some(&column(leaf.table_field_pair()))),
+        })
+        .can_pass
+    }
+}
+
+/// Renders the filter for error messages, e.g. `users.age > 30`.
+impl std::fmt::Display for FieldFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |value: &Datatype| match value {
+            Datatype::Text(v) => format!("'{v}'"),
+            other => other.to_string(),
+        };
+        let list = |values: &[Datatype]| values.iter().map(value).collect::<Vec<_>>().join(", ");
+
+        let column = |pair: &TableFieldPair| format!("{}.{}", pair.table_name, pair.field_name);
+
+        match self {
+            Self::Or(filters) => {
+                let filters: Vec<_> = filters.iter().map(ToString::to_string).collect();
+                return write!(f, "({})", filters.join(" OR "));
+            }
+            Self::Not(filter) => return write!(f, "NOT ({filter})"),
+            _ => {}
         }
+
+        let left = column(self.table_field_pair());
+        let operator = match self {
+            Self::Eq(_) => "=",
+            Self::Gt(_) => ">",
+            Self::Lt(_) => "<",
+            Self::Gte(_) => ">=",
+            Self::Lte(_) => "<=",
+            Self::Ne(_) => "!=",
+            Self::EqNullable(_) => "IS",
+            Self::DistinctFrom(_) => "IS NOT",
+            Self::ContainsElement(_) => "CONTAINS",
+            Self::In(m) => return write!(f, "{left} IN ({})", list(&m.right)),
+            Self::EqColumn(m) => return write!(f, "{left} = {}", column(&m.right)),
+            Self::IsNull(_) => return write!(f, "{left} IS NULL"),
+            Self::IsNotNull(_) => return write!(f, "{left} IS NOT NULL"),
+            Self::Call(m) if m.args.is_empty() => {
+                return write!(f, "{}({left})", m.function.name());
+            }
+            Self::Call(m) => {
+                return write!(f, "{}({left}, {})", m.function.name(), list(&m.args));
+            }
+            Self::Or(_) | Self::Not(_) => unreachable!(),
+        };
+        write!(f, "{left} {operator} {}", value(&self.metadata().right))
     }
 }
 
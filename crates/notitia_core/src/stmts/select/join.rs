@@ -5,9 +5,44 @@ use smallvec::SmallVec;
 use unions::{IsUnion, Union};
 
 use crate::{
-    Database, FieldKindGroup, IsTable, Record, SelectStmtSelectable, StrongTableKind, TableKind,
+    Aliased, Database, FieldKindGroup, IsTable, Record, SelectStmtSelectable, StrongTableKind,
+    TableAlias, TableKind,
 };
 
+/// A table that can be joined: a [`StrongTableKind`], or a [`TableAlias`]
+/// to join a table with itself.
+pub trait JoinableTable<Db: Database> {
+    /// The kind of the fields selected from the table in the join.
+    type FieldKind;
+
+    /// How the table is listed among the tables of a statement.
+    fn table_entry(&self) -> &'static str;
+}
+
+impl<Db, Tbl> JoinableTable<Db> for StrongTableKind<Db, Tbl>
+where
+    Db: Database,
+    Tbl: IsTable<Database = Db>,
+{
+    type FieldKind = <Tbl::Record as Record>::FieldKind;
+
+    fn table_entry(&self) -> &'static str {
+        self.kind.name()
+    }
+}
+
+impl<Db, Tbl> JoinableTable<Db> for TableAlias<Db, Tbl>
+where
+    Db: Database,
+    Tbl: IsTable<Database = Db>,
+{
+    type FieldKind = Aliased<<Tbl::Record as Record>::FieldKind>;
+
+    fn table_entry(&self) -> &'static str {
+        self.entry()
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct SelectStmtJoin<Db, FieldsUnion>
@@ -53,12 +88,11 @@ where
     Db: Database,
     FieldsUnion: IsUnion,
 {
-    fn join<Tbl: IsTable<Database = Db>>(
+    fn join<Tbl: JoinableTable<Db>>(
         mut self,
-        table: StrongTableKind<Db, Tbl>,
-    ) -> SelectStmtJoin<Db, Union<FieldsUnion, <<Tbl as IsTable>::Record as Record>::FieldKind>>
-    {
-        self.tables.push(table.kind.name());
+        table: Tbl,
+    ) -> SelectStmtJoin<Db, Union<FieldsUnion, Tbl::FieldKind>> {
+        self.tables.push(table.table_entry());
         SelectStmtJoin::new(self.tables)
     }
 
@@ -72,10 +106,10 @@ where
     Db: Database,
     FieldsUnion: IsUnion,
 {
-    fn join<Tbl: IsTable<Database = Db>>(
+    fn join<Tbl: JoinableTable<Db>>(
         self,
-        table: StrongTableKind<Db, Tbl>,
-    ) -> SelectStmtJoin<Db, Union<FieldsUnion, <<Tbl as IsTable>::Record as Record>::FieldKind>>;
+        table: Tbl,
+    ) -> SelectStmtJoin<Db, Union<FieldsUnion, Tbl::FieldKind>>;
 
     fn tables(self) -> SmallVec<[&'static str; 2]>;
 }
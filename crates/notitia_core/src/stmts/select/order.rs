@@ -1,28 +1,47 @@
-use std::marker::PhantomData;
+use std::{collections::BTreeMap, marker::PhantomData};
 
 use derivative::Derivative;
 use smallvec::SmallVec;
 use unions::{IntoUnion, IsUnion, UnionPath};
 
 use crate::{
-    Database, FieldFilter, FieldKindGroup, FieldKindOfDatabase, InnerFieldType, OrderedCollection,
-    SelectStmtBuilt, SelectStmtFetchAll, SelectStmtFetchFirst, SelectStmtFetchMany,
-    SelectStmtFetchMode, SelectStmtFetchOne, StrongFieldKind,
+    Database, FieldExpr, FieldFilter, FieldKindGroup, FieldKindOfDatabase, InnerFieldType,
+    OrderKey, OrderedCollection, SelectStmtBuilt, SelectStmtFetchAll, SelectStmtFetchAvg,
+    SelectStmtFetchCount, SelectStmtFetchFirst, SelectStmtFetchMany, SelectStmtFetchMax,
+    SelectStmtFetchMin, SelectStmtFetchMode, SelectStmtFetchOne, SelectStmtFetchSum,
+    StrongFieldKind, SubscribableRow,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[cfg(feature = "serde")]
+use crate::serde_impls::interned;
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderDirection {
     Asc,
     Desc,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderBy {
-    pub field: &'static str,
+    /// What rows are sorted by. Its fields are columns of `table`.
+    pub expr: FieldExpr,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned"))]
     pub table: &'static str,
     pub direction: OrderDirection,
 }
 
+impl OrderBy {
+    /// The column sorted by, unless this orders by a larger expression.
+    pub fn field(&self) -> Option<&'static str> {
+        match self.expr {
+            FieldExpr::Field(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct SelectStmtOrder<Db, FieldUnion, FieldPath, Fields>
@@ -73,8 +92,51 @@ where
     ) -> SelectStmtOrder<Db, FieldUnion, FieldPath, Fields> {
         let (tables, fields, filters, mut order_by) = self.tables_fields_filters_and_orders();
         order_by.push(OrderBy {
-            field: field.kind.name(),
-            table: InnerField::table_name(),
+            expr: FieldExpr::Field(field.kind.name()),
+            table: field.kind.table_alias().unwrap_or(InnerField::table_name()),
+            direction,
+        });
+        SelectStmtOrder {
+            tables,
+            fields,
+            filters,
+            order_by,
+            _database: PhantomData,
+            _path: PhantomData,
+            _union: PhantomData,
+        }
+    }
+
+    /// Order by an expression of the columns of the selected table:
+    ///
+    /// ```ignore
+    /// // Built-in SQL functions need no registering; the closure mirrors
+    /// // them for subscriptions, which sort new rows locally.
+    /// static COALESCE: LazyLock<SqlFunction> = LazyLock::new(|| {
+    ///     SqlFunction::new("coalesce", 2, |args| {
+    ///         args.iter().find(|arg| **arg != Datatype::Null).cloned().unwrap_or(Datatype::Null)
+    ///     })
+    /// });
+    ///
+    /// MyDb::MESSAGES
+    ///     .select((Message::ID, Message::EDITED_AT, Message::CREATED_AT))
+    ///     .order_by_expr(
+    ///         FieldExpr::Call(COALESCE.clone(), vec![Message::EDITED_AT.into(), Message::CREATED_AT.into()]),
+    ///         OrderDirection::Desc,
+    ///     )
+    /// ```
+    ///
+    /// A subscription re-sorts a row after an update from the row's selected
+    /// fields, so select every column the expression reads.
+    fn order_by_expr(
+        self,
+        expr: impl Into<FieldExpr>,
+        direction: OrderDirection,
+    ) -> SelectStmtOrder<Db, FieldUnion, FieldPath, Fields> {
+        let (tables, fields, filters, mut order_by) = self.tables_fields_filters_and_orders();
+        order_by.push(OrderBy {
+            expr: expr.into(),
+            table: tables.first().copied().unwrap_or_default(),
             direction,
         });
         SelectStmtOrder {
@@ -158,6 +220,24 @@ where
         )
     }
 
+    /// Fetches all matching rows in order, keyed by their position, the
+    /// same as `fetch_all::<BTreeMap<OrderKey, _>>()`.
+    pub fn fetch_ordered(
+        self,
+    ) -> SelectStmtBuilt<
+        Db,
+        FieldUnion,
+        FieldPath,
+        Fields,
+        SelectStmtFetchAll<BTreeMap<OrderKey, Fields::Type>>,
+    >
+    where
+        Fields::Type: SubscribableRow,
+        SelectStmtFetchAll<BTreeMap<OrderKey, Fields::Type>>: SelectStmtFetchMode<Fields::Type>,
+    {
+        self.fetch_all()
+    }
+
     /// Fetches up to `max` matching rows into an ordered collection.
     pub fn fetch_many<FetchAs: OrderedCollection>(
         self,
@@ -174,4 +254,80 @@ where
             SelectStmtFetchMany::new(max),
         )
     }
+
+    /// Counts the matching rows, see
+    /// [`fetch_count`](crate::SelectStmtBuildable::fetch_count). The order
+    /// only matters to which rows are
+    /// [kept per group](SelectStmtBuilt::limit_per_group).
+    pub fn fetch_count(
+        self,
+    ) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchCount> {
+        SelectStmtBuilt::new_ordered(
+            self.tables,
+            self.fields,
+            self.filters,
+            self.order_by,
+            SelectStmtFetchCount {},
+        )
+    }
+
+    /// Sums the selected field of the matching rows, see
+    /// [`fetch_count`](Self::fetch_count) for the order.
+    pub fn fetch_sum(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchSum>
+    where
+        SelectStmtFetchSum: SelectStmtFetchMode<Fields::Type>,
+    {
+        SelectStmtBuilt::new_ordered(
+            self.tables,
+            self.fields,
+            self.filters,
+            self.order_by,
+            SelectStmtFetchSum {},
+        )
+    }
+
+    /// Averages the selected field of the matching rows, see
+    /// [`fetch_count`](Self::fetch_count) for the order.
+    pub fn fetch_avg(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchAvg>
+    where
+        SelectStmtFetchAvg: SelectStmtFetchMode<Fields::Type>,
+    {
+        SelectStmtBuilt::new_ordered(
+            self.tables,
+            self.fields,
+            self.filters,
+            self.order_by,
+            SelectStmtFetchAvg {},
+        )
+    }
+
+    /// The least value of the selected field among the matching rows, see
+    /// [`fetch_count`](Self::fetch_count) for the order.
+    pub fn fetch_min(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchMin>
+    where
+        SelectStmtFetchMin: SelectStmtFetchMode<Fields::Type>,
+    {
+        SelectStmtBuilt::new_ordered(
+            self.tables,
+            self.fields,
+            self.filters,
+            self.order_by,
+            SelectStmtFetchMin {},
+        )
+    }
+
+    /// The greatest value of the selected field among the matching rows,
+    /// see [`fetch_count`](Self::fetch_count) for the order.
+    pub fn fetch_max(self) -> SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, SelectStmtFetchMax>
+    where
+        SelectStmtFetchMax: SelectStmtFetchMode<Fields::Type>,
+    {
+        SelectStmtBuilt::new_ordered(
+            self.tables,
+            self.fields,
+            self.filters,
+            self.order_by,
+            SelectStmtFetchMax {},
+        )
+    }
 }
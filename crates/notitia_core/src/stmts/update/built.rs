@@ -1,17 +1,23 @@
 use std::marker::PhantomData;
 
+use derivative::Derivative;
 use smallvec::SmallVec;
-use unions::{IntoUnion, UnionPath};
 
 use crate::{
-    Adapter, Database, FieldFilter, FieldKindOfDatabase, InnerFieldType, Mutation, MutationEvent,
-    MutationEventKind, Notitia, PartialRecord, Record, StrongFieldFilter,
+    Adapter, Database, DatatypeConversionError, FieldFilter, IntoFieldFilter, Mutation,
+    MutationEvent, MutationEventKind, Notitia, OutboxMessage, PartialRecord, Record,
 };
 
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
 pub struct UpdateStmtBuilt<Db: Database, Rec: Record, P: PartialRecord> {
     pub table_name: &'static str,
     pub partial: P,
     pub filters: SmallVec<[FieldFilter; 1]>,
+    pub outbox: Vec<OutboxMessage>,
+    /// The columns to read back off the updated rows, for subscribers to
+    /// merge; none when nothing reads them.
+    pub returning: Vec<&'static str>,
     _database: PhantomData<Db>,
     _record: PhantomData<Rec>,
 }
@@ -26,20 +32,18 @@ impl<Db: Database, Rec: Record, P: PartialRecord> UpdateStmtBuilt<Db, Rec, P> {
             table_name,
             partial,
             filters,
+            outbox: Vec::new(),
+            returning: Vec::new(),
             _database: PhantomData,
             _record: PhantomData,
         }
     }
 
-    pub fn filter<FieldPath: UnionPath, Field, T>(
-        mut self,
-        filter: StrongFieldFilter<Field, T>,
-    ) -> Self
+    pub fn filter<FieldPath, Filter>(mut self, filter: Filter) -> Self
     where
-        Field: FieldKindOfDatabase<Db> + IntoUnion<Rec::FieldKind, FieldPath>,
-        T: InnerFieldType,
+        Filter: IntoFieldFilter<Db, Rec::FieldKind, FieldPath>,
     {
-        self.filters.push(filter.to_weak());
+        self.filters.push(filter.into_field_filter());
         self
     }
 }
@@ -55,14 +59,52 @@ where
     fn to_mutation_event(&self) -> MutationEvent {
         MutationEvent {
             table_name: self.table_name,
+            sequence: 0,
             kind: MutationEventKind::Update {
                 changed: self.partial.clone().into_set_fields(),
                 filters: self.filters.clone(),
+                rows: Vec::new(),
             },
         }
     }
 
+    fn apply_mutation_event(
+        &mut self,
+        event: MutationEvent,
+    ) -> Result<(), DatatypeConversionError> {
+        if let MutationEventKind::Update { changed, .. } = event.kind {
+            for (column, expr) in changed {
+                self.partial.replace_set_field(column, expr);
+            }
+        }
+        Ok(())
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
     async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
-        db.execute_update_stmt(self).await
+        db.execute_update_stmt(self).await?;
+        Ok(())
+    }
+
+    /// The event carries the rows the adapter returned for the update, with
+    /// the columns subscribers read off them.
+    async fn execute_with_event<Adptr: Adapter>(
+        mut self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent), Adptr::Error> {
+        let mut event = self.to_mutation_event();
+        let columns: Vec<&'static str> = Rec::_FIELDS.iter().map(|(name, _)| *name).collect();
+        self.returning = db
+            .inner
+            .subscriptions
+            .columns_read_from_updates(self.table_name, &columns);
+        let updated = db.execute_update_stmt(self).await?;
+        if let MutationEventKind::Update { rows, .. } = &mut event.kind {
+            *rows = updated;
+        }
+        Ok(((), event))
     }
 }
@@ -1,17 +1,27 @@
-use crate::Datatype;
+use crate::{Datatype, SqlFunction};
+
+#[cfg(feature = "serde")]
+use crate::serde_impls::interned;
 
 /// A composable expression tree for update field values.
 ///
 /// Allows both literal values and field-reference-based expressions
 /// (e.g. `SET content = content || 'chunk'`).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FieldExpr {
     /// A literal value: `SET field = 'value'`
     Literal(Datatype),
     /// A reference to a field's current value: `SET field = other_field`
-    Field(&'static str),
+    Field(#[cfg_attr(feature = "serde", serde(deserialize_with = "interned"))] &'static str),
     /// String concatenation: `SET field = left || right`
     Concat(Box<FieldExpr>, Box<FieldExpr>),
+    /// Numeric addition: `SET field = left + right`
+    Add(Box<FieldExpr>, Box<FieldExpr>),
+    /// Numeric subtraction: `SET field = left - right`
+    Sub(Box<FieldExpr>, Box<FieldExpr>),
+    /// A registered function: `SET field = name(args...)`
+    Call(SqlFunction, Vec<FieldExpr>),
 }
 
 impl FieldExpr {
@@ -36,10 +46,89 @@ impl FieldExpr {
                     (_, r) => r,
                 }
             }
+            FieldExpr::Add(left, right) => arithmetic(
+                left.resolve(row),
+                right.resolve(row),
+                i64::checked_add,
+                |a, b| a + b,
+            ),
+            FieldExpr::Sub(left, right) => arithmetic(
+                left.resolve(row),
+                right.resolve(row),
+                i64::checked_sub,
+                |a, b| a - b,
+            ),
+            FieldExpr::Call(function, args) => {
+                let args: Vec<Datatype> = args.iter().map(|arg| arg.resolve(row)).collect();
+                function.call(&args)
+            }
+        }
+    }
+
+    /// Whether every column the expression reads is in `row`, so that
+    /// [`resolve`](Self::resolve) doesn't read missing ones as NULL.
+    pub fn resolves_in(&self, row: &[(&'static str, Datatype)]) -> bool {
+        match self {
+            FieldExpr::Literal(_) => true,
+            FieldExpr::Field(name) => row.iter().any(|(k, _)| k == name),
+            FieldExpr::Concat(left, right)
+            | FieldExpr::Add(left, right)
+            | FieldExpr::Sub(left, right) => left.resolves_in(row) && right.resolves_in(row),
+            FieldExpr::Call(_, args) => args.iter().all(|arg| arg.resolves_in(row)),
+        }
+    }
+
+    /// Whether the expression reads `column`.
+    pub fn references(&self, column: &str) -> bool {
+        match self {
+            FieldExpr::Literal(_) => false,
+            FieldExpr::Field(name) => *name == column,
+            FieldExpr::Concat(left, right)
+            | FieldExpr::Add(left, right)
+            | FieldExpr::Sub(left, right) => left.references(column) || right.references(column),
+            FieldExpr::Call(_, args) => args.iter().any(|arg| arg.references(column)),
         }
     }
 }
 
+/// Apply an arithmetic operator the way SQLite does: integers stay integers
+/// unless they overflow, anything involving a float is a float, and
+/// anything involving NULL or a non-number is NULL.
+fn arithmetic(
+    left: Datatype,
+    right: Datatype,
+    int: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Datatype {
+    let as_int = |value: &Datatype| match value {
+        Datatype::Int(v) => Some(*v as i64),
+        Datatype::BigInt(v) => Some(*v),
+        _ => None,
+    };
+    let as_float = |value: &Datatype| match value {
+        Datatype::Float(v) => Some(*v as f64),
+        Datatype::Double(v) => Some(*v),
+        other => as_int(other).map(|v| v as f64),
+    };
+
+    if let (Some(a), Some(b)) = (as_int(&left), as_int(&right)) {
+        return match int(a, b) {
+            Some(v) => match (&left, &right, i32::try_from(v)) {
+                (Datatype::Int(_), Datatype::Int(_), Ok(v)) => Datatype::Int(v),
+                _ => Datatype::BigInt(v),
+            },
+            None => Datatype::Double(float(a as f64, b as f64)),
+        };
+    }
+    match (as_float(&left), as_float(&right)) {
+        (Some(a), Some(b)) => match (&left, &right) {
+            (Datatype::Float(_), Datatype::Float(_)) => Datatype::Float(float(a, b) as f32),
+            _ => Datatype::Double(float(a, b)),
+        },
+        _ => Datatype::Null,
+    }
+}
+
 // Raw values that convert to Datatype automatically become Literal.
 impl<T: Into<Datatype>> From<T> for FieldExpr {
     fn from(val: T) -> Self {
@@ -65,6 +154,16 @@ mod tests {
         assert_eq!(expr.resolve(&row), Datatype::Text("Alice".into()));
     }
 
+    #[test]
+    fn resolves_only_in_rows_with_every_column_it_reads() {
+        let expr = FieldExpr::Add(
+            Box::new(FieldExpr::Field("count")),
+            Box::new(FieldExpr::Literal(Datatype::BigInt(1))),
+        );
+        assert!(expr.resolves_in(&[("count", Datatype::BigInt(2))]));
+        assert!(!expr.resolves_in(&[("name", Datatype::Text("Alice".into()))]));
+    }
+
     #[test]
     fn field_resolve_missing() {
         let expr = FieldExpr::Field("missing");
@@ -108,6 +207,68 @@ mod tests {
         assert_eq!(expr.resolve(&row), Datatype::Text("abc".into()));
     }
 
+    #[test]
+    fn add_and_sub_resolve() {
+        let row = vec![("length", Datatype::BigInt(90_000_000))];
+        let extended = FieldExpr::Add(
+            Box::new(FieldExpr::Field("length")),
+            Box::new(std::time::Duration::from_secs(30).into()),
+        );
+        assert_eq!(extended.resolve(&row), Datatype::BigInt(120_000_000));
+
+        let shortened = FieldExpr::Sub(
+            Box::new(FieldExpr::Field("length")),
+            Box::new(FieldExpr::Literal(Datatype::Double(0.5))),
+        );
+        assert_eq!(shortened.resolve(&row), Datatype::Double(89_999_999.5));
+
+        let missing = FieldExpr::Add(
+            Box::new(FieldExpr::Field("missing")),
+            Box::new(FieldExpr::Literal(Datatype::Int(1))),
+        );
+        assert_eq!(missing.resolve(&row), Datatype::Null);
+    }
+
+    #[test]
+    fn add_overflows_to_double() {
+        let expr = FieldExpr::Add(
+            Box::new(FieldExpr::Literal(Datatype::BigInt(i64::MAX))),
+            Box::new(FieldExpr::Literal(Datatype::Int(1))),
+        );
+        assert_eq!(expr.resolve(&[]), Datatype::Double(i64::MAX as f64 + 1.0));
+    }
+
+    #[test]
+    fn call_resolve() {
+        let upper = SqlFunction::new("upper", 1, |args| match &args[0] {
+            Datatype::Text(s) => Datatype::Text(s.to_uppercase()),
+            other => other.clone(),
+        });
+        let expr = FieldExpr::Call(upper, vec![FieldExpr::Field("name")]);
+        let row = vec![("name", Datatype::Text("Alice".into()))];
+        assert_eq!(expr.resolve(&row), Datatype::Text("ALICE".into()));
+    }
+
+    #[test]
+    fn references_nested_fields() {
+        let coalesce = SqlFunction::new("coalesce", 2, |args| {
+            args.iter()
+                .find(|arg| **arg != Datatype::Null)
+                .cloned()
+                .unwrap_or(Datatype::Null)
+        });
+        let expr = FieldExpr::Call(
+            coalesce,
+            vec![
+                FieldExpr::Field("edited_at"),
+                FieldExpr::Field("created_at"),
+            ],
+        );
+        assert!(expr.references("edited_at"));
+        assert!(expr.references("created_at"));
+        assert!(!expr.references("content"));
+    }
+
     #[test]
     fn from_string() {
         let expr: FieldExpr = "hello".to_string().into();
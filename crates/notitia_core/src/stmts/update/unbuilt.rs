@@ -1,16 +1,16 @@
 use std::marker::PhantomData;
 
 use smallvec::SmallVec;
-use unions::{IntoUnion, UnionPath};
 
 use crate::{
-    Adapter, Database, FieldKindOfDatabase, InnerFieldType, Mutation, MutationEvent,
-    MutationEventKind, Notitia, PartialRecord, Record, StrongFieldFilter, UpdateStmtBuilt,
+    Adapter, Database, DatatypeConversionError, IntoFieldFilter, Mutation, MutationEvent,
+    MutationEventKind, Notitia, OutboxMessage, PartialRecord, Record, UpdateStmtBuilt,
 };
 
 pub struct UpdateStmtUnbuilt<Db: Database, Rec: Record, P: PartialRecord> {
     pub table_name: &'static str,
     pub partial: P,
+    pub outbox: Vec<OutboxMessage>,
     _database: PhantomData<Db>,
     _record: PhantomData<Rec>,
 }
@@ -20,23 +20,29 @@ impl<Db: Database, Rec: Record, P: PartialRecord> UpdateStmtUnbuilt<Db, Rec, P>
         Self {
             table_name,
             partial,
+            outbox: Vec::new(),
             _database: PhantomData,
             _record: PhantomData,
         }
     }
 
-    pub fn filter<FieldPath: UnionPath, Field, T>(
-        self,
-        filter: StrongFieldFilter<Field, T>,
-    ) -> UpdateStmtBuilt<Db, Rec, P>
+    pub fn filter<FieldPath, Filter>(self, filter: Filter) -> UpdateStmtBuilt<Db, Rec, P>
     where
-        Field: FieldKindOfDatabase<Db> + IntoUnion<Rec::FieldKind, FieldPath>,
-        T: InnerFieldType,
+        Filter: IntoFieldFilter<Db, Rec::FieldKind, FieldPath>,
     {
         let mut filters = SmallVec::new();
-        filters.push(filter.to_weak());
+        filters.push(filter.into_field_filter());
+
+        let mut built = UpdateStmtBuilt::new(self.table_name, self.partial, filters);
+        built.outbox = self.outbox;
+        built
+    }
 
-        UpdateStmtBuilt::new(self.table_name, self.partial, filters)
+    /// The update of every row.
+    fn into_built(self) -> UpdateStmtBuilt<Db, Rec, P> {
+        let mut built = UpdateStmtBuilt::new(self.table_name, self.partial, SmallVec::new());
+        built.outbox = self.outbox;
+        built
     }
 }
 
@@ -51,16 +57,39 @@ where
     fn to_mutation_event(&self) -> MutationEvent {
         MutationEvent {
             table_name: self.table_name,
+            sequence: 0,
             kind: MutationEventKind::Update {
                 changed: self.partial.clone().into_set_fields(),
                 filters: SmallVec::new(),
+                rows: Vec::new(),
             },
         }
     }
 
+    fn apply_mutation_event(
+        &mut self,
+        event: MutationEvent,
+    ) -> Result<(), DatatypeConversionError> {
+        if let MutationEventKind::Update { changed, .. } = event.kind {
+            for (column, expr) in changed {
+                self.partial.replace_set_field(column, expr);
+            }
+        }
+        Ok(())
+    }
+
+    fn outbox(&mut self) -> &mut Vec<OutboxMessage> {
+        &mut self.outbox
+    }
+
     async fn execute<Adptr: Adapter>(self, db: &Notitia<Db, Adptr>) -> Result<(), Adptr::Error> {
-        let built: UpdateStmtBuilt<Db, Rec, P> =
-            UpdateStmtBuilt::new(self.table_name, self.partial, SmallVec::new());
-        db.execute_update_stmt(built).await
+        self.into_built().execute(db).await
+    }
+
+    async fn execute_with_event<Adptr: Adapter>(
+        self,
+        db: &Notitia<Db, Adptr>,
+    ) -> Result<((), MutationEvent), Adptr::Error> {
+        self.into_built().execute_with_event(db).await
     }
 }
@@ -0,0 +1,83 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Travels with a change to every subscription it was merged into. A
+/// mutation run with
+/// [`read_your_writes`](crate::MutateExecutor::read_your_writes) resolves
+/// once every copy was dropped, i.e. every consumer has taken its change.
+#[derive(Clone, Default)]
+pub struct Delivery {
+    pending: Option<Arc<Pending>>,
+}
+
+impl Delivery {
+    /// A delivery nothing waits for.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn tracked() -> (Self, Settled) {
+        let settlement = Arc::new(Mutex::new(Settlement::default()));
+        let delivery = Self {
+            pending: Some(Arc::new(Pending(settlement.clone()))),
+        };
+        (delivery, Settled(settlement))
+    }
+}
+
+struct Pending(Arc<Mutex<Settlement>>);
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        let mut settlement = self.0.lock().unwrap();
+        settlement.settled = true;
+        if let Some(waker) = settlement.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Settlement {
+    settled: bool,
+    waker: Option<Waker>,
+}
+
+/// Resolves once every copy of a tracked [`Delivery`] was dropped.
+pub(crate) struct Settled(Arc<Mutex<Settlement>>);
+
+impl Future for Settled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut settlement = self.0.lock().unwrap();
+        if settlement.settled {
+            Poll::Ready(())
+        } else {
+            settlement.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn settles_once_every_copy_is_dropped() {
+        let (delivery, mut settled) = Delivery::tracked();
+        let copy = delivery.clone();
+
+        drop(delivery);
+        assert!((&mut settled).now_or_never().is_none());
+
+        drop(copy);
+        assert!(settled.now_or_never().is_some());
+    }
+}
@@ -1,12 +1,124 @@
+use std::hash::{Hash, Hasher};
+
 use smallvec::SmallVec;
 
-use crate::{FieldFilter, OrderDirection};
+use crate::{FieldFilter, OrderBy, split_table_entry};
+
+#[cfg(feature = "serde")]
+use crate::serde_impls::{interned_all, interned_opt};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubscriptionDescriptor {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned_all"))]
     pub tables: SmallVec<[&'static str; 2]>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "interned_all"))]
     pub field_names: SmallVec<[&'static str; 4]>,
     pub filters: SmallVec<[FieldFilter; 1]>,
-    pub order_by_field_names: SmallVec<[&'static str; 1]>,
-    pub order_by_directions: SmallVec<[OrderDirection; 1]>,
+    /// The query's orders, usually by a single field, with the table (or
+    /// alias) whose columns each one reads.
+    pub order_by: SmallVec<[OrderBy; 1]>,
+    /// The selected column that is the primary key of the only table the
+    /// query reads, which tells its rows apart when merging inserts.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "interned_opt"))]
+    pub primary_key: Option<&'static str>,
+}
+
+impl SubscriptionDescriptor {
+    /// A hash of the descriptor that is the same in every process and on
+    /// every platform, unlike [`Hash`] with the std hasher, for cache keys
+    /// and for matching descriptors sent over the wire.
+    pub fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The orders on the columns of `table_name`, which a mutation of it can
+    /// move rows by.
+    pub(crate) fn orders_on_table(&self, table_name: &str) -> impl Iterator<Item = &OrderBy> {
+        self.order_by.iter().filter(move |order| {
+            let (table, alias) = split_table_entry(order.table);
+            let name = alias.unwrap_or(table);
+            self.tables
+                .iter()
+                .map(|entry| split_table_entry(*entry))
+                .any(|(table, alias)| table == table_name && alias.unwrap_or(table) == name)
+        })
+    }
+
+    /// Whether merging an update of `table_name` reads `column` off the rows
+    /// it returns: the query selects, filters or orders on it.
+    pub(crate) fn reads_updated_column(&self, table_name: &str, column: &str) -> bool {
+        let reads_table = self
+            .tables
+            .iter()
+            .any(|entry| split_table_entry(*entry).0 == table_name);
+        reads_table
+            && (self.field_names.iter().any(|name| *name == column)
+                || self.primary_key == Some(column)
+                || self
+                    .filters
+                    .iter()
+                    .flat_map(FieldFilter::leaves)
+                    .any(|leaf| leaf.table_field_pair().field_name == column)
+                || self
+                    .orders_on_table(table_name)
+                    .any(|order| order.expr.references(column)))
+    }
+}
+
+/// FNV-1a, with integers written as fixed-width little-endian bytes so
+/// `usize` lengths hash the same on 32 and 64-bit targets.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
 }
@@ -3,12 +3,20 @@ use smallvec::SmallVec;
 use crate::{Datatype, FieldExpr, FieldFilter};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MutationEvent {
     pub table_name: &'static str,
+    /// The position of the event among those its database broadcast,
+    /// counting from 1 without gaps, so a consumer seeing every event, like a
+    /// [`MutationHook`](crate::MutationHook), can tell when it missed one.
+    /// Each subscription receives the events it matches in this order. It's
+    /// 0 until the event is broadcast.
+    pub sequence: u64,
     pub kind: MutationEventKind,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MutationEventKind {
     Insert {
         /// All columns and their values for the inserted row.
@@ -19,9 +27,17 @@ pub enum MutationEventKind {
         changed: Vec<(&'static str, FieldExpr)>,
         /// The filters on the UPDATE statement (which rows were targeted).
         filters: SmallVec<[FieldFilter; 1]>,
+        /// All columns and their values for every updated row, as the
+        /// update left them. Empty until the update ran, and for adapters
+        /// that can't return the rows they update.
+        rows: Vec<Vec<(&'static str, Datatype)>>,
     },
     Delete {
         /// The filters on the DELETE statement (which rows were targeted).
         filters: SmallVec<[FieldFilter; 1]>,
     },
+    BulkInsert {
+        /// All columns and their values for every inserted row.
+        rows: Vec<Vec<(&'static str, Datatype)>>,
+    },
 }
@@ -1,28 +1,163 @@
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{
+    Arc, Mutex, MutexGuard,
+    atomic::{AtomicBool, Ordering},
+};
 
-use super::SubscriptionMetadata;
+use smallvec::SmallVec;
+
+use super::{
+    Delivery, MutationEvent, MutationEventKind, SubscriptionMetadata, SubscriptionReceiver,
+};
 
 pub struct Subscription<T> {
     data: Arc<Mutex<T>>,
-    receiver: crossbeam_channel::Receiver<SubscriptionMetadata>,
+    receiver: SubscriptionReceiver,
+    /// The selected columns, empty for derived values.
+    field_names: SmallVec<[&'static str; 4]>,
+    removed: Arc<AtomicBool>,
 }
 
 impl<T> Subscription<T> {
     pub(crate) fn new(
         data: Arc<Mutex<T>>,
-        receiver: crossbeam_channel::Receiver<SubscriptionMetadata>,
+        receiver: SubscriptionReceiver,
+        field_names: SmallVec<[&'static str; 4]>,
+        removed: Arc<AtomicBool>,
     ) -> Self {
-        Self { data, receiver }
+        Self {
+            data,
+            receiver,
+            field_names,
+            removed,
+        }
     }
 
     /// Block until the subscription data changes. Returns the metadata
-    /// describing what changed.
+    /// describing what changed. Fails right away for a subscription
+    /// delivered through a [callback](super::DeliveryStrategy::Callback).
     pub fn recv(&self) -> Result<SubscriptionMetadata, crossbeam_channel::RecvError> {
-        self.receiver.recv()
+        self.recv_delivery().map(|(metadata, _)| metadata)
+    }
+
+    /// Like [`recv`](Self::recv), for consumers that pass the data on, e.g.
+    /// to a UI thread. A mutation waiting for its subscribers keeps waiting
+    /// until the returned [`Delivery`] is dropped, once the data was applied.
+    pub fn recv_delivery(
+        &self,
+    ) -> Result<(SubscriptionMetadata, Delivery), crossbeam_channel::RecvError> {
+        match &self.receiver {
+            SubscriptionReceiver::Blocking(receiver) => receiver.recv(),
+            SubscriptionReceiver::Async(receiver) => receiver
+                .recv_blocking()
+                .map_err(|_| crossbeam_channel::RecvError),
+            SubscriptionReceiver::Callback { .. } => Err(crossbeam_channel::RecvError),
+        }
+    }
+
+    /// Wait until the subscription data changes, for a subscription
+    /// delivered through an [async channel](super::DeliveryStrategy::Async).
+    /// Fails right away for other strategies, which can't be awaited.
+    pub async fn recv_async(&self) -> Result<SubscriptionMetadata, crossbeam_channel::RecvError> {
+        self.recv_delivery_async()
+            .await
+            .map(|(metadata, _)| metadata)
+    }
+
+    /// Like [`recv_async`](Self::recv_async), with the [`Delivery`], see
+    /// [`recv_delivery`](Self::recv_delivery).
+    pub async fn recv_delivery_async(
+        &self,
+    ) -> Result<(SubscriptionMetadata, Delivery), crossbeam_channel::RecvError> {
+        match &self.receiver {
+            SubscriptionReceiver::Async(receiver) => receiver
+                .recv()
+                .await
+                .map_err(|_| crossbeam_channel::RecvError),
+            _ => Err(crossbeam_channel::RecvError),
+        }
     }
 
     /// Returns a reference to the current data.
     pub fn data(&self) -> MutexGuard<'_, T> {
         self.data.lock().unwrap()
     }
+
+    /// Whether the row of a `fetch_one` or `fetch_first` subscription was
+    /// deleted. [`data`](Self::data) still returns it, e.g. for a detail
+    /// pane to close. An insert of another row the query matches takes its
+    /// place and clears the flag.
+    pub fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Acquire)
+    }
+
+    /// The selected columns that the update `metadata` describes set, e.g.
+    /// to highlight the fields of an edit form that changed elsewhere.
+    /// `None` for other changes, as inserts and deletes concern whole rows.
+    pub fn changed_fields(
+        &self,
+        metadata: &SubscriptionMetadata,
+    ) -> Option<SmallVec<[&'static str; 4]>> {
+        let SubscriptionMetadata::Changed(
+            MutationEvent {
+                kind: MutationEventKind::Update { changed, .. },
+                ..
+            },
+            _,
+        ) = metadata
+        else {
+            return None;
+        };
+
+        Some(
+            changed
+                .iter()
+                .map(|(column, _)| *column)
+                .filter(|column| self.field_names.contains(column))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use crate::{ChangeReason, Datatype, DeliveryStrategy, FieldExpr};
+
+    use super::*;
+
+    #[test]
+    fn lists_selected_columns_an_update_set() {
+        let (_, receiver) = DeliveryStrategy::<()>::Blocking.channel();
+        let subscription = Subscription::new(
+            Arc::new(Mutex::new(())),
+            receiver,
+            smallvec!["name"],
+            Arc::default(),
+        );
+        let update = SubscriptionMetadata::Changed(
+            MutationEvent {
+                table_name: "users",
+                sequence: 1,
+                kind: MutationEventKind::Update {
+                    changed: vec![
+                        ("name", FieldExpr::Literal(Datatype::Text("Bob".into()))),
+                        ("age", FieldExpr::Literal(Datatype::BigInt(30))),
+                    ],
+                    filters: SmallVec::new(),
+                    rows: Vec::new(),
+                },
+            },
+            ChangeReason::RowsUpdated(1),
+        );
+
+        assert_eq!(
+            subscription.changed_fields(&update).as_deref(),
+            Some(&["name"][..])
+        );
+        assert_eq!(
+            subscription.changed_fields(&SubscriptionMetadata::None),
+            None
+        );
+    }
 }
@@ -1,8 +1,12 @@
 use crate::{
-    Collection, Datatype, DatatypeConversionError, FieldExpr, FieldFilter, OrderDirection, OrderKey,
+    Collection, Datatype, DatatypeConversionError, FieldExpr, FieldFilter, OrderBy, OrderDirection,
+    OrderKey, Upsert, split_table_entry,
 };
 
-use super::{MutationEvent, MutationEventKind, SubscriptionDescriptor};
+use super::{
+    MergeCounts, MutationEvent, MutationEventKind, SubscriptionDescriptor,
+    overlap::insert_matches_filters,
+};
 
 /// Trait for row types that can be decomposed and recomposed for patch merging.
 ///
@@ -18,37 +22,53 @@ pub trait SubscribableRow: Clone + PartialEq + Send + Sized + 'static {
     ) -> Result<Self, DatatypeConversionError>;
 }
 
-/// Merge a mutation event into the subscription's local data.
+/// Merge a mutation event into the subscription's local data, returning
+/// what it changed.
 pub fn merge_event_into_data<C: Collection>(
     data: &mut C,
     descriptor: &SubscriptionDescriptor,
     event: &MutationEvent,
-) {
+) -> MergeCounts {
     match &event.kind {
-        MutationEventKind::Insert { values } => {
-            merge_insert(data, descriptor, values);
-        }
+        MutationEventKind::Insert { values } => merge_insert(data, descriptor, values),
         MutationEventKind::Update {
             changed,
             filters: mutation_filters,
-        } => {
-            merge_update(data, descriptor, changed, mutation_filters);
-        }
+            rows,
+        } => merge_update(
+            data,
+            descriptor,
+            event.table_name,
+            changed,
+            mutation_filters,
+            rows,
+        ),
         MutationEventKind::Delete {
             filters: mutation_filters,
-        } => {
-            merge_delete(data, descriptor, mutation_filters);
+        } => merge_delete(data, descriptor, mutation_filters),
+        MutationEventKind::BulkInsert { rows } => {
+            // The event as a whole matched, but each row still has to pass
+            // the subscription's filters on its own.
+            let mut counts = MergeCounts::default();
+            for values in rows {
+                if insert_matches_filters(values, &descriptor.filters) {
+                    counts += merge_insert(data, descriptor, values);
+                }
+            }
+            counts
         }
     }
 }
 
 /// For an insert: extract the subscription's selected fields from the inserted row,
-/// construct a new row, and push it into the data.
+/// construct a new row, and push it into the data. A row the data already has
+/// by primary key, e.g. read by a refresh before the event arrived, is
+/// replaced instead, which counts as an update if it differed.
 fn merge_insert<C: Collection>(
     data: &mut C,
     descriptor: &SubscriptionDescriptor,
     inserted_values: &[(&'static str, Datatype)],
-) {
+) -> MergeCounts {
     let ordered_values: Vec<Datatype> = descriptor
         .field_names
         .iter()
@@ -66,64 +86,102 @@ fn merge_insert<C: Collection>(
         })
         .collect();
 
-    if let Ok(row) = C::Item::from_datatypes(&mut ordered_values.into_iter()) {
-        let order_key = order_key_from_values(
-            &descriptor.order_by_field_names,
-            &descriptor.order_by_directions,
-            inserted_values,
-        );
+    let Ok(row) = C::Item::from_datatypes(&mut ordered_values.into_iter()) else {
+        return MergeCounts::default();
+    };
+    let order_key = order_key_from_values(&descriptor.order_by, inserted_values);
+    let Some((position, key)) = primary_key_value(descriptor, inserted_values) else {
         data.push(row, order_key);
+        return MergeCounts {
+            inserted: 1,
+            ..MergeCounts::default()
+        };
+    };
+    let same_key = |existing: &C::Item| {
+        existing
+            .to_datatypes(&descriptor.field_names)
+            .get(position)
+            .is_some_and(|(_, val)| *val == key)
+    };
+    match data.upsert(row, order_key, same_key) {
+        Upsert::Inserted => MergeCounts {
+            inserted: 1,
+            ..MergeCounts::default()
+        },
+        Upsert::Replaced => MergeCounts {
+            updated: 1,
+            ..MergeCounts::default()
+        },
+        Upsert::Unchanged => MergeCounts::default(),
     }
 }
 
-/// Extract an `OrderKey` from named values using the descriptor's order_by field names and directions.
-fn order_key_from_values(
-    order_by_field_names: &[&'static str],
-    order_by_directions: &[OrderDirection],
-    values: &[(&'static str, Datatype)],
-) -> OrderKey {
-    let vals = order_by_field_names
+/// The primary key an insert sets, if the subscription selects it, along
+/// with its position among the selected fields.
+fn primary_key_value(
+    descriptor: &SubscriptionDescriptor,
+    inserted_values: &[(&'static str, Datatype)],
+) -> Option<(usize, Datatype)> {
+    let column = descriptor.primary_key?;
+    let position = descriptor
+        .field_names
         .iter()
-        .map(|name| {
-            values
-                .iter()
-                .find_map(
-                    |(col, val)| {
-                        if col == name { Some(val.clone()) } else { None }
-                    },
-                )
-                .unwrap_or(Datatype::Null)
-        })
+        .position(|field_name| *field_name == column)?;
+    let key = inserted_values
+        .iter()
+        .find_map(|(col, val)| (*col == column && *val != Datatype::Null).then(|| val.clone()))?;
+    Some((position, key))
+}
+
+/// Extract an `OrderKey` from named values using the descriptor's orders.
+fn order_key_from_values(order_by: &[OrderBy], values: &[(&'static str, Datatype)]) -> OrderKey {
+    let vals = order_by
+        .iter()
+        .map(|order| order.expr.resolve(values))
         .collect();
-    let reversed = order_by_directions
+    let reversed = order_by
         .iter()
-        .map(|d| matches!(d, OrderDirection::Desc))
+        .map(|order| matches!(order.direction, OrderDirection::Desc))
         .collect();
     OrderKey::new(vals, reversed)
 }
 
 /// For an update: find rows that match the mutation's filters and apply the changes.
 /// Uses `FieldExpr::resolve` to evaluate expressions against the current row values.
+///
+/// Updated rows that no longer pass the subscription's filters are removed,
+/// and the rows the adapter returned for the update that now pass them are
+/// added.
 fn merge_update<C: Collection>(
     data: &mut C,
     descriptor: &SubscriptionDescriptor,
+    table_name: &'static str,
     changed: &[(&'static str, FieldExpr)],
     mutation_filters: &[FieldFilter],
-) {
+    rows: &[Vec<(&'static str, Datatype)>],
+) -> MergeCounts {
+    let filters = filters_on_table(descriptor, table_name);
+    // The positions of the rows that left, as `update_each` visits them.
+    let mut left: Vec<usize> = Vec::new();
+    let mut position = 0;
+    let mut stayed: Vec<C::Item> = Vec::new();
+    let mut updated = 0;
+
     // Check if any ORDER BY field was changed.
     let order_changed = descriptor
-        .order_by_field_names
-        .iter()
-        .any(|name| changed.iter().any(|(col, _)| col == name));
+        .orders_on_table(table_name)
+        .any(|order| changed.iter().any(|(col, _)| order.expr.references(col)));
 
-    // Collect deferred order updates to apply after the mutable iteration.
+    // Collect deferred order updates to apply after the rows are replaced.
     let mut deferred_order_updates: Vec<(C::Item, OrderKey)> = Vec::new();
 
-    for row in data.iter_mut() {
+    data.update_each(|row| {
+        let row_position = position;
+        position += 1;
         let row_values = row.to_datatypes(&descriptor.field_names);
 
         if !row_matches_mutation_filters(&row_values, mutation_filters) {
-            continue;
+            return None;
         }
 
         // Apply the changed values using FieldExpr::resolve.
@@ -160,27 +218,148 @@ fn merge_update<C: Collection>(
                         .map(|(name, expr)| (*name, expr.resolve(&row_values))),
                 )
                 .collect();
-            Some(order_key_from_values(
-                &descriptor.order_by_field_names,
-                &descriptor.order_by_directions,
-                &all_values,
-            ))
+            Some(order_key_from_values(&descriptor.order_by, &all_values))
         } else {
             None
         };
 
-        if let Ok(updated_row) = C::Item::from_datatypes(&mut updated_values.into_iter()) {
-            if let Some(ref order_key) = new_order_key {
-                deferred_order_updates.push((updated_row.clone(), order_key.clone()));
-            }
-            *row = updated_row;
+        // Filters on unselected columns match every row, so only rows the
+        // mutation's filters were all checked on can leave. The values of
+        // changes that read unselected columns aren't known.
+        let targeted = mutation_filters
+            .iter()
+            .flat_map(FieldFilter::leaves)
+            .all(|filter| {
+                let column = filter.table_field_pair().field_name;
+                row_values.iter().any(|(col, _)| *col == column)
+            });
+        let leaves_filters = targeted
+            && filters.as_ref().is_some_and(|filters| {
+                let known_values: Vec<(&'static str, Datatype)> = descriptor
+                    .field_names
+                    .iter()
+                    .zip(updated_values.iter())
+                    .map(|(name, val)| (*name, val.clone()))
+                    .chain(
+                        changed
+                            .iter()
+                            .filter(|(_, expr)| expr.resolves_in(&row_values))
+                            .map(|(name, expr)| (*name, expr.resolve(&row_values))),
+                    )
+                    .collect();
+                !insert_matches_filters(&known_values, filters)
+            });
+
+        if leaves_filters {
+            left.push(row_position);
+            return None;
+        }
+        let updated_row = C::Item::from_datatypes(&mut updated_values.into_iter()).ok()?;
+        if !rows.is_empty() {
+            stayed.push(updated_row.clone());
+        }
+        if let Some(order_key) = new_order_key {
+            deferred_order_updates.push((updated_row.clone(), order_key));
+        }
+        if updated_row == *row {
+            return None;
         }
+        updated += 1;
+        Some(updated_row)
+    });
+
+    let mut counts = MergeCounts {
+        updated,
+        removed: left.len(),
+        // Rows whose values changed are counted as updated already.
+        reordered: !deferred_order_updates.is_empty(),
+        ..MergeCounts::default()
+    };
+
+    // Removed first, as moving rows changes their positions.
+    if !left.is_empty() {
+        data.remove_positions(&left);
     }
 
     // Apply deferred order updates.
     for (item, order_key) in deferred_order_updates {
         data.update_order(&item, order_key);
     }
+
+    if let Some(filters) = filters {
+        counts += merge_update_entering(data, descriptor, &filters, rows, stayed);
+    }
+    counts
+}
+
+/// The subscription's filters on the columns of the mutated table, which its
+/// rows' values can be checked against. `None` for a table joined with
+/// itself, whose rows hold the values of every side.
+fn filters_on_table(
+    descriptor: &SubscriptionDescriptor,
+    table_name: &'static str,
+) -> Option<Vec<FieldFilter>> {
+    let mut names = descriptor
+        .tables
+        .iter()
+        .map(|entry| split_table_entry(*entry))
+        .filter(|(table, _)| *table == table_name);
+    let (table, alias) = names.next()?;
+    if names.next().is_some() {
+        return None;
+    }
+    let name = alias.unwrap_or(table);
+
+    Some(
+        descriptor
+            .filters
+            .iter()
+            .filter(|filter| {
+                filter
+                    .leaves()
+                    .iter()
+                    .all(|leaf| leaf.table_field_pair().table_name == name)
+            })
+            .cloned()
+            .collect(),
+    )
+}
+
+/// For an update: add the updated `rows` that moved into the subscription's
+/// filters. Each of the data's rows the update matched and kept, `stayed`,
+/// is one of them already. Rows of a table joined with others lack the
+/// other tables' columns, so they can't be added.
+fn merge_update_entering<C: Collection>(
+    data: &mut C,
+    descriptor: &SubscriptionDescriptor,
+    filters: &[FieldFilter],
+    rows: &[Vec<(&'static str, Datatype)>],
+    mut stayed: Vec<C::Item>,
+) -> MergeCounts {
+    let mut counts = MergeCounts::default();
+    for values in rows {
+        let complete = descriptor
+            .field_names
+            .iter()
+            .all(|field_name| values.iter().any(|(col, _)| col == field_name))
+            && descriptor
+                .order_by
+                .iter()
+                .all(|order| order.expr.resolves_in(values));
+        if !complete || !insert_matches_filters(values, filters) {
+            continue;
+        }
+        let Some(row) = row_from_insert::<C::Item>(descriptor, values) else {
+            continue;
+        };
+        match stayed.iter().position(|kept| *kept == row) {
+            Some(position) => {
+                stayed.swap_remove(position);
+            }
+            None => counts += merge_insert(data, descriptor, values),
+        }
+    }
+    counts
 }
 
 /// Construct a row from inserted values, using the subscription's field ordering.
@@ -208,6 +387,18 @@ pub(crate) fn row_from_insert<T: SubscribableRow>(
     T::from_datatypes(&mut ordered_values.into_iter()).ok()
 }
 
+/// The last row of a bulk insert that matches the subscription, which is
+/// what a single-row output ends up holding.
+pub(crate) fn last_row_from_bulk_insert<T: SubscribableRow>(
+    descriptor: &SubscriptionDescriptor,
+    rows: &[Vec<(&'static str, Datatype)>],
+) -> Option<T> {
+    rows.iter()
+        .rev()
+        .filter(|values| insert_matches_filters(values, &descriptor.filters))
+        .find_map(|values| row_from_insert(descriptor, values))
+}
+
 /// Apply changed values to a single row if it matches the mutation's filters.
 /// Returns `true` if the row was modified.
 pub(crate) fn merge_update_single_row<T: SubscribableRow>(
@@ -252,37 +443,213 @@ pub(crate) fn merge_update_single_row<T: SubscribableRow>(
     false
 }
 
+/// Whether the delete `event` removes the single-row output `row`.
+pub(crate) fn delete_removes_row<T: SubscribableRow>(
+    row: &T,
+    descriptor: &SubscriptionDescriptor,
+    event: &MutationEvent,
+) -> bool {
+    let MutationEventKind::Delete { filters } = &event.kind else {
+        return false;
+    };
+    row_matches_mutation_filters(&row.to_datatypes(&descriptor.field_names), filters)
+}
+
 /// For a delete: remove rows that match the mutation's filters.
 fn merge_delete<C: Collection>(
     data: &mut C,
     descriptor: &SubscriptionDescriptor,
     mutation_filters: &[FieldFilter],
-) {
+) -> MergeCounts {
+    let mut removed = 0;
     data.retain(|row| {
         let row_values = row.to_datatypes(&descriptor.field_names);
-        !row_matches_mutation_filters(&row_values, mutation_filters)
+        let matches = row_matches_mutation_filters(&row_values, mutation_filters);
+        removed += matches as usize;
+        !matches
     });
+    MergeCounts {
+        removed,
+        ..MergeCounts::default()
+    }
 }
 
-/// Check if a row's values satisfy all of the mutation's filters.
+/// Check if a row's values satisfy all of the mutation's filters. Filters
+/// on columns the row doesn't have could pass, so they do.
 pub(crate) fn row_matches_mutation_filters(
     row_values: &[(&'static str, Datatype)],
     mutation_filters: &[FieldFilter],
 ) -> bool {
-    for filter in mutation_filters {
-        let column = filter.table_field_pair().field_name;
+    insert_matches_filters(row_values, mutation_filters)
+}
 
-        let Some(value) = row_values
-            .iter()
-            .find_map(|(col, val)| if *col == column { Some(val) } else { None })
-        else {
-            continue;
-        };
+#[cfg(test)]
+mod tests {
+    use smallvec::{SmallVec, smallvec};
+
+    use super::*;
+    use crate::{ChangeReason, FieldFilterMetadata, TableFieldPair};
 
-        if !super::overlap::filter_satisfied_by_value(filter, value) {
-            return false;
+    fn eq(column: &'static str, value: Datatype) -> FieldFilter {
+        FieldFilter::Eq(FieldFilterMetadata {
+            left: TableFieldPair::new("tasks", column),
+            right: value,
+        })
+    }
+
+    fn task(id: i64, status: &str) -> Vec<(&'static str, Datatype)> {
+        vec![
+            ("id", Datatype::BigInt(id)),
+            ("project", Datatype::BigInt(7)),
+            ("status", Datatype::Text(status.into())),
+        ]
+    }
+
+    fn set_status(id: i64, status: &str) -> MutationEvent {
+        MutationEvent {
+            table_name: "tasks",
+            sequence: 1,
+            kind: MutationEventKind::Update {
+                changed: vec![("status", FieldExpr::Literal(Datatype::Text(status.into())))],
+                filters: smallvec![eq("id", Datatype::BigInt(id))],
+                rows: vec![task(id, status)],
+            },
         }
     }
 
-    true
+    fn active_tasks() -> SubscriptionDescriptor {
+        SubscriptionDescriptor {
+            tables: smallvec!["tasks"],
+            field_names: smallvec!["id", "status"],
+            filters: smallvec![eq("status", Datatype::Text("active".into()))],
+            order_by: SmallVec::new(),
+            primary_key: Some("id"),
+        }
+    }
+
+    #[test]
+    fn inserts_replace_rows_with_the_same_primary_key() {
+        let mut descriptor = active_tasks();
+        descriptor.filters.clear();
+        let mut data: Vec<(i64, String)> = vec![(1, "draft".into()), (2, "active".into())];
+        let insert = MutationEvent {
+            table_name: "tasks",
+            sequence: 1,
+            kind: MutationEventKind::Insert {
+                values: vec![
+                    ("id", Datatype::BigInt(1)),
+                    ("status", Datatype::Text("active".into())),
+                ],
+            },
+        };
+
+        let counts = merge_event_into_data(&mut data, &descriptor, &insert);
+        assert_eq!(data, vec![(1, "active".into()), (2, "active".into())]);
+        assert_eq!(counts.reason(), Some(ChangeReason::RowsUpdated(1)));
+        assert!(merge_event_into_data(&mut data, &descriptor, &insert).is_empty());
+    }
+
+    #[test]
+    fn updates_move_rows_out_of_and_into_the_filters() {
+        let descriptor = active_tasks();
+        let mut data: Vec<(i64, String)> = vec![(1, "active".into()), (2, "active".into())];
+
+        let left = merge_event_into_data(&mut data, &descriptor, &set_status(1, "archived"));
+        assert_eq!(data, vec![(2, "active".into())]);
+        assert_eq!(left.reason(), Some(ChangeReason::RowsRemoved(1)));
+
+        let entered = merge_event_into_data(&mut data, &descriptor, &set_status(3, "active"));
+        assert_eq!(data, vec![(2, "active".into()), (3, "active".into())]);
+        assert_eq!(entered.reason(), Some(ChangeReason::RowsInserted(1)));
+    }
+
+    #[test]
+    fn updates_remove_only_the_rows_that_left() {
+        let mut descriptor = active_tasks();
+        descriptor.field_names = smallvec!["status"];
+        descriptor.primary_key = None;
+        // The second row is equal to what the first becomes, but the update
+        // doesn't match it.
+        let mut data: Vec<String> = vec!["active".into(), "archived".into()];
+        let archive_active = MutationEvent {
+            table_name: "tasks",
+            sequence: 1,
+            kind: MutationEventKind::Update {
+                changed: vec![(
+                    "status",
+                    FieldExpr::Literal(Datatype::Text("archived".into())),
+                )],
+                filters: smallvec![eq("status", Datatype::Text("active".into()))],
+                rows: Vec::new(),
+            },
+        };
+
+        merge_event_into_data(&mut data, &descriptor, &archive_active);
+        assert_eq!(data, vec!["archived".to_string()]);
+    }
+
+    #[test]
+    fn updates_only_add_the_rows_they_returned() {
+        let descriptor = active_tasks();
+        let mut data: Vec<(i64, String)> = vec![(2, "active".into())];
+        let activate_project = |rows| MutationEvent {
+            table_name: "tasks",
+            sequence: 1,
+            kind: MutationEventKind::Update {
+                changed: vec![(
+                    "status",
+                    FieldExpr::Literal(Datatype::Text("active".into())),
+                )],
+                filters: smallvec![eq("project", Datatype::BigInt(7))],
+                rows,
+            },
+        };
+
+        // Without the rows, the update's filters don't say which rows it set.
+        merge_event_into_data(&mut data, &descriptor, &activate_project(Vec::new()));
+        assert_eq!(data, vec![(2, "active".into())]);
+
+        let rows = vec![task(1, "active"), task(2, "active"), task(4, "active")];
+        merge_event_into_data(&mut data, &descriptor, &activate_project(rows));
+        assert_eq!(
+            data,
+            vec![
+                (2, "active".into()),
+                (1, "active".into()),
+                (4, "active".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_move_rows_out_of_and_into_is_null_filters() {
+        let assignee = TableFieldPair::new("tasks", "assignee");
+        let descriptor = SubscriptionDescriptor {
+            tables: smallvec!["tasks"],
+            field_names: smallvec!["id", "assignee"],
+            filters: smallvec![FieldFilter::IsNull(assignee)],
+            order_by: SmallVec::new(),
+            primary_key: Some("id"),
+        };
+        let assign = |id: i64, assignee: Datatype| MutationEvent {
+            table_name: "tasks",
+            sequence: 1,
+            kind: MutationEventKind::Update {
+                changed: vec![("assignee", FieldExpr::Literal(assignee.clone()))],
+                filters: smallvec![eq("id", Datatype::BigInt(id))],
+                rows: vec![vec![("id", Datatype::BigInt(id)), ("assignee", assignee)]],
+            },
+        };
+        let mut data: Vec<(i64, Option<String>)> = vec![(1, None), (2, None)];
+
+        merge_event_into_data(
+            &mut data,
+            &descriptor,
+            &assign(1, Datatype::Text("bob".into())),
+        );
+        assert_eq!(data, vec![(2, None)]);
+
+        merge_event_into_data(&mut data, &descriptor, &assign(3, Datatype::Null));
+        assert_eq!(data, vec![(2, None), (3, None)]);
+    }
 }
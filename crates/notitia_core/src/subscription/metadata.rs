@@ -1,7 +1,108 @@
+use std::ops::AddAssign;
+
 use super::MutationEvent;
 
 #[derive(Clone, Debug)]
 pub enum SubscriptionMetadata {
     None,
-    Changed(MutationEvent),
+    /// The data changed, last by the event, in the way the reason says.
+    Changed(MutationEvent, ChangeReason),
+}
+
+/// What a change did to a subscription's data, worked out while merging it,
+/// e.g. for a list to scroll to new rows or to mark them unread without
+/// comparing its rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangeReason {
+    /// Rows were added, and none removed.
+    RowsInserted(usize),
+    /// Rows changed in place, and some may have moved with them, or rows
+    /// were both added and removed. Also the reason when the row of a
+    /// `fetch_one` or `fetch_first` is replaced.
+    RowsUpdated(usize),
+    /// Rows were removed, and none added.
+    RowsRemoved(usize),
+    /// The rows are the same, in another order.
+    Reordered,
+    /// The data was computed anew rather than merged, by a
+    /// [requerying](crate::QueryExecutor::subscribe_requerying) or
+    /// [derived](crate::Notitia::derive) subscription.
+    Recomputed,
+}
+
+/// What merging events did to a subscription's data, counted by the merge
+/// itself, so the [`ChangeReason`] of a batch adds up without comparing the
+/// data before and after it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeCounts {
+    pub inserted: usize,
+    pub updated: usize,
+    pub removed: usize,
+    /// Rows moved without their selected values changing, e.g. as an update
+    /// set a column the query is ordered by but doesn't select.
+    pub reordered: bool,
+}
+
+impl MergeCounts {
+    /// Whether the merge left the data as it was.
+    pub fn is_empty(&self) -> bool {
+        self.inserted == 0 && self.updated == 0 && self.removed == 0 && !self.reordered
+    }
+
+    /// How the data changed, or `None` if it didn't. Rows both added and
+    /// removed count as updated.
+    pub fn reason(&self) -> Option<ChangeReason> {
+        Some(match (self.inserted, self.updated, self.removed) {
+            (0, 0, 0) if self.reordered => ChangeReason::Reordered,
+            (0, 0, 0) => return None,
+            (inserted, 0, 0) => ChangeReason::RowsInserted(inserted),
+            (0, 0, removed) => ChangeReason::RowsRemoved(removed),
+            (inserted, updated, removed) => ChangeReason::RowsUpdated(inserted + updated + removed),
+        })
+    }
+}
+
+impl AddAssign for MergeCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.removed += other.removed;
+        self.reordered |= other.reordered;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_inserts_removals_updates_and_moves_apart() {
+        let reason = |inserted, updated, removed, reordered| {
+            MergeCounts {
+                inserted,
+                updated,
+                removed,
+                reordered,
+            }
+            .reason()
+        };
+        assert_eq!(reason(0, 0, 0, false), None);
+        assert_eq!(reason(1, 0, 0, false), Some(ChangeReason::RowsInserted(1)));
+        assert_eq!(reason(0, 0, 2, true), Some(ChangeReason::RowsRemoved(2)));
+        assert_eq!(reason(0, 0, 0, true), Some(ChangeReason::Reordered));
+        assert_eq!(reason(1, 0, 1, false), Some(ChangeReason::RowsUpdated(2)));
+        assert_eq!(reason(0, 1, 0, true), Some(ChangeReason::RowsUpdated(1)));
+
+        let mut batch = MergeCounts::default();
+        batch += MergeCounts {
+            inserted: 2,
+            ..MergeCounts::default()
+        };
+        batch += MergeCounts {
+            inserted: 1,
+            ..MergeCounts::default()
+        };
+        assert_eq!(batch.reason(), Some(ChangeReason::RowsInserted(3)));
+    }
 }
@@ -1,3 +1,6 @@
+mod delivery;
+pub use delivery::*;
+
 mod descriptor;
 pub use descriptor::*;
 
@@ -15,5 +18,11 @@ pub use metadata::*;
 
 pub(crate) mod overlap;
 
+mod pool;
+pub use pool::*;
+
 mod registry;
 pub use registry::*;
+
+mod strategy;
+pub use strategy::*;
@@ -1,21 +1,32 @@
 use std::cmp::Ordering;
 
-use crate::{Datatype, FieldFilter};
+use crate::{Datatype, FieldFilter, TableFieldPair, function::is_truthy, split_table_entry};
 
 use super::{MutationEvent, MutationEventKind, SubscriptionDescriptor};
 
 /// Check if a mutation event could affect a subscription.
 pub fn event_matches_descriptor(event: &MutationEvent, desc: &SubscriptionDescriptor) -> bool {
     // The mutation must affect a table the subscription depends on.
-    if !desc.tables.contains(&event.table_name) {
+    let joined = desc
+        .tables
+        .iter()
+        .filter(|entry| split_table_entry(**entry).0 == event.table_name)
+        .count();
+    if joined == 0 {
         return false;
     }
+    // A table joined with itself has the mutated rows on every side of the
+    // join, and the filters of one side say nothing about the others.
+    if joined > 1 {
+        return true;
+    }
 
     match &event.kind {
         MutationEventKind::Insert { values } => insert_matches_filters(values, &desc.filters),
         MutationEventKind::Update {
             changed,
             filters: mutation_filters,
+            rows,
         } => {
             // The mutation must touch at least one column the subscription selects.
             let touches_selected_column = changed
@@ -30,22 +41,27 @@ pub fn event_matches_descriptor(event: &MutationEvent, desc: &SubscriptionDescri
                 let touches_filtered_column = changed.iter().any(|(col, _)| {
                     desc.filters
                         .iter()
+                        .flat_map(FieldFilter::leaves)
                         .any(|f| f.table_field_pair().field_name == *col)
                 });
 
                 // Also check if the mutation changes an ORDER BY column, which affects
                 // the sort position even if it's not a selected column.
-                let touches_order_column = changed
-                    .iter()
-                    .any(|(col, _)| desc.order_by_field_names.contains(col));
+                let touches_order_column = desc
+                    .orders_on_table(event.table_name)
+                    .any(|order| changed.iter().any(|(col, _)| order.expr.references(col)));
 
                 if !touches_filtered_column && !touches_order_column {
                     return false;
                 }
             }
 
-            // Check if the mutation's target rows could overlap with the subscription's rows.
+            // Check if the mutation's target rows could overlap with the
+            // subscription's rows, or an updated row moved into them.
             !filters_provably_disjoint(&desc.filters, mutation_filters)
+                || rows
+                    .iter()
+                    .any(|values| insert_matches_filters(values, &desc.filters))
         }
         MutationEventKind::Delete {
             filters: mutation_filters,
@@ -53,41 +69,125 @@ pub fn event_matches_descriptor(event: &MutationEvent, desc: &SubscriptionDescri
             // Check if the delete's target rows could overlap with the subscription's rows.
             !filters_provably_disjoint(&desc.filters, mutation_filters)
         }
+        MutationEventKind::BulkInsert { rows } => rows
+            .iter()
+            .any(|values| insert_matches_filters(values, &desc.filters)),
     }
 }
 
 /// Check if an inserted row satisfies all of the subscription's filters.
-fn insert_matches_filters(
+pub(crate) fn insert_matches_filters(
     values: &[(&'static str, Datatype)],
     sub_filters: &[FieldFilter],
 ) -> bool {
-    for filter in sub_filters {
-        let column = filter.table_field_pair().field_name;
+    sub_filters.iter().all(|filter| {
+        filter_outcomes(filter, &|leaf| {
+            let column = leaf.table_field_pair().field_name;
+            // A column not present in the insert could have any value.
+            let value = values
+                .iter()
+                .find_map(|(col, val)| if *col == column { Some(val) } else { None });
+            leaf_outcomes(leaf, value)
+        })
+        .can_pass
+    })
+}
 
-        // Find the inserted value for this column.
-        let Some(value) = values
-            .iter()
-            .find_map(|(col, val)| if *col == column { Some(val) } else { None })
-        else {
-            // Column not present in insert — can't confirm match, be conservative.
-            return true;
-        };
+/// Whether a filter can be true for a row, and whether it can be false.
+/// When it compares with NULL it is neither, and so is its negation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Outcomes {
+    pub can_pass: bool,
+    pub can_fail: bool,
+}
 
-        if !filter_satisfied_by_value(filter, value) {
-            return false;
+impl Outcomes {
+    const ANY: Self = Self {
+        can_pass: true,
+        can_fail: true,
+    };
+
+    fn of(truth: Option<bool>) -> Self {
+        Self {
+            can_pass: truth == Some(true),
+            can_fail: truth == Some(false),
         }
     }
+}
 
-    true
+/// The outcomes of `filter`, from those of its single-column filters.
+pub(crate) fn filter_outcomes(
+    filter: &FieldFilter,
+    leaf: &impl Fn(&FieldFilter) -> Outcomes,
+) -> Outcomes {
+    match filter {
+        FieldFilter::Or(filters) => {
+            let outcomes: Vec<_> = filters.iter().map(|f| filter_outcomes(f, leaf)).collect();
+            Outcomes {
+                can_pass: outcomes.iter().any(|o| o.can_pass),
+                can_fail: outcomes.iter().all(|o| o.can_fail),
+            }
+        }
+        FieldFilter::Not(filter) => {
+            let outcomes = filter_outcomes(filter, leaf);
+            Outcomes {
+                can_pass: outcomes.can_fail,
+                can_fail: outcomes.can_pass,
+            }
+        }
+        _ => leaf(filter),
+    }
 }
 
-/// Check if a single filter condition is satisfied by a given value.
-pub(crate) fn filter_satisfied_by_value(filter: &FieldFilter, value: &Datatype) -> bool {
+/// The outcomes of a single-column filter for the column's `value`, which
+/// could be anything if it isn't known.
+pub(crate) fn leaf_outcomes(filter: &FieldFilter, value: Option<&Datatype>) -> Outcomes {
+    match (filter, value) {
+        (_, None) => Outcomes::ANY,
+        // The other column isn't known, except that it can't be NULL.
+        (FieldFilter::EqColumn(_), Some(Datatype::Null)) => Outcomes::of(None),
+        (FieldFilter::EqColumn(_), Some(_)) => Outcomes::ANY,
+        (_, Some(value)) => Outcomes::of(filter_truth(filter, value)),
+    }
+}
+
+/// The value of a single-column filter for the column's `value`: `None`
+/// where SQL's is NULL.
+fn filter_truth(filter: &FieldFilter, value: &Datatype) -> Option<bool> {
     match filter {
-        FieldFilter::In(m) => m.right.contains(value),
+        FieldFilter::In(_) | FieldFilter::ContainsElement(_) if *value == Datatype::Null => None,
+        FieldFilter::In(m) => Some(m.right.contains(value)),
+        FieldFilter::Call(m) => {
+            let mut args = Vec::with_capacity(m.args.len() + 1);
+            args.push(value.clone());
+            args.extend(m.args.iter().cloned());
+            match m.function.call(&args) {
+                Datatype::Null => None,
+                result => Some(is_truthy(&result)),
+            }
+        }
+        FieldFilter::EqNullable(m) => Some(*value == m.right),
+        FieldFilter::DistinctFrom(m) => Some(*value != m.right),
+        FieldFilter::IsNull(_) => Some(*value == Datatype::Null),
+        FieldFilter::IsNotNull(_) => Some(*value != Datatype::Null),
+        FieldFilter::ContainsElement(m) => Some(match value {
+            Datatype::Array(items) => items.iter().any(|item| same_value(item, &m.right)),
+            Datatype::Text(json) => Datatype::array_from_json(json)
+                .is_some_and(|items| items.iter().any(|item| same_value(item, &m.right))),
+            _ => false,
+        }),
+        FieldFilter::EqColumn(_) | FieldFilter::Or(_) | FieldFilter::Not(_) => {
+            unreachable!("evaluated by leaf_outcomes and filter_outcomes")
+        }
         _ => {
             let expected = &filter.metadata().right;
-            match filter {
+            // Like in SQL, comparing with NULL is never true. NaN is
+            // written as NULL.
+            let unknown = |value: &Datatype| *value == Datatype::Null || value.is_nan();
+            if unknown(value) || unknown(expected) {
+                return None;
+            }
+            Some(match filter {
                 FieldFilter::Eq(_) => value == expected,
                 FieldFilter::Ne(_) => value != expected,
                 FieldFilter::Gt(_) => {
@@ -102,98 +202,458 @@ pub(crate) fn filter_satisfied_by_value(filter: &FieldFilter, value: &Datatype)
                     value.partial_cmp(expected),
                     Some(Ordering::Less | Ordering::Equal)
                 ),
-                FieldFilter::In(_) => unreachable!(),
-            }
+                FieldFilter::In(_)
+                | FieldFilter::Call(_)
+                | FieldFilter::EqNullable(_)
+                | FieldFilter::DistinctFrom(_)
+                | FieldFilter::ContainsElement(_)
+                | FieldFilter::EqColumn(_)
+                | FieldFilter::IsNull(_)
+                | FieldFilter::IsNotNull(_)
+                | FieldFilter::Or(_)
+                | FieldFilter::Not(_) => unreachable!(),
+            })
         }
     }
 }
 
 /// Returns true if the two filter sets are provably disjoint (no row can match both).
 /// Conservative: returns false (not disjoint) when uncertain.
+///
+/// Both sets are conjunctions, so they are disjoint when the filters of both
+/// on some column can't hold at the same time, e.g. `age > 30` against
+/// `age >= 18 AND age <= 30`.
 fn filters_provably_disjoint(
     sub_filters: &[FieldFilter],
     mutation_filters: &[FieldFilter],
 ) -> bool {
-    // For each pair of filters on the same (table, column), check if they're contradictory.
-    for sf in sub_filters {
-        let s_pair = sf.table_field_pair();
+    let mut columns: Vec<(&TableFieldPair, ColumnConstraint)> = Vec::new();
 
-        for mf in mutation_filters {
-            let m_pair = mf.table_field_pair();
-
-            // Only compare filters on the same table and column.
-            if s_pair.table_name != m_pair.table_name || s_pair.field_name != m_pair.field_name {
-                continue;
-            }
-
-            if pair_provably_disjoint(sf, mf) {
-                return true;
+    // An `Or` or `Not` says too little about any one column to rule rows
+    // out, so only the single-column filters are compared.
+    for filter in sub_filters
+        .iter()
+        .chain(mutation_filters)
+        .filter(|filter| !filter.is_compound())
+    {
+        let pair = filter.table_field_pair();
+        match columns.iter_mut().find(|(column, _)| *column == pair) {
+            Some((_, constraint)) => constraint.add(filter),
+            None => {
+                let mut constraint = ColumnConstraint::default();
+                constraint.add(filter);
+                columns.push((pair, constraint));
             }
         }
     }
 
-    false
+    columns
+        .iter()
+        .any(|(_, constraint)| constraint.is_unsatisfiable())
 }
 
-/// Check if two filters on the same column are provably disjoint.
-fn pair_provably_disjoint(a: &FieldFilter, b: &FieldFilter) -> bool {
-    // In filters need special handling — be conservative.
-    if matches!(a, FieldFilter::In(_)) || matches!(b, FieldFilter::In(_)) {
-        return false;
+/// What the filters on one column require of its value.
+#[derive(Default)]
+struct ColumnConstraint<'a> {
+    /// A filter no value passes, like `= NULL`.
+    impossible: bool,
+    /// The column must be NULL.
+    null: bool,
+    /// The column can't be NULL, since a comparison filters on it.
+    not_null: bool,
+    /// The values the column must be one of, from `Eq` and `In` filters.
+    candidates: Option<Vec<&'a Datatype>>,
+    /// With whether the bound itself is allowed.
+    lower: Option<(&'a Datatype, bool)>,
+    upper: Option<(&'a Datatype, bool)>,
+    /// Values the column can't have if it isn't NULL.
+    excluded: Vec<&'a Datatype>,
+}
+
+impl<'a> ColumnConstraint<'a> {
+    fn add(&mut self, filter: &'a FieldFilter) {
+        match filter {
+            FieldFilter::In(m) => {
+                self.not_null = true;
+                self.restrict(m.right.iter().filter(|v| **v != Datatype::Null).collect());
+            }
+            // Opaque to us.
+            FieldFilter::Call(_) => {}
+            // Says nothing about the array as a whole, except that it's there.
+            FieldFilter::ContainsElement(_) => self.not_null = true,
+            FieldFilter::EqColumn(_) => self.not_null = true,
+            FieldFilter::IsNull(_) => self.null = true,
+            FieldFilter::IsNotNull(_) => self.not_null = true,
+            FieldFilter::EqNullable(m) if m.right == Datatype::Null => self.null = true,
+            FieldFilter::DistinctFrom(m) if m.right == Datatype::Null => self.not_null = true,
+            FieldFilter::DistinctFrom(m) => self.excluded.push(&m.right),
+            _ => {
+                let value = &filter.metadata().right;
+                if *value == Datatype::Null {
+                    self.impossible = true;
+                    return;
+                }
+                self.not_null = true;
+
+                match filter {
+                    FieldFilter::Eq(_) | FieldFilter::EqNullable(_) => self.restrict(vec![value]),
+                    FieldFilter::Ne(_) => self.excluded.push(value),
+                    FieldFilter::Gt(_) => self.tighten_lower(value, false),
+                    FieldFilter::Gte(_) => self.tighten_lower(value, true),
+                    FieldFilter::Lt(_) => self.tighten_upper(value, false),
+                    FieldFilter::Lte(_) => self.tighten_upper(value, true),
+                    FieldFilter::In(_)
+                    | FieldFilter::Call(_)
+                    | FieldFilter::DistinctFrom(_)
+                    | FieldFilter::ContainsElement(_)
+                    | FieldFilter::EqColumn(_)
+                    | FieldFilter::IsNull(_)
+                    | FieldFilter::IsNotNull(_)
+                    | FieldFilter::Or(_)
+                    | FieldFilter::Not(_) => unreachable!(),
+                }
+            }
+        }
     }
 
-    let a_val = &a.metadata().right;
-    let b_val = &b.metadata().right;
+    fn restrict(&mut self, values: Vec<&'a Datatype>) {
+        self.candidates = Some(match self.candidates.take() {
+            None => values,
+            Some(current) => current
+                .into_iter()
+                .filter(|candidate| values.iter().any(|v| same_value(candidate, v)))
+                .collect(),
+        });
+    }
 
-    match (a, b) {
-        // Eq(x) vs Eq(y) where x != y
-        (FieldFilter::Eq(_), FieldFilter::Eq(_)) => a_val != b_val,
+    fn tighten_lower(&mut self, value: &'a Datatype, inclusive: bool) {
+        self.lower = match self.lower {
+            Some((current, current_inclusive)) => match compare(value, current) {
+                Some(Ordering::Greater) => Some((value, inclusive)),
+                Some(Ordering::Equal) => Some((current, current_inclusive && inclusive)),
+                Some(Ordering::Less) => Some((current, current_inclusive)),
+                // Bounds of different types; keep one and stay conservative.
+                None => Some((current, current_inclusive)),
+            },
+            None => Some((value, inclusive)),
+        };
+    }
 
-        // Eq(x) vs Ne(x) — always disjoint
-        (FieldFilter::Eq(_), FieldFilter::Ne(_)) | (FieldFilter::Ne(_), FieldFilter::Eq(_)) => {
-            a_val == b_val
-        }
+    fn tighten_upper(&mut self, value: &'a Datatype, inclusive: bool) {
+        self.upper = match self.upper {
+            Some((current, current_inclusive)) => match compare(value, current) {
+                Some(Ordering::Less) => Some((value, inclusive)),
+                Some(Ordering::Equal) => Some((current, current_inclusive && inclusive)),
+                Some(Ordering::Greater) | None => Some((current, current_inclusive)),
+            },
+            None => Some((value, inclusive)),
+        };
+    }
 
-        // Eq(x) vs Gt(y) — disjoint if x <= y
-        (FieldFilter::Eq(_), FieldFilter::Gt(_)) | (FieldFilter::Gt(_), FieldFilter::Eq(_)) => {
-            let (eq_val, gt_val) = if matches!(a, FieldFilter::Eq(_)) {
-                (a_val, b_val)
-            } else {
-                (b_val, a_val)
-            };
+    /// Whether a non-NULL `value` provably fails the bounds or exclusions.
+    fn rules_out(&self, value: &Datatype) -> bool {
+        let below_lower = self.lower.is_some_and(|(lower, inclusive)| {
             matches!(
-                eq_val.partial_cmp(gt_val),
-                Some(Ordering::Less | Ordering::Equal)
+                (compare(value, lower), inclusive),
+                (Some(Ordering::Less), _) | (Some(Ordering::Equal), false)
             )
-        }
-
-        // Eq(x) vs Lt(y) — disjoint if x >= y
-        (FieldFilter::Eq(_), FieldFilter::Lt(_)) | (FieldFilter::Lt(_), FieldFilter::Eq(_)) => {
-            let (eq_val, lt_val) = if matches!(a, FieldFilter::Eq(_)) {
-                (a_val, b_val)
-            } else {
-                (b_val, a_val)
-            };
+        });
+        let above_upper = self.upper.is_some_and(|(upper, inclusive)| {
             matches!(
-                eq_val.partial_cmp(lt_val),
-                Some(Ordering::Greater | Ordering::Equal)
+                (compare(value, upper), inclusive),
+                (Some(Ordering::Greater), _) | (Some(Ordering::Equal), false)
             )
+        });
+        let excluded = self
+            .excluded
+            .iter()
+            .any(|excluded| same_value(value, excluded));
+
+        below_lower || above_upper || excluded
+    }
+
+    fn is_unsatisfiable(&self) -> bool {
+        if self.impossible || (self.null && self.not_null) {
+            return true;
+        }
+        // A NULL column passes every remaining filter.
+        if !self.not_null {
+            return false;
         }
 
-        // Gt(x) vs Lt(y) — disjoint if x >= y
-        (FieldFilter::Gt(_), FieldFilter::Lt(_)) | (FieldFilter::Lt(_), FieldFilter::Gt(_)) => {
-            let (gt_val, lt_val) = if matches!(a, FieldFilter::Gt(_)) {
-                (a_val, b_val)
-            } else {
-                (b_val, a_val)
-            };
-            matches!(
-                gt_val.partial_cmp(lt_val),
-                Some(Ordering::Greater | Ordering::Equal)
-            )
+        if let Some(candidates) = &self.candidates {
+            return candidates.iter().all(|candidate| self.rules_out(candidate));
+        }
+
+        match (self.lower, self.upper) {
+            (Some((lower, lower_inclusive)), Some((upper, upper_inclusive))) => {
+                match compare(lower, upper) {
+                    Some(Ordering::Greater) => true,
+                    // Only the bound itself is left.
+                    Some(Ordering::Equal) => {
+                        !lower_inclusive || !upper_inclusive || self.rules_out(lower)
+                    }
+                    Some(Ordering::Less) | None => false,
+                }
+            }
+            _ => false,
         }
+    }
+}
+
+/// Compare two values like SQLite does, or `None` when they are of
+/// different kinds and the answer depends on column affinity.
+fn compare(a: &Datatype, b: &Datatype) -> Option<Ordering> {
+    let as_f64 = |value: &Datatype| match value {
+        Datatype::Int(v) => Some(*v as f64),
+        Datatype::BigInt(v) => Some(*v as f64),
+        Datatype::Float(v) => Some(*v as f64),
+        Datatype::Double(v) => Some(*v),
+        _ => None,
+    };
+
+    match (a, b) {
+        (Datatype::Int(_) | Datatype::BigInt(_), Datatype::Int(_) | Datatype::BigInt(_))
+        | (Datatype::Text(_), Datatype::Text(_))
+        | (Datatype::Blob(_), Datatype::Blob(_))
+        | (Datatype::Bool(_), Datatype::Bool(_)) => Some(a.cmp(b)),
+        _ => as_f64(a)?.partial_cmp(&as_f64(b)?),
+    }
+}
+
+fn same_value(a: &Datatype, b: &Datatype) -> bool {
+    compare(a, b) == Some(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::SmallVec;
+
+    use super::*;
+    use crate::{FieldExpr, FieldFilterInMetadata, FieldFilterMetadata, OrderBy, OrderDirection};
+
+    fn age(build: fn(FieldFilterMetadata) -> FieldFilter, value: i64) -> FieldFilter {
+        build(FieldFilterMetadata {
+            left: TableFieldPair::new("users", "age"),
+            right: Datatype::BigInt(value),
+        })
+    }
+
+    fn age_in(values: &[i64]) -> FieldFilter {
+        FieldFilter::In(FieldFilterInMetadata {
+            left: TableFieldPair::new("users", "age"),
+            right: values.iter().map(|v| Datatype::BigInt(*v)).collect(),
+        })
+    }
+
+    #[test]
+    fn in_sets() {
+        let sub = [age_in(&[1, 2])];
+        assert!(filters_provably_disjoint(&sub, &[age_in(&[3, 4])]));
+        assert!(filters_provably_disjoint(&sub, &[age(FieldFilter::Gt, 2)]));
+        assert!(!filters_provably_disjoint(&sub, &[age_in(&[2, 3])]));
+    }
+
+    #[test]
+    fn ne_against_a_single_value_range() {
+        let sub = [age(FieldFilter::Ne, 5)];
+        let mutation = [age(FieldFilter::Gte, 5), age(FieldFilter::Lte, 5)];
+        assert!(filters_provably_disjoint(&sub, &mutation));
+        assert!(!filters_provably_disjoint(
+            &sub,
+            &[age(FieldFilter::Gte, 5)]
+        ));
+    }
+
+    #[test]
+    fn mutation_filters_jointly() {
+        let sub = [age(FieldFilter::Eq, 30)];
+        let mutation = [age(FieldFilter::Gte, 18), age(FieldFilter::Lt, 30)];
+        assert!(filters_provably_disjoint(&sub, &mutation));
+
+        let sub = [age(FieldFilter::Gt, 30)];
+        assert!(filters_provably_disjoint(&sub, &mutation));
+        assert!(!filters_provably_disjoint(
+            &sub,
+            &[age(FieldFilter::Gte, 18)]
+        ));
+    }
+
+    #[test]
+    fn null_only_matches_nullable_filters() {
+        let is_null = [FieldFilter::EqNullable(FieldFilterMetadata {
+            left: TableFieldPair::new("users", "age"),
+            right: Datatype::Null,
+        })];
+        assert!(filters_provably_disjoint(
+            &is_null,
+            &[age(FieldFilter::Ne, 5)]
+        ));
+        assert!(!filters_provably_disjoint(
+            &is_null,
+            &[age(FieldFilter::DistinctFrom, 5)]
+        ));
+    }
+
+    #[test]
+    fn is_null_rules_out_comparisons() {
+        let unset = [FieldFilter::IsNull(TableFieldPair::new("users", "age"))];
+        assert!(filters_provably_disjoint(
+            &unset,
+            &[age(FieldFilter::Eq, 5)]
+        ));
+        assert!(filters_provably_disjoint(
+            &unset,
+            &[FieldFilter::IsNotNull(TableFieldPair::new("users", "age"))]
+        ));
+        assert!(insert_matches_filters(&[("age", Datatype::Null)], &unset));
+        assert!(!insert_matches_filters(
+            &[("age", Datatype::BigInt(5))],
+            &unset
+        ));
+    }
+
+    #[test]
+    fn nan_compares_with_nothing() {
+        let above = FieldFilter::Gt(FieldFilterMetadata {
+            left: TableFieldPair::new("players", "score"),
+            right: Datatype::Double(0.0),
+        });
+        assert!(leaf_outcomes(&above, Some(&Datatype::Double(1.0))).can_pass);
+        assert_eq!(
+            leaf_outcomes(&above, Some(&Datatype::Double(f64::NAN))),
+            Outcomes::of(None)
+        );
+    }
+
+    #[test]
+    fn or_and_not_compare_with_null_like_sql() {
+        let row =
+            |age: Datatype, name: &str| vec![("age", age), ("name", Datatype::Text(name.into()))];
+        let minor_or_bob = [FieldFilter::Or(vec![
+            age(FieldFilter::Lt, 18),
+            FieldFilter::Eq(FieldFilterMetadata {
+                left: TableFieldPair::new("users", "name"),
+                right: Datatype::Text("bob".into()),
+            }),
+        ])];
+        assert!(insert_matches_filters(
+            &row(Datatype::BigInt(12), "alice"),
+            &minor_or_bob
+        ));
+        assert!(insert_matches_filters(
+            &row(Datatype::Null, "bob"),
+            &minor_or_bob
+        ));
+        assert!(!insert_matches_filters(
+            &row(Datatype::BigInt(30), "alice"),
+            &minor_or_bob
+        ));
+
+        // `NOT (age < 18)` is NULL for a NULL age, like `age < 18` is.
+        let adult = [FieldFilter::Not(Box::new(age(FieldFilter::Lt, 18)))];
+        assert!(insert_matches_filters(
+            &row(Datatype::BigInt(30), "alice"),
+            &adult
+        ));
+        assert!(!insert_matches_filters(
+            &row(Datatype::BigInt(12), "alice"),
+            &adult
+        ));
+        assert!(!insert_matches_filters(
+            &row(Datatype::Null, "alice"),
+            &adult
+        ));
+        // Unless the insert leaves the age out, and then it could be anything.
+        assert!(insert_matches_filters(
+            &[("name", Datatype::Text("alice".into()))],
+            &adult
+        ));
+    }
+
+    #[test]
+    fn different_columns_are_never_disjoint() {
+        let sub = [age(FieldFilter::Eq, 30)];
+        let mutation = [FieldFilter::Eq(FieldFilterMetadata {
+            left: TableFieldPair::new("users", "name"),
+            right: Datatype::Text("bob".into()),
+        })];
+        assert!(!filters_provably_disjoint(&sub, &mutation));
+    }
+
+    #[test]
+    fn self_joins_match_every_mutation_of_the_table() {
+        let mut desc = SubscriptionDescriptor {
+            tables: smallvec::smallvec!["users"],
+            field_names: smallvec::smallvec!["name"],
+            filters: smallvec::smallvec![age(FieldFilter::Eq, 30)],
+            order_by: Default::default(),
+            primary_key: None,
+        };
+        let event = MutationEvent {
+            table_name: "users",
+            sequence: 0,
+            kind: MutationEventKind::Delete {
+                filters: smallvec::smallvec![age(FieldFilter::Eq, 40)],
+            },
+        };
+        assert!(!event_matches_descriptor(&event, &desc));
+
+        // The deleted users may be the peers of the subscribed ones.
+        desc.tables.push("users AS peers");
+        assert!(event_matches_descriptor(&event, &desc));
+    }
+
+    #[test]
+    fn only_orders_on_the_mutated_table_move_rows() {
+        let desc = SubscriptionDescriptor {
+            tables: smallvec::smallvec!["users", "teams"],
+            field_names: smallvec::smallvec!["name"],
+            filters: SmallVec::new(),
+            order_by: smallvec::smallvec![OrderBy {
+                expr: FieldExpr::Field("created_at"),
+                table: "teams",
+                direction: OrderDirection::Asc,
+            }],
+            primary_key: None,
+        };
+        let update = |table_name| MutationEvent {
+            table_name,
+            sequence: 0,
+            kind: MutationEventKind::Update {
+                changed: vec![("created_at", FieldExpr::Literal(Datatype::BigInt(1)))],
+                filters: SmallVec::new(),
+                rows: Vec::new(),
+            },
+        };
+        assert!(!event_matches_descriptor(&update("users"), &desc));
+        assert!(event_matches_descriptor(&update("teams"), &desc));
+    }
+
+    #[test]
+    fn updated_rows_can_enter_disjoint_filters() {
+        let desc = SubscriptionDescriptor {
+            tables: smallvec::smallvec!["users"],
+            field_names: smallvec::smallvec!["name", "age"],
+            filters: smallvec::smallvec![age(FieldFilter::Eq, 30)],
+            order_by: Default::default(),
+            primary_key: None,
+        };
+        let birthday = |rows| MutationEvent {
+            table_name: "users",
+            sequence: 0,
+            kind: MutationEventKind::Update {
+                changed: vec![("age", FieldExpr::Literal(Datatype::BigInt(30)))],
+                filters: smallvec::smallvec![age(FieldFilter::Eq, 29)],
+                rows,
+            },
+        };
+        assert!(!event_matches_descriptor(&birthday(Vec::new()), &desc));
 
-        // For other combinations, be conservative.
-        _ => false,
+        let rows = vec![vec![
+            ("name", Datatype::Text("Ada".into())),
+            ("age", Datatype::BigInt(30)),
+        ]];
+        assert!(event_matches_descriptor(&birthday(rows), &desc));
     }
 }
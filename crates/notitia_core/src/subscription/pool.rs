@@ -0,0 +1,172 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use super::{Delivery, MutationEvent};
+
+type BatchNotify = Box<dyn Fn(&[&MutationEvent], &Delivery) -> bool + Send + Sync>;
+
+/// Threads that merge events into the subscriptions moved onto them with
+/// [`merge_on`](crate::QueryExecutor::merge_on), instead of the task that
+/// ran the mutation, so that an expensive subscription doesn't hold up
+/// writes. Every subscription queues its events and is merged by one
+/// thread at a time, in order; a subscription with a deep queue yields its
+/// thread to the others after every batch.
+///
+/// ```ignore
+/// let pool = MergePool::new(2);
+/// let sub = db.query(stmt).merge_on(&pool).subscribe().await?;
+/// ```
+///
+/// The threads exit once the pool and its subscriptions are dropped.
+#[derive(Clone)]
+pub struct MergePool {
+    ready: crossbeam_channel::Sender<Arc<SubscriptionQueue>>,
+}
+
+impl MergePool {
+    /// A pool of `threads` threads, at least one.
+    pub fn new(threads: usize) -> Self {
+        let (ready, receiver) = crossbeam_channel::unbounded::<Arc<SubscriptionQueue>>();
+        for index in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("notitia-merge-{index}"))
+                .spawn(move || {
+                    for queue in receiver {
+                        queue.merge_next();
+                    }
+                })
+                .expect("failed to spawn a merge thread");
+        }
+        Self { ready }
+    }
+
+    /// A registry callback that queues the events for `notify` to merge on
+    /// the pool. Like `notify`, it returns `false` once the subscription was
+    /// dropped, which the pool notices on the batch after.
+    pub(crate) fn offload(&self, notify: BatchNotify) -> BatchNotify {
+        let queue = Arc::new(SubscriptionQueue {
+            notify,
+            pending: Mutex::new(Pending::default()),
+            alive: AtomicBool::new(true),
+            ready: self.ready.clone(),
+        });
+        Box::new(move |events: &[&MutationEvent], delivery: &Delivery| {
+            if !queue.alive.load(Ordering::Acquire) {
+                return false;
+            }
+            let events = events.iter().map(|event| (*event).clone()).collect();
+            let mut pending = queue.pending.lock().unwrap();
+            pending.batches.push_back((events, delivery.clone()));
+            if !pending.scheduled {
+                pending.scheduled = true;
+                drop(pending);
+                let _ = queue.ready.send(queue.clone());
+            }
+            true
+        })
+    }
+}
+
+/// The batches a subscription has yet to merge.
+struct SubscriptionQueue {
+    notify: BatchNotify,
+    pending: Mutex<Pending>,
+    alive: AtomicBool,
+    ready: crossbeam_channel::Sender<Arc<SubscriptionQueue>>,
+}
+
+#[derive(Default)]
+struct Pending {
+    batches: VecDeque<(Vec<MutationEvent>, Delivery)>,
+    /// Whether the queue is on the pool's channel or being merged, so that
+    /// no two threads merge it at once.
+    scheduled: bool,
+}
+
+impl SubscriptionQueue {
+    /// Merge the oldest batch, then put the queue back behind the others if
+    /// it has more.
+    fn merge_next(self: Arc<Self>) {
+        let Some((events, delivery)) = self.pending.lock().unwrap().batches.pop_front() else {
+            self.pending.lock().unwrap().scheduled = false;
+            return;
+        };
+        let events: Vec<&MutationEvent> = events.iter().collect();
+        if !(self.notify)(&events, &delivery) {
+            self.alive.store(false, Ordering::Release);
+            self.pending.lock().unwrap().batches.clear();
+            return;
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        if pending.batches.is_empty() {
+            pending.scheduled = false;
+        } else {
+            drop(pending);
+            let _ = self.ready.send(self.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use smallvec::SmallVec;
+
+    use crate::MutationEventKind;
+
+    use super::*;
+
+    fn delete(sequence: u64) -> MutationEvent {
+        MutationEvent {
+            table_name: "users",
+            sequence,
+            kind: MutationEventKind::Delete {
+                filters: SmallVec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn merges_every_subscription_in_order_off_the_broadcasting_thread() {
+        let pool = MergePool::new(2);
+        let (merged, receiver) = crossbeam_channel::unbounded();
+        let slow = pool.offload(Box::new(move |events, _| {
+            std::thread::sleep(Duration::from_millis(20));
+            events
+                .iter()
+                .all(|event| merged.send(event.sequence).is_ok())
+        }));
+
+        for sequence in 1..=3 {
+            assert!(slow(&[&delete(sequence)], &Delivery::none()));
+        }
+        // Merging 3 batches takes 60ms, but the broadcasts didn't wait.
+        assert!(receiver.try_recv().is_err());
+
+        let sequences: Vec<u64> = receiver.iter().take(3).collect();
+        assert_eq!(sequences, [1, 2, 3]);
+    }
+
+    #[test]
+    fn stops_queueing_once_the_subscription_is_dropped() {
+        let pool = MergePool::new(1);
+        let (merged, receiver) = crossbeam_channel::bounded(1);
+        let notify = pool.offload(Box::new(move |_, _| merged.send(()).is_ok()));
+
+        drop(receiver);
+        assert!(notify(&[&delete(1)], &Delivery::none()));
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while notify(&[&delete(2)], &Delivery::none()) {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
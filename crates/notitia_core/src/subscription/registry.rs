@@ -1,43 +1,264 @@
-use std::sync::Mutex;
+use std::sync::{
+    Mutex,
+    atomic::{AtomicU64, Ordering},
+};
 
-use super::{MutationEvent, SubscriptionDescriptor};
+use super::{Delivery, MutationEvent, SubscriptionDescriptor};
 
 pub struct SubscriptionRegistry {
     subscribers: Mutex<Vec<SubscriberEntry>>,
+    /// The sequence number of the last event broadcast. Only advanced while
+    /// `subscribers` is locked, so events reach every subscriber in order.
+    sequence: AtomicU64,
 }
 
 struct SubscriberEntry {
-    descriptor: SubscriptionDescriptor,
-    /// Type-erased callback. Returns `false` if the subscriber is dead (channel disconnected).
-    notify: Box<dyn Fn(&MutationEvent) -> bool + Send + Sync>,
+    interest: Interest,
+    notify: Notify,
+}
+
+/// Type-erased callback. Returns `false` if the subscriber is dead (channel disconnected).
+enum Notify {
+    /// Called with each event.
+    Each(Box<dyn Fn(&MutationEvent, &Delivery) -> bool + Send + Sync>),
+    /// Called once per broadcast, with the events of it the subscriber gets.
+    Batch(Box<dyn Fn(&[&MutationEvent], &Delivery) -> bool + Send + Sync>),
+}
+
+/// The events a subscriber gets.
+enum Interest {
+    /// Those that may change the query's output.
+    Query(SubscriptionDescriptor),
+    /// Every event of the table.
+    Table(&'static str),
 }
 
 impl SubscriptionRegistry {
     pub fn new() -> Self {
         Self {
             subscribers: Mutex::new(Vec::new()),
+            sequence: AtomicU64::new(0),
         }
     }
 
     pub fn register(
         &self,
         descriptor: SubscriptionDescriptor,
-        notify: Box<dyn Fn(&MutationEvent) -> bool + Send + Sync>,
+        notify: Box<dyn Fn(&MutationEvent, &Delivery) -> bool + Send + Sync>,
     ) {
         let mut subscribers = self.subscribers.lock().unwrap();
-        subscribers.push(SubscriberEntry { descriptor, notify });
+        subscribers.push(SubscriberEntry {
+            interest: Interest::Query(descriptor),
+            notify: Notify::Each(notify),
+        });
     }
 
-    /// Broadcast a mutation event to all matching subscribers.
+    /// Like [`register`](Self::register), with the events of a
+    /// [batch](Self::broadcast_batch) passed at once, so that the subscriber
+    /// can merge them all before notifying its consumer.
+    pub fn register_batched(
+        &self,
+        descriptor: SubscriptionDescriptor,
+        notify: Box<dyn Fn(&[&MutationEvent], &Delivery) -> bool + Send + Sync>,
+    ) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.push(SubscriberEntry {
+            interest: Interest::Query(descriptor),
+            notify: Notify::Batch(notify),
+        });
+    }
+
+    /// Register a callback for every event of `table_name`.
+    pub fn watch(
+        &self,
+        table_name: &'static str,
+        notify: Box<dyn Fn(&MutationEvent, &Delivery) -> bool + Send + Sync>,
+    ) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.push(SubscriberEntry {
+            interest: Interest::Table(table_name),
+            notify: Notify::Each(notify),
+        });
+    }
+
+    /// Which of `columns`, those of `table_name`, subscribers read off the
+    /// rows an update of the table returns: the ones their queries select,
+    /// filter or order on, or all of them if the table is
+    /// [watched](Self::watch). Empty if no subscriber reads the table.
+    pub fn columns_read_from_updates(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> Vec<&'static str> {
+        let subscribers = self.subscribers.lock().unwrap();
+        columns
+            .iter()
+            .copied()
+            .filter(|column| {
+                subscribers.iter().any(|entry| match &entry.interest {
+                    Interest::Query(descriptor) => {
+                        descriptor.reads_updated_column(table_name, column)
+                    }
+                    Interest::Table(watched) => *watched == table_name,
+                })
+            })
+            .collect()
+    }
+
+    /// The sequence number of the last event broadcast, 0 before the first.
+    pub fn sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Acquire)
+    }
+
+    /// Broadcast a mutation event to all matching subscribers, each keeping
+    /// a copy of `delivery` until its consumer took the change. Returns the
+    /// event with its sequence number.
     /// Removes any subscribers whose channels have been dropped.
-    pub fn broadcast(&self, event: &MutationEvent) {
+    pub fn broadcast(&self, event: &MutationEvent, delivery: &Delivery) -> MutationEvent {
+        let mut events = self.broadcast_batch(std::slice::from_ref(event), delivery);
+        events.pop().unwrap()
+    }
+
+    /// Broadcast `events` under consecutive sequence numbers, e.g. the
+    /// statements of one mutation, passing subscribers registered with
+    /// [`register_batched`](Self::register_batched) the ones they match at
+    /// once, so that they notify their consumers at most once. Returns the
+    /// events with their sequence numbers.
+    pub fn broadcast_batch(
+        &self,
+        events: &[MutationEvent],
+        delivery: &Delivery,
+    ) -> Vec<MutationEvent> {
         let mut subscribers = self.subscribers.lock().unwrap();
+        let events: Vec<MutationEvent> = events
+            .iter()
+            .map(|event| MutationEvent {
+                sequence: self.sequence.fetch_add(1, Ordering::AcqRel) + 1,
+                ..event.clone()
+            })
+            .collect();
         subscribers.retain(|entry| {
-            let matches = super::overlap::event_matches_descriptor(event, &entry.descriptor);
-            if !matches {
+            let matching: Vec<&MutationEvent> = events
+                .iter()
+                .filter(|event| match &entry.interest {
+                    Interest::Query(descriptor) => {
+                        super::overlap::event_matches_descriptor(event, descriptor)
+                    }
+                    Interest::Table(table_name) => event.table_name == *table_name,
+                })
+                .collect();
+            if matching.is_empty() {
                 return true; // not relevant, but still alive
             }
-            (entry.notify)(event) // returns false if channel disconnected
+            // Returns false if channel disconnected.
+            match &entry.notify {
+                Notify::Each(notify) => matching.into_iter().all(|event| notify(event, delivery)),
+                Notify::Batch(notify) => notify(&matching, delivery),
+            }
         });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use smallvec::{SmallVec, smallvec};
+
+    use crate::{Datatype, MutationEventKind};
+
+    use super::*;
+
+    fn users() -> SubscriptionDescriptor {
+        SubscriptionDescriptor {
+            tables: smallvec!["users"],
+            field_names: smallvec!["id"],
+            filters: SmallVec::new(),
+            order_by: SmallVec::new(),
+            primary_key: None,
+        }
+    }
+
+    fn insert(table_name: &'static str) -> MutationEvent {
+        MutationEvent {
+            table_name,
+            sequence: 0,
+            kind: MutationEventKind::Insert {
+                values: vec![("id", Datatype::BigInt(1))],
+            },
+        }
+    }
+
+    #[test]
+    fn numbers_events_in_broadcast_order() {
+        let registry = SubscriptionRegistry::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        registry.register(
+            users(),
+            Box::new(move |event: &MutationEvent, _: &Delivery| {
+                sink.lock().unwrap().push(event.sequence);
+                true
+            }),
+        );
+
+        let event = insert("users");
+        assert_eq!(registry.broadcast(&event, &Delivery::none()).sequence, 1);
+        assert_eq!(registry.broadcast(&event, &Delivery::none()).sequence, 2);
+
+        assert_eq!(*received.lock().unwrap(), [1, 2]);
+        assert_eq!(registry.sequence(), 2);
+    }
+
+    #[test]
+    fn passes_batched_subscribers_the_events_they_match_at_once() {
+        let registry = SubscriptionRegistry::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        registry.register_batched(
+            users(),
+            Box::new(move |events: &[&MutationEvent], _: &Delivery| {
+                let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+                sink.lock().unwrap().push(sequences);
+                true
+            }),
+        );
+
+        let events = [insert("users"), insert("teams"), insert("users")];
+        let broadcast = registry.broadcast_batch(&events, &Delivery::none());
+        registry.broadcast_batch(&[insert("teams")], &Delivery::none());
+
+        let sequences: Vec<u64> = broadcast.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, [1, 2, 3]);
+        assert_eq!(*received.lock().unwrap(), [vec![1, 3]]);
+    }
+
+    #[test]
+    fn updates_return_only_the_columns_subscribers_read() {
+        let registry = SubscriptionRegistry::new();
+        let columns = ["id", "name", "bio"];
+        assert!(
+            registry
+                .columns_read_from_updates("users", &columns)
+                .is_empty()
+        );
+
+        registry.register(users(), Box::new(|_: &MutationEvent, _: &Delivery| true));
+        assert_eq!(
+            registry.columns_read_from_updates("users", &columns),
+            ["id"]
+        );
+        assert!(
+            registry
+                .columns_read_from_updates("teams", &columns)
+                .is_empty()
+        );
+
+        registry.watch("users", Box::new(|_: &MutationEvent, _: &Delivery| true));
+        assert_eq!(
+            registry.columns_read_from_updates("users", &columns),
+            columns
+        );
     }
 }
@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use super::{ChangeReason, Delivery, MutationEvent, SubscriptionMetadata};
+
+/// How a [`Subscription`](super::Subscription) hands its changes to the
+/// consumer, see
+/// [`subscribe_via`](crate::QueryExecutor::subscribe_via).
+pub enum DeliveryStrategy<T> {
+    /// A channel read with the blocking
+    /// [`recv`](super::Subscription::recv), e.g. from a dedicated thread.
+    /// What [`subscribe`](crate::QueryExecutor::subscribe) uses.
+    Blocking,
+    /// A channel read with
+    /// [`recv_async`](super::Subscription::recv_async), for consumers on an
+    /// executor.
+    Async,
+    /// Called with the data after every change, on the thread that merged
+    /// it, which holds up the mutation's other subscribers: pass the data
+    /// on rather than doing work here. Not called for the initial data,
+    /// and no longer once the subscription is dropped.
+    Callback(Box<dyn Fn(&T, SubscriptionMetadata, Delivery) + Send + Sync>),
+}
+
+impl<T> DeliveryStrategy<T> {
+    pub fn callback(
+        callback: impl Fn(&T, SubscriptionMetadata, Delivery) + Send + Sync + 'static,
+    ) -> Self {
+        Self::Callback(Box::new(callback))
+    }
+
+    /// The two ends of the strategy, with the initial
+    /// [`SubscriptionMetadata::None`] queued for channels.
+    pub(crate) fn channel(self) -> (SubscriptionSender<T>, SubscriptionReceiver) {
+        match self {
+            Self::Blocking => {
+                let (sender, receiver) = crossbeam_channel::unbounded();
+                let _ = sender.send((SubscriptionMetadata::None, Delivery::none()));
+                (
+                    SubscriptionSender::Blocking(sender),
+                    SubscriptionReceiver::Blocking(receiver),
+                )
+            }
+            Self::Async => {
+                let (sender, receiver) = async_channel::unbounded();
+                let _ = sender.try_send((SubscriptionMetadata::None, Delivery::none()));
+                (
+                    SubscriptionSender::Async(sender),
+                    SubscriptionReceiver::Async(receiver),
+                )
+            }
+            Self::Callback(callback) => {
+                let alive = Arc::new(());
+                (
+                    SubscriptionSender::Callback {
+                        callback,
+                        alive: Arc::downgrade(&alive),
+                    },
+                    SubscriptionReceiver::Callback { _alive: alive },
+                )
+            }
+        }
+    }
+}
+
+pub(crate) enum SubscriptionSender<T> {
+    Blocking(crossbeam_channel::Sender<(SubscriptionMetadata, Delivery)>),
+    Async(async_channel::Sender<(SubscriptionMetadata, Delivery)>),
+    Callback {
+        callback: Box<dyn Fn(&T, SubscriptionMetadata, Delivery) + Send + Sync>,
+        alive: Weak<()>,
+    },
+}
+
+impl<T: Send + 'static> SubscriptionSender<T> {
+    /// A registry callback that passes every change of `data` on. Like the
+    /// registry's, it returns `false` once the subscription was dropped.
+    pub(crate) fn on_change(
+        self,
+        data: &Arc<Mutex<T>>,
+    ) -> Box<dyn Fn(&MutationEvent, ChangeReason, &Delivery) -> bool + Send + Sync> {
+        let data = data.clone();
+        Box::new(move |event: &MutationEvent, reason, delivery: &Delivery| {
+            let message = (
+                SubscriptionMetadata::Changed(event.clone(), reason),
+                delivery.clone(),
+            );
+            match &self {
+                Self::Blocking(sender) => sender.send(message).is_ok(),
+                Self::Async(sender) => sender.try_send(message).is_ok(),
+                Self::Callback { callback, alive } => {
+                    if alive.strong_count() == 0 {
+                        return false;
+                    }
+                    let (metadata, delivery) = message;
+                    callback(&data.lock().unwrap(), metadata, delivery);
+                    true
+                }
+            }
+        })
+    }
+}
+
+pub(crate) enum SubscriptionReceiver {
+    Blocking(crossbeam_channel::Receiver<(SubscriptionMetadata, Delivery)>),
+    Async(async_channel::Receiver<(SubscriptionMetadata, Delivery)>),
+    /// Dropped with the subscription, which stops the callback.
+    Callback {
+        _alive: Arc<()>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+    use smallvec::SmallVec;
+
+    use crate::MutationEventKind;
+
+    use super::*;
+
+    fn delete() -> MutationEvent {
+        MutationEvent {
+            table_name: "users",
+            sequence: 1,
+            kind: MutationEventKind::Delete {
+                filters: SmallVec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn callbacks_stop_once_the_subscription_is_dropped() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let strategy = DeliveryStrategy::callback({
+            let calls = calls.clone();
+            move |data: &u32, metadata, _| {
+                calls.lock().unwrap().push((*data, metadata));
+            }
+        });
+        let (sender, receiver) = strategy.channel();
+        let on_change = sender.on_change(&Arc::new(Mutex::new(7)));
+
+        assert!(on_change(
+            &delete(),
+            ChangeReason::RowsRemoved(1),
+            &Delivery::none()
+        ));
+        drop(receiver);
+        assert!(!on_change(
+            &delete(),
+            ChangeReason::RowsRemoved(1),
+            &Delivery::none()
+        ));
+
+        let calls = calls.lock().unwrap();
+        assert!(matches!(
+            calls.as_slice(),
+            [(
+                7,
+                SubscriptionMetadata::Changed(_, ChangeReason::RowsRemoved(1))
+            )]
+        ));
+    }
+
+    #[test]
+    fn async_channels_queue_the_initial_data_and_changes() {
+        let (sender, receiver) = DeliveryStrategy::<u32>::Async.channel();
+        let on_change = sender.on_change(&Arc::new(Mutex::new(7)));
+        assert!(on_change(
+            &delete(),
+            ChangeReason::RowsRemoved(1),
+            &Delivery::none()
+        ));
+
+        let SubscriptionReceiver::Async(receiver) = receiver else {
+            unreachable!();
+        };
+        let first = receiver.recv().now_or_never().unwrap().unwrap();
+        let second = receiver.recv().now_or_never().unwrap().unwrap();
+        assert!(matches!(first.0, SubscriptionMetadata::None));
+        assert!(matches!(second.0, SubscriptionMetadata::Changed(..)));
+    }
+}
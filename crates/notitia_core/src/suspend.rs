@@ -0,0 +1,159 @@
+use crate::{Adapter, Database, Datatype, Delivery, MutationEvent, MutationEventKind, Notitia};
+
+/// The events held back while notifications are suspended.
+#[derive(Default)]
+pub(crate) struct Suspension {
+    /// How many [`SuspendedNotifications`] guards are alive.
+    pub(crate) depth: usize,
+    pub(crate) events: Vec<MutationEvent>,
+}
+
+/// Holds back every mutation event of a database until it's dropped, see
+/// [`Notitia::suspend_notifications`].
+#[must_use = "notifications resume as soon as the guard is dropped"]
+pub struct SuspendedNotifications<Db: Database, Adptr: Adapter> {
+    db: Notitia<Db, Adptr>,
+}
+
+impl<Db: Database, Adptr: Adapter> SuspendedNotifications<Db, Adptr> {
+    /// Broadcast the held back events, the same as dropping the guard.
+    pub fn resume(self) {}
+}
+
+impl<Db: Database, Adptr: Adapter> Drop for SuspendedNotifications<Db, Adptr> {
+    fn drop(&mut self) {
+        self.db.resume_notifications();
+    }
+}
+
+impl<Db: Database, Adptr: Adapter> Notitia<Db, Adptr> {
+    /// Hold back mutation events until the returned guard is dropped, e.g.
+    /// during a large import, so that subscriptions merge the changes once
+    /// instead of once per statement:
+    ///
+    /// ```ignore
+    /// let suspended = db.suspend_notifications();
+    /// for chunk in messages.chunks(500) {
+    ///     db.bulk_load(MyDb::MESSAGES, chunk.iter().cloned()).execute().await?;
+    /// }
+    /// drop(suspended);
+    /// ```
+    ///
+    /// Consecutive inserts into the same table are then broadcast as one
+    /// bulk insert; other events are broadcast as they were, in order, as
+    /// one [batch](crate::SubscriptionRegistry::broadcast_batch).
+    /// Subscription data stays as it was until then, and mutations run with
+    /// [`read_your_writes`](crate::MutateExecutor::read_your_writes) don't
+    /// wait for it. Guards nest: events are broadcast when the last one is
+    /// dropped.
+    pub fn suspend_notifications(&self) -> SuspendedNotifications<Db, Adptr> {
+        self.inner.suspension.lock().unwrap().depth += 1;
+        SuspendedNotifications { db: self.clone() }
+    }
+
+    fn resume_notifications(&self) {
+        let mut suspension = self.inner.suspension.lock().unwrap();
+        suspension.depth -= 1;
+        if suspension.depth > 0 {
+            return;
+        }
+
+        // Broadcast and call the hook while still holding the lock, so that
+        // no event that came later can overtake the held back ones.
+        let events = self.inner.subscriptions.broadcast_batch(
+            &coalesce(std::mem::take(&mut suspension.events)),
+            &Delivery::none(),
+        );
+        if let Some(hook) = self.inner.mutation_hook.get() {
+            for event in &events {
+                hook.on_event(event);
+            }
+        }
+        drop(suspension);
+    }
+}
+
+/// Merge runs of inserts into the same table into single bulk inserts.
+fn coalesce(events: Vec<MutationEvent>) -> Vec<MutationEvent> {
+    let mut coalesced: Vec<MutationEvent> = Vec::new();
+    for event in events {
+        let run = coalesced.last_mut().filter(|last| {
+            last.table_name == event.table_name && is_insert(&last.kind) && is_insert(&event.kind)
+        });
+        match run {
+            Some(last) => {
+                let mut rows = inserted_rows(std::mem::replace(
+                    &mut last.kind,
+                    MutationEventKind::BulkInsert { rows: Vec::new() },
+                ));
+                rows.extend(inserted_rows(event.kind));
+                last.kind = MutationEventKind::BulkInsert { rows };
+            }
+            None => coalesced.push(event),
+        }
+    }
+    coalesced
+}
+
+fn is_insert(kind: &MutationEventKind) -> bool {
+    matches!(
+        kind,
+        MutationEventKind::Insert { .. } | MutationEventKind::BulkInsert { .. }
+    )
+}
+
+fn inserted_rows(kind: MutationEventKind) -> Vec<Vec<(&'static str, Datatype)>> {
+    match kind {
+        MutationEventKind::Insert { values } => vec![values],
+        MutationEventKind::BulkInsert { rows } => rows,
+        MutationEventKind::Update { .. } | MutationEventKind::Delete { .. } => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::SmallVec;
+
+    use super::*;
+
+    fn insert(table_name: &'static str, id: i64) -> MutationEvent {
+        MutationEvent {
+            table_name,
+            sequence: 0,
+            kind: MutationEventKind::Insert {
+                values: vec![("id", Datatype::BigInt(id))],
+            },
+        }
+    }
+
+    #[test]
+    fn merges_runs_of_inserts() {
+        let delete = MutationEvent {
+            table_name: "users",
+            sequence: 0,
+            kind: MutationEventKind::Delete {
+                filters: SmallVec::new(),
+            },
+        };
+        let events = vec![
+            insert("users", 1),
+            insert("users", 2),
+            insert("posts", 3),
+            delete,
+            insert("users", 4),
+        ];
+
+        let kinds: Vec<_> = coalesce(events)
+            .into_iter()
+            .map(|event| match event.kind {
+                MutationEventKind::BulkInsert { rows } => (event.table_name, rows.len()),
+                MutationEventKind::Insert { .. } => (event.table_name, 1),
+                _ => (event.table_name, 0),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [("users", 2), ("posts", 1), ("users", 0), ("users", 1)]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{LazyLock, Mutex},
+};
+
+use derivative::Derivative;
+use unions::{IntoUnion, UnionPath};
+
+use crate::{
+    Database, FieldKind, FieldKindOfDatabase, InnerFieldType, IsTable, Record, StrongFieldKind,
+};
+
+static ENTRIES: LazyLock<Mutex<HashMap<(&'static str, &'static str), &'static str>>> =
+    LazyLock::new(Default::default);
+
+/// Another name for a table, to join it with itself, e.g. replies with the
+/// messages they reply to:
+///
+/// ```ignore
+/// let parents = MyDb::MESSAGES.alias("parents");
+/// MyDb::MESSAGES
+///     .join(parents)
+///     .select((Message::ID, Message::BODY, parents.field(Message::BODY)))
+///     .join_on(Message::PARENT_ID, parents.field(Message::ID))
+///     .filter(parents.field(Message::AUTHOR).eq("bob"))
+/// ```
+///
+/// The fields of an alias are of their own kind, [`Aliased`], so
+/// `Message::BODY` is always the column of the table and
+/// `parents.field(Message::BODY)` the one of the alias.
+#[derive(Derivative)]
+#[derivative(Debug, Clone(bound = ""), Copy(bound = ""))]
+pub struct TableAlias<Db, Tbl>
+where
+    Db: Database,
+    Tbl: IsTable<Database = Db>,
+{
+    pub table_name: &'static str,
+    pub name: &'static str,
+    #[doc(hidden)]
+    #[derivative(Debug = "ignore")]
+    _database: PhantomData<Db>,
+    #[doc(hidden)]
+    #[derivative(Debug = "ignore")]
+    _table: PhantomData<Tbl>,
+}
+
+impl<Db, Tbl> TableAlias<Db, Tbl>
+where
+    Db: Database,
+    Tbl: IsTable<Database = Db>,
+{
+    pub(crate) fn new(table_name: &'static str, name: &'static str) -> Self {
+        Self {
+            table_name,
+            name,
+            _database: PhantomData,
+            _table: PhantomData,
+        }
+    }
+
+    /// The alias as an entry of a statement's tables, e.g.
+    /// `messages AS parents`, see [`split_table_entry`].
+    pub(crate) fn entry(&self) -> &'static str {
+        // Leaked once per table and alias, which are written in code.
+        let mut entries = ENTRIES.lock().unwrap();
+        *entries
+            .entry((self.table_name, self.name))
+            .or_insert_with(|| {
+                let entry: &'static str =
+                    Box::leak(format!("{} AS {}", self.table_name, self.name).into_boxed_str());
+                entry
+            })
+    }
+}
+
+impl<Db, Tbl, Rec> TableAlias<Db, Tbl>
+where
+    Db: Database,
+    Rec: Record,
+    Tbl: IsTable<Record = Rec, Database = Db>,
+{
+    /// `field` of the table, taken from the alias.
+    pub fn field<FieldPath, K, T>(
+        &self,
+        field: StrongFieldKind<K, T>,
+    ) -> StrongFieldKind<Aliased<K>, T>
+    where
+        FieldPath: UnionPath,
+        K: FieldKind + IntoUnion<Rec::FieldKind, FieldPath>,
+        T: InnerFieldType,
+    {
+        StrongFieldKind::new(Aliased {
+            kind: field.kind,
+            alias: self.name,
+        })
+    }
+}
+
+/// The table an entry of a statement's tables names, and the alias it is
+/// joined under if it's a [`TableAlias`].
+pub fn split_table_entry(entry: &'static str) -> (&'static str, Option<&'static str>) {
+    match entry.split_once(" AS ") {
+        Some((table, alias)) => (table, Some(alias)),
+        None => (entry, None),
+    }
+}
+
+/// A field taken from a [`TableAlias`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Aliased<K> {
+    pub kind: K,
+    pub alias: &'static str,
+}
+
+impl<K: FieldKind> FieldKind for Aliased<K> {
+    fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn table_alias(&self) -> Option<&'static str> {
+        Some(self.alias)
+    }
+}
+
+impl<D: Database, K: FieldKindOfDatabase<D>> FieldKindOfDatabase<D> for Aliased<K> {
+    fn table_name() -> &'static str {
+        K::table_name()
+    }
+}
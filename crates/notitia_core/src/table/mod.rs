@@ -5,6 +5,9 @@ use derivative::Derivative;
 mod table_kind;
 pub use table_kind::*;
 
+mod alias;
+pub use alias::*;
+
 use crate::{Database, DatatypeKind, Record};
 
 #[derive(Derivative)]
@@ -2,18 +2,37 @@ use std::{fmt::Debug, marker::PhantomData};
 
 use derivative::Derivative;
 use smallvec::{SmallVec, smallvec};
-use unions::{IsUnion, Union};
+use unions::{IntoUnion, IsUnion, Union, UnionPath};
 
 use crate::{
-    BuiltRecord, Database, DeleteStmtUnbuilt, FieldKindGroup, InsertStmtBuilt, IsTable,
-    PartialRecord, Record, SelectStmtJoin, SelectStmtJoinable, SelectStmtSelectable,
-    UpdateStmtUnbuilt,
+    BuiltRecord, BulkInsertStmtBuilt, Database, Datatype, DeleteByKeysStmtBuilt, DeleteStmtUnbuilt,
+    FieldKind, FieldKindGroup, InnerFieldType, InsertFromSelectStmtBuilt, InsertStmtBuilt, IsTable,
+    JoinableTable, PartialRecord, QualifiedField, Record, SelectStmtBuildable, SelectStmtJoin,
+    SelectStmtJoinable, SelectStmtSelectable, StrongFieldKind, TableAlias, UpdateStmtUnbuilt,
 };
 
 pub trait TableKind: Debug {
     fn name(&self) -> &'static str;
 }
 
+/// The table kind of a database that includes other databases: tables are
+/// identified by name alone, so an included database can hand out kinds for
+/// its own tables without knowing the database it ends up in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ComposedTableKind(&'static str);
+
+impl ComposedTableKind {
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+}
+
+impl TableKind for ComposedTableKind {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
 pub struct StrongTableKind<Db, Tbl>
@@ -75,14 +94,11 @@ where
     Rec: Record,
     Tbl: IsTable<Record = Rec, Database = Db>,
 {
-    fn join<InnerTbl: IsTable<Database = Db>>(
+    fn join<InnerTbl: JoinableTable<Db>>(
         self,
-        table: StrongTableKind<Db, InnerTbl>,
-    ) -> SelectStmtJoin<
-        Db,
-        Union<Rec::FieldKind, <<InnerTbl as IsTable>::Record as Record>::FieldKind>,
-    > {
-        SelectStmtJoin::new(SmallVec::from_buf([self.kind.name(), table.kind.name()]))
+        table: InnerTbl,
+    ) -> SelectStmtJoin<Db, Union<Rec::FieldKind, InnerTbl::FieldKind>> {
+        SelectStmtJoin::new(SmallVec::from_buf([self.kind.name(), table.table_entry()]))
     }
 
     fn tables(self) -> SmallVec<[&'static str; 2]> {
@@ -96,10 +112,69 @@ where
     Rec: Record,
     Tbl: IsTable<Record = Rec, Database = Db>,
 {
+    /// Qualify `field` with this table, to tell apart columns of the same
+    /// name in a join, see [`QualifiedField`].
+    pub fn field<FieldPath, K, T>(&self, field: StrongFieldKind<K, T>) -> QualifiedField<K, T>
+    where
+        FieldPath: UnionPath,
+        K: FieldKind + Clone + IntoUnion<Rec::FieldKind, FieldPath>,
+        T: InnerFieldType,
+    {
+        QualifiedField {
+            table_name: self.kind.name(),
+            field,
+        }
+    }
+
+    /// Another name for the table, to join it with itself, see
+    /// [`TableAlias`].
+    pub fn alias(&self, name: &'static str) -> TableAlias<Db, Tbl> {
+        TableAlias::new(self.kind.name(), name)
+    }
+
     pub fn insert<B: BuiltRecord<Record = Rec>>(&self, builder: B) -> InsertStmtBuilt<Db, Rec> {
         InsertStmtBuilt::new(self.kind.name(), builder.finish())
     }
 
+    pub fn insert_many<B: BuiltRecord<Record = Rec>>(
+        &self,
+        builders: impl IntoIterator<Item = B>,
+    ) -> BulkInsertStmtBuilt<Db, Rec> {
+        BulkInsertStmtBuilt::new(
+            self.kind.name(),
+            builders.into_iter().map(BuiltRecord::finish).collect(),
+        )
+    }
+
+    /// Copy the rows matched by `select` into this table, without reading
+    /// them into the application:
+    ///
+    /// ```ignore
+    /// db.mutate(
+    ///     MyDb::ARCHIVED_MESSAGES.insert_from(
+    ///         MyDb::MESSAGES
+    ///             .select((Message::ID, Message::BODY, Message::SENT_AT))
+    ///             .filter(Message::SENT_AT.lt(cutoff)),
+    ///     ),
+    /// )
+    /// .execute()
+    /// .await?;
+    /// ```
+    ///
+    /// Selected fields are written to the columns of the same name; panics if
+    /// this table lacks one of them.
+    pub fn insert_from<FieldUnion, FieldPath, Fields>(
+        &self,
+        select: impl SelectStmtBuildable<Db, FieldUnion, FieldPath, Fields>,
+    ) -> InsertFromSelectStmtBuilt<Db, Rec>
+    where
+        FieldUnion: IsUnion,
+        Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    {
+        let (tables, fields, filters) = select.tables_fields_and_filters();
+        InsertFromSelectStmtBuilt::new(self.kind.name(), fields.field_names(), tables, filters)
+    }
+
     pub fn update<B: PartialRecord<FieldKind = Rec::FieldKind>>(
         &self,
         builder: B,
@@ -110,4 +185,19 @@ where
     pub fn delete(&self) -> DeleteStmtUnbuilt<Db, Rec> {
         DeleteStmtUnbuilt::new(self.kind.name())
     }
+
+    /// Delete the rows with these primary keys, however many there are, in
+    /// one transaction and with one event for subscribers:
+    ///
+    /// ```ignore
+    /// db.mutate(MyDb::MESSAGES.delete_by_keys(expired_ids)).execute().await?;
+    /// ```
+    ///
+    /// Panics if the table's primary key isn't a single column.
+    pub fn delete_by_keys(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<Datatype>>,
+    ) -> DeleteByKeysStmtBuilt<Db, Rec> {
+        DeleteByKeysStmtBuilt::new(self.kind.name(), keys.into_iter().map(Into::into).collect())
+    }
 }
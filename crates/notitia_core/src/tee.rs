@@ -0,0 +1,560 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use tracing::warn;
+use unions::IsUnion;
+
+use crate::{
+    Adapter, AdapterCapabilities, ArchiveStmt, BlobLocation, BulkInsertStmtBuilt, Database,
+    Datatype, DeleteByKeysStmtBuilt, DeleteStmtBuilt, FieldKindGroup, InsertFromSelectStmtBuilt,
+    InsertStmtBuilt, MutationEvent, MutationRejected, NonFiniteFloat, Notitia, OutboxEvent,
+    PartialRecord, Record, SelectStmtBuilt, SelectStmtDef, SelectStmtFetchMode, SqlFunction,
+    Unsupported, UpdateStmtBuilt,
+};
+
+/// Applies every mutation to a primary and then a secondary adapter, e.g. a
+/// local SQLite file and a remote database, and reads from the primary only.
+///
+/// ```ignore
+/// let tee = TeeAdapter::new(SqliteAdapter::new(local), PostgresAdapter::new(remote));
+/// let db = Notitia::new(MyDb::new(), tee).await;
+///
+/// // Later, e.g. before switching reads over.
+/// let report = db.adapter().reconcile(db.database()).await?;
+/// ```
+///
+/// A mutation the primary rejects isn't sent to the secondary. One the
+/// secondary rejects still succeeds, and is listed in
+/// [`failures`](Self::failures) instead. Blobs are only streamed into the
+/// primary.
+pub struct TeeAdapter<P: Adapter, S: Adapter> {
+    primary: P,
+    secondary: S,
+    failures: Mutex<Vec<TeeFailure>>,
+}
+
+/// A mutation the secondary adapter of a [`TeeAdapter`] failed to apply.
+#[derive(Clone, Debug)]
+pub struct TeeFailure {
+    pub table_name: &'static str,
+    pub error: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TeeError<P, S> {
+    #[error("primary adapter: {0}")]
+    Primary(P),
+    #[error("secondary adapter: {0}")]
+    Secondary(S),
+    #[error("expected the URLs of both adapters separated by `|`, got `{0}`")]
+    InvalidUrl(String),
+}
+
+impl<P: From<NonFiniteFloat>, S> From<NonFiniteFloat> for TeeError<P, S> {
+    fn from(err: NonFiniteFloat) -> Self {
+        Self::Primary(err.into())
+    }
+}
+
+impl<P: From<Unsupported>, S> From<Unsupported> for TeeError<P, S> {
+    fn from(err: Unsupported) -> Self {
+        Self::Primary(err.into())
+    }
+}
+
+impl<P: From<MutationRejected>, S> From<MutationRejected> for TeeError<P, S> {
+    fn from(err: MutationRejected) -> Self {
+        Self::Primary(err.into())
+    }
+}
+
+/// How the data of the secondary adapter of a [`TeeAdapter`] differs from
+/// the primary's, see [`TeeAdapter::reconcile`].
+#[derive(Clone, Debug, Default)]
+pub struct ReconciliationReport {
+    /// The mutations the secondary failed to apply, oldest first.
+    pub failures: Vec<TeeFailure>,
+    /// The tables whose rows differ.
+    pub tables: Vec<TableReconciliation>,
+}
+
+impl ReconciliationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.failures.is_empty() && self.tables.is_empty()
+    }
+}
+
+/// The rows of a table that differ between the adapters, by primary key.
+#[derive(Clone, Debug)]
+pub struct TableReconciliation {
+    pub table_name: &'static str,
+    /// Keys of the rows only the primary has.
+    pub missing: Vec<Vec<Datatype>>,
+    /// Keys of the rows only the secondary has.
+    pub extra: Vec<Vec<Datatype>>,
+    /// Keys of the rows both have, with different values.
+    pub differing: Vec<Vec<Datatype>>,
+}
+
+type Rows = Vec<Vec<(&'static str, Datatype)>>;
+
+impl<P: Adapter, S: Adapter> TeeAdapter<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            failures: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    /// The mutations the secondary failed to apply since the adapter was
+    /// created, oldest first.
+    pub fn failures(&self) -> Vec<TeeFailure> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// Compare every table of `database` between the two adapters. Rows are
+    /// matched by primary key, so tables need one.
+    pub async fn reconcile<Db: Database>(
+        &self,
+        database: &Db,
+    ) -> Result<ReconciliationReport, TeeError<P::Error, S::Error>> {
+        let mut tables = Vec::new();
+
+        for (table_name, fields) in database.tables() {
+            let columns: Vec<&'static str> = fields.iter().map(|(name, _)| *name).collect();
+            let key_columns: Vec<usize> = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, kind))| kind.metadata().primary_key)
+                .map(|(idx, _)| idx)
+                .collect();
+
+            let primary = self
+                .primary
+                .fetch_rows(table_name, &columns)
+                .await
+                .map_err(TeeError::Primary)?;
+            let secondary = self
+                .secondary
+                .fetch_rows(table_name, &columns)
+                .await
+                .map_err(TeeError::Secondary)?;
+
+            let by_key = |rows: Rows| -> BTreeMap<Vec<Datatype>, Vec<(&'static str, Datatype)>> {
+                rows.into_iter()
+                    .map(|row| {
+                        let key = key_columns.iter().map(|idx| row[*idx].1.clone()).collect();
+                        (key, row)
+                    })
+                    .collect()
+            };
+            let primary = by_key(primary);
+            let mut secondary = by_key(secondary);
+
+            let mut table = TableReconciliation {
+                table_name,
+                missing: Vec::new(),
+                extra: Vec::new(),
+                differing: Vec::new(),
+            };
+            for (key, row) in primary {
+                match secondary.remove(&key) {
+                    None => table.missing.push(key),
+                    Some(other) if other != row => table.differing.push(key),
+                    Some(_) => {}
+                }
+            }
+            table.extra = secondary.into_keys().collect();
+
+            if !(table.missing.is_empty() && table.extra.is_empty() && table.differing.is_empty()) {
+                tables.push(table);
+            }
+        }
+
+        Ok(ReconciliationReport {
+            failures: self.failures(),
+            tables,
+        })
+    }
+
+    /// Run `secondary` once `primary` succeeded. The primary's output is
+    /// returned either way, and a failure of the secondary is only noted.
+    async fn replicate<T, U>(
+        &self,
+        table_name: &'static str,
+        primary: impl Future<Output = Result<T, P::Error>>,
+        secondary: impl Future<Output = Result<U, S::Error>>,
+    ) -> Result<T, TeeError<P::Error, S::Error>> {
+        let output = primary.await.map_err(TeeError::Primary)?;
+        if let Err(err) = secondary.await {
+            warn!("the secondary adapter failed to apply a mutation of {table_name}: {err}");
+            self.failures.lock().unwrap().push(TeeFailure {
+                table_name,
+                error: err.to_string(),
+            });
+        }
+        Ok(output)
+    }
+}
+
+impl<P: Adapter, S: Adapter> Adapter for TeeAdapter<P, S> {
+    type QueryBuilder = P::QueryBuilder;
+    type Connection = (P::Connection, S::Connection);
+    type Error = TeeError<P::Error, S::Error>;
+    type BlobReader = P::BlobReader;
+    type BlobWriter = P::BlobWriter;
+    type Snapshot = P::Snapshot;
+
+    fn new((primary, secondary): Self::Connection) -> Self {
+        Self::new(P::new(primary), S::new(secondary))
+    }
+
+    /// Mutations run on both adapters, so only what both support.
+    fn capabilities(&self) -> AdapterCapabilities {
+        self.primary
+            .capabilities()
+            .intersection(self.secondary.capabilities())
+    }
+
+    async fn initialize<Db: Database>(&self, database: &Db) {
+        self.primary.initialize(database).await;
+        self.secondary.initialize(database).await;
+    }
+
+    async fn migrate<Db: Database>(&self, database: &Db) {
+        self.primary.migrate(database).await;
+        self.secondary.migrate(database).await;
+    }
+
+    /// Opens `{primary url}|{secondary url}` with both adapters.
+    async fn open<Db: Database>(url: &str) -> Result<Notitia<Db, Self>, Self::Error> {
+        let Some((primary, secondary)) = url.split_once('|') else {
+            return Err(TeeError::InvalidUrl(url.to_owned()));
+        };
+        let primary = P::open::<Db>(primary.trim())
+            .await
+            .map_err(TeeError::Primary)?
+            .into_adapter()
+            .expect("nothing else holds a database that was just opened");
+        let secondary = S::open::<Db>(secondary.trim())
+            .await
+            .map_err(TeeError::Secondary)?
+            .into_adapter()
+            .expect("nothing else holds a database that was just opened");
+
+        Ok(Notitia::new(Db::new(), Self::new(primary, secondary)).await)
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.primary.ping().await.map_err(TeeError::Primary)?;
+        self.secondary.ping().await.map_err(TeeError::Secondary)
+    }
+
+    async fn reconnect(&self) -> Result<(), Self::Error> {
+        self.primary.reconnect().await.map_err(TeeError::Primary)?;
+        self.secondary
+            .reconnect()
+            .await
+            .map_err(TeeError::Secondary)
+    }
+
+    async fn register_function(&self, function: SqlFunction) -> Result<(), Self::Error> {
+        self.primary
+            .register_function(function.clone())
+            .await
+            .map_err(TeeError::Primary)?;
+        self.secondary
+            .register_function(function)
+            .await
+            .map_err(TeeError::Secondary)
+    }
+
+    async fn execute_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        self.primary
+            .execute_select_stmt(stmt)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn execute_select_stmt_until<Db, FieldUnion, FieldPath, Fields, Mode, Deadline>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+        deadline: Deadline,
+    ) -> Result<(Mode::Output, bool), Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+        Deadline: Future<Output = ()> + Send,
+    {
+        self.primary
+            .execute_select_stmt_until(stmt, deadline)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn begin_snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+        self.primary
+            .begin_snapshot()
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn execute_snapshot_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        snapshot: &Self::Snapshot,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        self.primary
+            .execute_snapshot_select_stmt(snapshot, stmt)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> Result<Vec<Vec<Datatype>>, Self::Error> {
+        self.primary
+            .execute_select_def(def)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn execute_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: InsertStmtBuilt<Db, R>,
+    ) -> Result<bool, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_insert_stmt(stmt),
+            self.secondary.execute_insert_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_bulk_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> Result<Rows, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_bulk_insert_stmt(stmt),
+            self.secondary.execute_bulk_insert_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_insert_from_select_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> Result<Rows, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_insert_from_select_stmt(stmt),
+            self.secondary.execute_insert_from_select_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_update_stmt<Db: Database, Rec: Record + Send, Pr: PartialRecord + Send>(
+        &self,
+        stmt: UpdateStmtBuilt<Db, Rec, Pr>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_update_stmt(stmt),
+            self.secondary.execute_update_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_delete_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_delete_stmt(stmt),
+            self.secondary.execute_delete_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_delete_by_keys_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let table_name = stmt.table_name;
+        let mut copy = stmt.clone();
+        copy.outbox.clear();
+        self.replicate(
+            table_name,
+            self.primary.execute_delete_by_keys_stmt(stmt),
+            self.secondary.execute_delete_by_keys_stmt(copy),
+        )
+        .await
+    }
+
+    async fn execute_archive_stmt(
+        &self,
+        stmt: &ArchiveStmt,
+    ) -> Result<(Rows, Vec<MutationEvent>), Self::Error> {
+        self.replicate(
+            stmt.hot_table,
+            self.primary.execute_archive_stmt(stmt),
+            self.secondary.execute_archive_stmt(stmt),
+        )
+        .await
+    }
+
+    async fn is_table_empty(&self, table_name: &'static str) -> Result<bool, Self::Error> {
+        self.primary
+            .is_table_empty(table_name)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn fetch_rows(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> Result<Rows, Self::Error> {
+        self.primary
+            .fetch_rows(table_name, columns)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn insert_rows(&self, table_name: &'static str, rows: Rows) -> Result<(), Self::Error> {
+        self.replicate(
+            table_name,
+            self.primary.insert_rows(table_name, rows.clone()),
+            self.secondary.insert_rows(table_name, rows),
+        )
+        .await
+    }
+
+    async fn update_rows(
+        &self,
+        table_name: &'static str,
+        key_column: &'static str,
+        column: &'static str,
+        rows: Vec<(Datatype, Datatype)>,
+    ) -> Result<(), Self::Error> {
+        self.replicate(
+            table_name,
+            self.primary
+                .update_rows(table_name, key_column, column, rows.clone()),
+            self.secondary
+                .update_rows(table_name, key_column, column, rows),
+        )
+        .await
+    }
+
+    async fn open_blob_reader(
+        &self,
+        location: &BlobLocation,
+    ) -> Result<P::BlobReader, Self::Error> {
+        self.primary
+            .open_blob_reader(location)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn open_blob_writer(
+        &self,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<P::BlobWriter, Self::Error> {
+        self.primary
+            .open_blob_writer(location, len)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn vacuum(&self) -> Result<(), Self::Error> {
+        self.primary.vacuum().await.map_err(TeeError::Primary)?;
+        self.secondary.vacuum().await.map_err(TeeError::Secondary)
+    }
+
+    async fn fixture_version(&self, name: &'static str) -> Result<Option<u32>, Self::Error> {
+        self.primary
+            .fixture_version(name)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn set_fixture_version(
+        &self,
+        name: &'static str,
+        version: u32,
+    ) -> Result<(), Self::Error> {
+        self.primary
+            .set_fixture_version(name, version)
+            .await
+            .map_err(TeeError::Primary)?;
+        self.secondary
+            .set_fixture_version(name, version)
+            .await
+            .map_err(TeeError::Secondary)
+    }
+
+    /// The outbox is the primary's; the statements the secondary runs
+    /// leave their messages out.
+    async fn fetch_outbox(&self, limit: usize) -> Result<Vec<OutboxEvent>, Self::Error> {
+        self.primary
+            .fetch_outbox(limit)
+            .await
+            .map_err(TeeError::Primary)
+    }
+
+    async fn mark_outbox_dispatched(&self, ids: Vec<i64>) -> Result<(), Self::Error> {
+        self.primary
+            .mark_outbox_dispatched(ids)
+            .await
+            .map_err(TeeError::Primary)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{DatatypeConversionError, FieldFilter};
+
+/// A NULL was read into a field that isn't an `Option`, e.g. from a column
+/// added by a migration or written by other tools.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("unexpected NULL in `{table}.{column}` (row {row} of the rows filtered by {filter})")]
+pub struct UnexpectedNull {
+    pub table: String,
+    pub column: &'static str,
+    /// The index of the row within the result set.
+    pub row: usize,
+    /// The query's filters, e.g. `users.age > 30 AND users.active = true`.
+    pub filter: String,
+}
+
+impl UnexpectedNull {
+    /// The NULL behind `err`, a decoding error annotated by
+    /// [`DatatypeConversionError::in_column`], in the result of a query
+    /// with `filters`.
+    pub fn from_decode_error(
+        err: &DatatypeConversionError,
+        filters: &[FieldFilter],
+    ) -> Option<Self> {
+        let DatatypeConversionError::InColumn {
+            table, column, row, ..
+        } = err
+        else {
+            return None;
+        };
+        if !matches!(err.root_cause(), DatatypeConversionError::UnexpectedNull) {
+            return None;
+        }
+
+        let filter = match filters {
+            [] => "nothing".to_string(),
+            filters => filters
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        };
+        Some(Self {
+            table: table.clone(),
+            column,
+            row: *row,
+            filter,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Datatype, FieldFilterMetadata, TableFieldPair};
+
+    #[test]
+    fn names_the_column_and_filter() {
+        let filters = [FieldFilter::Eq(FieldFilterMetadata {
+            left: TableFieldPair::new("users", "team"),
+            right: Datatype::Text("core".into()),
+        })];
+        let err = i32::try_from(Datatype::Null)
+            .unwrap_err()
+            .in_column("users", "age", 2);
+
+        let null = UnexpectedNull::from_decode_error(&err, &filters).unwrap();
+        assert_eq!(
+            null.to_string(),
+            "unexpected NULL in `users.age` (row 2 of the rows filtered by users.team = 'core')"
+        );
+
+        let mismatch = i32::try_from(Datatype::Text("1".into()))
+            .unwrap_err()
+            .in_column("users", "age", 2);
+        assert_eq!(UnexpectedNull::from_decode_error(&mismatch, &filters), None);
+    }
+}
@@ -31,7 +31,7 @@ impl Render for Main {
                 db.query(
                     TodosDatabase::TODOS
                         .select((Todo::ID, Todo::TITLE, Todo::CONTENT, Todo::COMPLETED))
-                        .fetch_all::<Vec<_>>(),
+                        .fetch_vec(),
                 )
             })
         });
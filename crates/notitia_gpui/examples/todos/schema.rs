@@ -1,7 +1,7 @@
 use nanoid::nanoid;
 use notitia::{
     AsDatatypeKind, Datatype, DatatypeConversionError, DatatypeKind, DatatypeKindMetadata,
-    InnerFieldType, Table, database, record,
+    DatatypeRef, FromDatatypeRef, InnerFieldType, Table, database, record,
 };
 
 #[database]
@@ -46,6 +46,12 @@ impl TryFrom<Datatype> for UniqueId {
     }
 }
 
+impl FromDatatypeRef for UniqueId {
+    fn from_datatype_ref(value: DatatypeRef<'_>) -> Result<Self, DatatypeConversionError> {
+        String::from_datatype_ref(value).map(UniqueId)
+    }
+}
+
 impl InnerFieldType for UniqueId {
     type Inner = UniqueId;
 }
@@ -0,0 +1,46 @@
+use std::{fs, io, path::PathBuf, sync::Mutex};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+/// The last output of every named query, kept in a JSON file next to the
+/// database so that the next launch can render them before the database
+/// answers, see [`WindowNotitiaExt::use_cached_db_query`](crate::WindowNotitiaExt::use_cached_db_query).
+pub struct QueryCache {
+    path: PathBuf,
+    outputs: Mutex<Map<String, Value>>,
+}
+
+impl QueryCache {
+    /// Load the outputs stored at `path`. A missing or unreadable file
+    /// starts an empty cache, which is only a slower launch.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let outputs = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            outputs: Mutex::new(outputs),
+        }
+    }
+
+    /// The last output stored under `name`, unless there is none or it no
+    /// longer fits `T`.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let outputs = self.outputs.lock().unwrap();
+        serde_json::from_value(outputs.get(name)?.clone()).ok()
+    }
+
+    /// Store `output` as the last of `name` and write the file.
+    pub fn set<T: Serialize>(&self, name: &str, output: &T) -> io::Result<()> {
+        let mut outputs = self.outputs.lock().unwrap();
+        outputs.insert(name.to_owned(), serde_json::to_value(output)?);
+
+        // Through a temporary file, so a crash mid-write keeps the old one.
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&*outputs)?)?;
+        fs::rename(tmp, &self.path)
+    }
+}
@@ -3,10 +3,18 @@ use std::sync::Arc;
 
 use gpui::{App, AppContext, AsyncApp, ElementId, Entity};
 use notitia::{
-    Adapter, Database, FieldKindGroup, QueryExecutor, SelectStmtFetchMode, SubscribableRow,
+    Adapter, Database, FieldKindGroup, MergingFetchMode, QueryExecutor, SubscribableRow,
     SubscriptionDescriptor,
 };
 
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+pub use cache::*;
+
+/// Called on the bridge thread with every new output of a query.
+type Persist<T> = Box<dyn Fn(&T) + Send>;
+
 pub struct DbEntity<T: 'static> {
     entity: Entity<Option<T>>,
 }
@@ -44,7 +52,7 @@ pub trait WindowNotitiaExt {
         FieldPath: Send + Sync + 'static,
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
         Fields::Type: SubscribableRow,
-        Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
         Mode::Output: Clone + PartialEq + Send;
 
     fn use_db_query<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
@@ -62,8 +70,33 @@ pub trait WindowNotitiaExt {
         FieldPath: Send + Sync + 'static,
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
         Fields::Type: SubscribableRow,
-        Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
         Mode::Output: Clone + PartialEq + Send;
+
+    /// Like [`use_keyed_db_query`](Self::use_keyed_db_query), keyed by
+    /// `name`, but starting out with the output `cache` last stored under
+    /// it, so that the first frames after a launch aren't blank. Every new
+    /// output is stored in turn.
+    #[cfg(feature = "cache")]
+    fn use_cached_db_query<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+        &mut self,
+        name: &'static str,
+        cache: &Arc<QueryCache>,
+        cx: &mut App,
+        init_query: impl FnOnce(
+            &mut Self,
+            &mut App,
+        ) -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> DbEntity<Mode::Output>
+    where
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        FieldUnion: unions::IsUnion + Send + Sync + 'static,
+        FieldPath: Send + Sync + 'static,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+        Fields::Type: SubscribableRow,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode::Output: Clone + PartialEq + Send + serde::Serialize + serde::de::DeserializeOwned;
 }
 
 impl WindowNotitiaExt for gpui::Window {
@@ -83,7 +116,7 @@ impl WindowNotitiaExt for gpui::Window {
         FieldPath: Send + Sync + 'static,
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
         Fields::Type: SubscribableRow,
-        Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
         Mode::Output: Clone + PartialEq + Send,
     {
         let state_entity: Entity<DbQueryState<Mode::Output>> =
@@ -97,7 +130,7 @@ impl WindowNotitiaExt for gpui::Window {
             });
 
         let query = init_query(self, cx);
-        maybe_resubscribe(state_entity.clone(), query, cx);
+        maybe_resubscribe(state_entity.clone(), query, None, cx);
 
         let data_entity = state_entity.read(cx).data_entity.clone();
         DbEntity {
@@ -121,7 +154,7 @@ impl WindowNotitiaExt for gpui::Window {
         FieldPath: Send + Sync + 'static,
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
         Fields::Type: SubscribableRow,
-        Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
         Mode::Output: Clone + PartialEq + Send,
     {
         let state_entity: Entity<DbQueryState<Mode::Output>> =
@@ -135,7 +168,54 @@ impl WindowNotitiaExt for gpui::Window {
             });
 
         let query = init_query(self, cx);
-        maybe_resubscribe(state_entity.clone(), query, cx);
+        maybe_resubscribe(state_entity.clone(), query, None, cx);
+
+        let data_entity = state_entity.read(cx).data_entity.clone();
+        DbEntity {
+            entity: data_entity,
+        }
+    }
+
+    #[cfg(feature = "cache")]
+    fn use_cached_db_query<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+        &mut self,
+        name: &'static str,
+        cache: &Arc<QueryCache>,
+        cx: &mut App,
+        init_query: impl FnOnce(
+            &mut Self,
+            &mut App,
+        ) -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> DbEntity<Mode::Output>
+    where
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        FieldUnion: unions::IsUnion + Send + Sync + 'static,
+        FieldPath: Send + Sync + 'static,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+        Fields::Type: SubscribableRow,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode::Output: Clone + PartialEq + Send + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let state_entity: Entity<DbQueryState<Mode::Output>> =
+            self.use_keyed_state(name, cx, |_window, cx| {
+                let data_entity = cx.new(|_cx| cache.get(name));
+                DbQueryState {
+                    data_entity,
+                    cancel_flag: None,
+                    current_descriptor: None,
+                }
+            });
+
+        let cache = cache.clone();
+        let persist: Persist<Mode::Output> = Box::new(move |output| {
+            if let Err(err) = cache.set(name, output) {
+                tracing::warn!("failed to cache the output of query {name:?}: {err}");
+            }
+        });
+
+        let query = init_query(self, cx);
+        maybe_resubscribe(state_entity.clone(), query, Some(persist), cx);
 
         let data_entity = state_entity.read(cx).data_entity.clone();
         DbEntity {
@@ -147,6 +227,7 @@ impl WindowNotitiaExt for gpui::Window {
 fn maybe_resubscribe<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
     state_entity: Entity<DbQueryState<Mode::Output>>,
     query: QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
+    persist: Option<Persist<Mode::Output>>,
     cx: &mut App,
 ) where
     Db: Database + 'static,
@@ -155,7 +236,7 @@ fn maybe_resubscribe<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
     FieldPath: Send + Sync + 'static,
     Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
     Fields::Type: SubscribableRow,
-    Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
     Mode::Output: Clone + PartialEq + Send,
 {
     let new_descriptor = query.descriptor();
@@ -187,13 +268,14 @@ fn maybe_resubscribe<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
         state.cancel_flag = Some(cancel_flag.clone());
     });
 
-    spawn_subscription(query, data_entity, cancel_flag, cx);
+    spawn_subscription(query, data_entity, cancel_flag, persist, cx);
 }
 
 fn spawn_subscription<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
     query: QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
     data_entity: Entity<Option<Mode::Output>>,
     cancel_flag: Arc<AtomicBool>,
+    persist: Option<Persist<Mode::Output>>,
     cx: &mut App,
 ) where
     Db: Database + 'static,
@@ -202,7 +284,7 @@ fn spawn_subscription<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
     FieldPath: Send + Sync + 'static,
     Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
     Fields::Type: SubscribableRow,
-    Mode: SelectStmtFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
     Mode::Output: Clone + PartialEq + Send,
 {
     let weak_data = data_entity.downgrade();
@@ -215,18 +297,21 @@ fn spawn_subscription<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
         let (tx, rx) = async_channel::unbounded();
         let bridge_cancel = cancel_flag.clone();
         std::thread::spawn(move || {
-            while let Ok(_meta) = sub.recv() {
+            while let Ok((_meta, delivery)) = sub.recv_delivery() {
                 if bridge_cancel.load(Ordering::Relaxed) {
                     break;
                 }
                 let data = sub.data().clone();
-                if tx.send_blocking(data).is_err() {
+                if let Some(persist) = &persist {
+                    persist(&data);
+                }
+                if tx.send_blocking((data, delivery)).is_err() {
                     break;
                 }
             }
         });
 
-        while let Ok(data) = rx.recv().await {
+        while let Ok((data, delivery)) = rx.recv().await {
             if cancel_flag.load(Ordering::Relaxed) {
                 break;
             }
@@ -237,6 +322,8 @@ fn spawn_subscription<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
             if result.is_err() {
                 break; // Entity was dropped.
             }
+            // Only now can a mutation waiting to read its writes see them.
+            drop(delivery);
         }
     })
     .detach();
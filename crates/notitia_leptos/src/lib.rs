@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+
+use async_channel::{Receiver, Sender};
+use futures_util::{FutureExt, select_biased};
+use leptos::logging::error;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use notitia::{
+    Adapter, Database, DeliveryStrategy, FieldKindGroup, MergingFetchMode, QueryExecutor,
+    SubscribableRow, SubscriptionDescriptor,
+};
+
+/// Internal state for a database query subscription.
+struct DbQueryState {
+    /// Dropped to end the task following the current subscription, which
+    /// drops the subscription right away.
+    cancel: Option<Sender<()>>,
+    /// Descriptor of the current query (for comparison).
+    current_descriptor: Option<SubscriptionDescriptor>,
+}
+
+impl DbQueryState {
+    fn cancel(&mut self) {
+        self.cancel = None;
+    }
+}
+
+/// Run a query and keep the returned signal in sync with the database.
+///
+/// `init_query` runs inside an effect, so any signals it reads are tracked:
+/// when they change the query is rebuilt, and if its descriptor differs from
+/// the current one the old subscription is cancelled and a new one started.
+/// The signal holds `None` until the initial results arrive.
+pub fn use_db_query<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+    init_query: impl Fn() -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode> + 'static,
+) -> ReadSignal<Option<Mode::Output>>
+where
+    Db: Database + 'static,
+    Adptr: Adapter + 'static,
+    FieldUnion: unions::IsUnion + Send + Sync + 'static,
+    FieldPath: Send + Sync + 'static,
+    Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+    Fields::Type: SubscribableRow,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode::Output: Clone + PartialEq + Send + Sync,
+{
+    let (data, set_data) = signal(None);
+    let state = Arc::new(Mutex::new(DbQueryState {
+        cancel: None,
+        current_descriptor: None,
+    }));
+
+    {
+        let state = state.clone();
+        Effect::new(move |_| {
+            let query = init_query();
+            maybe_resubscribe(&state, query, set_data);
+        });
+    }
+
+    // Stop following the query once the owning component is unmounted.
+    on_cleanup(move || state.lock().unwrap().cancel());
+
+    data
+}
+
+fn maybe_resubscribe<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+    state: &Mutex<DbQueryState>,
+    query: QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
+    set_data: WriteSignal<Option<Mode::Output>>,
+) where
+    Db: Database + 'static,
+    Adptr: Adapter + 'static,
+    FieldUnion: unions::IsUnion + Send + Sync + 'static,
+    FieldPath: Send + Sync + 'static,
+    Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+    Fields::Type: SubscribableRow,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode::Output: Clone + PartialEq + Send + Sync,
+{
+    let new_descriptor = query.descriptor();
+
+    let cancelled = {
+        let mut state = state.lock().unwrap();
+
+        let needs_subscribe = state
+            .current_descriptor
+            .as_ref()
+            .map_or(true, |current| current != &new_descriptor);
+
+        if !needs_subscribe {
+            return;
+        }
+
+        // Cancel old subscription if any.
+        state.cancel();
+        state.current_descriptor = Some(new_descriptor);
+
+        let (cancel, cancelled) = async_channel::bounded(1);
+        state.cancel = Some(cancel);
+        cancelled
+    };
+
+    spawn_subscription(query, set_data, cancelled);
+}
+
+fn spawn_subscription<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+    query: QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>,
+    set_data: WriteSignal<Option<Mode::Output>>,
+    cancelled: Receiver<()>,
+) where
+    Db: Database + 'static,
+    Adptr: Adapter + 'static,
+    FieldUnion: unions::IsUnion + Send + Sync + 'static,
+    FieldPath: Send + Sync + 'static,
+    Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+    Fields::Type: SubscribableRow,
+    Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+    Mode::Output: Clone + PartialEq + Send + Sync,
+{
+    spawn_local(async move {
+        let sub = match query.subscribe_via(DeliveryStrategy::Async).await {
+            Ok(sub) => sub,
+            Err(err) => {
+                // The signal stays `None`.
+                error!("failed to subscribe to a query: {err}");
+                return;
+            }
+        };
+
+        loop {
+            // A cancelled subscription's changes are stale, even those that
+            // are already in.
+            let next = select_biased! {
+                _ = cancelled.recv().fuse() => break,
+                next = sub.recv_delivery_async().fuse() => next,
+            };
+            let Ok((_meta, delivery)) = next else {
+                break;
+            };
+            let data = sub.data().clone();
+            if set_data.try_set(Some(data)).is_some() {
+                break; // Signal was disposed.
+            }
+            // Only now can a mutation waiting to read its writes see them.
+            drop(delivery);
+        }
+    });
+}
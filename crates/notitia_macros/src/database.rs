@@ -13,9 +13,19 @@ use syn::{
     Type, TypePath, parse::ParseBuffer, parse_macro_input,
 };
 
-use crate::utils::{get_migrate_from_attr, parse_ident_list_attr};
+use crate::utils::{
+    get_attr_idx, get_migrate_from_attr, parse_flag_attr, parse_ident_list_attr, parse_str_attr,
+};
 
 pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let includable = parse_flag_attr(attr.clone(), "includable");
+    let prefix = match parse_str_attr(attr.clone(), "prefix") {
+        Ok(prefix) => prefix.unwrap_or_default(),
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // `removed_tables` and `migrate_from` name tables as they exist in the
+    // database, so they aren't prefixed: that way they can also refer to the
+    // tables from before the prefix was added.
     let removed_tables = parse_ident_list_attr(attr, "removed_tables");
 
     let input = parse_macro_input!(item as ItemStruct);
@@ -38,12 +48,26 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut embedding_table_entries: Vec<(String, &Type)> = vec![];
     let _ = &embedding_table_entries; // suppress unused warning when embeddings feature is off
 
+    // (table_name, event, body) of the `#[db(trigger(...))]` attributes.
+    let mut triggers: Vec<(String, Ident, syn::LitStr)> = vec![];
+
+    // (table_name, table_field_name, index) of the `#[db(index(...))]` attributes.
+    let mut indexes: Vec<(String, Ident, IndexAttr)> = vec![];
+
+    // (table_name, table_field_name, columns) of the `#[db(unique(...))]` attributes.
+    let mut unique_constraints: Vec<(String, Ident, Vec<Ident>)> = vec![];
+
+    // (table_name, table_field_name, column) of the `#[db(counted_by(...))]` attributes.
+    let mut counters: Vec<(String, Ident, Ident)> = vec![];
+
     // Collect table migration metadata: (current_table_name, [old_names], record_type).
     let mut table_migrations: Vec<(String, Vec<String>, &Type)> = vec![];
 
-    let mut table_kinds = vec![];
-    let mut table_kinds_consts = vec![];
-    let mut table_kinds_enum_to_str = vec![];
+    // (table_name, UPPER_SNAKE, Pascal, record_type) of the tables declared on this database.
+    let mut own_tables: Vec<(String, Ident, Ident, &Type)> = vec![];
+    // (field_name, database_type, alias, tables_type) of the `#[db(include)]` fields.
+    let mut included_databases: Vec<(&Ident, &Type, Ident, TypePath)> = vec![];
+
     let table_kinds_enum_name = Ident::new(
         &format!("{}TableKind", database_name.to_string()),
         Span::call_site(),
@@ -55,6 +79,44 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
         let table_field_vis = &field.vis;
         let table_field_ty = &field.ty;
 
+        if let Some(include_idx) = get_attr_idx(table_field_attrs.as_slice(), "db", "include") {
+            table_field_attrs.remove(include_idx);
+
+            let Some(table_field_name) = table_field_name else {
+                continue;
+            };
+
+            let Some(tables_ty) = included_tables_type(table_field_ty) else {
+                return syn::Error::new_spanned(
+                    table_field_ty,
+                    "`#[db(include)]` fields must have the type of another database, e.g. `auth: AuthDatabase`.",
+                )
+                .to_compile_error()
+                .into();
+            };
+
+            let alias = Ident::new(
+                &format!(
+                    "{}{}",
+                    database_name,
+                    table_field_name.to_string().to_case(Case::Pascal)
+                ),
+                Span::call_site(),
+            );
+
+            field_initializers.push(quote! {
+                #table_field_name: <#table_field_ty as notitia::Database>::new()
+            });
+
+            fields.push(quote! {
+                #(#table_field_attrs)*
+                #table_field_vis #table_field_name: #table_field_ty
+            });
+
+            included_databases.push((table_field_name, table_field_ty, alias, tables_ty));
+            continue;
+        }
+
         let record_ty = match parse_table_type(table_field_ty) {
             Some(record_ty) => record_ty,
 
@@ -72,9 +134,64 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
 
         if let Some(table_field_name) = table_field_name {
             let table_field_name_string = table_field_name.to_string();
+            let table_name = format!("{prefix}{table_field_name_string}");
 
             // Track table migrations.
-            table_migrations.push((table_field_name_string.clone(), migrate_from_names, record_ty));
+            table_migrations.push((table_name.clone(), migrate_from_names, record_ty));
+
+            match get_trigger_attr(table_field_attrs.as_slice(), "db") {
+                Some(Ok((trigger_idx, table_triggers))) => {
+                    table_field_attrs.remove(trigger_idx);
+                    for (event, body) in table_triggers {
+                        triggers.push((table_name.clone(), event, body));
+                    }
+                }
+                Some(Err(err)) => return err.to_compile_error().into(),
+                None => {}
+            }
+
+            let table_indexes = match get_index_attrs(table_field_attrs.as_slice(), "db")
+                .collect::<Result<Vec<_>>>()
+            {
+                Ok(table_indexes) => table_indexes,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            // From the last one, so that the positions of the others stay valid.
+            for (index_idx, _) in table_indexes.iter().rev() {
+                table_field_attrs.remove(*index_idx);
+            }
+            for (_, index) in table_indexes {
+                indexes.push((table_name.clone(), table_field_name.clone(), index));
+            }
+
+            let table_unique_constraints =
+                match get_unique_attrs(table_field_attrs.as_slice(), "db")
+                    .collect::<Result<Vec<_>>>()
+                {
+                    Ok(table_unique_constraints) => table_unique_constraints,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+            for (unique_idx, _) in table_unique_constraints.iter().rev() {
+                table_field_attrs.remove(*unique_idx);
+            }
+            for (_, columns) in table_unique_constraints {
+                unique_constraints.push((table_name.clone(), table_field_name.clone(), columns));
+            }
+
+            let table_counters = match get_counted_by_attrs(table_field_attrs.as_slice(), "db")
+                .collect::<Result<Vec<_>>>()
+            {
+                Ok(table_counters) => table_counters,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            for (counted_by_idx, _) in table_counters.iter().rev() {
+                table_field_attrs.remove(*counted_by_idx);
+            }
+            for (_, columns) in table_counters {
+                for column in columns {
+                    counters.push((table_name.clone(), table_field_name.clone(), column));
+                }
+            }
 
             let upper_snake_table_field_name_string = Ident::new(
                 &table_field_name_string.to_case(Case::UpperSnake),
@@ -84,21 +201,19 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
                 &table_field_name_string.to_case(Case::Pascal),
                 Span::call_site(),
             );
-            table_kinds.push(quote! { #pascal_table_field_name_string });
-            table_kinds_consts.push(quote! {
-                pub const #upper_snake_table_field_name_string: notitia::StrongTableKind<#database_name, Table<#record_ty, #database_name>> =
-                    notitia::StrongTableKind::new(#module_name::#table_kinds_enum_name::#pascal_table_field_name_string)
-            });
-            table_kinds_enum_to_str.push(quote! {
-                Self::#pascal_table_field_name_string => #table_field_name_string
-            });
+            own_tables.push((
+                table_name.clone(),
+                upper_snake_table_field_name_string,
+                pascal_table_field_name_string,
+                record_ty,
+            ));
 
             tables_method_items.push(quote! {
-                (#table_field_name_string, self.#table_field_name.rows_self())
+                (#table_name, self.#table_field_name.rows_self())
             });
 
             field_initializers.push(quote! {
-                #table_field_name: Table::new(#table_field_name_string)
+                #table_field_name: Table::new(#table_name)
             });
 
             let mut inner_foreign_relationships = Vec::new();
@@ -123,6 +238,7 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
 
                 let local_field_str = local_field.to_string();
                 let foreign_table_str = foreign_table.to_string();
+                let foreign_table_name = format!("{prefix}{foreign_table_str}");
                 let foreign_field_str = foreign_field.to_string();
 
                 if table_field_name_string == foreign_table_str {
@@ -153,14 +269,14 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
                             let _ = db.#foreign_table.test_type().#foreign_field;
                         }
 
-                        notitia::ForeignRelationship::new(#foreign_table_str, #foreign_field_str, #on_delete, #on_update)
+                        notitia::ForeignRelationship::new(#foreign_table_name, #foreign_field_str, #on_delete, #on_update)
                     }
                 })
             }
 
             if inner_foreign_relationships.len() != 0 {
                 foreign_relationships.push(quote! {
-                    #table_field_name_string => {
+                    #table_name => {
                         use notitia::phf;
 
                         phf::phf_map! {
@@ -170,9 +286,9 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
                 });
             }
 
-            embedding_table_entries.push((table_field_name_string.clone(), record_ty));
+            embedding_table_entries.push((table_name.clone(), record_ty));
 
-            let record_ty_with_name = RecordTyWithName::new(record_ty, table_field_name_string);
+            let record_ty_with_name = RecordTyWithName::new(record_ty, table_name);
 
             if used_tables.contains(&record_ty_with_name) {
                 return syn::Error::new_spanned(record_ty, "You can only use the same record type once in a database to prevent ambiguities with types.")
@@ -188,7 +304,58 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
-    let fields_of_database = used_tables
+    // A database that includes others identifies its tables by name, as the
+    // included databases can't name its table kind enum.
+    let composed = !included_databases.is_empty();
+
+    let table_kind_ty = if composed {
+        quote! { notitia::ComposedTableKind }
+    } else {
+        quote! { #module_name::#table_kinds_enum_name }
+    };
+
+    let table_kinds_consts = own_tables.iter().map(|(name, upper_snake, pascal, record_ty)| {
+        let kind = if composed {
+            quote! { notitia::ComposedTableKind::new(#name) }
+        } else {
+            quote! { #module_name::#table_kinds_enum_name::#pascal }
+        };
+
+        quote! {
+            pub const #upper_snake: notitia::StrongTableKind<#database_name, Table<#record_ty, #database_name>> =
+                notitia::StrongTableKind::new(#kind)
+        }
+    });
+
+    let table_kinds_module = if composed {
+        quote! {}
+    } else {
+        let table_kinds = own_tables.iter().map(|(_, _, pascal, _)| pascal);
+        let table_kinds_enum_to_str = own_tables
+            .iter()
+            .map(|(name, _, pascal, _)| quote! { Self::#pascal => #name });
+
+        quote! {
+            #[doc(hidden)]
+            mod #module_name {
+                #[derive(Debug)]
+                #[doc(hidden)]
+                pub enum #table_kinds_enum_name {
+                    #(#table_kinds),*
+                }
+
+                impl notitia::TableKind for #table_kinds_enum_name {
+                    fn name(&self) -> &'static str {
+                        match self {
+                            #(#table_kinds_enum_to_str),*
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let record_field_kinds = used_tables
         .iter()
         .filter_map(|RecordTyWithName { ty, name }| {
             let record_name = type_name(ty)?;
@@ -196,15 +363,92 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
             let record_mod = Ident::new(&format!("notitia_{}", record_name), Span::call_site());
             let record_field_name = Ident::new(&format!("{}Field", record_name), Span::call_site());
 
-            Some(quote! {
-                impl notitia::FieldKindOfDatabase<#database_name> for #record_mod::#record_field_name {
+            Some((quote! { #record_mod::#record_field_name }, name))
+        })
+        .collect::<Vec<_>>();
+
+    let fields_of_database = record_field_kinds.iter().map(|(field_kind, name)| {
+        quote! {
+            impl notitia::FieldKindOfDatabase<#database_name> for #field_kind {
+                fn table_name() -> &'static str {
+                    #name
+                }
+            }
+        }
+    });
+
+    // `#[database(includable)]`: table kinds and field kinds for every
+    // database that includes this one.
+    let includable_items = if includable {
+        let tables_name = Ident::new(&format!("{}Tables", database_name), Span::call_site());
+        let doc = format!(
+            "The tables of [`{database_name}`] inside a database `P` that includes it with `#[db(include)]`."
+        );
+
+        let consts = own_tables.iter().map(|(name, upper_snake, _, record_ty)| {
+            quote! {
+                pub const #upper_snake: notitia::StrongTableKind<P, notitia::Table<#record_ty, P>> =
+                    notitia::StrongTableKind::new(notitia::ComposedTableKind::new(#name));
+            }
+        });
+
+        let field_kinds = record_field_kinds.iter().map(|(field_kind, name)| {
+            quote! {
+                impl<P: notitia::Includes<#database_name>> notitia::FieldKindOfDatabase<P> for #field_kind {
                     fn table_name() -> &'static str {
                         #name
                     }
                 }
-            })
+            }
         });
 
+        quote! {
+            #[doc = #doc]
+            #vis struct #tables_name<P>(std::marker::PhantomData<P>);
+
+            impl<P: notitia::Includes<#database_name>> #tables_name<P> {
+                #(#consts)*
+            }
+
+            #(#field_kinds)*
+        }
+    } else {
+        quote! {}
+    };
+
+    let included_items = included_databases
+        .iter()
+        .map(|(field_name, ty, alias, tables_ty)| {
+            let doc = format!("The tables included through [`{database_name}::{field_name}`].");
+
+            quote! {
+                impl notitia::Includes<#ty> for #database_name {}
+
+                #[doc = #doc]
+                #vis type #alias = #tables_ty<#database_name>;
+            }
+        });
+
+    let included_fields = included_databases
+        .iter()
+        .map(|(field_name, ..)| field_name)
+        .collect::<Vec<_>>();
+
+    let foreign_relationships_override = if composed {
+        quote! {
+            fn foreign_relationships(
+                &self,
+                table_name: &str,
+            ) -> Option<&'static notitia::phf::Map<&'static str, notitia::ForeignRelationship>> {
+                Self::_FOREIGN_RELATIONSHIPS
+                    .get(table_name)
+                    #(.or_else(|| self.#included_fields.foreign_relationships(table_name)))*
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     // Generate embedded_tables() override and embedder-aware connect, gated on embeddings feature.
     #[cfg(feature = "embeddings")]
     let embedded_tables_override = {
@@ -225,6 +469,7 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
             fn embedded_tables(&self) -> Vec<notitia::EmbeddedTableDef> {
                 let mut tables = Vec::new();
                 #(#items)*
+                #(tables.extend(self.#included_fields.embedded_tables());)*
                 tables
             }
         }
@@ -260,13 +505,131 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     });
 
+    let record_name_items = own_tables.iter().map(|(table_name, _, _, record_ty)| {
+        let record_name = match record_ty {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| quote!(#record_ty).to_string());
+        quote! { (#table_name, #record_name) }
+    });
+
+    let trigger_items = triggers.iter().map(|(table_name, event, body)| {
+        quote! {
+            notitia::TriggerDef {
+                table_name: #table_name,
+                event: notitia::TriggerEvent::#event,
+                body: #body,
+            }
+        }
+    });
+
+    let counter_items = counters
+        .iter()
+        .map(|(table_name, table_field_name, column)| {
+            let column_name = column.to_string();
+            let counts_table = format!("{table_name}_counts_by_{column_name}");
+
+            quote! {
+                {
+                    #[allow(deprecated)]
+                    fn _check_fields(db: #database_name) {
+                        /// Throws error if a counted field doesn't exist.
+                        let _ = db.#table_field_name.test_type().#column;
+                    }
+
+                    notitia::CounterDef {
+                        table_name: #table_name,
+                        column: #column_name,
+                        counts_table: #counts_table,
+                    }
+                }
+            }
+        });
+
+    let index_items = indexes.iter().map(|(table_name, table_field_name, index)| {
+        let name = index.name.clone().unwrap_or_else(|| {
+            let columns: Vec<&str> = index
+                .columns
+                .iter()
+                .map(|(column, _)| column.as_str())
+                .collect();
+            format!("{table_name}_by_{}", columns.join("_"))
+        });
+        let columns = index.columns.iter().map(|(column, direction)| {
+            quote! {
+                notitia::IndexColumnDef {
+                    name: #column,
+                    direction: notitia::OrderDirection::#direction,
+                }
+            }
+        });
+        let include = &index.include;
+        let unique = index.unique;
+        let column_idents = index
+            .columns
+            .iter()
+            .map(|(column, _)| column)
+            .chain(include)
+            .map(|column| Ident::new(column, index.span));
+
+        quote! {
+            (#table_name, {
+                #[allow(deprecated)]
+                fn _check_fields(db: #database_name) {
+                    /// Throws error if an indexed field doesn't exist.
+                    #(let _ = db.#table_field_name.test_type().#column_idents;)*
+                }
+
+                notitia::IndexDef {
+                    name: Some(#name),
+                    columns: vec![#(#columns),*],
+                    include: vec![#(#include),*],
+                    unique: #unique,
+                }
+            })
+        }
+    });
+
+    let unique_constraint_items =
+        unique_constraints
+            .iter()
+            .map(|(table_name, table_field_name, columns)| {
+                let column_names = columns.iter().map(|column| column.to_string());
+
+                quote! {
+                    (#table_name, {
+                        #[allow(deprecated)]
+                        fn _check_fields(db: #database_name) {
+                            /// Throws error if a field of the constraint doesn't exist.
+                            #(let _ = db.#table_field_name.test_type().#columns;)*
+                        }
+
+                        notitia::IndexDef {
+                            name: None,
+                            columns: vec![#(notitia::IndexColumnDef {
+                                name: #column_names,
+                                direction: notitia::OrderDirection::Asc,
+                            }),*],
+                            include: Vec::new(),
+                            unique: true,
+                        }
+                    })
+                }
+            });
+    let index_items = index_items.chain(unique_constraint_items);
+
     let expanded = quote! {
         #vis struct #database_name #generics {
             #(#fields),*
         }
 
         impl notitia::Database for #database_name {
-            type TableKind = #module_name::#table_kinds_enum_name;
+            type TableKind = #table_kind_ty;
 
             const _FOREIGN_RELATIONSHIPS: notitia::phf::Map<&'static str, notitia::phf::Map<&'static str, notitia::ForeignRelationship>> = {
                 use notitia::phf;
@@ -285,8 +648,11 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
             #[allow(deprecated)]
             fn tables(&self) -> impl Iterator<Item = (&'static str, notitia::FieldsDef)> {
                 [#(#tables_method_items),*].into_iter()
+                    #(.chain(self.#included_fields.tables()))*
             }
 
+            #foreign_relationships_override
+
             #embedded_tables_override
 
             const _REMOVED_TABLES: &'static [&'static str] = #removed_tables_tokens;
@@ -294,6 +660,27 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             fn table_migration_metadata(&self) -> impl Iterator<Item = (&'static str, notitia::TableMigrationMeta)> {
                 [#(#migration_metadata_items),*].into_iter()
+                    #(.chain(self.#included_fields.table_migration_metadata()))*
+            }
+
+            fn record_names(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+                [#(#record_name_items),*].into_iter()
+                    #(.chain(self.#included_fields.record_names()))*
+            }
+
+            fn triggers(&self) -> impl Iterator<Item = notitia::TriggerDef> {
+                [#(#trigger_items),*].into_iter()
+                    #(.chain(self.#included_fields.triggers()))*
+            }
+
+            fn counters(&self) -> impl Iterator<Item = notitia::CounterDef> {
+                [#(#counter_items),*].into_iter()
+                    #(.chain(self.#included_fields.counters()))*
+            }
+
+            fn indexes(&self) -> impl Iterator<Item = (&'static str, notitia::IndexDef)> {
+                [#(#index_items),*].into_iter()
+                    #(.chain(self.#included_fields.indexes()))*
             }
         }
 
@@ -303,22 +690,11 @@ pub fn impl_database(attr: TokenStream, item: TokenStream) -> TokenStream {
 
         #(#fields_of_database)*
 
-        #[doc(hidden)]
-        mod #module_name {
-            #[derive(Debug)]
-            #[doc(hidden)]
-            pub enum #table_kinds_enum_name {
-                #(#table_kinds),*
-            }
+        #includable_items
 
-            impl notitia::TableKind for #table_kinds_enum_name {
-                fn name(&self) -> &'static str {
-                    match self {
-                        #(#table_kinds_enum_to_str),*
-                    }
-                }
-            }
-        }
+        #(#included_items)*
+
+        #table_kinds_module
     };
 
     TokenStream::from(expanded)
@@ -407,6 +783,294 @@ where
     })
 }
 
+/// Parse `trigger(after_insert = "...", after_delete = "...")` from `#[db(...)]`
+/// attributes on a table field, turning the events into `TriggerEvent` variants.
+fn get_trigger_attr<T>(
+    attrs: &[T],
+    ident: &str,
+) -> Option<Result<(usize, Vec<(Ident, syn::LitStr)>)>>
+where
+    T: Borrow<Attribute>,
+{
+    const EVENTS: [(&str, &str); 6] = [
+        ("before_insert", "BeforeInsert"),
+        ("after_insert", "AfterInsert"),
+        ("before_update", "BeforeUpdate"),
+        ("after_update", "AfterUpdate"),
+        ("before_delete", "BeforeDelete"),
+        ("after_delete", "AfterDelete"),
+    ];
+
+    for (attr_idx, attr) in attrs.iter().enumerate() {
+        let attr = attr.borrow();
+
+        if !attr.path().is_ident(ident) {
+            continue;
+        }
+
+        let mut found = false;
+        let mut triggers: Vec<(Ident, syn::LitStr)> = Vec::new();
+
+        let result = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("trigger") {
+                return Ok(());
+            }
+
+            found = true;
+
+            meta.parse_nested_meta(|event| {
+                let key = event.path.require_ident()?;
+                let Some((_, variant)) = EVENTS.iter().find(|(name, _)| key == name) else {
+                    return Err(Error::new_spanned(
+                        key,
+                        "expected `before_insert`, `after_insert`, `before_update`, `after_update`, `before_delete`, or `after_delete`",
+                    ));
+                };
+
+                let variant = Ident::new(variant, key.span());
+                if triggers.iter().any(|(existing, _)| *existing == variant) {
+                    return Err(Error::new_spanned(key, format!("duplicate `{key}`")));
+                }
+
+                let body: syn::LitStr = event.value()?.parse()?;
+                triggers.push((variant, body));
+                Ok(())
+            })
+        });
+
+        // Errors from the other arguments are left to their own parsers.
+        if found {
+            return Some(result.map(|()| (attr_idx, triggers)));
+        }
+    }
+
+    None
+}
+
+struct IndexAttr {
+    name: Option<String>,
+    /// The key columns, each with its `OrderDirection` variant.
+    columns: Vec<(String, Ident)>,
+    include: Vec<String>,
+    unique: bool,
+    span: Span,
+}
+
+/// Parse `index(columns = "chat_id, created_at desc", include = "content")`
+/// from `#[db(...)]` attributes on a table field. `name` and the `unique`
+/// flag are optional.
+fn get_index_attrs<T>(attrs: &[T], ident: &str) -> impl Iterator<Item = Result<(usize, IndexAttr)>>
+where
+    T: Borrow<Attribute>,
+{
+    attrs.iter().enumerate().filter_map(move |(idx, attr)| {
+        let attr = attr.borrow();
+
+        if !attr.path().is_ident(ident) {
+            return None;
+        }
+
+        let mut found: Option<IndexAttr> = None;
+
+        let result = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("index") {
+                return Ok(());
+            }
+
+            let mut name = None;
+            let mut columns = None;
+            let mut include = Vec::new();
+            let mut unique = false;
+
+            meta.parse_nested_meta(|arg| {
+                let key = arg.path.require_ident()?;
+                if key == "unique" {
+                    unique = true;
+                    return Ok(());
+                }
+                let value: syn::LitStr = arg.value()?.parse()?;
+
+                if key == "name" {
+                    name = Some(value.value());
+                } else if key == "columns" {
+                    columns = Some(parse_index_columns(&value)?);
+                } else if key == "include" {
+                    include = split_column_list(&value)?;
+                    if include
+                        .iter()
+                        .any(|column| column.contains(char::is_whitespace))
+                    {
+                        return Err(Error::new_spanned(
+                            value,
+                            "included columns have no direction",
+                        ));
+                    }
+                } else {
+                    return Err(Error::new_spanned(
+                        key,
+                        "expected `columns`, `include`, `name`, or `unique`",
+                    ));
+                }
+                Ok(())
+            })?;
+
+            let Some(columns) = columns else {
+                return Err(meta.error("`index` needs `columns`"));
+            };
+
+            found = Some(IndexAttr {
+                name,
+                columns,
+                include,
+                unique,
+                span: meta.path.require_ident()?.span(),
+            });
+            Ok(())
+        });
+
+        // Errors from the other arguments are left to their own parsers.
+        found.map(|index| result.map(|()| (idx, index)))
+    })
+}
+
+/// Parse `unique(message_id, user_id)` from `#[db(...)]` attributes on a
+/// table field.
+fn get_unique_attrs<T>(
+    attrs: &[T],
+    ident: &str,
+) -> impl Iterator<Item = Result<(usize, Vec<Ident>)>>
+where
+    T: Borrow<Attribute>,
+{
+    attrs.iter().enumerate().filter_map(move |(idx, attr)| {
+        let attr = attr.borrow();
+
+        if !attr.path().is_ident(ident) {
+            return None;
+        }
+
+        let mut found: Option<Vec<Ident>> = None;
+
+        let result = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("unique") {
+                return Ok(());
+            }
+
+            let mut columns = Vec::new();
+            meta.parse_nested_meta(|column| {
+                columns.push(column.path.require_ident()?.clone());
+                Ok(())
+            })?;
+            if columns.len() < 2 {
+                return Err(meta.error(
+                    "`unique` needs at least two columns, a single one is marked `#[db(unique)]` in the record",
+                ));
+            }
+
+            found = Some(columns);
+            Ok(())
+        });
+
+        // Errors from the other arguments are left to their own parsers.
+        found.map(|columns| result.map(|()| (idx, columns)))
+    })
+}
+
+/// Parse `counted_by(chat_id, author_id)` from `#[db(...)]` attributes on a
+/// table field, a counter for each column.
+fn get_counted_by_attrs<T>(
+    attrs: &[T],
+    ident: &str,
+) -> impl Iterator<Item = Result<(usize, Vec<Ident>)>>
+where
+    T: Borrow<Attribute>,
+{
+    attrs.iter().enumerate().filter_map(move |(idx, attr)| {
+        let attr = attr.borrow();
+
+        if !attr.path().is_ident(ident) {
+            return None;
+        }
+
+        let mut found: Option<Vec<Ident>> = None;
+
+        let result = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("counted_by") {
+                return Ok(());
+            }
+
+            let mut columns = Vec::new();
+            meta.parse_nested_meta(|column| {
+                columns.push(column.path.require_ident()?.clone());
+                Ok(())
+            })?;
+            if columns.is_empty() {
+                return Err(meta.error("`counted_by` needs a column"));
+            }
+
+            found = Some(columns);
+            Ok(())
+        });
+
+        // Errors from the other arguments are left to their own parsers.
+        found.map(|columns| result.map(|()| (idx, columns)))
+    })
+}
+
+fn split_column_list(list: &syn::LitStr) -> Result<Vec<String>> {
+    list.value()
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(|column| {
+            let name = column.split_whitespace().next().unwrap_or_default();
+            if syn::parse_str::<Ident>(name).is_err() {
+                return Err(Error::new_spanned(
+                    list,
+                    format!("`{name}` is not a field name"),
+                ));
+            }
+            Ok(column.to_string())
+        })
+        .collect()
+}
+
+/// `"chat_id, created_at desc"`, where every column is ascending unless it
+/// is followed by `desc`.
+fn parse_index_columns(list: &syn::LitStr) -> Result<Vec<(String, Ident)>> {
+    let columns = split_column_list(list)?
+        .iter()
+        .map(|column| {
+            let mut words = column.split_whitespace();
+            let name = words.next().unwrap_or_default().to_string();
+            let direction = match words.next().map(str::to_lowercase).as_deref() {
+                None | Some("asc") => "Asc",
+                Some("desc") => "Desc",
+                Some(_) => {
+                    return Err(Error::new_spanned(
+                        list,
+                        format!("expected `asc` or `desc` after `{name}`"),
+                    ));
+                }
+            };
+            if words.next().is_some() {
+                return Err(Error::new_spanned(
+                    list,
+                    "expected columns separated by commas",
+                ));
+            }
+
+            Ok((name, Ident::new(direction, list.span())))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if columns.is_empty() {
+        return Err(Error::new_spanned(list, "expected at least one column"));
+    }
+
+    Ok(columns)
+}
+
 fn parse_on_actions(
     content: &ParseBuffer<'_>,
 ) -> Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
@@ -489,6 +1153,21 @@ impl<'a> Hash for RecordTyWithName<'a> {
     }
 }
 
+/// `path::AuthDatabase` -> `path::AuthDatabaseTables`, the type generated by
+/// `#[database(includable)]`.
+fn included_tables_type(ty: &Type) -> Option<TypePath> {
+    let Type::Path(ty_path) = ty else { return None };
+
+    let mut tables_ty = ty_path.clone();
+    let segment = tables_ty.path.segments.last_mut()?;
+    if !segment.arguments.is_none() {
+        return None;
+    }
+    segment.ident = Ident::new(&format!("{}Tables", segment.ident), segment.ident.span());
+
+    Some(tables_ty)
+}
+
 fn type_name(ty: &Type) -> Option<String> {
     match ty {
         Type::Path(TypePath { path, .. }) => path.get_ident().map(|ident| ident.to_string()),
@@ -5,7 +5,9 @@ use proc_macro2::Span;
 use quote::quote;
 use syn::{Fields, GenericArgument, Ident, ItemStruct, PathArguments, Type, parse_macro_input};
 
-use crate::utils::{get_attr_idx, get_migrate_from_attr, parse_ident_list_attr};
+use crate::utils::{
+    attr_is, get_attr_idx, get_migrate_from_attr, parse_flag_attr, parse_ident_list_attr,
+};
 #[cfg(feature = "embeddings")]
 use crate::utils::get_embed_attr;
 
@@ -32,7 +34,36 @@ fn extract_option_inner(ty: &Type) -> Option<&Type> {
     }
 }
 
+/// A field whose value the database fills in on insert when it's left unset.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Generated {
+    /// `#[db(auto_id)]`: a `String` from the database's `IdGenerator`.
+    Id,
+    /// `#[db(created_at)]`: an `i64` of milliseconds from its `Clock`.
+    CreatedAt,
+}
+
+fn get_generated(attrs: &[syn::Attribute]) -> Option<Generated> {
+    if get_attr_idx(attrs, "db", "auto_id").is_some() {
+        Some(Generated::Id)
+    } else if get_attr_idx(attrs, "db", "created_at").is_some() {
+        Some(Generated::CreatedAt)
+    } else {
+        None
+    }
+}
+
+/// Strip the attributes that only change the generated builder.
+fn strip_builder_attrs(attrs: &mut Vec<&syn::Attribute>) {
+    attrs.retain(|attr| {
+        !attr_is(attr, "db", "auto_id")
+            && !attr_is(attr, "db", "created_at")
+            && !attr_is(attr, "db", "redact")
+    });
+}
+
 pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let serde = parse_flag_attr(attr.clone(), "serde");
     let removed_fields = parse_ident_list_attr(attr, "removed_fields");
 
     let input = parse_macro_input!(item as ItemStruct);
@@ -46,8 +77,9 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let module_name = Ident::new(&format!("notitia_{}", name), Span::call_site());
 
-    // Track the primary key field name for _PK_FIELD const.
+    // Track the primary key field name and type for the _PK_FIELD / PRIMARY_KEY consts.
     let mut pk_field_name: Option<String> = None;
+    let mut pk_field_ty: Option<Type> = None;
 
     // Collect field migration metadata: (current_field_name, [old_names]).
     let mut field_migrations: Vec<(String, Vec<String>)> = Vec::new();
@@ -87,6 +119,15 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     });
 
+    let field_set_datatypes = fields_named.named.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_name_string = field_name.to_string();
+
+        quote! {
+            #field_name_string => self.#field_name = ::core::convert::TryFrom::try_from(value)?,
+        }
+    });
+
     let constructor_fields: Vec<_> = fields_named
         .named
         .iter()
@@ -106,8 +147,10 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             if let Some(attr_idx) = get_attr_idx(field_attrs.as_slice(), "db", "primary_key") {
                 field_attrs.remove(attr_idx);
+                strip_builder_attrs(&mut field_attrs);
 
                 pk_field_name = Some(field_name.as_ref().unwrap().to_string());
+                pk_field_ty = Some(field_ty.clone());
 
                 // Also strip embed if present (primary_key takes precedence for wrapping).
                 #[cfg(feature = "embeddings")]
@@ -121,6 +164,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             } else if let Some(attr_idx) = get_attr_idx(field_attrs.as_slice(), "db", "unique") {
                 field_attrs.remove(attr_idx);
+                strip_builder_attrs(&mut field_attrs);
 
                 // Also strip embed if present.
                 #[cfg(feature = "embeddings")]
@@ -133,6 +177,8 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #field_vis #field_name: notitia::Unique<#field_ty>
                 }
             } else {
+                strip_builder_attrs(&mut field_attrs);
+
                 #[cfg(feature = "embeddings")]
                 if let Some((embed_idx, embed_attr)) = get_embed_attr(field_attrs.as_slice(), "db")
                 {
@@ -161,10 +207,16 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
             return None;
         };
 
-        Some(Ident::new(
+        let variant = Ident::new(
             &field_name.to_string().to_case(convert_case::Case::Pascal),
             Span::call_site(),
-        ))
+        );
+        if serde {
+            let field_name_string = field_name.to_string();
+            Some(quote! { #[serde(rename = #field_name_string)] #variant })
+        } else {
+            Some(quote! { #variant })
+        }
     });
 
     let enum_field_consts = fields_named.named.iter().filter_map(|field| {
@@ -239,6 +291,11 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         is_embed: bool,
         is_optional: bool,
         option_inner_ty: Option<proc_macro2::TokenStream>,
+        generated: Option<Generated>,
+        /// Optional and generated fields may be left unset.
+        is_defaulted: bool,
+        /// `#[db(redact)]`: hidden from the builder's `Debug` output.
+        is_redacted: bool,
     }
 
     let builder_fields: Vec<BuilderFieldInfo> = fields_named
@@ -265,6 +322,11 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
             let is_optional = option_inner.is_some();
             let option_inner_ty = option_inner.map(|inner| quote! { #inner });
 
+            let generated = get_generated(field_attrs);
+            if generated.is_some() && is_optional {
+                panic!("`{field_name}`: generated fields can't be optional");
+            }
+
             Some(BuilderFieldInfo {
                 field_name,
                 generic_ident,
@@ -274,6 +336,9 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
                 is_embed,
                 is_optional,
                 option_inner_ty,
+                is_defaulted: is_optional || generated.is_some(),
+                generated,
+                is_redacted: get_attr_idx(field_attrs, "db", "redact").is_some(),
             })
         })
         .collect();
@@ -281,17 +346,17 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
     // Builder struct generic params with defaults (only for non-optional fields)
     let builder_generic_params_with_defaults: Vec<_> = builder_fields
         .iter()
-        .filter(|f| !f.is_optional)
+        .filter(|f| !f.is_defaulted)
         .map(|f| {
             let gi = &f.generic_ident;
             quote! { #gi = notitia::UnsetField }
         })
         .collect();
 
-    // Builder struct fields: optional fields use Option<FieldExpr>, others use generics
+    // Builder struct fields: defaulted fields use Option<FieldExpr>, others use generics
     let builder_struct_fields = builder_fields.iter().map(|f| {
         let fname = &f.field_name;
-        if f.is_optional {
+        if f.is_defaulted {
             quote! { #fname: Option<notitia::FieldExpr> }
         } else {
             let gi = &f.generic_ident;
@@ -301,7 +366,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let builder_generic_idents: Vec<_> = builder_fields
         .iter()
-        .filter(|f| !f.is_optional)
+        .filter(|f| !f.is_defaulted)
         .map(|f| &f.generic_ident)
         .collect();
 
@@ -312,9 +377,9 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         let return_generics: Vec<_> = builder_fields
             .iter()
             .enumerate()
-            .filter(|(_, fj)| !fj.is_optional)
+            .filter(|(_, fj)| !fj.is_defaulted)
             .map(|(j, fj)| {
-                if j == idx && !f.is_optional {
+                if j == idx && !f.is_defaulted {
                     quote! { notitia::FieldExpr }
                 } else {
                     let gi = &fj.generic_ident;
@@ -326,7 +391,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         let struct_init_fields = builder_fields.iter().enumerate().map(|(j, fj)| {
             let fj_name = &fj.field_name;
             if j == idx {
-                if f.is_optional {
+                if f.is_defaulted {
                     quote! { #fj_name: Some(value.into()) }
                 } else {
                     quote! { #fj_name: value.into() }
@@ -345,10 +410,10 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     });
 
-    // BuiltRecord: all non-optional fields are FieldExpr
+    // BuiltRecord: all non-defaulted fields are FieldExpr
     let builder_concrete_types: Vec<_> = builder_fields
         .iter()
-        .filter(|f| !f.is_optional)
+        .filter(|f| !f.is_defaulted)
         .map(|_| quote! { notitia::FieldExpr })
         .collect();
 
@@ -356,7 +421,26 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
     let finish_fields = builder_fields.iter().map(|f| {
         let fname = &f.field_name;
         let raw_ty = &f.raw_ty;
-        if f.is_primary_key {
+        if f.generated.is_some() {
+            // Left at the type's default for `Record::fill_generated`.
+            let value = quote! {
+                self.#fname
+                    .map(|expr| {
+                        let notitia::FieldExpr::Literal(val) = expr else {
+                            panic!("BuiltRecord::finish only supports literal field values");
+                        };
+                        <#raw_ty as TryFrom<notitia::Datatype>>::try_from(val).unwrap()
+                    })
+                    .unwrap_or_default()
+            };
+            if f.is_primary_key {
+                quote! { #fname: notitia::PrimaryKey::new(#value) }
+            } else if f.is_unique {
+                quote! { #fname: notitia::Unique::new(#value) }
+            } else {
+                quote! { #fname: #value }
+            }
+        } else if f.is_primary_key {
             quote! {
                 #fname: {
                     let notitia::FieldExpr::Literal(val) = self.#fname else {
@@ -411,7 +495,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
     // PartialRecord impl
     let partial_record_generic_params: Vec<_> = builder_fields
         .iter()
-        .filter(|f| !f.is_optional)
+        .filter(|f| !f.is_defaulted)
         .map(|f| {
             let gi = &f.generic_ident;
             quote! { #gi: notitia::MaybeSetExpr }
@@ -420,7 +504,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let partial_record_generic_args: Vec<_> = builder_fields
         .iter()
-        .filter(|f| !f.is_optional)
+        .filter(|f| !f.is_defaulted)
         .map(|f| {
             let gi = &f.generic_ident;
             quote! { #gi }
@@ -432,7 +516,7 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         .map(|f| {
             let fname = &f.field_name;
             let fname_str = fname.to_string();
-            if f.is_optional {
+            if f.is_defaulted {
                 quote! {
                     if let Some(expr) = self.#fname {
                         fields.push((#fname_str, expr));
@@ -448,20 +532,175 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
         })
         .collect();
 
+    let partial_record_field_replaces: Vec<_> = builder_fields
+        .iter()
+        .map(|f| {
+            let fname = &f.field_name;
+            let fname_str = fname.to_string();
+            if f.is_defaulted {
+                quote! {
+                    #fname_str => match &mut self.#fname {
+                        Some(current) => {
+                            *current = expr;
+                            true
+                        }
+                        None => false,
+                    },
+                }
+            } else {
+                quote! {
+                    #fname_str => notitia::MaybeSetExpr::replace_field_expr(&mut self.#fname, expr),
+                }
+            }
+        })
+        .collect();
+
     let build_init_fields = builder_fields.iter().map(|f| {
         let fname = &f.field_name;
-        if f.is_optional {
+        if f.is_defaulted {
             quote! { #fname: None }
         } else {
             quote! { #fname: notitia::UnsetField }
         }
     });
 
-    // Generate _PK_FIELD const if a primary key field was found.
-    let pk_field_const = if let Some(ref pk_name) = pk_field_name {
+    // Fill generated fields that were left unset, with the database's ids
+    // and clock.
+    let fill_generated = {
+        let uses = |kind: Generated| builder_fields.iter().any(|f| f.generated == Some(kind));
+        let ids = Ident::new(
+            if uses(Generated::Id) { "ids" } else { "_ids" },
+            Span::call_site(),
+        );
+        let clock = Ident::new(
+            if uses(Generated::CreatedAt) {
+                "clock"
+            } else {
+                "_clock"
+            },
+            Span::call_site(),
+        );
+
+        let fills = builder_fields.iter().filter_map(|f| {
+            let fname = &f.field_name;
+            let wrap = |value: proc_macro2::TokenStream| {
+                if f.is_primary_key {
+                    quote! { notitia::PrimaryKey::new(#value) }
+                } else if f.is_unique {
+                    quote! { notitia::Unique::new(#value) }
+                } else {
+                    value
+                }
+            };
+            let current = if f.is_primary_key || f.is_unique {
+                quote! { *self.#fname }
+            } else {
+                quote! { self.#fname }
+            };
+
+            match f.generated? {
+                Generated::Id => {
+                    let value = wrap(quote! { #ids.next_id() });
+                    Some(quote! {
+                        if #current.is_empty() {
+                            self.#fname = #value;
+                        }
+                    })
+                }
+                Generated::CreatedAt => {
+                    let value = wrap(quote! { #clock.now_millis() });
+                    Some(quote! {
+                        if #current == 0 {
+                            self.#fname = #value;
+                        }
+                    })
+                }
+            }
+        });
+
+        if builder_fields.iter().any(|f| f.generated.is_some()) {
+            quote! {
+                fn fill_generated(&mut self, #ids: &dyn notitia::IdGenerator, #clock: &dyn notitia::Clock) {
+                    #(#fills)*
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
+
+    // Debug shows the fields that were set, as the expressions they were set
+    // to.
+    let builder_name_string = builder_name.to_string();
+    let debug_fields = builder_fields.iter().map(|f| {
+        let fname = &f.field_name;
+        let fname_str = fname.to_string();
+        let expr = if f.is_defaulted {
+            quote! { self.#fname.clone() }
+        } else {
+            quote! { notitia::MaybeSetExpr::into_field_expr(self.#fname.clone()) }
+        };
+        if f.is_redacted {
+            quote! {
+                if #expr.is_some() {
+                    debug.field(#fname_str, &format_args!("<redacted>"));
+                }
+            }
+        } else {
+            quote! {
+                if let Some(expr) = #expr {
+                    debug.field(#fname_str, &expr);
+                }
+            }
+        }
+    });
+
+    // With `#[record(serde)]`, the field enum (de)serializes as the column
+    // name, and builders serialize as a map of the fields that were set.
+    let (field_enum_serde, builder_serde) = if serde {
+        let field_enum_serde = quote! {
+            #[derive(notitia::serde::Serialize, notitia::serde::Deserialize)]
+            #[serde(crate = "notitia::serde")]
+        };
+        let builder_serde = quote! {
+            impl<#(#partial_record_generic_params),*> notitia::serde::Serialize for #builder_name<#(#partial_record_generic_args),*> {
+                fn serialize<S: notitia::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    use notitia::serde::ser::SerializeMap;
+
+                    let fields = notitia::PartialRecord::into_set_fields(self.clone());
+                    let mut map = serializer.serialize_map(Some(fields.len()))?;
+                    for (name, expr) in &fields {
+                        map.serialize_entry(name, expr)?;
+                    }
+                    map.end()
+                }
+            }
+        };
+        (field_enum_serde, builder_serde)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // Generate _PK_FIELD and PRIMARY_KEY consts if a primary key field was found.
+    let pk_field_const = if let (Some(pk_name), Some(pk_ty)) = (&pk_field_name, &pk_field_ty) {
+        let pk_const_name = pk_name.to_case(convert_case::Case::UpperSnake);
+
+        // A field literally named `primary_key` already provides the const.
+        let pk_alias = if pk_const_name == "PRIMARY_KEY" {
+            quote! {}
+        } else {
+            let pk_const = Ident::new(&pk_const_name, Span::call_site());
+            quote! {
+                /// The primary key field, under a name generic code can rely on.
+                pub const PRIMARY_KEY: notitia::StrongFieldKind<#module_name::#table_field_enum_name, notitia::PrimaryKey<#pk_ty>> =
+                    Self::#pk_const;
+            }
+        };
+
         quote! {
             impl #generics #name #generics {
                 pub const _PK_FIELD: &'static str = #pk_name;
+                #pk_alias
             }
         }
     } else {
@@ -524,11 +763,26 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
             fn into_datatypes(self) -> Vec<(&'static str, notitia::Datatype)> {
                 vec![#(#field_into_datatypes),*]
             }
+
+            fn set_datatype(
+                &mut self,
+                column: &str,
+                value: notitia::Datatype,
+            ) -> Result<(), notitia::DatatypeConversionError> {
+                match column {
+                    #(#field_set_datatypes)*
+                    _ => {}
+                }
+                Ok(())
+            }
+
+            #fill_generated
         }
 
         #[doc(hidden)]
         mod #module_name {
             #[derive(Clone, Copy, Debug)]
+            #field_enum_serde
             #[doc(hidden)]
             pub enum #table_field_enum_name {
                 #(#enum_fields),*
@@ -560,8 +814,25 @@ pub fn impl_record(attr: TokenStream, item: TokenStream) -> TokenStream {
                 #(#partial_record_field_pushes)*
                 fields
             }
+
+            fn replace_set_field(&mut self, column: &str, expr: notitia::FieldExpr) -> bool {
+                match column {
+                    #(#partial_record_field_replaces)*
+                    _ => false,
+                }
+            }
+        }
+
+        impl<#(#partial_record_generic_params),*> std::fmt::Debug for #builder_name<#(#partial_record_generic_args),*> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let mut debug = f.debug_struct(#builder_name_string);
+                #(#debug_fields)*
+                debug.finish()
+            }
         }
 
+        #builder_serde
+
         impl notitia::BuiltRecord for #builder_name<#(#builder_concrete_types),*> {
             type Record = #name;
 
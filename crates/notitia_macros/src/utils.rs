@@ -171,6 +171,8 @@ pub fn parse_ident_list_attr(
                 for id in idents {
                     names.push(id.to_string());
                 }
+            } else {
+                skip_attr_value(input)?;
             }
 
             // Skip comma between top-level items
@@ -182,3 +184,74 @@ pub fn parse_ident_list_attr(
     let _ = parser.parse(attr);
     names
 }
+
+/// Whether a bare `name` flag is present in an attribute argument list, e.g. the
+/// `includable` in `#[database(includable, removed_tables(old))]`.
+pub fn parse_flag_attr(attr: proc_macro::TokenStream, expected_name: &str) -> bool {
+    use syn::parse::Parser;
+
+    let mut found = false;
+
+    let parser = |input: syn::parse::ParseStream| -> syn::Result<()> {
+        while !input.is_empty() {
+            let meta_ident: Ident = input.parse()?;
+
+            if meta_ident == expected_name && !input.peek(syn::token::Paren) {
+                found = true;
+            }
+            skip_attr_value(input)?;
+
+            // Skip comma between top-level items
+            let _ = input.parse::<Token![,]>();
+        }
+        Ok(())
+    };
+
+    let _ = parser.parse(attr);
+    found
+}
+
+/// Parse a string argument from a `TokenStream`, e.g. the `"chat_"` in
+/// `#[database(prefix = "chat_")]`.
+pub fn parse_str_attr(
+    attr: proc_macro::TokenStream,
+    expected_name: &str,
+) -> syn::Result<Option<String>> {
+    use syn::parse::Parser;
+
+    let mut value = None;
+
+    let parser = |input: syn::parse::ParseStream| -> syn::Result<()> {
+        while !input.is_empty() {
+            let meta_ident: Ident = input.parse()?;
+
+            if meta_ident == expected_name {
+                input.parse::<Token![=]>()?;
+                let lit: syn::LitStr = input.parse()?;
+                value = Some(lit.value());
+            } else {
+                skip_attr_value(input)?;
+            }
+
+            // Skip comma between top-level items
+            let _ = input.parse::<Token![,]>();
+        }
+        Ok(())
+    };
+
+    parser.parse(attr)?;
+    Ok(value)
+}
+
+/// Skip the value of an argument another parser is responsible for: the
+/// list in `name(...)` or the literal in `name = "..."`.
+fn skip_attr_value(input: syn::parse::ParseStream) -> syn::Result<()> {
+    if input.peek(syn::token::Paren) {
+        let _content;
+        syn::parenthesized!(_content in input);
+    } else if input.peek(Token![=]) {
+        input.parse::<Token![=]>()?;
+        input.parse::<syn::Lit>()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::io::AsyncWrite;
+use notitia_core::{BlobLocation, Datatype, FieldExpr};
+
+use crate::MemoryStore;
+
+/// The blob writer of a [`MemoryAdapter`](crate::MemoryAdapter). It
+/// buffers the bytes and stores them in the column when closed.
+pub struct MemoryBlobWriter {
+    pub(crate) store: MemoryStore,
+    pub(crate) location: BlobLocation,
+    pub(crate) len: usize,
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) closed: bool,
+}
+
+impl AsyncWrite for MemoryBlobWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = buf.len().min(this.len - this.bytes.len());
+        this.bytes.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Bytes that weren't written are zero, as with a real blob writer.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if std::mem::replace(&mut this.closed, true) {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut bytes = std::mem::take(&mut this.bytes);
+        bytes.resize(this.len, 0);
+        let location = &this.location;
+        let written = this.store.lock().write(Vec::new(), |tables| {
+            tables.update(
+                location.table_name,
+                &[(location.column, FieldExpr::Literal(Datatype::Blob(bytes)))],
+                &[location.key_filter()],
+            )
+        });
+        Poll::Ready(written.map_err(io::Error::other))
+    }
+}
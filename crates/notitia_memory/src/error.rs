@@ -0,0 +1,70 @@
+use notitia_core::{
+    DatatypeConversionError, DecodeRowsError, FetchError, MutationRejected, NonFiniteFloat,
+    UnexpectedNull, UniqueViolation, Unsupported,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MemoryError {
+    /// The statement read or wrote a table the schema doesn't have.
+    #[error("no such table: `{0}`")]
+    NoSuchTable(String),
+    /// A write clashed with an existing row on its primary key or unique
+    /// columns.
+    #[error(transparent)]
+    UniqueViolation(#[from] UniqueViolation),
+    /// A write left a foreign key referring to a row that doesn't exist.
+    #[error("`{table}.{column}` refers to a row that doesn't exist")]
+    ForeignKey { table: String, column: String },
+    /// A blob was opened on a row that doesn't exist.
+    #[error("no row of `{table}` has the key {key}")]
+    NoSuchRow { table: String, key: String },
+    /// A write was refused under
+    /// [`FloatPolicy::RejectNonFinite`](notitia_core::FloatPolicy::RejectNonFinite).
+    #[error(transparent)]
+    NonFiniteFloat(#[from] NonFiniteFloat),
+    /// A [`BeforeMutationHook`](notitia_core::BeforeMutationHook) refused
+    /// the write.
+    #[error(transparent)]
+    MutationRejected(#[from] MutationRejected),
+    /// The statement needs something missing from
+    /// [`MemoryAdapter::capabilities`](notitia_core::Adapter::capabilities).
+    #[error(transparent)]
+    Unsupported(#[from] Unsupported),
+    /// The query ran but its rows did not fit the fetch mode.
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    /// A selected field that isn't an `Option` read a NULL.
+    #[error(transparent)]
+    UnexpectedNull(#[from] UnexpectedNull),
+    /// A stored value could not be decoded into the selected field type.
+    #[error("failed to decode row: {0}")]
+    Decode(#[from] DatatypeConversionError),
+}
+
+impl MemoryError {
+    /// The [`FetchError`] behind this error, if that is what it is.
+    pub fn as_fetch_error(&self) -> Option<FetchError> {
+        match self {
+            Self::Fetch(err) => Some(*err),
+            _ => None,
+        }
+    }
+
+    /// The [`UniqueViolation`] behind this error, if that is what it is.
+    pub fn as_unique_violation(&self) -> Option<&UniqueViolation> {
+        match self {
+            Self::UniqueViolation(violation) => Some(violation),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeRowsError> for MemoryError {
+    fn from(err: DecodeRowsError) -> Self {
+        match err {
+            DecodeRowsError::Decode(err) => Self::Decode(err),
+            DecodeRowsError::UnexpectedNull(null) => Self::UnexpectedNull(null),
+            DecodeRowsError::Fetch(err) => Self::Fetch(err),
+        }
+    }
+}
@@ -0,0 +1,1150 @@
+#[cfg(test)]
+extern crate notitia_core as notitia;
+
+mod blob;
+pub use blob::MemoryBlobWriter;
+
+mod error;
+pub use error::*;
+
+mod store;
+pub use store::{MemorySnapshot, MemoryStore};
+
+mod tables;
+use tables::{Row, Tables, project};
+
+use futures_util::io::Cursor;
+use notitia_core::{
+    Adapter, AdapterCapabilities, ArchiveStmt, BlobLocation, BulkInsertStmtBuilt, CascadeWalk,
+    Database, Datatype, DatatypeKind, DatatypeRef, DeleteByKeysStmtBuilt, DeleteStmtBuilt,
+    FieldExpr, FieldFilter, FieldFilterMetadata, FieldKindGroup, InsertFromSelectStmtBuilt,
+    InsertStmtBuilt, MutationEvent, Notitia, OrderBy, OutboxEvent, PartialRecord, RawRow, Record,
+    SelectStmtBuilt, SelectStmtDef, SelectStmtFetchMode, SqlFunction, TableFieldPair,
+    UpdateStmtBuilt, decode_rows_with_order_keys,
+};
+use unions::IsUnion;
+
+/// An [`Adapter`] keeping the tables in plain Rust data structures, for
+/// unit tests of queries and subscriptions that shouldn't touch the
+/// filesystem or link SQLite.
+///
+/// ```ignore
+/// let db = ChatDatabase::connect::<MemoryAdapter>("").await?;
+/// db.mutate(ChatDatabase::USERS.insert(alice)).execute().await?;
+///
+/// let names = db
+///     .query(
+///         ChatDatabase::USERS
+///             .select(User::NAME)
+///             .order_by(User::NAME, OrderDirection::Asc)
+///             .fetch_vec(),
+///     )
+///     .execute()
+///     .await?;
+/// ```
+///
+/// Statements run on the rows the way SQLite runs them: filters compare
+/// NULLs as SQL does, primary keys, unique columns and foreign keys are
+/// enforced, `ON DELETE` actions are applied and every statement is atomic.
+/// The SQL of `#[db(trigger(...))]` triggers doesn't run, though
+/// `#[db(counted_by(...))]` counters are kept, and neither do `ON UPDATE`
+/// actions.
+pub struct MemoryAdapter {
+    store: MemoryStore,
+}
+
+impl MemoryAdapter {
+    pub fn store(&self) -> &MemoryStore {
+        &self.store
+    }
+}
+
+impl Adapter for MemoryAdapter {
+    type QueryBuilder = sea_query::SqliteQueryBuilder;
+    type Connection = MemoryStore;
+    type Error = MemoryError;
+    type BlobReader = Cursor<Vec<u8>>;
+    type BlobWriter = MemoryBlobWriter;
+    type Snapshot = MemorySnapshot;
+
+    fn new(connection: Self::Connection) -> Self {
+        Self { store: connection }
+    }
+
+    /// Nothing but the adapter's own writes reaches the store, and there's
+    /// no full-text index.
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            listen_notify: false,
+            full_text_search: false,
+            savepoints: false,
+            ..AdapterCapabilities::ALL
+        }
+    }
+
+    async fn initialize<Db: Database>(&self, database: &Db) {
+        self.store.lock().tables.create(
+            &Db::schema(),
+            Db::relationships(),
+            database.counters().collect(),
+        );
+    }
+
+    /// [`initialize`](Adapter::initialize) already added the missing
+    /// columns.
+    async fn migrate<Db: Database>(&self, _database: &Db) {}
+
+    /// Ignores `url`: every call opens an empty store.
+    async fn open<Db: Database>(_url: &str) -> Result<Notitia<Db, Self>, Self::Error> {
+        Ok(Notitia::new(Db::new(), Self::new(MemoryStore::new())).await)
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Filters and expressions call their functions directly, so there's
+    /// nothing to register.
+    async fn register_function(&self, _function: SqlFunction) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn execute_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        select(&self.store.lock().tables, stmt)
+    }
+
+    async fn begin_snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+        Ok(MemorySnapshot {
+            tables: self.store.lock().tables.clone(),
+        })
+    }
+
+    async fn execute_snapshot_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        snapshot: &Self::Snapshot,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        select(&snapshot.tables, stmt)
+    }
+
+    async fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> Result<Vec<Vec<Datatype>>, Self::Error> {
+        select_def(&self.store.lock().tables, def)
+    }
+
+    async fn execute_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: InsertStmtBuilt<Db, R>,
+    ) -> Result<bool, Self::Error> {
+        let values = stmt.record.into_datatypes();
+        let inserted = self.store.lock().write(stmt.outbox, |tables| {
+            tables.insert(stmt.table_name, values, stmt.ignore_conflicts)
+        })?;
+        Ok(inserted.is_some())
+    }
+
+    async fn execute_bulk_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let rows: Vec<_> = stmt
+            .records
+            .into_iter()
+            .map(|record| record.into_datatypes())
+            .collect();
+
+        self.store.lock().write(stmt.outbox, |tables| {
+            let mut inserted = Vec::with_capacity(rows.len());
+            for values in rows {
+                let columns: Vec<&'static str> = values.iter().map(|(name, _)| *name).collect();
+                if let Some(row) = tables.insert(stmt.table_name, values, stmt.ignore_conflicts)? {
+                    inserted.push(project(&row, &columns));
+                }
+            }
+            Ok(inserted)
+        })
+    }
+
+    async fn execute_insert_from_select_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let columns: Vec<&'static str> = Rec::_FIELDS.iter().map(|(name, _)| *name).collect();
+        self.store.lock().write(stmt.outbox, |tables| {
+            let copied = copied_rows(tables, &stmt.source_tables, &stmt.filters, &stmt.columns)?;
+            insert_all(tables, stmt.table_name, copied, &columns)
+        })
+    }
+
+    async fn execute_archive_stmt(
+        &self,
+        stmt: &ArchiveStmt,
+    ) -> Result<(Vec<Row>, Vec<MutationEvent>), Self::Error> {
+        self.store.lock().write(Vec::new(), |tables| {
+            let cascaded = cascaded_deletes(tables, stmt.cascade.clone())?;
+            let copied = copied_rows(tables, &[stmt.hot_table], &stmt.filters, &stmt.columns)?;
+            let archived = insert_all(tables, stmt.archive_table, copied, &stmt.columns)?;
+            tables.delete(stmt.hot_table, &stmt.filters)?;
+            Ok((archived, cascaded))
+        })
+    }
+
+    async fn execute_update_stmt<Db: Database, Rec: Record + Send, P: PartialRecord + Send>(
+        &self,
+        stmt: UpdateStmtBuilt<Db, Rec, P>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let changed = stmt.partial.into_set_fields();
+        self.store.lock().write(stmt.outbox, |tables| {
+            tables.update_returning(stmt.table_name, &changed, &stmt.filters, &stmt.returning)
+        })
+    }
+
+    async fn execute_delete_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let cascade = stmt.cascade();
+        self.store.lock().write(stmt.outbox, |tables| {
+            let cascaded = cascaded_deletes(tables, cascade)?;
+            tables.delete(stmt.table_name, &stmt.filters)?;
+            Ok(cascaded)
+        })
+    }
+
+    async fn execute_delete_by_keys_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let filters: Vec<FieldFilter> = stmt.chunks(stmt.keys.len()).collect();
+        let cascade = stmt.cascade();
+        self.store.lock().write(stmt.outbox, |tables| {
+            let cascaded = cascaded_deletes(tables, cascade)?;
+            // No keys make no filter, rather than one matching every row.
+            for filter in filters {
+                tables.delete(stmt.table_name, &[filter])?;
+            }
+            Ok(cascaded)
+        })
+    }
+
+    async fn is_table_empty(&self, table_name: &'static str) -> Result<bool, Self::Error> {
+        self.store.lock().tables.is_empty(table_name)
+    }
+
+    async fn fetch_rows(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        self.store.lock().tables.rows(table_name, columns)
+    }
+
+    async fn insert_rows(
+        &self,
+        table_name: &'static str,
+        rows: Vec<Vec<(&'static str, Datatype)>>,
+    ) -> Result<(), Self::Error> {
+        self.store.lock().write(Vec::new(), |tables| {
+            for values in rows {
+                tables.insert(table_name, values, false)?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn update_rows(
+        &self,
+        table_name: &'static str,
+        key_column: &'static str,
+        column: &'static str,
+        rows: Vec<(Datatype, Datatype)>,
+    ) -> Result<(), Self::Error> {
+        self.store.lock().write(Vec::new(), |tables| {
+            for (key, value) in rows {
+                tables.update(
+                    table_name,
+                    &[(column, FieldExpr::Literal(value))],
+                    &[FieldFilter::Eq(FieldFilterMetadata {
+                        left: TableFieldPair::new(table_name, key_column),
+                        right: key,
+                    })],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn open_blob_reader(
+        &self,
+        location: &BlobLocation,
+    ) -> Result<Self::BlobReader, Self::Error> {
+        match blob_value(&self.store.lock().tables, location)? {
+            Datatype::Blob(bytes) => Ok(Cursor::new(bytes)),
+            _ => Ok(Cursor::new(Vec::new())),
+        }
+    }
+
+    async fn open_blob_writer(
+        &self,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<Self::BlobWriter, Self::Error> {
+        let mut state = self.store.lock();
+        blob_value(&state.tables, location)?;
+        state.write(Vec::new(), |tables| {
+            tables.update(
+                location.table_name,
+                &[(
+                    location.column,
+                    FieldExpr::Literal(Datatype::Blob(vec![0; len as usize])),
+                )],
+                &[location.key_filter()],
+            )
+        })?;
+
+        Ok(MemoryBlobWriter {
+            store: self.store.clone(),
+            location: location.clone(),
+            len: len as usize,
+            bytes: Vec::new(),
+            closed: false,
+        })
+    }
+
+    async fn fixture_version(&self, name: &'static str) -> Result<Option<u32>, Self::Error> {
+        Ok(self.store.lock().fixture_versions.get(name).copied())
+    }
+
+    async fn set_fixture_version(
+        &self,
+        name: &'static str,
+        version: u32,
+    ) -> Result<(), Self::Error> {
+        self.store.lock().fixture_versions.insert(name, version);
+        Ok(())
+    }
+
+    async fn fetch_outbox(&self, limit: usize) -> Result<Vec<OutboxEvent>, Self::Error> {
+        let state = self.store.lock();
+        Ok(state
+            .outbox
+            .iter()
+            .filter(|event| !state.dispatched.contains(&event.id))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_outbox_dispatched(&self, ids: Vec<i64>) -> Result<(), Self::Error> {
+        self.store.lock().dispatched.extend(ids);
+        Ok(())
+    }
+}
+
+/// Run `stmt` on `tables`, reading the rows as an adapter reading them
+/// from SQL would.
+fn select<Db, FieldUnion, FieldPath, Fields, Mode>(
+    tables: &Tables,
+    stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+) -> Result<Mode::Output, MemoryError>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    let rows = tables.select(
+        &stmt.tables,
+        &stmt.filters,
+        &stmt.order_by,
+        stmt.per_group.as_ref(),
+    )?;
+    let total = rows.len();
+
+    let field_names = stmt.fields.field_names();
+    let field_tables = stmt.fields.field_tables();
+    if let Some(aggregating) = stmt.mode.aggregating() {
+        let values = rows
+            .iter()
+            .map(|row| row.column(field_tables[0], field_names[0]));
+        let raw_rows = [Values::new(vec![aggregating.aggregate().of(values)])];
+        return Ok(decode_rows_with_order_keys(stmt, raw_rows, None)?);
+    }
+    let unselected: Vec<&OrderBy> = stmt.unselected_order_by().collect();
+
+    let raw_rows = rows
+        .iter()
+        .take(stmt.mode.limit().unwrap_or(usize::MAX))
+        .map(|row| {
+            let mut values: Vec<Datatype> = field_names
+                .iter()
+                .zip(&field_tables)
+                .map(|(column, table)| row.column(*table, column))
+                .collect();
+            values.extend(unselected.iter().map(|order| row.resolve(order)));
+            if stmt.mode.needs_total_count() {
+                values.push(Datatype::BigInt(total as i64));
+            }
+            Values::new(values)
+        });
+
+    Ok(decode_rows_with_order_keys(stmt, raw_rows, None)?)
+}
+
+/// The rows of `def`: the values of its fields, then of its orders.
+fn select_def(tables: &Tables, def: &SelectStmtDef) -> Result<Vec<Vec<Datatype>>, MemoryError> {
+    let rows = tables.select(
+        &def.tables,
+        &def.filters,
+        &def.order_by,
+        def.per_group.as_ref(),
+    )?;
+
+    Ok(rows
+        .iter()
+        .take(def.limit.unwrap_or(usize::MAX))
+        .map(|row| {
+            def.fields
+                .iter()
+                .map(|field| row.column(None, field))
+                .chain(def.order_by.iter().map(|order| row.resolve(order)))
+                .collect()
+        })
+        .collect())
+}
+
+/// The deletes `cascade` finds, read before the delete in the same write.
+fn cascaded_deletes(
+    tables: &Tables,
+    mut cascade: CascadeWalk,
+) -> Result<Vec<MutationEvent>, MemoryError> {
+    while let Some(lookup) = cascade.next_lookup() {
+        cascade.found(select_def(tables, &lookup)?);
+    }
+    Ok(cascade.into_events())
+}
+
+/// The values of `columns` of the rows of `source_tables` that pass
+/// `filters`.
+fn copied_rows(
+    tables: &Tables,
+    source_tables: &[&'static str],
+    filters: &[FieldFilter],
+    columns: &[&'static str],
+) -> Result<Vec<Row>, MemoryError> {
+    Ok(tables
+        .select(source_tables, filters, &[], None)?
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| (*column, row.column(None, column)))
+                .collect()
+        })
+        .collect())
+}
+
+/// Insert `rows` into `table`, returning `returning` of each.
+fn insert_all(
+    tables: &mut Tables,
+    table: &'static str,
+    rows: Vec<Row>,
+    returning: &[&'static str],
+) -> Result<Vec<Row>, MemoryError> {
+    rows.into_iter()
+        .map(|values| {
+            let row = tables
+                .insert(table, values, false)?
+                .expect("conflicts aren't ignored");
+            Ok(project(&row, returning))
+        })
+        .collect()
+}
+
+/// The value of the column `location` points at.
+fn blob_value(tables: &Tables, location: &BlobLocation) -> Result<Datatype, MemoryError> {
+    let rows = tables.select(&[location.table_name], &[location.key_filter()], &[], None)?;
+    match rows.first() {
+        Some(row) => Ok(row.column(None, location.column)),
+        None => Err(MemoryError::NoSuchRow {
+            table: location.table_name.to_owned(),
+            key: location.key.to_string(),
+        }),
+    }
+}
+
+/// A row of a select as [`RawRow`] lays it out. Arrays are read as their
+/// JSON text, like SQLite stores them.
+struct Values(Vec<Datatype>);
+
+impl Values {
+    fn new(values: Vec<Datatype>) -> Self {
+        Self(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Datatype::Array(items) => Datatype::Text(Datatype::array_to_json(&items)),
+                    value => value,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl RawRow for Values {
+    fn column_count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn value(&self, index: usize, _kind: Option<&DatatypeKind>) -> DatatypeRef<'_> {
+        match &self.0[index] {
+            Datatype::Int(v) => DatatypeRef::Int(*v),
+            Datatype::BigInt(v) => DatatypeRef::BigInt(*v),
+            Datatype::Float(v) => DatatypeRef::Float(*v),
+            Datatype::Double(v) => DatatypeRef::Double(*v),
+            Datatype::Text(v) => DatatypeRef::Text(v),
+            Datatype::Blob(v) => DatatypeRef::Blob(v),
+            Datatype::Bool(v) => DatatypeRef::Bool(*v),
+            Datatype::Null => DatatypeRef::Null,
+            Datatype::Array(_) => unreachable!("arrays are read as JSON text"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::store::MemoryState;
+    use futures_util::{AsyncReadExt, AsyncWriteExt};
+    use notitia_core::{
+        ChangeReason, Counted, MutationEvent, MutationEventKind, MutationHook, OrderDirection,
+        OrderKey, OutboxMessage, SelectStmtBuildable, SelectStmtFilterable, SelectStmtJoinable,
+        SelectStmtOrderable, SelectStmtSelectable, Subscription, SubscriptionMetadata, Table,
+    };
+    use notitia_macros::{database, record};
+    use notitia_sqlite::SqliteAdapter;
+
+    #[derive(Debug)]
+    #[database]
+    struct ChatDb {
+        users: Table<User>,
+        chats: Table<Chat>,
+        #[db(foreign_key(chat_id, chats.id, on_delete = Cascade))]
+        messages: Table<Message>,
+        #[db(foreign_key(author_id, users.id, on_delete = Cascade))]
+        posts: Table<Post>,
+        attachments: Table<Attachment>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct User {
+        #[db(primary_key)]
+        id: String,
+        name: String,
+        age: i64,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Chat {
+        #[db(primary_key)]
+        id: String,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Message {
+        #[db(primary_key)]
+        id: String,
+        chat_id: String,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Post {
+        #[db(primary_key)]
+        id: String,
+        author_id: String,
+        title: String,
+        parent_id: Option<String>,
+        score: i64,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Attachment {
+        #[db(primary_key)]
+        id: String,
+        data: Vec<u8>,
+    }
+
+    fn state() -> MemoryState {
+        let mut state = MemoryState::default();
+        state.tables.create(
+            &ChatDb::schema(),
+            ChatDb::relationships(),
+            ChatDb::new().counters().collect(),
+        );
+        state
+    }
+
+    fn text(value: &str) -> Datatype {
+        Datatype::Text(value.to_owned())
+    }
+
+    fn user(id: &str, name: &str, age: i64) -> Row {
+        vec![
+            ("id", text(id)),
+            ("name", text(name)),
+            ("age", Datatype::BigInt(age)),
+        ]
+    }
+
+    #[test]
+    fn selects_filtered_rows_in_order() {
+        let mut state = state();
+        for row in [
+            user("1", "Alice", 34),
+            user("2", "Bob", 17),
+            user("3", "Carol", 52),
+        ] {
+            state.tables.insert("users", row, false).unwrap();
+        }
+
+        let stmt = ChatDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.gt(18i64))
+            .order_by(User::AGE, OrderDirection::Desc)
+            .fetch_all::<Vec<_>>();
+
+        assert_eq!(select(&state.tables, &stmt).unwrap(), ["Carol", "Alice"]);
+    }
+
+    #[test]
+    fn failed_writes_change_nothing() {
+        let mut state = state();
+        let err = state
+            .write(Vec::new(), |tables| {
+                tables.insert("users", user("1", "Alice", 34), false)?;
+                tables.insert("users", user("1", "Bob", 17), false)
+            })
+            .unwrap_err();
+
+        assert_eq!(
+            err.as_unique_violation()
+                .map(|violation| &violation.columns),
+            Some(&vec!["id".to_owned()])
+        );
+        assert!(state.tables.is_empty("users").unwrap());
+    }
+
+    #[test]
+    fn deletes_cascade_to_referring_rows() {
+        let mut state = state();
+        state
+            .write(Vec::new(), |tables| {
+                tables.insert("chats", vec![("id", text("c"))], false)?;
+                tables.insert(
+                    "messages",
+                    vec![("id", text("m")), ("chat_id", text("c"))],
+                    false,
+                )
+            })
+            .unwrap();
+        assert!(matches!(
+            state.tables.insert(
+                "messages",
+                vec![("id", text("n")), ("chat_id", text("gone"))],
+                false
+            ),
+            Err(MemoryError::ForeignKey { .. })
+        ));
+
+        state.tables.delete("chats", &[]).unwrap();
+        assert!(state.tables.is_empty("messages").unwrap());
+    }
+
+    /// Records the sequence of every event.
+    #[derive(Default)]
+    struct Sequences(Mutex<Vec<u64>>);
+
+    impl MutationHook for Sequences {
+        fn on_event(&self, event: &MutationEvent) {
+            self.0.lock().unwrap().push(event.sequence);
+        }
+    }
+
+    #[tokio::test]
+    async fn the_mutation_hook_sees_concurrent_broadcasts_in_order() {
+        let db = Notitia::new(ChatDb::new(), MemoryAdapter::new(MemoryStore::new())).await;
+        let hook = Arc::new(Sequences::default());
+        db.set_mutation_hook(hook.clone());
+
+        std::thread::scope(|scope| {
+            for thread in 0..2 {
+                let db = &db;
+                scope.spawn(move || {
+                    for id in 0..500 {
+                        db.notify_subscribers(&MutationEvent {
+                            table_name: "chats",
+                            sequence: 0,
+                            kind: MutationEventKind::Insert {
+                                values: vec![("id", text(&format!("{thread}-{id}")))],
+                            },
+                        });
+                    }
+                });
+            }
+        });
+
+        let sequences = hook.0.lock().unwrap().clone();
+        assert_eq!(sequences, (1..=1000).collect::<Vec<u64>>());
+    }
+
+    // The tests below run the same statements through SQLite and the memory
+    // adapter, which must agree on everything but speed.
+
+    /// A database of two users with two posts each, the second of each a
+    /// reply to the first.
+    async fn seeded<Adptr: Adapter + 'static>(url: &str) -> Notitia<ChatDb, Adptr> {
+        let db = ChatDb::connect::<Adptr>(url).await.unwrap();
+        for (id, name, age) in [("u1", "Alice", 34i64), ("u2", "Bob", 17)] {
+            db.mutate(ChatDb::USERS.insert(User::build().id(id).name(name).age(age)))
+                .execute()
+                .await
+                .unwrap();
+        }
+        for (id, author_id, title, parent_id, score) in [
+            ("a", "u1", "Intro", None, 5i64),
+            ("b", "u1", "Follow-up", Some("a"), 3),
+            ("c", "u2", "Question", None, 1),
+            ("d", "u2", "Answer", Some("c"), 7),
+        ] {
+            let post = Post::build()
+                .id(id)
+                .author_id(author_id)
+                .title(title)
+                .score(score);
+            let post = match parent_id {
+                Some(parent_id) => post.parent_id(parent_id),
+                None => post,
+            };
+            db.mutate(ChatDb::POSTS.insert(post))
+                .execute()
+                .await
+                .unwrap();
+        }
+        db
+    }
+
+    /// The URL of a new SQLite file, for what an in-memory database can't
+    /// do with its single connection.
+    fn sqlite_file(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("notitia_memory_{name}_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        format!("sqlite:{}", path.display())
+    }
+
+    /// Wait for the next change of `sub`, while the requeries of others run
+    /// on the other worker threads.
+    fn changed<T: Clone>(sub: &Subscription<T>) -> (T, ChangeReason) {
+        let SubscriptionMetadata::Changed(_, reason) =
+            tokio::task::block_in_place(|| sub.recv()).unwrap()
+        else {
+            panic!("expected a change");
+        };
+        (sub.data().clone(), reason)
+    }
+
+    type Joined = (Vec<(String, String)>, Vec<(String, String)>);
+
+    async fn joined<Adptr: Adapter + 'static>(db: Notitia<ChatDb, Adptr>) -> Joined {
+        let mut authors = db
+            .query(
+                ChatDb::POSTS
+                    .join(ChatDb::USERS)
+                    .select((Post::TITLE, User::NAME))
+                    .join_on(Post::AUTHOR_ID, User::ID)
+                    .fetch_vec(),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let parents = ChatDb::POSTS.alias("parents");
+        let mut replies = db
+            .query(
+                ChatDb::POSTS
+                    .join(parents)
+                    .select((Post::TITLE, parents.field(Post::TITLE)))
+                    .join_on(Post::PARENT_ID, parents.field(Post::ID))
+                    .fetch_vec(),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Neither orders its rows.
+        authors.sort();
+        replies.sort();
+        (authors, replies)
+    }
+
+    /// Records the table and filters of every delete.
+    #[derive(Default)]
+    struct Deletes(Mutex<Vec<(&'static str, Vec<FieldFilter>)>>);
+
+    impl MutationHook for Deletes {
+        fn on_event(&self, event: &MutationEvent) {
+            if let MutationEventKind::Delete { filters } = &event.kind {
+                let delete = (event.table_name, filters.to_vec());
+                self.0.lock().unwrap().push(delete);
+            }
+        }
+    }
+
+    async fn cascaded<Adptr: Adapter + 'static>(
+        db: Notitia<ChatDb, Adptr>,
+    ) -> Vec<(&'static str, Vec<FieldFilter>)> {
+        let hook = Arc::new(Deletes::default());
+        db.set_mutation_hook(hook.clone());
+        db.mutate(ChatDb::USERS.delete().filter(User::ID.eq("u1")))
+            .execute()
+            .await
+            .unwrap();
+        hook.0.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn deletes_announce_the_rows_they_cascade_to_like_sqlite() {
+        let deletes = cascaded(seeded::<SqliteAdapter>("sqlite::memory:").await).await;
+        assert_eq!(deletes, cascaded(seeded::<MemoryAdapter>("").await).await);
+        let tables: Vec<&str> = deletes.iter().map(|(table, _)| *table).collect();
+        assert_eq!(tables, ["users", "posts"]);
+    }
+
+    #[tokio::test]
+    async fn joins_and_aliases_match_sqlite() {
+        assert_eq!(
+            joined(seeded::<SqliteAdapter>("sqlite::memory:").await).await,
+            joined(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+
+    type Filtered = (Vec<String>, Vec<String>, Vec<String>, Vec<String>);
+
+    async fn filtered<Adptr: Adapter + 'static>(db: Notitia<ChatDb, Adptr>) -> Filtered {
+        let ids = || ChatDb::POSTS.select(Post::ID);
+        let by_id = |mut ids: Vec<String>| {
+            ids.sort();
+            ids
+        };
+
+        let low_or_intro = db
+            .query(
+                ids()
+                    .filter(Post::SCORE.lt(2i64).or(Post::TITLE.eq("Intro")))
+                    .fetch_vec(),
+            )
+            .execute()
+            .await
+            .unwrap();
+        // Like NOT in SQL, skips the posts without a parent.
+        let not_replies_to_a = db
+            .query(ids().filter(!Post::PARENT_ID.eq("a")).fetch_vec())
+            .execute()
+            .await
+            .unwrap();
+        let roots = db
+            .query(ids().filter(Post::PARENT_ID.is_null()).fetch_vec())
+            .execute()
+            .await
+            .unwrap();
+        let replies = db
+            .query(ids().filter(Post::PARENT_ID.is_not_null()).fetch_vec())
+            .execute()
+            .await
+            .unwrap();
+
+        (
+            by_id(low_or_intro),
+            by_id(not_replies_to_a),
+            by_id(roots),
+            by_id(replies),
+        )
+    }
+
+    #[tokio::test]
+    async fn or_not_and_null_filters_match_sqlite() {
+        assert_eq!(
+            filtered(seeded::<SqliteAdapter>("sqlite::memory:").await).await,
+            filtered(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+
+    async fn paged<Adptr: Adapter + 'static>(
+        db: Notitia<ChatDb, Adptr>,
+    ) -> (Vec<String>, Counted<Vec<String>>) {
+        let first = db
+            .query(
+                ChatDb::POSTS
+                    .select(Post::TITLE)
+                    .order_by(Post::SCORE, OrderDirection::Desc)
+                    .fetch_many::<BTreeMap<OrderKey, _>>(2),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let counted = db
+            .query(
+                ChatDb::POSTS
+                    .select(Post::TITLE)
+                    .filter(Post::SCORE.gt(1i64))
+                    .order_by(Post::SCORE, OrderDirection::Desc)
+                    .fetch_many::<BTreeMap<OrderKey, _>>(2)
+                    .with_total_count(),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let counted = Counted {
+            rows: counted.rows.into_values().collect(),
+            total: counted.total,
+        };
+        (first.into_values().collect(), counted)
+    }
+
+    #[tokio::test]
+    async fn fetch_many_and_total_counts_match_sqlite() {
+        assert_eq!(
+            paged(seeded::<SqliteAdapter>("sqlite::memory:").await).await,
+            paged(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+
+    type Grouped = (
+        Vec<(String, String)>,
+        usize,
+        i64,
+        Option<f64>,
+        Option<i64>,
+        Option<i64>,
+        (i64, Option<f64>, Option<i64>),
+    );
+
+    async fn grouped<Adptr: Adapter + 'static>(db: Notitia<ChatDb, Adptr>) -> Grouped {
+        let best = db
+            .query(
+                ChatDb::POSTS
+                    .select((Post::AUTHOR_ID, Post::TITLE))
+                    .order_by(Post::SCORE, OrderDirection::Desc)
+                    .fetch_vec()
+                    .limit_per_group(Post::AUTHOR_ID, 1),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let scores = || ChatDb::POSTS.select(Post::SCORE);
+        let none = || scores().filter(Post::SCORE.gt(100i64));
+
+        (
+            best,
+            db.query(ChatDb::POSTS.select(Post::ID).fetch_count())
+                .execute()
+                .await
+                .unwrap(),
+            db.query(scores().fetch_sum()).execute().await.unwrap(),
+            db.query(scores().fetch_avg()).execute().await.unwrap(),
+            db.query(scores().fetch_min()).execute().await.unwrap(),
+            db.query(scores().fetch_max()).execute().await.unwrap(),
+            (
+                db.query(none().fetch_sum()).execute().await.unwrap(),
+                db.query(none().fetch_avg()).execute().await.unwrap(),
+                db.query(none().fetch_max()).execute().await.unwrap(),
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn per_group_limits_and_aggregates_match_sqlite() {
+        assert_eq!(
+            grouped(seeded::<SqliteAdapter>("sqlite::memory:").await).await,
+            grouped(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+
+    type ReadBack = (
+        Vec<String>,
+        Vec<String>,
+        Vec<u8>,
+        Vec<u8>,
+        Vec<(String, String)>,
+        usize,
+    );
+
+    async fn read_back<Adptr: Adapter + 'static>(db: Notitia<ChatDb, Adptr>) -> ReadBack {
+        let titles = || {
+            ChatDb::POSTS
+                .select(Post::TITLE)
+                .order_by(Post::SCORE, OrderDirection::Desc)
+                .fetch_vec()
+        };
+
+        let snapshot = db.snapshot().await.unwrap();
+        db.mutate(ChatDb::POSTS.delete().filter(Post::AUTHOR_ID.eq("u2")))
+            .enqueue(OutboxMessage::new("posts.deleted", "u2"))
+            .execute()
+            .await
+            .unwrap();
+        // A failed mutation enqueues nothing.
+        db.mutate(ChatDb::USERS.insert(User::build().id("u1").name("Eve").age(20i64)))
+            .enqueue(OutboxMessage::new("users.created", "u1"))
+            .execute()
+            .await
+            .unwrap_err();
+        let before = snapshot.query(titles()).await.unwrap();
+        drop(snapshot);
+        let after = db.query(titles()).execute().await.unwrap();
+
+        db.mutate(ChatDb::ATTACHMENTS.insert(Attachment::build().id("f").data(Vec::<u8>::new())))
+            .execute()
+            .await
+            .unwrap();
+        let mut writer = db
+            .blob_writer(ChatDb::ATTACHMENTS, Attachment::DATA, "f", 5)
+            .await
+            .unwrap();
+        writer.write_all(b"abc").await.unwrap();
+        writer.close().await.unwrap();
+        let mut streamed = Vec::new();
+        db.blob_reader(ChatDb::ATTACHMENTS, Attachment::DATA, "f")
+            .await
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .await
+            .unwrap();
+        let selected = db
+            .query(
+                ChatDb::ATTACHMENTS
+                    .select(Attachment::DATA)
+                    .filter(Attachment::ID.eq("f"))
+                    .fetch_one(),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let pending = db.pending_outbox(10).await.unwrap();
+        let ids: Vec<i64> = pending.iter().map(|event| event.id).collect();
+        db.mark_dispatched(&ids).await.unwrap();
+        let messages = pending
+            .into_iter()
+            .map(|event| (event.topic, event.payload))
+            .collect();
+        let left = db.pending_outbox(10).await.unwrap().len();
+
+        (before, after, streamed, selected, messages, left)
+    }
+
+    #[tokio::test]
+    async fn snapshots_blobs_and_the_outbox_match_sqlite() {
+        let url = sqlite_file("read_back");
+        assert_eq!(
+            read_back(seeded::<SqliteAdapter>(&url).await).await,
+            read_back(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+
+    type Live = (
+        Vec<(Vec<(String, i64)>, ChangeReason)>,
+        Vec<(usize, ChangeReason)>,
+    );
+
+    async fn live<Adptr: Adapter + 'static>(db: Notitia<ChatDb, Adptr>) -> Live {
+        let posts = db
+            .query(
+                ChatDb::POSTS
+                    .select((Post::TITLE, Post::SCORE))
+                    .order_by(Post::SCORE, OrderDirection::Desc)
+                    .fetch_vec(),
+            )
+            .subscribe()
+            .await
+            .unwrap();
+        let (count, requery) = db
+            .query(ChatDb::POSTS.select(Post::ID).fetch_count())
+            .subscribe_requerying()
+            .await
+            .unwrap();
+        tokio::spawn(requery);
+        posts.recv().unwrap();
+        count.recv().unwrap();
+
+        let mut post_changes = Vec::new();
+        let mut count_changes = Vec::new();
+
+        let draft = Post::build()
+            .id("e")
+            .author_id("u1")
+            .title("Draft")
+            .score(4i64);
+        db.mutate(ChatDb::POSTS.insert(draft))
+            .execute()
+            .await
+            .unwrap();
+        post_changes.push(changed(&posts));
+        count_changes.push(changed(&count));
+
+        // Leaves the count as it is.
+        db.mutate(
+            ChatDb::POSTS
+                .update(Post::build().score(Post::SCORE.minus(4i64)))
+                .filter(Post::ID.eq("a")),
+        )
+        .execute()
+        .await
+        .unwrap();
+        post_changes.push(changed(&posts));
+
+        db.mutate(ChatDb::POSTS.delete().filter(Post::ID.eq("d")))
+            .execute()
+            .await
+            .unwrap();
+        post_changes.push(changed(&posts));
+        count_changes.push(changed(&count));
+
+        (post_changes, count_changes)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscriptions_match_sqlite() {
+        assert_eq!(
+            live(seeded::<SqliteAdapter>("sqlite::memory:").await).await,
+            live(seeded::<MemoryAdapter>("").await).await
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use notitia_core::{OutboxEvent, OutboxMessage};
+
+use crate::{MemoryError, tables::Tables};
+
+#[derive(Default)]
+pub(crate) struct MemoryState {
+    pub(crate) tables: Tables,
+    pub(crate) fixture_versions: HashMap<&'static str, u32>,
+    pub(crate) outbox: Vec<OutboxEvent>,
+    pub(crate) dispatched: HashSet<i64>,
+}
+
+impl MemoryState {
+    /// Run `write` on the tables as one transaction: if it fails, they are
+    /// left as they were, and otherwise `outbox` is committed with it.
+    pub(crate) fn write<T>(
+        &mut self,
+        outbox: Vec<OutboxMessage>,
+        write: impl FnOnce(&mut Tables) -> Result<T, MemoryError>,
+    ) -> Result<T, MemoryError> {
+        let before = self.tables.clone();
+        match write(&mut self.tables) {
+            Ok(output) => {
+                self.tables.count();
+                for message in outbox {
+                    let id = self.outbox.len() as i64 + 1;
+                    self.outbox.push(OutboxEvent {
+                        id,
+                        topic: message.topic,
+                        payload: message.payload,
+                    });
+                }
+                Ok(output)
+            }
+            Err(err) => {
+                self.tables = before;
+                Err(err)
+            }
+        }
+    }
+}
+
+/// The tables of a [`MemoryAdapter`](crate::MemoryAdapter). Clones share
+/// them, so adapters made from clones of one store read and write the same
+/// rows, like connections to one database.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// A copy of the tables of a [`MemoryStore`], taken by
+/// [`begin_snapshot`](notitia_core::Adapter::begin_snapshot).
+pub struct MemorySnapshot {
+    pub(crate) tables: Tables,
+}
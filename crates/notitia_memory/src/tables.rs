@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+
+use notitia_core::{
+    CounterDef, Datatype, FieldExpr, FieldFilter, FieldFilterInMetadata, OnAction, OrderBy,
+    OrderDirection, OrderKey, PerGroup, Relationship, SchemaDef, TableDef, TableFieldPair,
+    UniqueViolation, split_table_entry,
+};
+
+use crate::MemoryError;
+
+/// Every column of a row, in the order the table declares them.
+pub(crate) type Row = Vec<(&'static str, Datatype)>;
+
+#[derive(Clone, Debug)]
+struct MemoryTable {
+    columns: Vec<&'static str>,
+    /// The primary key, then the unique columns and constraints.
+    unique_keys: Vec<Vec<&'static str>>,
+    rows: Vec<Row>,
+}
+
+impl MemoryTable {
+    fn new(def: &TableDef) -> Self {
+        let primary_key: Vec<&'static str> = def.primary_key().map(|column| column.name).collect();
+        let unique = def
+            .indexes
+            .iter()
+            .filter(|index| index.unique)
+            .map(|index| index.columns.iter().map(|column| column.name).collect());
+
+        Self {
+            columns: def.columns.iter().map(|column| column.name).collect(),
+            unique_keys: (!primary_key.is_empty())
+                .then_some(primary_key)
+                .into_iter()
+                .chain(unique)
+                .collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// `values` as a whole row, with the columns it doesn't set NULL.
+    fn row(&self, mut values: Vec<(&'static str, Datatype)>) -> Row {
+        self.columns
+            .iter()
+            .map(|column| {
+                let value = values
+                    .iter()
+                    .position(|(name, _)| name == column)
+                    .map(|idx| stored(values.swap_remove(idx).1))
+                    .unwrap_or(Datatype::Null);
+                (*column, value)
+            })
+            .collect()
+    }
+
+    /// The key `row` shares with a row other than the one at `skip`. Like
+    /// in SQL, keys with a NULL never clash.
+    fn conflict(&self, row: &Row, skip: Option<usize>) -> Option<&[&'static str]> {
+        self.unique_keys
+            .iter()
+            .find(|key| {
+                let values: Vec<Datatype> = key.iter().map(|column| value(row, column)).collect();
+                !values.contains(&Datatype::Null)
+                    && self.rows.iter().enumerate().any(|(idx, other)| {
+                        Some(idx) != skip
+                            && key
+                                .iter()
+                                .zip(&values)
+                                .all(|(column, key)| value(other, column) == *key)
+                    })
+            })
+            .map(|key| key.as_slice())
+    }
+
+    fn matching(&self, filters: &[FieldFilter]) -> Vec<usize> {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| passes(row, filters))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
+
+/// A row of each table a select reads, under the name the select refers to
+/// the table by.
+pub(crate) struct Joined<'t> {
+    parts: Vec<(&'static str, &'static str, &'t Row)>,
+}
+
+impl Joined<'_> {
+    /// The value of `column` in `table`, or if it wasn't qualified, in the
+    /// first table that has it.
+    pub(crate) fn column(&self, table: Option<&str>, column: &str) -> Datatype {
+        let row = match table {
+            Some(table) => self.part(table),
+            None => self
+                .parts
+                .iter()
+                .map(|(_, _, row)| *row)
+                .find(|row| row.iter().any(|(name, _)| *name == column)),
+        };
+        row.map(|row| value(row, column)).unwrap_or(Datatype::Null)
+    }
+
+    /// What `order` sorts the row by.
+    pub(crate) fn resolve(&self, order: &OrderBy) -> Datatype {
+        match self
+            .part(order.table)
+            .or(self.parts.first().map(|(_, _, row)| *row))
+        {
+            Some(row) => order.expr.resolve(row),
+            None => Datatype::Null,
+        }
+    }
+
+    fn part(&self, table: &str) -> Option<&Row> {
+        self.parts
+            .iter()
+            .find(|(name, _, _)| *name == table)
+            .or_else(|| self.parts.iter().find(|(_, name, _)| *name == table))
+            .map(|(_, _, row)| *row)
+    }
+}
+
+/// The tables of a [`MemoryStore`](crate::MemoryStore) and what ties them
+/// together.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tables {
+    tables: HashMap<&'static str, MemoryTable>,
+    relationships: Vec<Relationship>,
+    counters: Vec<CounterDef>,
+}
+
+impl Tables {
+    /// Create the tables of `schema` that don't exist, and add the columns
+    /// missing from those that do.
+    pub(crate) fn create(
+        &mut self,
+        schema: &SchemaDef,
+        relationships: Vec<Relationship>,
+        counters: Vec<CounterDef>,
+    ) {
+        for def in &schema.tables {
+            let table = MemoryTable::new(def);
+            match self.tables.get_mut(def.name) {
+                Some(existing) => {
+                    existing.rows = std::mem::take(&mut existing.rows)
+                        .into_iter()
+                        .map(|row| table.row(row))
+                        .collect();
+                    existing.columns = table.columns;
+                    existing.unique_keys = table.unique_keys;
+                }
+                None => {
+                    self.tables.insert(def.name, table);
+                }
+            }
+        }
+        self.relationships = relationships;
+        self.counters = counters;
+        self.count();
+    }
+
+    fn table(&self, name: &str) -> Result<&MemoryTable, MemoryError> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| MemoryError::NoSuchTable(name.to_owned()))
+    }
+
+    fn table_mut(&mut self, name: &str) -> Result<&mut MemoryTable, MemoryError> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| MemoryError::NoSuchTable(name.to_owned()))
+    }
+
+    pub(crate) fn is_empty(&self, table: &str) -> Result<bool, MemoryError> {
+        Ok(self.table(table)?.rows.is_empty())
+    }
+
+    /// The rows of `tables` that pass `filters`, sorted by `order_by`,
+    /// keeping only the first `per_group.limit` of each group.
+    pub(crate) fn select(
+        &self,
+        tables: &[&'static str],
+        filters: &[FieldFilter],
+        order_by: &[OrderBy],
+        per_group: Option<&PerGroup>,
+    ) -> Result<Vec<Joined<'_>>, MemoryError> {
+        let mut joined = vec![Joined { parts: Vec::new() }];
+        for entry in tables {
+            let (table_name, alias) = split_table_entry(*entry);
+            let table = self.table(table_name)?;
+            joined = joined
+                .into_iter()
+                .flat_map(|row| {
+                    table.rows.iter().map(move |part| {
+                        let mut parts = row.parts.clone();
+                        parts.push((alias.unwrap_or(table_name), table_name, part));
+                        Joined { parts }
+                    })
+                })
+                .collect();
+        }
+
+        joined.retain(|row| {
+            filters.iter().all(|filter| {
+                filter.matches(|pair| row.column(Some(pair.table_name), pair.field_name))
+            })
+        });
+
+        let reversed: Vec<bool> = order_by
+            .iter()
+            .map(|order| matches!(order.direction, OrderDirection::Desc))
+            .collect();
+        // Stable, so rows that sort the same stay in insertion order.
+        joined.sort_by_cached_key(|row| {
+            OrderKey::new(
+                order_by.iter().map(|order| row.resolve(order)).collect(),
+                reversed.iter().copied().collect(),
+            )
+        });
+
+        if let Some(per_group) = per_group {
+            let mut counts: HashMap<Datatype, usize> = HashMap::new();
+            let group = &per_group.field;
+            joined.retain(|row| {
+                let count = counts
+                    .entry(row.column(Some(group.table_name), group.field_name))
+                    .or_default();
+                *count += 1;
+                *count <= per_group.limit
+            });
+        }
+
+        Ok(joined)
+    }
+
+    /// Every row of `table`, with `columns` in the given order.
+    pub(crate) fn rows(
+        &self,
+        table: &str,
+        columns: &[&'static str],
+    ) -> Result<Vec<Row>, MemoryError> {
+        Ok(self
+            .table(table)?
+            .rows
+            .iter()
+            .map(|row| project(row, columns))
+            .collect())
+    }
+
+    /// Insert `values` into `table`, returning the whole row, or nothing if
+    /// it clashed with a row and `ignore_conflicts` is set.
+    pub(crate) fn insert(
+        &mut self,
+        table_name: &'static str,
+        values: Vec<(&'static str, Datatype)>,
+        ignore_conflicts: bool,
+    ) -> Result<Option<Row>, MemoryError> {
+        let table = self.table(table_name)?;
+        let row = table.row(values);
+        if let Some(key) = table.conflict(&row, None) {
+            if ignore_conflicts {
+                return Ok(None);
+            }
+            return Err(unique_violation(table_name, key));
+        }
+        self.check_references(table_name, &row)?;
+
+        self.table_mut(table_name)?.rows.push(row.clone());
+        Ok(Some(row))
+    }
+
+    /// Set `changed` on the rows of `table` that pass `filters`, evaluating
+    /// every expression against the row as it was.
+    pub(crate) fn update(
+        &mut self,
+        table_name: &'static str,
+        changed: &[(&'static str, FieldExpr)],
+        filters: &[FieldFilter],
+    ) -> Result<(), MemoryError> {
+        self.update_returning(table_name, changed, filters, &[])
+            .map(drop)
+    }
+
+    /// Like [`update`](Self::update), returning the `returning` columns of
+    /// the updated rows, or no rows if there are none.
+    pub(crate) fn update_returning(
+        &mut self,
+        table_name: &'static str,
+        changed: &[(&'static str, FieldExpr)],
+        filters: &[FieldFilter],
+        returning: &[&'static str],
+    ) -> Result<Vec<Row>, MemoryError> {
+        let table = self.table_mut(table_name)?;
+        let matching = table.matching(filters);
+        for &idx in &matching {
+            let row = &table.rows[idx];
+            let values: Vec<(&'static str, Datatype)> = changed
+                .iter()
+                .map(|(column, expr)| (*column, stored(expr.resolve(row))))
+                .collect();
+            for (column, new) in values {
+                if let Some((_, value)) =
+                    table.rows[idx].iter_mut().find(|(name, _)| *name == column)
+                {
+                    *value = new;
+                }
+            }
+        }
+
+        let table = self.table(table_name)?;
+        for &idx in &matching {
+            let row = &table.rows[idx];
+            if let Some(key) = table.conflict(row, Some(idx)) {
+                return Err(unique_violation(table_name, key));
+            }
+            self.check_references(table_name, row)?;
+        }
+        if returning.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(matching
+            .iter()
+            .map(|&idx| project(&table.rows[idx], returning))
+            .collect())
+    }
+
+    /// Delete the rows of `table` that pass `filters`, and apply the
+    /// `ON DELETE` actions of the foreign keys referring to them. Returns
+    /// the deleted rows of `table`.
+    pub(crate) fn delete(
+        &mut self,
+        table_name: &'static str,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<Row>, MemoryError> {
+        let table = self.table_mut(table_name)?;
+        let (deleted, kept): (Vec<Row>, Vec<Row>) = std::mem::take(&mut table.rows)
+            .into_iter()
+            .partition(|row| passes(row, filters));
+        table.rows = kept;
+
+        let referring: Vec<Relationship> = self
+            .relationships
+            .iter()
+            .filter(|relationship| relationship.foreign_table == table_name)
+            .copied()
+            .collect();
+        for relationship in referring {
+            let keys: Vec<Datatype> = deleted
+                .iter()
+                .map(|row| value(row, relationship.foreign_field))
+                .filter(|key| *key != Datatype::Null)
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            let filters = [FieldFilter::In(FieldFilterInMetadata {
+                left: TableFieldPair::new(relationship.table, relationship.field),
+                right: keys,
+            })];
+
+            match relationship.on_delete {
+                OnAction::Cascade => {
+                    self.delete(relationship.table, &filters)?;
+                }
+                OnAction::SetNull | OnAction::SetDefault => self.update(
+                    relationship.table,
+                    &[(relationship.field, FieldExpr::Literal(Datatype::Null))],
+                    &filters,
+                )?,
+                OnAction::NoAction | OnAction::Restrict => {
+                    if !self
+                        .table(relationship.table)?
+                        .matching(&filters)
+                        .is_empty()
+                    {
+                        return Err(MemoryError::ForeignKey {
+                            table: relationship.table.to_owned(),
+                            column: relationship.field.to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Fail unless every foreign key of `row` refers to an existing row.
+    fn check_references(&self, table_name: &str, row: &Row) -> Result<(), MemoryError> {
+        for relationship in &self.relationships {
+            if relationship.table != table_name {
+                continue;
+            }
+            let key = value(row, relationship.field);
+            if key == Datatype::Null {
+                continue;
+            }
+            let foreign = self.table(relationship.foreign_table)?;
+            if !foreign
+                .rows
+                .iter()
+                .any(|other| value(other, relationship.foreign_field) == key)
+            {
+                return Err(MemoryError::ForeignKey {
+                    table: relationship.table.to_owned(),
+                    column: relationship.field.to_owned(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Recount the side tables of the `#[db(counted_by(...))]` counters,
+    /// which SQLite keeps up to date with triggers.
+    pub(crate) fn count(&mut self) {
+        for counter in &self.counters {
+            let Some(table) = self.tables.get(counter.table_name) else {
+                continue;
+            };
+            let mut counts: Vec<(Datatype, i64)> = Vec::new();
+            for row in &table.rows {
+                let key = value(row, counter.column);
+                if key == Datatype::Null {
+                    continue;
+                }
+                match counts.iter_mut().find(|(value, _)| *value == key) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((key, 1)),
+                }
+            }
+
+            let counts_table = MemoryTable {
+                columns: vec!["value", "count"],
+                unique_keys: vec![vec!["value"]],
+                rows: counts
+                    .into_iter()
+                    .map(|(value, count)| {
+                        vec![("value", value), ("count", Datatype::BigInt(count))]
+                    })
+                    .collect(),
+            };
+            self.tables.insert(counter.counts_table, counts_table);
+        }
+    }
+}
+
+fn value(row: &Row, column: &str) -> Datatype {
+    row.iter()
+        .find(|(name, _)| *name == column)
+        .map(|(_, value)| value.clone())
+        .unwrap_or(Datatype::Null)
+}
+
+fn passes(row: &Row, filters: &[FieldFilter]) -> bool {
+    filters
+        .iter()
+        .all(|filter| filter.matches(|pair| value(row, pair.field_name)))
+}
+
+pub(crate) fn project(row: &Row, columns: &[&'static str]) -> Row {
+    columns
+        .iter()
+        .map(|column| (*column, value(row, column)))
+        .collect()
+}
+
+/// NaN is stored as NULL, as SQLite stores it.
+fn stored(value: Datatype) -> Datatype {
+    match value {
+        Datatype::Float(v) if v.is_nan() => Datatype::Null,
+        Datatype::Double(v) if v.is_nan() => Datatype::Null,
+        value => value,
+    }
+}
+
+fn unique_violation(table: &str, key: &[&'static str]) -> MemoryError {
+    MemoryError::UniqueViolation(UniqueViolation {
+        table: table.to_owned(),
+        columns: key.iter().map(|column| column.to_string()).collect(),
+    })
+}
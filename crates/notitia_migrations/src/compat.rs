@@ -286,8 +286,8 @@ fn check_field_compat(
         issues.push(CompatIssue::FieldTypeChanged {
             table: table.to_string(),
             field: field.to_string(),
-            old_type: old.field_type,
-            new_type: current.field_type,
+            old_type: old.field_type.clone(),
+            new_type: current.field_type.clone(),
         });
     }
 
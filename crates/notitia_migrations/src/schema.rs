@@ -45,7 +45,7 @@ pub struct FieldSchema {
     pub migrate_from: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum FieldType {
     Int,
     BigInt,
@@ -54,6 +54,8 @@ pub enum FieldType {
     Text,
     Blob,
     Bool,
+    /// With the type of its elements.
+    Array(Box<FieldType>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -88,7 +88,7 @@ fn extract_schema<Db: Database>(db: &Db) -> Schema {
             fields.insert(field_name.to_string(), field_schema);
         }
 
-        let foreign_keys = extract_foreign_keys::<Db>(table_name);
+        let foreign_keys = extract_foreign_keys(db, table_name);
 
         let (table_migrate_from, removed_fields) = match migration_meta.get(table_name) {
             Some(meta) => (
@@ -116,18 +116,10 @@ fn extract_schema<Db: Database>(db: &Db) -> Schema {
 }
 
 fn convert_field(kind: &DatatypeKind) -> FieldSchema {
-    let (field_type, metadata) = match kind {
-        DatatypeKind::Int(m) => (FieldType::Int, m),
-        DatatypeKind::BigInt(m) => (FieldType::BigInt, m),
-        DatatypeKind::Float(m) => (FieldType::Float, m),
-        DatatypeKind::Double(m) => (FieldType::Double, m),
-        DatatypeKind::Text(m) => (FieldType::Text, m),
-        DatatypeKind::Blob(m) => (FieldType::Blob, m),
-        DatatypeKind::Bool(m) => (FieldType::Bool, m),
-    };
+    let metadata = kind.metadata();
 
     FieldSchema {
-        field_type,
+        field_type: field_type(kind),
         primary_key: metadata.primary_key,
         unique: metadata.unique,
         optional: metadata.optional,
@@ -135,10 +127,26 @@ fn convert_field(kind: &DatatypeKind) -> FieldSchema {
     }
 }
 
-fn extract_foreign_keys<Db: Database>(table_name: &str) -> IndexMap<String, ForeignKeySchema> {
+fn field_type(kind: &DatatypeKind) -> FieldType {
+    match kind {
+        DatatypeKind::Int(_) => FieldType::Int,
+        DatatypeKind::BigInt(_) => FieldType::BigInt,
+        DatatypeKind::Float(_) => FieldType::Float,
+        DatatypeKind::Double(_) => FieldType::Double,
+        DatatypeKind::Text(_) => FieldType::Text,
+        DatatypeKind::Blob(_) => FieldType::Blob,
+        DatatypeKind::Bool(_) => FieldType::Bool,
+        DatatypeKind::Array(_, element) => FieldType::Array(Box::new(field_type(element))),
+    }
+}
+
+fn extract_foreign_keys<Db: Database>(
+    db: &Db,
+    table_name: &str,
+) -> IndexMap<String, ForeignKeySchema> {
     let mut fks = IndexMap::new();
 
-    if let Some(relationships) = Db::_FOREIGN_RELATIONSHIPS.get(table_name) {
+    if let Some(relationships) = db.foreign_relationships(table_name) {
         for (local_field, rel) in relationships {
             fks.insert(
                 local_field.to_string(),
@@ -0,0 +1,109 @@
+//! What `notitia_tauri` and `notitia_server` send to remote clients of live
+//! queries, so that both speak the same JSON.
+
+use notitia::{ChangeReason, MutationEventKind, SubscriptionMetadata};
+use serde::Serialize;
+
+/// A message of a live query, tagged by `type` in JSON.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum QueryEvent<T> {
+    /// The query's output when the subscription started.
+    Initial { data: T },
+    /// A mutation that changed the output, for the client to merge into the
+    /// output it has.
+    Change(Change),
+}
+
+/// The last of the mutations that changed a query's output, and how they
+/// changed it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub table: &'static str,
+    pub kind: ChangeKind,
+    /// The event's [`MutationEvent::sequence`](notitia::MutationEvent::sequence).
+    pub sequence: u64,
+    pub reason: ChangeReason,
+    /// The mutation itself, with the values it wrote.
+    pub mutation: MutationEventKind,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Change {
+    pub fn from_metadata(meta: SubscriptionMetadata) -> Option<Self> {
+        match meta {
+            SubscriptionMetadata::None => None,
+            SubscriptionMetadata::Changed(event, reason) => Some(Self {
+                table: event.table_name,
+                kind: match event.kind {
+                    MutationEventKind::Insert { .. } | MutationEventKind::BulkInsert { .. } => {
+                        ChangeKind::Insert
+                    }
+                    MutationEventKind::Update { .. } => ChangeKind::Update,
+                    MutationEventKind::Delete { .. } => ChangeKind::Delete,
+                },
+                sequence: event.sequence,
+                reason,
+                mutation: event.kind,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("no query registered under the name `{0}`")]
+    UnknownQuery(String),
+    #[error("invalid query arguments: {0}")]
+    InvalidArgs(serde_json::Error),
+    #[error("query failed: {0}")]
+    Query(String),
+    #[error("failed to serialize query output: {0}")]
+    Serialize(serde_json::Error),
+}
+
+// Remote clients only need the message.
+impl Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notitia::{Datatype, MutationEvent};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn changes_carry_the_mutation_and_reason() {
+        let meta = SubscriptionMetadata::Changed(
+            MutationEvent {
+                table_name: "users",
+                sequence: 3,
+                kind: MutationEventKind::Insert {
+                    values: vec![("name", Datatype::Text("ada".into()))],
+                },
+            },
+            ChangeReason::RowsInserted(1),
+        );
+        let event = QueryEvent::<()>::Change(Change::from_metadata(meta).unwrap());
+
+        let json = serde_json::to_value(event).unwrap();
+        assert_eq!(json["type"], "change");
+        assert_eq!(json["table"], "users");
+        assert_eq!(json["kind"], "insert");
+        assert_eq!(json["sequence"], 3);
+        assert_eq!(json["reason"], json!({ "RowsInserted": 1 }));
+        assert_eq!(json["mutation"]["Insert"]["values"][0][0], "name");
+        assert!(Change::from_metadata(SubscriptionMetadata::None).is_none());
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+use notitia::{
+    Adapter, Database, DeliveryStrategy, FieldKindGroup, MergingFetchMode, Notitia, QueryExecutor,
+    SelectStmtDef, SubscribableRow,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use notitia_remote::{Change, ChangeKind, Error, QueryEvent};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+type ExecuteFn = dyn Fn(Value) -> BoxFuture<Result<Value, Error>> + Send + Sync;
+
+type SubscribeFn =
+    dyn Fn(Value) -> BoxFuture<Result<BoxStream<'static, String>, Error>> + Send + Sync;
+
+type ExecuteDefFn = dyn Fn(SelectStmtDef) -> BoxFuture<Result<Value, Error>> + Send + Sync;
+
+/// A query registered under a name, type-erased so it can be driven by JSON
+/// arguments from the request.
+struct RegisteredQuery {
+    execute: Box<ExecuteFn>,
+    subscribe: Box<SubscribeFn>,
+}
+
+/// Serves registered queries over HTTP.
+///
+/// Routes (relative to wherever the router is nested):
+/// - `GET /{name}?args=<json>` — run the query once and return its output.
+/// - `GET /{name}/live?args=<json>` — a server-sent event stream. The first
+///   event carries the initial output, every following one a change for the
+///   client to merge into it.
+/// - `POST /` with a JSON [`SelectStmtDef`] — run the statement and return
+///   its rows, once enabled with [`select_defs`](Self::select_defs).
+///
+/// Each event's data is a JSON [`QueryEvent`]. Only named queries stream
+/// changes: merging one takes the types of the query's rows, which a
+/// [`SelectStmtDef`] doesn't have.
+pub struct LiveQueries {
+    queries: HashMap<&'static str, RegisteredQuery>,
+    execute_def: Option<Box<ExecuteDefFn>>,
+}
+
+impl Default for LiveQueries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveQueries {
+    pub fn new() -> Self {
+        Self {
+            queries: HashMap::new(),
+            execute_def: None,
+        }
+    }
+
+    /// Run the [`SelectStmtDef`]s clients post against `db`, for clients
+    /// that build their statements themselves. They can read every table of
+    /// `db` this way, unlike with named queries, so it's opt in.
+    pub fn select_defs<Db, Adptr>(mut self, db: Notitia<Db, Adptr>) -> Self
+    where
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+    {
+        self.execute_def = Some(Box::new(move |def| {
+            let db = db.clone();
+            Box::pin(async move {
+                let rows = db
+                    .execute_select_def(&def)
+                    .await
+                    .map_err(|err| Error::Query(err.to_string()))?;
+                serde_json::to_value(rows).map_err(Error::Serialize)
+            })
+        }));
+        self
+    }
+
+    /// Register a query under `name`. `build` receives the deserialized
+    /// `args` parameter (use `()` for queries without arguments).
+    pub fn query<Args, F, Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+        mut self,
+        name: &'static str,
+        build: F,
+    ) -> Self
+    where
+        Args: DeserializeOwned,
+        F: Fn(Args) -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+            + Send
+            + Sync
+            + 'static,
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        Adptr::Error: Send,
+        FieldUnion: unions::IsUnion + Send + Sync + 'static,
+        FieldPath: Send + Sync + 'static,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+        Fields::Type: SubscribableRow,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode::Output: Serialize + Clone + PartialEq + Send,
+    {
+        let build = Arc::new(build);
+
+        let execute: Box<ExecuteFn> = {
+            let build = build.clone();
+            Box::new(move |args| {
+                let query = match serde_json::from_value(args) {
+                    Ok(args) => build(args),
+                    Err(err) => return Box::pin(async move { Err(Error::InvalidArgs(err)) }),
+                };
+
+                Box::pin(async move {
+                    let output = query
+                        .execute()
+                        .await
+                        .map_err(|err| Error::Query(err.to_string()))?;
+                    serde_json::to_value(output).map_err(Error::Serialize)
+                })
+            })
+        };
+
+        let subscribe: Box<SubscribeFn> = Box::new(move |args| {
+            let query = match serde_json::from_value(args) {
+                Ok(args) => build(args),
+                Err(err) => return Box::pin(async move { Err(Error::InvalidArgs(err)) }),
+            };
+
+            Box::pin(async move {
+                let sub = query
+                    .subscribe_via(DeliveryStrategy::Async)
+                    .await
+                    .map_err(|err| Error::Query(err.to_string()))?;
+
+                // The first message is the initial output.
+                let _ = sub.recv_async().await;
+                let initial = serde_json::to_string(&QueryEvent::Initial { data: &*sub.data() })
+                    .map_err(Error::Serialize)?;
+
+                // The stream owns the subscription, which is dropped with it
+                // once the client disconnects.
+                let changes = stream::unfold(sub, |sub| async move {
+                    loop {
+                        let meta = sub.recv_async().await.ok()?;
+                        if let Some(change) = Change::from_metadata(meta) {
+                            let json = serde_json::to_string(&QueryEvent::<()>::Change(change));
+                            return Some((json.ok()?, sub));
+                        }
+                    }
+                });
+
+                Ok(stream::once(async { initial }).chain(changes).boxed())
+            })
+        });
+
+        self.queries
+            .insert(name, RegisteredQuery { execute, subscribe });
+        self
+    }
+
+    pub fn router(self) -> Router {
+        let mut router = Router::new()
+            .route("/{name}", get(execute))
+            .route("/{name}/live", get(subscribe));
+        if self.execute_def.is_some() {
+            router = router.route("/", post(execute_def));
+        }
+        router.with_state(Arc::new(self))
+    }
+
+    fn get(&self, name: &str) -> Result<&RegisteredQuery, Error> {
+        self.queries
+            .get(name)
+            .ok_or_else(|| Error::UnknownQuery(name.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct ArgsParam {
+    args: Option<String>,
+}
+
+impl ArgsParam {
+    fn parse(&self) -> Result<Value, Error> {
+        match &self.args {
+            Some(args) => serde_json::from_str(args).map_err(Error::InvalidArgs),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+async fn execute(
+    State(queries): State<Arc<LiveQueries>>,
+    Path(name): Path<String>,
+    Query(params): Query<ArgsParam>,
+) -> Result<Json<Value>, ErrorResponse> {
+    let future = (queries.get(&name)?.execute)(params.parse()?);
+    Ok(Json(future.await?))
+}
+
+async fn execute_def(
+    State(queries): State<Arc<LiveQueries>>,
+    Json(def): Json<SelectStmtDef>,
+) -> Result<Json<Value>, ErrorResponse> {
+    // The route only exists with `execute_def` set.
+    let execute_def = queries.execute_def.as_ref().unwrap();
+    Ok(Json(execute_def(def).await?))
+}
+
+async fn subscribe(
+    State(queries): State<Arc<LiveQueries>>,
+    Path(name): Path<String>,
+    Query(params): Query<ArgsParam>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ErrorResponse> {
+    let future = (queries.get(&name)?.subscribe)(params.parse()?);
+    let events = future.await?;
+
+    let stream = events.map(|json| Ok(Event::default().data(json)));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Responds with an [`Error`], which is `notitia_remote`'s and so can't
+/// implement [`IntoResponse`] here.
+struct ErrorResponse(Error);
+
+impl From<Error> for ErrorResponse {
+    fn from(error: Error) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            Error::UnknownQuery(_) => StatusCode::NOT_FOUND,
+            Error::InvalidArgs(_) => StatusCode::BAD_REQUEST,
+            Error::Query(_) | Error::Serialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.0.to_string()).into_response()
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    ffi::{CStr, CString, c_int, c_void},
+    io,
+    pin::Pin,
+    ptr::{self, NonNull},
+    task::{Context, Poll},
+};
+
+use futures_util::io::{AsyncRead, AsyncWrite};
+use libsqlite3_sys::{
+    SQLITE_OK, sqlite3, sqlite3_blob, sqlite3_blob_bytes, sqlite3_blob_close, sqlite3_blob_open,
+    sqlite3_blob_read, sqlite3_blob_write, sqlite3_exec,
+};
+use notitia_core::BlobLocation;
+use sea_query::{Alias, Expr, Query, SqliteQueryBuilder};
+use sqlx::{Row, Sqlite, pool::PoolConnection};
+
+use crate::{SqlStatement, SqliteError, convert_stmts::filter_to_expr};
+
+/// An open `sqlite3_blob` together with the connection it was opened on.
+///
+/// The connection is checked out of the pool for as long as the blob is
+/// open, so nothing else runs on it and the blob API can be called directly
+/// instead of through sqlx's worker.
+struct Blob {
+    db: NonNull<sqlite3>,
+    handle: Option<NonNull<sqlite3_blob>>,
+    len: usize,
+    offset: usize,
+    /// Whether the blob was opened in a transaction that closing ends.
+    transaction: bool,
+    // Returned to the pool after `Drop` closed the blob.
+    _conn: PoolConnection<Sqlite>,
+}
+
+// SAFETY: the handles are only used through `&mut self`, and the connection
+// they belong to is not used by anything else while the blob is open.
+unsafe impl Send for Blob {}
+
+impl Blob {
+    async fn open(
+        conn: &mut PoolConnection<Sqlite>,
+        location: &BlobLocation,
+        rowid: i64,
+        writable: bool,
+    ) -> Result<(NonNull<sqlite3>, NonNull<sqlite3_blob>, usize), SqliteError> {
+        let table = CString::new(location.table_name).expect("table names have no nul bytes");
+        let column = CString::new(location.column).expect("column names have no nul bytes");
+
+        let mut handle = conn.lock_handle().await?;
+        let db = handle.as_raw_handle();
+        let mut blob = ptr::null_mut();
+        // SAFETY: the connection's handle is locked, and the names outlive
+        // the call.
+        let rc = unsafe {
+            sqlite3_blob_open(
+                db.as_ptr(),
+                c"main".as_ptr(),
+                table.as_ptr(),
+                column.as_ptr(),
+                rowid,
+                writable as c_int,
+                &mut blob,
+            )
+        };
+
+        match NonNull::new(blob) {
+            Some(blob) if rc == SQLITE_OK => {
+                // SAFETY: `blob` was just opened.
+                let len = unsafe { sqlite3_blob_bytes(blob.as_ptr()) };
+                Ok((db, blob, len as usize))
+            }
+            _ => Err(SqliteError::Blob(format!(
+                "failed to open {}.{} (error code {rc})",
+                location.table_name, location.column
+            ))),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.len - self.offset
+    }
+
+    fn handle(&self) -> io::Result<NonNull<sqlite3_blob>> {
+        self.handle
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "blob is closed"))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let handle = self.handle()?;
+        let n = buf.len().min(self.remaining()).min(c_int::MAX as usize);
+        if n == 0 {
+            return Ok(0);
+        }
+
+        // SAFETY: `buf` holds at least `n` bytes, and `offset + n` is within
+        // the blob.
+        let rc = unsafe {
+            sqlite3_blob_read(
+                handle.as_ptr(),
+                buf.as_mut_ptr().cast::<c_void>(),
+                n as c_int,
+                self.offset as c_int,
+            )
+        };
+        self.advance(rc, n)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let handle = self.handle()?;
+        // Blobs can't grow while open; a full one accepts nothing, which
+        // `write_all` reports as `WriteZero`.
+        let n = buf.len().min(self.remaining()).min(c_int::MAX as usize);
+        if n == 0 {
+            return Ok(0);
+        }
+
+        // SAFETY: as in `read`.
+        let rc = unsafe {
+            sqlite3_blob_write(
+                handle.as_ptr(),
+                buf.as_ptr().cast::<c_void>(),
+                n as c_int,
+                self.offset as c_int,
+            )
+        };
+        self.advance(rc, n)
+    }
+
+    fn advance(&mut self, rc: c_int, n: usize) -> io::Result<usize> {
+        if rc != SQLITE_OK {
+            return Err(io::Error::other(format!(
+                "blob I/O failed (error code {rc})"
+            )));
+        }
+        self.offset += n;
+        Ok(n)
+    }
+
+    /// Close the blob, and commit or roll back its transaction.
+    fn close(&mut self, commit: bool) -> io::Result<()> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+
+        // SAFETY: the handle is open, and is not used after this.
+        let mut rc = unsafe { sqlite3_blob_close(handle.as_ptr()) };
+        if self.transaction {
+            let end: &CStr = if commit && rc == SQLITE_OK {
+                c"COMMIT"
+            } else {
+                c"ROLLBACK"
+            };
+            // SAFETY: the connection is still open, see `_conn`.
+            let ended = unsafe {
+                sqlite3_exec(
+                    self.db.as_ptr(),
+                    end.as_ptr(),
+                    None,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            };
+            if rc == SQLITE_OK {
+                rc = ended;
+            }
+        }
+
+        if rc != SQLITE_OK {
+            return Err(io::Error::other(format!(
+                "failed to close blob (error code {rc})"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Blob {
+    /// A writer that wasn't closed leaves the column as it was.
+    fn drop(&mut self) {
+        let _ = self.close(false);
+    }
+}
+
+/// Reads a blob column straight from the database file, in the chunks the
+/// caller asks for.
+pub struct SqliteBlobReader(Blob);
+
+/// Writes a blob column in place, in a transaction that holds the
+/// database's write lock until the writer is closed. With a
+/// [write queue](crate::SqliteAdapter::with_write_queue) it holds the writer
+/// connection too, so queued writes wait for it. Dropping it without
+/// closing leaves the column unchanged.
+pub struct SqliteBlobWriter(Blob);
+
+impl SqliteBlobReader {
+    pub(crate) async fn open(
+        mut conn: PoolConnection<Sqlite>,
+        location: &BlobLocation,
+    ) -> Result<Self, SqliteError> {
+        let statement: SqlStatement = Query::select()
+            .expr(Expr::cust("rowid"))
+            .from(Alias::new(location.table_name))
+            .and_where(filter_to_expr(&location.key_filter()))
+            .build(SqliteQueryBuilder)
+            .into();
+        let rowid: i64 = statement.query().fetch_one(&mut *conn).await?.try_get(0)?;
+
+        let (db, handle, len) = Blob::open(&mut conn, location, rowid, false).await?;
+        Ok(Self(Blob {
+            db,
+            handle: Some(handle),
+            len,
+            offset: 0,
+            transaction: false,
+            _conn: conn,
+        }))
+    }
+
+    /// The blob's size in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+}
+
+impl SqliteBlobWriter {
+    pub(crate) async fn open(
+        mut conn: PoolConnection<Sqlite>,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<Self, SqliteError> {
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+
+        match Self::resize_and_open(&mut conn, location, len).await {
+            Ok((db, handle, len)) => Ok(Self(Blob {
+                db,
+                handle: Some(handle),
+                len,
+                offset: 0,
+                transaction: true,
+                _conn: conn,
+            })),
+            Err(err) => {
+                let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn resize_and_open(
+        conn: &mut PoolConnection<Sqlite>,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<(NonNull<sqlite3>, NonNull<sqlite3_blob>, usize), SqliteError> {
+        let statement: SqlStatement = Query::update()
+            .table(Alias::new(location.table_name))
+            .value(
+                Alias::new(location.column),
+                Expr::cust(format!("zeroblob({len})")),
+            )
+            .and_where(filter_to_expr(&location.key_filter()))
+            .returning(Query::returning().expr(Expr::cust("rowid")))
+            .build(SqliteQueryBuilder)
+            .into();
+        let rowid: i64 = statement.query().fetch_one(&mut **conn).await?.try_get(0)?;
+
+        Blob::open(conn, location, rowid, true).await
+    }
+}
+
+impl AsyncRead for SqliteBlobReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().0.read(buf))
+    }
+}
+
+impl AsyncWrite for SqliteBlobWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().0.write(buf))
+    }
+
+    /// Writes go straight to the blob; only closing commits them.
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().0.close(true))
+    }
+}
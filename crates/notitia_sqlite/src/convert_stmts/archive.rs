@@ -0,0 +1,69 @@
+use notitia_core::ArchiveStmt;
+
+use super::{delete::delete_stmt_to_sql, insert::insert_select_to_sql};
+use crate::SqlStatement;
+
+/// The copy into the archive table, returning the archived columns, and the
+/// delete from the hot table. Run them in one transaction.
+pub fn archive_stmt_to_sql(stmt: &ArchiveStmt) -> [SqlStatement; 2] {
+    [
+        insert_select_to_sql(
+            stmt.archive_table,
+            &stmt.columns,
+            &[stmt.hot_table],
+            &stmt.filters,
+            &stmt.columns,
+        ),
+        delete_stmt_to_sql(stmt.hot_table, &stmt.filters),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notitia_core::Table;
+    use notitia_macros::{database, record};
+
+    #[derive(Debug)]
+    #[database]
+    struct TestDb {
+        messages: Table<Message>,
+        archived_messages: Table<ArchivedMessage>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Message {
+        #[db(primary_key)]
+        id: String,
+        body: String,
+        draft: Option<String>,
+        sent_at: i64,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct ArchivedMessage {
+        #[db(primary_key)]
+        id: String,
+        body: String,
+        sent_at: i64,
+    }
+
+    #[test]
+    fn archive_copies_then_deletes() {
+        let policy = TestDb::MESSAGES
+            .archive_into(TestDb::ARCHIVED_MESSAGES)
+            .when(|| Message::SENT_AT.lt(1000i64));
+
+        let [copy, delete] = archive_stmt_to_sql(&policy.to_stmt());
+        assert_eq!(
+            copy.to_inline_sql(),
+            r#"INSERT INTO "archived_messages" ("id", "body", "sent_at") SELECT "id", "body", "sent_at" FROM "messages" WHERE "messages"."sent_at" < 1000 RETURNING "id", "body", "sent_at""#
+        );
+        assert_eq!(
+            delete.to_inline_sql(),
+            r#"DELETE FROM "messages" WHERE "messages"."sent_at" < 1000"#
+        );
+    }
+}
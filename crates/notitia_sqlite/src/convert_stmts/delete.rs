@@ -1,9 +1,10 @@
-use notitia_core::FieldFilter;
+use notitia_core::{Database, DeleteByKeysStmtBuilt, FieldFilter, Record};
 use sea_query::{Alias, Query, SqliteQueryBuilder};
 
 use super::select::filter_to_expr;
+use crate::SqlStatement;
 
-pub fn delete_stmt_to_sql(table_name: &str, filters: &[FieldFilter]) -> String {
+pub fn delete_stmt_to_sql(table_name: &str, filters: &[FieldFilter]) -> SqlStatement {
     let mut query = Query::delete();
 
     query.from_table(Alias::new(table_name));
@@ -12,7 +13,17 @@ pub fn delete_stmt_to_sql(table_name: &str, filters: &[FieldFilter]) -> String {
         query.and_where(filter_to_expr(filter));
     }
 
-    query.to_string(SqliteQueryBuilder)
+    query.build(SqliteQueryBuilder).into()
+}
+
+/// One `DELETE ... WHERE key IN (...)` per `chunk_size` keys.
+pub fn delete_by_keys_stmts_to_sql<Db: Database, Rec: Record>(
+    stmt: &DeleteByKeysStmtBuilt<Db, Rec>,
+    chunk_size: usize,
+) -> Vec<SqlStatement> {
+    stmt.chunks(chunk_size)
+        .map(|filter| delete_stmt_to_sql(stmt.table_name, &[filter]))
+        .collect()
 }
 
 #[cfg(test)]
@@ -39,7 +50,7 @@ mod tests {
     #[test]
     fn delete_all() {
         let stmt = TestDb::USERS.delete();
-        let sql = delete_stmt_to_sql(stmt.table_name, &[]);
+        let sql = delete_stmt_to_sql(stmt.table_name, &[]).to_inline_sql();
 
         assert_eq!(sql, r#"DELETE FROM "users""#);
     }
@@ -47,7 +58,7 @@ mod tests {
     #[test]
     fn delete_with_filter() {
         let stmt = TestDb::USERS.delete().filter(User::ID.eq("abc"));
-        let sql = delete_stmt_to_sql(stmt.table_name, &stmt.filters);
+        let sql = delete_stmt_to_sql(stmt.table_name, &stmt.filters).to_inline_sql();
 
         assert_eq!(sql, r#"DELETE FROM "users" WHERE "users"."id" = 'abc'"#);
     }
@@ -58,11 +69,28 @@ mod tests {
             .delete()
             .filter(User::ID.eq("abc"))
             .filter(User::AGE.gt(18i64));
-        let sql = delete_stmt_to_sql(stmt.table_name, &stmt.filters);
+        let sql = delete_stmt_to_sql(stmt.table_name, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
             r#"DELETE FROM "users" WHERE "users"."id" = 'abc' AND "users"."age" > 18"#
         );
     }
+
+    #[test]
+    fn delete_by_keys_in_chunks() {
+        let stmt = TestDb::USERS.delete_by_keys(["a", "b", "c"]);
+
+        let statements: Vec<String> = delete_by_keys_stmts_to_sql(&stmt, 2)
+            .iter()
+            .map(SqlStatement::to_inline_sql)
+            .collect();
+        assert_eq!(
+            statements,
+            [
+                r#"DELETE FROM "users" WHERE "users"."id" IN ('a', 'b')"#,
+                r#"DELETE FROM "users" WHERE "users"."id" IN ('c')"#,
+            ]
+        );
+    }
 }
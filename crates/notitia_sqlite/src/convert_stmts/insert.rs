@@ -1,9 +1,16 @@
-use notitia_core::Datatype;
-use sea_query::{Alias, Expr, Query, SqliteQueryBuilder};
+use notitia_core::{Database, Datatype, FieldFilter, InsertFromSelectStmtBuilt, Record};
+use sea_query::{Alias, Expr, InsertStatement, OnConflict, Query, SqliteQueryBuilder};
 
-use super::select::datatype_to_sea_value;
+use super::select::{datatype_to_sea_value, filter_to_expr, push_from};
+use crate::SqlStatement;
 
-pub fn insert_stmt_to_sql(table_name: &str, fields: &[(&str, Datatype)]) -> String {
+/// With `ignore_conflicts`, the statement skips a conflicting row and
+/// returns its columns only if it was inserted.
+pub fn insert_stmt_to_sql(
+    table_name: &str,
+    fields: &[(&str, Datatype)],
+    ignore_conflicts: bool,
+) -> SqlStatement {
     let mut query = Query::insert();
 
     query.into_table(Alias::new(table_name));
@@ -17,19 +24,115 @@ pub fn insert_stmt_to_sql(table_name: &str, fields: &[(&str, Datatype)]) -> Stri
         .collect();
     query.values_panic(values);
 
-    query.to_string(SqliteQueryBuilder)
+    if ignore_conflicts {
+        skip_conflicts(&mut query, fields);
+    }
+
+    query.build(SqliteQueryBuilder).into()
+}
+
+/// Build one multi-row `INSERT` for `rows`, which must all list the same
+/// columns in the same order. `ignore_conflicts` works like in
+/// [`insert_stmt_to_sql`], returning the rows that were inserted.
+pub fn insert_many_stmt_to_sql(
+    table_name: &str,
+    rows: &[Vec<(&str, Datatype)>],
+    ignore_conflicts: bool,
+) -> SqlStatement {
+    let mut query = Query::insert();
+
+    query.into_table(Alias::new(table_name));
+
+    if let Some(first) = rows.first() {
+        let columns: Vec<_> = first.iter().map(|(name, _)| Alias::new(*name)).collect();
+        query.columns(columns);
+    }
+
+    for fields in rows {
+        let values: Vec<_> = fields
+            .iter()
+            .map(|(_, datatype)| Expr::val(datatype_to_sea_value(datatype)).into())
+            .collect();
+        query.values_panic(values);
+    }
+
+    if let (true, Some(first)) = (ignore_conflicts, rows.first()) {
+        skip_conflicts(&mut query, first);
+    }
+
+    query.build(SqliteQueryBuilder).into()
+}
+
+/// `ON CONFLICT DO NOTHING RETURNING ...`: unlike `INSERT OR IGNORE`, only
+/// unique and primary key conflicts are skipped, not e.g. a missing value
+/// for a `NOT NULL` column.
+fn skip_conflicts(query: &mut InsertStatement, fields: &[(&str, Datatype)]) {
+    query.on_conflict(OnConflict::new().do_nothing().to_owned());
+    query.returning(Query::returning().columns(fields.iter().map(|(name, _)| Alias::new(*name))));
+}
+
+/// Build `INSERT INTO ... SELECT ...`, returning every column of the
+/// destination table for each inserted row.
+pub fn insert_from_select_stmt_to_sql<Db: Database, Rec: Record>(
+    stmt: &InsertFromSelectStmtBuilt<Db, Rec>,
+) -> SqlStatement {
+    let returning: Vec<&str> = Rec::_FIELDS.iter().map(|(name, _)| *name).collect();
+    insert_select_to_sql(
+        stmt.table_name,
+        &stmt.columns,
+        &stmt.source_tables,
+        &stmt.filters,
+        &returning,
+    )
+}
+
+pub(crate) fn insert_select_to_sql(
+    table_name: &str,
+    columns: &[&str],
+    source_tables: &[&'static str],
+    filters: &[FieldFilter],
+    returning: &[&str],
+) -> SqlStatement {
+    let mut select = Query::select();
+    for column in columns {
+        select.column(Alias::new(*column));
+    }
+    push_from(&mut select, source_tables, None);
+    for filter in filters {
+        select.and_where(filter_to_expr(filter));
+    }
+
+    let mut query = Query::insert();
+    query.into_table(Alias::new(table_name));
+    query.columns(columns.iter().map(|column| Alias::new(*column)));
+    query
+        .select_from(select)
+        .expect("insert and select list the same columns");
+    query.returning(Query::returning().columns(returning.iter().map(|name| Alias::new(*name))));
+
+    query.build(SqliteQueryBuilder).into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use notitia_core::{Record, Table};
+    use notitia_core::{SelectStmtFilterable, SelectStmtSelectable, Table};
     use notitia_macros::{database, record};
 
     #[derive(Debug)]
     #[database]
     struct TestDb {
         users: Table<User>,
+        archived_users: Table<ArchivedUser>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct ArchivedUser {
+        #[db(primary_key)]
+        id: String,
+        name: String,
+        age: i64,
     }
 
     #[derive(Debug)]
@@ -47,11 +150,63 @@ mod tests {
         let stmt = TestDb::USERS.insert(user);
 
         let fields = stmt.record.into_datatypes();
-        let sql = insert_stmt_to_sql(stmt.table_name, &fields);
+        let sql =
+            insert_stmt_to_sql(stmt.table_name, &fields, stmt.ignore_conflicts).to_inline_sql();
 
         assert_eq!(
             sql,
             r#"INSERT INTO "users" ("id", "name", "age") VALUES ('abc', 'Bob', 36)"#
         );
     }
+
+    #[test]
+    fn insert_many_records() {
+        let stmt = TestDb::USERS.insert_many([
+            User::build().id("a").name("Ann").age(20),
+            User::build().id("b").name("Ben").age(30),
+        ]);
+
+        let rows: Vec<_> = stmt
+            .records
+            .into_iter()
+            .map(|record| record.into_datatypes())
+            .collect();
+        let sql =
+            insert_many_stmt_to_sql(stmt.table_name, &rows, stmt.ignore_conflicts).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"INSERT INTO "users" ("id", "name", "age") VALUES ('a', 'Ann', 20), ('b', 'Ben', 30)"#
+        );
+    }
+
+    #[test]
+    fn insert_ignoring_conflicts() {
+        let stmt = TestDb::USERS
+            .insert(User::build().id("abc").name("Bob").age(36))
+            .ignore_conflicts();
+
+        let fields = stmt.record.into_datatypes();
+        let sql =
+            insert_stmt_to_sql(stmt.table_name, &fields, stmt.ignore_conflicts).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"INSERT INTO "users" ("id", "name", "age") VALUES ('abc', 'Bob', 36) ON CONFLICT DO NOTHING RETURNING "id", "name", "age""#
+        );
+    }
+
+    #[test]
+    fn insert_from_select() {
+        let stmt = TestDb::ARCHIVED_USERS.insert_from(
+            TestDb::USERS
+                .select((User::ID, User::NAME))
+                .filter(User::AGE.gt(18i64)),
+        );
+
+        assert_eq!(
+            insert_from_select_stmt_to_sql(&stmt).to_inline_sql(),
+            r#"INSERT INTO "archived_users" ("id", "name") SELECT "id", "name" FROM "users" WHERE "users"."age" > 18 RETURNING "id", "name", "age""#
+        );
+    }
 }
@@ -9,3 +9,6 @@ pub use update::*;
 
 pub mod delete;
 pub use delete::*;
+
+pub mod archive;
+pub use archive::*;
@@ -1,30 +1,108 @@
 use notitia_core::{
-    Database, Datatype, FieldFilter, FieldFilterMetadata, FieldKindGroup, OrderDirection,
-    SelectStmtBuilt, SelectStmtFetchMode,
+    Aggregate, Database, Datatype, FieldFilter, FieldFilterMetadata, FieldKindGroup, IndexHint,
+    OrderBy, OrderDirection, PerGroup, SelectStmtBuilt, SelectStmtDef, SelectStmtFetchMode,
+    split_table_entry,
 };
-use sea_query::{Alias, Expr, Query, SimpleExpr, SqliteQueryBuilder};
+use sea_query::{
+    Alias, Expr, Func, Iden, Query, Quote, SelectStatement, SimpleExpr, SqliteQueryBuilder,
+    WindowStatement,
+};
+use std::fmt;
 use unions::IsUnion;
 
+use super::update::field_expr_to_sea_expr;
+use crate::{SqlStatement, storage::bool_to_storage};
+
+/// Values an `IN (...)` filter lists one by one. Longer lists are read from
+/// them as a JSON array, one value for SQLite however many it holds, to
+/// stay within its limits on bound parameters and statement length.
+const MAX_LISTED_VALUES: usize = 900;
+
+/// The column a row's place within its group is selected as, for
+/// statements limited per group.
+const GROUP_RANK: &str = "_notitia_group_rank";
+
 pub(crate) fn datatype_to_sea_value(datatype: &Datatype) -> sea_query::Value {
     match datatype {
         Datatype::Int(v) => sea_query::Value::Int(Some(*v)),
         Datatype::BigInt(v) => sea_query::Value::BigInt(Some(*v)),
+        // SQLite stores NaN as NULL.
+        Datatype::Float(v) if v.is_nan() => sea_query::Value::Float(None),
+        Datatype::Double(v) if v.is_nan() => sea_query::Value::Double(None),
         Datatype::Float(v) => sea_query::Value::Float(Some(*v)),
         Datatype::Double(v) => sea_query::Value::Double(Some(*v)),
         Datatype::Text(v) => sea_query::Value::String(Some(Box::new(v.clone()))),
         Datatype::Blob(v) => sea_query::Value::Bytes(Some(Box::new(v.clone()))),
-        Datatype::Bool(v) => sea_query::Value::Bool(Some(*v)),
+        Datatype::Bool(v) => sea_query::Value::BigInt(Some(bool_to_storage(*v))),
+        // SQLite has no arrays; they're stored as JSON text.
+        Datatype::Array(v) => sea_query::Value::String(Some(Box::new(Datatype::array_to_json(v)))),
         Datatype::Null => sea_query::Value::Int(None),
     }
 }
 
 pub(crate) fn filter_to_expr(filter: &FieldFilter) -> SimpleExpr {
     match filter {
+        FieldFilter::In(m) if m.right.len() > MAX_LISTED_VALUES => {
+            if m.right.iter().all(survives_json) {
+                Expr::cust_with_values(
+                    format!(
+                        r#""{}"."{}" IN (SELECT value FROM json_each(?))"#,
+                        m.left.table_name, m.left.field_name
+                    ),
+                    [sea_query::Value::String(Some(Box::new(
+                        Datatype::array_to_json(&m.right),
+                    )))],
+                )
+            } else {
+                let col = Expr::col((Alias::new(m.left.table_name), Alias::new(m.left.field_name)));
+                m.right
+                    .chunks(MAX_LISTED_VALUES)
+                    .map(|chunk| col.clone().is_in(chunk.iter().map(datatype_to_sea_value)))
+                    .reduce(SimpleExpr::or)
+                    .expect("the list is longer than a chunk")
+            }
+        }
         FieldFilter::In(m) => {
             let col = Expr::col((Alias::new(m.left.table_name), Alias::new(m.left.field_name)));
             let values: Vec<sea_query::Value> = m.right.iter().map(datatype_to_sea_value).collect();
             col.is_in(values)
         }
+        FieldFilter::Call(m) => {
+            let col = Expr::col((Alias::new(m.left.table_name), Alias::new(m.left.field_name)));
+            let args = m
+                .args
+                .iter()
+                .map(|arg| Expr::val(datatype_to_sea_value(arg)));
+            Func::cust(Alias::new(m.function.name()))
+                .arg(col)
+                .args(args)
+                .into()
+        }
+        FieldFilter::EqColumn(m) => {
+            Expr::col((Alias::new(m.left.table_name), Alias::new(m.left.field_name))).equals((
+                Alias::new(m.right.table_name),
+                Alias::new(m.right.field_name),
+            ))
+        }
+        FieldFilter::ContainsElement(m) => Expr::cust_with_values(
+            format!(
+                r#"EXISTS (SELECT 1 FROM json_each("{}"."{}") WHERE value = ?)"#,
+                m.left.table_name, m.left.field_name
+            ),
+            [datatype_to_sea_value(&m.right)],
+        ),
+        FieldFilter::IsNull(pair) => {
+            Expr::col((Alias::new(pair.table_name), Alias::new(pair.field_name))).is_null()
+        }
+        FieldFilter::IsNotNull(pair) => {
+            Expr::col((Alias::new(pair.table_name), Alias::new(pair.field_name))).is_not_null()
+        }
+        FieldFilter::Or(filters) => filters
+            .iter()
+            .map(filter_to_expr)
+            .reduce(SimpleExpr::or)
+            .unwrap_or_else(|| Expr::cust("FALSE")),
+        FieldFilter::Not(filter) => filter_to_expr(filter).not(),
         _ => {
             let (metadata, build): (
                 &FieldFilterMetadata,
@@ -36,7 +114,16 @@ pub(crate) fn filter_to_expr(filter: &FieldFilter) -> SimpleExpr {
                 FieldFilter::Gte(m) => (m, |col, val| col.gte(val)),
                 FieldFilter::Lte(m) => (m, |col, val| col.lte(val)),
                 FieldFilter::Ne(m) => (m, |col, val| col.ne(val)),
-                FieldFilter::In(_) => unreachable!(),
+                FieldFilter::EqNullable(m) => (m, |col, val| col.is(val)),
+                FieldFilter::DistinctFrom(m) => (m, |col, val| col.is_not(val)),
+                FieldFilter::In(_)
+                | FieldFilter::Call(_)
+                | FieldFilter::ContainsElement(_)
+                | FieldFilter::EqColumn(_)
+                | FieldFilter::IsNull(_)
+                | FieldFilter::IsNotNull(_)
+                | FieldFilter::Or(_)
+                | FieldFilter::Not(_) => unreachable!(),
             };
 
             let col = Expr::col((
@@ -50,9 +137,20 @@ pub(crate) fn filter_to_expr(filter: &FieldFilter) -> SimpleExpr {
     }
 }
 
+/// Whether `json_each` reads `value` back as the value it is bound as
+/// otherwise. Blobs and arrays become JSON arrays, the infinities `null`.
+fn survives_json(value: &Datatype) -> bool {
+    match value {
+        Datatype::Blob(_) | Datatype::Array(_) => false,
+        Datatype::Float(v) => !v.is_infinite(),
+        Datatype::Double(v) => !v.is_infinite(),
+        _ => true,
+    }
+}
+
 pub fn select_stmt_to_sql<Db, FieldUnion, FieldPath, Fields, Mode>(
     stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
-) -> String
+) -> SqlStatement
 where
     Db: Database,
     FieldUnion: IsUnion,
@@ -62,27 +160,77 @@ where
     let mut query = Query::select();
 
     let field_names = stmt.fields.field_names();
-    for name in &field_names {
-        query.column(Alias::new(*name));
+    let aggregate = stmt.mode.aggregating().map(|mode| mode.aggregate());
+    if let Some(aggregate) = aggregate {
+        // Only the aggregate is selected, of the first field.
+        let name = field_names[0];
+        let table = match stmt.fields.field_tables()[0] {
+            Some(table) => Some(table),
+            None if stmt.tables.len() > 1 => joined_column_table(stmt)(name),
+            None => None,
+        };
+        let column = match table {
+            Some(table) => Expr::col((Alias::new(table), Alias::new(name))),
+            None => Expr::col(Alias::new(name)),
+        };
+        query.expr(match aggregate {
+            Aggregate::Count => Expr::cust("COUNT(*)"),
+            Aggregate::Sum => Func::sum(column).into(),
+            Aggregate::Avg => Func::avg(column).into(),
+            Aggregate::Min => Func::min(column).into(),
+            Aggregate::Max => Func::max(column).into(),
+        });
+    } else if stmt.tables.len() > 1 {
+        // Qualify the columns of a join, which may share names.
+        let table_of = joined_column_table(stmt);
+        for (name, table) in field_names.iter().zip(stmt.fields.field_tables()) {
+            match table.or_else(|| table_of(name)) {
+                Some(table) => query.column((Alias::new(table), Alias::new(*name))),
+                None => query.column(Alias::new(*name)),
+            };
+        }
+    } else {
+        for name in &field_names {
+            query.column(Alias::new(*name));
+        }
     }
 
-    // Only add ORDER BY fields to the SELECT list when the fetch mode
-    // needs order keys (fetch_all / fetch_many).
-    if stmt.mode.needs_order_keys() {
-        for order in &stmt.order_by {
-            if !field_names.contains(&order.field) {
-                query.column(Alias::new(order.field));
+    // The order key values that aren't selected already, in the columns
+    // `decode_rows_with_order_keys` reads them from. An aggregate is the
+    // only column.
+    let unselected = stmt.unselected_order_by().filter(|_| aggregate.is_none());
+    for order in unselected {
+        match order.field() {
+            Some(field) if stmt.tables.len() > 1 => {
+                query.column((Alias::new(order.table), Alias::new(field)));
+            }
+            Some(field) => {
+                query.column(Alias::new(field));
+            }
+            None => {
+                query.expr(field_expr_to_sea_expr(&order.expr, Some(order.table)));
             }
         }
     }
 
-    for table in &stmt.tables {
-        query.from(Alias::new(*table));
+    // After the order key columns, so that it is always the last one.
+    if stmt.mode.needs_total_count() {
+        query.expr(Expr::cust("COUNT(*) OVER ()"));
     }
 
+    push_from(&mut query, &stmt.tables, stmt.index_hint.as_ref());
+
     for filter in &stmt.filters {
         query.and_where(filter_to_expr(filter));
     }
+    if let Some(per_group) = &stmt.per_group {
+        query.and_where(per_group_filter(
+            per_group,
+            &stmt.tables,
+            &stmt.filters,
+            &stmt.order_by,
+        ));
+    }
 
     // When similarity search is active, use CASE-based ordering by PK rank.
     // This preserves the zvec similarity ranking in the SQL results.
@@ -115,27 +263,208 @@ where
         }
     }
 
-    for order in &stmt.order_by {
-        let col = Expr::col((Alias::new(order.table), Alias::new(order.field)));
-        match order.direction {
-            OrderDirection::Asc => {
-                query.order_by_expr(col.into(), sea_query::Order::Asc);
+    // The one row of an aggregate has no order; the order still ranks rows
+    // within their group.
+    if aggregate.is_none() {
+        push_order_by(&mut query, &stmt.order_by);
+    }
+
+    // The rows past the limit of the fetch mode would only be dropped by
+    // `from_rows`, along with their order keys.
+    if let Some(limit) = stmt.mode.limit() {
+        query.limit(limit as u64);
+    }
+
+    query.build(SqliteQueryBuilder).into()
+}
+
+/// Like [`select_stmt_to_sql`], for a statement sent as data. The value of
+/// every order expression is selected after the fields, even when it is one
+/// of them, so that clients can rebuild the order keys.
+pub fn select_def_to_sql(def: &SelectStmtDef) -> SqlStatement {
+    let mut query = Query::select();
+
+    for name in &def.fields {
+        query.column(Alias::new(*name));
+    }
+    for order in &def.order_by {
+        query.expr(field_expr_to_sea_expr(&order.expr, Some(order.table)));
+    }
+
+    push_from(&mut query, &def.tables, None);
+
+    for filter in &def.filters {
+        query.and_where(filter_to_expr(filter));
+    }
+    if let Some(per_group) = &def.per_group {
+        query.and_where(per_group_filter(
+            per_group,
+            &def.tables,
+            &def.filters,
+            &def.order_by,
+        ));
+    }
+
+    push_order_by(&mut query, &def.order_by);
+
+    if let Some(limit) = def.limit {
+        query.limit(limit as u64);
+    }
+
+    query.build(SqliteQueryBuilder).into()
+}
+
+/// List `tables` in the FROM clause, aliased ones as `"table" AS "alias"`.
+/// The index hint goes after the first table, or its alias.
+///
+/// # Panics
+///
+/// If there is an index hint but no table to put it after.
+pub(crate) fn push_from(
+    query: &mut SelectStatement,
+    tables: &[&'static str],
+    index_hint: Option<&IndexHint>,
+) {
+    assert!(
+        index_hint.is_none() || !tables.is_empty(),
+        "an index hint needs a table to go after"
+    );
+
+    for (idx, entry) in tables.iter().enumerate() {
+        let hint = index_hint.filter(|_| idx == 0).cloned();
+        match (split_table_entry(*entry), hint) {
+            ((table, Some(alias)), Some(hint)) => {
+                query.from_as(Alias::new(table), Hinted { name: alias, hint })
             }
-            OrderDirection::Desc => {
-                query.order_by_expr(col.into(), sea_query::Order::Desc);
+            ((table, Some(alias)), None) => query.from_as(Alias::new(table), Alias::new(alias)),
+            ((table, None), Some(hint)) => query.from(Hinted { name: table, hint }),
+            ((table, None), None) => query.from(Alias::new(table)),
+        };
+    }
+}
+
+/// A table or alias followed by its index hint, which sea_query has no
+/// syntax for.
+struct Hinted {
+    name: &'static str,
+    hint: IndexHint,
+}
+
+impl Iden for Hinted {
+    fn prepare(&self, s: &mut dyn fmt::Write, q: Quote) {
+        write!(s, "{}{}{}", q.left(), self.name, q.right()).unwrap();
+        match self.hint {
+            IndexHint::IndexedBy(index) => {
+                write!(s, " INDEXED BY {}{}{}", q.left(), index, q.right()).unwrap()
             }
+            IndexHint::NotIndexed => write!(s, " NOT INDEXED").unwrap(),
         }
     }
 
-    query.to_string(SqliteQueryBuilder)
+    fn unquoted(&self, s: &mut dyn fmt::Write) {
+        write!(s, "{}", self.name).unwrap();
+    }
+}
+
+/// Keep the rows among the first `per_group.limit` of their group, by the
+/// rowids of the rows `filters` match, numbered within their group in the
+/// order of `order_by`.
+fn per_group_filter(
+    per_group: &PerGroup,
+    tables: &[&'static str],
+    filters: &[FieldFilter],
+    order_by: &[OrderBy],
+) -> SimpleExpr {
+    let rowid = (Alias::new(tables[0]), Alias::new("rowid"));
+
+    let mut window = WindowStatement::partition_by((
+        Alias::new(per_group.field.table_name),
+        Alias::new(per_group.field.field_name),
+    ));
+    for order in order_by {
+        let expr = field_expr_to_sea_expr(&order.expr, Some(order.table));
+        sea_query::OrderedStatement::order_by_expr(&mut window, expr, sea_order(&order.direction));
+    }
+
+    let mut ranked = Query::select();
+    ranked.column(rowid.clone()).expr_window_as(
+        Func::cust(Alias::new("ROW_NUMBER")),
+        window,
+        Alias::new(GROUP_RANK),
+    );
+    push_from(&mut ranked, tables, None);
+    for filter in filters {
+        ranked.and_where(filter_to_expr(filter));
+    }
+
+    let mut kept = Query::select();
+    kept.column(Alias::new("rowid"))
+        .from_subquery(ranked, Alias::new("ranked"))
+        .and_where(Expr::col(Alias::new(GROUP_RANK)).lte(per_group.limit as u64));
+
+    Expr::col(rowid).in_subquery(kept)
+}
+
+/// The joined table that has a column named `name`, if exactly one does.
+/// Aliases of a table don't count, their fields are always qualified.
+fn joined_column_table<Db, FieldUnion, FieldPath, Fields, Mode>(
+    stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+) -> impl Fn(&str) -> Option<&'static str>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    let joined: Vec<_> = Db::new()
+        .tables()
+        .filter(|(table, _)| stmt.tables.contains(table))
+        .collect();
+
+    move |name: &str| {
+        let mut tables = joined
+            .iter()
+            .filter(|(_, fields)| fields.iter().any(|(column, _)| *column == name));
+        match (tables.next(), tables.next()) {
+            (Some((table, _)), None) => Some(*table),
+            _ => None,
+        }
+    }
+}
+
+fn push_order_by(query: &mut SelectStatement, order_by: &[OrderBy]) {
+    for order in order_by {
+        let expr = field_expr_to_sea_expr(&order.expr, Some(order.table));
+        query.order_by_expr(expr, sea_order(&order.direction));
+    }
+}
+
+fn sea_order(direction: &OrderDirection) -> sea_query::Order {
+    match direction {
+        OrderDirection::Asc => sea_query::Order::Asc,
+        OrderDirection::Desc => sea_query::Order::Desc,
+    }
+}
+
+/// Select `columns` of every row of `table_name`, for callers that don't
+/// have a statement, like exports.
+pub fn select_rows_sql(table_name: &str, columns: &[&str]) -> SqlStatement {
+    let mut query = Query::select();
+
+    query.from(Alias::new(table_name));
+    query.columns(columns.iter().map(|column| Alias::new(*column)));
+
+    query.build(SqliteQueryBuilder).into()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use super::*;
     use notitia_core::{
-        OrderDirection, SelectStmtBuildable, SelectStmtFilterable, SelectStmtOrderable,
-        SelectStmtSelectable, Table,
+        OrderDirection, OrderKey, SelectStmtBuildable, SelectStmtFilterable, SelectStmtJoinable,
+        SelectStmtOrderable, SelectStmtSelectable, SqlFunction, Table,
     };
     use notitia_macros::{database, record};
 
@@ -143,6 +472,7 @@ mod tests {
     #[database]
     struct TestDb {
         users: Table<User>,
+        posts: Table<Post>,
     }
 
     #[derive(Debug)]
@@ -152,14 +482,23 @@ mod tests {
         id: String,
         name: String,
         age: i64,
+        nickname: Option<String>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Post {
+        #[db(primary_key)]
+        id: String,
+        title: String,
     }
 
     #[test]
     fn select_all_no_filters() {
         let stmt = TestDb::USERS.select(User::NAME).fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
-        assert_eq!(sql, r#"SELECT "name" FROM "users""#);
+        assert_eq!(sql, r#"SELECT "name" FROM "users" LIMIT 2"#);
     }
 
     #[test]
@@ -168,11 +507,32 @@ mod tests {
             .select(User::NAME)
             .filter(User::ID.eq("abc"))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" WHERE "users"."id" = 'abc'"#
+            r#"SELECT "name" FROM "users" WHERE "users"."id" = 'abc' LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn select_binds_values() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::ID.eq("abc' OR 1 = 1 --"))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt);
+
+        assert_eq!(
+            sql.sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."id" = ? LIMIT ?"#
+        );
+        assert_eq!(
+            sql.values,
+            [
+                sea_query::Value::String(Some(Box::new("abc' OR 1 = 1 --".into()))),
+                sea_query::Value::BigUnsigned(Some(2)),
+            ]
         );
     }
 
@@ -182,11 +542,11 @@ mod tests {
             .select(User::NAME)
             .filter(User::AGE.gt(18i64))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" WHERE "users"."age" > 18"#
+            r#"SELECT "name" FROM "users" WHERE "users"."age" > 18 LIMIT 2"#
         );
     }
 
@@ -196,9 +556,12 @@ mod tests {
             .select(User::AGE)
             .filter(User::AGE.lt(30i64))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
-        assert_eq!(sql, r#"SELECT "age" FROM "users" WHERE "users"."age" < 30"#);
+        assert_eq!(
+            sql,
+            r#"SELECT "age" FROM "users" WHERE "users"."age" < 30 LIMIT 2"#
+        );
     }
 
     #[test]
@@ -207,11 +570,11 @@ mod tests {
             .select(User::AGE)
             .filter(User::AGE.gte(21i64))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "age" FROM "users" WHERE "users"."age" >= 21"#
+            r#"SELECT "age" FROM "users" WHERE "users"."age" >= 21 LIMIT 2"#
         );
     }
 
@@ -221,11 +584,11 @@ mod tests {
             .select(User::AGE)
             .filter(User::AGE.lte(65i64))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "age" FROM "users" WHERE "users"."age" <= 65"#
+            r#"SELECT "age" FROM "users" WHERE "users"."age" <= 65 LIMIT 2"#
         );
     }
 
@@ -235,11 +598,62 @@ mod tests {
             .select(User::NAME)
             .filter(User::NAME.ne("admin"))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" WHERE "users"."name" <> 'admin'"#
+            r#"SELECT "name" FROM "users" WHERE "users"."name" <> 'admin' LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn select_with_eq_nullable_filter() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::NAME.eq_nullable(None::<String>))
+            .fetch_all::<Vec<_>>();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."name" IS NULL"#
+        );
+    }
+
+    #[test]
+    fn select_with_distinct_from_filter() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::NAME.is_distinct_from("bob".to_string()))
+            .fetch_all::<Vec<_>>();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."name" IS NOT 'bob'"#
+        );
+    }
+
+    #[test]
+    fn select_with_null_filters() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::NICKNAME.is_null())
+            .fetch_all::<Vec<_>>();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."nickname" IS NULL"#
+        );
+
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::NICKNAME.is_not_null())
+            .fetch_all::<Vec<_>>();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."nickname" IS NOT NULL"#
         );
     }
 
@@ -250,11 +664,26 @@ mod tests {
             .filter(User::AGE.gte(18i64))
             .filter(User::AGE.lt(65i64))
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."age" >= 18 AND "users"."age" < 65 LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn select_with_or_filter() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.lt(18i64).or(User::NAME.eq("bob")))
+            .filter(User::ID.ne("admin"))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" WHERE "users"."age" >= 18 AND "users"."age" < 65"#
+            r#"SELECT "name" FROM "users" WHERE ("users"."age" < 18 OR "users"."name" = 'bob') AND "users"."id" <> 'admin' LIMIT 2"#
         );
     }
 
@@ -264,11 +693,11 @@ mod tests {
             .select(User::NAME)
             .order_by(User::AGE, OrderDirection::Asc)
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" ORDER BY "users"."age" ASC"#
+            r#"SELECT "name" FROM "users" ORDER BY "users"."age" ASC LIMIT 2"#
         );
     }
 
@@ -278,11 +707,11 @@ mod tests {
             .select(User::NAME)
             .order_by(User::NAME, OrderDirection::Desc)
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" ORDER BY "users"."name" DESC"#
+            r#"SELECT "name" FROM "users" ORDER BY "users"."name" DESC LIMIT 2"#
         );
     }
 
@@ -293,11 +722,11 @@ mod tests {
             .order_by(User::AGE, OrderDirection::Desc)
             .order_by(User::NAME, OrderDirection::Asc)
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" ORDER BY "users"."age" DESC, "users"."name" ASC"#
+            r#"SELECT "name" FROM "users" ORDER BY "users"."age" DESC, "users"."name" ASC LIMIT 2"#
         );
     }
 
@@ -308,11 +737,217 @@ mod tests {
             .filter(User::AGE.gte(18i64))
             .order_by(User::NAME, OrderDirection::Asc)
             .fetch_one();
-        let sql = select_stmt_to_sql(&stmt);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE "users"."age" >= 18 ORDER BY "users"."name" ASC LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn select_with_function_filter() {
+        let fuzzy_match = SqlFunction::new("fuzzy_match", 2, |_| Datatype::Bool(true));
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::NAME.satisfies(&fuzzy_match, ["bob".into()]))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" WHERE fuzzy_match("users"."name", 'bob') LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn select_with_order_by_expr() {
+        let length = SqlFunction::new("length", 1, |args| match &args[0] {
+            Datatype::Text(text) => Datatype::BigInt(text.chars().count() as i64),
+            _ => Datatype::Null,
+        });
+        let stmt = TestDb::USERS
+            .select(User::ID)
+            .order_by_expr(User::NAME.apply(&length), OrderDirection::Desc)
+            .fetch_all::<BTreeMap<OrderKey, _>>();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "id", length("users"."name") FROM "users" ORDER BY length("users"."name") DESC"#
+        );
+    }
+
+    #[test]
+    fn select_with_total_count() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.gt(18i64))
+            .order_by(User::AGE, OrderDirection::Asc)
+            .fetch_many::<BTreeMap<OrderKey, _>>(20)
+            .with_total_count();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name", "age", COUNT(*) OVER () FROM "users" WHERE "users"."age" > 18 ORDER BY "users"."age" ASC LIMIT 20"#
+        );
+    }
+
+    #[test]
+    fn select_aggregates() {
+        let count = TestDb::USERS
+            .select(User::ID)
+            .filter(User::AGE.gt(18i64))
+            .fetch_count();
+        assert_eq!(
+            select_stmt_to_sql(&count).to_inline_sql(),
+            r#"SELECT COUNT(*) FROM "users" WHERE "users"."age" > 18"#
+        );
+
+        let sum = TestDb::USERS.select(User::AGE).fetch_sum();
+        assert_eq!(
+            select_stmt_to_sql(&sum).to_inline_sql(),
+            r#"SELECT SUM("age") FROM "users""#
+        );
+
+        let max = TestDb::USERS.select(User::AGE).fetch_max();
+        assert_eq!(
+            select_stmt_to_sql(&max).to_inline_sql(),
+            r#"SELECT MAX("age") FROM "users""#
+        );
+    }
+
+    #[test]
+    fn ordered_aggregates_select_only_the_aggregate() {
+        let sum = TestDb::USERS
+            .select(User::AGE)
+            .order_by(User::NAME, OrderDirection::Asc)
+            .fetch_sum();
+        assert_eq!(
+            select_stmt_to_sql(&sum).to_inline_sql(),
+            r#"SELECT SUM("age") FROM "users""#
+        );
+    }
+
+    #[test]
+    fn select_indexed_by() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.gt(18i64))
+            .fetch_all::<Vec<_>>()
+            .indexed_by("users_by_age");
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "name" FROM "users" INDEXED BY "users_by_age" WHERE "users"."age" > 18"#
+        );
+    }
+
+    #[test]
+    fn index_hints_go_after_the_alias() {
+        let mut query = Query::select();
+        query.column(Alias::new("name"));
+        push_from(
+            &mut query,
+            &["users AS peers", "posts"],
+            Some(&IndexHint::IndexedBy("users_by_age")),
+        );
+        let sql = query.to_string(SqliteQueryBuilder);
 
         assert_eq!(
             sql,
-            r#"SELECT "name" FROM "users" WHERE "users"."age" >= 18 ORDER BY "users"."name" ASC"#
+            r#"SELECT "name" FROM "users" AS "peers" INDEXED BY "users_by_age", "posts""#
+        );
+    }
+
+    #[test]
+    fn select_def_selects_order_values_and_limits() {
+        let stmt = TestDb::USERS
+            .select((User::ID, User::AGE))
+            .filter(User::AGE.gte(18i64))
+            .order_by(User::AGE, OrderDirection::Desc)
+            .fetch_many::<BTreeMap<OrderKey, _>>(10);
+        let sql = select_def_to_sql(&stmt.to_def()).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "id", "age", "users"."age" FROM "users" WHERE "users"."age" >= 18 ORDER BY "users"."age" DESC LIMIT 10"#
+        );
+    }
+
+    #[test]
+    fn select_rows_by_column_names() {
+        let sql = select_rows_sql("users", &["id", "name"]).to_inline_sql();
+
+        assert_eq!(sql, r#"SELECT "id", "name" FROM "users""#);
+    }
+
+    #[test]
+    fn select_join_qualifies_columns() {
+        let stmt = TestDb::USERS
+            .join(TestDb::POSTS)
+            .select((TestDb::POSTS.field(Post::ID), User::NAME, Post::TITLE))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "posts"."id", "users"."name", "posts"."title" FROM "users", "posts" LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn long_in_lists_are_read_from_json() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.is_in(0..1000i64))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert!(sql.starts_with(
+            r#"SELECT "name" FROM "users" WHERE "users"."age" IN (SELECT value FROM json_each('[0,1,2,"#
+        ));
+        assert!(sql.ends_with(r#",998,999]')) LIMIT 2"#));
+    }
+
+    #[test]
+    fn select_self_join_through_alias() {
+        let peers = TestDb::USERS.alias("peers");
+        let stmt = TestDb::USERS
+            .join(peers)
+            .select((User::NAME, peers.field(User::NAME)))
+            .join_on(User::AGE, peers.field(User::AGE))
+            .filter(peers.field(User::ID).ne("u1".to_string()))
+            .fetch_one();
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"SELECT "users"."name", "peers"."name" FROM "users", "users" AS "peers" WHERE "users"."age" = "peers"."age" AND "peers"."id" <> 'u1' LIMIT 2"#
+        );
+    }
+
+    #[test]
+    fn limit_per_group_ranks_rows_within_their_group() {
+        let stmt = TestDb::USERS
+            .select(User::NAME)
+            .filter(User::AGE.gt(18i64))
+            .order_by(User::NAME, OrderDirection::Asc)
+            .fetch_one()
+            .limit_per_group(User::AGE, 2);
+        let sql = select_stmt_to_sql(&stmt).to_inline_sql();
+
+        assert!(sql.starts_with(
+            r#"SELECT "name" FROM "users" WHERE "users"."age" > 18 AND "users"."rowid" IN (SELECT "rowid" FROM (SELECT "users"."rowid", ROW_NUMBER() OVER"#
+        ));
+        assert!(sql.contains(r#"PARTITION BY "users"."age" ORDER BY "users"."name" ASC"#));
+        assert!(sql.contains(r#"FROM "users" WHERE "users"."age" > 18) AS "ranked""#));
+        assert!(
+            sql.ends_with(
+                r#"WHERE "_notitia_group_rank" <= 2) ORDER BY "users"."name" ASC LIMIT 2"#
+            )
         );
     }
 }
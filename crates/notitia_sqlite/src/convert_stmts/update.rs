@@ -1,21 +1,35 @@
 use notitia_core::{FieldExpr, FieldFilter};
-use sea_query::{Alias, Expr, Query, SimpleExpr, SqliteQueryBuilder};
+use sea_query::{Alias, Expr, Func, Query, SimpleExpr, SqliteQueryBuilder, UpdateStatement};
 
 use super::select::{datatype_to_sea_value, filter_to_expr};
+use crate::SqlStatement;
 
-fn field_expr_to_sea_expr(expr: &FieldExpr) -> SimpleExpr {
+/// With a `table`, field references are qualified by it.
+pub(crate) fn field_expr_to_sea_expr(expr: &FieldExpr, table: Option<&str>) -> SimpleExpr {
     match expr {
         FieldExpr::Literal(val) => Expr::val(datatype_to_sea_value(val)).into(),
-        FieldExpr::Field(name) => Expr::col(Alias::new(*name)).into(),
+        FieldExpr::Field(name) => match table {
+            Some(table) => Expr::col((Alias::new(table), Alias::new(*name))).into(),
+            None => Expr::col(Alias::new(*name)).into(),
+        },
         FieldExpr::Concat(left, right) => {
-            let l = field_expr_to_sea_expr(left);
-            let r = field_expr_to_sea_expr(right);
-            SimpleExpr::Binary(
-                Box::new(l),
-                sea_query::BinOper::Custom("||"),
-                Box::new(r),
-            )
+            let l = field_expr_to_sea_expr(left, table);
+            let r = field_expr_to_sea_expr(right, table);
+            SimpleExpr::Binary(Box::new(l), sea_query::BinOper::Custom("||"), Box::new(r))
         }
+        FieldExpr::Add(left, right) => SimpleExpr::Binary(
+            Box::new(field_expr_to_sea_expr(left, table)),
+            sea_query::BinOper::Add,
+            Box::new(field_expr_to_sea_expr(right, table)),
+        ),
+        FieldExpr::Sub(left, right) => SimpleExpr::Binary(
+            Box::new(field_expr_to_sea_expr(left, table)),
+            sea_query::BinOper::Sub,
+            Box::new(field_expr_to_sea_expr(right, table)),
+        ),
+        FieldExpr::Call(function, args) => Func::cust(Alias::new(function.name()))
+            .args(args.iter().map(|arg| field_expr_to_sea_expr(arg, table)))
+            .into(),
     }
 }
 
@@ -23,26 +37,49 @@ pub fn update_stmt_to_sql(
     table_name: &str,
     fields: &[(&str, FieldExpr)],
     filters: &[FieldFilter],
-) -> String {
+) -> SqlStatement {
+    update_query(table_name, fields, filters)
+        .build(SqliteQueryBuilder)
+        .into()
+}
+
+/// Like [`update_stmt_to_sql`], returning the `returning` columns of every
+/// updated row.
+pub fn update_returning_stmt_to_sql(
+    table_name: &str,
+    fields: &[(&str, FieldExpr)],
+    filters: &[FieldFilter],
+    returning: &[&str],
+) -> SqlStatement {
+    let mut query = update_query(table_name, fields, filters);
+    query.returning(Query::returning().columns(returning.iter().map(|name| Alias::new(*name))));
+    query.build(SqliteQueryBuilder).into()
+}
+
+fn update_query(
+    table_name: &str,
+    fields: &[(&str, FieldExpr)],
+    filters: &[FieldFilter],
+) -> UpdateStatement {
     let mut query = Query::update();
 
     query.table(Alias::new(table_name));
 
     for (name, expr) in fields {
-        query.value(Alias::new(*name), field_expr_to_sea_expr(expr));
+        query.value(Alias::new(*name), field_expr_to_sea_expr(expr, None));
     }
 
     for filter in filters {
         query.and_where(filter_to_expr(filter));
     }
 
-    query.to_string(SqliteQueryBuilder)
+    query
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use notitia_core::{PartialRecord, Table};
+    use notitia_core::{Datatype, PartialRecord, SqlFunction, Table};
     use notitia_macros::{database, record};
 
     #[derive(Debug)]
@@ -66,7 +103,7 @@ mod tests {
         let stmt = TestDb::USERS.update(user);
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &[]);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &[]).to_inline_sql();
 
         assert_eq!(
             sql,
@@ -80,7 +117,7 @@ mod tests {
         let stmt = TestDb::USERS.update(partial).filter(User::ID.eq("abc"));
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
@@ -94,7 +131,7 @@ mod tests {
         let stmt = TestDb::USERS.update(user).filter(User::ID.eq("abc"));
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
@@ -111,7 +148,7 @@ mod tests {
             .filter(User::AGE.gt(18i64));
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
@@ -125,7 +162,7 @@ mod tests {
         let stmt = TestDb::USERS.update(partial).filter(User::ID.eq("abc"));
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
@@ -133,17 +170,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_with_arithmetic_expression() {
+        let partial = User::build().age(User::AGE.plus(1i64));
+        let stmt = TestDb::USERS.update(partial).filter(User::ID.eq("abc"));
+
+        let fields = stmt.partial.into_set_fields();
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"UPDATE "users" SET "age" = "age" + 1 WHERE "users"."id" = 'abc'"#
+        );
+    }
+
     #[test]
     fn update_with_field_reference() {
         let partial = User::build().name(User::ID);
         let stmt = TestDb::USERS.update(partial).filter(User::ID.eq("abc"));
 
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
 
         assert_eq!(
             sql,
             r#"UPDATE "users" SET "name" = "id" WHERE "users"."id" = 'abc'"#
         );
     }
+
+    #[test]
+    fn update_with_function_call() {
+        let normalize = SqlFunction::new("normalize", 1, |args| args[0].clone());
+        let partial = User::build().name(User::NAME.apply(&normalize));
+        let stmt = TestDb::USERS.update(partial).filter(User::ID.eq("abc"));
+
+        let fields = stmt.partial.into_set_fields();
+        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters).to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"UPDATE "users" SET "name" = normalize("name") WHERE "users"."id" = 'abc'"#
+        );
+    }
+
+    #[test]
+    fn update_returning_the_updated_rows() {
+        let partial = User::build().age(User::AGE.plus(1i64));
+        let stmt = TestDb::USERS.update(partial).filter(User::NAME.eq("Bob"));
+
+        let fields = stmt.partial.into_set_fields();
+        let sql =
+            update_returning_stmt_to_sql(stmt.table_name, &fields, &stmt.filters, &["id", "age"])
+                .to_inline_sql();
+
+        assert_eq!(
+            sql,
+            r#"UPDATE "users" SET "age" = "age" + 1 WHERE "users"."name" = 'Bob' RETURNING "id", "age""#
+        );
+    }
 }
@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use notitia_core::{
+    DatatypeConversionError, DecodeRowsError, FetchError, MutationRejected, NonFiniteFloat,
+    UnexpectedNull, UniqueViolation, Unsupported,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SqliteError {
+    #[error(transparent)]
+    Sqlx(sqlx::Error),
+    /// A write clashed with an existing row on its primary key or unique
+    /// columns.
+    #[error(transparent)]
+    UniqueViolation(UniqueViolation),
+    /// A write was refused under
+    /// [`FloatPolicy::RejectNonFinite`](notitia_core::FloatPolicy::RejectNonFinite).
+    #[error(transparent)]
+    NonFiniteFloat(#[from] NonFiniteFloat),
+    /// A [`BeforeMutationHook`](notitia_core::BeforeMutationHook) refused
+    /// the write.
+    #[error(transparent)]
+    MutationRejected(#[from] MutationRejected),
+    /// The statement needs something missing from
+    /// [`SqliteAdapter::capabilities`](notitia_core::Adapter::capabilities).
+    #[error(transparent)]
+    Unsupported(#[from] Unsupported),
+    /// The query ran but its rows did not fit the fetch mode.
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    /// A selected field that isn't an `Option` read a NULL.
+    #[error(transparent)]
+    UnexpectedNull(#[from] UnexpectedNull),
+    /// A returned column could not be decoded into the selected field type.
+    #[error("failed to decode row: {0}")]
+    Decode(#[from] DatatypeConversionError),
+    /// The queued write was dropped together with the task flushing it, so
+    /// whether it was committed is unknown.
+    #[error("write was abandoned before its outcome was known")]
+    WriteAbandoned,
+    /// The database file was deleted while the database was open.
+    #[error("database file {} no longer exists", .0.display())]
+    FileMissing(PathBuf),
+    /// SQL functions were registered on an adapter whose pools weren't made
+    /// by [`Adapter::open`](notitia_core::Adapter::open).
+    #[error("SQL functions can only be registered on adapters made by `open`")]
+    UnmanagedPool,
+    /// SQLite's incremental blob API failed, e.g. because the column isn't
+    /// a blob.
+    #[error("{0}")]
+    Blob(String),
+}
+
+impl SqliteError {
+    /// The [`FetchError`] behind this error, if that is what it is.
+    pub fn as_fetch_error(&self) -> Option<FetchError> {
+        match self {
+            Self::Fetch(err) => Some(*err),
+            _ => None,
+        }
+    }
+
+    /// The [`UniqueViolation`] behind this error, if that is what it is.
+    pub fn as_unique_violation(&self) -> Option<&UniqueViolation> {
+        match self {
+            Self::UniqueViolation(violation) => Some(violation),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for SqliteError {
+    fn from(err: sqlx::Error) -> Self {
+        let violation = err
+            .as_database_error()
+            .filter(|err| err.is_unique_violation())
+            .and_then(|err| unique_violation(err.message()));
+        match violation {
+            Some(violation) => Self::UniqueViolation(violation),
+            None => Self::Sqlx(err),
+        }
+    }
+}
+
+impl From<DecodeRowsError> for SqliteError {
+    fn from(err: DecodeRowsError) -> Self {
+        match err {
+            DecodeRowsError::Decode(err) => Self::Decode(err),
+            DecodeRowsError::UnexpectedNull(null) => Self::UnexpectedNull(null),
+            DecodeRowsError::Fetch(err) => Self::Fetch(err),
+        }
+    }
+}
+
+/// Parse SQLite's `UNIQUE constraint failed: reactions.message_id,
+/// reactions.user_id`.
+fn unique_violation(message: &str) -> Option<UniqueViolation> {
+    let columns = message.strip_prefix("UNIQUE constraint failed: ")?;
+
+    let mut table = None;
+    let mut names = Vec::new();
+    for column in columns.split(", ") {
+        let (column_table, name) = column.split_once('.')?;
+        table = Some(column_table.to_owned());
+        names.push(name.to_owned());
+    }
+
+    Some(UniqueViolation {
+        table: table?,
+        columns: names,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unique_violations() {
+        assert_eq!(
+            unique_violation("UNIQUE constraint failed: reactions.message_id, reactions.user_id"),
+            Some(UniqueViolation {
+                table: "reactions".to_owned(),
+                columns: vec!["message_id".to_owned(), "user_id".to_owned()],
+            })
+        );
+        assert_eq!(
+            unique_violation("NOT NULL constraint failed: users.name"),
+            None
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    ffi::{CString, c_char, c_int, c_void},
+    panic::{AssertUnwindSafe, catch_unwind},
+    ptr::NonNull,
+    sync::{Mutex, RwLock},
+};
+
+use libsqlite3_sys::{
+    SQLITE_BLOB, SQLITE_DETERMINISTIC, SQLITE_FLOAT, SQLITE_INTEGER, SQLITE_OK, SQLITE_TEXT,
+    SQLITE_TRANSIENT, SQLITE_UTF8, sqlite3, sqlite3_context, sqlite3_create_function_v2,
+    sqlite3_result_blob64, sqlite3_result_double, sqlite3_result_error, sqlite3_result_int,
+    sqlite3_result_int64, sqlite3_result_null, sqlite3_result_text64, sqlite3_user_data,
+    sqlite3_value, sqlite3_value_blob, sqlite3_value_bytes, sqlite3_value_double,
+    sqlite3_value_int64, sqlite3_value_text, sqlite3_value_type,
+};
+use notitia_core::{Datatype, SqlFunction};
+use sqlx::SqliteConnection;
+
+use crate::storage::bool_to_storage;
+
+/// The functions registered with an adapter, and how many of them each
+/// connection already has.
+///
+/// Pools call [`install`](Self::install) when they open a connection and
+/// whenever they hand out an idle one, so a function registered later
+/// reaches every connection before its next query.
+#[derive(Default)]
+pub(crate) struct SqlFunctions {
+    functions: RwLock<Vec<SqlFunction>>,
+    /// Keyed by the connection's `sqlite3` handle. A handle of a closed
+    /// connection may be reused by a new one, which is why new connections
+    /// are installed from scratch.
+    installed: Mutex<HashMap<usize, usize>>,
+}
+
+impl SqlFunctions {
+    pub(crate) fn push(&self, function: SqlFunction) -> Result<(), sqlx::Error> {
+        if CString::new(function.name()).is_err() {
+            return Err(sqlx::Error::Protocol(format!(
+                "SQL function name {:?} contains a nul byte",
+                function.name()
+            )));
+        }
+
+        let mut functions = self.functions.write().unwrap();
+        // Registering a function again replaces it, like in SQLite.
+        functions.retain(|existing| existing != &function);
+        functions.push(function);
+        // Replacing shifts the list, so reinstall everywhere.
+        self.installed.lock().unwrap().clear();
+        Ok(())
+    }
+
+    /// Create the functions `conn` doesn't have yet. `new` is whether the
+    /// connection was just opened.
+    pub(crate) async fn install(
+        &self,
+        conn: &mut SqliteConnection,
+        new: bool,
+    ) -> Result<(), sqlx::Error> {
+        let mut handle = conn.lock_handle().await?;
+        let db = handle.as_raw_handle();
+
+        let functions = self.functions.read().unwrap();
+        let mut installed = self.installed.lock().unwrap();
+        let key = db.as_ptr() as usize;
+        let from = if new {
+            0
+        } else {
+            installed.get(&key).copied().unwrap_or(0)
+        };
+
+        for function in &functions[from.min(functions.len())..] {
+            // SAFETY: `db` stays valid while the handle is locked.
+            unsafe { create_function(db, function.clone())? };
+        }
+        installed.insert(key, functions.len());
+        Ok(())
+    }
+}
+
+unsafe fn create_function(db: NonNull<sqlite3>, function: SqlFunction) -> Result<(), sqlx::Error> {
+    let name = CString::new(function.name()).expect("checked when registered");
+    let arity = function.arity() as c_int;
+    let app = Box::into_raw(Box::new(function)).cast::<c_void>();
+
+    // SQLite owns `app` from here on and calls `destroy_function` when the
+    // function is replaced, the connection closes or registering fails.
+    let rc = unsafe {
+        sqlite3_create_function_v2(
+            db.as_ptr(),
+            name.as_ptr(),
+            arity,
+            SQLITE_UTF8 | SQLITE_DETERMINISTIC,
+            app,
+            Some(call_function),
+            None,
+            None,
+            Some(destroy_function),
+        )
+    };
+
+    if rc != SQLITE_OK {
+        return Err(sqlx::Error::Protocol(format!(
+            "failed to register SQL function {name:?} (error code {rc})"
+        )));
+    }
+    Ok(())
+}
+
+unsafe extern "C" fn destroy_function(app: *mut c_void) {
+    drop(unsafe { Box::from_raw(app.cast::<SqlFunction>()) });
+}
+
+unsafe extern "C" fn call_function(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    let function = unsafe { &*sqlite3_user_data(ctx).cast::<SqlFunction>() };
+    let args: Vec<Datatype> = (0..argc as usize)
+        .map(|idx| unsafe { value_to_datatype(*argv.add(idx)) })
+        .collect();
+
+    // Unwinding into SQLite is undefined behavior.
+    match catch_unwind(AssertUnwindSafe(|| function.call(&args))) {
+        Ok(result) => unsafe { set_result(ctx, result) },
+        Err(_) => {
+            let message = c"SQL function panicked";
+            unsafe { sqlite3_result_error(ctx, message.as_ptr(), -1) };
+        }
+    }
+}
+
+unsafe fn value_to_datatype(value: *mut sqlite3_value) -> Datatype {
+    unsafe {
+        match sqlite3_value_type(value) {
+            SQLITE_INTEGER => Datatype::BigInt(sqlite3_value_int64(value)),
+            SQLITE_FLOAT => Datatype::Double(sqlite3_value_double(value)),
+            SQLITE_TEXT => {
+                let text = sqlite3_value_text(value);
+                let len = sqlite3_value_bytes(value) as usize;
+                if text.is_null() {
+                    return Datatype::Text(String::new());
+                }
+                let bytes = std::slice::from_raw_parts(text, len);
+                Datatype::Text(String::from_utf8_lossy(bytes).into_owned())
+            }
+            SQLITE_BLOB => {
+                let blob = sqlite3_value_blob(value).cast::<u8>();
+                let len = sqlite3_value_bytes(value) as usize;
+                // Empty blobs come back as a null pointer.
+                if blob.is_null() {
+                    return Datatype::Blob(Vec::new());
+                }
+                Datatype::Blob(std::slice::from_raw_parts(blob, len).to_vec())
+            }
+            _ => Datatype::Null,
+        }
+    }
+}
+
+unsafe fn set_result(ctx: *mut sqlite3_context, result: Datatype) {
+    unsafe {
+        match result {
+            Datatype::Int(v) => sqlite3_result_int(ctx, v),
+            Datatype::BigInt(v) => sqlite3_result_int64(ctx, v),
+            Datatype::Float(v) => sqlite3_result_double(ctx, v as f64),
+            Datatype::Double(v) => sqlite3_result_double(ctx, v),
+            Datatype::Text(v) => sqlite3_result_text64(
+                ctx,
+                v.as_ptr().cast::<c_char>(),
+                v.len() as u64,
+                SQLITE_TRANSIENT(),
+                SQLITE_UTF8 as u8,
+            ),
+            Datatype::Blob(v) => sqlite3_result_blob64(
+                ctx,
+                v.as_ptr().cast::<c_void>(),
+                v.len() as u64,
+                SQLITE_TRANSIENT(),
+            ),
+            Datatype::Bool(v) => sqlite3_result_int64(ctx, bool_to_storage(v)),
+            Datatype::Array(items) => {
+                set_result(ctx, Datatype::Text(Datatype::array_to_json(&items)))
+            }
+            Datatype::Null => sqlite3_result_null(ctx),
+        }
+    }
+}
@@ -1,55 +1,197 @@
 #[cfg(test)]
 extern crate notitia_core as notitia;
 
+mod blob;
+pub use blob::{SqliteBlobReader, SqliteBlobWriter};
+
 mod convert_stmts;
 pub use convert_stmts::*;
 
-use std::{path::Path, sync::Arc};
+mod error;
+pub use error::*;
+
+mod functions;
+use functions::SqlFunctions;
+
+mod outbox;
+use outbox::{create_outbox_table_sql, mark_dispatched_sql, pending_outbox_sql, with_outbox};
+
+mod schema_lock;
+use schema_lock::SchemaLock;
+
+mod snapshot;
+pub use snapshot::SqliteSnapshot;
+
+mod statement;
+pub use statement::SqlStatement;
+
+mod storage;
+use storage::{SelectRow, sqlite_row_column_to_datatype_as, sqlite_rows_to_datatypes};
+
+mod write_queue;
+use write_queue::WriteQueue;
+pub use write_queue::WriteQueueOptions;
 
+use std::{
+    path::Path,
+    pin::pin,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use futures_util::{
+    StreamExt,
+    future::{Either, select},
+};
 use notitia_core::{
-    Adapter, Database, Datatype, DeleteStmtBuilt, FieldKindGroup, InsertStmtBuilt, Notitia,
-    OrderKey, PartialRecord, Record, SelectStmtBuilt, SelectStmtFetchMode, UpdateStmtBuilt,
+    Adapter, AdapterCapabilities, ArchiveStmt, BlobLocation, BulkInsertStmtBuilt, CascadeWalk,
+    ColumnKinds, Database, Datatype, DeleteByKeysStmtBuilt, DeleteStmtBuilt, FieldExpr,
+    FieldFilter, FieldFilterMetadata, FieldKindGroup, InsertFromSelectStmtBuilt, InsertStmtBuilt,
+    MutationEvent, Notitia, OutboxEvent, OutboxMessage, PartialRecord, Record, SelectRowDecoder,
+    SelectStmtBuilt, SelectStmtDef, SelectStmtFetchMode, SqlFunction, TableFieldPair, Unsupported,
+    UpdateStmtBuilt, decode_rows_with_order_keys,
+};
+use sea_query::{Alias, ColumnDef, OnConflict, Query};
+use sqlx::{
+    Pool, Row, Sqlite,
+    sqlite::{SqlitePoolOptions, SqliteRow},
 };
-use smallvec::SmallVec;
-use sqlx::{Column, Pool, Row, Sqlite, TypeInfo, sqlite::SqlitePoolOptions};
 use unions::IsUnion;
 
-fn sqlite_row_column_to_datatype(row: &sqlx::sqlite::SqliteRow, index: usize) -> Datatype {
-    let col = &row.columns()[index];
-    let type_name = col.type_info().name();
+/// Rows per multi-row `INSERT` in a bulk load. SQLite caps the number of
+/// rows in a single `VALUES` list at 500 by default.
+const BULK_INSERT_CHUNK_ROWS: usize = 500;
+
+/// SQLite's default limit on the values bound to one statement.
+const MAX_BOUND_VALUES: usize = 32766;
+
+/// Keys per `IN (...)` list in `delete_by_keys`, which keeps each statement
+/// well within SQLite's limits on statement length.
+const DELETE_KEYS_CHUNK: usize = 900;
+
+/// Bookkeeping table recording which versioned fixtures have been applied.
+/// It is not part of the schema, so migrations and snapshots ignore it.
+const FIXTURES_TABLE: &str = "_notitia_fixtures";
+
+fn create_fixtures_table_sql() -> SqlStatement {
+    sea_query::Table::create()
+        .if_not_exists()
+        .table(Alias::new(FIXTURES_TABLE))
+        .col(
+            ColumnDef::new(Alias::new("name"))
+                .text()
+                .not_null()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Alias::new("version"))
+                .big_integer()
+                .not_null(),
+        )
+        .build(sea_query::SqliteQueryBuilder)
+        .into()
+}
 
-    match type_name {
-        "TEXT" => {
-            let v: String = row.get(index);
-            Datatype::Text(v)
-        }
-        "INTEGER" | "INT" | "BIGINT" => {
-            let v: i64 = row.get(index);
-            Datatype::BigInt(v)
-        }
-        "REAL" | "FLOAT" | "DOUBLE" => {
-            let v: f64 = row.get(index);
-            Datatype::Double(v)
-        }
-        "BLOB" => {
-            let v: Vec<u8> = row.get(index);
-            Datatype::Blob(v)
-        }
-        "BOOLEAN" => {
-            let v: bool = row.get(index);
-            Datatype::Bool(v)
-        }
-        "NULL" => Datatype::Null,
-        _ => {
-            // Fall back: try text, then blob
-            if let Ok(v) = row.try_get::<String, _>(index) {
-                Datatype::Text(v)
-            } else if let Ok(v) = row.try_get::<Vec<u8>, _>(index) {
-                Datatype::Blob(v)
-            } else {
-                Datatype::Null
+fn set_fixture_version_sql(name: &str, version: u32) -> SqlStatement {
+    Query::insert()
+        .into_table(Alias::new(FIXTURES_TABLE))
+        .columns([Alias::new("name"), Alias::new("version")])
+        .values_panic([name.into(), i64::from(version).into()])
+        .on_conflict(
+            OnConflict::column(Alias::new("name"))
+                .update_column(Alias::new("version"))
+                .to_owned(),
+        )
+        .build(sea_query::SqliteQueryBuilder)
+        .into()
+}
+
+/// Split `rows` into multi-row inserts that stay within SQLite's limits on
+/// rows and bound values per statement.
+fn chunked_inserts(
+    table_name: &str,
+    rows: &[Vec<(&str, Datatype)>],
+    ignore_conflicts: bool,
+) -> Vec<SqlStatement> {
+    let columns = rows.first().map_or(1, |row| row.len().max(1));
+    let chunk_rows = (MAX_BOUND_VALUES / columns).clamp(1, BULK_INSERT_CHUNK_ROWS);
+    rows.chunks(chunk_rows)
+        .map(|chunk| insert_many_stmt_to_sql(table_name, chunk, ignore_conflicts))
+        .collect()
+}
+
+/// Whether `url` opens an in-memory database.
+fn is_in_memory(url: &str) -> bool {
+    url.contains(":memory:") || url.contains("mode=memory")
+}
+
+/// The file behind a `sqlite:` URL, or `None` for an in-memory database.
+fn database_file(url: &str) -> Option<&Path> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+
+    if is_in_memory(url) {
+        return None;
+    }
+
+    Some(Path::new(path.split('?').next().unwrap_or(path)))
+}
+
+fn create_local_file(url: &str) -> std::io::Result<()> {
+    if let Some(path) = database_file(url) {
+        // Create parent directories if needed.
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
             }
         }
+
+        if !path.exists() {
+            std::fs::File::create(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pool options that install `functions` on every connection they hand out.
+fn pool_options(functions: &Arc<SqlFunctions>) -> SqlitePoolOptions {
+    let on_connect = functions.clone();
+    let on_acquire = functions.clone();
+
+    SqlitePoolOptions::new()
+        .after_connect(move |conn, _| {
+            let functions = on_connect.clone();
+            Box::pin(async move { functions.install(conn, true).await })
+        })
+        .before_acquire(move |conn, _| {
+            let functions = on_acquire.clone();
+            Box::pin(async move { functions.install(conn, false).await.map(|()| true) })
+        })
+}
+
+/// The read pool and the single-connection writer pool for `url`.
+async fn connect(
+    url: &str,
+    functions: &Arc<SqlFunctions>,
+) -> Result<(Pool<Sqlite>, Pool<Sqlite>), sqlx::Error> {
+    if is_in_memory(url) {
+        // Every connection to an in-memory database gets its own copy, so
+        // keep exactly one open and let it double as the writer.
+        let connection = pool_options(functions)
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect(url)
+            .await?;
+        Ok((connection.clone(), connection))
+    } else {
+        let connection = pool_options(functions).connect(url).await?;
+        let writer = pool_options(functions)
+            .max_connections(1)
+            .connect(url)
+            .await?;
+        Ok((connection, writer))
     }
 }
 
@@ -57,40 +199,232 @@ pub struct SqliteAdapter
 where
     Self: Send + Sync,
 {
-    connection: Arc<Pool<Sqlite>>,
+    connection: RwLock<Arc<Pool<Sqlite>>>,
+    write_queue: Option<WriteQueue>,
+    /// Set by [`Adapter::open`]; needed to check for and reconnect to the file.
+    url: Option<String>,
+    /// Installed on every connection of the pools [`Adapter::open`] made.
+    functions: Arc<SqlFunctions>,
+    /// Set by [`Adapter::initialize`]; rows decode by their storage class
+    /// until then.
+    column_kinds: OnceLock<ColumnKinds>,
+}
+
+impl SqliteAdapter {
+    /// Like [`Adapter::new`], but every insert, update and delete is funneled
+    /// through `writer`, which must be a pool with a single connection to the
+    /// same database. Concurrent writes are then queued (and committed in
+    /// batches) instead of racing for SQLite's write lock.
+    ///
+    /// [`Adapter::open`] sets this up automatically.
+    pub fn with_write_queue(
+        connection: Arc<Pool<Sqlite>>,
+        writer: Pool<Sqlite>,
+        options: WriteQueueOptions,
+    ) -> Self {
+        Self {
+            connection: RwLock::new(connection),
+            write_queue: Some(WriteQueue::new(writer, options)),
+            url: None,
+            functions: Arc::default(),
+            column_kinds: OnceLock::new(),
+        }
+    }
+
+    fn connection(&self) -> Arc<Pool<Sqlite>> {
+        self.connection.read().unwrap().clone()
+    }
+
+    /// Decode a row holding `columns` of `table`, in order.
+    fn decode_columns(
+        &self,
+        row: &SqliteRow,
+        table: &'static str,
+        columns: &[&'static str],
+    ) -> Vec<(&'static str, Datatype)> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let kind = self
+                    .column_kinds
+                    .get()
+                    .and_then(|kinds| kinds.get(Some(table), &[], *name));
+                (*name, sqlite_row_column_to_datatype_as(row, idx, kind))
+            })
+            .collect()
+    }
+
+    async fn execute_write_transaction(
+        &self,
+        statements: &[SqlStatement],
+    ) -> Result<(), SqliteError> {
+        match &self.write_queue {
+            Some(queue) => queue.execute_transaction(statements).await,
+            None => {
+                let mut conn = self.connection().acquire().await?;
+                write_queue::execute_transaction(&mut conn, statements).await
+            }
+        }
+    }
+
+    async fn fetch_write_transaction(
+        &self,
+        statements: &[SqlStatement],
+    ) -> Result<Vec<SqliteRow>, SqliteError> {
+        match &self.write_queue {
+            Some(queue) => queue.fetch_transaction(statements).await,
+            None => {
+                let mut conn = self.connection().acquire().await?;
+                write_queue::fetch_transaction(&mut conn, statements).await
+            }
+        }
+    }
+
+    /// Like [`fetch_write_transaction`](Self::fetch_write_transaction),
+    /// first reading the lookups of `cascade` in the transaction, and
+    /// returning its deletes along with the rows of `statements`.
+    async fn fetch_write_transaction_cascading(
+        &self,
+        cascade: CascadeWalk,
+        statements: &[SqlStatement],
+    ) -> Result<(Vec<SqliteRow>, Vec<MutationEvent>), SqliteError> {
+        if cascade.is_done() {
+            let rows = self.fetch_write_transaction(statements).await?;
+            return Ok((rows, Vec::new()));
+        }
+        let mut conn = match &self.write_queue {
+            Some(queue) => queue.acquire().await?,
+            None => self.connection().acquire().await?,
+        };
+        write_queue::fetch_transaction_cascading(&mut conn, cascade, statements).await
+    }
+
+    async fn fetch_write(&self, statement: &SqlStatement) -> Result<Vec<SqliteRow>, SqliteError> {
+        match &self.write_queue {
+            Some(queue) => queue.fetch_all(statement).await,
+            None => Ok(statement
+                .query()
+                .fetch_all(self.connection().as_ref())
+                .await?),
+        }
+    }
+
+    async fn execute_write(&self, statement: SqlStatement) -> Result<(), SqliteError> {
+        match &self.write_queue {
+            Some(queue) => queue.execute(statement).await,
+            None => {
+                statement
+                    .query()
+                    .execute(self.connection().as_ref())
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Like [`execute_write`](Self::execute_write), committing `outbox` in
+    /// the same transaction.
+    async fn execute_write_with_outbox(
+        &self,
+        statement: SqlStatement,
+        outbox: &[OutboxMessage],
+    ) -> Result<(), SqliteError> {
+        if outbox.is_empty() {
+            self.execute_write(statement).await
+        } else {
+            self.execute_write_transaction(&with_outbox(vec![statement], outbox))
+                .await
+        }
+    }
+
+    /// Like [`fetch_write`](Self::fetch_write), committing `outbox` in the
+    /// same transaction.
+    async fn fetch_write_with_outbox(
+        &self,
+        statement: SqlStatement,
+        outbox: &[OutboxMessage],
+    ) -> Result<Vec<SqliteRow>, SqliteError> {
+        if outbox.is_empty() {
+            self.fetch_write(&statement).await
+        } else {
+            self.fetch_write_transaction(&with_outbox(vec![statement], outbox))
+                .await
+        }
+    }
 }
 
 impl Adapter for SqliteAdapter {
     type QueryBuilder = sea_query::SqliteQueryBuilder;
     type Connection = Arc<Pool<Sqlite>>;
-    type Error = sqlx::Error;
+    type Error = SqliteError;
+    type BlobReader = SqliteBlobReader;
+    type BlobWriter = SqliteBlobWriter;
+    type Snapshot = SqliteSnapshot;
 
     fn new(connection: Self::Connection) -> Self {
-        Self { connection }
+        Self {
+            connection: RwLock::new(connection),
+            write_queue: None,
+            url: None,
+            functions: Arc::default(),
+            column_kinds: OnceLock::new(),
+        }
+    }
+
+    /// Arrays are stored as JSON text. Other processes' writes go unnoticed.
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            listen_notify: false,
+            ..AdapterCapabilities::ALL
+        }
     }
 
+    /// Creates the tables while holding the write lock, see [`SchemaLock`],
+    /// along with the fixtures and outbox tables, so reads and writes of
+    /// them needn't check for them.
     async fn initialize<Db: Database>(&self, database: &Db) {
-        let mut schema_sql = database.schema_sql(Self::QueryBuilder::default());
+        let schema_sql = database.schema_sql(Self::QueryBuilder::default());
 
-        if Db::_FOREIGN_RELATIONSHIPS.len() != 0 {
-            schema_sql = format!("PRAGMA foreign_keys = ON;\n\n{}", schema_sql);
+        let mut conn = self.connection().acquire().await.unwrap();
+
+        if database
+            .tables()
+            .any(|(table_name, _)| database.foreign_relationships(table_name).is_some())
+        {
+            // Has no effect inside a transaction.
+            sqlx::query("PRAGMA foreign_keys = ON")
+                .execute(&mut *conn)
+                .await
+                .unwrap();
         };
 
-        sqlx::query(&schema_sql)
-            .execute(self.connection.as_ref())
-            .await
-            .unwrap();
+        let mut lock = SchemaLock::acquire(&mut conn).await.unwrap();
+        let mut result = sqlx::query(&schema_sql).execute(lock.conn()).await;
+        for table in [create_fixtures_table_sql(), create_outbox_table_sql()] {
+            if result.is_ok() {
+                result = table.query().execute(lock.conn()).await;
+            }
+        }
+        lock.finish(result).await.unwrap();
+
+        let _ = self.column_kinds.set(ColumnKinds::of(database));
     }
 
+    /// Reads the existing columns and adds the missing ones under one write
+    /// lock, so concurrent processes don't both try to add them.
     async fn migrate<Db: Database>(&self, database: &Db) {
         let table_names: Vec<&'static str> = database.tables().map(|(name, _)| name).collect();
 
+        let mut conn = self.connection().acquire().await.unwrap();
+        let mut lock = SchemaLock::acquire(&mut conn).await.unwrap();
+
         let mut existing_columns = Vec::new();
 
         for table_name in &table_names {
             let sql = format!("PRAGMA table_info(\"{}\")", table_name);
             let rows = sqlx::query(&sql)
-                .fetch_all(self.connection.as_ref())
+                .fetch_all(lock.conn())
                 .await
                 .unwrap_or_default();
 
@@ -107,50 +441,82 @@ impl Adapter for SqliteAdapter {
             &existing_columns,
         );
 
-        if !migration_sql.is_empty() {
-            for stmt in migration_sql.split(";\n") {
-                let stmt = stmt.trim_end_matches(';').trim();
-                if !stmt.is_empty() {
-                    sqlx::query(stmt)
-                        .execute(self.connection.as_ref())
-                        .await
-                        .unwrap();
-                }
+        let mut result = Ok(());
+        for stmt in migration_sql.split(";\n") {
+            let stmt = stmt.trim_end_matches(';').trim();
+            if stmt.is_empty() {
+                continue;
+            }
+            if let Err(err) = sqlx::query(stmt).execute(lock.conn()).await {
+                result = Err(err);
+                break;
             }
         }
+
+        lock.finish(result).await.unwrap();
     }
 
     async fn open<Db: Database>(url: &str) -> Result<Notitia<Db, Self>, Self::Error> {
-        fn create_local_file(url: &str) -> std::io::Result<()> {
-            if let Some(path) = url
-                .strip_prefix("sqlite://")
-                .or_else(|| url.strip_prefix("sqlite:"))
-            {
-                if path != ":memory:" && !path.starts_with(":memory:") {
-                    let path = Path::new(path.split('?').next().unwrap_or(path));
-
-                    // Create parent directories if needed.
-                    if let Some(parent) = path.parent() {
-                        if !parent.as_os_str().is_empty() {
-                            std::fs::create_dir_all(parent)?;
-                        }
-                    }
-
-                    if !path.exists() {
-                        std::fs::File::create(path)?;
-                    }
-                }
-            }
+        // TODO: better error handling via early return with Result::Err.
+        create_local_file(url).unwrap();
 
-            Ok(())
+        let functions = Arc::new(SqlFunctions::default());
+        let (connection, writer) = connect(url, &functions).await?;
+        let mut adapter =
+            Self::with_write_queue(Arc::new(connection), writer, WriteQueueOptions::default());
+        adapter.url = Some(url.to_owned());
+        adapter.functions = functions;
+
+        Ok(Notitia::new(Db::new(), adapter).await)
+    }
+
+    /// Besides reaching the database, this checks that its file still
+    /// exists: connections opened before it was deleted keep working on the
+    /// unlinked file, and everything they write is lost.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        if let Some(path) = self.url.as_deref().and_then(database_file) {
+            if !path.exists() {
+                return Err(SqliteError::FileMissing(path.to_owned()));
+            }
         }
 
-        // TODO: better error handling via early return with Result::Err.
-        create_local_file(url).unwrap();
+        sqlx::query("SELECT 1")
+            .execute(self.connection().as_ref())
+            .await?;
+        Ok(())
+    }
+
+    /// Opens new pools for the URL the adapter was opened with, recreating
+    /// the file if it's gone. Adapters made with [`Adapter::new`] or
+    /// [`SqliteAdapter::with_write_queue`] keep their pools.
+    async fn reconnect(&self) -> Result<(), Self::Error> {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+
+        create_local_file(url).map_err(sqlx::Error::Io)?;
+        let (connection, writer) = connect(url, &self.functions).await?;
+
+        *self.connection.write().unwrap() = Arc::new(connection);
+        if let Some(queue) = &self.write_queue {
+            queue.replace_writer(writer);
+        }
+        Ok(())
+    }
 
-        let connection = SqlitePoolOptions::new().connect(url).await?;
+    /// Only adapters made by [`Adapter::open`] support this, since the
+    /// function has to be installed on each connection as the pool hands it
+    /// out.
+    async fn register_function(&self, function: SqlFunction) -> Result<(), Self::Error> {
+        if self.url.is_none() {
+            return Err(SqliteError::UnmanagedPool);
+        }
 
-        Ok(Notitia::new(Db::new(), Self::new(Arc::new(connection))).await)
+        self.functions.push(function)?;
+        // Install it right away, so a function SQLite rejects fails here
+        // rather than at the next query.
+        self.connection().acquire().await?;
+        Ok(())
     }
 
     async fn execute_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
@@ -164,95 +530,364 @@ impl Adapter for SqliteAdapter {
         Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
         Mode: SelectStmtFetchMode<Fields::Type> + Sync,
     {
-        let sql = select_stmt_to_sql(stmt);
-        let rows = sqlx::query(&sql)
-            .fetch_all(self.connection.as_ref())
+        let statement = select_stmt_to_sql(stmt);
+        let rows = statement
+            .query()
+            .fetch_all(self.connection().as_ref())
             .await?;
+        Ok(decode_rows_with_order_keys(
+            stmt,
+            rows.iter().map(SelectRow),
+            self.column_kinds.get(),
+        )?)
+    }
 
-        let needs_order_keys = stmt.mode.needs_order_keys();
-        let field_names = stmt.fields.field_names();
-        let user_field_count = field_names.len();
-
-        // Build column index mapping for ORDER BY fields (only when needed).
-        let order_key_indices: SmallVec<[usize; 1]> = if needs_order_keys {
-            let mut indices = SmallVec::new();
-            let mut extra_col_idx = user_field_count;
-            for order in &stmt.order_by {
-                if let Some(pos) = field_names.iter().position(|n| *n == order.field) {
-                    indices.push(pos);
-                } else {
-                    indices.push(extra_col_idx);
-                    extra_col_idx += 1;
+    async fn execute_select_stmt_until<Db, FieldUnion, FieldPath, Fields, Mode, Deadline>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+        deadline: Deadline,
+    ) -> Result<(Mode::Output, bool), Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+        Deadline: Future<Output = ()> + Send,
+    {
+        let statement = select_stmt_to_sql(stmt);
+        let mut decoder = SelectRowDecoder::new(stmt, self.column_kinds.get());
+        let mut deadline = pin!(deadline);
+
+        // Rows are decoded as they arrive, so that those read before the
+        // deadline are kept when the rest is dropped unread.
+        let pool = self.connection();
+        let truncated = {
+            let mut rows = statement.query().fetch(pool.as_ref());
+            loop {
+                match select(rows.next(), deadline.as_mut()).await {
+                    Either::Left((Some(row), _)) => decoder.push(&SelectRow(&row?))?,
+                    Either::Left((None, _)) => break false,
+                    Either::Right(((), _)) => break true,
                 }
             }
-            indices
-        } else {
-            SmallVec::new()
         };
+        Ok((decoder.finish()?, truncated))
+    }
 
-        let (typed_rows, order_keys): (Vec<_>, Vec<_>) = rows
-            .into_iter()
-            .map(|row| {
-                let all_values: Vec<Datatype> = (0..row.columns().len())
-                    .map(|i| sqlite_row_column_to_datatype(&row, i))
-                    .collect();
-
-                let order_key = if needs_order_keys {
-                    OrderKey::new(
-                        order_key_indices
-                            .iter()
-                            .map(|&idx| all_values[idx].clone())
-                            .collect(),
-                        stmt.order_by
-                            .iter()
-                            .map(|o| matches!(o.direction, notitia_core::OrderDirection::Desc))
-                            .collect(),
-                    )
-                } else {
-                    OrderKey::default()
-                };
-
-                let user_values: Vec<Datatype> =
-                    all_values.into_iter().take(user_field_count).collect();
-                let typed = Fields::from_datatypes(&mut user_values.into_iter())
-                    .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
-                Ok((typed, order_key))
-            })
-            .collect::<Result<Vec<_>, sqlx::Error>>()?
-            .into_iter()
-            .unzip();
+    /// Fails when the pool has a single connection, as for in-memory
+    /// databases: it would be held by the snapshot, so that every other query
+    /// waited until it was dropped.
+    async fn begin_snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+        let connection = self.connection();
+        if connection.options().get_max_connections() <= 1 {
+            return Err(Unsupported {
+                feature: "a second connection",
+                needed_by: "snapshots of single-connection pools",
+            }
+            .into());
+        }
+        SqliteSnapshot::begin(connection.begin().await?).await
+    }
+
+    async fn execute_snapshot_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        snapshot: &Self::Snapshot,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        let statement = select_stmt_to_sql(stmt);
+        let rows = {
+            let mut transaction = snapshot.transaction.lock().await;
+            statement.query().fetch_all(&mut **transaction).await?
+        };
+        Ok(decode_rows_with_order_keys(
+            stmt,
+            rows.iter().map(SelectRow),
+            self.column_kinds.get(),
+        )?)
+    }
 
-        stmt.mode
-            .from_rows(typed_rows, order_keys)
-            .map_err(|e| sqlx::Error::Protocol(e.to_string()))
+    async fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> Result<Vec<Vec<Datatype>>, Self::Error> {
+        let rows = select_def_to_sql(def)
+            .query()
+            .fetch_all(self.connection().as_ref())
+            .await?;
+        Ok(sqlite_rows_to_datatypes(&rows))
     }
 
     async fn execute_insert_stmt<Db: Database, R: Record + Send>(
         &self,
         stmt: InsertStmtBuilt<Db, R>,
-    ) -> Result<(), Self::Error> {
+    ) -> Result<bool, Self::Error> {
         let fields = stmt.record.into_datatypes();
-        let sql = insert_stmt_to_sql(stmt.table_name, &fields);
-        sqlx::query(&sql).execute(self.connection.as_ref()).await?;
-        Ok(())
+        let statement = insert_stmt_to_sql(stmt.table_name, &fields, stmt.ignore_conflicts);
+
+        if stmt.ignore_conflicts {
+            // RETURNING yields nothing for a skipped row.
+            Ok(!self
+                .fetch_write_with_outbox(statement, &stmt.outbox)
+                .await?
+                .is_empty())
+        } else {
+            self.execute_write_with_outbox(statement, &stmt.outbox)
+                .await?;
+            Ok(true)
+        }
+    }
+
+    async fn execute_bulk_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let rows: Vec<_> = stmt
+            .records
+            .into_iter()
+            .map(|record| record.into_datatypes())
+            .collect();
+        let statements = with_outbox(
+            chunked_inserts(stmt.table_name, &rows, stmt.ignore_conflicts),
+            &stmt.outbox,
+        );
+
+        let columns: Vec<&'static str> = match rows.first() {
+            Some(first) if stmt.ignore_conflicts => first.iter().map(|(name, _)| *name).collect(),
+            _ => {
+                if !statements.is_empty() {
+                    self.execute_write_transaction(&statements).await?;
+                }
+                return Ok(rows);
+            }
+        };
+
+        // RETURNING lists the inserted columns in the order they were given.
+        Ok(self
+            .fetch_write_transaction(&statements)
+            .await?
+            .iter()
+            .map(|row| self.decode_columns(row, stmt.table_name, &columns))
+            .collect())
+    }
+
+    async fn execute_insert_from_select_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let rows = self
+            .fetch_write_with_outbox(insert_from_select_stmt_to_sql(&stmt), &stmt.outbox)
+            .await?;
+
+        // RETURNING lists the destination's columns in declaration order.
+        let columns: Vec<&'static str> = Rec::_FIELDS.iter().map(|(name, _)| *name).collect();
+        Ok(rows
+            .iter()
+            .map(|row| self.decode_columns(row, stmt.table_name, &columns))
+            .collect())
+    }
+
+    async fn execute_archive_stmt(
+        &self,
+        stmt: &ArchiveStmt,
+    ) -> Result<(Vec<Vec<(&'static str, Datatype)>>, Vec<MutationEvent>), Self::Error> {
+        let statements = archive_stmt_to_sql(stmt);
+        let (rows, cascaded) = self
+            .fetch_write_transaction_cascading(stmt.cascade.clone(), &statements)
+            .await?;
+        // Only the copy returns rows, with the archived columns in order.
+        let archived = rows
+            .iter()
+            .map(|row| self.decode_columns(row, stmt.hot_table, &stmt.columns))
+            .collect();
+        Ok((archived, cascaded))
     }
 
     async fn execute_update_stmt<Db: Database, Rec: Record + Send, P: PartialRecord + Send>(
         &self,
         stmt: UpdateStmtBuilt<Db, Rec, P>,
-    ) -> Result<(), Self::Error> {
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
         let fields = stmt.partial.into_set_fields();
-        let sql = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
-        sqlx::query(&sql).execute(self.connection.as_ref()).await?;
-        Ok(())
+        if stmt.returning.is_empty() {
+            let statement = update_stmt_to_sql(stmt.table_name, &fields, &stmt.filters);
+            self.execute_write_with_outbox(statement, &stmt.outbox)
+                .await?;
+            return Ok(Vec::new());
+        }
+        let statement =
+            update_returning_stmt_to_sql(stmt.table_name, &fields, &stmt.filters, &stmt.returning);
+        let rows = self
+            .fetch_write_with_outbox(statement, &stmt.outbox)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| self.decode_columns(row, stmt.table_name, &stmt.returning))
+            .collect())
     }
 
     async fn execute_delete_stmt<Db: Database, Rec: Record + Send>(
         &self,
         stmt: DeleteStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let cascade = stmt.cascade();
+        let statement = delete_stmt_to_sql(stmt.table_name, &stmt.filters);
+        if cascade.is_done() {
+            self.execute_write_with_outbox(statement, &stmt.outbox)
+                .await?;
+            return Ok(Vec::new());
+        }
+        let statements = with_outbox(vec![statement], &stmt.outbox);
+        let (_, cascaded) = self
+            .fetch_write_transaction_cascading(cascade, &statements)
+            .await?;
+        Ok(cascaded)
+    }
+
+    async fn execute_delete_by_keys_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let statements = with_outbox(
+            delete_by_keys_stmts_to_sql(&stmt, DELETE_KEYS_CHUNK),
+            &stmt.outbox,
+        );
+        let cascade = stmt.cascade();
+        if cascade.is_done() {
+            self.execute_write_transaction(&statements).await?;
+            return Ok(Vec::new());
+        }
+        let (_, cascaded) = self
+            .fetch_write_transaction_cascading(cascade, &statements)
+            .await?;
+        Ok(cascaded)
+    }
+
+    async fn is_table_empty(&self, table_name: &'static str) -> Result<bool, Self::Error> {
+        let sql = format!("SELECT EXISTS (SELECT 1 FROM \"{table_name}\")");
+        let has_rows: bool = sqlx::query_scalar(&sql)
+            .fetch_one(self.connection().as_ref())
+            .await?;
+        Ok(!has_rows)
+    }
+
+    async fn fetch_rows(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let rows = select_rows_sql(table_name, columns)
+            .query()
+            .fetch_all(self.connection().as_ref())
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| self.decode_columns(row, table_name, columns))
+            .collect())
+    }
+
+    async fn insert_rows(
+        &self,
+        table_name: &'static str,
+        rows: Vec<Vec<(&'static str, Datatype)>>,
     ) -> Result<(), Self::Error> {
-        let sql = delete_stmt_to_sql(stmt.table_name, &stmt.filters);
-        sqlx::query(&sql).execute(self.connection.as_ref()).await?;
+        self.execute_write_transaction(&chunked_inserts(table_name, &rows, false))
+            .await
+    }
+
+    async fn update_rows(
+        &self,
+        table_name: &'static str,
+        key_column: &'static str,
+        column: &'static str,
+        rows: Vec<(Datatype, Datatype)>,
+    ) -> Result<(), Self::Error> {
+        let statements: Vec<SqlStatement> = rows
+            .into_iter()
+            .map(|(key, value)| {
+                update_stmt_to_sql(
+                    table_name,
+                    &[(column, FieldExpr::Literal(value))],
+                    &[FieldFilter::Eq(FieldFilterMetadata {
+                        left: TableFieldPair::new(table_name, key_column),
+                        right: key,
+                    })],
+                )
+            })
+            .collect();
+        self.execute_write_transaction(&statements).await
+    }
+
+    async fn open_blob_reader(
+        &self,
+        location: &BlobLocation,
+    ) -> Result<Self::BlobReader, Self::Error> {
+        SqliteBlobReader::open(self.connection().acquire().await?, location).await
+    }
+
+    async fn open_blob_writer(
+        &self,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<Self::BlobWriter, Self::Error> {
+        // Writes go through the writer connection, like every other write.
+        let conn = match &self.write_queue {
+            Some(queue) => queue.acquire().await?,
+            None => self.connection().acquire().await?,
+        };
+        SqliteBlobWriter::open(conn, location, len).await
+    }
+
+    async fn vacuum(&self) -> Result<(), Self::Error> {
+        // VACUUM can't run inside a transaction, so it must not be batched
+        // with other queued writes.
+        self.fetch_write(&"VACUUM".into()).await?;
         Ok(())
     }
+
+    async fn fixture_version(&self, name: &'static str) -> Result<Option<u32>, Self::Error> {
+        let sql = format!("SELECT version FROM \"{FIXTURES_TABLE}\" WHERE name = ?");
+        let version: Option<i64> = sqlx::query_scalar(&sql)
+            .bind(name)
+            .fetch_optional(self.connection().as_ref())
+            .await?;
+        Ok(version.map(|version| version as u32))
+    }
+
+    async fn set_fixture_version(
+        &self,
+        name: &'static str,
+        version: u32,
+    ) -> Result<(), Self::Error> {
+        self.execute_write(set_fixture_version_sql(name, version))
+            .await
+    }
+
+    async fn fetch_outbox(&self, limit: usize) -> Result<Vec<OutboxEvent>, Self::Error> {
+        let rows = pending_outbox_sql(limit)
+            .query()
+            .fetch_all(self.connection().as_ref())
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                Ok(OutboxEvent {
+                    id: row.try_get(0)?,
+                    topic: row.try_get(1)?,
+                    payload: row.try_get(2)?,
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()?)
+    }
+
+    async fn mark_outbox_dispatched(&self, ids: Vec<i64>) -> Result<(), Self::Error> {
+        self.execute_write(mark_dispatched_sql(&ids)).await
+    }
 }
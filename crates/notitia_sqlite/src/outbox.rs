@@ -0,0 +1,102 @@
+//! The table integration events wait in until they are dispatched. Each is
+//! inserted in the transaction of the mutation that enqueued it. The table
+//! is created when the adapter is initialized.
+
+use notitia_core::OutboxMessage;
+use sea_query::{Alias, ColumnDef, Expr, Order, Query, SqliteQueryBuilder};
+
+use crate::SqlStatement;
+
+/// Like the fixtures table, it is not part of the schema, so migrations and
+/// snapshots ignore it.
+const OUTBOX_TABLE: &str = "_notitia_outbox";
+
+pub(crate) fn create_outbox_table_sql() -> SqlStatement {
+    sea_query::Table::create()
+        .if_not_exists()
+        .table(Alias::new(OUTBOX_TABLE))
+        .col(
+            ColumnDef::new(Alias::new("id"))
+                .integer()
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Alias::new("topic")).text().not_null())
+        .col(ColumnDef::new(Alias::new("payload")).text().not_null())
+        .col(
+            ColumnDef::new(Alias::new("dispatched"))
+                .boolean()
+                .not_null()
+                .default(0),
+        )
+        .build(SqliteQueryBuilder)
+        .into()
+}
+
+/// `statements` followed by inserting `outbox`, to run in one transaction.
+pub(crate) fn with_outbox(
+    mut statements: Vec<SqlStatement>,
+    outbox: &[OutboxMessage],
+) -> Vec<SqlStatement> {
+    if outbox.is_empty() {
+        return statements;
+    }
+
+    let mut insert = Query::insert();
+    insert
+        .into_table(Alias::new(OUTBOX_TABLE))
+        .columns([Alias::new("topic"), Alias::new("payload")]);
+    for message in outbox {
+        insert.values_panic([
+            message.topic.as_str().into(),
+            message.payload.as_str().into(),
+        ]);
+    }
+
+    statements.push(insert.build(SqliteQueryBuilder).into());
+    statements
+}
+
+pub(crate) fn pending_outbox_sql(limit: usize) -> SqlStatement {
+    Query::select()
+        .columns([Alias::new("id"), Alias::new("topic"), Alias::new("payload")])
+        .from(Alias::new(OUTBOX_TABLE))
+        .and_where(Expr::col(Alias::new("dispatched")).eq(0))
+        .order_by(Alias::new("id"), Order::Asc)
+        .limit(limit as u64)
+        .build(SqliteQueryBuilder)
+        .into()
+}
+
+pub(crate) fn mark_dispatched_sql(ids: &[i64]) -> SqlStatement {
+    Query::update()
+        .table(Alias::new(OUTBOX_TABLE))
+        .value(Alias::new("dispatched"), 1)
+        .and_where(Expr::col(Alias::new("id")).is_in(ids.iter().copied()))
+        .build(SqliteQueryBuilder)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserts_messages_after_the_statements() {
+        let statements = with_outbox(
+            vec![r#"DELETE FROM "users""#.into()],
+            &[OutboxMessage::new("users.cleared", "{}")],
+        );
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].sql, r#"DELETE FROM "users""#);
+        assert_eq!(
+            statements[1].to_inline_sql(),
+            r#"INSERT INTO "_notitia_outbox" ("topic", "payload") VALUES ('users.cleared', '{}')"#
+        );
+        assert_eq!(
+            with_outbox(vec!["SELECT 1".into()], &[]),
+            [SqlStatement::from("SELECT 1")]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use sqlx::SqliteConnection;
+
+/// Holds SQLite's write lock while the schema is created or migrated.
+///
+/// `BEGIN IMMEDIATE` takes the lock when the transaction starts rather than
+/// at its first write. When several processes open the same file at once,
+/// the others wait (up to the busy timeout) until the first one committed,
+/// and only then read the schema, so nobody adds a column that another
+/// process just added.
+pub(crate) struct SchemaLock<'c> {
+    conn: &'c mut SqliteConnection,
+}
+
+impl<'c> SchemaLock<'c> {
+    pub(crate) async fn acquire(conn: &'c mut SqliteConnection) -> Result<Self, sqlx::Error> {
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+        Ok(Self { conn })
+    }
+
+    pub(crate) fn conn(&mut self) -> &mut SqliteConnection {
+        &mut *self.conn
+    }
+
+    /// Commit if `result` is a success and roll back otherwise, releasing
+    /// the lock either way.
+    pub(crate) async fn finish<T>(self, result: Result<T, sqlx::Error>) -> Result<T, sqlx::Error> {
+        let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+        sqlx::query(end).execute(&mut *self.conn).await?;
+        result
+    }
+}
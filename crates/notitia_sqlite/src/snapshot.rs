@@ -0,0 +1,53 @@
+use futures_util::lock::Mutex;
+use sqlx::{Sqlite, Transaction};
+
+use crate::SqliteError;
+
+/// A read transaction on a connection of its own, see
+/// [`Notitia::snapshot`](notitia_core::Notitia::snapshot).
+///
+/// SQLite takes a read transaction's snapshot at its first read, so opening
+/// one reads the schema right away. Dropping it rolls the transaction back
+/// before the connection is used again. In-memory databases have a single
+/// connection, so they can't take snapshots.
+pub struct SqliteSnapshot {
+    pub(crate) transaction: Mutex<Transaction<'static, Sqlite>>,
+}
+
+impl SqliteSnapshot {
+    pub(crate) async fn begin(
+        mut transaction: Transaction<'static, Sqlite>,
+    ) -> Result<Self, SqliteError> {
+        sqlx::query("SELECT count(*) FROM sqlite_master")
+            .execute(&mut *transaction)
+            .await?;
+        Ok(Self {
+            transaction: Mutex::new(transaction),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use notitia_core::Adapter;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::{SqliteAdapter, SqliteError};
+
+    #[tokio::test]
+    async fn single_connection_pools_refuse_snapshots() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let adapter = SqliteAdapter::new(Arc::new(pool));
+
+        assert!(matches!(
+            adapter.begin_snapshot().await,
+            Err(SqliteError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use sea_query::{SqliteQueryBuilder, Value, Values};
+use sqlx::{Sqlite, query::Query, sqlite::SqliteArguments};
+
+/// A statement and the values bound to its `?` placeholders, in order.
+/// Values never end up in the SQL text, so statements that only differ in
+/// them share an entry in sqlx's statement cache.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SqlStatement {
+    pub sql: String,
+    pub values: Vec<Value>,
+}
+
+impl SqlStatement {
+    pub fn new(sql: impl Into<String>, values: Vec<Value>) -> Self {
+        Self {
+            sql: sql.into(),
+            values,
+        }
+    }
+
+    /// The SQL with the values written in place of their placeholders, for
+    /// logs and tests.
+    pub fn to_inline_sql(&self) -> String {
+        sea_query::inject_parameters(&self.sql, self.values.clone(), &SqliteQueryBuilder)
+    }
+
+    /// The statement with its values bound.
+    pub(crate) fn query(&self) -> Query<'_, Sqlite, SqliteArguments<'_>> {
+        self.values
+            .iter()
+            .fold(sqlx::query(&self.sql), |query, value| match value {
+                Value::Bool(v) => query.bind(*v),
+                Value::TinyInt(v) => query.bind(*v),
+                Value::SmallInt(v) => query.bind(*v),
+                Value::Int(v) => query.bind(*v),
+                Value::BigInt(v) => query.bind(*v),
+                Value::TinyUnsigned(v) => query.bind(*v),
+                Value::SmallUnsigned(v) => query.bind(*v),
+                Value::Unsigned(v) => query.bind(*v),
+                // SQLite integers are signed; sea_query only uses these for
+                // limits and offsets.
+                Value::BigUnsigned(v) => query.bind(v.map(|v| v.min(i64::MAX as u64) as i64)),
+                Value::Float(v) => query.bind(*v),
+                Value::Double(v) => query.bind(*v),
+                Value::String(v) => query.bind(v.as_deref().map(String::as_str)),
+                Value::Char(v) => query.bind(v.map(String::from)),
+                Value::Bytes(v) => query.bind(v.as_deref().map(Vec::as_slice)),
+            })
+    }
+}
+
+impl From<(String, Values)> for SqlStatement {
+    fn from((sql, values): (String, Values)) -> Self {
+        Self::new(sql, values.0)
+    }
+}
+
+/// A statement without values, like DDL.
+impl From<String> for SqlStatement {
+    fn from(sql: String) -> Self {
+        Self::new(sql, Vec::new())
+    }
+}
+
+impl From<&str> for SqlStatement {
+    fn from(sql: &str) -> Self {
+        Self::new(sql, Vec::new())
+    }
+}
@@ -0,0 +1,179 @@
+//! How values map onto SQLite's storage classes, in both directions.
+//!
+//! SQLite has no boolean storage class: booleans are written as INTEGER 0
+//! and 1, and only the declared type of their column tells them apart from
+//! other integers when read back. Columns decode by the kind their record
+//! declares where it's known, and by storage class otherwise.
+
+use notitia_core::{Datatype, DatatypeKind, DatatypeRef, RawRow};
+use sqlx::{Column, Row, TypeInfo, ValueRef, sqlite::SqliteRow};
+
+/// The INTEGER a boolean is stored as.
+pub(crate) fn bool_to_storage(v: bool) -> i64 {
+    v as i64
+}
+
+/// Whether a column declared as `declared_type` holds booleans. sea-query
+/// declares them `boolean`, other tools may write `BOOL`.
+fn is_bool_column(declared_type: &str) -> bool {
+    declared_type.eq_ignore_ascii_case("BOOLEAN") || declared_type.eq_ignore_ascii_case("BOOL")
+}
+
+/// An INTEGER read from a column declared as `declared_type`.
+fn integer_from_storage(v: i64, declared_type: &str) -> DatatypeRef<'static> {
+    if is_bool_column(declared_type) {
+        DatatypeRef::Bool(v != 0)
+    } else {
+        DatatypeRef::BigInt(v)
+    }
+}
+
+/// A row read for a select, as notitia_core decodes them.
+pub(crate) struct SelectRow<'r>(pub(crate) &'r SqliteRow);
+
+impl RawRow for SelectRow<'_> {
+    fn column_count(&self) -> usize {
+        self.0.columns().len()
+    }
+
+    fn value(&self, index: usize, kind: Option<&DatatypeKind>) -> DatatypeRef<'_> {
+        sqlite_row_column_to_datatype_ref_as(self.0, index, kind)
+    }
+}
+
+pub(crate) fn sqlite_row_column_to_datatype(row: &SqliteRow, index: usize) -> Datatype {
+    sqlite_row_column_to_datatype_ref(row, index).into_owned()
+}
+
+/// Every column of every row, as a select sent as data returns them.
+pub(crate) fn sqlite_rows_to_datatypes(rows: &[SqliteRow]) -> Vec<Vec<Datatype>> {
+    rows.iter()
+        .map(|row| {
+            (0..row.columns().len())
+                .map(|idx| sqlite_row_column_to_datatype(row, idx))
+                .collect()
+        })
+        .collect()
+}
+
+/// Borrow a column's value out of the row without copying text or blobs.
+///
+/// Decoded by the storage class of the value itself, which the declared
+/// type of its column doesn't constrain in SQLite, e.g. an expression
+/// yields plain INTEGERs for a boolean column.
+pub(crate) fn sqlite_row_column_to_datatype_ref(row: &SqliteRow, index: usize) -> DatatypeRef<'_> {
+    let Ok(value) = row.try_get_raw(index) else {
+        return DatatypeRef::Null;
+    };
+    if value.is_null() {
+        return DatatypeRef::Null;
+    }
+
+    match value.type_info().name() {
+        "INTEGER" | "BOOLEAN" => {
+            let declared_type = row.columns()[index].type_info().name();
+            integer_from_storage(row.get(index), declared_type)
+        }
+        "REAL" => DatatypeRef::Double(row.get(index)),
+        "TEXT" => DatatypeRef::Text(row.get(index)),
+        "BLOB" => DatatypeRef::Blob(row.get(index)),
+        _ => {
+            // Fall back: try text, then blob
+            if let Ok(v) = row.try_get::<&str, _>(index) {
+                DatatypeRef::Text(v)
+            } else if let Ok(v) = row.try_get::<&[u8], _>(index) {
+                DatatypeRef::Blob(v)
+            } else {
+                DatatypeRef::Null
+            }
+        }
+    }
+}
+
+/// Like [`sqlite_row_column_to_datatype_ref`], but decodes the value as the
+/// column's declared `kind` when there is one.
+pub(crate) fn sqlite_row_column_to_datatype_ref_as<'r>(
+    row: &'r SqliteRow,
+    index: usize,
+    kind: Option<&DatatypeKind>,
+) -> DatatypeRef<'r> {
+    let value = sqlite_row_column_to_datatype_ref(row, index);
+    match kind {
+        Some(kind) => value_as_kind(value, kind),
+        None => value,
+    }
+}
+
+pub(crate) fn sqlite_row_column_to_datatype_as(
+    row: &SqliteRow,
+    index: usize,
+    kind: Option<&DatatypeKind>,
+) -> Datatype {
+    sqlite_row_column_to_datatype_ref_as(row, index, kind).into_owned()
+}
+
+/// Reinterpret a value decoded by its storage class as `kind`. Values whose
+/// storage class doesn't fit the kind are left for the record to reject.
+fn value_as_kind<'r>(value: DatatypeRef<'r>, kind: &DatatypeKind) -> DatatypeRef<'r> {
+    match (kind, value) {
+        (DatatypeKind::Bool(_), DatatypeRef::BigInt(v)) => DatatypeRef::Bool(v != 0),
+        (DatatypeKind::Int(_) | DatatypeKind::BigInt(_), DatatypeRef::Bool(v)) => {
+            DatatypeRef::BigInt(bool_to_storage(v))
+        }
+        (DatatypeKind::Int(_), DatatypeRef::BigInt(v)) => {
+            i32::try_from(v).map_or(DatatypeRef::BigInt(v), DatatypeRef::Int)
+        }
+        (DatatypeKind::Float(_) | DatatypeKind::Double(_), DatatypeRef::BigInt(v)) => {
+            DatatypeRef::Double(v as f64)
+        }
+        (_, value) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notitia_core::DatatypeKindMetadata;
+
+    use super::*;
+
+    #[test]
+    fn reads_integers_of_boolean_columns_as_booleans() {
+        assert_eq!(integer_from_storage(1, "BOOLEAN"), DatatypeRef::Bool(true));
+        assert_eq!(integer_from_storage(0, "bool"), DatatypeRef::Bool(false));
+        assert_eq!(integer_from_storage(1, "INTEGER"), DatatypeRef::BigInt(1));
+        assert_eq!(
+            integer_from_storage(bool_to_storage(true), "BOOLEAN"),
+            DatatypeRef::Bool(true)
+        );
+    }
+
+    #[test]
+    fn reads_values_as_their_declared_kind() {
+        let kind = |kind: fn(DatatypeKindMetadata) -> DatatypeKind| kind(Default::default());
+
+        assert_eq!(
+            value_as_kind(DatatypeRef::BigInt(1), &kind(DatatypeKind::Bool)),
+            DatatypeRef::Bool(true)
+        );
+        assert_eq!(
+            value_as_kind(DatatypeRef::BigInt(7), &kind(DatatypeKind::Int)),
+            DatatypeRef::Int(7)
+        );
+        assert_eq!(
+            value_as_kind(DatatypeRef::BigInt(i64::MAX), &kind(DatatypeKind::Int)),
+            DatatypeRef::BigInt(i64::MAX)
+        );
+        assert_eq!(
+            value_as_kind(DatatypeRef::BigInt(2), &kind(DatatypeKind::Double)),
+            DatatypeRef::Double(2.0)
+        );
+        assert_eq!(
+            value_as_kind(DatatypeRef::Text("2"), &kind(DatatypeKind::Int)),
+            DatatypeRef::Text("2")
+        );
+        assert_eq!(
+            value_as_kind(DatatypeRef::Null, &kind(DatatypeKind::Bool)),
+            DatatypeRef::Null
+        );
+    }
+}
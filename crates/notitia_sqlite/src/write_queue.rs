@@ -0,0 +1,299 @@
+use std::sync::{
+    Mutex, RwLock,
+    atomic::{AtomicU64, Ordering},
+    mpsc,
+};
+
+use sqlx::{Connection, Pool, Sqlite, SqliteConnection, pool::PoolConnection, sqlite::SqliteRow};
+
+use notitia_core::{CascadeWalk, MutationEvent};
+
+use crate::{SqlStatement, SqliteError, select_def_to_sql, storage::sqlite_rows_to_datatypes};
+
+#[derive(Clone, Debug)]
+pub struct WriteQueueOptions {
+    /// The most writes committed together in one transaction.
+    pub max_batch: usize,
+}
+
+impl Default for WriteQueueOptions {
+    fn default() -> Self {
+        Self { max_batch: 64 }
+    }
+}
+
+struct PendingWrite {
+    id: u64,
+    statement: SqlStatement,
+    result: mpsc::Sender<Result<(), sqlx::Error>>,
+}
+
+/// Serializes all writes onto a single connection.
+///
+/// There is no background task: a caller queues its statement and then
+/// waits for the writer connection. Whoever gets it flushes everything
+/// queued so far in one transaction, so under contention many writes share
+/// a commit instead of fighting over the database lock.
+///
+/// A caller dropped while its write is still queued takes the write back
+/// off the queue, so it never runs. Once a writer took it into a batch, it
+/// commits with the others.
+pub(crate) struct WriteQueue {
+    /// Must be limited to a single connection; holding it is what makes a
+    /// caller the current writer.
+    writer: RwLock<Pool<Sqlite>>,
+    pending: Mutex<Vec<PendingWrite>>,
+    next_id: AtomicU64,
+    options: WriteQueueOptions,
+}
+
+/// Takes a caller's write back off the queue when the caller is done with
+/// it, in case no writer took it yet.
+struct Queued<'a> {
+    queue: &'a WriteQueue,
+    id: u64,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        let mut pending = self.queue.pending.lock().unwrap();
+        pending.retain(|write| write.id != self.id);
+    }
+}
+
+impl WriteQueue {
+    pub(crate) fn new(writer: Pool<Sqlite>, options: WriteQueueOptions) -> Self {
+        Self {
+            writer: RwLock::new(writer),
+            pending: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+            options,
+        }
+    }
+
+    pub(crate) async fn execute(&self, statement: SqlStatement) -> Result<(), SqliteError> {
+        let (sender, receiver) = mpsc::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().push(PendingWrite {
+            id,
+            statement,
+            result: sender,
+        });
+        let _queued = Queued { queue: self, id };
+
+        loop {
+            let mut conn = self.writer().acquire().await?;
+
+            // A previous writer may already have flushed our statement
+            // before releasing the connection.
+            match receiver.try_recv() {
+                Ok(result) => return Ok(result?),
+                Err(mpsc::TryRecvError::Disconnected) => return Err(SqliteError::WriteAbandoned),
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            let batch = self.take_batch();
+            flush(&mut conn, batch).await;
+
+            match receiver.try_recv() {
+                Ok(result) => return Ok(result?),
+                Err(mpsc::TryRecvError::Disconnected) => return Err(SqliteError::WriteAbandoned),
+                // Queued behind a full batch; go again.
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+    }
+
+    /// Run `statements` in their own transaction on the writer connection,
+    /// all or nothing. Writes queued meanwhile wait until it is done.
+    pub(crate) async fn execute_transaction(
+        &self,
+        statements: &[SqlStatement],
+    ) -> Result<(), SqliteError> {
+        let mut conn = self.writer().acquire().await?;
+        execute_transaction(&mut conn, statements).await
+    }
+
+    /// Like [`execute_transaction`](Self::execute_transaction), returning the
+    /// rows of every statement in order.
+    pub(crate) async fn fetch_transaction(
+        &self,
+        statements: &[SqlStatement],
+    ) -> Result<Vec<SqliteRow>, SqliteError> {
+        let mut conn = self.writer().acquire().await?;
+        fetch_transaction(&mut conn, statements).await
+    }
+
+    /// Run one statement that returns rows, such as `INSERT ... RETURNING`,
+    /// on the writer connection.
+    pub(crate) async fn fetch_all(
+        &self,
+        statement: &SqlStatement,
+    ) -> Result<Vec<SqliteRow>, SqliteError> {
+        let mut conn = self.writer().acquire().await?;
+        Ok(statement.query().fetch_all(&mut *conn).await?)
+    }
+
+    /// Hold the writer connection, e.g. for a blob writer, until the
+    /// returned connection is dropped. Writes queued meanwhile wait.
+    pub(crate) async fn acquire(&self) -> Result<PoolConnection<Sqlite>, SqliteError> {
+        Ok(self.writer().acquire().await?)
+    }
+
+    /// Swap in a new writer pool, e.g. after reconnecting. Writers holding
+    /// a connection of the old pool finish on it.
+    pub(crate) fn replace_writer(&self, writer: Pool<Sqlite>) {
+        *self.writer.write().unwrap() = writer;
+    }
+
+    fn writer(&self) -> Pool<Sqlite> {
+        self.writer.read().unwrap().clone()
+    }
+
+    fn take_batch(&self) -> Vec<PendingWrite> {
+        let mut pending = self.pending.lock().unwrap();
+        let len = pending.len().min(self.options.max_batch.max(1));
+        pending.drain(..len).collect()
+    }
+}
+
+pub(crate) async fn execute_transaction(
+    conn: &mut SqliteConnection,
+    statements: &[SqlStatement],
+) -> Result<(), SqliteError> {
+    let mut tx = conn.begin().await?;
+    for statement in statements {
+        statement.query().execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn fetch_transaction(
+    conn: &mut SqliteConnection,
+    statements: &[SqlStatement],
+) -> Result<Vec<SqliteRow>, SqliteError> {
+    let mut tx = conn.begin().await?;
+    let mut rows = Vec::new();
+    for statement in statements {
+        rows.extend(statement.query().fetch_all(&mut *tx).await?);
+    }
+    tx.commit().await?;
+    Ok(rows)
+}
+
+/// Like [`fetch_transaction`], first reading the lookups of `cascade` in
+/// the transaction, and returning its deletes along with the rows.
+pub(crate) async fn fetch_transaction_cascading(
+    conn: &mut SqliteConnection,
+    mut cascade: CascadeWalk,
+    statements: &[SqlStatement],
+) -> Result<(Vec<SqliteRow>, Vec<MutationEvent>), SqliteError> {
+    let mut tx = conn.begin().await?;
+    while let Some(lookup) = cascade.next_lookup() {
+        let keys = select_def_to_sql(&lookup)
+            .query()
+            .fetch_all(&mut *tx)
+            .await?;
+        cascade.found(sqlite_rows_to_datatypes(&keys));
+    }
+    let mut rows = Vec::new();
+    for statement in statements {
+        rows.extend(statement.query().fetch_all(&mut *tx).await?);
+    }
+    tx.commit().await?;
+    Ok((rows, cascade.into_events()))
+}
+
+async fn flush(conn: &mut SqliteConnection, batch: Vec<PendingWrite>) {
+    if let [write] = batch.as_slice() {
+        let result = write.statement.query().execute(&mut *conn).await;
+        let _ = write.result.send(result.map(|_| ()));
+        return;
+    }
+
+    match flush_batch(conn, &batch).await {
+        Ok(results) => {
+            for (write, result) in batch.iter().zip(results) {
+                let _ = write.result.send(result);
+            }
+        }
+        Err(_) => {
+            // The batch transaction itself failed, so none of it was
+            // applied. Retry each write on its own to get individual results.
+            for write in &batch {
+                let result = write.statement.query().execute(&mut *conn).await;
+                let _ = write.result.send(result.map(|_| ()));
+            }
+        }
+    }
+}
+
+async fn flush_batch(
+    conn: &mut SqliteConnection,
+    batch: &[PendingWrite],
+) -> Result<Vec<Result<(), sqlx::Error>>, sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    let mut results = Vec::with_capacity(batch.len());
+
+    for write in batch {
+        // A savepoint per write keeps one failing statement from rolling
+        // back the others.
+        let mut savepoint = tx.begin().await?;
+        match write.statement.query().execute(&mut *savepoint).await {
+            Ok(_) => {
+                savepoint.commit().await?;
+                results.push(Ok(()));
+            }
+            Err(err) => {
+                savepoint.rollback().await?;
+                results.push(Err(err));
+            }
+        }
+    }
+
+    tx.commit().await?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::{Context, Waker},
+    };
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn dropped_callers_take_their_queued_writes_back() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let queue = WriteQueue::new(pool, WriteQueueOptions::default());
+        let writer = queue.acquire().await.unwrap();
+
+        {
+            let mut write = pin!(queue.execute("CREATE TABLE dropped (id INTEGER)".into()));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(write.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(queue.pending.lock().unwrap().len(), 1);
+        }
+        assert!(queue.pending.lock().unwrap().is_empty());
+
+        drop(writer);
+        queue
+            .execute("CREATE TABLE kept (id INTEGER)".into())
+            .await
+            .unwrap();
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master")
+            .fetch_all(&queue.writer())
+            .await
+            .unwrap();
+        assert_eq!(tables, ["kept"]);
+    }
+}
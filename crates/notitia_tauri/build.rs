@@ -0,0 +1,5 @@
+const COMMANDS: &[&str] = &["execute", "subscribe", "unsubscribe"];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
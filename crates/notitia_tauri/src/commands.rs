@@ -0,0 +1,36 @@
+use serde_json::Value;
+use tauri::{AppHandle, Runtime, State};
+
+use crate::{Error, NotitiaQueries, SubscriptionInfo};
+
+#[tauri::command]
+pub(crate) async fn execute<R: Runtime>(
+    queries: State<'_, NotitiaQueries<R>>,
+    name: String,
+    args: Option<Value>,
+) -> Result<Value, Error> {
+    let future = queries.execute(&name, args.unwrap_or(Value::Null))?;
+    future.await
+}
+
+#[tauri::command]
+pub(crate) async fn subscribe<R: Runtime>(
+    app: AppHandle<R>,
+    queries: State<'_, NotitiaQueries<R>>,
+    name: String,
+    args: Option<Value>,
+) -> Result<SubscriptionInfo, Error> {
+    let (id, event, future) = queries.subscribe(app, &name, args.unwrap_or(Value::Null))?;
+    match future.await {
+        Ok(data) => Ok(SubscriptionInfo { id, event, data }),
+        Err(err) => {
+            queries.unsubscribe(id);
+            Err(err)
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn unsubscribe<R: Runtime>(queries: State<'_, NotitiaQueries<R>>, id: u64) -> bool {
+    queries.unsubscribe(id)
+}
@@ -0,0 +1,28 @@
+mod commands;
+
+mod registry;
+pub use registry::*;
+
+pub use notitia_remote::{Change, ChangeKind, Error, QueryEvent};
+
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{Manager, Runtime};
+
+/// Build the tauri plugin exposing `queries` to the webview.
+///
+/// JS invokes `plugin:notitia|execute` / `plugin:notitia|subscribe` with a
+/// query name and optional args, and `plugin:notitia|unsubscribe` with the id
+/// returned from `subscribe`.
+pub fn init<R: Runtime>(queries: NotitiaQueries<R>) -> TauriPlugin<R> {
+    Builder::new("notitia")
+        .invoke_handler(tauri::generate_handler![
+            commands::execute,
+            commands::subscribe,
+            commands::unsubscribe
+        ])
+        .setup(move |app, _api| {
+            app.manage(queries);
+            Ok(())
+        })
+        .build()
+}
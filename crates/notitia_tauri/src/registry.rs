@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{FutureExt, select};
+use notitia::{
+    Adapter, Database, DeliveryStrategy, FieldKindGroup, MergingFetchMode, QueryExecutor,
+    SubscribableRow,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::{Change, Error, QueryEvent};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+type ExecuteFn = dyn Fn(Value) -> BoxFuture<Result<Value, Error>> + Send + Sync;
+
+type SubscribeFn<R> = dyn Fn(AppHandle<R>, String, Value, async_channel::Receiver<()>) -> BoxFuture<Result<Value, Error>>
+    + Send
+    + Sync;
+
+/// A query registered under a name, type-erased so it can be driven by JSON
+/// arguments coming from the webview.
+struct RegisteredQuery<R: Runtime> {
+    execute: Box<ExecuteFn>,
+    subscribe: Box<SubscribeFn<R>>,
+}
+
+/// Returned to JS from the `subscribe` command.
+#[derive(Clone, Debug, Serialize)]
+pub struct SubscriptionInfo {
+    pub id: u64,
+    /// The event name the changes of this subscription are emitted on, each
+    /// a [`QueryEvent::Change`].
+    pub event: String,
+    /// The query's initial result. JS should start listening on `event`
+    /// right away; no event is emitted for the initial result itself.
+    pub data: Value,
+}
+
+/// The set of queries the webview is allowed to run.
+///
+/// Queries are described on the Rust side with the regular builder API and
+/// registered by name; JS only supplies the name and a JSON value that is
+/// deserialized into the builder's arguments.
+pub struct NotitiaQueries<R: Runtime> {
+    queries: HashMap<&'static str, RegisteredQuery<R>>,
+    /// Dropping a sender ends the task emitting the subscription's changes.
+    subscriptions: Mutex<HashMap<u64, async_channel::Sender<()>>>,
+    next_id: AtomicU64,
+}
+
+impl<R: Runtime> Default for NotitiaQueries<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Runtime> NotitiaQueries<R> {
+    pub fn new() -> Self {
+        Self {
+            queries: HashMap::new(),
+            subscriptions: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Register a query under `name`. `build` receives the arguments sent
+    /// from JS (use `()` for queries without arguments).
+    pub fn query<Args, F, Db, Adptr, FieldUnion, FieldPath, Fields, Mode>(
+        mut self,
+        name: &'static str,
+        build: F,
+    ) -> Self
+    where
+        Args: DeserializeOwned,
+        F: Fn(Args) -> QueryExecutor<Db, Adptr, FieldUnion, FieldPath, Fields, Mode>
+            + Send
+            + Sync
+            + 'static,
+        Db: Database + 'static,
+        Adptr: Adapter + 'static,
+        Adptr::Error: Send,
+        FieldUnion: unions::IsUnion + Send + Sync + 'static,
+        FieldPath: Send + Sync + 'static,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync + 'static,
+        Fields::Type: SubscribableRow,
+        Mode: MergingFetchMode<Fields::Type> + Send + Sync + 'static,
+        Mode::Output: Serialize + Clone + PartialEq + Send,
+    {
+        let build = Arc::new(build);
+
+        let execute: Box<ExecuteFn> = {
+            let build = build.clone();
+            Box::new(move |args| {
+                let query = match serde_json::from_value(args) {
+                    Ok(args) => build(args),
+                    Err(err) => return Box::pin(async move { Err(Error::InvalidArgs(err)) }),
+                };
+
+                Box::pin(async move {
+                    let output = query
+                        .execute()
+                        .await
+                        .map_err(|err| Error::Query(err.to_string()))?;
+                    serde_json::to_value(output).map_err(Error::Serialize)
+                })
+            })
+        };
+
+        let subscribe: Box<SubscribeFn<R>> = Box::new(move |app, event, args, cancelled| {
+            let query = match serde_json::from_value(args) {
+                Ok(args) => build(args),
+                Err(err) => return Box::pin(async move { Err(Error::InvalidArgs(err)) }),
+            };
+
+            Box::pin(async move {
+                let sub = query
+                    .subscribe_via(DeliveryStrategy::Async)
+                    .await
+                    .map_err(|err| Error::Query(err.to_string()))?;
+
+                // The first message is the initial output, which is handed
+                // back through the command rather than emitted.
+                let _ = sub.recv_async().await;
+                let initial = serde_json::to_value(&*sub.data()).map_err(Error::Serialize)?;
+
+                // Ends, dropping the subscription, once it is unsubscribed.
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        let meta = select! {
+                            meta = sub.recv_async().fuse() => meta,
+                            _ = cancelled.recv().fuse() => break,
+                        };
+                        let Ok(meta) = meta else {
+                            break;
+                        };
+                        let Some(change) = Change::from_metadata(meta) else {
+                            continue;
+                        };
+                        if app.emit(&event, QueryEvent::<()>::Change(change)).is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                Ok(initial)
+            })
+        });
+
+        self.queries
+            .insert(name, RegisteredQuery { execute, subscribe });
+        self
+    }
+
+    pub(crate) fn execute(
+        &self,
+        name: &str,
+        args: Value,
+    ) -> Result<BoxFuture<Result<Value, Error>>, Error> {
+        let query = self
+            .queries
+            .get(name)
+            .ok_or_else(|| Error::UnknownQuery(name.to_string()))?;
+        Ok((query.execute)(args))
+    }
+
+    pub(crate) fn subscribe(
+        &self,
+        app: AppHandle<R>,
+        name: &str,
+        args: Value,
+    ) -> Result<(u64, String, BoxFuture<Result<Value, Error>>), Error> {
+        let query = self
+            .queries
+            .get(name)
+            .ok_or_else(|| Error::UnknownQuery(name.to_string()))?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let event = format!("notitia://subscription/{id}");
+        let (cancel, cancelled) = async_channel::bounded(1);
+        self.subscriptions.lock().unwrap().insert(id, cancel);
+
+        let future = (query.subscribe)(app, event.clone(), args, cancelled);
+        Ok((id, event, future))
+    }
+
+    /// Stop emitting events for a subscription. Returns false if the id was
+    /// not (or no longer) active.
+    pub(crate) fn unsubscribe(&self, id: u64) -> bool {
+        self.subscriptions.lock().unwrap().remove(&id).is_some()
+    }
+}
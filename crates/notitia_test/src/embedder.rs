@@ -0,0 +1,68 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use notitia::DatabaseEmbedder;
+
+/// A deterministic embedder that needs no model.
+///
+/// Each word is hashed into one of `dimension` buckets and the counts are
+/// normalized, so texts sharing words end up close together. That is enough
+/// for similarity search to return sensible results in tests.
+#[derive(Clone, Debug)]
+pub struct FakeEmbedder {
+    dimension: u32,
+}
+
+impl FakeEmbedder {
+    pub fn new(dimension: u32) -> Self {
+        assert!(dimension > 0, "embedding dimension must be non-zero");
+        Self { dimension }
+    }
+}
+
+impl Default for FakeEmbedder {
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+impl DatabaseEmbedder for FakeEmbedder {
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimension as usize];
+
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            word.to_lowercase().hash(&mut hasher);
+            vector[(hasher.finish() % self.dimension as u64) as usize] += 1.0;
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+
+    fn dimension(&self) -> u32 {
+        self.dimension
+    }
+}
+
+/// A directory for one test database's vector index.
+pub(crate) fn scratch_dir() -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let dir: PathBuf = std::env::temp_dir().join(format!(
+        "notitia_test_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    dir.to_string_lossy().into_owned()
+}
@@ -0,0 +1,35 @@
+mod sql;
+pub use sql::*;
+
+mod recorder;
+pub use recorder::*;
+
+mod mock;
+pub use mock::*;
+
+#[cfg(feature = "embeddings")]
+mod embedder;
+#[cfg(feature = "embeddings")]
+pub use embedder::*;
+
+use notitia::{ConnectionOptions, Database, Notitia};
+use notitia_sqlite::SqliteAdapter;
+
+/// Open a fresh, empty in-memory database with the schema of `Db`.
+///
+/// Every call gets its own database, so tests can run in parallel without
+/// sharing state. With the `embeddings` feature, embedded fields are backed
+/// by a [`FakeEmbedder`] and a throwaway index under the system temp dir.
+///
+/// Panics if the database can't be opened.
+pub async fn memory_db<Db: Database>() -> Notitia<Db, SqliteAdapter> {
+    let options = ConnectionOptions::new("sqlite::memory:");
+    #[cfg(feature = "embeddings")]
+    let options = options
+        .embeddings_uri(embedder::scratch_dir())
+        .embedder(FakeEmbedder::default());
+
+    Db::connect::<SqliteAdapter>(options)
+        .await
+        .expect("failed to open in-memory test database")
+}
@@ -0,0 +1,743 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_util::io::{AsyncWrite, Cursor};
+use notitia::{
+    Adapter, AdapterCapabilities, ArchiveStmt, BlobLocation, BulkInsertStmtBuilt, Database,
+    Datatype, DatatypeConversionError, DeleteByKeysStmtBuilt, DeleteStmtBuilt, FetchError,
+    FieldExpr, FieldFilter, FieldFilterMetadata, FieldKindGroup, InsertFromSelectStmtBuilt,
+    InsertStmtBuilt, MutationEvent, MutationRejected, NonFiniteFloat, Notitia, OrderDirection,
+    OrderKey, OutboxEvent, OutboxMessage, PartialRecord, Record, SelectStmtBuilt, SelectStmtDef,
+    SelectStmtFetchMode, SqlFunction, TableFieldPair, UnexpectedNull, Unsupported, UpdateStmtBuilt,
+};
+use unions::IsUnion;
+
+/// A canned row: column names and their values.
+///
+/// Columns are matched by name, so a row may list them in any order. It
+/// should contain every selected field, plus any columns the `order_by`
+/// expressions of a sorting fetch mode read.
+pub type MockRow = Vec<(&'static str, Datatype)>;
+
+/// A statement the [`MockAdapter`] received.
+#[derive(Clone, Debug)]
+pub enum MockStatement {
+    Select {
+        tables: Vec<&'static str>,
+        fields: Vec<&'static str>,
+        filters: Vec<FieldFilter>,
+    },
+    Insert {
+        table: &'static str,
+        values: Vec<(&'static str, Datatype)>,
+    },
+    BulkInsert {
+        table: &'static str,
+        rows: Vec<Vec<(&'static str, Datatype)>>,
+    },
+    InsertFromSelect {
+        table: &'static str,
+        columns: Vec<&'static str>,
+        source_tables: Vec<&'static str>,
+        filters: Vec<FieldFilter>,
+    },
+    Update {
+        table: &'static str,
+        changed: Vec<(&'static str, FieldExpr)>,
+        filters: Vec<FieldFilter>,
+    },
+    Delete {
+        table: &'static str,
+        filters: Vec<FieldFilter>,
+    },
+}
+
+impl MockStatement {
+    /// The (first) table the statement targets.
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Select { tables, .. } => tables.first().copied().unwrap_or_default(),
+            Self::Insert { table, .. }
+            | Self::BulkInsert { table, .. }
+            | Self::InsertFromSelect { table, .. }
+            | Self::Update { table, .. }
+            | Self::Delete { table, .. } => table,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MockError {
+    #[error("no response was queued for a select on {0}")]
+    NoResponse(String),
+    #[error("{0}")]
+    Scripted(String),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error(transparent)]
+    Decode(#[from] DatatypeConversionError),
+    #[error(transparent)]
+    NonFiniteFloat(#[from] NonFiniteFloat),
+    #[error(transparent)]
+    MutationRejected(#[from] MutationRejected),
+    #[error(transparent)]
+    UnexpectedNull(#[from] UnexpectedNull),
+    #[error(transparent)]
+    Unsupported(#[from] Unsupported),
+}
+
+enum MockResponse {
+    Rows(Vec<MockRow>),
+    Error(String),
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: VecDeque<MockResponse>,
+    write_failures: VecDeque<String>,
+    ping_failures: VecDeque<String>,
+    received: Vec<MockStatement>,
+    written_tables: HashSet<&'static str>,
+    fixture_versions: HashMap<&'static str, u32>,
+    outbox: Vec<OutboxEvent>,
+    dispatched: HashSet<i64>,
+    /// All of them unless set.
+    capabilities: Option<AdapterCapabilities>,
+}
+
+/// The test's side of a [`MockAdapter`]: queue responses and inspect the
+/// statements it received. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct MockHandle {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the next select with `rows`. Responses are used in the order
+    /// they were queued, and are returned as is: filters, ordering and limits
+    /// of the query are not applied to them.
+    pub fn push_rows(&self, rows: impl IntoIterator<Item = MockRow>) -> &Self {
+        self.lock()
+            .responses
+            .push_back(MockResponse::Rows(rows.into_iter().collect()));
+        self
+    }
+
+    /// Make the next select fail with `message`.
+    pub fn push_select_error(&self, message: impl Into<String>) -> &Self {
+        self.lock()
+            .responses
+            .push_back(MockResponse::Error(message.into()));
+        self
+    }
+
+    /// Make the next insert, update or delete fail with `message`. Writes
+    /// succeed otherwise.
+    pub fn push_write_error(&self, message: impl Into<String>) -> &Self {
+        self.lock().write_failures.push_back(message.into());
+        self
+    }
+
+    /// Make the next ping fail with `message`, e.g. to exercise a
+    /// [`Supervisor`](notitia::Supervisor). Pings succeed otherwise.
+    pub fn push_ping_error(&self, message: impl Into<String>) -> &Self {
+        self.lock().ping_failures.push_back(message.into());
+        self
+    }
+
+    /// Pretend the database supports only `capabilities`, to test how
+    /// statements needing more fail.
+    pub fn set_capabilities(&self, capabilities: AdapterCapabilities) -> &Self {
+        self.lock().capabilities = Some(capabilities);
+        self
+    }
+
+    /// Every statement received so far, oldest first.
+    pub fn received(&self) -> Vec<MockStatement> {
+        self.lock().received.clone()
+    }
+
+    /// Drain the received statements.
+    pub fn take_received(&self) -> Vec<MockStatement> {
+        std::mem::take(&mut self.lock().received)
+    }
+
+    /// Panics unless every queued response, write error and ping error was
+    /// used.
+    pub fn assert_exhausted(&self) {
+        let state = self.lock();
+        assert!(
+            state.responses.is_empty()
+                && state.write_failures.is_empty()
+                && state.ping_failures.is_empty(),
+            "{} select response(s), {} write error(s) and {} ping error(s) were never used",
+            state.responses.len(),
+            state.write_failures.len(),
+            state.ping_failures.len(),
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    fn write(&self, statement: MockStatement) -> Result<(), MockError> {
+        let mut state = self.lock();
+        state.written_tables.insert(statement.table());
+        state.received.push(statement);
+        match state.write_failures.pop_front() {
+            Some(message) => Err(MockError::Scripted(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Like [`write`](Self::write), adding `outbox` to the outbox if the
+    /// write succeeds.
+    fn write_with_outbox(
+        &self,
+        statement: MockStatement,
+        outbox: Vec<OutboxMessage>,
+    ) -> Result<(), MockError> {
+        self.write(statement)?;
+        let mut state = self.lock();
+        for message in outbox {
+            let id = state.outbox.len() as i64 + 1;
+            state.outbox.push(OutboxEvent {
+                id,
+                topic: message.topic,
+                payload: message.payload,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An [`Adapter`] with no database behind it, for unit tests of code that
+/// issues queries.
+///
+/// Selects are answered from rows queued on its [`MockHandle`], writes
+/// always succeed unless told otherwise, and every statement is recorded.
+///
+/// ```ignore
+/// let (db, mock) = mock_db::<MyDb>().await;
+/// mock.push_rows([vec![("name", Datatype::Text("Alice".into()))]]);
+///
+/// let names = db.query(MyDb::USERS.select(User::NAME).fetch_vec()).execute().await?;
+/// assert_eq!(names, ["Alice"]);
+/// assert!(matches!(&mock.received()[0], MockStatement::Select { .. }));
+/// ```
+pub struct MockAdapter {
+    handle: MockHandle,
+}
+
+impl MockAdapter {
+    pub fn handle(&self) -> &MockHandle {
+        &self.handle
+    }
+}
+
+/// The blob writer of a [`MockAdapter`]. It buffers the bytes and records
+/// them as a [`MockStatement::Update`] of the column when closed.
+pub struct MockBlobWriter {
+    handle: MockHandle,
+    location: BlobLocation,
+    len: usize,
+    bytes: Vec<u8>,
+    closed: bool,
+}
+
+impl AsyncWrite for MockBlobWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = buf.len().min(this.len - this.bytes.len());
+        this.bytes.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Bytes that weren't written are zero, as with a real blob writer.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if std::mem::replace(&mut this.closed, true) {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut bytes = std::mem::take(&mut this.bytes);
+        bytes.resize(this.len, 0);
+        let written = this.handle.write(MockStatement::Update {
+            table: this.location.table_name,
+            changed: vec![(
+                this.location.column,
+                FieldExpr::Literal(Datatype::Blob(bytes)),
+            )],
+            filters: vec![this.location.key_filter()],
+        });
+        Poll::Ready(written.map_err(io::Error::other))
+    }
+}
+
+/// A database backed by a fresh [`MockAdapter`], with the handle to script it.
+pub async fn mock_db<Db: Database>() -> (Notitia<Db, MockAdapter>, MockHandle) {
+    let handle = MockHandle::new();
+    let db = Notitia::new(Db::new(), MockAdapter::new(handle.clone())).await;
+    (db, handle)
+}
+
+impl Adapter for MockAdapter {
+    type QueryBuilder = sea_query::SqliteQueryBuilder;
+    type Connection = MockHandle;
+    type Error = MockError;
+    type BlobReader = Cursor<Vec<u8>>;
+    type BlobWriter = MockBlobWriter;
+    /// Queued responses are answered in order either way, so snapshot
+    /// queries behave like any others.
+    type Snapshot = ();
+
+    fn new(connection: Self::Connection) -> Self {
+        Self { handle: connection }
+    }
+
+    fn capabilities(&self) -> AdapterCapabilities {
+        self.handle
+            .lock()
+            .capabilities
+            .unwrap_or(AdapterCapabilities::ALL)
+    }
+
+    async fn initialize<Db: Database>(&self, _database: &Db) {}
+
+    async fn migrate<Db: Database>(&self, _database: &Db) {}
+
+    /// Ignores `url`; use [`mock_db`] to keep hold of the handle.
+    async fn open<Db: Database>(_url: &str) -> Result<Notitia<Db, Self>, Self::Error> {
+        Ok(Notitia::new(Db::new(), Self::new(MockHandle::new())).await)
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        match self.handle.lock().ping_failures.pop_front() {
+            Some(message) => Err(MockError::Scripted(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Accepted and ignored; the mock runs no SQL.
+    async fn register_function(&self, _function: SqlFunction) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn execute_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        let field_names = stmt.fields.field_names();
+
+        let response = {
+            let mut state = self.handle.lock();
+            state.received.push(MockStatement::Select {
+                tables: stmt.tables.to_vec(),
+                fields: field_names.to_vec(),
+                filters: stmt.filters.to_vec(),
+            });
+            state.responses.pop_front()
+        };
+
+        let rows = match response {
+            Some(MockResponse::Rows(rows)) => rows,
+            Some(MockResponse::Error(message)) => return Err(MockError::Scripted(message)),
+            None => return Err(MockError::NoResponse(stmt.tables.join(", "))),
+        };
+
+        let column = |row: &MockRow, name: &str| {
+            row.iter()
+                .find(|(column, _)| *column == name)
+                .map(|(_, value)| value.clone())
+                .unwrap_or(Datatype::Null)
+        };
+
+        let mut typed_rows = Vec::with_capacity(rows.len());
+        let mut order_keys = Vec::with_capacity(rows.len());
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let mut values = field_names.iter().map(|name| column(row, name));
+            let typed = Fields::from_datatypes(&mut values).map_err(|err| {
+                let consumed = field_names.len() - values.len();
+                let field = consumed
+                    .checked_sub(1)
+                    .and_then(|idx| field_names.get(idx).copied())
+                    .unwrap_or("?");
+                let err = err.in_column(stmt.tables.join(", "), field, row_index);
+                match UnexpectedNull::from_decode_error(&err, &stmt.filters) {
+                    Some(null) => MockError::from(null),
+                    None => MockError::from(err),
+                }
+            })?;
+            typed_rows.push(typed);
+
+            order_keys.push(if stmt.mode.needs_order_keys() {
+                OrderKey::new(
+                    stmt.order_by
+                        .iter()
+                        .map(|order| order.expr.resolve(row))
+                        .collect(),
+                    stmt.order_by
+                        .iter()
+                        .map(|order| matches!(order.direction, OrderDirection::Desc))
+                        .collect(),
+                )
+            } else {
+                OrderKey::default()
+            });
+        }
+
+        Ok(stmt.mode.from_rows(typed_rows, order_keys)?)
+    }
+
+    /// Records the statement as a [`MockStatement::Select`] and answers with
+    /// the next scripted rows.
+    async fn begin_snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+        Ok(())
+    }
+
+    async fn execute_snapshot_select_stmt<Db, FieldUnion, FieldPath, Fields, Mode>(
+        &self,
+        _snapshot: &Self::Snapshot,
+        stmt: &SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>,
+    ) -> Result<Mode::Output, Self::Error>
+    where
+        Db: Database,
+        FieldUnion: IsUnion + Send + Sync,
+        FieldPath: Send + Sync,
+        Fields: FieldKindGroup<FieldUnion, FieldPath> + Send + Sync,
+        Mode: SelectStmtFetchMode<Fields::Type> + Sync,
+    {
+        self.execute_select_stmt(stmt).await
+    }
+
+    async fn execute_select_def(
+        &self,
+        def: &SelectStmtDef,
+    ) -> Result<Vec<Vec<Datatype>>, Self::Error> {
+        let response = {
+            let mut state = self.handle.lock();
+            state.received.push(MockStatement::Select {
+                tables: def.tables.clone(),
+                fields: def.fields.clone(),
+                filters: def.filters.clone(),
+            });
+            state.responses.pop_front()
+        };
+
+        let rows = match response {
+            Some(MockResponse::Rows(rows)) => rows,
+            Some(MockResponse::Error(message)) => return Err(MockError::Scripted(message)),
+            None => return Err(MockError::NoResponse(def.tables.join(", "))),
+        };
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                def.fields
+                    .iter()
+                    .map(|name| FieldExpr::Field(*name).resolve(row))
+                    .chain(def.order_by.iter().map(|order| order.expr.resolve(row)))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Records the statement. The mock has no rows to conflict with, so
+    /// every record counts as inserted.
+    async fn execute_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: InsertStmtBuilt<Db, R>,
+    ) -> Result<bool, Self::Error> {
+        self.handle.write_with_outbox(
+            MockStatement::Insert {
+                table: stmt.table_name,
+                values: stmt.record.into_datatypes(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(true)
+    }
+
+    async fn execute_bulk_insert_stmt<Db: Database, R: Record + Send>(
+        &self,
+        stmt: BulkInsertStmtBuilt<Db, R>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let rows: Vec<_> = stmt
+            .records
+            .into_iter()
+            .map(|record| record.into_datatypes())
+            .collect();
+        self.handle.write_with_outbox(
+            MockStatement::BulkInsert {
+                table: stmt.table_name,
+                rows: rows.clone(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(rows)
+    }
+
+    /// Records the statement and reports no copied rows.
+    async fn execute_insert_from_select_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: InsertFromSelectStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        self.handle.write_with_outbox(
+            MockStatement::InsertFromSelect {
+                table: stmt.table_name,
+                columns: stmt.columns.to_vec(),
+                source_tables: stmt.source_tables.to_vec(),
+                filters: stmt.filters.to_vec(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(Vec::new())
+    }
+
+    /// Records the cascade lookups, the copy and the delete, and archives no
+    /// rows.
+    async fn execute_archive_stmt(
+        &self,
+        stmt: &ArchiveStmt,
+    ) -> Result<(Vec<Vec<(&'static str, Datatype)>>, Vec<MutationEvent>), Self::Error> {
+        let cascaded = stmt.cascade.clone().read_with(self).await?;
+        self.handle.write(MockStatement::InsertFromSelect {
+            table: stmt.archive_table,
+            columns: stmt.columns.to_vec(),
+            source_tables: vec![stmt.hot_table],
+            filters: stmt.filters.to_vec(),
+        })?;
+        self.handle.write(MockStatement::Delete {
+            table: stmt.hot_table,
+            filters: stmt.filters.to_vec(),
+        })?;
+        Ok((Vec::new(), cascaded))
+    }
+
+    /// Records the statement and returns no updated rows.
+    async fn execute_update_stmt<Db: Database, Rec: Record + Send, P: PartialRecord + Send>(
+        &self,
+        stmt: UpdateStmtBuilt<Db, Rec, P>,
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        self.handle.write_with_outbox(
+            MockStatement::Update {
+                table: stmt.table_name,
+                changed: stmt.partial.into_set_fields(),
+                filters: stmt.filters.to_vec(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(Vec::new())
+    }
+
+    /// Records the selects of the cascade lookups, then the statement.
+    async fn execute_delete_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let cascaded = stmt.cascade().read_with(self).await?;
+        self.handle.write_with_outbox(
+            MockStatement::Delete {
+                table: stmt.table_name,
+                filters: stmt.filters.to_vec(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(cascaded)
+    }
+
+    /// Records one delete filtering on every key, however many there are.
+    async fn execute_delete_by_keys_stmt<Db: Database, Rec: Record + Send>(
+        &self,
+        stmt: DeleteByKeysStmtBuilt<Db, Rec>,
+    ) -> Result<Vec<MutationEvent>, Self::Error> {
+        let cascaded = stmt.cascade().read_with(self).await?;
+        self.handle.write_with_outbox(
+            MockStatement::Delete {
+                table: stmt.table_name,
+                filters: stmt.chunks(stmt.keys.len()).collect(),
+            },
+            stmt.outbox,
+        )?;
+        Ok(cascaded)
+    }
+
+    /// A table counts as empty until the mock has received a write to it.
+    async fn is_table_empty(&self, table_name: &'static str) -> Result<bool, Self::Error> {
+        Ok(!self.handle.lock().written_tables.contains(table_name))
+    }
+
+    /// Answered from the queued rows like a select, with missing columns
+    /// read as null.
+    async fn fetch_rows(
+        &self,
+        table_name: &'static str,
+        columns: &[&'static str],
+    ) -> Result<Vec<Vec<(&'static str, Datatype)>>, Self::Error> {
+        let response = {
+            let mut state = self.handle.lock();
+            state.received.push(MockStatement::Select {
+                tables: vec![table_name],
+                fields: columns.to_vec(),
+                filters: Vec::new(),
+            });
+            state.responses.pop_front()
+        };
+
+        let rows = match response {
+            Some(MockResponse::Rows(rows)) => rows,
+            Some(MockResponse::Error(message)) => return Err(MockError::Scripted(message)),
+            None => return Err(MockError::NoResponse(table_name.to_string())),
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|name| {
+                        let value = row
+                            .iter()
+                            .find(|(column, _)| column == name)
+                            .map(|(_, value)| value.clone())
+                            .unwrap_or(Datatype::Null);
+                        (*name, value)
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    async fn insert_rows(
+        &self,
+        table_name: &'static str,
+        rows: Vec<Vec<(&'static str, Datatype)>>,
+    ) -> Result<(), Self::Error> {
+        self.handle.write(MockStatement::BulkInsert {
+            table: table_name,
+            rows,
+        })
+    }
+
+    /// Records an update per row.
+    async fn update_rows(
+        &self,
+        table_name: &'static str,
+        key_column: &'static str,
+        column: &'static str,
+        rows: Vec<(Datatype, Datatype)>,
+    ) -> Result<(), Self::Error> {
+        for (key, value) in rows {
+            self.handle.write(MockStatement::Update {
+                table: table_name,
+                changed: vec![(column, FieldExpr::Literal(value))],
+                filters: vec![FieldFilter::Eq(FieldFilterMetadata {
+                    left: TableFieldPair::new(table_name, key_column),
+                    right: key,
+                })],
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Records a [`MockStatement::Select`] of the column and reads the blob
+    /// from the first of the next scripted rows.
+    async fn open_blob_reader(
+        &self,
+        location: &BlobLocation,
+    ) -> Result<Self::BlobReader, Self::Error> {
+        let response = {
+            let mut state = self.handle.lock();
+            state.received.push(MockStatement::Select {
+                tables: vec![location.table_name],
+                fields: vec![location.column],
+                filters: vec![location.key_filter()],
+            });
+            state.responses.pop_front()
+        };
+
+        let rows = match response {
+            Some(MockResponse::Rows(rows)) => rows,
+            Some(MockResponse::Error(message)) => return Err(MockError::Scripted(message)),
+            None => return Err(MockError::NoResponse(location.table_name.to_string())),
+        };
+
+        let value = rows
+            .first()
+            .map(|row| FieldExpr::Field(location.column).resolve(row));
+        Ok(Cursor::new(match value {
+            Some(Datatype::Blob(bytes)) => bytes,
+            _ => Vec::new(),
+        }))
+    }
+
+    /// Nothing is recorded until the writer is closed.
+    async fn open_blob_writer(
+        &self,
+        location: &BlobLocation,
+        len: u64,
+    ) -> Result<Self::BlobWriter, Self::Error> {
+        Ok(MockBlobWriter {
+            handle: self.handle.clone(),
+            location: location.clone(),
+            len: len as usize,
+            bytes: Vec::new(),
+            closed: false,
+        })
+    }
+
+    async fn fixture_version(&self, name: &'static str) -> Result<Option<u32>, Self::Error> {
+        Ok(self.handle.lock().fixture_versions.get(name).copied())
+    }
+
+    async fn set_fixture_version(
+        &self,
+        name: &'static str,
+        version: u32,
+    ) -> Result<(), Self::Error> {
+        self.handle.lock().fixture_versions.insert(name, version);
+        Ok(())
+    }
+
+    async fn fetch_outbox(&self, limit: usize) -> Result<Vec<OutboxEvent>, Self::Error> {
+        let state = self.handle.lock();
+        Ok(state
+            .outbox
+            .iter()
+            .filter(|event| !state.dispatched.contains(&event.id))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_outbox_dispatched(&self, ids: Vec<i64>) -> Result<(), Self::Error> {
+        self.handle.lock().dispatched.extend(ids);
+        Ok(())
+    }
+}
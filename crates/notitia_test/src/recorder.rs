@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use notitia::{Adapter, Database, MutationEvent, MutationHook, Notitia};
+
+/// Records every mutation event a database broadcasts.
+///
+/// ```ignore
+/// let db = memory_db::<MyDb>().await;
+/// let recorder = EventRecorder::attach(&db);
+///
+/// db.mutate(MyDb::USERS.insert(alice)).execute().await?;
+/// assert_eq!(recorder.tables(), ["users"]);
+/// ```
+#[derive(Default)]
+pub struct EventRecorder {
+    events: Mutex<Vec<MutationEvent>>,
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install a new recorder as `db`'s mutation hook.
+    ///
+    /// A database has a single hook slot, so this records nothing if one is
+    /// already set (for instance the embedding manager).
+    pub fn attach<Db: Database, Adptr: Adapter>(db: &Notitia<Db, Adptr>) -> Arc<Self> {
+        let recorder = Arc::new(Self::new());
+        db.set_mutation_hook(recorder.clone());
+        recorder
+    }
+
+    /// Every event recorded so far, oldest first.
+    pub fn events(&self) -> Vec<MutationEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Drain the recorded events, leaving the recorder empty.
+    pub fn take(&self) -> Vec<MutationEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+
+    /// The table of each recorded event, oldest first.
+    pub fn tables(&self) -> Vec<&'static str> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.table_name)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+}
+
+impl MutationHook for EventRecorder {
+    fn on_event(&self, event: &MutationEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
@@ -0,0 +1,384 @@
+use notitia::{
+    ArchiveStmt, BulkInsertStmtBuilt, Database, DeleteStmtBuilt, DeleteStmtUnbuilt, FieldKindGroup,
+    InsertFromSelectStmtBuilt, InsertStmtBuilt, PartialRecord, Record, SelectStmtBuilt,
+    SelectStmtFetchMode, UpdateStmtBuilt, UpdateStmtUnbuilt,
+};
+use notitia_sqlite::{
+    SqlStatement, archive_stmt_to_sql, delete_stmt_to_sql, insert_from_select_stmt_to_sql,
+    insert_many_stmt_to_sql, insert_stmt_to_sql, select_stmt_to_sql, update_stmt_to_sql,
+};
+use unions::IsUnion;
+
+/// A statement that can be rendered to the SQL the SQLite adapter would run,
+/// with its bound values written inline.
+pub trait RenderSql {
+    fn render_sql(&self) -> String;
+}
+
+/// Assert that a statement renders to the expected SQL.
+///
+/// ```ignore
+/// assert_sql!(
+///     MyDb::USERS.select(User::NAME).filter(User::AGE.gt(18i64)).fetch_vec(),
+///     r#"SELECT "name" FROM "users" WHERE "users"."age" > 18"#,
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_sql {
+    ($stmt:expr, $expected:expr $(,)?) => {
+        assert_eq!(
+            $crate::RenderSql::render_sql(&$stmt),
+            $expected,
+            "statement rendered unexpected SQL"
+        )
+    };
+}
+
+impl<Db, FieldUnion, FieldPath, Fields, Mode> RenderSql
+    for SelectStmtBuilt<Db, FieldUnion, FieldPath, Fields, Mode>
+where
+    Db: Database,
+    FieldUnion: IsUnion,
+    Fields: FieldKindGroup<FieldUnion, FieldPath>,
+    Mode: SelectStmtFetchMode<Fields::Type>,
+{
+    fn render_sql(&self) -> String {
+        select_stmt_to_sql(self).to_inline_sql()
+    }
+}
+
+impl<Db: Database, R: Record> RenderSql for InsertStmtBuilt<Db, R> {
+    fn render_sql(&self) -> String {
+        insert_stmt_to_sql(
+            self.table_name,
+            &self.record.clone().into_datatypes(),
+            self.ignore_conflicts,
+        )
+        .to_inline_sql()
+    }
+}
+
+impl<Db: Database, R: Record> RenderSql for BulkInsertStmtBuilt<Db, R> {
+    fn render_sql(&self) -> String {
+        let rows: Vec<_> = self
+            .records
+            .iter()
+            .map(|record| record.clone().into_datatypes())
+            .collect();
+        insert_many_stmt_to_sql(self.table_name, &rows, self.ignore_conflicts).to_inline_sql()
+    }
+}
+
+impl<Db: Database, Rec: Record> RenderSql for InsertFromSelectStmtBuilt<Db, Rec> {
+    fn render_sql(&self) -> String {
+        insert_from_select_stmt_to_sql(self).to_inline_sql()
+    }
+}
+
+/// The copy and the delete, separated by `;\n`.
+impl RenderSql for ArchiveStmt {
+    fn render_sql(&self) -> String {
+        archive_stmt_to_sql(self)
+            .iter()
+            .map(SqlStatement::to_inline_sql)
+            .collect::<Vec<_>>()
+            .join(";\n")
+    }
+}
+
+impl<Db: Database, Rec: Record, P: PartialRecord> RenderSql for UpdateStmtUnbuilt<Db, Rec, P> {
+    fn render_sql(&self) -> String {
+        update_stmt_to_sql(
+            self.table_name,
+            &self.partial.clone().into_set_fields(),
+            &[],
+        )
+        .to_inline_sql()
+    }
+}
+
+impl<Db: Database, Rec: Record, P: PartialRecord> RenderSql for UpdateStmtBuilt<Db, Rec, P> {
+    fn render_sql(&self) -> String {
+        update_stmt_to_sql(
+            self.table_name,
+            &self.partial.clone().into_set_fields(),
+            &self.filters,
+        )
+        .to_inline_sql()
+    }
+}
+
+impl<Db: Database, Rec: Record> RenderSql for DeleteStmtUnbuilt<Db, Rec> {
+    fn render_sql(&self) -> String {
+        delete_stmt_to_sql(self.table_name, &[]).to_inline_sql()
+    }
+}
+
+impl<Db: Database, Rec: Record> RenderSql for DeleteStmtBuilt<Db, Rec> {
+    fn render_sql(&self) -> String {
+        delete_stmt_to_sql(self.table_name, &self.filters).to_inline_sql()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notitia::{SelectStmtBuildable, SelectStmtFilterable, SelectStmtSelectable, Table};
+    use notitia::{database, record};
+
+    #[derive(Debug)]
+    #[database]
+    struct TestDb {
+        users: Table<User>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct User {
+        #[db(primary_key)]
+        id: String,
+        name: String,
+        age: i64,
+    }
+
+    #[derive(Debug)]
+    #[database(includable)]
+    pub struct AuthDb {
+        sessions: Table<Session>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    pub struct Session {
+        #[db(primary_key)]
+        token: String,
+        user_id: String,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct AppDb {
+        #[db(include)]
+        auth: AuthDb,
+        users: Table<User>,
+    }
+
+    #[derive(Debug)]
+    #[database(prefix = "chat_")]
+    struct PrefixedDb {
+        users: Table<User>,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct CountedDb {
+        #[db(trigger(after_insert = "UPDATE stats SET user_count = user_count + 1"))]
+        users: Table<User>,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct MessageCountsDb {
+        #[db(counted_by(chat_id))]
+        messages: Table<Message>,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct IndexedDb {
+        #[db(index(columns = "age desc, name", include = "id"))]
+        #[db(index(name = "users_by_name", columns = "name"))]
+        users: Table<User>,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct ChatDb {
+        chats: Table<Chat>,
+        #[db(foreign_key(chat_id, chats.id, on_delete = Cascade))]
+        messages: Table<Message>,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct ReactionDb {
+        #[db(unique(message_id, user_id))]
+        reactions: Table<Reaction>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Reaction {
+        #[db(primary_key)]
+        id: String,
+        message_id: String,
+        user_id: String,
+    }
+
+    #[derive(Debug)]
+    #[database]
+    struct BlogDb {
+        posts: Table<Post>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Post {
+        #[db(primary_key)]
+        id: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Chat {
+        #[db(primary_key)]
+        id: String,
+    }
+
+    #[derive(Debug)]
+    #[record]
+    struct Message {
+        #[db(primary_key)]
+        id: String,
+        chat_id: String,
+    }
+
+    #[test]
+    fn renders_select() {
+        assert_sql!(
+            TestDb::USERS
+                .select(User::NAME)
+                .filter(User::AGE.gt(18i64))
+                .fetch_all::<Vec<_>>(),
+            r#"SELECT "name" FROM "users" WHERE "users"."age" > 18"#,
+        );
+    }
+
+    #[test]
+    fn renders_mutations() {
+        assert_sql!(
+            TestDb::USERS.insert(User::build().id("abc").name("Bob").age(36i64)),
+            r#"INSERT INTO "users" ("id", "name", "age") VALUES ('abc', 'Bob', 36)"#,
+        );
+        assert_sql!(
+            TestDb::USERS
+                .update(User::build().name("Alice"))
+                .filter(User::ID.eq("abc")),
+            r#"UPDATE "users" SET "name" = 'Alice' WHERE "users"."id" = 'abc'"#,
+        );
+        assert_sql!(
+            TestDb::USERS.delete().filter(User::ID.eq("abc")),
+            r#"DELETE FROM "users" WHERE "users"."id" = 'abc'"#,
+        );
+    }
+
+    #[test]
+    fn renders_array_columns() {
+        assert_sql!(
+            BlogDb::POSTS.insert(Post::build().id("abc").tags(vec!["rust".to_string()])),
+            r#"INSERT INTO "posts" ("id", "tags") VALUES ('abc', '["rust"]')"#,
+        );
+        assert_sql!(
+            BlogDb::POSTS
+                .select(Post::ID)
+                .filter(Post::TAGS.contains_element("rust"))
+                .fetch_all::<Vec<_>>(),
+            r#"SELECT "id" FROM "posts" WHERE EXISTS (SELECT 1 FROM json_each("posts"."tags") WHERE value = 'rust')"#,
+        );
+    }
+
+    #[test]
+    fn renders_included_tables() {
+        use notitia::Database;
+
+        assert_sql!(
+            AppDbAuth::SESSIONS
+                .select(Session::TOKEN)
+                .filter(Session::USER_ID.eq("abc"))
+                .fetch_all::<Vec<_>>(),
+            r#"SELECT "token" FROM "sessions" WHERE "sessions"."user_id" = 'abc'"#,
+        );
+
+        let tables: Vec<_> = AppDb::new().tables().map(|(name, _)| name).collect();
+        assert_eq!(tables, ["users", "sessions"]);
+    }
+
+    #[test]
+    fn renders_prefixed_tables() {
+        assert_sql!(
+            PrefixedDb::USERS
+                .select(User::NAME)
+                .filter(User::AGE.gt(18i64))
+                .fetch_all::<Vec<_>>(),
+            r#"SELECT "name" FROM "chat_users" WHERE "chat_users"."age" > 18"#,
+        );
+    }
+
+    #[test]
+    fn renders_triggers() {
+        use notitia::Database;
+
+        let sql = CountedDb::new().schema_sql(sea_query::SqliteQueryBuilder);
+        assert!(sql.ends_with(
+            r#"DROP TRIGGER IF EXISTS "users_after_insert";
+
+CREATE TRIGGER "users_after_insert" AFTER INSERT ON "users" FOR EACH ROW BEGIN UPDATE stats SET user_count = user_count + 1; END;"#
+        ));
+    }
+
+    #[test]
+    fn renders_counters() {
+        use notitia::Database;
+
+        let sql = MessageCountsDb::new().schema_sql(sea_query::SqliteQueryBuilder);
+        assert!(sql.contains(
+            r#"CREATE TABLE IF NOT EXISTS "messages_counts_by_chat_id" ("value" PRIMARY KEY, "count" integer NOT NULL);"#
+        ));
+        assert!(sql.ends_with(
+            r#"CREATE TRIGGER "messages_counts_by_chat_id_after_update" AFTER UPDATE OF "chat_id" ON "messages" FOR EACH ROW WHEN OLD."chat_id" IS NOT NEW."chat_id" BEGIN UPDATE "messages_counts_by_chat_id" SET "count" = "count" - 1 WHERE "value" = OLD."chat_id"; DELETE FROM "messages_counts_by_chat_id" WHERE "value" = OLD."chat_id" AND "count" <= 0; INSERT INTO "messages_counts_by_chat_id" ("value", "count") SELECT NEW."chat_id", 1 WHERE NEW."chat_id" IS NOT NULL ON CONFLICT ("value") DO UPDATE SET "count" = "count" + 1; END;"#
+        ));
+    }
+
+    #[test]
+    fn renders_indexes() {
+        use notitia::Database;
+
+        assert_eq!(
+            IndexedDb::new().index_sql(sea_query::SqliteQueryBuilder),
+            r#"CREATE INDEX IF NOT EXISTS "users_by_age_name" ON "users" ("age" DESC, "name" ASC, "id");
+CREATE INDEX IF NOT EXISTS "users_by_name" ON "users" ("name" ASC);"#
+        );
+        assert_eq!(IndexedDb::schema().tables[0].indexes.len(), 2);
+    }
+
+    #[test]
+    fn renders_unique_constraints() {
+        use notitia::Database;
+
+        let sql = ReactionDb::new().schema_sql(sea_query::SqliteQueryBuilder);
+        assert!(sql.contains(r#", UNIQUE ("message_id", "user_id")"#));
+        // It's part of the table, not a separate index.
+        assert!(
+            ReactionDb::new()
+                .index_sql(sea_query::SqliteQueryBuilder)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn lists_relationships() {
+        use notitia::{Database, OnAction, Relationship};
+
+        assert_eq!(
+            ChatDb::relationships(),
+            [Relationship {
+                table: "messages",
+                field: "chat_id",
+                foreign_table: "chats",
+                foreign_field: "id",
+                on_delete: OnAction::Cascade,
+                on_update: OnAction::NoAction,
+            }]
+        );
+    }
+}
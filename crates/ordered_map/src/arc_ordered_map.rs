@@ -0,0 +1,286 @@
+use std::{fmt, hash::Hash, sync::Arc};
+
+use crate::OrderedMap;
+
+/// A copy-on-write `OrderedMap` whose clones share storage.
+///
+/// Cloning (or calling [`snapshot`](Self::snapshot)) is O(1). The first
+/// mutation after a clone copies the key and order indexes, but rows are
+/// stored behind their own `Arc` and only the rows that are actually written
+/// get copied. This keeps handing a fresh snapshot to a reader after every
+/// change cheap even for large maps.
+pub struct ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    inner: Arc<OrderedMap<K, Arc<V>, O>>,
+}
+
+impl<K, V, O> fmt::Debug for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone + fmt::Debug,
+    V: fmt::Debug,
+    O: Ord + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl<K, V, O> Default for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(OrderedMap::new()),
+        }
+    }
+}
+
+impl<K, V, O> Clone for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K, V, O> PartialEq for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    V: PartialEq,
+    O: Ord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) || self.inner == other.inner
+    }
+}
+
+impl<K, V, O> ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A read-only handle sharing all storage with `self`.
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Whether both maps still share the same storage.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.inner.get(key).map(|value| &**value)
+    }
+
+    /// The order key `key` is sorted by.
+    pub fn order_key_of(&self, key: &K) -> Option<&O> {
+        self.inner.order_key_of(key)
+    }
+
+    /// The entry with the smallest order key.
+    pub fn first(&self) -> Option<(&K, &O, &V)> {
+        let (key, order_key, value) = self.inner.first()?;
+        Some((key, order_key, &**value))
+    }
+
+    /// The entry with the largest order key.
+    pub fn last(&self) -> Option<(&K, &O, &V)> {
+        let (key, order_key, value) = self.inner.last()?;
+        Some((key, order_key, &**value))
+    }
+
+    /// Iterate over `(key, order_key, value)` in sorted order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &O, &V)> + ExactSizeIterator {
+        self.inner
+            .iter()
+            .map(|(key, order_key, value)| (key, order_key, &**value))
+    }
+
+    /// Iterate over keys in sorted order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.inner.keys()
+    }
+
+    /// Iterate over values in sorted order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.inner.values().map(|value| &**value)
+    }
+}
+
+impl<K, V, O> ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    O: Ord + Clone,
+{
+    fn inner_mut(&mut self) -> &mut OrderedMap<K, Arc<V>, O> {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    pub fn insert(&mut self, key: K, value: V, order_key: O) {
+        self.inner_mut().insert(key, Arc::new(value), order_key);
+    }
+
+    pub fn update_order_for_key(&mut self, key: &K, new_order_key: O) -> Option<()> {
+        self.inner_mut().update_order_for_key(key, new_order_key)
+    }
+
+    /// Mutable access to a value, copying it first if a snapshot still
+    /// shares it.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.inner_mut().get_mut(key).map(Arc::make_mut)
+    }
+
+    /// Iterate mutably over all values (arbitrary order). Every value is
+    /// copied out of any snapshot that still shares it.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.inner_mut().values_mut().map(Arc::make_mut)
+    }
+
+    /// Replace the value stored under `key` without copying the old one.
+    /// Returns `None` (and stores nothing) if the key is absent.
+    pub fn replace(&mut self, key: &K, value: V) -> Option<Arc<V>> {
+        let slot = self.inner_mut().get_mut(key)?;
+        Some(std::mem::replace(slot, Arc::new(value)))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner_mut().remove(key).map(Arc::unwrap_or_clone)
+    }
+
+    /// Retain only entries for which the predicate returns `true`. The
+    /// predicate is called once per entry, in sorted order.
+    pub fn retain(&mut self, mut f: impl FnMut(&V) -> bool) {
+        let removed: Vec<K> = self
+            .iter()
+            .filter(|(_, _, value)| !f(value))
+            .map(|(key, _, _)| key.clone())
+            .collect();
+        // Avoid un-sharing the map when nothing would be removed.
+        if removed.is_empty() {
+            return;
+        }
+
+        let inner = self.inner_mut();
+        for key in &removed {
+            inner.remove(key);
+        }
+    }
+}
+
+impl<K, V, O> From<OrderedMap<K, V, O>> for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn from(map: OrderedMap<K, V, O>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K, V, O> FromIterator<(K, V, O)> for ArcOrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V, O)>>(iter: I) -> Self {
+        Self {
+            inner: Arc::new(
+                iter.into_iter()
+                    .map(|(key, value, order_key)| (key, Arc::new(value), order_key))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_is_unaffected_by_writes() {
+        let mut map = ArcOrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+
+        let snapshot = map.snapshot();
+        assert!(map.ptr_eq(&snapshot));
+
+        *map.get_mut(&"a").unwrap() = 42;
+        map.insert("c", 3, 30);
+
+        assert!(!map.ptr_eq(&snapshot));
+        assert_eq!(snapshot.values().collect::<Vec<_>>(), vec![&1, &2]);
+        assert_eq!(map.values().collect::<Vec<_>>(), vec![&42, &2, &3]);
+    }
+
+    #[test]
+    fn unchanged_rows_stay_shared() {
+        let mut map = ArcOrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+
+        let snapshot = map.snapshot();
+        map.replace(&"a", 5);
+
+        let shared = |map: &ArcOrderedMap<&str, i32, i32>| map.inner.get(&"b").unwrap().clone();
+        assert!(Arc::ptr_eq(&shared(&map), &shared(&snapshot)));
+        assert_eq!(snapshot.get(&"a"), Some(&1));
+        assert_eq!(map.get(&"a"), Some(&5));
+    }
+
+    #[test]
+    fn retain_without_removals_keeps_sharing() {
+        let mut map = ArcOrderedMap::new();
+        map.insert("a", 1, 10);
+
+        let snapshot = map.snapshot();
+        map.retain(|_| true);
+        assert!(map.ptr_eq(&snapshot));
+
+        map.retain(|_| false);
+        assert!(map.is_empty());
+        assert_eq!(snapshot.len(), 1);
+    }
+
+    #[test]
+    fn retain_calls_the_predicate_once_per_entry() {
+        let mut map = ArcOrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+        map.insert("c", 3, 30);
+
+        // Keeps every other entry, so it only works if called once each.
+        let mut calls = 0;
+        map.retain(|_| {
+            calls += 1;
+            calls % 2 == 1
+        });
+
+        assert_eq!(calls, 3);
+        assert_eq!(map.values().collect::<Vec<_>>(), vec![&1, &3]);
+    }
+}
@@ -1,8 +1,14 @@
 mod ordered_set;
 pub use ordered_set::*;
 
+mod arc_ordered_map;
+pub use arc_ordered_map::*;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, btree_map},
     fmt,
     hash::Hash,
     sync::Arc,
@@ -122,6 +128,30 @@ where
         }
     }
 
+    /// Create a map with room for at least `capacity` entries before the key
+    /// index needs to reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lookup_map: HashMap::with_capacity(capacity),
+            order_map: BTreeMap::new(),
+        }
+    }
+
+    /// How many entries the key index can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.lookup_map.capacity()
+    }
+
+    /// Reserve room for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        self.lookup_map.reserve(additional);
+    }
+
+    /// Shrink the key index as much as possible to fit the current entries.
+    pub fn shrink_to_fit(&mut self) {
+        self.lookup_map.shrink_to_fit();
+    }
+
     pub fn len(&self) -> usize {
         self.lookup_map.len()
     }
@@ -158,6 +188,11 @@ where
         self.lookup_map.get(key).map(|(_, value)| value)
     }
 
+    /// The order key `key` is sorted by.
+    pub fn order_key_of(&self, key: &K) -> Option<&O> {
+        self.lookup_map.get(key).map(|(order_key, _)| &**order_key)
+    }
+
     pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
         self.lookup_map.get_mut(key).map(|(_, value)| value)
     }
@@ -168,6 +203,72 @@ where
         Some(value)
     }
 
+    /// The entry with the smallest order key.
+    pub fn first(&self) -> Option<(&K, &O, &V)> {
+        let (order_key, key) = self.order_map.first_key_value()?;
+        Some((key, order_key, &self.lookup_map[key].1))
+    }
+
+    /// The entry with the largest order key.
+    pub fn last(&self) -> Option<(&K, &O, &V)> {
+        let (order_key, key) = self.order_map.last_key_value()?;
+        Some((key, order_key, &self.lookup_map[key].1))
+    }
+
+    /// Remove and return the entry with the smallest order key.
+    pub fn pop_first(&mut self) -> Option<(K, V, O)> {
+        let (order_key, key) = self.order_map.pop_first()?;
+        Some(take_entry(&mut self.lookup_map, order_key, key))
+    }
+
+    /// Remove and return the entry with the largest order key.
+    pub fn pop_last(&mut self) -> Option<(K, V, O)> {
+        let (order_key, key) = self.order_map.pop_last()?;
+        Some(take_entry(&mut self.lookup_map, order_key, key))
+    }
+
+    /// Keep the first `len` entries in sorted order, dropping the rest from
+    /// the back. Does nothing if the map is already that short.
+    pub fn truncate_back(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_last();
+        }
+    }
+
+    /// The entry at position `index` in sorted order.
+    ///
+    /// Walks from whichever end is closer, so this is O(n) in the worst case.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &O, &V)> {
+        let len = self.len();
+        if index >= len {
+            return None;
+        }
+        if index < len / 2 {
+            self.iter().nth(index)
+        } else {
+            self.iter().nth_back(len - 1 - index)
+        }
+    }
+
+    /// The position of `key` in sorted order, the inverse of `get_index`.
+    pub fn rank_of(&self, key: &K) -> Option<usize> {
+        let (order_key, _) = self.lookup_map.get(key)?;
+        Some(self.order_map.range::<O, _>(..&**order_key).count())
+    }
+
+    /// Iterate over `(key, order_key, value)` in sorted order.
+    pub fn iter(&self) -> Iter<'_, K, V, O> {
+        Iter {
+            order: self.order_map.iter(),
+            lookup_map: &self.lookup_map,
+        }
+    }
+
+    /// Iterate over keys in sorted order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.order_map.values()
+    }
+
     /// Iterate over values in sorted order.
     pub fn values(&self) -> impl Iterator<Item = &V> {
         self.order_map.values().map(|key| &self.lookup_map[key].1)
@@ -184,21 +285,223 @@ where
         self.values_mut()
     }
 
-    /// Retain only entries for which the predicate returns `true`.
-    pub fn retain(&mut self, mut f: impl FnMut(&V) -> bool) {
-        let keys_to_remove: Vec<K> = self
+    /// Iterate mutably over values in sorted order.
+    ///
+    /// Costs one temporary index of the map up front; prefer `values_mut`
+    /// when order doesn't matter.
+    pub fn values_mut_ordered(&mut self) -> impl Iterator<Item = &mut V> {
+        let mut by_key: HashMap<&K, &mut V> = self
             .lookup_map
-            .iter()
-            .filter(|(_, (_, value))| !f(value))
-            .map(|(key, _)| key.clone())
+            .iter_mut()
+            .map(|(key, (_, value))| (key, value))
             .collect();
 
-        for key in keys_to_remove {
-            self.remove(&key);
+        self.order_map.values().map(move |key| {
+            by_key
+                .remove(key)
+                .expect("order_map and lookup_map out of sync")
+        })
+    }
+
+    /// Remove every entry, yielding them as owned `(key, value, order_key)`
+    /// in sorted order.
+    pub fn drain(&mut self) -> IntoIter<K, V, O> {
+        std::mem::take(self).into_iter()
+    }
+
+    /// Split the map at `order_key`: entries ordered at or after it are moved
+    /// into the returned map, earlier ones stay in `self`.
+    pub fn split_off(&mut self, order_key: &O) -> Self {
+        let order_map = self.order_map.split_off(order_key);
+        let lookup_map = order_map
+            .values()
+            .map(|key| {
+                self.lookup_map
+                    .remove_entry(key)
+                    .expect("order_map and lookup_map out of sync")
+            })
+            .collect();
+
+        Self {
+            lookup_map,
+            order_map,
         }
     }
+
+    /// Retain only entries for which the predicate returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(&V) -> bool) {
+        self.retain_with(|_, _, value| f(value));
+    }
+
+    /// Retain only entries for which the predicate returns `true`, giving it
+    /// the key and order key alongside the value.
+    pub fn retain_with(&mut self, mut f: impl FnMut(&K, &O, &V) -> bool) {
+        self.lookup_map.retain(|key, (order_key, value)| {
+            let keep = f(key, order_key, value);
+            if !keep {
+                self.order_map.remove(order_key);
+            }
+            keep
+        });
+    }
+}
+
+/// Remove `key` from `lookup_map` after its `order_map` entry has already
+/// been taken out, returning the owned entry.
+fn take_entry<K, V, O>(
+    lookup_map: &mut HashMap<K, (Arc<O>, V)>,
+    order_key: Arc<O>,
+    key: K,
+) -> (K, V, O)
+where
+    K: Eq + Hash,
+{
+    let (lookup_order_key, value) = lookup_map
+        .remove(&key)
+        .expect("order_map and lookup_map out of sync");
+    // Both maps hold a handle to the same order key; once the lookup
+    // side is dropped the order side is the only owner.
+    drop(lookup_order_key);
+    let order_key = Arc::try_unwrap(order_key)
+        .ok()
+        .expect("order key shared outside of the map");
+    (key, value, order_key)
+}
+
+impl<K, V, O> FromIterator<(K, V, O)> for OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V, O)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(iter.size_hint().0);
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, O> Extend<(K, V, O)> for OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    fn extend<I: IntoIterator<Item = (K, V, O)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value, order_key) in iter {
+            self.insert(key, value, order_key);
+        }
+    }
+}
+
+impl<'a, K, V, O> IntoIterator for &'a OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    type Item = (&'a K, &'a O, &'a V);
+    type IntoIter = Iter<'a, K, V, O>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, O> IntoIterator for OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone,
+    O: Ord,
+{
+    type Item = (K, V, O);
+    type IntoIter = IntoIter<K, V, O>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            order: self.order_map.into_iter(),
+            lookup_map: self.lookup_map,
+        }
+    }
+}
+
+/// Sorted iterator over `(&K, &O, &V)`, created by [`OrderedMap::iter`].
+pub struct Iter<'a, K, V, O> {
+    order: btree_map::Iter<'a, Arc<O>, K>,
+    lookup_map: &'a HashMap<K, (Arc<O>, V)>,
+}
+
+impl<'a, K, V, O> Iterator for Iter<'a, K, V, O>
+where
+    K: Eq + Hash,
+{
+    type Item = (&'a K, &'a O, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (order_key, key) = self.order.next()?;
+        Some((key, order_key, &self.lookup_map[key].1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl<K, V, O> DoubleEndedIterator for Iter<'_, K, V, O>
+where
+    K: Eq + Hash,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (order_key, key) = self.order.next_back()?;
+        Some((key, order_key, &self.lookup_map[key].1))
+    }
+}
+
+impl<K, V, O> ExactSizeIterator for Iter<'_, K, V, O> where K: Eq + Hash {}
+
+/// Sorted owning iterator over `(K, V, O)`, created by
+/// [`OrderedMap::into_iter`].
+pub struct IntoIter<K, V, O> {
+    order: btree_map::IntoIter<Arc<O>, K>,
+    lookup_map: HashMap<K, (Arc<O>, V)>,
+}
+
+impl<K, V, O> IntoIter<K, V, O>
+where
+    K: Eq + Hash,
+{
+    fn take(&mut self, order_key: Arc<O>, key: K) -> (K, V, O) {
+        take_entry(&mut self.lookup_map, order_key, key)
+    }
+}
+
+impl<K, V, O> Iterator for IntoIter<K, V, O>
+where
+    K: Eq + Hash,
+{
+    type Item = (K, V, O);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (order_key, key) = self.order.next()?;
+        Some(self.take(order_key, key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
 }
 
+impl<K, V, O> DoubleEndedIterator for IntoIter<K, V, O>
+where
+    K: Eq + Hash,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (order_key, key) = self.order.next_back()?;
+        Some(self.take(order_key, key))
+    }
+}
+
+impl<K, V, O> ExactSizeIterator for IntoIter<K, V, O> where K: Eq + Hash {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,4 +614,178 @@ mod tests {
         assert_eq!(map.len(), 0);
         assert_eq!(map.values().count(), 0);
     }
+
+    #[test]
+    fn iter_and_keys_sorted_order() {
+        let mut map = OrderedMap::new();
+        map.insert("c", 3, 30);
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(
+            entries,
+            vec![(&"a", &10, &1), (&"b", &20, &2), (&"c", &30, &3)]
+        );
+
+        let keys: Vec<_> = map.keys().collect();
+        assert_eq!(keys, vec![&"a", &"b", &"c"]);
+
+        let reversed: Vec<_> = map.iter().rev().map(|(k, _, _)| k).collect();
+        assert_eq!(reversed, vec![&"c", &"b", &"a"]);
+    }
+
+    #[test]
+    fn into_iter_sorted_order() {
+        let mut map = OrderedMap::new();
+        map.insert("b", 2, 20);
+        map.insert("a", 1, 10);
+
+        let entries: Vec<_> = map.into_iter().collect();
+        assert_eq!(entries, vec![("a", 1, 10), ("b", 2, 20)]);
+    }
+
+    #[test]
+    fn from_iter_and_extend() {
+        let mut map: OrderedMap<_, _, _> = [("b", 2, 20), ("a", 1, 10)].into_iter().collect();
+        map.extend([("c", 3, 30), ("a", 99, 40)]);
+
+        let entries: Vec<_> = map.into_iter().collect();
+        assert_eq!(entries, vec![("b", 2, 20), ("c", 3, 30), ("a", 99, 40)]);
+    }
+
+    #[test]
+    fn values_mut_ordered() {
+        let mut map = OrderedMap::new();
+        map.insert("c", 3, 30);
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+
+        for (i, value) in map.values_mut_ordered().enumerate() {
+            *value += i as i32 * 100;
+        }
+
+        let values: Vec<&i32> = map.values().collect();
+        assert_eq!(values, vec![&1, &102, &203]);
+    }
+
+    #[test]
+    fn first_last_and_pop() {
+        let mut map = OrderedMap::new();
+        map.insert("b", 2, 20);
+        map.insert("a", 1, 10);
+        map.insert("c", 3, 30);
+
+        assert_eq!(map.first(), Some((&"a", &10, &1)));
+        assert_eq!(map.last(), Some((&"c", &30, &3)));
+
+        assert_eq!(map.pop_first(), Some(("a", 1, 10)));
+        assert_eq!(map.pop_last(), Some(("c", 3, 30)));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&"a"), None);
+        assert_eq!(map.first(), map.last());
+    }
+
+    #[test]
+    fn truncate_back() {
+        let mut map = OrderedMap::new();
+        map.insert("c", 3, 30);
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+
+        map.truncate_back(5);
+        assert_eq!(map.len(), 3);
+
+        map.truncate_back(1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&"a"), Some(&1));
+
+        map.truncate_back(0);
+        assert!(map.is_empty());
+        assert_eq!(map.pop_first(), None);
+    }
+
+    #[test]
+    fn get_index_and_rank_of() {
+        let mut map = OrderedMap::new();
+        map.insert("c", 3, 30);
+        map.insert("a", 1, 10);
+        map.insert("d", 4, 40);
+        map.insert("b", 2, 20);
+
+        assert_eq!(map.get_index(0), Some((&"a", &10, &1)));
+        assert_eq!(map.get_index(1), Some((&"b", &20, &2)));
+        assert_eq!(map.get_index(3), Some((&"d", &40, &4)));
+        assert_eq!(map.get_index(4), None);
+
+        for (index, key) in ["a", "b", "c", "d"].iter().enumerate() {
+            assert_eq!(map.rank_of(key), Some(index));
+        }
+        assert_eq!(map.rank_of(&"e"), None);
+    }
+
+    #[test]
+    fn order_key_of() {
+        let mut map = OrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+        map.update_order_for_key(&"a", 30);
+
+        assert_eq!(map.order_key_of(&"a"), Some(&30));
+        assert_eq!(map.order_key_of(&"b"), Some(&20));
+        assert_eq!(map.order_key_of(&"c"), None);
+    }
+
+    #[test]
+    fn drain() {
+        let mut map = OrderedMap::new();
+        map.insert("b", 2, 20);
+        map.insert("a", 1, 10);
+
+        let drained: Vec<_> = map.drain().collect();
+        assert_eq!(drained, vec![("a", 1, 10), ("b", 2, 20)]);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn split_off() {
+        let mut map = OrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+        map.insert("c", 3, 30);
+
+        let tail = map.split_off(&20);
+
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![&"a"]);
+        assert_eq!(tail.keys().collect::<Vec<_>>(), vec![&"b", &"c"]);
+        assert_eq!(tail.get(&"c"), Some(&3));
+        assert_eq!(map.get(&"b"), None);
+    }
+
+    #[test]
+    fn retain_with() {
+        let mut map = OrderedMap::new();
+        map.insert("a", 1, 10);
+        map.insert("b", 2, 20);
+        map.insert("c", 3, 30);
+
+        map.retain_with(|key, order_key, _| *key != "a" && *order_key < 30);
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![&"b"]);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut map: OrderedMap<&str, i32, i32> = OrderedMap::with_capacity(16);
+        assert!(map.capacity() >= 16);
+
+        map.insert("a", 1, 10);
+        map.reserve(100);
+        assert!(map.capacity() >= 101);
+
+        map.shrink_to_fit();
+        assert!(map.capacity() >= 1);
+        assert_eq!(map.get(&"a"), Some(&1));
+    }
 }
@@ -10,7 +10,7 @@ where
     K: Eq + Hash + Clone,
     O: Ord,
 {
-    inner: OrderedMap<K, K, O>,
+    pub(crate) inner: OrderedMap<K, K, O>,
 }
 
 impl<K, O> fmt::Debug for OrderedSet<K, O>
@@ -88,6 +88,24 @@ where
         }
     }
 
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: OrderedMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+
     pub fn len(&self) -> usize {
         self.inner.len()
     }
@@ -113,6 +131,41 @@ where
         self.inner.update_order_for_key(key, new_order_key)
     }
 
+    /// The element with the smallest order key.
+    pub fn first(&self) -> Option<&K> {
+        self.inner.first().map(|(_, _, value)| value)
+    }
+
+    /// The element with the largest order key.
+    pub fn last(&self) -> Option<&K> {
+        self.inner.last().map(|(_, _, value)| value)
+    }
+
+    /// Remove and return the element with the smallest order key.
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.inner.pop_first().map(|(_, value, _)| value)
+    }
+
+    /// Remove and return the element with the largest order key.
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.inner.pop_last().map(|(_, value, _)| value)
+    }
+
+    /// Keep the first `len` elements in sorted order, dropping the rest.
+    pub fn truncate_back(&mut self, len: usize) {
+        self.inner.truncate_back(len);
+    }
+
+    /// The element at position `index` in sorted order.
+    pub fn get_index(&self, index: usize) -> Option<&K> {
+        self.inner.get_index(index).map(|(_, _, value)| value)
+    }
+
+    /// The position of `key` in sorted order.
+    pub fn rank_of(&self, key: &K) -> Option<usize> {
+        self.inner.rank_of(key)
+    }
+
     /// Iterate over values in sorted order.
     pub fn iter(&self) -> impl Iterator<Item = &K> {
         self.inner.values()
@@ -127,6 +180,12 @@ where
     pub fn retain(&mut self, f: impl FnMut(&K) -> bool) {
         self.inner.retain(f);
     }
+
+    /// Retain only entries for which the predicate returns `true`, giving it
+    /// the order key alongside the element.
+    pub fn retain_with(&mut self, mut f: impl FnMut(&K, &O) -> bool) {
+        self.inner.retain_with(|key, order_key, _| f(key, order_key));
+    }
 }
 
 #[cfg(test)]
@@ -181,6 +240,23 @@ mod tests {
         assert!(set.contains(&"c"));
     }
 
+    #[test]
+    fn first_last_and_pop() {
+        let mut set = OrderedSet::new();
+        set.insert("b", 20);
+        set.insert("a", 10);
+        set.insert("c", 30);
+
+        assert_eq!(set.first(), Some(&"a"));
+        assert_eq!(set.last(), Some(&"c"));
+        assert_eq!(set.pop_last(), Some("c"));
+
+        set.truncate_back(1);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.pop_first(), Some("a"));
+        assert!(set.is_empty());
+    }
+
     #[test]
     fn debug_format() {
         let mut set = OrderedSet::new();
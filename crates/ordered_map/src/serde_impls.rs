@@ -0,0 +1,149 @@
+//! Both collections serialize as a sequence of entries in sorted order, each
+//! entry carrying its order key so the exact ordering survives a round trip.
+
+use std::{fmt, hash::Hash, marker::PhantomData};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+use crate::{OrderedMap, OrderedSet};
+
+impl<K, V, O> Serialize for OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone + Serialize,
+    V: Serialize,
+    O: Ord + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.iter()
+                .map(|(key, order_key, value)| (key, value, order_key)),
+        )
+    }
+}
+
+impl<'de, K, V, O> Deserialize<'de> for OrderedMap<K, V, O>
+where
+    K: Eq + Hash + Clone + Deserialize<'de>,
+    V: Deserialize<'de>,
+    O: Ord + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor<K, V, O>(PhantomData<(K, V, O)>);
+
+        impl<'de, K, V, O> Visitor<'de> for MapVisitor<K, V, O>
+        where
+            K: Eq + Hash + Clone + Deserialize<'de>,
+            V: Deserialize<'de>,
+            O: Ord + Deserialize<'de>,
+        {
+            type Value = OrderedMap<K, V, O>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence of (key, value, order_key) entries")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut map = OrderedMap::new();
+                while let Some((key, value, order_key)) = seq.next_element::<(K, V, O)>()? {
+                    if map.order_map.contains_key(&order_key) {
+                        return Err(A::Error::custom("duplicate order key"));
+                    }
+                    map.insert(key, value, order_key);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_seq(MapVisitor(PhantomData))
+    }
+}
+
+impl<K, O> Serialize for OrderedSet<K, O>
+where
+    K: Eq + Hash + Clone + Serialize,
+    O: Ord + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.inner
+                .iter()
+                .map(|(key, order_key, _)| (key, order_key)),
+        )
+    }
+}
+
+impl<'de, K, O> Deserialize<'de> for OrderedSet<K, O>
+where
+    K: Eq + Hash + Clone + Deserialize<'de>,
+    O: Ord + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SetVisitor<K, O>(PhantomData<(K, O)>);
+
+        impl<'de, K, O> Visitor<'de> for SetVisitor<K, O>
+        where
+            K: Eq + Hash + Clone + Deserialize<'de>,
+            O: Ord + Deserialize<'de>,
+        {
+            type Value = OrderedSet<K, O>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence of (key, order_key) entries")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut set = OrderedSet::new();
+                while let Some((key, order_key)) = seq.next_element::<(K, O)>()? {
+                    if set.inner.order_map.contains_key(&order_key) {
+                        return Err(A::Error::custom("duplicate order key"));
+                    }
+                    set.insert(key, order_key);
+                }
+                Ok(set)
+            }
+        }
+
+        deserializer.deserialize_seq(SetVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_round_trip() {
+        let mut map = OrderedMap::new();
+        map.insert("b".to_string(), 2, 20);
+        map.insert("a".to_string(), 1, 10);
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"[["a",1,10],["b",2,20]]"#);
+
+        let restored: OrderedMap<String, i32, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, map);
+    }
+
+    #[test]
+    fn set_round_trip() {
+        let mut set = OrderedSet::new();
+        set.insert("b".to_string(), 20);
+        set.insert("a".to_string(), 10);
+
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, r#"[["a",10],["b",20]]"#);
+
+        let restored: OrderedSet<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, set);
+    }
+
+    #[test]
+    fn duplicate_order_key_is_rejected() {
+        let result =
+            serde_json::from_str::<OrderedMap<String, i32, i32>>(r#"[["a",1,10],["b",2,10]]"#);
+        assert!(result.is_err());
+    }
+}